@@ -32,7 +32,7 @@ fn main() {
         chrono::Utc::now().to_rfc3339()
     );
     
-    let full_content = build_info_header + &types_ts;
+    let full_content = build_info_header + types_ts.as_str();
     fs::write(output_path, full_content).expect("Failed to write TypeScript bindings");
 
     // Create build info with dependency verification
@@ -69,7 +69,9 @@ fn generate_typescript_types() -> String {
     let _model_info = mcp_core::ModelInfo { 
         id: String::new(), 
         display_name: String::new(), 
-        organization: String::new() 
+        organization: String::new(),
+        context_length: None,
+        pricing: None,
     };
     
     // Force export of all types by calling their TS implementations
@@ -81,6 +83,174 @@ fn generate_typescript_types() -> String {
     let _ = mcp_core::ChatErrorPayload::export();
     let _ = mcp_core::BuildInfo::export();
     let _ = mcp_core::DependencyInfo::export();
+    let _ = mcp_core::TranscriptSegment::export();
+    let _ = mcp_core::Transcript::export();
+    let _ = mcp_core::CaptureRegion::export();
+    let _ = mcp_core::CaptureAnalysis::export();
+    let _ = mcp_core::ScreenCapture::export();
+    let _ = mcp_core::TemplateProvenance::export();
+    let _ = mcp_core::PromptTemplate::export();
+    let _ = mcp_core::TemplateSyncResult::export();
+    let _ = mcp_core::PluginStatus::export();
+    let _ = mcp_core::PluginCommand::export();
+    let _ = mcp_core::PluginTool::export();
+    let _ = mcp_core::PluginInfo::export();
+    let _ = mcp_core::ScriptInfo::export();
+    let _ = mcp_core::AppEvent::export();
+    let _ = mcp_core::ServerStatus::export();
+    let _ = mcp_core::TokenScope::export();
+    let _ = mcp_core::ServerTokenInfo::export();
+    let _ = mcp_core::IssuedServerToken::export();
+    let _ = mcp_core::ServerTlsConfig::export();
+    let _ = mcp_core::ServerNetworkConfig::export();
+    let _ = mcp_core::ServerNetworkUpdate::export();
+    let _ = mcp_core::MessageStatus::export();
+    let _ = mcp_core::HistoryMessage::export();
+    let _ = mcp_core::GenerationMode::export();
+    let _ = mcp_core::GenerationSettings::export();
+    let _ = mcp_core::CandidateSelection::export();
+    let _ = mcp_core::Candidate::export();
+    let _ = mcp_core::CostEstimate::export();
+    let _ = mcp_core::ModelPricing::export();
+    let _ = mcp_core::GuardrailConfig::export();
+    let _ = mcp_core::ConversationCompression::export();
+    let _ = mcp_core::Memory::export();
+    let _ = mcp_core::ToolCallRecord::export();
+    let _ = mcp_core::GenerationParameters::export();
+    let _ = mcp_core::MessageDetails::export();
+    let _ = mcp_core::BackupInfo::export();
+    let _ = mcp_core::RestoreMode::export();
+    let _ = mcp_core::RestoreSummary::export();
+    let _ = mcp_core::BackupSchedule::export();
+    let _ = mcp_core::AttachmentKind::export();
+    let _ = mcp_core::Attachment::export();
+    let _ = mcp_core::KindUsage::export();
+    let _ = mcp_core::StorageUsage::export();
+    let _ = mcp_core::CleanupSummary::export();
+    let _ = mcp_core::UploadStatus::export();
+    let _ = mcp_core::ExportFormat::export();
+    let _ = mcp_core::ExportSummary::export();
+    let _ = mcp_core::SyncConfig::export();
+    let _ = mcp_core::SyncBackendConfig::export();
+    let _ = mcp_core::SyncReport::export();
+    let _ = mcp_core::DatabaseStatus::export();
+    let _ = mcp_core::CacheScope::export();
+    let _ = mcp_core::IpcCommandStats::export();
+    let _ = mcp_core::Subsystem::export();
+    let _ = mcp_core::SubsystemState::export();
+    let _ = mcp_core::SubsystemStatus::export();
+    let _ = mcp_core::AllocatorStats::export();
+    let _ = mcp_core::SubsystemMemory::export();
+    let _ = mcp_core::CacheHits::export();
+    let _ = mcp_core::MemoryStats::export();
+    let _ = mcp_core::MessageCatalogue::export();
+    let _ = mcp_core::AccessibilitySettings::export();
+    let _ = mcp_core::MessageRole::export();
+    let _ = mcp_core::MessageBoundary::export();
+    let _ = mcp_core::CompletionStatus::export();
+    let _ = mcp_core::LivePoliteness::export();
+    let _ = mcp_core::Theme::export();
+    let _ = mcp_core::MessageDensity::export();
+    let _ = mcp_core::AppearanceSettings::export();
+    let _ = mcp_core::RateLimitState::export();
+    let _ = mcp_core::QueuedJobs::export();
+    let _ = mcp_core::StatusSnapshot::export();
+    let _ = mcp_core::AppMode::export();
+    let _ = mcp_core::ModeBehavior::export();
+    let _ = mcp_core::OnboardingStep::export();
+    let _ = mcp_core::OnboardingAction::export();
+    let _ = mcp_core::OnboardingState::export();
+    let _ = mcp_core::Feature::export();
+    let _ = mcp_core::FlagSource::export();
+    let _ = mcp_core::FeatureFlag::export();
+    let _ = mcp_core::ValueSource::export();
+    let _ = mcp_core::PortableStatus::export();
+    let _ = mcp_core::SystemDefaultsInfo::export();
+    let _ = mcp_core::Policy::export();
+    let _ = mcp_core::PolicyStatus::export();
+    let _ = mcp_core::LockedConversation::export();
+    let _ = mcp_core::InsightsPeriod::export();
+    let _ = mcp_core::DailyActivity::export();
+    let _ = mcp_core::ModelUsage::export();
+    let _ = mcp_core::Topic::export();
+    let _ = mcp_core::HistoryInsights::export();
+    let _ = mcp_core::DuplicateConversations::export();
+    let _ = mcp_core::MergeSummary::export();
+    let _ = mcp_core::MessageOrigin::export();
+    let _ = mcp_core::UnreadConversation::export();
+    let _ = mcp_core::ConversationStarter::export();
+    let _ = mcp_core::StartedConversation::export();
+    let _ = mcp_core::SpokenSentence::export();
+    let _ = mcp_core::ReadAloudState::export();
+    let _ = mcp_core::ConversationFilters::export();
+    let _ = mcp_core::RegenerationOverrides::export();
+    let _ = mcp_core::Replay::export();
+    let _ = mcp_core::DiffKind::export();
+    let _ = mcp_core::DiffSegment::export();
+    let _ = mcp_core::ResponseDiff::export();
+    let _ = mcp_core::EvalCase::export();
+    let _ = mcp_core::EvalSuite::export();
+    let _ = mcp_core::EvalResult::export();
+    let _ = mcp_core::EvalRun::export();
+    let _ = mcp_core::ModelScore::export();
+    let _ = mcp_core::BenchmarkResult::export();
+    let _ = mcp_core::BenchmarkReport::export();
+    let _ = mcp_core::TruncationStrategy::export();
+    let _ = mcp_core::ToolResultLimit::export();
+    let _ = mcp_core::ToolResultLimits::export();
+    let _ = mcp_core::ToolCacheSettings::export();
+    let _ = mcp_core::ToolCall::export();
+    let _ = mcp_core::ToolCallOutcome::export();
+    let _ = mcp_core::ToolStats::export();
+    let _ = mcp_core::ToolStatsReport::export();
+    let _ = mcp_core::SecretInfo::export();
+    let _ = mcp_core::IntegrationAuth::export();
+    let _ = mcp_core::IntegrationEndpoint::export();
+    let _ = mcp_core::IntegrationManifest::export();
+    let _ = mcp_core::IntegrationInfo::export();
+    let _ = mcp_core::DeviceAuthorization::export();
+    let _ = mcp_core::CustomModelKind::export();
+    let _ = mcp_core::ModelHealth::export();
+    let _ = mcp_core::CustomModel::export();
+    let _ = mcp_core::Provider::export();
+    let _ = mcp_core::ProviderStatus::export();
+    let _ = mcp_core::AzureProfile::export();
+    let _ = mcp_core::BedrockAccount::export();
+    let _ = mcp_core::GatewayPreset::export();
+    let _ = mcp_core::GatewayPresetInfo::export();
+    let _ = mcp_core::GatewayRoute::export();
+    let _ = mcp_core::CompatibleEndpoint::export();
+    let _ = mcp_core::MaintenanceJob::export();
+    let _ = mcp_core::MaintenanceSettings::export();
+    let _ = mcp_core::JobStatus::export();
+    let _ = mcp_core::MaintenanceStatus::export();
+    let _ = mcp_core::LowPowerMode::export();
+    let _ = mcp_core::LowPowerSettings::export();
+    let _ = mcp_core::LowPowerReason::export();
+    let _ = mcp_core::LowPowerStatus::export();
+    let _ = mcp_core::ModelCapabilities::export();
+    let _ = mcp_core::ProviderCapabilities::export();
+    let _ = mcp_core::MessageCost::export();
+    let _ = mcp_core::MentionKind::export();
+    let _ = mcp_core::MentionSuggestion::export();
+    let _ = mcp_core::MessageMentions::export();
+    let _ = mcp_core::OrchestrationAgent::export();
+    let _ = mcp_core::OrchestrationPlan::export();
+    let _ = mcp_core::OrchestrationEnd::export();
+    let _ = mcp_core::HealthState::export();
+    let _ = mcp_core::ProviderHealth::export();
+    let _ = mcp_core::PlanStepStatus::export();
+    let _ = mcp_core::PlanStep::export();
+    let _ = mcp_core::TaskPlan::export();
+    let _ = mcp_core::TopToken::export();
+    let _ = mcp_core::TokenLogprob::export();
+    let _ = mcp_core::RequestShaping::export();
+    let _ = mcp_core::PairingCode::export();
+    let _ = mcp_core::PairedDevice::export();
+    let _ = mcp_core::HandoffTransport::export();
+    let _ = mcp_core::Handoff::export();
+    let _ = mcp_core::SyncDevice::export();
+    let _ = mcp_core::TelemetryConfig::export();
     
     // Collect all generated TypeScript files
     let bindings_dir = Path::new("bindings");
@@ -97,7 +267,175 @@ fn generate_typescript_types() -> String {
         "ChatStreamPayload.ts",
         "ChatErrorPayload.ts",
         "DependencyInfo.ts",
-        "BuildInfo.ts"
+        "BuildInfo.ts",
+        "TranscriptSegment.ts",
+        "Transcript.ts",
+        "CaptureRegion.ts",
+        "CaptureAnalysis.ts",
+        "ScreenCapture.ts",
+        "TemplateProvenance.ts",
+        "PromptTemplate.ts",
+        "TemplateSyncResult.ts",
+        "PluginStatus.ts",
+        "PluginCommand.ts",
+        "PluginTool.ts",
+        "PluginInfo.ts",
+        "ScriptInfo.ts",
+        "AppEvent.ts",
+        "ServerStatus.ts",
+        "TokenScope.ts",
+        "ServerTokenInfo.ts",
+        "IssuedServerToken.ts",
+        "ServerTlsConfig.ts",
+        "ServerNetworkConfig.ts",
+        "ServerNetworkUpdate.ts",
+        "MessageStatus.ts",
+        "HistoryMessage.ts",
+        "GenerationMode.ts",
+        "GenerationSettings.ts",
+        "CandidateSelection.ts",
+        "Candidate.ts",
+        "CostEstimate.ts",
+        "ModelPricing.ts",
+        "GuardrailConfig.ts",
+        "ConversationCompression.ts",
+        "Memory.ts",
+        "ToolCallRecord.ts",
+        "GenerationParameters.ts",
+        "MessageDetails.ts",
+        "BackupInfo.ts",
+        "RestoreMode.ts",
+        "RestoreSummary.ts",
+        "BackupSchedule.ts",
+        "AttachmentKind.ts",
+        "Attachment.ts",
+        "KindUsage.ts",
+        "StorageUsage.ts",
+        "CleanupSummary.ts",
+        "UploadStatus.ts",
+        "ExportFormat.ts",
+        "ExportSummary.ts",
+        "SyncConfig.ts",
+        "SyncBackendConfig.ts",
+        "SyncReport.ts",
+        "DatabaseStatus.ts",
+        "CacheScope.ts",
+        "IpcCommandStats.ts",
+        "Subsystem.ts",
+        "SubsystemState.ts",
+        "SubsystemStatus.ts",
+        "AllocatorStats.ts",
+        "SubsystemMemory.ts",
+        "CacheHits.ts",
+        "MemoryStats.ts",
+        "MessageCatalogue.ts",
+        "AccessibilitySettings.ts",
+        "MessageRole.ts",
+        "MessageBoundary.ts",
+        "CompletionStatus.ts",
+        "LivePoliteness.ts",
+        "Theme.ts",
+        "MessageDensity.ts",
+        "AppearanceSettings.ts",
+        "RateLimitState.ts",
+        "QueuedJobs.ts",
+        "StatusSnapshot.ts",
+        "AppMode.ts",
+        "ModeBehavior.ts",
+        "OnboardingStep.ts",
+        "OnboardingAction.ts",
+        "OnboardingState.ts",
+        "Feature.ts",
+        "FlagSource.ts",
+        "FeatureFlag.ts",
+        "ValueSource.ts",
+        "PortableStatus.ts",
+        "SystemDefaultsInfo.ts",
+        "Policy.ts",
+        "PolicyStatus.ts",
+        "LockedConversation.ts",
+        "InsightsPeriod.ts",
+        "DailyActivity.ts",
+        "ModelUsage.ts",
+        "Topic.ts",
+        "HistoryInsights.ts",
+        "DuplicateConversations.ts",
+        "MergeSummary.ts",
+        "MessageOrigin.ts",
+        "UnreadConversation.ts",
+        "ConversationStarter.ts",
+        "StartedConversation.ts",
+        "SpokenSentence.ts",
+        "ReadAloudState.ts",
+        "ConversationFilters.ts",
+        "RegenerationOverrides.ts",
+        "Replay.ts",
+        "DiffKind.ts",
+        "DiffSegment.ts",
+        "ResponseDiff.ts",
+        "EvalCase.ts",
+        "EvalSuite.ts",
+        "EvalResult.ts",
+        "EvalRun.ts",
+        "ModelScore.ts",
+        "BenchmarkResult.ts",
+        "BenchmarkReport.ts",
+        "TruncationStrategy.ts",
+        "ToolResultLimit.ts",
+        "ToolResultLimits.ts",
+        "ToolCacheSettings.ts",
+        "ToolCall.ts",
+        "ToolCallOutcome.ts",
+        "ToolStats.ts",
+        "ToolStatsReport.ts",
+        "SecretInfo.ts",
+        "IntegrationAuth.ts",
+        "IntegrationEndpoint.ts",
+        "IntegrationManifest.ts",
+        "IntegrationInfo.ts",
+        "DeviceAuthorization.ts",
+        "CustomModelKind.ts",
+        "ModelHealth.ts",
+        "CustomModel.ts",
+        "Provider.ts",
+        "ProviderStatus.ts",
+        "AzureProfile.ts",
+        "BedrockAccount.ts",
+        "GatewayPreset.ts",
+        "GatewayPresetInfo.ts",
+        "GatewayRoute.ts",
+        "CompatibleEndpoint.ts",
+        "MaintenanceJob.ts",
+        "MaintenanceSettings.ts",
+        "JobStatus.ts",
+        "MaintenanceStatus.ts",
+        "LowPowerMode.ts",
+        "LowPowerSettings.ts",
+        "LowPowerReason.ts",
+        "LowPowerStatus.ts",
+        "ModelCapabilities.ts",
+        "ProviderCapabilities.ts",
+        "MessageCost.ts",
+        "MentionKind.ts",
+        "MentionSuggestion.ts",
+        "MessageMentions.ts",
+        "OrchestrationAgent.ts",
+        "OrchestrationPlan.ts",
+        "OrchestrationEnd.ts",
+        "HealthState.ts",
+        "ProviderHealth.ts",
+        "PlanStepStatus.ts",
+        "PlanStep.ts",
+        "TaskPlan.ts",
+        "TopToken.ts",
+        "TokenLogprob.ts",
+        "RequestShaping.ts",
+        "PairingCode.ts",
+        "PairedDevice.ts",
+        "HandoffTransport.ts",
+        "Handoff.ts",
+        "SyncDevice.ts",
+        "TelemetryConfig.ts"
     ];
     
     for file_name in &type_files {
@@ -1,4 +1,4 @@
-import { readFileSync, existsSync } from 'fs';
+import { readFileSync, readdirSync, existsSync } from 'fs';
 import { execSync } from 'child_process';
 import * as ts from 'typescript';
 
@@ -10,9 +10,10 @@ interface TypeContract {
 
 function extractRustTypes(): TypeContract {
   // Parse Rust source files directly (stable approach)
-  const rustSource = readFileSync('../mcp-core/src/lib.rs', 'utf-8');
-  const buildInfoSource = readFileSync('../mcp-core/src/build_info.rs', 'utf-8');
-  const allRustSource = rustSource + '\n' + buildInfoSource;
+  const allRustSource = readdirSync('../mcp-core/src')
+    .filter(file => file.endsWith('.rs'))
+    .map(file => readFileSync(`../mcp-core/src/${file}`, 'utf-8'))
+    .join('\n');
   
   const contract: TypeContract = {
     structs: new Map(),
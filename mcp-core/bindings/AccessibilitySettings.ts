@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AccessibilitySettings = { 
+/**
+ * Deliver each answer as one whole message instead of token by token, for screen readers
+ * and reduced motion
+ */
+plain_stream: boolean, 
+/**
+ * Speak answers sentence by sentence as they stream
+ */
+read_aloud: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Heap totals from the counting allocator, only present in `diagnostics` builds
+ */
+export type AllocatorStats = { current_bytes: bigint, peak_bytes: bigint, allocations: bigint, deallocations: bigint, };
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AppMode } from "./AppMode";
+import type { AppearanceSettings } from "./AppearanceSettings";
+import type { CompletionStatus } from "./CompletionStatus";
+import type { FeatureFlag } from "./FeatureFlag";
+import type { Handoff } from "./Handoff";
+import type { LivePoliteness } from "./LivePoliteness";
+import type { LowPowerReason } from "./LowPowerReason";
+import type { MaintenanceJob } from "./MaintenanceJob";
+import type { MergeSummary } from "./MergeSummary";
+import type { MessageBoundary } from "./MessageBoundary";
+import type { MessageRole } from "./MessageRole";
+import type { ModeBehavior } from "./ModeBehavior";
+import type { OrchestrationEnd } from "./OrchestrationEnd";
+import type { PairedDevice } from "./PairedDevice";
+import type { ProviderHealth } from "./ProviderHealth";
+import type { ReadAloudState } from "./ReadAloudState";
+import type { StatusSnapshot } from "./StatusSnapshot";
+import type { Subsystem } from "./Subsystem";
+import type { TaskPlan } from "./TaskPlan";
+import type { TruncationStrategy } from "./TruncationStrategy";
+
+/**
+ * Typed application event published on the internal bus.
+ * The desktop app forwards these to the webview; server mode exposes them over WebSocket.
+ */
+export type AppEvent = { "ChatStarted": { request_id: string, model: string, conversation_id: string, message_id: string, } } | { "ChatContent": { request_id: string, content: string, } } | { "ChatCompleted": { request_id: string, model: string, response_chars: number, tokens_per_second: number | null, } } | { "ChatError": { request_id: string, error: string, } } | { "AccessibilityHint": { message_id: string, role: MessageRole, boundary: MessageBoundary, status: CompletionStatus, politeness: LivePoliteness, announcement: string, } } | { "ContextReduced": { conversation_id: string, dropped_messages: number, dropped_chars: number, budget_tokens: number, } } | { "ContextCompressed": { conversation_id: string, before_tokens: number, after_tokens: number, } } | { "ContextDeduplicated": { conversation_id: string, dropped_chunks: number, } } | { "ModelRaceDecided": { fast_model: string, preferred_model: string, winner: string, } } | { "DraftCompleted": { draft_model: string, draft_chars: number, used: boolean, } } | { "CandidatesReady": { message_id: string, count: number, } } | { "GuardrailViolated": { violations: Array<string>, attempt: number, retrying: boolean, } } | { "MemoriesLearned": { conversation_id: string, count: number, } } | { "ModeChanged": { from: AppMode, to: AppMode, behavior: ModeBehavior, } } | { "ConfigUnreadable": { moved_to: string, } } | { "FeatureFlagChanged": { flag: FeatureFlag, } } | { "ConversationLockChanged": { conversation_id: string, locked: boolean, } } | { "UnreadChanged": { conversation_id: string, unread: number, } } | { "ConversationsMerged": { summary: MergeSummary, merged: Array<string>, } } | { "OrchestrationEnded": { conversation_id: string, turns: number, reason: OrchestrationEnd, } } | { "PlanUpdated": { plan: TaskPlan, } } | { "ResponseTruncated": { message_id: string, truncated_by: string, } } | { "ReadAloudChanged": { state: ReadAloudState, } } | { "EvalRunStarted": { run_id: string, suite_name: string, total: number, } } | { "EvalProgress": { run_id: string, done: number, total: number, } } | { "EvalRunCompleted": { run_id: string, suite_name: string, average_score: number | null, } } | { "BenchmarkProgress": { report_id: string, model: string, done: number, total: number, } } | { "DemoModeChanged": { enabled: boolean, } } | { "AppearanceChanged": { settings: AppearanceSettings, } } | { "BackupCompleted": { path: string, scheduled: boolean, } } | { "HistoryRestored": { messages_added: number, files_restored: number, } } | { "SyncCompleted": { pushed: number, pulled: number, conflicts: number, } } | { "MaintenanceCompleted": { job: MaintenanceJob, summary: string, } } | { "LowPowerChanged": { active: boolean, reasons: Array<LowPowerReason>, } } | { "ExportStarted": { export_id: string, conversation_id: string, path: string, total: number, } } | { "ExportProgress": { export_id: string, written: number, total: number, } } | "DatabaseLocked" | { "DatabaseUnlocked": { replayed_writes: number, } } | { "SubsystemReady": { subsystem: Subsystem, elapsed_ms: number, } } | { "SubsystemFailed": { subsystem: Subsystem, error: string, } } | { "ProviderHealthChanged": { health: ProviderHealth, } } | { "StatusChanged": { status: StatusSnapshot, } } | { "ToolCalled": { plugin: string, tool: string, success: boolean, } } | { "ToolResultTruncated": { plugin: string, tool: string, original_chars: number, kept_chars: number, strategy: TruncationStrategy, } } | { "OAuthCompleted": { integration: string, } } | { "OAuthFailed": { integration: string, error: string, } } | { "PluginsReloaded": { count: number, } } | { "ScriptsReloaded": { count: number, } } | { "TemplatesSynced": { repositories: number, } } | { "ServerStarted": { address: string, } } | "ServerStopped" | { "DevicePaired": { device: PairedDevice, } } | { "DeviceUnpaired": { device_id: string, } } | { "HandoffSent": { handoff: Handoff, } } | { "HandoffReceived": { handoff: Handoff, } } | { "HandoffAccepted": { handoff: Handoff, } };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What the app is doing with the user's input. Each mode fixes a set of behaviors (see
+ * `behavior`), and `check_transition` decides which switches are allowed.
+ */
+export type AppMode = "Chat" | "Command" | "Agent" | "Compare";
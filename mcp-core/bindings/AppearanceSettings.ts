@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MessageDensity } from "./MessageDensity";
+import type { Theme } from "./Theme";
+
+export type AppearanceSettings = { theme: Theme, 
+/**
+ * CSS hex color, `#rrggbb`
+ */
+accent_color: string, 
+/**
+ * None uses the system UI font
+ */
+font_family: string | null, font_size: number, density: MessageDensity, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AttachmentKind } from "./AttachmentKind";
+
+/**
+ * A stored file. Identical content is stored once, however many conversations refer to it.
+ */
+export type Attachment = { 
+/**
+ * SHA-256 of the content
+ */
+id: string, kind: AttachmentKind, 
+/**
+ * Name of the file it was imported from
+ */
+file_name: string, 
+/**
+ * Absolute path of the stored copy
+ */
+path: string, bytes: bigint, 
+/**
+ * Number of conversations and starters referring to it; 0 means it can be cleaned up
+ */
+references: number, created_at: string, };
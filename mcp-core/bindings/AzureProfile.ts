@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AzureProfile = { 
+/**
+ * What the model picker lists the deployment as
+ */
+name: string, 
+/**
+ * The resource's endpoint, e.g. `https://my-resource.openai.azure.com`
+ */
+endpoint: string, deployment: string, api_version: string, };
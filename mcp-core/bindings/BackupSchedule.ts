@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Automatic backups into `directory`, keeping the newest `keep`
+ */
+export type BackupSchedule = { enabled: boolean, directory: string, interval_hours: number, keep: number, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The access key Bedrock is reached with; the secret access key is kept with the other
+ * providers' keys, in the encrypted config or the environment
+ */
+export type BedrockAccount = { access_key_id: string, 
+/**
+ * e.g. `us-east-1`
+ */
+region: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BenchmarkResult } from "./BenchmarkResult";
+
+export type BenchmarkReport = { id: string, prompt: string, started_at: string, finished_at: string | null, 
+/**
+ * In the order the models were given
+ */
+results: Array<BenchmarkResult>, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How one model did on the benchmark prompt
+ */
+export type BenchmarkResult = { provider: string, model: string, 
+/**
+ * Time to first token: from sending the request to the first non-empty chunk
+ */
+ttft_ms: number | null, total_ms: number | null, 
+/**
+ * Output tokens over the time after the first token
+ */
+tokens_per_second: number | null, output_tokens: number, output_chars: number, estimated_usd: number | null, error: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How often an in-process cache had what was asked for, since startup
+ */
+export type CacheHits = { name: string, hits: bigint, misses: bigint, };
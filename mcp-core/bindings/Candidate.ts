@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One of several sampled answers to a message, kept for comparison
+ */
+export type Candidate = { id: string, 
+/**
+ * The assistant message the candidate was sampled for
+ */
+message_id: string, model: string, content: string, 
+/**
+ * Whether this candidate is the message's current content
+ */
+selected: boolean, created_at: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What to do with the image after it has been captured
+ */
+export type CaptureAnalysis = "None" | "Ocr" | "Vision";
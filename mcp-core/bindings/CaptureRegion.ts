@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Screen rectangle in logical pixels; capture the full screen when omitted
+ */
+export type CaptureRegion = { x: number, y: number, width: number, height: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CleanupSummary = { files_removed: number, bytes_freed: bigint, 
+/**
+ * Manifest entries dropped because their file had disappeared
+ */
+missing_entries: number, };
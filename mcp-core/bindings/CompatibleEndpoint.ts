@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CompatibleEndpoint = { 
+/**
+ * What its models are listed under, e.g. `lm-studio`
+ */
+name: string, 
+/**
+ * The API base, e.g. `http://localhost:1234/v1`
+ */
+base_url: string, api_key: string | null, 
+/**
+ * Sent with every request to the endpoint
+ */
+headers: { [key in string]?: string }, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Per-conversation compression of old turns and retrieved context
+ */
+export type ConversationCompression = { conversation_id: string, enabled: boolean, 
+/**
+ * Target share of the original text to keep, between 0.1 and 1.0
+ */
+ratio: number, 
+/**
+ * Estimated tokens of history before and after the last compression pass
+ */
+last_before_tokens: number | null, last_after_tokens: number | null, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ConversationFilters = { 
+/**
+ * Literal text that ends the answer where it appears
+ */
+stop_sequences: Array<string>, 
+/**
+ * Regular expressions that end the answer where they match
+ */
+content_filters: Array<string>, };
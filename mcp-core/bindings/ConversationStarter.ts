@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ConversationStarter = { name: string, 
+/**
+ * Sent instead of the active persona's prompt
+ */
+system_prompt: string | null, first_message: string, 
+/**
+ * None to use the preferred model
+ */
+model: string | null, attachment_ids: Array<string>, created_at: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Up-front estimate for answering one message with the current generation settings
+ */
+export type CostEstimate = { requests: number, prompt_tokens: number, 
+/**
+ * Assumes ASSUMED_RESPONSE_TOKENS per request
+ */
+completion_tokens: number, 
+/**
+ * None when the provider hasn't reported prices for every model involved
+ */
+estimated_usd: number | null, };
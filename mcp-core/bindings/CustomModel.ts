@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CustomModelKind } from "./CustomModelKind";
+import type { ModelHealth } from "./ModelHealth";
+
+export type CustomModel = { 
+/**
+ * The model name requests use, e.g. `user/Meta-Llama-3-8B-ft-abc123`
+ */
+id: string, display_name: string, kind: CustomModelKind, 
+/**
+ * An OpenAI-compatible API base for endpoints not served from the shared API; None uses
+ * Together's
+ */
+base_url: string | null, context_length: number | null, last_health: ModelHealth | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Messages sent and received on one day (UTC)
+ */
+export type DailyActivity = { 
+/**
+ * `YYYY-MM-DD`
+ */
+date: string, prompts: number, responses: number, };
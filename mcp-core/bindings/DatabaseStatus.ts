@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DatabaseStatus = { 
+/**
+ * Another instance holds the history database
+ */
+read_only: boolean, 
+/**
+ * Writes waiting to be replayed once this instance gets the database back
+ */
+queued_writes: number, };
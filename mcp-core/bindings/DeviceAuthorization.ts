@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What the user needs to finish a device code sign-in
+ */
+export type DeviceAuthorization = { integration: string, user_code: string, verification_uri: string, 
+/**
+ * The verification page with the code filled in, where the service offers one
+ */
+verification_uri_complete: string | null, expires_in_secs: number, };
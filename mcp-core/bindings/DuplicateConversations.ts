@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Conversations that start with the same prompt
+ */
+export type DuplicateConversations = { 
+/**
+ * Oldest first, which is the one the others merge into by default
+ */
+conversation_ids: Array<string>, 
+/**
+ * The first prompt of the oldest conversation, shortened
+ */
+preview: string, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One model's answer to one case, as the judge scored it
+ */
+export type EvalResult = { case_index: number, model: string, 
+/**
+ * The case's prompt when it ran, in case the suite changes later
+ */
+prompt: string, output: string, 
+/**
+ * Out of MAX_SCORE; None when the model or the judge failed
+ */
+score: number | null, reasoning: string, error: string | null, 
+/**
+ * Answering and judging together
+ */
+estimated_usd: number | null, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EvalResult } from "./EvalResult";
+
+export type EvalRun = { id: string, suite_name: string, judge_model: string, scheduled: boolean, started_at: string, 
+/**
+ * None while the run is in progress, or if the app closed during it
+ */
+finished_at: string | null, results: Array<EvalResult>, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EvalCase } from "./EvalCase";
+
+export type EvalSuite = { name: string, cases: Array<EvalCase>, 
+/**
+ * Models whose answers are scored
+ */
+models: Array<string>, judge_model: string, 
+/**
+ * Run automatically this often; None to run only on demand
+ */
+interval_hours: number | null, created_at: string, updated_at: string, };
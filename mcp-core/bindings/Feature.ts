@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Experimental parts of the app that can be switched off without a new build
+ */
+export type Feature = "AgentMode" | "ApiServer" | "Plugins";
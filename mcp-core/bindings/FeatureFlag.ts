@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Feature } from "./Feature";
+import type { FlagSource } from "./FlagSource";
+
+export type FeatureFlag = { feature: Feature, enabled: boolean, default_enabled: boolean, source: FlagSource, 
+/**
+ * Localized name, e.g. for the settings page and "turned off" errors
+ */
+name: string, description: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a flag's current value comes from, highest precedence first
+ */
+export type FlagSource = "Environment" | "Config" | "Default";
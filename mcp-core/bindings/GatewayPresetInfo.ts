@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GatewayPreset } from "./GatewayPreset";
+import type { Provider } from "./Provider";
+
+/**
+ * What the settings screen shows about a preset before it's set up
+ */
+export type GatewayPresetInfo = { preset: GatewayPreset, name: string, default_base_url: string | null, 
+/**
+ * The header the gateway's key is sent in
+ */
+key_header: string, takes_virtual_key: boolean, 
+/**
+ * Whether the provider's own key is still needed
+ */
+needs_provider_key: boolean, 
+/**
+ * Whether the model picker lists the gateway's names for models rather than the provider's
+ */
+lists_own_models: boolean, providers: Array<Provider>, };
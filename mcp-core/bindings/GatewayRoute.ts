@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GatewayPreset } from "./GatewayPreset";
+
+/**
+ * How a provider's requests reach it through a gateway
+ */
+export type GatewayRoute = { preset: GatewayPreset, 
+/**
+ * Where the gateway listens; required for LiteLLM, and replaces the hosted gateways' own
+ * address for self-hosted or regional deployments
+ */
+base_url: string | null, 
+/**
+ * The gateway's own key; for LiteLLM, the virtual key requests are made with
+ */
+gateway_key: string, 
+/**
+ * A Portkey virtual key, standing in for the provider's key
+ */
+virtual_key: string | null, };
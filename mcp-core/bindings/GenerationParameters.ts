@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GenerationMode } from "./GenerationMode";
+
+/**
+ * Settings a response was generated with
+ */
+export type GenerationParameters = { mode: GenerationMode, max_tokens: number | null, fast_model: string | null, sample_count: number | null, guardrails: boolean, memory: boolean, 
+/**
+ * Set when a regeneration overrode the provider's default
+ */
+temperature: number | null, 
+/**
+ * The seed the provider was sent, for replaying the answer
+ */
+seed: number | null, };
@@ -0,0 +1,30 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CandidateSelection } from "./CandidateSelection";
+import type { GenerationMode } from "./GenerationMode";
+
+export type GenerationSettings = { mode: GenerationMode, 
+/**
+ * Cheap, low-latency model used by Race and DraftRefine modes
+ */
+fast_model: string | null, 
+/**
+ * Persona id whose content is sent as the system prompt
+ */
+active_persona: string | null, 
+/**
+ * Modes that override `mode` while a given persona is active
+ */
+persona_modes: { [key in string]?: GenerationMode }, 
+/**
+ * Answers sampled per message in BestOfN mode
+ */
+sample_count: number, selection: CandidateSelection, 
+/**
+ * Record each token's probability, where the provider reports them, for the confidence view
+ */
+token_logprobs: boolean, 
+/**
+ * Sent with each Standard answer to providers that take one, so the same prompt and
+ * settings give the same answer; None to sample freely
+ */
+seed: number | null, };
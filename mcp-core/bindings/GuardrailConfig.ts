@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Rules every response must pass before it is shown. While enabled, responses are
+ * buffered rather than streamed so a failing answer never reaches the user.
+ */
+export type GuardrailConfig = { enabled: boolean, 
+/**
+ * Regular expressions the response must not match
+ */
+banned_patterns: Array<string>, 
+/**
+ * JSON Schema the response must satisfy; a surrounding ```json fence is allowed
+ */
+json_schema: string | null, max_chars: number | null, 
+/**
+ * Words that must not appear, matched case-insensitively as whole words
+ */
+profanity: Array<string>, 
+/**
+ * Corrective re-prompts before giving up
+ */
+max_retries: number, };
@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HandoffTransport } from "./HandoffTransport";
+
+export type Handoff = { id: string, conversation_id: string, 
+/**
+ * Sync device id of the sender, or its machine id without sync
+ */
+from_device: string, 
+/**
+ * Paired device (token) id or sync device id
+ */
+to_device: string, transport: HandoffTransport, 
+/**
+ * Text typed but not sent yet
+ */
+draft: string, 
+/**
+ * `plugin::tool` names the session could call
+ */
+enabled_tools: Array<string>, 
+/**
+ * An answer cut off or still streaming when the conversation left, for `resume_response`
+ */
+resume_message_id: string | null, sent_at: string, accepted_at: string | null, };
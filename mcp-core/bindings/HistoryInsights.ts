@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DailyActivity } from "./DailyActivity";
+import type { InsightsPeriod } from "./InsightsPeriod";
+import type { ModelUsage } from "./ModelUsage";
+import type { Topic } from "./Topic";
+
+export type HistoryInsights = { period: InsightsPeriod, conversations: number, prompts: number, responses: number, 
+/**
+ * Every day from the start of the period (or the first message) to today, quiet days included
+ */
+daily: Array<DailyActivity>, 
+/**
+ * Most responses first
+ */
+models: Array<ModelUsage>, 
+/**
+ * Over completed responses
+ */
+average_response_chars: number, 
+/**
+ * Estimated spend on responses in the period
+ */
+estimated_usd: number, 
+/**
+ * Largest first; empty when embeddings couldn't be fetched
+ */
+topics: Array<Topic>, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MessageStatus } from "./MessageStatus";
+
+export type HistoryMessage = { id: string, conversation_id: string, role: string, content: string, model: string | null, status: MessageStatus, 
+/**
+ * For assistant messages, the user message they answer
+ */
+parent_id: string | null, created_at: string, updated_at: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How requests to a service are authenticated
+ */
+export type IntegrationAuth = { "type": "None" } | { "type": "ApiKey", header: string, } | { "type": "Bearer" } | { "type": "OAuth2", 
+/**
+ * The app's registration with the service; sign-in needs one
+ */
+client_id: string | null, authorization_url: string | null, 
+/**
+ * For signing in on devices without a browser, where the service supports it
+ */
+device_authorization_url: string | null, token_url: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type IntegrationEndpoint = { name: string, method: string, 
+/**
+ * Relative to the base URL; `{name}` placeholders are filled from the call's parameters
+ */
+path: string, description: string, 
+/**
+ * Scopes the endpoint needs, from the manifest's scopes
+ */
+scopes: Array<string>, };
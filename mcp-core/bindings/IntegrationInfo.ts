@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { IntegrationManifest } from "./IntegrationManifest";
+
+export type IntegrationInfo = { manifest: IntegrationManifest, 
+/**
+ * Shipped with the app rather than loaded from the data directory
+ */
+built_in: boolean, 
+/**
+ * A credential is stored in the vault; always true for integrations without auth
+ */
+has_credential: boolean, };
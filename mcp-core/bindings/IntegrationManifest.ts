@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { IntegrationAuth } from "./IntegrationAuth";
+import type { IntegrationEndpoint } from "./IntegrationEndpoint";
+
+export type IntegrationManifest = { name: string, display_name: string, description: string, base_url: string, auth: IntegrationAuth, 
+/**
+ * Every scope the integration asks for
+ */
+scopes: Array<string>, endpoints: Array<IntegrationEndpoint>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Timing and payload sizes of one IPC command since startup. Sizes are of the arguments and
+ * result as JSON, which is close to what crosses the bridge.
+ */
+export type IpcCommandStats = { command: string, calls: bigint, errors: bigint, total_ms: number, mean_ms: number, max_ms: number, last_ms: number, request_bytes: bigint, response_bytes: bigint, max_response_bytes: bigint, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ServerTokenInfo } from "./ServerTokenInfo";
+
+/**
+ * Returned when a token is created or rotated; the secret is never shown again
+ */
+export type IssuedServerToken = { info: ServerTokenInfo, token: string, };
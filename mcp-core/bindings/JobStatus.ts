@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MaintenanceJob } from "./MaintenanceJob";
+
+export type JobStatus = { job: MaintenanceJob, 
+/**
+ * When the job last finished, in this or an earlier session
+ */
+last_run_at: string | null, due: boolean, 
+/**
+ * What the last run this session did, e.g. "Freed 12 MB"
+ */
+last_summary: string | null, last_error: string | null, last_duration_ms: number | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type LockedConversation = { conversation_id: string, locked_at: string, 
+/**
+ * Readable now, without authenticating again
+ */
+unlocked: boolean, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LowPowerMode } from "./LowPowerMode";
+
+export type LowPowerSettings = { mode: LowPowerMode, 
+/**
+ * In Auto, turn the profile on when running on battery
+ */
+on_battery: boolean, 
+/**
+ * In Auto, turn the profile on when the connection is metered
+ */
+on_metered: boolean, };
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LowPowerReason } from "./LowPowerReason";
+import type { LowPowerSettings } from "./LowPowerSettings";
+
+export type LowPowerStatus = { active: boolean, 
+/**
+ * Why it's on; empty when it's off
+ */
+reasons: Array<LowPowerReason>, settings: LowPowerSettings, 
+/**
+ * Set for this session only, over the settings; None follows them
+ */
+session_override: boolean | null, 
+/**
+ * None where it can't be told
+ */
+on_battery: boolean | null, metered: boolean | null, };
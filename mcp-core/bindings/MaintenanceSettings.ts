@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type MaintenanceSettings = { enabled: boolean, 
+/**
+ * Minutes without activity before jobs start
+ */
+idle_minutes: number, 
+/**
+ * Run jobs on battery power too, unless the low-power profile is on
+ */
+on_battery: boolean, };
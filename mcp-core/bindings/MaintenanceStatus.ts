@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobStatus } from "./JobStatus";
+import type { MaintenanceJob } from "./MaintenanceJob";
+import type { MaintenanceSettings } from "./MaintenanceSettings";
+
+export type MaintenanceStatus = { settings: MaintenanceSettings, 
+/**
+ * Seconds since the user last did anything
+ */
+idle_secs: number, 
+/**
+ * Whether jobs may run now: idle long enough, nothing streaming and on mains power
+ */
+can_run: boolean, 
+/**
+ * None where the power source can't be told
+ */
+on_battery: boolean | null, running: MaintenanceJob | null, jobs: Array<JobStatus>, };
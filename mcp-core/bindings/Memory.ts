@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A durable fact or preference learned from a conversation
+ */
+export type Memory = { id: string, content: string, 
+/**
+ * Conversation the fact was learned from
+ */
+conversation_id: string | null, created_at: string, };
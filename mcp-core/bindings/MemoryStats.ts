@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AllocatorStats } from "./AllocatorStats";
+import type { CacheHits } from "./CacheHits";
+import type { SubsystemMemory } from "./SubsystemMemory";
+
+export type MemoryStats = { allocator: AllocatorStats | null, 
+/**
+ * Memory SQLite holds across all open history connections
+ */
+sqlite_bytes: bigint, sqlite_peak_bytes: bigint, subsystems: Array<SubsystemMemory>, caches: Array<CacheHits>, };
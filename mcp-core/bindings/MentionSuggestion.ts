@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MentionKind } from "./MentionKind";
+
+/**
+ * A model or tool the composer can offer while a mention is being typed
+ */
+export type MentionSuggestion = { kind: MentionKind, 
+/**
+ * What to put in the message, `@` included
+ */
+mention: string, label: string, 
+/**
+ * The model's organization or the tool's description
+ */
+detail: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type MergeSummary = { 
+/**
+ * The conversation everything was merged into
+ */
+conversation_id: string, messages_moved: number, 
+/**
+ * Messages identical to one already in the merged conversation
+ */
+duplicates_dropped: number, transcripts_moved: number, captures_moved: number, };
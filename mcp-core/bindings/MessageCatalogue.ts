@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A locale's Fluent source, for the UI to build its own bundle from
+ */
+export type MessageCatalogue = { 
+/**
+ * The negotiated locale messages are formatted in
+ */
+locale: string, available_locales: Array<string>, ftl: string, 
+/**
+ * The en-US source, for ids the locale's catalogue doesn't have
+ */
+fallback_ftl: string, };
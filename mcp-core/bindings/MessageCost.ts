@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ModelPricing } from "./ModelPricing";
+
+/**
+ * What an answer was estimated to cost and, once the provider reported its usage, what it did
+ */
+export type MessageCost = { message_id: string, model: string, 
+/**
+ * As the provider reported them, summed over every request; None when it didn't
+ */
+prompt_tokens: number | null, completion_tokens: number | null, estimated_usd: number | null, 
+/**
+ * None when usage or a price is missing for any request
+ */
+actual_usd: number | null, pricing: ModelPricing | null, };
@@ -0,0 +1,42 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GenerationParameters } from "./GenerationParameters";
+import type { TokenLogprob } from "./TokenLogprob";
+import type { ToolCallRecord } from "./ToolCallRecord";
+
+/**
+ * How an assistant message was produced
+ */
+export type MessageDetails = { message_id: string, provider: string, model: string, parameters: GenerationParameters, tool_calls: Array<ToolCallRecord>, 
+/**
+ * What went into the prompt besides the message itself, e.g. "6 earlier messages"
+ */
+context_sources: Array<string>, first_token_ms: number | null, latency_ms: number | null, 
+/**
+ * From the provider's prices and the answer's length; None where prices aren't known
+ */
+estimated_usd: number | null, 
+/**
+ * Summed over every request, as the providers reported them once the answer finished
+ */
+prompt_tokens: number | null, completion_tokens: number | null, 
+/**
+ * From the reported usage; None when a request reported none or a price isn't known
+ */
+actual_usd: number | null, 
+/**
+ * The stop sequence or content filter that cut the answer short
+ */
+truncated_by: string | null, 
+/**
+ * Each token's probability, when they were asked for and the provider reported them
+ */
+token_logprobs: Array<TokenLogprob>, 
+/**
+ * Tags the request was sent with, e.g. a gateway's trace id or the client's project, kept
+ * for auditing where answers came from
+ */
+metadata: { [key in string]?: string }, 
+/**
+ * Tags `on_response_complete` scripts gave the answer once it finished
+ */
+tags: Array<string>, };
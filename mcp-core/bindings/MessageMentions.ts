@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PluginTool } from "./PluginTool";
+
+/**
+ * What a message's mentions ask for
+ */
+export type MessageMentions = { 
+/**
+ * The message as it's sent: model mentions removed, tool mentions left as the tool's name
+ */
+message: string, 
+/**
+ * The qualified id of the model answering this message, if one was mentioned
+ */
+model: string | null, 
+/**
+ * Tools the model is told to call in its next step, in the order they were mentioned
+ */
+tools: Array<PluginTool>, };
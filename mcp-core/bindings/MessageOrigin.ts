@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The conversation a message was in before it was merged into another
+ */
+export type MessageOrigin = { message_id: string, conversation_id: string, merged_at: string, };
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AppMode } from "./AppMode";
+
+/**
+ * Behaviors a mode governs, so the UI doesn't decide them itself
+ */
+export type ModeBehavior = { mode: AppMode, 
+/**
+ * Input is sent to the model as a chat message
+ */
+send_messages: boolean, 
+/**
+ * Plugin commands can be run
+ */
+plugin_commands: boolean, 
+/**
+ * Tool calls run without asking for confirmation
+ */
+auto_execute_tools: boolean, 
+/**
+ * Answers are sampled as candidates for the user to compare, whatever the generation mode
+ */
+compare_answers: boolean, 
+/**
+ * Localized hint for the status bar
+ */
+status_hint: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ModelCapabilities = { model: string, streaming: boolean, vision: boolean, tools: boolean, json_mode: boolean, 
+/**
+ * An embedding model rather than a chat model
+ */
+embeddings: boolean, 
+/**
+ * Read from the provider's model list rather than judged from the id
+ */
+listed: boolean, };
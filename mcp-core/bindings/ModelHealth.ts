@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The result of the last health check
+ */
+export type ModelHealth = { healthy: boolean, latency_ms: number, error: string | null, checked_at: string, };
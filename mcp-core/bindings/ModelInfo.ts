@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ModelPricing } from "./ModelPricing";
+
+export type ModelInfo = { id: string, display_name: string, organization: string, context_length: number | null, 
+/**
+ * What the provider charges, where its model list says
+ */
+pricing: ModelPricing | null, };
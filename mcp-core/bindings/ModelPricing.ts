@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Provider prices in USD per million tokens
+ */
+export type ModelPricing = { input: number, output: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A model's average over one run: a point on its score history
+ */
+export type ModelScore = { run_id: string, started_at: string, model: string, 
+/**
+ * None if none of its answers could be scored
+ */
+average_score: number | null, scored: number, failed: number, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type OnboardingAction = { "ChooseProvider": { provider: string, } } | { "EnterKey": { api_key: string, } } | "Validate" | { "PickModel": { model: string, } } | "Skip" | "Back";
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OnboardingStep } from "./OnboardingStep";
+
+export type OnboardingState = { step: OnboardingStep, provider: string | null, providers: Array<string>, 
+/**
+ * The current step can be skipped
+ */
+optional: boolean, error: string | null, completed_at: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * First-run steps, in order
+ */
+export type OnboardingStep = "ChooseProvider" | "EnterKey" | "ValidateKey" | "PickModel" | "McpServers" | "Complete";
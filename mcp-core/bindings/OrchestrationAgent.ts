@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One participant: who the others address and what it speaks as
+ */
+export type OrchestrationAgent = { 
+/**
+ * What the other agents call it, e.g. "Critic"
+ */
+name: string, 
+/**
+ * Id of the persona template it speaks as, if any
+ */
+persona: string | null, 
+/**
+ * Qualified id of the model it runs on; the preferred model if None
+ */
+model: string | null, };
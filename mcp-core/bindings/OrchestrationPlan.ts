@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OrchestrationAgent } from "./OrchestrationAgent";
+
+export type OrchestrationPlan = { 
+/**
+ * What the agents are working toward, saved as the user's message
+ */
+goal: string, 
+/**
+ * In speaking order
+ */
+agents: Array<OrchestrationAgent>, 
+/**
+ * Turns in all, after which the conversation stops however far it got
+ */
+max_turns: number, 
+/**
+ * Model that reads the transcript after each round and ends the conversation once it judges
+ * the goal reached
+ */
+moderator_model: string | null, 
+/**
+ * Ends the conversation when an agent says it, e.g. "GOAL REACHED"
+ */
+stop_phrase: string | null, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TokenScope } from "./TokenScope";
+
+/**
+ * A companion device holding a token issued by pairing
+ */
+export type PairedDevice = { 
+/**
+ * The id of the device's token
+ */
+id: string, name: string, scope: TokenScope, paired_at: string, };
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TokenScope } from "./TokenScope";
+
+export type PairingCode = { 
+/**
+ * For typing in by hand, as `ABCD-EFGH`
+ */
+code: string, 
+/**
+ * For the QR code: the server's address, the code and, with TLS, the fingerprint of the
+ * server certificate for the companion to pin
+ */
+uri: string, scope: TokenScope, expires_at: string, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PlanStepStatus } from "./PlanStepStatus";
+
+export type PlanStep = { 
+/**
+ * Short and unique within the plan, e.g. "3"
+ */
+id: string, title: string, detail: string | null, 
+/**
+ * Ids of the steps that have to be done first
+ */
+depends_on: Array<string>, status: PlanStepStatus, };
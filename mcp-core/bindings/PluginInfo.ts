@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PluginCommand } from "./PluginCommand";
+import type { PluginStatus } from "./PluginStatus";
+import type { PluginTool } from "./PluginTool";
+
+export type PluginInfo = { 
+/**
+ * The plugin's directory name, which its manifest can't choose
+ */
+id: string, 
+/**
+ * SHA-256 of the plugin's module, or None if it can't be read
+ */
+module_sha256: string | null, name: string, version: string, description: string, requested_permissions: Array<string>, granted_permissions: Array<string>, status: PluginStatus, error: string | null, commands: Array<PluginCommand>, tools: Array<PluginTool>, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Policy = { 
+/**
+ * Provider ids as recorded in message details, e.g. "together" or "demo"
+ */
+disallowed_providers: Array<string>, 
+/**
+ * Estimated spend per calendar month (UTC) after which no more requests are sent
+ */
+max_monthly_spend_usd: number | null, 
+/**
+ * Prompts are always redacted, whatever the user's setting
+ */
+force_redaction: boolean, 
+/**
+ * Config settings the user can't change; only those the defaults file could set are accepted
+ */
+locked_settings: Array<string>, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Policy } from "./Policy";
+
+/**
+ * The policy in force, for the settings UI to disable what it locks
+ */
+export type PolicyStatus = { 
+/**
+ * None when there is no policy file
+ */
+path: string | null, policy: Policy, 
+/**
+ * Estimated spend so far this month, from message details
+ */
+spent_this_month_usd: number, };
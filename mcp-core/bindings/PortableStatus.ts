@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type PortableStatus = { enabled: boolean, 
+/**
+ * The folder holding config and data
+ */
+root: string | null, 
+/**
+ * The passphrase has been entered this session
+ */
+unlocked: boolean, 
+/**
+ * A config exists, so unlocking checks the passphrase against it instead of setting a new one
+ */
+initialized: boolean, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TemplateProvenance } from "./TemplateProvenance";
+
+/**
+ * A prompt template or persona indexed from a template repository
+ */
+export type PromptTemplate = { id: string, name: string, kind: string, content: string, provenance: TemplateProvenance, };
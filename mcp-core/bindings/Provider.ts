@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Provider = "together" | "open_ai" | "anthropic" | "open_router" | "azure" | "groq" | "ollama" | "endpoint" | "bedrock";
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ModelCapabilities } from "./ModelCapabilities";
+import type { Provider } from "./Provider";
+
+export type ProviderCapabilities = { provider: Provider, 
+/**
+ * Whether the provider has an embeddings API
+ */
+embeddings: boolean, models: Array<ModelCapabilities>, };
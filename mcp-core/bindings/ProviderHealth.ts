@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HealthState } from "./HealthState";
+import type { Provider } from "./Provider";
+
+export type ProviderHealth = { provider: Provider, state: HealthState, 
+/**
+ * How long the last check took to be answered
+ */
+latency_ms: number | null, checked_at: string | null, 
+/**
+ * What was wrong at the last check, if anything
+ */
+error: string | null, 
+/**
+ * Checks in a row that weren't healthy
+ */
+failures: number, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Provider } from "./Provider";
+import type { ValueSource } from "./ValueSource";
+
+/**
+ * Whether a provider can be used, and where its key comes from
+ */
+export type ProviderStatus = { provider: Provider, name: string, api_base: string, key_source: ValueSource | null, 
+/**
+ * Has a key, or for Ollama a base URL
+ */
+configured: boolean, };
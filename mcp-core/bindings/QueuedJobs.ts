@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Work waiting or in progress in the background
+ */
+export type QueuedJobs = { exports: number, 
+/**
+ * History writes held while another instance has the database
+ */
+history_writes: number, 
+/**
+ * Deferred subsystems not started yet
+ */
+pending_subsystems: number, };
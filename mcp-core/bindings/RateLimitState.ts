@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type RateLimitState = { 
+/**
+ * The provider rejected a request for rate limiting within the last minute
+ */
+limited: boolean, 
+/**
+ * Seconds until `limited` clears, while it's set
+ */
+cooldown_secs: number | null, 
+/**
+ * Rate-limit rejections since startup
+ */
+hits: number, last_hit_at: string | null, };
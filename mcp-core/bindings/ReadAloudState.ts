@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SpokenSentence } from "./SpokenSentence";
+
+export type ReadAloudState = { 
+/**
+ * The answer being read
+ */
+message_id: string | null, 
+/**
+ * What the webview should be speaking now; None when there is nothing to say yet
+ */
+speaking: SpokenSentence | null, 
+/**
+ * Sentences waiting after the current one
+ */
+queued: number, paused: boolean, };
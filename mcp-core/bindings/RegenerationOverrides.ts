@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What to change when regenerating an answer; anything unset is as it would be for a new message
+ */
+export type RegenerationOverrides = { model: string | null, 
+/**
+ * None for the provider's default
+ */
+temperature: number | null, 
+/**
+ * Ignored by providers that don't take a seed
+ */
+seed: number | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Candidate } from "./Candidate";
+
+/**
+ * A replayed answer beside the original, and whether the two came out the same
+ */
+export type Replay = { candidate: Candidate, reproduced: boolean, };
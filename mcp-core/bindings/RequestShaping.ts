@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How chat requests made with a token are shaped, in place of the app's own settings, e.g. so
+ * a code editor's token always gets the coding persona on a cheaper model
+ */
+export type RequestShaping = { 
+/**
+ * Persona for every conversation the client has, in place of the active one
+ */
+persona: string | null, 
+/**
+ * The only model the client's requests use, including for racing and drafting
+ */
+model: string | null, 
+/**
+ * Tags recorded with every answer the client gets, alongside any its requests carry
+ */
+metadata: { [key in string]?: string }, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiffSegment } from "./DiffSegment";
+
+export type ResponseDiff = { from_candidate: string, from_model: string, to_candidate: string, to_model: string, 
+/**
+ * Both answers in order: Same and Removed segments make up the first, Same and Added the second
+ */
+segments: Array<DiffSegment>, words_added: number, words_removed: number, };
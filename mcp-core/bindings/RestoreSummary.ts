@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RestoreMode } from "./RestoreMode";
+
+export type RestoreSummary = { mode: RestoreMode, messages_added: number, files_restored: number, files_skipped: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CaptureAnalysis } from "./CaptureAnalysis";
+
+/**
+ * A screenshot attached to a conversation, with any extracted text or description
+ */
+export type ScreenCapture = { id: string, conversation_id: string, image_path: string, analysis: CaptureAnalysis, text: string | null, created_at: string, };
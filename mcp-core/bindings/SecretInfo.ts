@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Secret metadata safe to show in the UI
+ */
+export type SecretInfo = { name: string, owner: string, created_at: string, updated_at: string, };
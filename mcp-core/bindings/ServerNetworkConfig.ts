@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where the API server listens and which remote clients may connect.
+ * Loopback clients are always allowed; anyone else must match `allowlist`.
+ */
+export type ServerNetworkConfig = { bind_address: string, 
+/**
+ * Client IPs (`192.168.1.20`) or subnets (`192.168.1.0/24`)
+ */
+allowlist: Array<string>, 
+/**
+ * Set once the user has accepted the LAN exposure warning for this bind address
+ */
+lan_exposure_confirmed: boolean, 
+/**
+ * Web page origins (`https://app.example.com`) allowed to open the WebSocket endpoints.
+ * Browsers send the page's origin with every upgrade; native clients send none.
+ */
+allowed_origins: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Outcome of changing network settings; LAN bindings need a second, confirmed call
+ */
+export type ServerNetworkUpdate = { applied: boolean, confirmation_required: boolean, warning: string | null, };
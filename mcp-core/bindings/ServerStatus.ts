@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ServerStatus = { running: boolean, address: string | null, started_at: string | null, tls: boolean, mutual_tls: boolean, auth_required: boolean, lan_exposed: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * User-provided PEM files. Setting `client_ca_path` turns on mutual TLS.
+ */
+export type ServerTlsConfig = { cert_path: string, key_path: string, client_ca_path: string | null, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestShaping } from "./RequestShaping";
+import type { TokenScope } from "./TokenScope";
+
+/**
+ * Token metadata safe to show in the UI
+ */
+export type ServerTokenInfo = { id: string, name: string, scope: TokenScope, created_at: string, rotated_at: string | null, shaping: RequestShaping, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SpokenSentence = { message_id: string, 
+/**
+ * Position in the answer, from 0
+ */
+index: number, text: string, };
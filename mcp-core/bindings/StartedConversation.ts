@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Attachment } from "./Attachment";
+
+/**
+ * A new conversation set up from a starter; nothing has been sent yet
+ */
+export type StartedConversation = { conversation_id: string, 
+/**
+ * For the UI to put in the composer, where the user can edit it before sending
+ */
+first_message: string, 
+/**
+ * The starter's attachments, less any deleted since it was saved
+ */
+attachments: Array<Attachment>, };
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AppMode } from "./AppMode";
+import type { GenerationMode } from "./GenerationMode";
+import type { QueuedJobs } from "./QueuedJobs";
+import type { RateLimitState } from "./RateLimitState";
+
+/**
+ * Everything the status bar shows, in one call
+ */
+export type StatusSnapshot = { app_mode: AppMode, 
+/**
+ * The current mode's status bar hint
+ */
+mode_hint: string, mode: GenerationMode, provider: string, model: string, 
+/**
+ * Loaded plugins that provide tools. There are no external MCP server connections yet, so
+ * these are the only tool sources.
+ */
+tool_providers: Array<string>, api_server_running: boolean, history_read_only: boolean, jobs: QueuedJobs, rate_limit: RateLimitState, 
+/**
+ * Configured providers whose last health check found them degraded or down
+ */
+unhealthy_providers: Array<string>, 
+/**
+ * Answers completed in conversations that weren't on screen, for the tray and status bar badge
+ */
+unread: number, };
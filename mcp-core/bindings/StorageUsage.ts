@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { KindUsage } from "./KindUsage";
+
+export type StorageUsage = { attachments: Array<KindUsage>, history_bytes: bigint, 
+/**
+ * Transcripts, capture metadata and other app data outside the attachment store
+ */
+other_bytes: bigint, 
+/**
+ * Attachments no conversation refers to, plus files missing from the manifest
+ */
+orphaned_count: number, orphaned_bytes: bigint, total_bytes: bigint, };
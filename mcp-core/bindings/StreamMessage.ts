@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type StreamMessage = { "Content": string } | { "Error": string } | "Complete" | { "Replace": string } | { "ProviderSwitched": { from: string, to: string, model: string, reason: string, } } | { "Usage": { model: string, prompt_tokens: number, completion_tokens: number, } } | { "AgentTurn": { agent: string, model: string, message_id: string, } };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Parts of the app started after the window rather than before it, in the order they are warmed
+ */
+export type Subsystem = "Scripts" | "Plugins" | "Integrations" | "Schedules" | "Models";
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Memory held by one in-process cache or queue. Sizes are estimates from the serialized size
+ * of the entries, not exact heap usage.
+ */
+export type SubsystemMemory = { name: string, entries: number, approx_bytes: bigint, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Subsystem } from "./Subsystem";
+import type { SubsystemState } from "./SubsystemState";
+
+export type SubsystemStatus = { subsystem: Subsystem, state: SubsystemState, elapsed_ms: number | null, error: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where sync objects are stored. Every backend holds a flat set of named objects.
+ */
+export type SyncBackendConfig = { "type": "Folder", path: string, } | { "type": "WebDav", url: string, username: string, password: string, } | { "type": "S3", endpoint: string, bucket: string, region: string, access_key_id: string, secret_access_key: string, prefix: string, };
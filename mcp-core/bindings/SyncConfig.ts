@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SyncBackendConfig } from "./SyncBackendConfig";
+
+/**
+ * Per-device sync settings. `encryption_key` encrypts everything written to the backend and
+ * must be the same on each device; `device_id` identifies this device in vector clocks.
+ */
+export type SyncConfig = { enabled: boolean, backend: SyncBackendConfig | null, 
+/**
+ * Base64 of 32 random bytes, from `generate_sync_key`
+ */
+encryption_key: string, device_id: string, 
+/**
+ * Minutes between automatic syncs; 0 syncs only on request
+ */
+interval_minutes: number, last_synced_at: string | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Another device syncing with the same backend
+ */
+export type SyncDevice = { device_id: string, 
+/**
+ * When its newest batch that reached this device was written
+ */
+last_seen_at: string, };
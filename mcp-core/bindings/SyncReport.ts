@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SyncReport = { pushed: number, pulled: number, 
+/**
+ * Records changed on two devices since they last synced; the newer edit was kept
+ */
+conflicts: number, synced_at: string, };
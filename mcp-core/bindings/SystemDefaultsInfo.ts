@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SystemDefaultsInfo = { path: string | null, 
+/**
+ * Settings the file provides, for the UI to mark as managed
+ */
+settings: Array<string>, };
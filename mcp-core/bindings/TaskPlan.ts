@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PlanStep } from "./PlanStep";
+
+export type TaskPlan = { conversation_id: string, goal: string, 
+/**
+ * Each after the steps it depends on
+ */
+steps: Array<PlanStep>, created_at: string, updated_at: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TelemetryConfig = { enabled: boolean, 
+/**
+ * Base URL of an OTLP/HTTP collector; spans are posted to `<endpoint>/v1/traces`
+ */
+endpoint: string, 
+/**
+ * Sent with every export, e.g. an API key for a hosted collector
+ */
+headers: { [key in string]?: string }, 
+/**
+ * `service.name` of the exported spans
+ */
+service_name: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a template came from, so users can judge whether to trust it
+ */
+export type TemplateProvenance = { repository: string, commit: string, path: string, synced_at: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Outcome of syncing one repository
+ */
+export type TemplateSyncResult = { repository: string, success: boolean, commit: string | null, template_count: number, error: string | null, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TopToken } from "./TopToken";
+
+export type TokenLogprob = { token: string, 
+/**
+ * Natural log of the token's probability; -9999 when it was outside the top 20
+ */
+logprob: number, top: Array<TopToken>, };
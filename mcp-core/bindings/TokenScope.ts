@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a bearer token may do on the local API server
+ */
+export type TokenScope = "Chat" | "Admin";
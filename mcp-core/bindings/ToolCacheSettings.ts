@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ToolCacheSettings = { 
+/**
+ * Seconds a result is reused for tools without their own TTL; 0 runs them every time
+ */
+default_ttl_secs: number, 
+/**
+ * By `plugin::tool`, or by tool name for that tool from any plugin; 0 never caches the tool
+ */
+ttl_overrides: { [key in string]?: number }, };
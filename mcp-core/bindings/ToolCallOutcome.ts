@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ToolCallOutcome = { plugin: string, tool: string, 
+/**
+ * None when the call failed
+ */
+result: string | null, error: string | null, duration_ms: number, };
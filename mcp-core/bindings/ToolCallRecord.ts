@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A plugin tool invoked while a response was generating
+ */
+export type ToolCallRecord = { plugin: string, tool: string, success: boolean, };
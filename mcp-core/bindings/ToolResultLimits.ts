@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ToolResultLimit } from "./ToolResultLimit";
+
+export type ToolResultLimits = { default: ToolResultLimit, 
+/**
+ * By `plugin::tool`, or by tool name for that tool from any plugin
+ */
+overrides: { [key in string]?: ToolResultLimit }, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ToolStats = { plugin: string, 
+/**
+ * None for the totals over every tool of the plugin
+ */
+tool: string | null, calls: number, failures: number, cache_hits: number, 
+/**
+ * Durations leave out cache hits, which don't reach the plugin
+ */
+average_ms: number | null, p95_ms: number | null, result_tokens: bigint, last_error: string | null, last_called_at: string, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ToolStats } from "./ToolStats";
+
+export type ToolStatsReport = { 
+/**
+ * Most called first
+ */
+plugins: Array<ToolStats>, 
+/**
+ * Most called first
+ */
+tools: Array<ToolStats>, };
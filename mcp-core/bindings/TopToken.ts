@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A likely alternative at a token's position
+ */
+export type TopToken = { token: string, logprob: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Prompts that embed close together
+ */
+export type Topic = { 
+/**
+ * The prompt the others were grouped around, shortened
+ */
+label: string, prompts: number, conversations: number, };
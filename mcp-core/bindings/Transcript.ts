@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TranscriptSegment } from "./TranscriptSegment";
+
+/**
+ * Transcript of an ingested audio/video file, linked to a conversation
+ */
+export type Transcript = { id: string, conversation_id: string, source_file: string, 
+/**
+ * Stored copy of the media in the attachment store
+ */
+attachment_id: string | null, media_kind: string, language: string | null, duration: number | null, created_at: string, segments: Array<TranscriptSegment>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A timestamped span of speech. `speaker` is only set when the provider returns diarization labels.
+ */
+export type TranscriptSegment = { start: number, end: number, speaker: string | null, text: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A conversation with answers that completed while it wasn't on screen
+ */
+export type UnreadConversation = { conversation_id: string, unread: number, 
+/**
+ * When the conversation was last read, if ever
+ */
+last_read_at: string | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type UploadStatus = { upload_id: string, chunk_size: bigint, chunk_count: number, received_bytes: bigint, 
+/**
+ * Chunk indexes still to send, in order
+ */
+missing_chunks: Array<number>, };
@@ -0,0 +1,230 @@
+no-api-key = Kein API-Schlüssel konfiguriert
+no-provider-key = Kein API-Schlüssel für { $provider } konfiguriert
+invalid-locale = Kein gültiges Sprach-Tag: { $locale }
+
+## Uploads
+
+upload-invalid-sha256 = Erwartet wird ein hexadezimaler SHA-256 der gesamten Datei
+upload-invalid-file-name = Ungültiger Dateiname: { $file_name }
+upload-too-large = Die Datei ist { $bytes } Bytes groß; Uploads sind auf { $limit } Bytes begrenzt
+upload-chunk-checksum = Teil { $index } hat die Prüfsumme nicht bestanden; bitte erneut senden
+upload-chunk-past-end = Teil { $index } liegt hinter dem Ende des Uploads
+upload-chunk-size = Teil { $index } sollte { $expected } Bytes groß sein, ist aber { $actual }
+upload-incomplete = Dem Upload fehlen { $missing } von { $total } Teilen
+upload-checksum-mismatch = Die hochgeladene Datei stimmt nicht mit ihrer Prüfsumme überein; bitte erneut hochladen
+upload-not-found = Upload { $upload_id } nicht gefunden
+upload-invalid-id = Ungültige Upload-ID: { $upload_id }
+upload-gone = Upload nicht gefunden; er ist möglicherweise abgeschlossen, abgebrochen oder abgelaufen
+
+## Export
+
+export-not-running = Export { $export_id } läuft nicht
+export-cancelled = Export abgebrochen
+
+## Backups
+
+backup-unreadable = Das Backup-Archiv ist unlesbar oder beschädigt: { $error }
+backup-no-manifest = Kein Verlaufs-Backup: Manifest fehlt
+backup-too-new = Backup-Format { $version } ist neuer, als diese Version unterstützt
+backup-unexpected-path = Das Backup enthält einen unerwarteten Pfad: { $path }
+backup-missing-entry = Im Backup fehlt { $path }: { $error }
+backup-checksum-mismatch = Das Backup ist beschädigt: { $path } stimmt nicht mit seiner Prüfsumme überein
+
+## Sync
+
+sync-no-backend = Kein Sync-Backend konfiguriert
+sync-unreadable = { $name } kann nicht gelesen werden; ist der Sync-Schlüssel auf allen Geräten gleich? { $error }
+sync-key-not-base64 = Der Sync-Schlüssel ist kein gültiges Base64
+sync-key-length = Der Sync-Schlüssel muss 32 Bytes lang sein
+
+## History database
+
+history-migration-mismatch = Migration { $version } ({ $name }) der Verlaufsdatenbank stimmt nicht mit der dieser Version überein
+history-schema-too-new = Die Verlaufsdatenbank hat Schemaversion { $version }, diese Version kennt nur bis { $latest }; aktualisiere die App, statt eine Beschädigung zu riskieren
+history-migration-failed = Migration { $version } ({ $name }) der Verlaufsdatenbank ist fehlgeschlagen: { $error }
+history-integrity-failed = Die Integritätsprüfung der Verlaufsdatenbank ist fehlgeschlagen ({ $result }); stelle vor dem Upgrade ein Backup wieder her
+history-in-use = Der Verlauf wird von einer anderen Instanz verwendet und kann nicht gelesen werden: { $error }
+history-read-only = Der Verlauf ist schreibgeschützt, solange eine andere Instanz ihn geöffnet hat
+history-queue-full = Zu viele Änderungen warten darauf, dass eine andere Instanz den Verlauf schließt; schließe sie, um weitere zu speichern
+history-message-not-found = Nachricht { $id } nicht gefunden
+history-candidate-not-found = Kandidat { $id } nicht gefunden
+history-memory-not-found = Erinnerung { $id } nicht gefunden
+history-attachment-not-in-conversation = Anhang { $attachment_id } gehört nicht zur Unterhaltung { $conversation_id }
+
+## Screen reader announcements
+
+a11y-message-sent = Nachricht gesendet
+a11y-response-started = { $model } antwortet
+a11y-response-complete = Antwort vollständig
+a11y-response-failed = Antwort fehlgeschlagen: { $error }
+a11y-response-replaced = Die Antwort wurde durch eine bessere ersetzt
+
+## Settings
+
+appearance-invalid-accent = Akzentfarbe { $color } ist keine Hex-Farbe im Format #rrggbb
+appearance-invalid-font-size = Die Schriftgröße muss zwischen { $min } und { $max } Pixeln liegen
+appearance-invalid-font-family = { $family } ist kein verwendbarer Schriftname
+settings-unreadable = Keine Einstellungsdatei: { $error }
+
+## Modes
+
+mode-chat-hint = Chat: Nachrichten gehen an das Modell
+mode-command-hint = Befehl: Eingaben führen Plugin-Befehle aus
+mode-agent-hint = Agent: Tools laufen ohne Rückfrage
+mode-compare-hint = Vergleich: aus mehreren Antworten wählen
+mode-busy = Warte, bis die Antwort fertig ist, bevor du den Agent-Modus wechselst
+mode-agent-needs-tools = Der Agent-Modus braucht ein Plugin, das Tools bereitstellt
+mode-no-messages = Im Befehlsmodus können keine Nachrichten gesendet werden
+mode-no-plugin-commands = Plugin-Befehle sind im Vergleichsmodus nicht verfügbar
+plugin-not-found = In { $id } wurde kein Plugin mit lesbarem Modul gefunden
+
+## First run
+
+onboarding-complete = Die Einrichtung ist bereits abgeschlossen
+onboarding-unknown-provider = { $provider } ist kein unterstützter Anbieter
+onboarding-empty-key = Gib einen API-Schlüssel ein
+onboarding-wrong-step = Diese Aktion gehört nicht zum Schritt { $step }
+
+## Demo mode
+
+demo-unavailable = Im Demo-Modus ist der Anbieter nicht verfügbar; schalte den Demo-Modus aus, um ihn zu nutzen
+
+## Feature flags
+
+feature-agent-mode = Agent-Modus
+feature-agent-mode-description = Ein Modus, in dem Tools ohne Rückfrage laufen
+feature-api-server = API-Server
+feature-api-server-description = Ein lokaler HTTP-Server, über den andere Programme chatten können
+feature-plugins = Plugins
+feature-plugins-description = WASM-Plugins mit eigenen Befehlen und Tools
+feature-disabled = { $feature } ist ausgeschaltet
+feature-unknown = Unbekannte Funktion { $feature }
+
+## Portable mode
+
+portable-locked = Gib zuerst die Passphrase für diesen portablen Ordner ein
+portable-wrong-passphrase = Diese Passphrase entsperrt den portablen Ordner nicht
+portable-empty-passphrase = Gib eine Passphrase ein
+portable-inactive = Der portable Modus ist aus
+
+## Policy
+policy-provider-disallowed = Die Richtlinie deines Administrators erlaubt den Anbieter { $provider } nicht
+policy-spend-cap = Die Ausgaben dieses Monats haben das von deinem Administrator gesetzte Limit von { $cap } USD erreicht
+policy-locked-setting = Dein Administrator hat die Einstellung { $setting } gesperrt
+template-repository-invalid = { $repository } ist keine Git-URL; verwende eine, die mit https://, ssh:// oder git@ beginnt
+
+## Conversation locks
+conversation-locked = Diese Unterhaltung ist gesperrt; entsperre sie, um fortzufahren
+conversation-unlock-reason = Eine gesperrte Unterhaltung entsperren
+os-auth-failed = Die Authentifizierung ist fehlgeschlagen oder wurde abgebrochen
+os-auth-unavailable = Auf diesem System gibt es keine Authentifizierungsabfrage zum Entsperren von Unterhaltungen
+
+## Duplicate conversations
+merge-needs-two = Wähle mindestens zwei verschiedene Unterhaltungen zum Zusammenführen
+
+## Conversation starters
+starter-name-required = Gib der Vorlage einen Namen
+starter-empty-conversation = Diese Unterhaltung hat noch keine Nachricht, mit der sie beginnen könnte
+starter-not-found = Es gibt keine Vorlage namens { $name }
+
+## Stream filters
+filter-invalid-pattern = Der Inhaltsfilter { $pattern } ist kein gültiger regulärer Ausdruck: { $error }
+
+## Regeneration
+regenerate-temperature-range = Die Temperatur muss zwischen 0 und { $max } liegen
+regenerate-not-an-answer = Nur eine Antwort auf eine Eingabe kann neu erzeugt werden
+replay-no-details = Für diese Antwort wurden keine Einstellungen gespeichert, daher kann sie nicht wiederholt werden
+
+## Response diffs
+diff-different-messages = Nur Alternativen zur selben Nachricht können verglichen werden
+
+## Evals
+eval-name-required = Gib der Evaluierungssuite einen Namen
+eval-no-cases = Füge der Suite mindestens eine Eingabe hinzu
+eval-no-models = Wähle mindestens ein Modell zum Evaluieren
+eval-no-judge = Wähle ein Modell, das die Antworten bewertet
+eval-interval-invalid = Geplante Läufe brauchen ein Intervall von mindestens einer Stunde
+eval-suite-not-found = Es gibt keine Evaluierungssuite namens { $name }
+eval-already-running = { $name } läuft bereits
+eval-judge-failed = Der Bewerter konnte diese Antwort nicht bewerten: { $error }
+
+## Benchmarks
+benchmark-no-models = Wähle mindestens ein Modell für den Benchmark
+benchmark-model-not-found = { $model } wird von keinem eingerichteten Anbieter angeboten
+
+## Werkzeugergebnisse
+tool-limit-too-small = Ein Limit für Werkzeugergebnisse muss mindestens { $min } Zeichen betragen
+tool-cache-ttl-too-long = Zwischengespeicherte Werkzeugergebnisse können höchstens { $hours } Stunden wiederverwendet werden
+tool-parallelism-invalid = Es können zwischen 1 und { $max } Werkzeugaufrufe gleichzeitig laufen
+
+## Geheimnisse
+secret-name-invalid = Ein Geheimnisname muss aus 1 bis { $max } Buchstaben, Ziffern, Punkten, Binde- oder Unterstrichen bestehen
+secret-owner-missing = Ein Geheimnis braucht das Plugin (plugin:<Ordner>) oder die Integration (integration:<Name>), die es lesen darf
+secret-value-empty = Ein Geheimnis darf nicht leer sein
+integration-not-found = Es gibt keine Integration namens { $name }
+integration-call-failed = { $integration } antwortete mit { $status }: { $body }
+oauth-no-refresh-token = { $integration } hat kein Aktualisierungstoken; melde dich erneut an
+
+## Eigene Modelle
+custom-model-id-missing = Gib den Modellnamen an, den Anfragen an den Endpunkt verwenden sollen
+custom-model-url-invalid = { $url } ist keine http://- oder https://-API-Adresse
+ollama-keep-alive-invalid = { $value } ist keine Haltedauer, die Ollama versteht; verwende Sekunden oder eine Dauer wie 30m
+azure-profile-name-missing = Geben Sie einen Namen ein, unter dem das Deployment aufgeführt wird
+azure-deployment-missing = Geben Sie den Namen des Azure-OpenAI-Deployments ein
+azure-profile-unknown = Es gibt kein Azure-OpenAI-Profil namens { $profile }
+azure-chat-only = Azure-OpenAI-Deployments können nur für den Chat verwendet werden
+bedrock-access-key-missing = Geben Sie die AWS-Zugriffsschlüssel-ID für Bedrock ein
+bedrock-region-invalid = { $region } ist keine AWS-Region wie us-east-1
+bedrock-chat-only = Bedrock-Modelle können nur für den Chat verwendet werden
+provider-header-reserved = { $name } setzt die App selbst, daher kann er kein zusätzlicher Header sein
+provider-header-invalid = { $name } ist kein gültiger HTTP-Header, oder sein Wert kann nicht gesendet werden
+gateway-provider-unsupported = { $gateway } kann keine Anfragen an { $provider } weiterleiten
+gateway-key-missing = Geben Sie Ihren { $gateway }-Schlüssel ein
+gateway-url-missing = Geben Sie die Adresse ein, unter der Ihr { $gateway }-Proxy erreichbar ist
+endpoint-name-invalid = Geben Sie dem Endpunkt einen Namen ohne Schrägstriche
+endpoint-models-invalid = { $endpoint } hat keine Modellliste zurückgegeben
+endpoint-unreachable = Die Modelle von { $endpoint } konnten nicht abgerufen werden: { $error }
+endpoint-unknown = Kein registrierter Endpunkt stellt { $model } bereit
+failover-same-provider = { $model } wird von { $provider } selbst bereitgestellt; wählen Sie ein Modell eines anderen Anbieters als Ausweichlösung
+maintenance-vacuumed = { $megabytes } MB freigegeben
+maintenance-caches-pruned = { $entries } abgelaufene Cache-Einträge entfernt
+maintenance-embedded = { $prompts } Prompts eingebettet
+maintenance-models-refreshed = { $models } Modelle abgerufen
+maintenance-no-provider = Übersprungen, kein Anbieter eingerichtet
+expansion-trigger-invalid = Kein gültiges Kürzel: { $trigger }; verwenden Sie nur Buchstaben, Ziffern, - und _
+expansion-text-missing = Geben Sie den Text ein, zu dem das Kürzel erweitert wird
+preprocess-file-unreadable = { $path } konnte nicht gelesen werden: { $error }
+preprocess-file-too-large = { $path } ist größer als { $limit_kb } KB und zu groß für eine Nachricht
+preprocess-file-not-text = { $path } ist keine Textdatei
+request-metadata-too-many = Eine Anfrage kann höchstens { $max } Metadaten-Tags tragen
+orchestration-goal-missing = Geben Sie das Ziel ein, auf das die Agenten hinarbeiten sollen
+orchestration-agent-count = Eine Orchestrierung braucht zwischen 2 und { $max } Agenten
+orchestration-agent-name-missing = Geben Sie jedem Agenten einen Namen
+orchestration-agent-name-taken = Zwei Agenten heißen { $name }
+orchestration-turns-invalid = Eine Orchestrierung kann zwischen 1 und { $max } Runden dauern
+orchestration-persona-unknown = Die Persona { $persona } wurde nicht gefunden
+plan-goal-missing = Gib nach /plan an, wofür der Plan ist
+plan-not-in-answer = Die Antwort enthielt keinen lesbaren Plan
+plan-step-count = Ein Plan braucht zwischen 1 und { $max } Schritte
+plan-step-duplicate = Mehr als ein Schritt hat die ID { $id }
+plan-dependency-unknown = Schritt { $step } hängt von Schritt { $dependency } ab, der nicht im Plan ist
+plan-dependency-cycle = Schritt { $step } hängt über andere Schritte von sich selbst ab
+plan-step-unknown = Der Plan hat keinen Schritt { $id }
+plan-not-found = Diese Unterhaltung hat keinen Plan; sende /plan mit einem Ziel, um einen zu erstellen
+request-metadata-too-long = Das Metadaten-Tag { $key } ist zu lang
+shaping-persona-unknown = Die Persona { $persona } zum Anpassen der Anfragen wurde nicht gefunden
+shaping-token-not-found = Server-Token { $id } nicht gefunden
+
+## Handoff
+handoff-nothing = Diese Unterhaltung hat weder Nachrichten noch einen Entwurf zum Übergeben
+handoff-server-not-running = Starte den API-Server, um Unterhaltungen an gekoppelte Geräte zu übergeben
+handoff-unknown-device = { $device } ist kein gekoppeltes Gerät und bei eingeschalteter Synchronisierung keines deiner synchronisierten Geräte
+pairing-server-not-running = Starte den API-Server, bevor du ein Gerät koppelst
+pairing-device-not-found = Gekoppeltes Gerät { $id } nicht gefunden
+pairing-loopback-only = Der API-Server nimmt nur Verbindungen von diesem Computer an; binde ihn an eine Netzwerkadresse, um Geräte zu koppeln
+handoff-not-found = Für dieses Gerät wartet keine Übergabe { $id }
+handoff-already-accepted = Diese Unterhaltung wurde bereits übernommen
+
+## Telemetry
+telemetry-endpoint-invalid = { $endpoint } ist keine http://- oder https://-Adresse eines OTLP-Collectors
+telemetry-header-invalid = Der Header { $name } ist kein gültiger HTTP-Header
@@ -0,0 +1,233 @@
+# Backend messages shown to the user. Ids are shared with the UI; add new messages here first,
+# since en-US is the fallback for anything a translation is missing.
+
+no-api-key = No API key configured
+no-provider-key = No { $provider } API key configured
+invalid-locale = Not a language tag: { $locale }
+
+## Uploads
+
+upload-invalid-sha256 = Expected a hex SHA-256 of the whole file
+upload-invalid-file-name = Invalid file name: { $file_name }
+upload-too-large = File is { $bytes } bytes; uploads are limited to { $limit } bytes
+upload-chunk-checksum = Chunk { $index } failed its checksum; send it again
+upload-chunk-past-end = Chunk { $index } is past the end of the upload
+upload-chunk-size = Chunk { $index } should be { $expected } bytes, got { $actual }
+upload-incomplete = Upload is missing { $missing } of { $total } chunks
+upload-checksum-mismatch = Uploaded file does not match its checksum; upload it again
+upload-not-found = Upload { $upload_id } not found
+upload-invalid-id = Invalid upload id: { $upload_id }
+upload-gone = Upload not found; it may have finished, been cancelled or expired
+
+## Export
+
+export-not-running = Export { $export_id } is not running
+export-cancelled = Export cancelled
+
+## Backups
+
+backup-unreadable = Backup archive is unreadable or corrupt: { $error }
+backup-no-manifest = Not a history backup: no manifest
+backup-too-new = Backup format { $version } is newer than this version supports
+backup-unexpected-path = Backup contains an unexpected path: { $path }
+backup-missing-entry = Backup is missing { $path }: { $error }
+backup-checksum-mismatch = Backup is corrupt: { $path } does not match its checksum
+
+## Sync
+
+sync-no-backend = No sync backend configured
+sync-unreadable = Can't read { $name }; is the sync key the same on every device? { $error }
+sync-key-not-base64 = Sync key is not valid base64
+sync-key-length = Sync key must be 32 bytes
+
+## History database
+
+history-migration-mismatch = History database migration { $version } ({ $name }) does not match the one this version ships
+history-schema-too-new = History database is at schema version { $version } but this version only knows up to { $latest }; update the app rather than risk damaging it
+history-migration-failed = History database migration { $version } ({ $name }) failed: { $error }
+history-integrity-failed = History database failed its integrity check ({ $result }); restore a backup before upgrading
+history-in-use = History is in use by another instance and can't be read: { $error }
+history-read-only = History is read-only while another instance has it open
+history-queue-full = Too many changes are waiting for another instance to close the history; close it to save more
+history-message-not-found = Message { $id } not found
+history-candidate-not-found = Candidate { $id } not found
+history-memory-not-found = Memory { $id } not found
+history-attachment-not-in-conversation = Attachment { $attachment_id } is not part of conversation { $conversation_id }
+
+## Screen reader announcements
+
+a11y-message-sent = Message sent
+a11y-response-started = { $model } is responding
+a11y-response-complete = Response complete
+a11y-response-failed = Response failed: { $error }
+a11y-response-replaced = The response was replaced with a better answer
+
+## Settings
+
+appearance-invalid-accent = Accent color { $color } is not a #rrggbb hex color
+appearance-invalid-font-size = Font size must be between { $min } and { $max } pixels
+appearance-invalid-font-family = { $family } is not a usable font family name
+settings-unreadable = Not a settings file: { $error }
+
+## Modes
+
+mode-chat-hint = Chat: messages go to the model
+mode-command-hint = Command: input runs plugin commands
+mode-agent-hint = Agent: tools run without asking
+mode-compare-hint = Compare: pick from several answers
+mode-busy = Wait for the response to finish before switching agent mode
+mode-agent-needs-tools = Agent mode needs a plugin that provides tools
+mode-no-messages = Messages can't be sent in command mode
+mode-no-plugin-commands = Plugin commands aren't available in compare mode
+plugin-not-found = No plugin with a readable module was found in { $id }
+
+## First run
+
+onboarding-complete = Setup is already complete
+onboarding-unknown-provider = { $provider } is not a supported provider
+onboarding-empty-key = Enter an API key
+onboarding-wrong-step = That action doesn't belong to the { $step } step
+
+## Demo mode
+
+demo-unavailable = The provider is not available in demo mode; turn demo mode off to use it
+
+## Feature flags
+
+feature-agent-mode = Agent mode
+feature-agent-mode-description = A mode where tool calls run without asking
+feature-api-server = API server
+feature-api-server-description = A local HTTP server for other programs to chat through
+feature-plugins = Plugins
+feature-plugins-description = WASM plugins with their own commands and tools
+feature-disabled = { $feature } is turned off
+feature-unknown = Unknown feature { $feature }
+
+## Portable mode
+
+portable-locked = Enter the passphrase for this portable folder first
+portable-wrong-passphrase = That passphrase doesn't unlock this portable folder
+portable-empty-passphrase = Enter a passphrase
+portable-inactive = Portable mode is off
+
+## Policy
+policy-provider-disallowed = Your administrator's policy doesn't allow the { $provider } provider
+policy-spend-cap = This month's spending has reached the { $cap } USD limit your administrator set
+policy-locked-setting = Your administrator has locked the { $setting } setting
+template-repository-invalid = { $repository } isn't a git URL; use one starting with https://, ssh:// or git@
+
+## Conversation locks
+conversation-locked = This conversation is locked; unlock it to continue
+conversation-unlock-reason = Unlock a locked conversation
+os-auth-failed = Authentication failed or was cancelled
+os-auth-unavailable = This system has no authentication prompt available to unlock conversations
+
+## Duplicate conversations
+merge-needs-two = Choose at least two different conversations to merge
+
+## Conversation starters
+starter-name-required = Give the starter a name
+starter-empty-conversation = This conversation has no message to start from yet
+starter-not-found = There is no starter named { $name }
+
+## Stream filters
+filter-invalid-pattern = The content filter { $pattern } isn't a valid regular expression: { $error }
+
+## Regeneration
+regenerate-temperature-range = Temperature must be between 0 and { $max }
+regenerate-not-an-answer = Only an answer to a prompt can be regenerated
+replay-no-details = No settings were recorded for this answer, so it can't be replayed
+
+## Response diffs
+diff-different-messages = Only alternatives to the same message can be compared
+
+## Evals
+eval-name-required = Give the eval suite a name
+eval-no-cases = Add at least one prompt to the suite
+eval-no-models = Choose at least one model to evaluate
+eval-no-judge = Choose a model to judge the answers
+eval-interval-invalid = Scheduled runs need an interval of at least one hour
+eval-suite-not-found = There is no eval suite named { $name }
+eval-already-running = { $name } is already running
+eval-judge-failed = The judge couldn't score this answer: { $error }
+
+## Benchmarks
+benchmark-no-models = Choose at least one model to benchmark
+benchmark-model-not-found = { $model } isn't offered by any configured provider
+
+## Tool results
+tool-limit-too-small = A tool result limit must be at least { $min } characters
+tool-cache-ttl-too-long = Cached tool results can be reused for at most { $hours } hours
+tool-parallelism-invalid = Between 1 and { $max } tool calls can run at once
+
+## Secrets
+secret-name-invalid = A secret name must be 1 to { $max } letters, digits, dots, dashes or underscores
+secret-owner-missing = A secret needs the plugin (plugin:<folder>) or integration (integration:<name>) that may read it
+secret-value-empty = A secret can't be empty
+integration-not-found = There is no integration named { $name }
+integration-call-failed = { $integration } returned { $status }: { $body }
+oauth-no-refresh-token = { $integration } has no refresh token; sign in again
+
+## Custom models
+custom-model-id-missing = Enter the model name requests to the endpoint should use
+custom-model-url-invalid = { $url } isn't an http:// or https:// API address
+ollama-keep-alive-invalid = { $value } isn't a keep-alive Ollama understands; use seconds or a duration such as 30m
+azure-profile-name-missing = Enter a name to list the deployment under
+azure-deployment-missing = Enter the name of the Azure OpenAI deployment
+azure-profile-unknown = No Azure OpenAI profile is named { $profile }
+azure-chat-only = Azure OpenAI deployments can only be used for chat
+bedrock-access-key-missing = Enter the AWS access key ID to reach Bedrock with
+bedrock-region-invalid = { $region } is not an AWS region, such as us-east-1
+bedrock-chat-only = Bedrock models can only be used for chat
+provider-header-reserved = The app sets { $name } itself, so it can't be an extra header
+provider-header-invalid = { $name } isn't a valid HTTP header, or its value can't be sent
+gateway-provider-unsupported = { $gateway } can't route requests to { $provider }
+gateway-key-missing = Enter your { $gateway } key
+gateway-url-missing = Enter the address your { $gateway } proxy listens at
+endpoint-name-invalid = Give the endpoint a name without slashes
+endpoint-models-invalid = { $endpoint } didn't answer with a list of models
+endpoint-unreachable = Couldn't list the models of { $endpoint }: { $error }
+endpoint-unknown = No registered endpoint serves { $model }
+failover-same-provider = { $model } is served by { $provider } itself; fall back to a model on another provider
+maintenance-vacuumed = Freed { $megabytes } MB
+maintenance-caches-pruned = Dropped { $entries } expired cache entries
+maintenance-embedded = Embedded { $prompts } prompts
+maintenance-models-refreshed = Listed { $models } models
+maintenance-no-provider = Skipped, no provider is set up
+expansion-trigger-invalid = Not a usable shortcut: { $trigger }; use letters, digits, - and _ only
+expansion-text-missing = Enter the text the shortcut expands to
+preprocess-file-unreadable = Couldn't read { $path }: { $error }
+preprocess-file-too-large = { $path } is larger than { $limit_kb } KB, too large to include in a message
+preprocess-file-not-text = { $path } isn't a text file
+request-metadata-too-many = A request can carry at most { $max } metadata tags
+orchestration-goal-missing = Enter the goal the agents should work toward
+orchestration-agent-count = An orchestration needs between 2 and { $max } agents
+orchestration-agent-name-missing = Give every agent a name
+orchestration-agent-name-taken = Two agents are named { $name }
+orchestration-turns-invalid = An orchestration can take between 1 and { $max } turns
+orchestration-persona-unknown = No persona { $persona } was found
+plan-goal-missing = Say what the plan is for after /plan
+plan-not-in-answer = The answer didn't contain a readable plan
+plan-step-count = A plan needs between 1 and { $max } steps
+plan-step-duplicate = More than one step has the id { $id }
+plan-dependency-unknown = Step { $step } depends on step { $dependency }, which isn't in the plan
+plan-dependency-cycle = Step { $step } depends on itself through other steps
+plan-step-unknown = The plan has no step { $id }
+plan-not-found = This conversation has no plan; send /plan with a goal to make one
+request-metadata-too-long = The metadata tag { $key } is too long
+shaping-persona-unknown = No persona { $persona } was found to shape requests with
+shaping-token-not-found = Server token { $id } not found
+
+## Handoff
+handoff-nothing = This conversation has no messages or draft to hand over
+handoff-server-not-running = Start the API server to hand conversations to paired devices
+handoff-unknown-device = { $device } isn't a paired device, or one of your synced devices with sync turned on
+pairing-server-not-running = Start the API server before pairing a device
+pairing-device-not-found = Paired device { $id } not found
+pairing-loopback-only = The API server only accepts connections from this computer; bind it to a network address to pair devices
+handoff-not-found = No handoff { $id } is waiting for this device
+handoff-already-accepted = This conversation has already been taken over
+
+## Telemetry
+telemetry-endpoint-invalid = { $endpoint } isn't an http:// or https:// address of an OTLP collector
+telemetry-header-invalid = The header { $name } isn't a valid HTTP header
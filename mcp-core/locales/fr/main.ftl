@@ -0,0 +1,230 @@
+no-api-key = Aucune clé API configurée
+no-provider-key = Aucune clé API { $provider } configurée
+invalid-locale = Ce n'est pas une étiquette de langue : { $locale }
+
+## Uploads
+
+upload-invalid-sha256 = Un SHA-256 hexadécimal du fichier entier est attendu
+upload-invalid-file-name = Nom de fichier invalide : { $file_name }
+upload-too-large = Le fichier fait { $bytes } octets ; les envois sont limités à { $limit } octets
+upload-chunk-checksum = Le fragment { $index } a échoué à la vérification ; renvoyez-le
+upload-chunk-past-end = Le fragment { $index } dépasse la fin de l'envoi
+upload-chunk-size = Le fragment { $index } devrait faire { $expected } octets, il en fait { $actual }
+upload-incomplete = Il manque { $missing } fragments sur { $total } à l'envoi
+upload-checksum-mismatch = Le fichier envoyé ne correspond pas à sa somme de contrôle ; envoyez-le à nouveau
+upload-not-found = Envoi { $upload_id } introuvable
+upload-invalid-id = Identifiant d'envoi invalide : { $upload_id }
+upload-gone = Envoi introuvable ; il est peut-être terminé, annulé ou expiré
+
+## Export
+
+export-not-running = L'export { $export_id } n'est pas en cours
+export-cancelled = Export annulé
+
+## Backups
+
+backup-unreadable = L'archive de sauvegarde est illisible ou corrompue : { $error }
+backup-no-manifest = Ce n'est pas une sauvegarde de l'historique : manifeste absent
+backup-too-new = Le format de sauvegarde { $version } est plus récent que ce que cette version prend en charge
+backup-unexpected-path = La sauvegarde contient un chemin inattendu : { $path }
+backup-missing-entry = Il manque { $path } dans la sauvegarde : { $error }
+backup-checksum-mismatch = La sauvegarde est corrompue : { $path } ne correspond pas à sa somme de contrôle
+
+## Sync
+
+sync-no-backend = Aucun service de synchronisation configuré
+sync-unreadable = Impossible de lire { $name } ; la clé de synchronisation est-elle la même sur chaque appareil ? { $error }
+sync-key-not-base64 = La clé de synchronisation n'est pas du base64 valide
+sync-key-length = La clé de synchronisation doit faire 32 octets
+
+## History database
+
+history-migration-mismatch = La migration { $version } ({ $name }) de l'historique ne correspond pas à celle de cette version
+history-schema-too-new = L'historique est au schéma { $version } mais cette version ne connaît que jusqu'à { $latest } ; mettez l'application à jour plutôt que de risquer de l'endommager
+history-migration-failed = La migration { $version } ({ $name }) de l'historique a échoué : { $error }
+history-integrity-failed = L'historique a échoué à la vérification d'intégrité ({ $result }) ; restaurez une sauvegarde avant la mise à jour
+history-in-use = L'historique est utilisé par une autre instance et ne peut pas être lu : { $error }
+history-read-only = L'historique est en lecture seule tant qu'une autre instance l'a ouvert
+history-queue-full = Trop de modifications attendent qu'une autre instance ferme l'historique ; fermez-la pour en enregistrer d'autres
+history-message-not-found = Message { $id } introuvable
+history-candidate-not-found = Candidat { $id } introuvable
+history-memory-not-found = Souvenir { $id } introuvable
+history-attachment-not-in-conversation = La pièce jointe { $attachment_id } ne fait pas partie de la conversation { $conversation_id }
+
+## Screen reader announcements
+
+a11y-message-sent = Message envoyé
+a11y-response-started = { $model } répond
+a11y-response-complete = Réponse terminée
+a11y-response-failed = La réponse a échoué : { $error }
+a11y-response-replaced = La réponse a été remplacée par une meilleure
+
+## Settings
+
+appearance-invalid-accent = La couleur d'accent { $color } n'est pas une couleur hexadécimale #rrggbb
+appearance-invalid-font-size = La taille de police doit être comprise entre { $min } et { $max } pixels
+appearance-invalid-font-family = { $family } n'est pas un nom de police utilisable
+settings-unreadable = Ce n'est pas un fichier de réglages : { $error }
+
+## Modes
+
+mode-chat-hint = Discussion : les messages vont au modèle
+mode-command-hint = Commande : la saisie exécute des commandes de plugin
+mode-agent-hint = Agent : les outils s'exécutent sans demander
+mode-compare-hint = Comparaison : choisissez parmi plusieurs réponses
+mode-busy = Attendez la fin de la réponse avant de changer le mode agent
+mode-agent-needs-tools = Le mode agent nécessite un plugin qui fournit des outils
+mode-no-messages = Impossible d'envoyer des messages en mode commande
+mode-no-plugin-commands = Les commandes de plugin ne sont pas disponibles en mode comparaison
+plugin-not-found = Aucun plugin avec un module lisible n'a été trouvé dans { $id }
+
+## First run
+
+onboarding-complete = La configuration est déjà terminée
+onboarding-unknown-provider = { $provider } n'est pas un fournisseur pris en charge
+onboarding-empty-key = Saisissez une clé API
+onboarding-wrong-step = Cette action n'appartient pas à l'étape { $step }
+
+## Demo mode
+
+demo-unavailable = Le fournisseur n'est pas disponible en mode démo ; désactivez le mode démo pour l'utiliser
+
+## Feature flags
+
+feature-agent-mode = Mode agent
+feature-agent-mode-description = Un mode où les outils s'exécutent sans confirmation
+feature-api-server = Serveur API
+feature-api-server-description = Un serveur HTTP local par lequel d'autres programmes peuvent discuter
+feature-plugins = Plugins
+feature-plugins-description = Des plugins WASM avec leurs propres commandes et outils
+feature-disabled = { $feature } est désactivé
+feature-unknown = Fonctionnalité inconnue { $feature }
+
+## Portable mode
+
+portable-locked = Saisissez d'abord la phrase secrète de ce dossier portable
+portable-wrong-passphrase = Cette phrase secrète ne déverrouille pas ce dossier portable
+portable-empty-passphrase = Saisissez une phrase secrète
+portable-inactive = Le mode portable est désactivé
+
+## Policy
+policy-provider-disallowed = La stratégie de votre administrateur n'autorise pas le fournisseur { $provider }
+policy-spend-cap = Les dépenses de ce mois ont atteint la limite de { $cap } USD fixée par votre administrateur
+policy-locked-setting = Votre administrateur a verrouillé le paramètre { $setting }
+template-repository-invalid = { $repository } n'est pas une URL git ; utilisez-en une commençant par https://, ssh:// ou git@
+
+## Conversation locks
+conversation-locked = Cette conversation est verrouillée ; déverrouillez-la pour continuer
+conversation-unlock-reason = Déverrouiller une conversation verrouillée
+os-auth-failed = L'authentification a échoué ou a été annulée
+os-auth-unavailable = Ce système ne propose aucune invite d'authentification pour déverrouiller les conversations
+
+## Duplicate conversations
+merge-needs-two = Choisissez au moins deux conversations différentes à fusionner
+
+## Conversation starters
+starter-name-required = Donnez un nom au modèle de conversation
+starter-empty-conversation = Cette conversation n'a pas encore de message par lequel commencer
+starter-not-found = Aucun modèle de conversation ne s'appelle { $name }
+
+## Stream filters
+filter-invalid-pattern = Le filtre de contenu { $pattern } n'est pas une expression régulière valide : { $error }
+
+## Regeneration
+regenerate-temperature-range = La température doit être comprise entre 0 et { $max }
+regenerate-not-an-answer = Seule une réponse à un message peut être régénérée
+replay-no-details = Aucun réglage n'a été enregistré pour cette réponse, elle ne peut donc pas être rejouée
+
+## Response diffs
+diff-different-messages = Seules les alternatives d'un même message peuvent être comparées
+
+## Evals
+eval-name-required = Donnez un nom à la suite d'évaluation
+eval-no-cases = Ajoutez au moins un message à la suite
+eval-no-models = Choisissez au moins un modèle à évaluer
+eval-no-judge = Choisissez un modèle pour noter les réponses
+eval-interval-invalid = Les exécutions planifiées doivent être espacées d'au moins une heure
+eval-suite-not-found = Aucune suite d'évaluation ne s'appelle { $name }
+eval-already-running = { $name } est déjà en cours d'exécution
+eval-judge-failed = Le juge n'a pas pu noter cette réponse : { $error }
+
+## Benchmarks
+benchmark-no-models = Choisissez au moins un modèle à comparer
+benchmark-model-not-found = { $model } n'est proposé par aucun fournisseur configuré
+
+## Résultats d'outils
+tool-limit-too-small = Une limite de résultat d'outil doit être d'au moins { $min } caractères
+tool-cache-ttl-too-long = Les résultats d'outils en cache peuvent être réutilisés pendant { $hours } heures au plus
+tool-parallelism-invalid = Entre 1 et { $max } appels d'outils peuvent s'exécuter en même temps
+
+## Secrets
+secret-name-invalid = Un nom de secret doit comporter de 1 à { $max } lettres, chiffres, points, tirets ou traits de soulignement
+secret-owner-missing = Un secret a besoin du plugin (plugin:<dossier>) ou de l'intégration (integration:<nom>) autorisé à le lire
+secret-value-empty = Un secret ne peut pas être vide
+integration-not-found = Aucune intégration nommée { $name }
+integration-call-failed = { $integration } a répondu { $status } : { $body }
+oauth-no-refresh-token = { $integration } n'a pas de jeton d'actualisation ; reconnectez-vous
+
+## Modèles personnalisés
+custom-model-id-missing = Indiquez le nom de modèle que les requêtes vers le point de terminaison doivent utiliser
+custom-model-url-invalid = { $url } n'est pas une adresse d'API http:// ou https://
+ollama-keep-alive-invalid = { $value } n'est pas une durée de maintien comprise par Ollama ; utilisez des secondes ou une durée comme 30m
+azure-profile-name-missing = Saisissez un nom sous lequel lister le déploiement
+azure-deployment-missing = Saisissez le nom du déploiement Azure OpenAI
+azure-profile-unknown = Aucun profil Azure OpenAI ne s'appelle { $profile }
+azure-chat-only = Les déploiements Azure OpenAI ne peuvent servir qu'au chat
+bedrock-access-key-missing = Saisissez l'ID de clé d'accès AWS pour joindre Bedrock
+bedrock-region-invalid = { $region } n'est pas une région AWS, comme us-east-1
+bedrock-chat-only = Les modèles Bedrock ne peuvent servir qu'au chat
+provider-header-reserved = L'application définit { $name } elle-même, il ne peut donc pas être un en-tête supplémentaire
+provider-header-invalid = { $name } n'est pas un en-tête HTTP valide, ou sa valeur ne peut pas être envoyée
+gateway-provider-unsupported = { $gateway } ne peut pas acheminer les requêtes vers { $provider }
+gateway-key-missing = Saisissez votre clé { $gateway }
+gateway-url-missing = Saisissez l'adresse à laquelle votre proxy { $gateway } écoute
+endpoint-name-invalid = Donnez au point de terminaison un nom sans barre oblique
+endpoint-models-invalid = { $endpoint } n'a pas renvoyé de liste de modèles
+endpoint-unreachable = Impossible de lister les modèles de { $endpoint } : { $error }
+endpoint-unknown = Aucun point de terminaison enregistré ne sert { $model }
+failover-same-provider = { $model } est servi par { $provider } lui-même ; choisissez un modèle d'un autre fournisseur comme solution de repli
+maintenance-vacuumed = { $megabytes } Mo libérés
+maintenance-caches-pruned = { $entries } entrées de cache expirées supprimées
+maintenance-embedded = { $prompts } prompts vectorisés
+maintenance-models-refreshed = { $models } modèles récupérés
+maintenance-no-provider = Ignoré, aucun fournisseur n'est configuré
+expansion-trigger-invalid = Raccourci inutilisable : { $trigger } ; utilisez uniquement des lettres, des chiffres, - et _
+expansion-text-missing = Saisissez le texte par lequel le raccourci est remplacé
+preprocess-file-unreadable = Impossible de lire { $path } : { $error }
+preprocess-file-too-large = { $path } dépasse { $limit_kb } Ko, trop volumineux pour un message
+preprocess-file-not-text = { $path } n'est pas un fichier texte
+request-metadata-too-many = Une requête peut porter au plus { $max } étiquettes de métadonnées
+orchestration-goal-missing = Saisissez l'objectif vers lequel les agents doivent travailler
+orchestration-agent-count = Une orchestration nécessite entre 2 et { $max } agents
+orchestration-agent-name-missing = Donnez un nom à chaque agent
+orchestration-agent-name-taken = Deux agents s'appellent { $name }
+orchestration-turns-invalid = Une orchestration peut compter entre 1 et { $max } tours
+orchestration-persona-unknown = Aucun persona { $persona } n'a été trouvé
+plan-goal-missing = Indiquez l'objet du plan après /plan
+plan-not-in-answer = La réponse ne contenait pas de plan lisible
+plan-step-count = Un plan doit compter entre 1 et { $max } étapes
+plan-step-duplicate = Plusieurs étapes ont l'identifiant { $id }
+plan-dependency-unknown = L'étape { $step } dépend de l'étape { $dependency }, absente du plan
+plan-dependency-cycle = L'étape { $step } dépend d'elle-même par d'autres étapes
+plan-step-unknown = Le plan n'a pas d'étape { $id }
+plan-not-found = Cette conversation n'a pas de plan ; envoyez /plan avec un objectif pour en créer un
+request-metadata-too-long = L'étiquette de métadonnées { $key } est trop longue
+shaping-persona-unknown = Aucun persona { $persona } n'a été trouvé pour adapter les requêtes
+shaping-token-not-found = Jeton serveur { $id } introuvable
+
+## Handoff
+handoff-nothing = Cette conversation n'a ni message ni brouillon à transférer
+handoff-server-not-running = Démarrez le serveur API pour transférer des conversations vers les appareils associés
+handoff-unknown-device = { $device } n'est ni un appareil associé, ni l'un de vos appareils synchronisés avec la synchronisation activée
+pairing-server-not-running = Démarrez le serveur API avant d'associer un appareil
+pairing-device-not-found = Appareil associé { $id } introuvable
+pairing-loopback-only = Le serveur API n'accepte que les connexions de cet ordinateur ; liez-le à une adresse réseau pour associer des appareils
+handoff-not-found = Aucun transfert { $id } n'attend cet appareil
+handoff-already-accepted = Cette conversation a déjà été reprise
+
+## Telemetry
+telemetry-endpoint-invalid = { $endpoint } n'est pas une adresse http:// ou https:// de collecteur OTLP
+telemetry-header-invalid = L'en-tête { $name } n'est pas un en-tête HTTP valide
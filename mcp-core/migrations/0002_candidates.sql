@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS candidates (
+    id TEXT PRIMARY KEY,
+    message_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    content TEXT NOT NULL,
+    selected INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_candidates_message ON candidates (message_id);
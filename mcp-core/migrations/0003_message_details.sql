@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS message_details (
+    message_id TEXT PRIMARY KEY,
+    details TEXT NOT NULL
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS memories (
+    id TEXT PRIMARY KEY,
+    content TEXT NOT NULL,
+    conversation_id TEXT,
+    created_at TEXT NOT NULL
+);
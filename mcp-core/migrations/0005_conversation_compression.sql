@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS conversation_compression (
+    conversation_id TEXT PRIMARY KEY,
+    enabled INTEGER NOT NULL,
+    ratio REAL NOT NULL,
+    last_before_tokens INTEGER,
+    last_after_tokens INTEGER
+);
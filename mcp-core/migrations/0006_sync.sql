@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS sync_state (
+    key TEXT PRIMARY KEY,
+    clock TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    device_id TEXT NOT NULL,
+    pending INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sync_applied (
+    object TEXT PRIMARY KEY,
+    applied_at TEXT NOT NULL
+);
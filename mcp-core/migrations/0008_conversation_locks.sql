@@ -0,0 +1,4 @@
+CREATE TABLE conversation_locks (
+    conversation_id TEXT PRIMARY KEY,
+    locked_at TEXT NOT NULL
+);
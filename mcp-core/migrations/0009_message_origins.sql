@@ -0,0 +1,5 @@
+CREATE TABLE message_origins (
+    message_id TEXT PRIMARY KEY,
+    conversation_id TEXT NOT NULL,
+    merged_at TEXT NOT NULL
+);
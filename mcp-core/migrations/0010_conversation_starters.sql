@@ -0,0 +1,23 @@
+CREATE TABLE conversation_starters (
+    name TEXT PRIMARY KEY,
+    system_prompt TEXT,
+    first_message TEXT NOT NULL,
+    model TEXT,
+    created_at TEXT NOT NULL
+);
+
+-- Attachments a starter brings into each conversation; they count as references so cleanup keeps them
+CREATE TABLE starter_attachments (
+    starter_name TEXT NOT NULL,
+    attachment_id TEXT NOT NULL,
+    PRIMARY KEY (starter_name, attachment_id)
+);
+
+-- What a conversation was started with, copied so editing or deleting the starter leaves it alone
+CREATE TABLE starter_conversations (
+    conversation_id TEXT PRIMARY KEY,
+    starter_name TEXT NOT NULL,
+    system_prompt TEXT,
+    model TEXT,
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Stop sequences and content filter patterns, each a JSON array of strings
+CREATE TABLE conversation_filters (
+    conversation_id TEXT PRIMARY KEY,
+    stop_sequences TEXT NOT NULL,
+    content_filters TEXT NOT NULL
+);
@@ -0,0 +1,5 @@
+-- How each regenerated alternative was produced, as message_details records it for messages
+CREATE TABLE candidate_details (
+    candidate_id TEXT PRIMARY KEY,
+    details TEXT NOT NULL
+);
@@ -0,0 +1,35 @@
+-- Saved prompt suites for judged evaluation runs; cases and models are JSON arrays
+CREATE TABLE eval_suites (
+    name TEXT PRIMARY KEY,
+    cases TEXT NOT NULL,
+    models TEXT NOT NULL,
+    judge_model TEXT NOT NULL,
+    interval_hours INTEGER,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE eval_runs (
+    id TEXT PRIMARY KEY,
+    suite_name TEXT NOT NULL,
+    judge_model TEXT NOT NULL,
+    scheduled INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT
+);
+
+CREATE INDEX eval_runs_suite ON eval_runs (suite_name, started_at);
+
+-- One answer per case and model, with the judge's score out of 10
+CREATE TABLE eval_results (
+    run_id TEXT NOT NULL,
+    case_index INTEGER NOT NULL,
+    model TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    output TEXT NOT NULL,
+    score REAL,
+    reasoning TEXT NOT NULL,
+    error TEXT,
+    estimated_usd REAL,
+    PRIMARY KEY (run_id, case_index, model)
+);
@@ -0,0 +1,21 @@
+-- Conversations handed to another device; enabled_tools is a JSON array
+CREATE TABLE handoffs (
+    id TEXT PRIMARY KEY,
+    conversation_id TEXT NOT NULL,
+    from_device TEXT NOT NULL,
+    to_device TEXT NOT NULL,
+    transport TEXT NOT NULL,
+    draft TEXT NOT NULL,
+    enabled_tools TEXT NOT NULL,
+    resume_message_id TEXT,
+    sent_at TEXT NOT NULL,
+    accepted_at TEXT
+);
+
+CREATE INDEX handoffs_to_device ON handoffs (to_device, accepted_at);
+
+-- Other devices whose changes came in through sync, which conversations can be handed to
+CREATE TABLE sync_devices (
+    device_id TEXT PRIMARY KEY,
+    last_seen_at TEXT NOT NULL
+);
@@ -0,0 +1,22 @@
+-- Provider benchmark reports: one prompt streamed by several models in turn
+CREATE TABLE benchmark_reports (
+    id TEXT PRIMARY KEY,
+    prompt TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT
+);
+
+CREATE TABLE benchmark_results (
+    report_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    ttft_ms INTEGER,
+    total_ms INTEGER,
+    tokens_per_second REAL,
+    output_tokens INTEGER NOT NULL,
+    output_chars INTEGER NOT NULL,
+    estimated_usd REAL,
+    error TEXT,
+    PRIMARY KEY (report_id, position)
+);
@@ -0,0 +1,13 @@
+-- Every tool call, for usage and reliability statistics
+CREATE TABLE tool_invocations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    plugin TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    called_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    cached INTEGER NOT NULL,
+    error TEXT,
+    result_tokens INTEGER NOT NULL
+);
+
+CREATE INDEX tool_invocations_called_at ON tool_invocations (called_at);
@@ -0,0 +1,7 @@
+-- Answers completed while their conversation wasn't on screen, and when each conversation was
+-- last read
+CREATE TABLE conversation_reads (
+    conversation_id TEXT PRIMARY KEY,
+    unread INTEGER NOT NULL DEFAULT 0,
+    last_read_at TEXT
+);
@@ -0,0 +1,8 @@
+-- Embeddings of prompts, filled in while the app is idle so insights and duplicate detection
+-- needn't fetch them again. `text_hash` covers the embedding model and the text sent, so a
+-- prompt is embedded again once either changes (e.g. redaction was turned on).
+CREATE TABLE prompt_embeddings (
+    message_id TEXT PRIMARY KEY,
+    text_hash TEXT NOT NULL,
+    embedding BLOB NOT NULL
+);
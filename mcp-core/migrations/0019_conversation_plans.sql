@@ -0,0 +1,9 @@
+-- The task plan a conversation is working through, from its latest /plan; `steps` is the JSON
+-- array of steps with their status
+CREATE TABLE conversation_plans (
+    conversation_id TEXT PRIMARY KEY,
+    goal TEXT NOT NULL,
+    steps TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,93 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::events::{self, AppEvent};
+use crate::generation::MessageStream;
+use crate::StreamMessage;
+
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct AccessibilitySettings {
+    /// Deliver each answer as one whole message instead of token by token, for screen readers
+    /// and reduced motion
+    #[serde(default)]
+    pub plain_stream: bool,
+    /// Speak answers sentence by sentence as they stream
+    #[serde(default)]
+    pub read_aloud: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum MessageRole {
+    User,
+    Assistant,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum MessageBoundary {
+    Start,
+    End,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum CompletionStatus {
+    Streaming,
+    Complete,
+    /// The stream failed; what arrived was kept as a partial answer
+    Failed,
+    /// Everything streamed so far was swapped for a different answer, which starts over
+    Replaced,
+}
+
+/// How urgently a screen reader should speak the announcement, as in `aria-live`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum LivePoliteness {
+    Polite,
+    Assertive,
+}
+
+/// Publish an AccessibilityHint for `message_id`. Failures are assertive, everything else polite.
+pub fn announce(message_id: &str, role: MessageRole, boundary: MessageBoundary, status: CompletionStatus, announcement: String) {
+    let politeness = match status {
+        CompletionStatus::Failed => LivePoliteness::Assertive,
+        _ => LivePoliteness::Polite,
+    };
+    events::publish(AppEvent::AccessibilityHint {
+        message_id: message_id.to_string(),
+        role,
+        boundary,
+        status,
+        politeness,
+        announcement,
+    });
+}
+
+/// Hold back Content until the answer is finished, then deliver it as one chunk before the
+/// Error or Complete that ends it (or when the stream ends). A Replace swaps the held text.
+pub fn whole_message(stream: MessageStream) -> MessageStream {
+    Box::pin(futures::stream::unfold((Some(stream), String::new(), None), |(mut stream, mut text, mut ending)| async move {
+        if let Some(message) = ending.take() {
+            return Some((message, (stream, text, None)));
+        }
+        loop {
+            let Some(message) = stream.as_mut()?.next().await else {
+                stream = None;
+                if text.is_empty() {
+                    return None;
+                }
+                return Some((StreamMessage::Content(std::mem::take(&mut text)), (stream, text, None)));
+            };
+            match message {
+                StreamMessage::Content(content) => text.push_str(&content),
+                StreamMessage::Replace(replacement) => text = replacement,
+                usage @ StreamMessage::Usage { .. } => return Some((usage, (stream, text, None))),
+                message if text.is_empty() => return Some((message, (stream, text, None))),
+                message => return Some((StreamMessage::Content(std::mem::take(&mut text)), (stream, text, Some(message)))),
+            }
+        }
+    }))
+}
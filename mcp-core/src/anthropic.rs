@@ -0,0 +1,280 @@
+//! Anthropic's Messages API, which Claude models are streamed from. It isn't OpenAI-compatible:
+//! the system prompt is a field of its own, `max_tokens` is required, and the stream is a
+//! sequence of named SSE events (`message_start`, `content_block_delta`, `message_delta`, ...)
+//! rather than chat chunks. Requests are built from the chat request and the events turned
+//! back into chat chunks, so the rest of the streaming path doesn't need to know.
+
+use async_openai::error::{ApiError, OpenAIError};
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionStreamResponse, Stop};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use crate::ModelInfo;
+use crate::providers::{self, Provider};
+use crate::sse;
+
+/// The Messages API version requests are written against
+pub const API_VERSION: &str = "2023-06-01";
+
+/// Sent when the chat request has no token limit, since the Messages API requires one
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Context window of every current Claude model
+const CONTEXT_LENGTH: u32 = 200_000;
+
+/// What the stream has said about the message so far, for the chunks after `message_start`
+#[derive(Default)]
+pub struct StreamState {
+    id: String,
+    model: String,
+    input_tokens: u32,
+}
+
+impl StreamState {
+    /// The chat chunk `event` amounts to, if any; pings, block boundaries and deltas other than
+    /// text have none
+    pub fn chunk(&mut self, event: &sse::Event) -> Option<Result<CreateChatCompletionStreamResponse, OpenAIError>> {
+        let data: Value = match serde_json::from_str(&event.data) {
+            Ok(data) => data,
+            Err(e) if event.event.as_deref() == Some("error") => {
+                return Some(Err(OpenAIError::JSONDeserialize(e)));
+            }
+            Err(e) => {
+                if !event.data.is_empty() {
+                    log::warn!("Skipped an unreadable Anthropic event ({}): {}", e, event.data.chars().take(120).collect::<String>());
+                }
+                return None;
+            }
+        };
+        match data["type"].as_str().or(event.event.as_deref()) {
+            Some("message_start") => {
+                let message = &data["message"];
+                self.id = message["id"].as_str().unwrap_or_default().to_string();
+                self.model = message["model"].as_str().unwrap_or_default().to_string();
+                self.input_tokens = message["usage"]["input_tokens"].as_u64().unwrap_or(0) as u32;
+                None
+            }
+            Some("content_block_delta") if data["delta"]["type"] == "text_delta" => {
+                Some(self.chat_chunk(json!({ "content": data["delta"]["text"] }), None, None))
+            }
+            Some("message_delta") => {
+                let output_tokens = data["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32;
+                let usage = json!({
+                    "prompt_tokens": self.input_tokens,
+                    "completion_tokens": output_tokens,
+                    "total_tokens": self.input_tokens + output_tokens,
+                });
+                let finish_reason = data["delta"]["stop_reason"].as_str().map(finish_reason);
+                Some(self.chat_chunk(json!({}), finish_reason, Some(usage)))
+            }
+            Some("error") => Some(Err(OpenAIError::ApiError(api_error(&data)))),
+            _ => None,
+        }
+    }
+
+    fn chat_chunk(&self, delta: Value, finish_reason: Option<&str>, usage: Option<Value>) -> Result<CreateChatCompletionStreamResponse, OpenAIError> {
+        serde_json::from_value(json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": chrono::Utc::now().timestamp(),
+            "model": self.model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+            "usage": usage,
+        }))
+        .map_err(OpenAIError::JSONDeserialize)
+    }
+}
+
+/// The chat finish reason closest to a Messages API stop reason
+fn finish_reason(stop_reason: &str) -> &'static str {
+    match stop_reason {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "refusal" => "content_filter",
+        _ => "stop",
+    }
+}
+
+/// The error in an `{"type": "error", "error": {"type", "message"}}` body or event
+fn api_error(body: &Value) -> ApiError {
+    ApiError {
+        message: body["error"]["message"].as_str().unwrap_or("Anthropic API error").to_string(),
+        r#type: body["error"]["type"].as_str().map(str::to_string),
+        param: None,
+        code: None,
+    }
+}
+
+/// The Messages API request equivalent to a chat request. System messages become the system
+/// prompt; tool results are passed on as user turns, and consecutive turns of one role are
+/// joined, since the API expects user and assistant to alternate.
+pub fn messages_request(chat: &CreateChatCompletionRequest) -> Value {
+    let mut system = Vec::new();
+    let mut messages: Vec<(&str, String)> = Vec::new();
+    for message in &chat.messages {
+        let message = serde_json::to_value(message).unwrap_or_default();
+        let text = providers::message_text(&message);
+        let role = match message["role"].as_str() {
+            Some("system") | Some("developer") => {
+                system.push(text);
+                continue;
+            }
+            Some("assistant") => "assistant",
+            _ => "user",
+        };
+        match messages.last_mut() {
+            Some((last_role, last_text)) if *last_role == role => {
+                last_text.push_str("\n\n");
+                last_text.push_str(&text);
+            }
+            _ => messages.push((role, text)),
+        }
+    }
+
+    #[allow(deprecated)]
+    let max_tokens = chat.max_completion_tokens.or(chat.max_tokens).unwrap_or(DEFAULT_MAX_TOKENS);
+    let mut request = json!({
+        "model": providers::resolve(&chat.model).1,
+        "max_tokens": max_tokens,
+        "messages": messages.iter()
+            .map(|(role, text)| json!({ "role": role, "content": text }))
+            .collect::<Vec<_>>(),
+        "stream": true,
+    });
+    if !system.is_empty() {
+        request["system"] = json!(system.join("\n\n"));
+    }
+    // OpenAI's temperatures go up to 2, Anthropic's to 1
+    if let Some(temperature) = chat.temperature {
+        request["temperature"] = json!(temperature.clamp(0.0, 1.0));
+    }
+    if let Some(top_p) = chat.top_p {
+        request["top_p"] = json!(top_p);
+    }
+    match &chat.stop {
+        Some(Stop::String(stop)) => request["stop_sequences"] = json!([stop]),
+        Some(Stop::StringArray(stops)) => request["stop_sequences"] = json!(stops),
+        None => {}
+    }
+    request
+}
+
+/// Send `chat` to the Messages API and stream the reply as chat chunks
+pub async fn create_stream(
+    http_client: reqwest::Client,
+    api_base: &str,
+    api_key: &str,
+    chat: &CreateChatCompletionRequest,
+) -> Result<crate::ProviderStream, OpenAIError> {
+    let response = http_client
+        .post(format!("{}/messages", api_base))
+        .header("x-api-key", api_key)
+        .header("anthropic-version", API_VERSION)
+        .json(&messages_request(chat))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        log::warn!("Anthropic refused the request with {}", status);
+        let mut error = api_error(&body);
+        error.code = Some(status.as_u16().to_string());
+        return Err(OpenAIError::ApiError(error));
+    }
+
+    let mut decoder = sse::Decoder::default();
+    let mut state = StreamState::default();
+    let chunks = response.bytes_stream()
+        .map(move |bytes| match bytes {
+            Ok(bytes) => decoder.push(&bytes).iter().filter_map(|event| state.chunk(event)).collect(),
+            Err(e) => vec![Err(OpenAIError::Reqwest(e))],
+        })
+        .flat_map(futures::stream::iter);
+    Ok(Box::pin(chunks))
+}
+
+/// The models in a response from the Models API, remembering their context lengths
+pub fn models(response: &Value) -> Result<Vec<ModelInfo>, String> {
+    let data = response["data"].as_array().ok_or_else(|| {
+        log::error!("Anthropic models response has no data array");
+        "Invalid models response format".to_string()
+    })?;
+    Ok(data.iter()
+        .filter_map(|model| {
+            let id = model["id"].as_str()?;
+            let qualified = providers::qualified_id(Provider::Anthropic, id);
+            crate::context::remember_context_length(&qualified, CONTEXT_LENGTH as usize);
+            Some(ModelInfo {
+                id: qualified,
+                display_name: model["display_name"].as_str().unwrap_or(id).to_string(),
+                organization: "Anthropic".to_string(),
+                context_length: Some(CONTEXT_LENGTH),
+                pricing: None,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::types::{
+        ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
+        ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+        CreateChatCompletionRequestArgs,
+    };
+
+    #[test]
+    fn chat_requests_become_messages_requests() {
+        let messages: Vec<ChatCompletionRequestMessage> = vec![
+            ChatCompletionRequestSystemMessageArgs::default().content("Be brief.").build().unwrap().into(),
+            ChatCompletionRequestUserMessageArgs::default().content("Name a prime.").build().unwrap().into(),
+            ChatCompletionRequestUserMessageArgs::default().content("An odd one.").build().unwrap().into(),
+            ChatCompletionRequestAssistantMessageArgs::default().content("7").build().unwrap().into(),
+        ];
+        let chat = CreateChatCompletionRequestArgs::default()
+            .model("anthropic/claude-sonnet-4-5")
+            .messages(messages)
+            .temperature(1.5)
+            .stop("###")
+            .build()
+            .unwrap();
+        let request = messages_request(&chat);
+        assert_eq!(request["model"], "claude-sonnet-4-5");
+        assert_eq!(request["system"], "Be brief.");
+        assert_eq!(request["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(request["temperature"], 1.0);
+        assert_eq!(request["stop_sequences"], json!(["###"]));
+        assert_eq!(request["messages"], json!([
+            { "role": "user", "content": "Name a prime.\n\nAn odd one." },
+            { "role": "assistant", "content": "7" },
+        ]));
+    }
+
+    #[test]
+    fn events_become_chat_chunks() {
+        let event = |name: &str, data: Value| sse::Event { event: Some(name.to_string()), data: data.to_string() };
+        let mut state = StreamState::default();
+        assert!(state.chunk(&event("message_start", json!({
+            "type": "message_start",
+            "message": { "id": "msg_1", "model": "claude-sonnet-4-5", "usage": { "input_tokens": 12, "output_tokens": 1 } },
+        }))).is_none());
+
+        let text = state.chunk(&event("content_block_delta", json!({
+            "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "Hello" },
+        }))).unwrap().unwrap();
+        assert_eq!(text.id, "msg_1");
+        assert_eq!(text.choices[0].delta.content.as_deref(), Some("Hello"));
+
+        let done = state.chunk(&event("message_delta", json!({
+            "type": "message_delta", "delta": { "stop_reason": "max_tokens" }, "usage": { "output_tokens": 30 },
+        }))).unwrap().unwrap();
+        assert_eq!(done.choices[0].finish_reason, Some(async_openai::types::FinishReason::Length));
+        assert_eq!(done.usage.map(|usage| usage.total_tokens), Some(42));
+
+        assert!(state.chunk(&event("ping", json!({ "type": "ping" }))).is_none());
+        let error = state.chunk(&event("error", json!({
+            "type": "error", "error": { "type": "overloaded_error", "message": "Overloaded" },
+        }))).unwrap();
+        assert!(matches!(error, Err(OpenAIError::ApiError(e)) if e.message == "Overloaded"));
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+
+/// Smallest and largest message font size, in CSS pixels
+pub const FONT_SIZE_RANGE: std::ops::RangeInclusive<u32> = 10..=32;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, TS)]
+#[ts(export)]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Follow the OS setting
+    #[default]
+    System,
+}
+
+/// Spacing between and around messages
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, TS)]
+#[ts(export)]
+pub enum MessageDensity {
+    Compact,
+    #[default]
+    Comfortable,
+    Spacious,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct AppearanceSettings {
+    #[serde(default)]
+    pub theme: Theme,
+    /// CSS hex color, `#rrggbb`
+    #[serde(default = "default_accent_color")]
+    pub accent_color: String,
+    /// None uses the system UI font
+    #[serde(default)]
+    pub font_family: Option<String>,
+    #[serde(default = "default_font_size")]
+    pub font_size: u32,
+    #[serde(default)]
+    pub density: MessageDensity,
+}
+
+fn default_accent_color() -> String {
+    "#007bff".to_string()
+}
+
+fn default_font_size() -> u32 {
+    16
+}
+
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        AppearanceSettings {
+            theme: Theme::System,
+            accent_color: default_accent_color(),
+            font_family: None,
+            font_size: default_font_size(),
+            density: MessageDensity::Comfortable,
+        }
+    }
+}
+
+impl AppearanceSettings {
+    /// Check the values the webview will put into CSS
+    pub fn validate(&self) -> Result<(), String> {
+        let hex = self.accent_color.strip_prefix('#').unwrap_or_default();
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(tr!("appearance-invalid-accent", color = self.accent_color.as_str()));
+        }
+        if !FONT_SIZE_RANGE.contains(&self.font_size) {
+            return Err(tr!("appearance-invalid-font-size", min = *FONT_SIZE_RANGE.start(), max = *FONT_SIZE_RANGE.end()));
+        }
+        if let Some(family) = &self.font_family {
+            // Quotes and semicolons would let a family name break out of the font-family declaration
+            if family.trim().is_empty() || family.chars().any(|c| matches!(c, '"' | '\'' | ';' | '{' | '}' | '\\')) {
+                return Err(tr!("appearance-invalid-font-family", family = family.as_str()));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+//! Azure OpenAI. Azure serves each model from a deployment on the user's own resource, at
+//! `{endpoint}/openai/deployments/{deployment}/...?api-version=...`, and takes the key in an
+//! `api-key` header rather than as a bearer token. Each deployment is set up as a profile and
+//! listed in the model picker as `azure/<profile name>`.
+
+use async_openai::config::AzureConfig;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::providers::{self, Provider};
+use crate::tr;
+
+/// The newest generally available API version when profiles were added
+pub const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+fn default_api_version() -> String {
+    DEFAULT_API_VERSION.to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct AzureProfile {
+    /// What the model picker lists the deployment as
+    pub name: String,
+    /// The resource's endpoint, e.g. `https://my-resource.openai.azure.com`
+    pub endpoint: String,
+    pub deployment: String,
+    #[serde(default = "default_api_version")]
+    pub api_version: String,
+}
+
+/// `profile` trimmed, or a localized error if it's missing a name, deployment or usable endpoint
+pub fn validate(mut profile: AzureProfile) -> Result<AzureProfile, String> {
+    profile.name = profile.name.trim().to_string();
+    profile.endpoint = profile.endpoint.trim().trim_end_matches('/').to_string();
+    profile.deployment = profile.deployment.trim().to_string();
+    profile.api_version = profile.api_version.trim().to_string();
+    if profile.name.is_empty() {
+        return Err(tr!("azure-profile-name-missing"));
+    }
+    if profile.deployment.is_empty() {
+        return Err(tr!("azure-deployment-missing"));
+    }
+    if !profile.endpoint.starts_with("https://") && !profile.endpoint.starts_with("http://") {
+        return Err(tr!("custom-model-url-invalid", url = profile.endpoint.clone()));
+    }
+    if profile.api_version.is_empty() {
+        profile.api_version = default_api_version();
+    }
+    Ok(profile)
+}
+
+/// The profile `model` is listed as
+pub fn profile<'a>(profiles: &'a [AzureProfile], model: &str) -> Option<&'a AzureProfile> {
+    let (provider, name) = providers::resolve(model);
+    profiles.iter().find(|profile| provider == Provider::Azure && profile.name == name)
+}
+
+/// The model picker entries for the profiles
+pub fn models(profiles: &[AzureProfile]) -> Vec<ModelInfo> {
+    profiles.iter()
+        .map(|profile| ModelInfo {
+            id: providers::qualified_id(Provider::Azure, &profile.name),
+            display_name: profile.name.clone(),
+            organization: "Azure OpenAI".to_string(),
+            context_length: None,
+            pricing: None,
+        })
+        .collect()
+}
+
+/// A client configuration reaching `profile`'s deployment
+pub fn config(profile: &AzureProfile, api_key: &str) -> AzureConfig {
+    AzureConfig::new()
+        .with_api_base(&profile.endpoint)
+        .with_deployment_id(&profile.deployment)
+        .with_api_version(&profile.api_version)
+        .with_api_key(api_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::config::Config;
+
+    fn entered(name: &str, endpoint: &str) -> AzureProfile {
+        AzureProfile {
+            name: name.to_string(),
+            endpoint: endpoint.to_string(),
+            deployment: " gpt-4o-prod ".to_string(),
+            api_version: String::new(),
+        }
+    }
+
+    #[test]
+    fn profiles_reach_their_deployment() {
+        let profiles = vec![validate(entered(" Prod GPT-4o ", "https://acme.openai.azure.com/")).unwrap()];
+        assert_eq!(profiles[0].api_version, DEFAULT_API_VERSION);
+        let listed = models(&profiles);
+        assert_eq!(listed[0].id, "azure/Prod GPT-4o");
+        assert_eq!(profile(&profiles, &listed[0].id), Some(&profiles[0]));
+        assert_eq!(profile(&profiles, "Prod GPT-4o"), None);
+
+        let config = config(&profiles[0], "key");
+        assert_eq!(config.url("/chat/completions"), "https://acme.openai.azure.com/openai/deployments/gpt-4o-prod/chat/completions");
+        assert_eq!(config.query(), vec![("api-version", DEFAULT_API_VERSION)]);
+        assert_eq!(config.headers()["api-key"], "key");
+    }
+
+    #[test]
+    fn profiles_need_a_name_deployment_and_endpoint() {
+        assert!(validate(entered(" ", "https://acme.openai.azure.com")).is_err());
+        assert!(validate(entered("prod", "acme.openai.azure.com")).is_err());
+        let mut missing_deployment = entered("prod", "https://acme.openai.azure.com");
+        missing_deployment.deployment = String::new();
+        assert!(validate(missing_deployment).is_err());
+    }
+}
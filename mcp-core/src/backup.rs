@@ -0,0 +1,329 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::history::HistoryStore;
+use crate::storage::{digest_file, ATTACHMENTS_DIR};
+use crate::tr;
+
+const MANIFEST: &str = "manifest.json";
+const FORMAT_VERSION: u32 = 1;
+const HISTORY_ENTRY: &str = "history.db";
+
+/// The history a Replace restore overwrote
+const BEFORE_RESTORE: &str = "history.db.before-restore";
+
+/// Data directories holding conversation attachments
+const ATTACHMENT_DIRS: &[&str] = &["transcripts", "captures", ATTACHMENTS_DIR];
+
+/// File name prefix of scheduled backups; pruning only touches files that start with it
+const SCHEDULED_PREFIX: &str = "mcp-switchboard-backup-";
+
+/// Written first in every archive; restore refuses archives whose files don't match it
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    created_at: String,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct BackupInfo {
+    pub path: String,
+    pub created_at: String,
+    pub file_count: u32,
+    pub bytes: u64,
+}
+
+/// What to do with history that already exists when restoring
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum RestoreMode {
+    /// Add messages and attachments missing locally; local copies win on conflict
+    Merge,
+    /// Discard local history and attachments in favour of the backup
+    Replace,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct RestoreSummary {
+    pub mode: RestoreMode,
+    pub messages_added: u32,
+    pub files_restored: u32,
+    pub files_skipped: u32,
+}
+
+/// Automatic backups into `directory`, keeping the newest `keep`
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct BackupSchedule {
+    pub enabled: bool,
+    pub directory: String,
+    pub interval_hours: u32,
+    pub keep: u32,
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        BackupSchedule {
+            enabled: false,
+            directory: String::new(),
+            interval_hours: 24,
+            keep: 7,
+        }
+    }
+}
+
+/// Write a gzipped tar of the history database and attachments to `dest`
+pub fn backup(data_dir: &Path, dest: &Path) -> Result<BackupInfo> {
+    let staging = staging_dir(data_dir)?;
+    let result = write_archive(data_dir, &staging, dest);
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn write_archive(data_dir: &Path, staging: &Path, dest: &Path) -> Result<BackupInfo> {
+    // Snapshot through SQLite so a write in progress can't leave a torn copy
+    let snapshot = staging.join(HISTORY_ENTRY);
+    HistoryStore::open(data_dir)?.snapshot_to(&snapshot)?;
+
+    let mut files = vec![(HISTORY_ENTRY.to_string(), snapshot)];
+    for dir in ATTACHMENT_DIRS {
+        collect_files(&data_dir.join(dir), dir, &mut files)?;
+    }
+
+    let mut manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files: Vec::new(),
+    };
+    for (path, source) in &files {
+        let (size, sha256) = digest_file(source)?;
+        manifest.files.push(ManifestEntry { path: path.clone(), size, sha256 });
+    }
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = dest.with_extension("partial");
+    {
+        let mut archive = tar::Builder::new(GzEncoder::new(File::create(&partial)?, Compression::default()));
+        let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_json.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append_data(&mut header, MANIFEST, manifest_json.as_slice())?;
+        for (path, source) in &files {
+            archive.append_path_with_name(source, path)?;
+        }
+        archive.into_inner()?.finish()?;
+    }
+    std::fs::rename(&partial, dest)?;
+
+    let bytes = std::fs::metadata(dest)?.len();
+    log::info!("Backed up {} files to {} ({} bytes)", files.len(), dest.display(), bytes);
+    Ok(BackupInfo {
+        path: dest.display().to_string(),
+        created_at: manifest.created_at,
+        file_count: files.len() as u32,
+        bytes,
+    })
+}
+
+/// Verify `src` against its manifest, then restore it into `data_dir`
+pub fn restore(data_dir: &Path, src: &Path, mode: RestoreMode) -> Result<RestoreSummary> {
+    let staging = staging_dir(data_dir)?;
+    let result = unpack_and_restore(data_dir, src, &staging, mode);
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn unpack_and_restore(data_dir: &Path, src: &Path, staging: &Path, mode: RestoreMode) -> Result<RestoreSummary> {
+    // unpack refuses entries that would land outside the staging directory
+    tar::Archive::new(GzDecoder::new(BufReader::new(File::open(src)?)))
+        .unpack(staging)
+        .map_err(|e| anyhow::anyhow!(tr!("backup-unreadable", error = e.to_string())))?;
+
+    let manifest: Manifest = serde_json::from_slice(&std::fs::read(staging.join(MANIFEST))
+        .map_err(|_| anyhow::anyhow!(tr!("backup-no-manifest")))?)?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(anyhow::anyhow!(tr!("backup-too-new", version = manifest.format_version)));
+    }
+    for entry in &manifest.files {
+        if !is_restorable(&entry.path) {
+            return Err(anyhow::anyhow!(tr!("backup-unexpected-path", path = entry.path.as_str())));
+        }
+        let (size, sha256) = digest_file(&staging.join(&entry.path))
+            .map_err(|e| anyhow::anyhow!(tr!("backup-missing-entry", path = entry.path.as_str(), error = e.to_string())))?;
+        if size != entry.size || sha256 != entry.sha256 {
+            return Err(anyhow::anyhow!(tr!("backup-checksum-mismatch", path = entry.path.as_str())));
+        }
+    }
+
+    let mut summary = RestoreSummary { mode, messages_added: 0, files_restored: 0, files_skipped: 0 };
+    match mode {
+        RestoreMode::Replace => {
+            let history = data_dir.join(HISTORY_ENTRY);
+            if history.exists() {
+                // Keep the old database around in case the restore was a mistake. Copied through
+                // SQLite, since recent commits may still be in the write-ahead log
+                let kept = data_dir.join(BEFORE_RESTORE);
+                if kept.exists() {
+                    std::fs::remove_file(&kept)?;
+                }
+                HistoryStore::open(data_dir)?.snapshot_to(&kept)?;
+                std::fs::remove_file(&history)?;
+            }
+            for suffix in ["-wal", "-shm"] {
+                let _ = std::fs::remove_file(data_dir.join(format!("{}{}", HISTORY_ENTRY, suffix)));
+            }
+            std::fs::copy(staging.join(HISTORY_ENTRY), &history)?;
+            for dir in ATTACHMENT_DIRS {
+                let existing = data_dir.join(dir);
+                if existing.exists() {
+                    std::fs::remove_dir_all(&existing)?;
+                }
+            }
+            summary.messages_added = HistoryStore::open(data_dir)?.message_count()?;
+        }
+        RestoreMode::Merge => {
+            summary.messages_added = HistoryStore::open(data_dir)?.merge_from(&staging.join(HISTORY_ENTRY))?;
+        }
+    }
+
+    for entry in manifest.files.iter().filter(|e| e.path != HISTORY_ENTRY) {
+        let target = data_dir.join(&entry.path);
+        if target.exists() {
+            summary.files_skipped += 1;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(staging.join(&entry.path), &target)?;
+        summary.files_restored += 1;
+    }
+
+    log::info!(
+        "Restored {} ({:?}): {} messages, {} files, {} skipped",
+        src.display(), mode, summary.messages_added, summary.files_restored, summary.files_skipped
+    );
+    Ok(summary)
+}
+
+/// Path for the next scheduled backup in `directory`
+pub fn scheduled_path(directory: &Path) -> PathBuf {
+    directory.join(format!("{}{}.tar.gz", SCHEDULED_PREFIX, chrono::Utc::now().format("%Y%m%dT%H%M%SZ")))
+}
+
+/// Whether the newest scheduled backup in `directory` is older than the schedule's interval
+pub fn is_due(schedule: &BackupSchedule) -> Result<bool> {
+    let newest = scheduled_backups(Path::new(&schedule.directory))?.into_iter().next_back();
+    Ok(match newest {
+        Some(path) => {
+            let age = std::fs::metadata(path)?.modified()?.elapsed().unwrap_or_default();
+            age.as_secs() >= u64::from(schedule.interval_hours) * 3600
+        }
+        None => true,
+    })
+}
+
+/// Delete all but the newest `keep` scheduled backups
+pub fn prune(directory: &Path, keep: u32) -> Result<()> {
+    let backups = scheduled_backups(directory)?;
+    let excess = backups.len().saturating_sub(keep.max(1) as usize);
+    for old in &backups[..excess] {
+        log::info!("Removing old backup {}", old.display());
+        std::fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Scheduled backups in `directory`, oldest first (names sort by timestamp)
+fn scheduled_backups(directory: &Path) -> Result<Vec<PathBuf>> {
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name().and_then(|n| n.to_str())
+                .is_some_and(|name| name.starts_with(SCHEDULED_PREFIX) && name.ends_with(".tar.gz"))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Only the history database and files under the attachment directories may be restored
+fn is_restorable(path: &str) -> bool {
+    let path = Path::new(path);
+    let normal = path.components().all(|c| matches!(c, std::path::Component::Normal(_)));
+    normal && (path == Path::new(HISTORY_ENTRY) || ATTACHMENT_DIRS.iter().any(|dir| path.starts_with(dir)))
+}
+
+fn staging_dir(data_dir: &Path) -> Result<PathBuf> {
+    let staging = data_dir.join(format!(".backup-staging-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&staging)?;
+    Ok(staging)
+}
+
+fn collect_files(dir: &Path, archive_dir: &str, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let archive_path = format!("{}/{}", archive_dir, name);
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &archive_path, files)?;
+        } else {
+            files.push((archive_path, entry.path()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HistoryMessage;
+
+    #[test]
+    fn replacing_keeps_writes_still_in_the_wal() {
+        let data_dir = std::env::temp_dir().join(format!("mcp-backup-{}", uuid::Uuid::new_v4()));
+        let archive = data_dir.join("backup.tar.gz");
+        let store = HistoryStore::open(&data_dir).unwrap();
+        store.insert(&HistoryMessage::new("c", "user", "backed up".to_string(), None, None)).unwrap();
+        backup(&data_dir, &archive).unwrap();
+
+        // Committed but not checkpointed, since this connection stays open through the restore
+        store.insert(&HistoryMessage::new("c", "user", "written just before".to_string(), None, None)).unwrap();
+        let summary = restore(&data_dir, &archive, RestoreMode::Replace).unwrap();
+        assert_eq!(summary.messages_added, 1);
+        drop(store);
+
+        let kept = rusqlite::Connection::open(data_dir.join(BEFORE_RESTORE)).unwrap();
+        let contents: Vec<String> = kept.prepare("SELECT content FROM messages ORDER BY content").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(contents, vec!["backed up", "written just before"]);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
@@ -0,0 +1,558 @@
+//! Amazon Bedrock, reached with an AWS access key and SigV4-signed requests rather than a
+//! bearer key. Chat goes through the Converse API, which takes every model family in one shape
+//! and streams its reply as AWS event-stream frames (binary, length-prefixed and checksummed)
+//! carrying `messageStart`, `contentBlockDelta`, `messageStop` and `metadata` events. Requests
+//! are built from the chat request and the events turned back into chat chunks, as Anthropic's
+//! are. Models are listed as `bedrock/<model id>`, including the cross-region inference
+//! profiles (`us.anthropic...`) newer models are only served through.
+
+use std::collections::BTreeMap;
+use async_openai::error::{ApiError, OpenAIError};
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionStreamResponse, Stop};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::environment;
+use crate::providers::{self, Provider};
+use crate::sigv4::{self, Credentials};
+use crate::tr;
+
+/// Where requests go when neither the account nor the environment names a region
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// Sent when the chat request has no token limit
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Longest event-stream frame accepted; Bedrock's are a few hundred bytes
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// The access key Bedrock is reached with; the secret access key is kept with the other
+/// providers' keys, in the encrypted config or the environment
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct BedrockAccount {
+    pub access_key_id: String,
+    /// e.g. `us-east-1`
+    pub region: String,
+}
+
+/// `account` trimmed, or a localized error if the key id is missing or the region isn't one
+pub fn validate(account: BedrockAccount) -> Result<BedrockAccount, String> {
+    let access_key_id = account.access_key_id.trim().to_string();
+    let region = account.region.trim().to_lowercase();
+    if access_key_id.is_empty() {
+        return Err(tr!("bedrock-access-key-missing"));
+    }
+    let parts: Vec<&str> = region.split('-').collect();
+    let valid_region = parts.len() >= 3
+        && parts.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+        && parts.last().is_some_and(|number| number.chars().all(|c| c.is_ascii_digit()));
+    if !valid_region {
+        return Err(tr!("bedrock-region-invalid", region = region));
+    }
+    Ok(BedrockAccount { access_key_id, region })
+}
+
+/// Credentials from the saved account and secret, each falling back to the standard AWS
+/// environment variables; None until there's a key id and a secret
+pub fn credentials(account: Option<BedrockAccount>, secret_access_key: Option<String>) -> Option<Credentials> {
+    let var = |name: &str| environment::var(name).map(|(value, _)| value);
+    let (access_key_id, region) = match account {
+        Some(account) => (account.access_key_id, account.region),
+        None => (
+            var(environment::AWS_ACCESS_KEY_ID_VAR)?,
+            var(environment::AWS_REGION_VAR)
+                .or_else(|| var(environment::AWS_DEFAULT_REGION_VAR))
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+        ),
+    };
+    Some(Credentials {
+        access_key_id,
+        secret_access_key: secret_access_key?,
+        session_token: var(environment::AWS_SESSION_TOKEN_VAR),
+        region,
+    })
+}
+
+/// The runtime API chat is sent to
+pub fn runtime_base(region: &str) -> String {
+    format!("https://bedrock-runtime.{}.amazonaws.com", region)
+}
+
+/// The control-plane API models are listed from
+fn control_base(region: &str) -> String {
+    format!("https://bedrock.{}.amazonaws.com", region)
+}
+
+/// The Converse request equivalent to a chat request. System messages become system blocks;
+/// tool results are passed on as user turns, and consecutive turns of one role are joined,
+/// since the API expects user and assistant to alternate.
+pub fn converse_request(chat: &CreateChatCompletionRequest) -> Value {
+    let mut system = Vec::new();
+    let mut messages: Vec<(&str, String)> = Vec::new();
+    for message in &chat.messages {
+        let message = serde_json::to_value(message).unwrap_or_default();
+        let text = providers::message_text(&message);
+        let role = match message["role"].as_str() {
+            Some("system") | Some("developer") => {
+                system.push(json!({ "text": text }));
+                continue;
+            }
+            Some("assistant") => "assistant",
+            _ => "user",
+        };
+        match messages.last_mut() {
+            Some((last_role, last_text)) if *last_role == role => {
+                last_text.push_str("\n\n");
+                last_text.push_str(&text);
+            }
+            _ => messages.push((role, text)),
+        }
+    }
+
+    #[allow(deprecated)]
+    let max_tokens = chat.max_completion_tokens.or(chat.max_tokens).unwrap_or(DEFAULT_MAX_TOKENS);
+    let mut inference = json!({ "maxTokens": max_tokens });
+    // OpenAI's temperatures go up to 2, Bedrock's to 1
+    if let Some(temperature) = chat.temperature {
+        inference["temperature"] = json!(temperature.clamp(0.0, 1.0));
+    }
+    if let Some(top_p) = chat.top_p {
+        inference["topP"] = json!(top_p);
+    }
+    match &chat.stop {
+        Some(Stop::String(stop)) => inference["stopSequences"] = json!([stop]),
+        Some(Stop::StringArray(stops)) => inference["stopSequences"] = json!(stops),
+        None => {}
+    }
+    let mut request = json!({
+        "messages": messages.iter()
+            .map(|(role, text)| json!({ "role": role, "content": [{ "text": text }] }))
+            .collect::<Vec<_>>(),
+        "inferenceConfig": inference,
+    });
+    if !system.is_empty() {
+        request["system"] = json!(system);
+    }
+    request
+}
+
+/// One event-stream frame: its string headers (`:event-type`, `:message-type`, ...) and payload
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub headers: BTreeMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+/// Splits a byte stream into event-stream frames. Chunks may end anywhere, so bytes are held
+/// until the frame their prelude announced is complete.
+#[derive(Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    /// The frames completed by `bytes`; a corrupt frame is an error, after which nothing more
+    /// can be read
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Result<Frame, String>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+        while self.buffer.len() >= 4 {
+            let total = u32::from_be_bytes([self.buffer[0], self.buffer[1], self.buffer[2], self.buffer[3]]) as usize;
+            if !(16..=MAX_FRAME_BYTES).contains(&total) {
+                self.buffer.clear();
+                frames.push(Err(format!("Bedrock sent an event of impossible length {}", total)));
+                break;
+            }
+            if self.buffer.len() < total {
+                break;
+            }
+            let frame: Vec<u8> = self.buffer.drain(..total).collect();
+            frames.push(decode_frame(&frame));
+        }
+        frames
+    }
+}
+
+/// CRC-32 (IEEE), which each frame's prelude and whole are checked with
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// A whole frame: a 12-byte prelude (total length, headers length, prelude CRC), the headers,
+/// the payload and a CRC of everything before it
+fn decode_frame(frame: &[u8]) -> Result<Frame, String> {
+    let end = frame.len() - 4;
+    if crc32(&frame[..8]) != be_u32(&frame[8..12]) || crc32(&frame[..end]) != be_u32(&frame[end..]) {
+        return Err("Bedrock sent a corrupt event".to_string());
+    }
+    let headers_end = 12 + be_u32(&frame[4..8]) as usize;
+    if headers_end > end {
+        return Err("Bedrock sent a corrupt event".to_string());
+    }
+    Ok(Frame { headers: decode_headers(&frame[12..headers_end])?, payload: frame[headers_end..end].to_vec() })
+}
+
+/// The string-valued headers; headers of other types are skipped
+fn decode_headers(mut bytes: &[u8]) -> Result<BTreeMap<String, String>, String> {
+    let truncated = || "Bedrock sent an event with truncated headers".to_string();
+    let mut headers = BTreeMap::new();
+    while let Some((&name_len, rest)) = bytes.split_first() {
+        let name = rest.get(..name_len as usize).ok_or_else(truncated)?;
+        let (&value_type, rest) = rest[name_len as usize..].split_first().ok_or_else(truncated)?;
+        let value_len = match value_type {
+            // true and false carry no value
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            // Byte arrays and strings are prefixed with their length
+            6 | 7 => {
+                let len = rest.get(..2).ok_or_else(truncated)?;
+                2 + u16::from_be_bytes([len[0], len[1]]) as usize
+            }
+            other => return Err(format!("Bedrock sent an event header of unknown type {}", other)),
+        };
+        let value = rest.get(..value_len).ok_or_else(truncated)?;
+        if value_type == 7 {
+            headers.insert(String::from_utf8_lossy(name).to_string(), String::from_utf8_lossy(&value[2..]).to_string());
+        }
+        bytes = &rest[value_len..];
+    }
+    Ok(headers)
+}
+
+/// The chat finish reason closest to a Converse stop reason
+fn finish_reason(stop_reason: &str) -> &'static str {
+    match stop_reason {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "content_filtered" | "guardrail_intervened" => "content_filter",
+        _ => "stop",
+    }
+}
+
+/// Turns Converse stream events into chat chunks for `model`
+pub struct StreamState {
+    id: String,
+    model: String,
+}
+
+impl StreamState {
+    pub fn new(model: &str) -> Self {
+        StreamState { id: format!("bedrock-{}", uuid::Uuid::new_v4()), model: model.to_string() }
+    }
+
+    /// The chat chunk `frame` amounts to, if any; the start of the message and of each content
+    /// block have none
+    pub fn chunk(&self, frame: &Frame) -> Option<Result<CreateChatCompletionStreamResponse, OpenAIError>> {
+        let header = |name: &str| frame.headers.get(name).map(String::as_str);
+        let data: Value = serde_json::from_slice(&frame.payload).unwrap_or_default();
+        match header(":message-type") {
+            Some("exception") => {
+                return Some(Err(OpenAIError::ApiError(ApiError {
+                    message: data["message"].as_str().or(data["Message"].as_str()).unwrap_or("Bedrock error").to_string(),
+                    r#type: header(":exception-type").map(str::to_string),
+                    param: None,
+                    code: None,
+                })));
+            }
+            Some("error") => {
+                return Some(Err(OpenAIError::ApiError(ApiError {
+                    message: header(":error-message").unwrap_or("Bedrock error").to_string(),
+                    r#type: header(":error-code").map(str::to_string),
+                    param: None,
+                    code: None,
+                })));
+            }
+            _ => {}
+        }
+        match header(":event-type") {
+            Some("contentBlockDelta") => data["delta"]["text"].as_str()
+                .map(|text| self.chat_chunk(json!({ "content": text }), None, None)),
+            Some("messageStop") => {
+                let finish_reason = data["stopReason"].as_str().map(finish_reason);
+                Some(self.chat_chunk(json!({}), finish_reason, None))
+            }
+            Some("metadata") => {
+                let usage = &data["usage"];
+                let prompt_tokens = usage["inputTokens"].as_u64().unwrap_or(0);
+                let completion_tokens = usage["outputTokens"].as_u64().unwrap_or(0);
+                Some(self.chat_chunk(json!({}), None, Some(json!({
+                    "prompt_tokens": prompt_tokens,
+                    "completion_tokens": completion_tokens,
+                    "total_tokens": usage["totalTokens"].as_u64().unwrap_or(prompt_tokens + completion_tokens),
+                }))))
+            }
+            _ => None,
+        }
+    }
+
+    fn chat_chunk(&self, delta: Value, finish_reason: Option<&str>, usage: Option<Value>) -> Result<CreateChatCompletionStreamResponse, OpenAIError> {
+        serde_json::from_value(json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": chrono::Utc::now().timestamp(),
+            "model": self.model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+            "usage": usage,
+        }))
+        .map_err(OpenAIError::JSONDeserialize)
+    }
+}
+
+/// The error in a refused request's `{"message"}` body, typed by its `x-amzn-ErrorType` header
+async fn refusal(response: reqwest::Response) -> OpenAIError {
+    let status = response.status();
+    let error_type = response.headers().get("x-amzn-ErrorType")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(':').next().unwrap_or(value).to_string());
+    let body: Value = response.json().await.unwrap_or_default();
+    log::warn!("Bedrock refused the request with {}", status);
+    OpenAIError::ApiError(ApiError {
+        message: body["message"].as_str().or(body["Message"].as_str()).unwrap_or("Bedrock API error").to_string(),
+        r#type: error_type,
+        param: None,
+        code: Some(status.as_u16().to_string()),
+    })
+}
+
+/// A signed request for `service`, with `body` if it's a POST
+fn signed(http_client: &reqwest::Client, method: reqwest::Method, url: reqwest::Url, body: Vec<u8>, credentials: &Credentials, service: &str) -> reqwest::RequestBuilder {
+    let headers = sigv4::sign(method.as_str(), &url, &body, credentials, service, chrono::Utc::now());
+    let mut request = http_client.request(method.clone(), url);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    if method == reqwest::Method::POST {
+        request = request.header("content-type", "application/json").body(body);
+    }
+    request
+}
+
+/// A cheap signed request showing whether Bedrock answers and accepts the credentials: the
+/// foundation models of one model provider
+pub fn health_check(http_client: &reqwest::Client, credentials: &Credentials) -> Result<reqwest::RequestBuilder, String> {
+    let url = format!("{}/foundation-models?byProvider=amazon&byOutputModality=TEXT", control_base(&credentials.region));
+    let url = reqwest::Url::parse(&url).map_err(|e| e.to_string())?;
+    Ok(signed(http_client, reqwest::Method::GET, url, Vec::new(), credentials, "bedrock"))
+}
+
+/// Send `chat` to the Converse API and stream the reply as chat chunks
+pub async fn create_stream(
+    http_client: reqwest::Client,
+    credentials: &Credentials,
+    chat: &CreateChatCompletionRequest,
+) -> Result<crate::ProviderStream, OpenAIError> {
+    let model = providers::resolve(&chat.model).1;
+    let url = format!("{}/model/{}/converse-stream", runtime_base(&credentials.region), sigv4::uri_encode(model));
+    let url = reqwest::Url::parse(&url).map_err(|e| OpenAIError::InvalidArgument(e.to_string()))?;
+    let body = serde_json::to_vec(&converse_request(chat)).map_err(OpenAIError::JSONDeserialize)?;
+    let response = signed(&http_client, reqwest::Method::POST, url, body, credentials, "bedrock")
+        .header("accept", "application/vnd.amazon.eventstream")
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(refusal(response).await);
+    }
+
+    let mut decoder = Decoder::default();
+    let state = StreamState::new(model);
+    let chunks = response.bytes_stream()
+        .map(move |bytes| match bytes {
+            Ok(bytes) => decoder.push(&bytes).into_iter()
+                .filter_map(|frame| match frame {
+                    Ok(frame) => state.chunk(&frame),
+                    Err(e) => Some(Err(OpenAIError::StreamError(e))),
+                })
+                .collect(),
+            Err(e) => vec![Err(OpenAIError::Reqwest(e))],
+        })
+        .flat_map(futures::stream::iter);
+    Ok(Box::pin(chunks))
+}
+
+/// The text models that stream, and the inference profiles in the account's region
+pub async fn list_models(http_client: &reqwest::Client, credentials: &Credentials) -> Result<Vec<ModelInfo>, String> {
+    let get = |path: &str| {
+        let url = reqwest::Url::parse(&format!("{}{}", control_base(&credentials.region), path));
+        async move {
+            let url = url.map_err(|e| e.to_string())?;
+            let response = signed(http_client, reqwest::Method::GET, url, Vec::new(), credentials, "bedrock")
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(refusal(response).await.to_string());
+            }
+            response.json::<Value>().await.map_err(|e| e.to_string())
+        }
+    };
+    let foundation = get("/foundation-models?byOutputModality=TEXT").await?;
+    // Older accounts and regions without profiles still list their foundation models
+    let profiles = get("/inference-profiles?typeEquals=SYSTEM_DEFINED").await.unwrap_or_else(|e| {
+        log::warn!("Failed to list Bedrock inference profiles: {}", e);
+        Value::Null
+    });
+    models(&foundation, &profiles)
+}
+
+/// The models in responses from `ListFoundationModels` and `ListInferenceProfiles`. Only
+/// models that stream and can be called on demand are listed; the rest need provisioned
+/// throughput or a profile, which is listed instead.
+pub fn models(foundation: &Value, profiles: &Value) -> Result<Vec<ModelInfo>, String> {
+    let summaries = foundation["modelSummaries"].as_array().ok_or_else(|| {
+        log::error!("Bedrock models response has no modelSummaries array");
+        "Invalid models response format".to_string()
+    })?;
+    let has = |values: &Value, wanted: &str| values.as_array().is_some_and(|values| values.iter().any(|value| value == wanted));
+    let mut result: Vec<ModelInfo> = summaries.iter()
+        .filter(|model| model["responseStreamingSupported"] == true && has(&model["inferenceTypesSupported"], "ON_DEMAND"))
+        .filter_map(|model| {
+            let id = model["modelId"].as_str()?;
+            Some(ModelInfo {
+                id: providers::qualified_id(Provider::Bedrock, id),
+                display_name: model["modelName"].as_str().unwrap_or(id).to_string(),
+                organization: model["providerName"].as_str().unwrap_or("Amazon Bedrock").to_string(),
+                context_length: None,
+                pricing: None,
+            })
+        })
+        .collect();
+    for profile in profiles["inferenceProfileSummaries"].as_array().into_iter().flatten() {
+        let Some(id) = profile["inferenceProfileId"].as_str() else { continue };
+        if profile["status"].as_str().is_some_and(|status| status != "ACTIVE") {
+            continue;
+        }
+        result.push(ModelInfo {
+            id: providers::qualified_id(Provider::Bedrock, id),
+            display_name: profile["inferenceProfileName"].as_str().unwrap_or(id).to_string(),
+            organization: "Amazon Bedrock".to_string(),
+            context_length: None,
+            pricing: None,
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::types::{
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+        CreateChatCompletionRequestArgs,
+    };
+
+    /// A frame as Bedrock encodes it, with string headers
+    fn frame(headers: &[(&str, &str)], payload: &Value) -> Vec<u8> {
+        let mut encoded_headers = Vec::new();
+        for (name, value) in headers {
+            encoded_headers.push(name.len() as u8);
+            encoded_headers.extend_from_slice(name.as_bytes());
+            encoded_headers.push(7);
+            encoded_headers.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            encoded_headers.extend_from_slice(value.as_bytes());
+        }
+        let payload = payload.to_string().into_bytes();
+        let total = 12 + encoded_headers.len() + payload.len() + 4;
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(total as u32).to_be_bytes());
+        frame.extend_from_slice(&(encoded_headers.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&crc32(&frame).to_be_bytes());
+        frame.extend_from_slice(&encoded_headers);
+        frame.extend_from_slice(&payload);
+        frame.extend_from_slice(&crc32(&frame).to_be_bytes());
+        frame
+    }
+
+    fn event(event_type: &str, payload: Value) -> Vec<u8> {
+        frame(&[(":event-type", event_type), (":content-type", "application/json"), (":message-type", "event")], &payload)
+    }
+
+    #[test]
+    fn chat_requests_become_converse_requests() {
+        let messages: Vec<ChatCompletionRequestMessage> = vec![
+            ChatCompletionRequestSystemMessageArgs::default().content("Be brief.").build().unwrap().into(),
+            ChatCompletionRequestUserMessageArgs::default().content("Name a prime.").build().unwrap().into(),
+        ];
+        let chat = CreateChatCompletionRequestArgs::default()
+            .model("bedrock/anthropic.claude-3-5-haiku-20241022-v1:0")
+            .messages(messages)
+            .temperature(1.5)
+            .stop("###")
+            .build()
+            .unwrap();
+        assert_eq!(converse_request(&chat), json!({
+            "messages": [{ "role": "user", "content": [{ "text": "Name a prime." }] }],
+            "system": [{ "text": "Be brief." }],
+            "inferenceConfig": { "maxTokens": DEFAULT_MAX_TOKENS, "temperature": 1.0, "stopSequences": ["###"] },
+        }));
+    }
+
+    #[test]
+    fn event_stream_frames_become_chat_chunks() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        let mut bytes = event("messageStart", json!({ "role": "assistant" }));
+        bytes.extend(event("contentBlockDelta", json!({ "contentBlockIndex": 0, "delta": { "text": "Hello" } })));
+        bytes.extend(event("messageStop", json!({ "stopReason": "max_tokens" })));
+        bytes.extend(event("metadata", json!({ "usage": { "inputTokens": 12, "outputTokens": 30, "totalTokens": 42 } })));
+
+        // Split mid-frame, as the network may
+        let mut decoder = Decoder::default();
+        let mut frames = decoder.push(&bytes[..20]);
+        assert!(frames.is_empty());
+        frames.extend(decoder.push(&bytes[20..]));
+        let state = StreamState::new("anthropic.claude-3-5-haiku-20241022-v1:0");
+        let chunks: Vec<_> = frames.into_iter().filter_map(|frame| state.chunk(&frame.unwrap())).map(Result::unwrap).collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].choices[0].delta.content.as_deref(), Some("Hello"));
+        assert_eq!(chunks[1].choices[0].finish_reason, Some(async_openai::types::FinishReason::Length));
+        assert_eq!(chunks[2].usage.as_ref().map(|usage| (usage.prompt_tokens, usage.total_tokens)), Some((12, 42)));
+
+        let throttled = frame(&[(":exception-type", "throttlingException"), (":message-type", "exception")], &json!({ "message": "Slow down" }));
+        let error = state.chunk(&Decoder::default().push(&throttled).remove(0).unwrap()).unwrap();
+        assert!(matches!(error, Err(OpenAIError::ApiError(e)) if e.message == "Slow down"));
+
+        let mut corrupt = event("messageStop", json!({}));
+        let last = corrupt.len() - 5;
+        corrupt[last] ^= 1;
+        assert!(Decoder::default().push(&corrupt)[0].is_err());
+    }
+
+    #[test]
+    fn accounts_need_a_key_id_and_region() {
+        let account = |key: &str, region: &str| BedrockAccount { access_key_id: key.to_string(), region: region.to_string() };
+        assert_eq!(validate(account(" AKIAEXAMPLE ", " EU-West-1 ")), Ok(account("AKIAEXAMPLE", "eu-west-1")));
+        assert!(validate(account("AKIAEXAMPLE", "us-gov-west-1")).is_ok());
+        assert!(validate(account(" ", "us-east-1")).is_err());
+        assert!(validate(account("AKIAEXAMPLE", "moon")).is_err());
+        assert!(validate(account("AKIAEXAMPLE", "us-east")).is_err());
+
+        let listed = models(
+            &json!({ "modelSummaries": [
+                { "modelId": "amazon.nova-pro-v1:0", "modelName": "Nova Pro", "providerName": "Amazon", "responseStreamingSupported": true, "inferenceTypesSupported": ["ON_DEMAND"] },
+                { "modelId": "anthropic.claude-sonnet-4-20250514-v1:0", "responseStreamingSupported": true, "inferenceTypesSupported": ["INFERENCE_PROFILE"] },
+            ]}),
+            &json!({ "inferenceProfileSummaries": [
+                { "inferenceProfileId": "us.anthropic.claude-sonnet-4-20250514-v1:0", "inferenceProfileName": "US Claude Sonnet 4", "status": "ACTIVE" },
+            ]}),
+        ).unwrap();
+        let ids: Vec<&str> = listed.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(ids, ["bedrock/amazon.nova-pro-v1:0", "bedrock/us.anthropic.claude-sonnet-4-20250514-v1:0"]);
+    }
+}
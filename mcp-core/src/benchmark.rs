@@ -0,0 +1,149 @@
+//! Benchmarks of the configured providers and models on one standard prompt. Each model streams
+//! its answer in turn, so time to first token and generation speed reflect this machine and
+//! network rather than a provider's published figures. Reports are kept to compare over time.
+
+use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::openai_api::CatalogueEntry;
+use crate::tr;
+
+/// Sent when no prompt is given; long enough an answer to measure a steady generation rate
+pub const STANDARD_PROMPT: &str = "Explain how a hash map works, including how collisions are handled \
+    and what happens when it grows. Answer in about 300 words.";
+
+/// Cap on each answer, so a verbose model can't run up the cost of a benchmark
+pub const MAX_TOKENS: u32 = 512;
+
+/// How one model did on the benchmark prompt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct BenchmarkResult {
+    pub provider: String,
+    pub model: String,
+    /// Time to first token: from sending the request to the first non-empty chunk
+    pub ttft_ms: Option<u32>,
+    pub total_ms: Option<u32>,
+    /// Output tokens over the time after the first token
+    pub tokens_per_second: Option<f32>,
+    pub output_tokens: u32,
+    pub output_chars: u32,
+    pub estimated_usd: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct BenchmarkReport {
+    pub id: String,
+    pub prompt: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// In the order the models were given
+    pub results: Vec<BenchmarkResult>,
+}
+
+/// A model to benchmark: found in the catalogue, or the error to report for it
+pub enum Target {
+    Found(CatalogueEntry),
+    Unknown(String),
+}
+
+/// The prompt to send, trimmed, or STANDARD_PROMPT when none is given
+pub fn prompt(prompt: Option<String>) -> String {
+    prompt
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty())
+        .unwrap_or_else(|| STANDARD_PROMPT.to_string())
+}
+
+/// `models`, by namespaced or plain id, looked up in `catalogue`; blanks and repeats are dropped
+pub fn targets(models: &[String], catalogue: &[CatalogueEntry]) -> Result<Vec<Target>, String> {
+    let mut seen = BTreeSet::new();
+    let targets: Vec<Target> = models.iter()
+        .map(|model| model.trim())
+        .filter(|model| !model.is_empty() && seen.insert(model.to_string()))
+        .map(|model| {
+            catalogue.iter()
+                .find(|entry| entry.id() == model)
+                .or_else(|| catalogue.iter().find(|entry| entry.model.id == model))
+                .map(|entry| Target::Found(entry.clone()))
+                .unwrap_or_else(|| Target::Unknown(model.to_string()))
+        })
+        .collect();
+    if targets.is_empty() {
+        return Err(tr!("benchmark-no-models"));
+    }
+    Ok(targets)
+}
+
+/// Generation rate once the first token has arrived; None when there's too little to time
+pub fn tokens_per_second(output_tokens: u32, ttft_ms: u32, total_ms: u32) -> Option<f32> {
+    let generating_ms = total_ms.saturating_sub(ttft_ms);
+    (output_tokens > 1 && generating_ms > 0).then(|| output_tokens as f32 * 1000.0 / generating_ms as f32)
+}
+
+/// Results fastest to first token first, failures last
+pub fn ranked(results: &[BenchmarkResult]) -> Vec<&BenchmarkResult> {
+    let mut ranked: Vec<&BenchmarkResult> = results.iter().collect();
+    ranked.sort_by_key(|result| (result.error.is_some(), result.ttft_ms.unwrap_or(u32::MAX)));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ModelInfo;
+
+    fn entry(provider: &str, id: &str) -> CatalogueEntry {
+        CatalogueEntry {
+            provider: provider.to_string(),
+            model: ModelInfo { id: id.to_string(), display_name: id.to_string(), organization: String::new(), context_length: None, pricing: None },
+        }
+    }
+
+    fn result(model: &str, ttft_ms: Option<u32>, error: Option<&str>) -> BenchmarkResult {
+        BenchmarkResult {
+            provider: "together".to_string(),
+            model: model.to_string(),
+            ttft_ms,
+            total_ms: None,
+            tokens_per_second: None,
+            output_tokens: 0,
+            output_chars: 0,
+            estimated_usd: None,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn models_are_found_by_either_id() {
+        let catalogue = vec![entry("together", "meta/llama"), entry("together", "qwen/qwen")];
+        let models = ["together/meta/llama", " qwen/qwen ", "qwen/qwen", "", "missing"].map(str::to_string);
+        let found: Vec<String> = targets(&models, &catalogue).unwrap().into_iter()
+            .map(|target| match target {
+                Target::Found(entry) => entry.id(),
+                Target::Unknown(model) => format!("unknown {}", model),
+            })
+            .collect();
+        assert_eq!(found, vec!["together/meta/llama", "together/qwen/qwen", "unknown missing"]);
+        assert!(targets(&[" ".to_string()], &catalogue).is_err());
+        assert_eq!(prompt(Some("  ".to_string())), STANDARD_PROMPT);
+        assert_eq!(prompt(Some(" Hi ".to_string())), "Hi");
+    }
+
+    #[test]
+    fn rates_exclude_the_wait_and_failures_rank_last() {
+        assert_eq!(tokens_per_second(101, 500, 1500), Some(101.0));
+        assert_eq!(tokens_per_second(1, 500, 1500), None);
+        assert_eq!(tokens_per_second(50, 500, 500), None);
+
+        let results = vec![
+            result("slow", Some(900), None),
+            result("broken", None, Some("401")),
+            result("fast", Some(200), None),
+        ];
+        let order: Vec<&str> = ranked(&results).iter().map(|result| result.model.as_str()).collect();
+        assert_eq!(order, vec!["fast", "slow", "broken"]);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Which caches `invalidate` empties
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum CacheScope {
+    All,
+    /// The provider's model list
+    Models,
+    /// Context lengths, pricing, model types and capabilities learned from the model list
+    ProviderMetadata,
+    /// Templates indexed from synced repositories
+    Templates,
+    /// Results of tool calls kept for repeated identical calls
+    ToolResults,
+}
+
+struct Entry<V> {
+    value: V,
+    stored: Instant,
+    used: u64,
+}
+
+struct Entries<V> {
+    entries: BTreeMap<String, Entry<V>>,
+    /// Bumped on every hit so the least recently used entry can be found
+    clock: u64,
+}
+
+/// A small in-memory cache. Entries expire after `ttl`, and past `capacity` the least recently
+/// used entry makes room for a new one.
+pub struct Cache<V> {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<Entries<V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<V: Clone> Cache<V> {
+    pub const fn new(ttl: Duration, capacity: usize) -> Self {
+        Cache {
+            ttl,
+            capacity,
+            entries: Mutex::new(Entries { entries: BTreeMap::new(), clock: 0 }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<V> {
+        self.get_fresh(key, self.ttl)
+    }
+
+    /// Like `get`, but only if the entry was stored within `max_age`, for callers whose entries
+    /// go stale sooner than the cache's own ttl
+    pub fn get_fresh(&self, key: &str, max_age: Duration) -> Option<V> {
+        let value = self.lookup(key, max_age.min(self.ttl));
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    fn lookup(&self, key: &str, max_age: Duration) -> Option<V> {
+        let mut entries = self.entries.lock().ok()?;
+        entries.clock += 1;
+        let clock = entries.clock;
+        let entry = entries.entries.get_mut(key)?;
+        if entry.stored.elapsed() > self.ttl {
+            entries.entries.remove(key);
+            return None;
+        }
+        if entry.stored.elapsed() > max_age {
+            return None;
+        }
+        entry.used = clock;
+        Some(entry.value.clone())
+    }
+
+    /// Lookups that found a fresh entry and lookups that didn't, since startup
+    pub fn hit_counts(&self) -> (u64, u64) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+
+    pub fn insert(&self, key: &str, value: V) {
+        let Ok(mut entries) = self.entries.lock() else { return };
+        entries.clock += 1;
+        let used = entries.clock;
+        if !entries.entries.contains_key(key) && entries.entries.len() >= self.capacity {
+            let ttl = self.ttl;
+            entries.entries.retain(|_, entry| entry.stored.elapsed() <= ttl);
+            if entries.entries.len() >= self.capacity {
+                let oldest = entries.entries.iter().min_by_key(|(_, entry)| entry.used).map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.entries.remove(&oldest);
+                }
+            }
+        }
+        entries.entries.insert(key.to_string(), Entry { value, stored: Instant::now(), used });
+    }
+
+    /// Entry count and approximate size, with `size_of` estimating each value
+    pub fn usage(&self, size_of: impl Fn(&V) -> u64) -> (u32, u64) {
+        let Ok(entries) = self.entries.lock() else { return (0, 0) };
+        let bytes = entries.entries.iter().map(|(key, entry)| key.len() as u64 + size_of(&entry.value)).sum();
+        (entries.entries.len() as u32, bytes)
+    }
+
+    /// Drop expired entries, returning how many there were. Lookups drop them anyway; this frees
+    /// the ones nobody asks for again.
+    pub fn prune_expired(&self) -> usize {
+        let Ok(mut entries) = self.entries.lock() else { return 0 };
+        let before = entries.entries.len();
+        let ttl = self.ttl;
+        entries.entries.retain(|_, entry| entry.stored.elapsed() <= ttl);
+        before - entries.entries.len()
+    }
+
+    /// Empty the cache, returning how many entries it held
+    pub fn clear(&self) -> usize {
+        let Ok(mut entries) = self.entries.lock() else { return 0 };
+        let count = entries.entries.len();
+        entries.entries.clear();
+        count
+    }
+}
+
+/// Empty the caches in `scope`, returning how many entries were dropped
+pub fn invalidate(scope: CacheScope) -> u32 {
+    let mut dropped = 0;
+    if matches!(scope, CacheScope::All | CacheScope::Models | CacheScope::ProviderMetadata) {
+        // Provider metadata is learned from the model list, so it can only be refreshed by refetching that too
+        dropped += crate::MODELS.clear();
+    }
+    if matches!(scope, CacheScope::All | CacheScope::ProviderMetadata) {
+        dropped += crate::context::forget_context_lengths() + crate::cost::forget_pricing() + crate::completions::forget_model_types()
+            + crate::capabilities::forget_listed();
+    }
+    if matches!(scope, CacheScope::All | CacheScope::Templates) {
+        dropped += crate::templates::INDEXED.clear();
+    }
+    if matches!(scope, CacheScope::All | CacheScope::ToolResults) {
+        dropped += crate::tool_cache::RESULTS.clear();
+    }
+    log::info!("Invalidated {:?} caches, dropping {} entries", scope, dropped);
+    dropped as u32
+}
+
+
+/// Drop expired entries from every cache, returning how many were dropped
+pub fn prune_expired() -> u32 {
+    let pruned = crate::MODELS.prune_expired()
+        + crate::context::prune_context_lengths()
+        + crate::cost::prune_pricing()
+        + crate::completions::prune_model_types()
+        + crate::capabilities::prune_listed()
+        + crate::templates::INDEXED.prune_expired()
+        + crate::tool_cache::RESULTS.prune_expired();
+    log::info!("Pruned {} expired cache entries", pruned);
+    pruned as u32
+}
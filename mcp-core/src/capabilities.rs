@@ -0,0 +1,182 @@
+//! What each model can do beyond plain chat: look at images, call tools, answer in JSON mode,
+//! embed text. Where the provider's model list says (OpenRouter's modalities and supported
+//! parameters, Together's model types), that's what's reported; otherwise it's judged from
+//! the model id, which is a best guess for the families each provider is known to serve.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::cache::Cache;
+use crate::providers::{self, Provider};
+
+/// Ids of models that take images, lowercased
+const VISION_PATTERNS: [&str; 9] = ["vision", "-vl", "vl-", "llava", "llama-4", "llama4", "pixtral", "gemma3", "gemma-3"];
+
+/// Open-weight families trained for tool calls and constrained JSON output, lowercased
+const OPEN_TOOL_FAMILIES: [&str; 9] = ["llama-3.1", "llama-3.2", "llama-3.3", "llama3.1", "llama3.2", "llama3.3", "llama-4", "qwen", "mistral"];
+
+/// Model types in Together's list whose models only embed
+const EMBEDDING_TYPES: [&str; 1] = ["embedding"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ModelCapabilities {
+    pub model: String,
+    pub streaming: bool,
+    pub vision: bool,
+    pub tools: bool,
+    pub json_mode: bool,
+    /// An embedding model rather than a chat model
+    pub embeddings: bool,
+    /// Read from the provider's model list rather than judged from the id
+    pub listed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ProviderCapabilities {
+    pub provider: Provider,
+    /// Whether the provider has an embeddings API
+    pub embeddings: bool,
+    pub models: Vec<ModelCapabilities>,
+}
+
+/// Capabilities the model lists state, refreshed whenever they're fetched
+static LISTED: Cache<ModelCapabilities> = Cache::new(Duration::from_secs(24 * 3600), 4096);
+
+/// Record what OpenRouter's model list says `model` takes and accepts
+pub fn remember_openrouter(model: &str, listing: &serde_json::Value) {
+    let has = |values: &serde_json::Value, wanted: &str| values.as_array()
+        .is_some_and(|values| values.iter().any(|value| value.as_str() == Some(wanted)));
+    let parameters = &listing["supported_parameters"];
+    LISTED.insert(model, ModelCapabilities {
+        model: model.to_string(),
+        streaming: true,
+        vision: has(&listing["architecture"]["input_modalities"], "image"),
+        tools: has(parameters, "tools"),
+        json_mode: has(parameters, "response_format") || has(parameters, "structured_outputs"),
+        embeddings: false,
+        listed: true,
+    });
+}
+
+/// Record a Together model's type; embedding models are marked as such, chat models keep the
+/// guesses from their id
+pub fn remember_model_type(model: &str, model_type: &str) {
+    if EMBEDDING_TYPES.contains(&model_type) {
+        LISTED.insert(model, ModelCapabilities {
+            model: model.to_string(),
+            streaming: false,
+            vision: false,
+            tools: false,
+            json_mode: false,
+            embeddings: true,
+            listed: true,
+        });
+    }
+}
+
+pub fn forget_listed() -> usize {
+    LISTED.clear()
+}
+
+pub fn prune_listed() -> usize {
+    LISTED.prune_expired()
+}
+
+/// What `model` can do, as listed or else guessed from its id
+pub fn for_model(model: &str) -> ModelCapabilities {
+    LISTED.get(model).unwrap_or_else(|| guess(model))
+}
+
+/// The capabilities of `models`, grouped by the provider serving them in the order they're listed
+pub fn by_provider(models: &[ModelInfo]) -> Vec<ProviderCapabilities> {
+    let mut grouped: Vec<ProviderCapabilities> = Vec::new();
+    for model in models {
+        let provider = providers::resolve(&model.id).0;
+        let capabilities = for_model(&model.id);
+        match grouped.iter_mut().find(|group| group.provider == provider) {
+            Some(group) => group.models.push(capabilities),
+            None => grouped.push(ProviderCapabilities { provider, embeddings: provider.serves_embeddings(), models: vec![capabilities] }),
+        }
+    }
+    grouped
+}
+
+/// What `model` can likely do, judged from the provider serving it and its id
+pub fn guess(model: &str) -> ModelCapabilities {
+    let (provider, upstream) = providers::resolve(model);
+    let id = upstream.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|pattern| id.contains(pattern));
+    let embeddings = id.contains("embed") || id.starts_with("baai/bge") || id.contains("/bge-");
+    let (vision, tools, json_mode) = match provider {
+        Provider::OpenAi | Provider::Azure => {
+            let starts = |prefixes: &[&str]| prefixes.iter().any(|prefix| id.starts_with(prefix));
+            // The mini o1 has none of them, and the ChatGPT alias takes no tools
+            (!starts(&["o1-mini", "o3-mini", "gpt-3.5", "gpt-4-0"]), !starts(&["o1-mini", "chatgpt-"]), !starts(&["o1-mini"]))
+        }
+        // Every Claude from 3 on takes images and tools; there's no JSON mode, only tools
+        Provider::Anthropic => (!id.starts_with("claude-2"), true, false),
+        Provider::Groq => (matches(&VISION_PATTERNS), true, true),
+        // Converse takes tools for the Claude, Nova, Llama and Mistral families, and has no JSON mode
+        Provider::Bedrock => {
+            let vision = (id.contains("claude") && !id.contains("claude-v2") && !id.contains("claude-instant"))
+                || id.contains("nova-pro") || id.contains("nova-lite") || matches(&VISION_PATTERNS);
+            (vision, true, false)
+        }
+        // Ollama constrains any model to JSON
+        Provider::Ollama => (matches(&VISION_PATTERNS), matches(&OPEN_TOOL_FAMILIES), true),
+        Provider::Together | Provider::OpenRouter | Provider::Endpoint => {
+            let family = matches(&OPEN_TOOL_FAMILIES) || id.contains("deepseek-v3") || id.contains("gpt-oss");
+            (matches(&VISION_PATTERNS), family, family)
+        }
+    };
+    ModelCapabilities {
+        model: model.to_string(),
+        streaming: !embeddings,
+        vision: vision && !embeddings,
+        tools: tools && !embeddings,
+        json_mode: json_mode && !embeddings,
+        embeddings,
+        listed: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_follow_the_model_family() {
+        let gpt4o = guess("openai/gpt-4o-mini");
+        assert!(gpt4o.vision && gpt4o.tools && gpt4o.json_mode && gpt4o.streaming);
+        let o1_mini = guess("openai/o1-mini");
+        assert!(!o1_mini.vision && !o1_mini.tools && !o1_mini.json_mode);
+        assert!(!guess("openai/gpt-3.5-turbo").vision);
+
+        let claude = guess("anthropic/claude-sonnet-4-5");
+        assert!(claude.vision && claude.tools && !claude.json_mode);
+
+        let scout = guess("groq/meta-llama/llama-4-scout-17b-16e-instruct");
+        assert!(scout.vision && scout.tools);
+        let llama = guess("meta-llama/Llama-3.3-70B-Instruct-Turbo");
+        assert!(!llama.vision && llama.tools && llama.json_mode);
+        assert!(guess("meta-llama/Llama-3.2-11B-Vision-Instruct-Turbo").vision);
+
+        let bge = guess("BAAI/bge-base-en-v1.5");
+        assert!(bge.embeddings && !bge.streaming && !bge.tools);
+    }
+
+    #[test]
+    fn openrouter_listings_override_guesses() {
+        let listing = serde_json::json!({
+            "architecture": { "input_modalities": ["text", "image"] },
+            "supported_parameters": ["temperature", "tools", "tool_choice"],
+        });
+        remember_openrouter("openrouter/example/chat-model", &listing);
+        let capabilities = for_model("openrouter/example/chat-model");
+        assert!(capabilities.listed && capabilities.vision && capabilities.tools && !capabilities.json_mode);
+        assert!(!for_model("openrouter/example/other-model").listed);
+    }
+}
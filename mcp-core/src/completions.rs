@@ -0,0 +1,170 @@
+//! Base and code models that only offer the completions API. The model list says which models
+//! those are; a chat request to one is turned into a completion request with the conversation
+//! written out as a transcript prompt, and the completion's chunks are turned back into chat
+//! chunks, so the rest of the streaming path doesn't need to know.
+
+use std::time::Duration;
+use async_openai::error::OpenAIError;
+use async_openai::types::{
+    ChatCompletionRequestMessage, CreateChatCompletionRequest, CreateChatCompletionStreamResponse,
+    CreateCompletionRequest, CreateCompletionResponse, Prompt, Stop,
+};
+use crate::cache::Cache;
+
+/// Model types in the provider's list that are served by the completions API only
+const COMPLETION_TYPES: [&str; 2] = ["language", "code"];
+
+/// Stop sequences that end the model's turn before it writes the next speaker's
+const TURN_STOPS: [&str; 2] = ["\nUser:", "\nSystem:"];
+
+/// The providers' limit on stop sequences per request
+const MAX_STOPS: usize = 4;
+
+/// Models known to be completion-only, refreshed whenever the model list is fetched
+static COMPLETION_ONLY: Cache<bool> = Cache::new(Duration::from_secs(24 * 3600), 4096);
+
+/// Record a model's type as reported by the provider's model list
+pub fn remember_model_type(model: &str, model_type: &str) {
+    COMPLETION_ONLY.insert(model, COMPLETION_TYPES.contains(&model_type));
+}
+
+/// Whether requests to `model` have to go to the completions API
+pub fn is_completion_only(model: &str) -> bool {
+    COMPLETION_ONLY.get(model).unwrap_or(false)
+}
+
+pub fn forget_model_types() -> usize {
+    COMPLETION_ONLY.clear()
+}
+
+pub fn prune_model_types() -> usize {
+    COMPLETION_ONLY.prune_expired()
+}
+
+/// The conversation as a transcript ending with an open assistant turn, e.g.
+/// `System: ...\n\nUser: ...\n\nAssistant:`
+pub fn prompt_from_messages(messages: &[ChatCompletionRequestMessage]) -> String {
+    let mut prompt = String::new();
+    for message in messages {
+        let message = serde_json::to_value(message).unwrap_or_default();
+        let speaker = match message["role"].as_str() {
+            Some("system") | Some("developer") => "System",
+            Some("assistant") => "Assistant",
+            Some("tool") | Some("function") => "Tool",
+            _ => "User",
+        };
+        let content = match &message["content"] {
+            serde_json::Value::String(text) => text.clone(),
+            serde_json::Value::Array(parts) => parts.iter()
+                .filter_map(|part| part["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        };
+        prompt.push_str(&format!("{}: {}\n\n", speaker, content.trim()));
+    }
+    prompt.push_str("Assistant:");
+    prompt
+}
+
+/// The completion request equivalent to a chat request
+pub fn completion_request(chat: &CreateChatCompletionRequest) -> Result<CreateCompletionRequest, OpenAIError> {
+    let mut stops: Vec<String> = match &chat.stop {
+        Some(Stop::String(stop)) => vec![stop.clone()],
+        Some(Stop::StringArray(stops)) => stops.clone(),
+        None => Vec::new(),
+    };
+    for stop in TURN_STOPS {
+        if stops.len() < MAX_STOPS && !stops.iter().any(|existing| existing == stop) {
+            stops.push(stop.to_string());
+        }
+    }
+    #[allow(deprecated)]
+    let max_tokens = chat.max_tokens.or(chat.max_completion_tokens);
+    Ok(CreateCompletionRequest {
+        model: chat.model.clone(),
+        prompt: Prompt::String(prompt_from_messages(&chat.messages)),
+        max_tokens,
+        temperature: chat.temperature,
+        top_p: chat.top_p,
+        stream: Some(true),
+        stream_options: chat.stream_options,
+        stop: Some(Stop::StringArray(stops)),
+        presence_penalty: chat.presence_penalty,
+        frequency_penalty: chat.frequency_penalty,
+        seed: chat.seed,
+        user: chat.user.clone(),
+        ..Default::default()
+    })
+}
+
+/// A completion chunk in the shape of a chat chunk
+pub fn as_chat_chunk(chunk: CreateCompletionResponse) -> Result<CreateChatCompletionStreamResponse, OpenAIError> {
+    let choices: Vec<serde_json::Value> = chunk.choices.iter()
+        .map(|choice| serde_json::json!({
+            "index": choice.index,
+            "delta": { "content": choice.text },
+            "finish_reason": choice.finish_reason,
+        }))
+        .collect();
+    serde_json::from_value(serde_json::json!({
+        "id": chunk.id,
+        "choices": choices,
+        "created": chunk.created,
+        "model": chunk.model,
+        "system_fingerprint": chunk.system_fingerprint,
+        "object": "chat.completion.chunk",
+        "usage": chunk.usage,
+    }))
+    .map_err(OpenAIError::JSONDeserialize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::types::{
+        ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestSystemMessageArgs,
+        ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs,
+    };
+
+    #[test]
+    fn chat_requests_become_transcript_prompts() {
+        let messages: Vec<ChatCompletionRequestMessage> = vec![
+            ChatCompletionRequestSystemMessageArgs::default().content("Be brief.").build().unwrap().into(),
+            ChatCompletionRequestUserMessageArgs::default().content("Name a prime.").build().unwrap().into(),
+            ChatCompletionRequestAssistantMessageArgs::default().content("7").build().unwrap().into(),
+            ChatCompletionRequestUserMessageArgs::default().content("Another?").build().unwrap().into(),
+        ];
+        let chat = CreateChatCompletionRequestArgs::default()
+            .model("codellama/CodeLlama-34b-hf")
+            .messages(messages)
+            .stop("###")
+            .temperature(0.2)
+            .build()
+            .unwrap();
+        let request = completion_request(&chat).unwrap();
+        assert_eq!(
+            request.prompt,
+            Prompt::String("System: Be brief.\n\nUser: Name a prime.\n\nAssistant: 7\n\nUser: Another?\n\nAssistant:".to_string()),
+        );
+        assert_eq!(request.stop, Some(Stop::StringArray(vec!["###".to_string(), "\nUser:".to_string(), "\nSystem:".to_string()])));
+        assert_eq!(request.temperature, Some(0.2));
+    }
+
+    #[test]
+    fn completion_chunks_read_as_chat_chunks() {
+        let chunk: CreateCompletionResponse = serde_json::from_value(serde_json::json!({
+            "id": "c1", "object": "completion.chunk", "created": 1, "model": "m",
+            "choices": [{"index": 0, "text": " 11", "logprobs": null, "finish_reason": "stop"}],
+        })).unwrap();
+        let chat = as_chat_chunk(chunk).unwrap();
+        assert_eq!(chat.choices[0].delta.content.as_deref(), Some(" 11"));
+        assert_eq!(chat.object, "chat.completion.chunk");
+
+        remember_model_type("codellama/CodeLlama-34b-hf", "code");
+        remember_model_type("meta-llama/Llama-3-8b-chat-hf", "chat");
+        assert!(is_completion_only("codellama/CodeLlama-34b-hf"));
+        assert!(!is_completion_only("meta-llama/Llama-3-8b-chat-hf"));
+        assert!(!is_completion_only("unlisted"));
+    }
+}
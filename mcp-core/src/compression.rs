@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::context::estimate_tokens;
+use crate::history::HistoryMessage;
+
+/// Share of text kept when compression is turned on without a ratio
+pub const DEFAULT_COMPRESSION_RATIO: f32 = 0.5;
+
+/// The most recent messages are always sent verbatim
+const RECENT_MESSAGES_KEPT: usize = 2;
+
+/// Text shorter than this isn't worth compressing
+const MIN_COMPRESSIBLE_CHARS: usize = 400;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "can", "do", "for", "from", "has", "have",
+    "he", "her", "his", "i", "if", "in", "is", "it", "its", "me", "my", "not", "of", "on", "or", "our",
+    "she", "so", "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "was",
+    "we", "were", "what", "when", "which", "will", "with", "would", "you", "your",
+];
+
+/// Per-conversation compression of old turns and retrieved context
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ConversationCompression {
+    pub conversation_id: String,
+    pub enabled: bool,
+    /// Target share of the original text to keep, between 0.1 and 1.0
+    pub ratio: f32,
+    /// Estimated tokens of history before and after the last compression pass
+    pub last_before_tokens: Option<u32>,
+    pub last_after_tokens: Option<u32>,
+}
+
+/// Shrink every message except the most recent ones to about `ratio` of its length.
+/// Returns the messages with estimated token counts before and after.
+pub fn compress_history(history: Vec<HistoryMessage>, ratio: f32) -> (Vec<HistoryMessage>, u32, u32) {
+    let before: usize = history.iter().map(|m| estimate_tokens(&m.content)).sum();
+    let keep_from = history.len().saturating_sub(RECENT_MESSAGES_KEPT);
+    let history: Vec<HistoryMessage> = history.into_iter().enumerate()
+        .map(|(i, message)| {
+            if i < keep_from {
+                HistoryMessage { content: compress(&message.content, ratio), ..message }
+            } else {
+                message
+            }
+        })
+        .collect();
+    let after: usize = history.iter().map(|m| estimate_tokens(&m.content)).sum();
+    (history, before as u32, after as u32)
+}
+
+/// Keep the highest-scoring sentences, in their original order, until about `ratio` of the
+/// characters remain. Code blocks are kept whole. A sentence scores the average number of times
+/// its words appear across the whole text, so sentences about the main topic outrank asides, and
+/// the first sentence gets a bonus since it usually states the topic.
+pub fn compress(text: &str, ratio: f32) -> String {
+    let ratio = ratio.clamp(0.1, 1.0);
+    let total = text.chars().count();
+    if ratio >= 1.0 || total < MIN_COMPRESSIBLE_CHARS {
+        return text.to_string();
+    }
+
+    let units = split_units(text);
+    let stop_words: HashSet<&str> = STOP_WORDS.iter().copied().collect();
+    let words_of = |unit: &str| -> Vec<String> {
+        unit.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.len() > 1)
+            .map(str::to_lowercase)
+            .filter(|w| !stop_words.contains(w.as_str()))
+            .collect()
+    };
+
+    let mut frequency: HashMap<String, usize> = HashMap::new();
+    for unit in units.iter().filter(|unit| !unit.is_code) {
+        for word in words_of(&unit.text) {
+            *frequency.entry(word).or_default() += 1;
+        }
+    }
+
+    let mut scored: Vec<(usize, f32)> = units.iter().enumerate()
+        .map(|(i, unit)| {
+            if unit.is_code {
+                return (i, f32::MAX);
+            }
+            let words = words_of(&unit.text);
+            if words.is_empty() {
+                return (i, 0.0);
+            }
+            let score = words.iter().map(|w| frequency[w] as f32).sum::<f32>() / words.len() as f32;
+            (i, if i == 0 { score * 1.5 } else { score })
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let target = (total as f32 * ratio) as usize;
+    let mut kept = vec![false; units.len()];
+    let mut length = 0;
+    for (i, _) in scored {
+        let unit_length = units[i].text.chars().count();
+        if length > 0 && length + unit_length > target && !units[i].is_code {
+            // Stop at the first sentence that doesn't fit rather than padding with weaker ones
+            break;
+        }
+        kept[i] = true;
+        length += unit_length;
+    }
+
+    let mut result = String::new();
+    let mut skipped = false;
+    for (unit, keep) in units.iter().zip(kept) {
+        if keep {
+            if skipped && !result.is_empty() {
+                result.push_str(" … ");
+            } else if unit.is_code || result.ends_with("```") {
+                result.push('\n');
+            } else if !result.is_empty() {
+                result.push(' ');
+            }
+            result.push_str(&unit.text);
+            skipped = false;
+        } else {
+            skipped = true;
+        }
+    }
+    result
+}
+
+struct Unit {
+    text: String,
+    is_code: bool,
+}
+
+/// Sentences of prose, with each fenced code block as a single unit
+fn split_units(text: &str) -> Vec<Unit> {
+    let mut units = Vec::new();
+    let mut prose = String::new();
+    let mut code: Option<String> = None;
+
+    for line in text.lines() {
+        let fence = line.trim_start().starts_with("```");
+        match code.as_mut() {
+            Some(block) => {
+                block.push('\n');
+                block.push_str(line);
+                if fence {
+                    units.push(Unit { text: code.take().unwrap_or_default(), is_code: true });
+                }
+            }
+            None if fence => {
+                split_sentences(&prose, &mut units);
+                prose.clear();
+                code = Some(line.to_string());
+            }
+            None => {
+                prose.push_str(line);
+                prose.push('\n');
+            }
+        }
+    }
+    if let Some(block) = code {
+        units.push(Unit { text: block, is_code: true });
+    }
+    split_sentences(&prose, &mut units);
+    units
+}
+
+fn split_sentences(prose: &str, units: &mut Vec<Unit>) {
+    let mut sentence = String::new();
+    let mut chars = prose.chars().peekable();
+    while let Some(c) = chars.next() {
+        sentence.push(c);
+        let boundary = match c {
+            '.' | '!' | '?' => chars.peek().is_none_or(|next| next.is_whitespace()),
+            '\n' => chars.peek() == Some(&'\n'),
+            _ => false,
+        };
+        if boundary {
+            push_sentence(&mut sentence, units);
+        }
+    }
+    push_sentence(&mut sentence, units);
+}
+
+fn push_sentence(sentence: &mut String, units: &mut Vec<Unit>) {
+    let text = sentence.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !text.is_empty() {
+        units.push(Unit { text, is_code: false });
+    }
+    sentence.clear();
+}
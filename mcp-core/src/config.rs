@@ -1,15 +1,151 @@
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 use aes_gcm::{Aes256Gcm, Key, Nonce, KeyInit};
 use aes_gcm::aead::{Aead, OsRng, AeadCore};
+use aes_gcm::aead::rand_core::RngCore;
 use base64::{Engine as _, engine::general_purpose};
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 use sha2::{Sha256, Digest};
+use crate::accessibility::AccessibilitySettings;
+use crate::appearance::AppearanceSettings;
+use crate::backup::BackupSchedule;
+use crate::environment::{self, ValueSource};
+use crate::platform;
+use crate::policy;
+use crate::system_defaults;
+use crate::features::Feature;
+use crate::generation::GenerationSettings;
+use crate::low_power::LowPowerSettings;
+use crate::maintenance::{MaintenanceJob, MaintenanceSettings};
+use crate::modes::AppMode;
+use crate::onboarding::{OnboardingProgress, OnboardingStep};
+use crate::guardrails::GuardrailConfig;
+use crate::server_auth::{ServerNetworkConfig, ServerTlsConfig, ServerToken};
+use crate::sync::SyncConfig;
+use crate::telemetry::TelemetryConfig;
+use crate::tool_cache::ToolCacheSettings;
+use crate::tool_limits::ToolResultLimits;
+use crate::vault::Secret;
+use crate::azure::AzureProfile;
+use crate::bedrock::BedrockAccount;
+use crate::custom_models::CustomModel;
+use crate::endpoints::CompatibleEndpoint;
+use crate::gateways::GatewayRoute;
+use crate::providers::Provider;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 struct AppConfig {
     together_ai_api_key: String,
     preferred_model: Option<String>,
+    #[serde(default)]
+    template_repositories: Vec<String>,
+    #[serde(default)]
+    plugin_grants: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    server_tokens: Vec<ServerToken>,
+    /// Set when the first token is created and kept when the last is revoked, so the API
+    /// server never quietly goes back to accepting unauthenticated requests
+    #[serde(default)]
+    server_auth_enabled: bool,
+    #[serde(default)]
+    server_tls: Option<ServerTlsConfig>,
+    #[serde(default)]
+    server_network: ServerNetworkConfig,
+    #[serde(default)]
+    generation: GenerationSettings,
+    #[serde(default)]
+    guardrails: GuardrailConfig,
+    #[serde(default)]
+    memory_enabled: bool,
+    #[serde(default)]
+    backup_schedule: BackupSchedule,
+    #[serde(default)]
+    sync: SyncConfig,
+    /// Language tag chosen for backend messages; None follows the OS
+    #[serde(default)]
+    locale: Option<String>,
+    #[serde(default)]
+    accessibility: AccessibilitySettings,
+    #[serde(default)]
+    appearance: AppearanceSettings,
+    #[serde(default)]
+    mode: AppMode,
+    /// None until the first-run flow is started
+    #[serde(default)]
+    onboarding: Option<OnboardingProgress>,
+    /// Flags switched away from their default; unset flags follow the default of the build
+    #[serde(default)]
+    feature_flags: BTreeMap<Feature, bool>,
+    /// Mask personal data and secrets in prompts before they're sent
+    #[serde(default)]
+    redaction_enabled: bool,
+    /// Append every chunk of every provider stream to a transcript, for diagnosing provider glitches
+    #[serde(default)]
+    record_streams: bool,
+    /// Hold streamed answers back from the window while it's minimized or hidden
+    #[serde(default)]
+    pause_hidden_streams: bool,
+    #[serde(default)]
+    telemetry: TelemetryConfig,
+    #[serde(default)]
+    tool_result_limits: ToolResultLimits,
+    #[serde(default)]
+    tool_cache: ToolCacheSettings,
+    /// Tool calls from one agent step run at once
+    #[serde(default = "default_max_parallel_tools")]
+    max_parallel_tools: u32,
+    /// Secrets of tools and integrations, each readable only by its owner
+    #[serde(default)]
+    secrets: Vec<Secret>,
+    /// Fine-tuned models and dedicated endpoints missing from the provider's model list
+    #[serde(default)]
+    custom_models: Vec<CustomModel>,
+    /// Keys of the providers other than Together.ai, by provider name
+    #[serde(default)]
+    provider_api_keys: BTreeMap<String, String>,
+    /// Extra headers sent with every request to a provider, by provider name, e.g. a gateway's
+    /// auth or an `OpenAI-Organization`
+    #[serde(default)]
+    provider_headers: BTreeMap<String, BTreeMap<String, String>>,
+    /// Gateways providers' requests are routed through, by provider name
+    #[serde(default)]
+    gateways: BTreeMap<String, GatewayRoute>,
+    /// Where a local Ollama server listens; Ollama is used only once this is set
+    #[serde(default)]
+    ollama_base_url: Option<String>,
+    /// How long Ollama keeps a model loaded after a request, as seconds or a duration such as
+    /// `30m`; Ollama's own default when unset
+    #[serde(default)]
+    ollama_keep_alive: Option<String>,
+    /// Azure OpenAI deployments, each listed as a model
+    #[serde(default)]
+    azure_profiles: Vec<AzureProfile>,
+    /// The AWS access key Bedrock is reached with; its secret is kept with the provider keys
+    #[serde(default)]
+    bedrock_account: Option<BedrockAccount>,
+    /// OpenAI-compatible servers registered by hand, each listed as a provider of its own
+    #[serde(default)]
+    compatible_endpoints: Vec<CompatibleEndpoint>,
+    /// Models on other providers to fall back to, in order, by the provider name they stand in for
+    #[serde(default)]
+    failover_chains: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    maintenance: MaintenanceSettings,
+    /// When each idle maintenance job last finished, RFC 3339
+    #[serde(default)]
+    maintenance_runs: BTreeMap<MaintenanceJob, String>,
+    #[serde(default)]
+    low_power: LowPowerSettings,
+    /// Text typed in place of `;name` shortcuts, by name
+    #[serde(default)]
+    text_expansions: BTreeMap<String, String>,
+}
+
+fn default_max_parallel_tools() -> u32 {
+    crate::tool_calls::DEFAULT_MAX_PARALLEL
 }
 
 impl Default for AppConfig {
@@ -17,42 +153,209 @@ impl Default for AppConfig {
         Self {
             together_ai_api_key: String::new(),
             preferred_model: Some("meta-llama/Meta-Llama-3.1-8B-Instruct-Turbo".to_string()),
+            template_repositories: Vec::new(),
+            plugin_grants: HashMap::new(),
+            server_tokens: Vec::new(),
+            server_auth_enabled: false,
+            server_tls: None,
+            server_network: ServerNetworkConfig::default(),
+            generation: GenerationSettings::default(),
+            guardrails: GuardrailConfig::default(),
+            memory_enabled: false,
+            backup_schedule: BackupSchedule::default(),
+            sync: SyncConfig::default(),
+            locale: None,
+            accessibility: AccessibilitySettings::default(),
+            appearance: AppearanceSettings::default(),
+            mode: AppMode::Chat,
+            onboarding: None,
+            feature_flags: BTreeMap::new(),
+            redaction_enabled: false,
+            record_streams: false,
+            pause_hidden_streams: false,
+            telemetry: TelemetryConfig::default(),
+            tool_result_limits: ToolResultLimits::default(),
+            tool_cache: ToolCacheSettings::default(),
+            max_parallel_tools: default_max_parallel_tools(),
+            secrets: Vec::new(),
+            custom_models: Vec::new(),
+            provider_api_keys: BTreeMap::new(),
+            provider_headers: BTreeMap::new(),
+            gateways: BTreeMap::new(),
+            ollama_base_url: None,
+            ollama_keep_alive: None,
+            azure_profiles: Vec::new(),
+            bedrock_account: None,
+            compatible_endpoints: Vec::new(),
+            failover_chains: BTreeMap::new(),
+            maintenance: MaintenanceSettings::default(),
+            maintenance_runs: BTreeMap::new(),
+            low_power: LowPowerSettings::default(),
+            text_expansions: BTreeMap::new(),
         }
     }
 }
 
+/// Preferences that carry over to another install, as written by `export_settings`. Leaves out
+/// the API key and other secrets, server tokens and TLS, sync keys and anything tied to this
+/// machine's paths.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PortableSettings {
+    pub preferred_model: Option<String>,
+    pub generation: GenerationSettings,
+    pub guardrails: GuardrailConfig,
+    pub memory_enabled: bool,
+    pub template_repositories: Vec<String>,
+    pub locale: Option<String>,
+    pub accessibility: AccessibilitySettings,
+    pub appearance: AppearanceSettings,
+}
+
+/// Prefix of configs encrypted with the salted machine-id key; older files have none
+const KEY_V2_PREFIX: &str = "v2:";
+
+/// Salt for the config key, stored next to the config as `config.key`
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    /// Base64 of 32 random bytes
+    salt: String,
+    /// SHA-256 of the machine identity the key was derived from, to tell a config from another
+    /// machine (or a reinstalled OS) apart from a damaged one
+    machine_fingerprint: String,
+}
+
+/// How often cached config files are checked for changes made outside this process
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// SHA-256 of a config file's contents, or None if it doesn't exist
+type FileDigest = Option<[u8; 32]>;
+
+/// A decrypted config file, kept until the file's contents change
+struct CachedConfig {
+    config: Option<AppConfig>,
+    digest: FileDigest,
+}
+
+/// Decrypted configs by file. Saves write through to it; a watcher thread drops entries whose
+/// file was changed by something else, so reads only go back to disk after such a change.
+fn config_cache() -> &'static Mutex<HashMap<PathBuf, CachedConfig>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedConfig>>> = OnceLock::new();
+    CACHE.get_or_init(|| {
+        std::thread::spawn(watch_config_files);
+        Mutex::new(HashMap::new())
+    })
+}
+
+fn lock_config_cache() -> Result<std::sync::MutexGuard<'static, HashMap<PathBuf, CachedConfig>>> {
+    config_cache().lock().map_err(|_| anyhow::anyhow!("Config cache is poisoned"))
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn digest_of(contents: Option<&[u8]>) -> FileDigest {
+    contents.map(|contents| Sha256::digest(contents).into())
+}
+
+fn file_digest(path: &Path) -> Result<FileDigest> {
+    Ok(digest_of(read_if_exists(path)?.as_deref()))
+}
+
+/// Cached config count and approximate decrypted size
+pub fn cache_usage() -> (u32, u64) {
+    let Ok(cache) = config_cache().lock() else { return (0, 0) };
+    let bytes = cache.values().map(|cached| crate::metrics::payload_size(&cached.config)).sum();
+    (cache.len() as u32, bytes)
+}
+
+fn watch_config_files() {
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        drop_changed_configs();
+    }
+}
+
+/// Forget cached configs whose file no longer has the contents they were read from. Hashed
+/// rather than stat'ed, as a quick same-length rewrite can keep the size and modification time.
+fn drop_changed_configs() {
+    let Ok(mut cache) = config_cache().lock() else { return };
+    cache.retain(|path, cached| {
+        let unchanged = file_digest(path).is_ok_and(|digest| digest == cached.digest);
+        if !unchanged {
+            log::info!("Config file {:?} changed on disk, reloading on next read", path);
+        }
+        unchanged
+    });
+}
+
 pub struct ConfigManager {
     config_dir: PathBuf,
     config_file: PathBuf,
+    data_dir: PathBuf,
 }
 
 impl ConfigManager {
     pub fn new() -> Result<Self> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?
-            .join("mcp-switchboard");
+        let mut config_dir = platform::config_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+
+        let mut data_dir = platform::data_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine data directory"))?;
+
+        // Demo mode gets its own config and history, so nothing done in it touches the real ones
+        if crate::demo::active() {
+            config_dir = config_dir.join("demo");
+            data_dir = data_dir.join("demo");
+        }
 
         let config_file = config_dir.join("config.json");
 
         Ok(ConfigManager {
             config_dir,
             config_file,
+            data_dir,
         })
     }
 
+    /// A manager whose config and data live in `dir`, for tests
+    #[cfg(test)]
+    pub(crate) fn in_dir(dir: &Path) -> Self {
+        ConfigManager {
+            config_dir: dir.to_path_buf(),
+            config_file: dir.join("config.json"),
+            data_dir: dir.join("data"),
+        }
+    }
+
     pub fn get_api_key(&self) -> Result<Option<String>> {
-        // First check environment variable (for development)
-        if let Ok(env_key) = std::env::var("TOGETHERAI_API_KEY") {
-            if !env_key.is_empty() {
-                log::info!("Using API key from environment variable");
-                return Ok(Some(env_key));
-            }
+        Ok(self.resolve_api_key()?.map(|(key, _)| key))
+    }
+
+    /// Where the API key in use comes from, in the order described in `environment`
+    pub fn get_api_key_source(&self) -> Result<Option<ValueSource>> {
+        Ok(self.resolve_api_key()?.map(|(_, source)| source))
+    }
+
+    fn resolve_api_key(&self) -> Result<Option<(String, ValueSource)>> {
+        // First the command line, environment and .env file (for development)
+        if let Some((env_key, source)) = environment::var(environment::API_KEY_VAR) {
+            log::info!("Using API key from {:?}", source);
+            return Ok(Some((env_key, source)));
         }
 
         // Then check encrypted config file
         if let Some(config) = self.load_config()? {
             log::info!("Using API key from encrypted config file: {:?}", self.config_file);
-            return Ok(Some(config.together_ai_api_key));
+            // Empty when only another provider's key has been saved
+            if !config.together_ai_api_key.is_empty() {
+                return Ok(Some((config.together_ai_api_key, ValueSource::ConfigFile)));
+            }
         }
 
         log::warn!("No API key found in environment or config file");
@@ -63,25 +366,120 @@ impl ConfigManager {
         log::info!("Saving API key to encrypted config file: {:?}", self.config_file);
         
         // Preserve existing config if it exists
-        let mut config = self.load_config()?.unwrap_or_else(|| AppConfig {
-            together_ai_api_key: String::new(),
-            preferred_model: AppConfig::default().preferred_model,
-        });
-        config.together_ai_api_key = api_key;
-        
-        self.save_config(&config)?;
+        self.update_config(|config| {
+            config.together_ai_api_key = api_key;
+            Ok(())
+        })?;
         log::info!("Config saved to: {:?}", self.config_file);
         log::info!("API key successfully saved and encrypted");
         Ok(())
     }
 
-    pub fn get_preferred_model(&self) -> Result<String> {
-        // First check if we have a saved preference
-        if let Some(config) = self.load_config()? {
-            if let Some(model) = config.preferred_model {
-                log::info!("Using preferred model from config: {}", model);
-                return Ok(model);
+    /// The key for `provider`; Together's resolves as `get_api_key` does, the others' from
+    /// their environment variable and then the config file
+    pub fn get_provider_api_key(&self, provider: Provider) -> Result<Option<String>> {
+        Ok(self.resolve_provider_api_key(provider)?.map(|(key, _)| key))
+    }
+
+    pub fn get_provider_api_key_source(&self, provider: Provider) -> Result<Option<ValueSource>> {
+        Ok(self.resolve_provider_api_key(provider)?.map(|(_, source)| source))
+    }
+
+    fn resolve_provider_api_key(&self, provider: Provider) -> Result<Option<(String, ValueSource)>> {
+        if provider == Provider::Together {
+            return self.resolve_api_key();
+        }
+        if let Some(found) = provider.key_var().and_then(environment::var) {
+            return Ok(Some(found));
+        }
+        Ok(self.load_config()?
+            .and_then(|config| config.provider_api_keys.get(provider.name()).cloned())
+            .map(|key| (key, ValueSource::ConfigFile)))
+    }
+
+    /// Store the key for `provider`, or with None forget it
+    pub fn save_provider_api_key(&self, provider: Provider, api_key: Option<String>) -> Result<()> {
+        if provider == Provider::Together {
+            return self.save_api_key(api_key.unwrap_or_default());
+        }
+        log::info!("Saving {} API key to encrypted config file: {:?}", provider.name(), self.config_file);
+        self.update_config(|config| {
+            match api_key {
+                Some(api_key) => config.provider_api_keys.insert(provider.name().to_string(), api_key),
+                None => config.provider_api_keys.remove(provider.name()),
+            };
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_provider_headers(&self, provider: Provider) -> Result<BTreeMap<String, String>> {
+        Ok(self.load_config_or_defaults()?.provider_headers.get(provider.name()).cloned().unwrap_or_default())
+    }
+
+    /// Send `headers` with every request to `provider`; empty headers send none
+    pub fn save_provider_headers(&self, provider: Provider, headers: BTreeMap<String, String>) -> Result<()> {
+        log::info!("Saving {} extra headers for {}", headers.len(), provider.name());
+        self.update_config(|config| {
+            if headers.is_empty() {
+                config.provider_headers.remove(provider.name());
+            } else {
+                config.provider_headers.insert(provider.name().to_string(), headers);
             }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_gateway(&self, provider: Provider) -> Result<Option<GatewayRoute>> {
+        Ok(self.load_config_or_defaults()?.gateways.get(provider.name()).cloned())
+    }
+
+    /// Route `provider`'s requests through `route`, or with None send them to the provider again
+    pub fn save_gateway(&self, provider: Provider, route: Option<GatewayRoute>) -> Result<()> {
+        log::info!("Saving gateway for {} to encrypted config file: {:?}", provider.name(), route.as_ref().map(|route| route.preset));
+        self.update_config(|config| {
+            match route {
+                Some(route) => config.gateways.insert(provider.name().to_string(), route),
+                None => config.gateways.remove(provider.name()),
+            };
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_ollama_base_url(&self) -> Result<Option<String>> {
+        Ok(self.load_config_or_defaults()?.ollama_base_url)
+    }
+
+    /// Use the Ollama server at `base_url`, or with None stop using Ollama
+    pub fn save_ollama_base_url(&self, base_url: Option<String>) -> Result<()> {
+        log::info!("Saving Ollama base URL to config: {:?}", base_url);
+        self.update_config(|config| {
+            config.ollama_base_url = base_url;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_ollama_keep_alive(&self) -> Result<Option<String>> {
+        Ok(self.load_config_or_defaults()?.ollama_keep_alive)
+    }
+
+    pub fn save_ollama_keep_alive(&self, keep_alive: Option<String>) -> Result<()> {
+        log::info!("Saving Ollama keep-alive to config: {:?}", keep_alive);
+        self.update_config(|config| {
+            config.ollama_keep_alive = keep_alive;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_preferred_model(&self) -> Result<String> {
+        // First check if we have a saved preference, or one from the system defaults
+        if let Some(model) = self.load_config_or_defaults()?.preferred_model {
+            log::info!("Using preferred model from config: {}", model);
+            return Ok(model);
         }
         
         // Fall back to default model
@@ -94,98 +492,1103 @@ impl ConfigManager {
         log::info!("Saving preferred model to config: {}", model);
         
         // Load existing config or create new one
-        let mut config = self.load_config()?.unwrap_or_else(|| AppConfig {
-            together_ai_api_key: String::new(),
-            preferred_model: AppConfig::default().preferred_model,
-        });
-        config.preferred_model = Some(model);
-        
-        self.save_config(&config)?;
+        self.update_config(|config| {
+            config.preferred_model = Some(model);
+            Ok(())
+        })?;
         log::info!("Preferred model saved successfully");
         Ok(())
     }
 
+    pub fn get_generation_settings(&self) -> Result<GenerationSettings> {
+        Ok(self.load_config_or_defaults()?.generation)
+    }
+
+    pub fn save_generation_settings(&self, settings: GenerationSettings) -> Result<()> {
+        log::info!("Saving generation settings: {:?}", settings.mode);
+        self.update_config(|config| {
+            config.generation = settings;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_guardrails(&self) -> Result<GuardrailConfig> {
+        Ok(self.load_config_or_defaults()?.guardrails)
+    }
+
+    pub fn save_guardrails(&self, guardrails: GuardrailConfig) -> Result<()> {
+        log::info!("Saving guardrails (enabled: {})", guardrails.enabled);
+        self.update_config(|config| {
+            config.guardrails = guardrails;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_memory_enabled(&self) -> Result<bool> {
+        Ok(self.load_config_or_defaults()?.memory_enabled)
+    }
+
+    pub fn save_memory_enabled(&self, enabled: bool) -> Result<()> {
+        log::info!("Saving memory enabled: {}", enabled);
+        self.update_config(|config| {
+            config.memory_enabled = enabled;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_redaction_enabled(&self) -> Result<bool> {
+        Ok(self.load_config_or_defaults()?.redaction_enabled)
+    }
+
+    pub fn save_redaction_enabled(&self, enabled: bool) -> Result<()> {
+        log::info!("Saving redaction enabled: {}", enabled);
+        self.update_config(|config| {
+            config.redaction_enabled = enabled;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_record_streams(&self) -> Result<bool> {
+        Ok(self.load_config_or_defaults()?.record_streams)
+    }
+
+    pub fn save_record_streams(&self, enabled: bool) -> Result<()> {
+        log::info!("Saving stream recording enabled: {}", enabled);
+        self.update_config(|config| {
+            config.record_streams = enabled;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_pause_hidden_streams(&self) -> Result<bool> {
+        Ok(self.load_config_or_defaults()?.pause_hidden_streams)
+    }
+
+    pub fn save_pause_hidden_streams(&self, enabled: bool) -> Result<()> {
+        log::info!("Saving hidden stream pausing enabled: {}", enabled);
+        self.update_config(|config| {
+            config.pause_hidden_streams = enabled;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_backup_schedule(&self) -> Result<BackupSchedule> {
+        Ok(self.load_config_or_defaults()?.backup_schedule)
+    }
+
+    pub fn save_backup_schedule(&self, schedule: BackupSchedule) -> Result<()> {
+        log::info!("Saving backup schedule (enabled: {})", schedule.enabled);
+        self.update_config(|config| {
+            config.backup_schedule = schedule;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_sync_config(&self) -> Result<SyncConfig> {
+        Ok(self.load_config_or_defaults()?.sync)
+    }
+
+    pub fn save_sync_config(&self, sync: SyncConfig) -> Result<()> {
+        log::info!("Saving sync config (enabled: {})", sync.enabled);
+        self.update_config(|config| {
+            config.sync = sync;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_telemetry_config(&self) -> Result<TelemetryConfig> {
+        Ok(self.load_config_or_defaults()?.telemetry)
+    }
+
+    pub fn save_telemetry_config(&self, telemetry: TelemetryConfig) -> Result<()> {
+        log::info!("Saving telemetry config (enabled: {}, endpoint: {})", telemetry.enabled, telemetry.endpoint);
+        self.update_config(|config| {
+            config.telemetry = telemetry;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_tool_result_limits(&self) -> Result<ToolResultLimits> {
+        Ok(self.load_config_or_defaults()?.tool_result_limits)
+    }
+
+    pub fn save_tool_result_limits(&self, limits: ToolResultLimits) -> Result<()> {
+        log::info!("Saving tool result limits ({} chars by default, {} overrides)", limits.default.max_chars, limits.overrides.len());
+        self.update_config(|config| {
+            config.tool_result_limits = limits;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_tool_cache_settings(&self) -> Result<ToolCacheSettings> {
+        Ok(self.load_config_or_defaults()?.tool_cache)
+    }
+
+    pub fn save_tool_cache_settings(&self, settings: ToolCacheSettings) -> Result<()> {
+        log::info!("Saving tool cache settings ({}s by default, {} overrides)", settings.default_ttl_secs, settings.ttl_overrides.len());
+        self.update_config(|config| {
+            config.tool_cache = settings;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_max_parallel_tools(&self) -> Result<u32> {
+        Ok(self.load_config_or_defaults()?.max_parallel_tools)
+    }
+
+    pub fn save_max_parallel_tools(&self, max_parallel: u32) -> Result<()> {
+        log::info!("Saving tool parallelism: {}", max_parallel);
+        self.update_config(|config| {
+            config.max_parallel_tools = max_parallel;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_secrets(&self) -> Result<Vec<Secret>> {
+        Ok(self.load_config_or_defaults()?.secrets)
+    }
+
+    pub fn save_secrets(&self, secrets: Vec<Secret>) -> Result<()> {
+        log::info!("Saving {} secrets to config", secrets.len());
+        self.update_config(|config| {
+            config.secrets = secrets;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_custom_models(&self) -> Result<Vec<CustomModel>> {
+        Ok(self.load_config_or_defaults()?.custom_models)
+    }
+
+    pub fn save_custom_models(&self, models: Vec<CustomModel>) -> Result<()> {
+        log::info!("Saving {} custom models to config", models.len());
+        self.update_config(|config| {
+            config.custom_models = models;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_azure_profiles(&self) -> Result<Vec<AzureProfile>> {
+        Ok(self.load_config_or_defaults()?.azure_profiles)
+    }
+
+    pub fn save_azure_profiles(&self, profiles: Vec<AzureProfile>) -> Result<()> {
+        log::info!("Saving {} Azure OpenAI profiles to config", profiles.len());
+        self.update_config(|config| {
+            config.azure_profiles = profiles;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_bedrock_account(&self) -> Result<Option<BedrockAccount>> {
+        Ok(self.load_config_or_defaults()?.bedrock_account)
+    }
+
+    /// Reach Bedrock with `account`, or with None with the AWS environment variables
+    pub fn save_bedrock_account(&self, account: Option<BedrockAccount>) -> Result<()> {
+        log::info!("Saving Bedrock account to config: {:?}", account.as_ref().map(|account| &account.region));
+        self.update_config(|config| {
+            config.bedrock_account = account;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_compatible_endpoints(&self) -> Result<Vec<CompatibleEndpoint>> {
+        Ok(self.load_config_or_defaults()?.compatible_endpoints)
+    }
+
+    pub fn save_compatible_endpoints(&self, endpoints: Vec<CompatibleEndpoint>) -> Result<()> {
+        log::info!("Saving {} OpenAI-compatible endpoints to encrypted config file", endpoints.len());
+        self.update_config(|config| {
+            config.compatible_endpoints = endpoints;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_failover_chains(&self) -> Result<BTreeMap<String, Vec<String>>> {
+        Ok(self.load_config_or_defaults()?.failover_chains)
+    }
+
+    /// Fall back from `provider` to the models in `chain`, in order; an empty chain falls back to none
+    pub fn save_failover_chain(&self, provider: Provider, chain: Vec<String>) -> Result<()> {
+        log::info!("Saving failover chain of {} models for {}", chain.len(), provider.name());
+        self.update_config(|config| {
+            if chain.is_empty() {
+                config.failover_chains.remove(provider.name());
+            } else {
+                config.failover_chains.insert(provider.name().to_string(), chain);
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_maintenance_settings(&self) -> Result<MaintenanceSettings> {
+        Ok(self.load_config_or_defaults()?.maintenance)
+    }
+
+    pub fn save_maintenance_settings(&self, settings: MaintenanceSettings) -> Result<()> {
+        log::info!("Saving maintenance settings (enabled: {}, idle after {} minutes)", settings.enabled, settings.idle_minutes);
+        self.update_config(|config| {
+            config.maintenance = settings;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_maintenance_runs(&self) -> Result<BTreeMap<MaintenanceJob, String>> {
+        Ok(self.load_config_or_defaults()?.maintenance_runs)
+    }
+
+    pub fn save_maintenance_run(&self, job: MaintenanceJob, finished_at: String) -> Result<()> {
+        self.update_config(|config| {
+            config.maintenance_runs.insert(job, finished_at);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_low_power_settings(&self) -> Result<LowPowerSettings> {
+        Ok(self.load_config_or_defaults()?.low_power)
+    }
+
+    pub fn save_low_power_settings(&self, settings: LowPowerSettings) -> Result<()> {
+        log::info!("Saving low-power settings (mode: {:?})", settings.mode);
+        self.update_config(|config| {
+            config.low_power = settings;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_text_expansions(&self) -> Result<BTreeMap<String, String>> {
+        Ok(self.load_config_or_defaults()?.text_expansions)
+    }
+
+    pub fn save_text_expansions(&self, expansions: BTreeMap<String, String>) -> Result<()> {
+        log::info!("Saving {} text expansions", expansions.len());
+        self.update_config(|config| {
+            config.text_expansions = expansions;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_locale(&self) -> Result<Option<String>> {
+        Ok(self.load_config_or_defaults()?.locale)
+    }
+
+    pub fn save_locale(&self, locale: Option<String>) -> Result<()> {
+        log::info!("Saving locale: {:?}", locale);
+        self.update_config(|config| {
+            config.locale = locale;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_accessibility_settings(&self) -> Result<AccessibilitySettings> {
+        Ok(self.load_config_or_defaults()?.accessibility)
+    }
+
+    pub fn save_accessibility_settings(&self, settings: AccessibilitySettings) -> Result<()> {
+        log::info!("Saving accessibility settings (plain stream: {}, read aloud: {})", settings.plain_stream, settings.read_aloud);
+        self.update_config(|config| {
+            config.accessibility = settings;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// First-run progress. Configs from before the first-run flow existed count as complete if
+    /// they have a key.
+    pub fn get_onboarding(&self) -> Result<OnboardingProgress> {
+        let config = self.load_config()?;
+        if let Some(progress) = config.as_ref().and_then(|c| c.onboarding.clone()) {
+            return Ok(progress);
+        }
+        if crate::demo::active() || self.get_api_key()?.is_some() {
+            return Ok(OnboardingProgress { step: OnboardingStep::Complete, ..OnboardingProgress::default() });
+        }
+        Ok(OnboardingProgress::default())
+    }
+
+    pub fn save_onboarding(&self, progress: OnboardingProgress) -> Result<()> {
+        log::info!("Saving onboarding progress: {:?}", progress.step);
+        self.update_config(|config| {
+            config.onboarding = Some(progress);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_mode(&self) -> Result<AppMode> {
+        Ok(self.load_config_or_defaults()?.mode)
+    }
+
+    pub fn save_mode(&self, mode: AppMode) -> Result<()> {
+        log::info!("Saving mode: {:?}", mode);
+        self.update_config(|config| {
+            config.mode = mode;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_feature_flags(&self) -> Result<BTreeMap<Feature, bool>> {
+        Ok(self.load_config_or_defaults()?.feature_flags)
+    }
+
+    /// Save `enabled` for `feature`, or go back to its default with None
+    pub fn save_feature_flag(&self, feature: Feature, enabled: Option<bool>) -> Result<()> {
+        log::info!("Saving feature flag {:?}: {:?}", feature, enabled);
+        self.update_config(|config| {
+            match enabled {
+                Some(enabled) => config.feature_flags.insert(feature, enabled),
+                None => config.feature_flags.remove(&feature),
+            };
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_appearance(&self) -> Result<AppearanceSettings> {
+        Ok(self.load_config_or_defaults()?.appearance)
+    }
+
+    pub fn save_appearance(&self, appearance: AppearanceSettings) -> Result<()> {
+        log::info!("Saving appearance (theme: {:?})", appearance.theme);
+        self.update_config(|config| {
+            config.appearance = appearance;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn export_settings(&self) -> Result<PortableSettings> {
+        let config = self.load_config_or_defaults()?;
+        Ok(PortableSettings {
+            preferred_model: config.preferred_model,
+            generation: config.generation,
+            guardrails: config.guardrails,
+            memory_enabled: config.memory_enabled,
+            template_repositories: config.template_repositories,
+            locale: config.locale,
+            accessibility: config.accessibility,
+            appearance: config.appearance,
+        })
+    }
+
+    /// Replace the portable preferences, keeping secrets, machine-specific settings and whatever
+    /// the policy locks
+    pub fn import_settings(&self, settings: PortableSettings) -> Result<()> {
+        log::info!("Importing settings");
+        self.update_config(|config| {
+            config.preferred_model = settings.preferred_model.or(config.preferred_model.take());
+            config.generation = settings.generation;
+            config.guardrails = settings.guardrails;
+            config.memory_enabled = settings.memory_enabled;
+            for repository in &settings.template_repositories {
+                crate::templates::validate_repository(repository)?;
+            }
+            config.template_repositories = settings.template_repositories;
+            config.locale = settings.locale;
+            config.accessibility = settings.accessibility;
+            config.appearance = settings.appearance;
+            let mut enforced = serde_json::to_value(&*config)?;
+            if let Some(enforced) = enforced.as_object_mut() {
+                enforce_policy(enforced);
+            }
+            *config = serde_json::from_value(enforced)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_template_repositories(&self) -> Result<Vec<String>> {
+        Ok(self.load_config_or_defaults()?.template_repositories)
+    }
+
+    pub fn save_template_repositories(&self, repositories: Vec<String>) -> Result<()> {
+        log::info!("Saving {} template repositories to config", repositories.len());
+        for repository in &repositories {
+            crate::templates::validate_repository(repository)?;
+        }
+        self.update_config(|config| {
+            config.template_repositories = repositories;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Permissions the user has approved for each plugin, keyed by `plugins::grant_key`
+    pub fn get_plugin_grants(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(self.load_config_or_defaults()?.plugin_grants)
+    }
+
+    pub fn save_plugin_grant(&self, plugin: String, permissions: Option<Vec<String>>) -> Result<()> {
+        log::info!("Saving plugin grant for {}: {:?}", plugin, permissions);
+        self.update_config(|config| {
+            match permissions {
+                Some(permissions) => config.plugin_grants.insert(plugin, permissions),
+                None => config.plugin_grants.remove(&plugin),
+            };
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Bearer tokens accepted by the local API server (hashed)
+    pub fn get_server_tokens(&self) -> Result<Vec<ServerToken>> {
+        Ok(self.load_config_or_defaults()?.server_tokens)
+    }
+
+    pub fn save_server_tokens(&self, tokens: Vec<ServerToken>) -> Result<()> {
+        log::info!("Saving {} server tokens to config", tokens.len());
+        self.update_config(|config| {
+            config.server_auth_enabled |= !tokens.is_empty();
+            config.server_tokens = tokens;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Whether the API server authenticates every request, tokens or not
+    pub fn get_server_auth_enabled(&self) -> Result<bool> {
+        let config = self.load_config_or_defaults()?;
+        Ok(config.server_auth_enabled || !config.server_tokens.is_empty())
+    }
+
+    pub fn save_server_auth_enabled(&self, enabled: bool) -> Result<()> {
+        log::info!("Saving server authentication: {}", enabled);
+        self.update_config(|config| {
+            config.server_auth_enabled = enabled;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_server_tls(&self) -> Result<Option<ServerTlsConfig>> {
+        Ok(self.load_config_or_defaults()?.server_tls)
+    }
+
+    pub fn save_server_tls(&self, tls: Option<ServerTlsConfig>) -> Result<()> {
+        log::info!("Saving server TLS config (enabled: {})", tls.is_some());
+        self.update_config(|config| {
+            config.server_tls = tls;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn get_server_network(&self) -> Result<ServerNetworkConfig> {
+        Ok(self.load_config_or_defaults()?.server_network)
+    }
+
+    pub fn save_server_network(&self, network: ServerNetworkConfig) -> Result<()> {
+        log::info!("Saving server network config: bind {} with {} allowed clients", network.bind_address, network.allowlist.len());
+        self.update_config(|config| {
+            config.server_network = network;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
     fn load_config(&self) -> Result<Option<AppConfig>> {
-        if !self.config_file.exists() {
-            return Ok(None);
+        self.load_locked(&mut *lock_config_cache()?)
+    }
+
+    /// The cached config, or the file's if it isn't cached
+    fn load_locked(&self, cache: &mut HashMap<PathBuf, CachedConfig>) -> Result<Option<AppConfig>> {
+        if let Some(cached) = cache.get(&self.config_file) {
+            return Ok(cached.config.clone());
         }
 
-        let encrypted_data = std::fs::read_to_string(&self.config_file)?;
-        let decrypted_data = self.decrypt_data(&encrypted_data)?;
-        let config: AppConfig = serde_json::from_slice(&decrypted_data)?;
-        Ok(Some(config))
+        let contents = read_if_exists(&self.config_file)?;
+        let mut digest = digest_of(contents.as_deref());
+        let config = if let Some(contents) = contents {
+            let encrypted_data = String::from_utf8(contents)?;
+            match self.decrypt_data(&encrypted_data) {
+                Ok(decrypted_data) => {
+                    let mut config = serde_json::from_slice::<serde_json::Value>(&decrypted_data)?;
+                    if let Some(config) = config.as_object_mut() {
+                        system_defaults::fill(config, system_defaults());
+                        enforce_policy(config);
+                    }
+                    let config = serde_json::from_value::<AppConfig>(config)?;
+                    if !encrypted_data.starts_with(KEY_V2_PREFIX) {
+                        log::info!("Re-encrypting config {:?} with the machine-id key", self.config_file);
+                        self.write_config(&config)?;
+                        digest = file_digest(&self.config_file)?;
+                    }
+                    Some(config)
+                }
+                Err(e) if self.key_changed(&encrypted_data) => {
+                    self.set_aside(&e)?;
+                    digest = None;
+                    None
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            None
+        };
+        cache.insert(self.config_file.clone(), CachedConfig { config: config.clone(), digest });
+        Ok(config)
+    }
+
+    /// Change the config and save it. The cache stays locked from the read to the write, so a
+    /// concurrent change from this process, through any ConfigManager, can't be lost in between.
+    fn update_config<T>(&self, change: impl FnOnce(&mut AppConfig) -> Result<T>) -> Result<T> {
+        let mut cache = lock_config_cache()?;
+        // The watcher may not have caught up with an edit made elsewhere; start from that edit
+        if let Some(cached) = cache.get(&self.config_file) {
+            if cached.digest != file_digest(&self.config_file)? {
+                log::info!("Config file {:?} changed on disk, reloading before saving", self.config_file);
+                cache.remove(&self.config_file);
+            }
+        }
+        let mut config = self.load_locked(&mut cache)?.unwrap_or_else(defaults_config);
+        let result = change(&mut config)?;
+        self.save_locked(&mut cache, &config)?;
+        Ok(result)
+    }
+
+    fn save_locked(&self, cache: &mut HashMap<PathBuf, CachedConfig>, config: &AppConfig) -> Result<()> {
+        if let serde_json::Value::Object(requested) = serde_json::to_value(config)? {
+            let mut enforced = requested.clone();
+            enforce_policy(&mut enforced);
+            if let Some(setting) = policy::violation(&requested, &enforced) {
+                log::warn!("Refused to save {}, which the policy locks", setting);
+                anyhow::bail!(crate::tr!("policy-locked-setting", setting = setting));
+            }
+        }
+        self.write_config(config)?;
+        cache.insert(
+            self.config_file.clone(),
+            CachedConfig { config: Some(config.clone()), digest: file_digest(&self.config_file)? },
+        );
+        Ok(())
     }
 
-    fn save_config(&self, config: &AppConfig) -> Result<()> {
+    fn write_config(&self, config: &AppConfig) -> Result<()> {
         // Ensure config directory exists
         std::fs::create_dir_all(&self.config_dir)?;
 
-        let json_data = serde_json::to_vec(config)?;
+        let mut config = serde_json::to_value(config)?;
+        if let Some(config) = config.as_object_mut() {
+            system_defaults::strip(config, system_defaults());
+        }
+        let json_data = serde_json::to_vec(&config)?;
         let encrypted_data = self.encrypt_data(&json_data)?;
         std::fs::write(&self.config_file, encrypted_data)?;
         Ok(())
     }
 
-    fn get_encryption_key(&self) -> Result<[u8; 32]> {
-        // Generate a machine-specific key based on hostname and user
-        let machine_id = format!(
-            "{}:{}",
-            std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
-            gethostname::gethostname().to_string_lossy()
+    /// The user's config, or the system defaults when there isn't one yet
+    fn load_config_or_defaults(&self) -> Result<AppConfig> {
+        match self.load_config()? {
+            Some(config) => Ok(config),
+            None => Ok(defaults_config()),
+        }
+    }
+
+    /// Whether a config that failed to decrypt was written with a key this machine can no longer
+    /// derive, rather than being damaged
+    fn key_changed(&self, encrypted_data: &str) -> bool {
+        if crate::portable::active() {
+            // The key comes from the passphrase, so a failure means it was entered wrong
+            return false;
+        }
+        if !encrypted_data.starts_with(KEY_V2_PREFIX) {
+            // None of the hostname and user combinations tried match, so one of them changed
+            return true;
+        }
+        match self.load_key_file() {
+            Ok(Some(key_file)) => key_file.machine_fingerprint != machine_fingerprint(),
+            Ok(None) => true,
+            Err(_) => false,
+        }
+    }
+
+    /// Move an undecryptable config out of the way so the app starts fresh instead of failing
+    /// every read. The file is kept in case the old machine identity comes back.
+    fn set_aside(&self, error: &anyhow::Error) -> Result<()> {
+        let suffix = format!("unreadable-{}", chrono::Utc::now().format("%Y%m%d%H%M%S"));
+        let moved_to = self.config_dir.join(format!("config.json.{}", suffix));
+        log::error!(
+            "Config {:?} was encrypted with a key this machine can't derive ({}); moved it to {:?}",
+            self.config_file, error, moved_to
         );
-        
-        let mut hasher = Sha256::new();
-        hasher.update(machine_id.as_bytes());
-        hasher.update(b"mcp-switchboard-config-key");
-        let result = hasher.finalize();
-        
-        let mut key = [0u8; 32];
-        key.copy_from_slice(&result);
-        Ok(key)
+        std::fs::rename(&self.config_file, &moved_to)?;
+        // The salt goes with it, so the next save starts a key for this machine
+        if self.key_file_path().exists() {
+            std::fs::rename(self.key_file_path(), self.config_dir.join(format!("config.key.{}", suffix)))?;
+        }
+        crate::events::publish(crate::AppEvent::ConfigUnreadable { moved_to: moved_to.to_string_lossy().to_string() });
+        Ok(())
+    }
+
+    fn key_file_path(&self) -> PathBuf {
+        self.config_dir.join("config.key")
+    }
+
+    fn load_key_file(&self) -> Result<Option<KeyFile>> {
+        match std::fs::read_to_string(self.key_file_path()) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Key file left by an interrupted rotation; its salt still decrypts the config
+    fn previous_key_file_path(&self) -> PathBuf {
+        self.config_dir.join("config.key.previous")
+    }
+
+    fn new_key_file() -> KeyFile {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        KeyFile {
+            salt: general_purpose::STANDARD.encode(salt),
+            machine_fingerprint: machine_fingerprint(),
+        }
+    }
+
+    /// The salt for this config directory, created on first use
+    fn key_file(&self) -> Result<KeyFile> {
+        if let Some(key_file) = self.load_key_file()? {
+            return Ok(key_file);
+        }
+        let key_file = Self::new_key_file();
+        std::fs::create_dir_all(&self.config_dir)?;
+        std::fs::write(self.key_file_path(), serde_json::to_vec(&key_file)?)?;
+        Ok(key_file)
+    }
+
+    fn get_encryption_key(&self) -> Result<[u8; 32]> {
+        derive_key(&self.key_file()?)
+    }
+
+    /// Re-encrypt the config under a new random salt, e.g. when the old key file may have leaked
+    /// or the config was restored from another machine. Until the new config is in place the old
+    /// key file is kept as `config.key.previous`, so an interrupted rotation can still be read.
+    pub fn rotate_encryption_key(&self) -> Result<()> {
+        let mut cache = lock_config_cache()?;
+        let config = self.load_locked(&mut cache)?;
+        std::fs::create_dir_all(&self.config_dir)?;
+
+        let key_file = Self::new_key_file();
+        if self.key_file_path().exists() {
+            std::fs::copy(self.key_file_path(), self.previous_key_file_path())?;
+        }
+        let staged_key = self.config_dir.join("config.key.new");
+        std::fs::write(&staged_key, serde_json::to_vec(&key_file)?)?;
+        if let Some(config) = &config {
+            let encrypted_data = format!("{}{}", KEY_V2_PREFIX, encrypt_with(&derive_key(&key_file)?, &serde_json::to_vec(config)?)?);
+            let staged_config = self.config_dir.join("config.json.new");
+            std::fs::write(&staged_config, encrypted_data)?;
+            std::fs::rename(&staged_key, self.key_file_path())?;
+            std::fs::rename(&staged_config, &self.config_file)?;
+            if let Some(cached) = cache.get_mut(&self.config_file) {
+                cached.digest = file_digest(&self.config_file)?;
+            }
+        } else {
+            std::fs::rename(&staged_key, self.key_file_path())?;
+        }
+        if self.previous_key_file_path().exists() {
+            std::fs::remove_file(self.previous_key_file_path())?;
+        }
+        log::info!("Rotated config encryption key in {:?}", self.config_dir);
+        Ok(())
     }
 
     fn encrypt_data(&self, data: &[u8]) -> Result<String> {
         let key_bytes = self.get_encryption_key()?;
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
-        let cipher = Aes256Gcm::new(key);
-        
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let ciphertext = cipher.encrypt(&nonce, data)
-            .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
-        
-        // Combine nonce and ciphertext for storage
-        let mut combined = nonce.to_vec();
-        combined.extend_from_slice(&ciphertext);
-        
-        Ok(general_purpose::STANDARD.encode(&combined))
+        Ok(format!("{}{}", KEY_V2_PREFIX, encrypt_with(&key_bytes, data)?))
     }
 
     fn decrypt_data(&self, encrypted_data: &str) -> Result<Vec<u8>> {
-        let combined = general_purpose::STANDARD.decode(encrypted_data)?;
-        
-        if combined.len() < 12 {
-            return Err(anyhow::anyhow!("Invalid encrypted data"));
+        if let Some(encrypted_data) = encrypted_data.strip_prefix(KEY_V2_PREFIX) {
+            let key_bytes = self.get_encryption_key()?;
+            let result = decrypt_with(&key_bytes, encrypted_data);
+            if result.is_err() {
+                // A rotation stopped between writing the new key file and the new config
+                let previous = std::fs::read_to_string(self.previous_key_file_path()).ok()
+                    .and_then(|contents| serde_json::from_str::<KeyFile>(&contents).ok());
+                if let Some(previous) = previous {
+                    log::warn!("Config didn't decrypt with the current key, trying the one from before rotation");
+                    return decrypt_with(&derive_key(&previous)?, encrypted_data);
+                }
+            }
+            return result;
         }
-        
-        let (nonce_bytes, ciphertext) = combined.split_at(12);
-        let nonce = Nonce::from_slice(nonce_bytes);
-        
-        let key_bytes = self.get_encryption_key()?;
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
-        let cipher = Aes256Gcm::new(key);
-        
-        let plaintext = cipher.decrypt(nonce, ciphertext.as_ref())
-            .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))?;
+        // Written before the machine-id key; the hostname or user may have changed since
+        let mut last_error = anyhow::anyhow!("Invalid encrypted data");
+        for key_bytes in legacy_keys() {
+            match decrypt_with(&key_bytes, encrypted_data) {
+                Ok(plaintext) => return Ok(plaintext),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
 
-        Ok(plaintext)
+    /// Use `passphrase` for the portable config key. If there's a config already it must decrypt
+    /// with it; otherwise it becomes the passphrase for the config about to be created.
+    pub fn unlock_portable(&self, passphrase: String) -> Result<()> {
+        crate::portable::set_passphrase(Some(passphrase));
+        if let Ok(mut cache) = config_cache().lock() {
+            cache.remove(&self.config_file);
+        }
+        if let Err(e) = self.load_config() {
+            crate::portable::set_passphrase(None);
+            log::warn!("Portable config didn't decrypt with the passphrase given: {}", e);
+            return Err(anyhow::anyhow!(crate::tr!("portable-wrong-passphrase")));
+        }
+        Ok(())
+    }
+
+    /// Re-encrypt the portable config under `passphrase`, once unlocked with the current one
+    pub fn change_portable_passphrase(&self, passphrase: String) -> Result<()> {
+        // Loaded with the old passphrase, so the rotation below has the config in the cache
+        self.load_config()?;
+        crate::portable::set_passphrase(Some(passphrase));
+        self.rotate_encryption_key()
     }
 
     pub fn has_config(&self) -> bool {
-        // Check if we have either env var or config file
-        std::env::var("TOGETHERAI_API_KEY").is_ok() || self.config_file.exists()
+        // Check if we have either env var or config file; demo mode needs no key
+        crate::demo::active()
+            || crate::providers::ALL.iter().any(|provider| provider.key_var().and_then(environment::var).is_some())
+            || self.config_file.exists()
     }
 
     pub fn get_config_path(&self) -> &PathBuf {
         &self.config_file
     }
-}
\ No newline at end of file
+
+    /// Directory for application data (transcripts, media) that is not part of the config
+    pub fn get_data_dir(&self) -> &PathBuf {
+        &self.data_dir
+    }
+}
+
+/// The system defaults, read once. Settings that wouldn't fit the config (say, a string where a
+/// number belongs) are dropped here, so a bad defaults file can't make every config unreadable.
+fn system_defaults() -> &'static serde_json::Map<String, serde_json::Value> {
+    static DEFAULTS: OnceLock<serde_json::Map<String, serde_json::Value>> = OnceLock::new();
+    DEFAULTS.get_or_init(|| {
+        let Some(path) = system_defaults::path() else {
+            return serde_json::Map::new();
+        };
+        let mut defaults = system_defaults::read(&path);
+        defaults.retain(|key, value| {
+            let mut config = serde_json::Map::new();
+            config.insert("together_ai_api_key".to_string(), serde_json::Value::String(String::new()));
+            config.insert(key.clone(), value.clone());
+            let valid = serde_json::from_value::<AppConfig>(serde_json::Value::Object(config)).is_ok();
+            if !valid {
+                log::warn!("Ignoring invalid {} in system defaults {:?}", key, path);
+            }
+            valid
+        });
+        defaults
+    })
+}
+
+/// Built-in defaults under the system defaults, before the policy is applied
+fn unenforced_defaults() -> serde_json::Map<String, serde_json::Value> {
+    let mut config = match serde_json::to_value(AppConfig::default()) {
+        Ok(serde_json::Value::Object(config)) => config,
+        _ => serde_json::Map::new(),
+    };
+    for (key, value) in system_defaults() {
+        config.insert(key.clone(), value.clone());
+    }
+    config
+}
+
+/// A config with nothing of the user's in it yet: built-in defaults under the system defaults,
+/// with the policy applied
+fn defaults_config() -> AppConfig {
+    let mut config = unenforced_defaults();
+    enforce_policy(&mut config);
+    serde_json::from_value(serde_json::Value::Object(config)).unwrap_or_default()
+}
+
+/// Hold the settings the policy locks at their default and apply the ones it forces
+fn enforce_policy(config: &mut serde_json::Map<String, serde_json::Value>) {
+    policy::enforce(config, &unenforced_defaults(), policy::current());
+}
+
+/// Settings the system defaults file provides
+pub fn system_defaults_info() -> system_defaults::SystemDefaultsInfo {
+    system_defaults::SystemDefaultsInfo {
+        path: system_defaults::path().map(|path| path.to_string_lossy().to_string()),
+        settings: system_defaults().keys().cloned().collect(),
+    }
+}
+
+/// What the config key is derived from: the OS machine id, or the hostname where there is none
+fn machine_identity() -> String {
+    crate::machine_id::machine_id().unwrap_or_else(|| {
+        log::warn!("No machine id available, deriving the config key from the hostname");
+        platform::hostname()
+    })
+}
+
+/// The config key for `key_file`'s salt on this machine, or from the passphrase in portable mode
+fn derive_key(key_file: &KeyFile) -> Result<[u8; 32]> {
+    let salt = general_purpose::STANDARD.decode(&key_file.salt)?;
+    if crate::portable::active() {
+        return crate::portable::key(&salt);
+    }
+    // A random per-install salt and the OS machine id, which survives hostname and user renames
+    let mut hasher = Sha256::new();
+    hasher.update(&salt);
+    hasher.update(machine_identity().as_bytes());
+    hasher.update(b"mcp-switchboard-config-key-v2");
+    Ok(hasher.finalize().into())
+}
+
+fn machine_fingerprint() -> String {
+    format!("{:x}", Sha256::digest(machine_identity().as_bytes()))
+}
+
+/// Keys the original hostname and user scheme could have produced. Several user variables and
+/// both short and full hostnames are tried, since which one was set varied between launches.
+fn legacy_keys() -> Vec<[u8; 32]> {
+    let mut users: Vec<String> = ["USER", "LOGNAME", "USERNAME"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .collect();
+    users.push("unknown".to_string());
+    let hostname = platform::hostname();
+    let mut hostnames = vec![hostname.clone()];
+    if let Some((short, _)) = hostname.split_once('.') {
+        hostnames.push(short.to_string());
+    }
+
+    let mut keys = Vec::new();
+    for user in &users {
+        for hostname in &hostnames {
+            let mut hasher = Sha256::new();
+            hasher.update(format!("{}:{}", user, hostname).as_bytes());
+            hasher.update(b"mcp-switchboard-config-key");
+            let key: [u8; 32] = hasher.finalize().into();
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys
+}
+
+fn encrypt_with(key_bytes: &[u8; 32], data: &[u8]) -> Result<String> {
+    let key = Key::<Aes256Gcm>::from_slice(key_bytes);
+    let cipher = Aes256Gcm::new(key);
+
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, data)
+        .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
+
+    // Combine nonce and ciphertext for storage
+    let mut combined = nonce.to_vec();
+    combined.extend_from_slice(&ciphertext);
+
+    Ok(general_purpose::STANDARD.encode(&combined))
+}
+
+fn decrypt_with(key_bytes: &[u8; 32], encrypted_data: &str) -> Result<Vec<u8>> {
+    let combined = general_purpose::STANDARD.decode(encrypted_data)?;
+
+    if combined.len() < 12 {
+        return Err(anyhow::anyhow!("Invalid encrypted data"));
+    }
+
+    let (nonce_bytes, ciphertext) = combined.split_at(12);
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    let key = Key::<Aes256Gcm>::from_slice(key_bytes);
+    let cipher = Aes256Gcm::new(key);
+
+    cipher.decrypt(nonce, ciphertext)
+        .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager_in(dir: &Path) -> ConfigManager {
+        ConfigManager::in_dir(dir)
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("mcp-config-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn reads_see_saves_from_any_manager() {
+        let dir = temp_dir();
+        let first = manager_in(&dir);
+        let second = manager_in(&dir);
+        assert!(first.get_preferred_model().unwrap().contains("Llama"));
+
+        for model in ["model-a", "model-bb", "model-a"] {
+            first.save_preferred_model(model.to_string()).unwrap();
+            assert_eq!(first.get_preferred_model().unwrap(), model);
+            assert_eq!(second.get_preferred_model().unwrap(), model);
+        }
+        second.save_memory_enabled(true).unwrap();
+        assert!(first.get_memory_enabled().unwrap());
+        assert_eq!(first.get_preferred_model().unwrap(), "model-a");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_see_changes_made_outside_the_process() {
+        let dir = temp_dir();
+        let manager = manager_in(&dir);
+        manager.save_preferred_model("cached-model".to_string()).unwrap();
+        assert_eq!(manager.get_preferred_model().unwrap(), "cached-model");
+
+        // Same length and written straight away, so size and modification time may not change
+        let external = AppConfig { preferred_model: Some("edited-model".to_string()), ..AppConfig::default() };
+        let encrypted = manager.encrypt_data(&serde_json::to_vec(&external).unwrap()).unwrap();
+        std::fs::write(&manager.config_file, encrypted).unwrap();
+        drop_changed_configs();
+        assert_eq!(manager.get_preferred_model().unwrap(), "edited-model");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn deleting_the_file_is_noticed() {
+        let dir = temp_dir();
+        let manager = manager_in(&dir);
+        manager.save_template_repositories(vec!["https://example.com/templates.git".to_string()]).unwrap();
+        assert_eq!(manager.get_template_repositories().unwrap().len(), 1);
+
+        std::fs::remove_file(&manager.config_file).unwrap();
+        drop_changed_configs();
+        assert!(manager.get_template_repositories().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn saves_keep_edits_the_watcher_has_not_noticed_yet() {
+        let dir = temp_dir();
+        let manager = manager_in(&dir);
+        manager.save_preferred_model("ours".to_string()).unwrap();
+
+        let external = AppConfig { preferred_model: Some("theirs".to_string()), ..AppConfig::default() };
+        let encrypted = manager.encrypt_data(&serde_json::to_vec(&external).unwrap()).unwrap();
+        std::fs::write(&manager.config_file, encrypted).unwrap();
+        manager.save_memory_enabled(true).unwrap();
+        assert_eq!(manager.get_preferred_model().unwrap(), "theirs");
+        assert!(manager.get_memory_enabled().unwrap());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn concurrent_saves_in_the_process_are_all_kept() {
+        let dir = temp_dir();
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let dir = dir.clone();
+                std::thread::spawn(move || {
+                    let manager = manager_in(&dir);
+                    for n in 0..10 {
+                        let plugin = format!("plugin-{}-{}", writer, n);
+                        manager.save_plugin_grant(plugin.clone(), Some(vec!["network".to_string()])).unwrap();
+                        // No stale read straight after a save
+                        assert!(manager.get_plugin_grants().unwrap().contains_key(&plugin));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(manager_in(&dir).get_plugin_grants().unwrap().len(), 40);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn configs_from_the_hostname_key_are_re_encrypted() {
+        let dir = temp_dir();
+        let manager = manager_in(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy = AppConfig { preferred_model: Some("legacy-model".to_string()), ..AppConfig::default() };
+        let encrypted = encrypt_with(&legacy_keys()[0], &serde_json::to_vec(&legacy).unwrap()).unwrap();
+        std::fs::write(&manager.config_file, encrypted).unwrap();
+
+        assert_eq!(manager.get_preferred_model().unwrap(), "legacy-model");
+        assert!(std::fs::read_to_string(&manager.config_file).unwrap().starts_with(KEY_V2_PREFIX));
+        assert!(manager.key_file_path().exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn configs_from_another_machine_are_set_aside() {
+        let dir = temp_dir();
+        let manager = manager_in(&dir);
+        manager.save_preferred_model("other-machine".to_string()).unwrap();
+        let mut key_file = manager.load_key_file().unwrap().unwrap();
+        key_file.machine_fingerprint = "another machine".to_string();
+        key_file.salt = general_purpose::STANDARD.encode([7u8; 32]);
+        std::fs::write(manager.key_file_path(), serde_json::to_vec(&key_file).unwrap()).unwrap();
+        config_cache().lock().unwrap().remove(&manager.config_file);
+
+        assert!(manager.get_preferred_model().unwrap().contains("Llama"));
+        assert!(!manager.config_file.exists());
+        let set_aside = std::fs::read_dir(&dir).unwrap()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("config.json.unreadable-"));
+        assert!(set_aside);
+        assert!(!manager.key_file_path().exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotating_the_key_keeps_the_config_readable() {
+        let dir = temp_dir();
+        let manager = manager_in(&dir);
+        manager.save_preferred_model("rotated-model".to_string()).unwrap();
+        let old_salt = manager.load_key_file().unwrap().unwrap().salt;
+        let old_file = std::fs::read_to_string(&manager.config_file).unwrap();
+
+        manager.rotate_encryption_key().unwrap();
+        assert_ne!(manager.load_key_file().unwrap().unwrap().salt, old_salt);
+        assert_ne!(std::fs::read_to_string(&manager.config_file).unwrap(), old_file);
+        assert!(!manager.previous_key_file_path().exists());
+        config_cache().lock().unwrap().remove(&manager.config_file);
+        assert_eq!(manager.get_preferred_model().unwrap(), "rotated-model");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
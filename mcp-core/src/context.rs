@@ -0,0 +1,168 @@
+use std::time::Duration;
+use crate::cache::Cache;
+use crate::history::HistoryMessage;
+
+/// Tokens of conversation history and prompt sent with each request
+pub const DEFAULT_CONTEXT_BUDGET_TOKENS: usize = 6_000;
+
+/// Assumed context length for models the provider hasn't told us about
+pub const DEFAULT_MODEL_CONTEXT_TOKENS: usize = 8_192;
+
+/// Room always left for the answer when sizing history and max_tokens
+const MIN_RESPONSE_TOKENS: usize = 256;
+
+/// Allowance for chat formatting tokens around each message
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Smallest budget the overflow retry will shrink to
+const MIN_CONTEXT_BUDGET_TOKENS: usize = 512;
+
+/// A prompt and the history that fit in a token budget
+#[derive(Clone)]
+pub struct ContextWindow {
+    /// (role, content) pairs, oldest first, ending with the prompt. A persona's system prompt,
+    /// if any, comes first.
+    pub messages: Vec<(String, String)>,
+    pub dropped_messages: u32,
+    pub dropped_chars: u32,
+    pub budget_tokens: usize,
+}
+
+impl ContextWindow {
+    /// Put a system prompt in front of the history
+    pub fn with_system(mut self, system: Option<&str>) -> Self {
+        if let Some(system) = system {
+            self.messages.insert(0, ("system".to_string(), system.to_string()));
+        }
+        self
+    }
+
+    pub fn system(&self) -> Option<&str> {
+        self.messages.first()
+            .filter(|(role, _)| role == "system")
+            .map(|(_, content)| content.as_str())
+    }
+}
+
+/// Context lengths by model, refreshed whenever the model list is fetched
+static CONTEXT_LENGTHS: Cache<usize> = Cache::new(Duration::from_secs(24 * 3600), 4096);
+
+/// Record a model's context length as reported by the provider's model list
+pub fn remember_context_length(model: &str, tokens: usize) {
+    CONTEXT_LENGTHS.insert(model, tokens);
+}
+
+pub fn model_context_length(model: &str) -> usize {
+    CONTEXT_LENGTHS.get(model).unwrap_or(DEFAULT_MODEL_CONTEXT_TOKENS)
+}
+
+pub fn forget_context_lengths() -> usize {
+    CONTEXT_LENGTHS.clear()
+}
+
+pub fn prune_context_lengths() -> usize {
+    CONTEXT_LENGTHS.prune_expired()
+}
+
+pub fn context_lengths_usage() -> (u32, u64) {
+    CONTEXT_LENGTHS.usage(|_| std::mem::size_of::<usize>() as u64)
+}
+
+/// History budget for a model: the default, shrunk for small models so an answer still fits
+pub fn context_budget(model_limit: usize) -> usize {
+    DEFAULT_CONTEXT_BUDGET_TOKENS.min(model_limit.saturating_sub(MIN_RESPONSE_TOKENS * 4))
+        .max(MIN_CONTEXT_BUDGET_TOKENS)
+}
+
+/// max_tokens for a request: whatever the window leaves of the model's context.
+/// Token counts are estimates, so a tenth of the prompt is held back as a margin.
+pub fn max_response_tokens(window: &ContextWindow, model_limit: usize) -> u32 {
+    let prompt: usize = window.messages.iter()
+        .map(|(_, content)| estimate_tokens(content) + MESSAGE_OVERHEAD_TOKENS)
+        .sum();
+    let remaining = model_limit.saturating_sub(prompt + prompt / 10);
+    remaining.max(MIN_RESPONSE_TOKENS) as u32
+}
+
+/// Rough token count (about four characters per token for English text)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Keep the most recent history that fits alongside the prompt. The prompt is always sent;
+/// if it alone is over budget its middle is cut out.
+pub fn build_context(history: &[HistoryMessage], prompt: &str, budget_tokens: usize) -> ContextWindow {
+    let mut dropped_messages = 0;
+    let mut dropped_chars = 0;
+
+    let prompt_tokens = estimate_tokens(prompt);
+    let prompt = if prompt_tokens > budget_tokens {
+        let truncated = truncate_middle(prompt, budget_tokens * 4);
+        dropped_chars += (prompt.chars().count() - truncated.chars().count()) as u32;
+        truncated
+    } else {
+        prompt.to_string()
+    };
+
+    let mut remaining = budget_tokens.saturating_sub(estimate_tokens(&prompt));
+    let mut kept = Vec::new();
+    let usable = history.iter().filter(|m| !m.content.is_empty());
+    for message in usable.rev() {
+        let tokens = estimate_tokens(&message.content);
+        if tokens <= remaining && dropped_messages == 0 {
+            remaining -= tokens;
+            kept.push((message.role.clone(), message.content.clone()));
+        } else {
+            // Once something is dropped, drop everything older so the kept history stays contiguous
+            dropped_messages += 1;
+            dropped_chars += message.content.chars().count() as u32;
+        }
+    }
+    kept.reverse();
+    kept.push(("user".to_string(), prompt));
+
+    ContextWindow {
+        messages: kept,
+        dropped_messages,
+        dropped_chars,
+        budget_tokens,
+    }
+}
+
+/// Budget for the retry after the provider rejected `window` as too long
+pub fn reduced_budget(window: &ContextWindow) -> usize {
+    let used: usize = window.messages.iter().map(|(_, content)| estimate_tokens(content)).sum();
+    (used.min(window.budget_tokens) / 2).max(MIN_CONTEXT_BUDGET_TOKENS)
+}
+
+/// Whether a provider error means the request exceeded the model's context length
+pub fn is_context_overflow(error: &str) -> bool {
+    let error = error.to_lowercase();
+    [
+        "context length",
+        "context_length_exceeded",
+        "maximum context",
+        "context window",
+        "too many tokens",
+        "prompt is too long",
+        "input is too long",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let omitted = chars.len().saturating_sub(max_chars);
+    if omitted == 0 {
+        return text.to_string();
+    }
+    let head = max_chars / 2;
+    let tail = max_chars - head;
+    format!(
+        "{}\n[... {} characters omitted ...]\n{}",
+        chars[..head].iter().collect::<String>(),
+        omitted,
+        chars[chars.len() - tail..].iter().collect::<String>()
+    )
+}
@@ -0,0 +1,210 @@
+//! Conversations locked behind the OS's own authentication, so a sensitive thread isn't readable
+//! by whoever sits down at an unlocked machine. The prompt is the system's and asks only that the
+//! user prove they're present, never for an administrator: Touch ID or the login password on
+//! macOS, Windows Hello on Windows, the user's own password (or fingerprint) through polkit on
+//! Linux.
+//!
+//! Which conversations are locked is kept in history. Unlocking lasts for this session only and
+//! lapses once a conversation goes unused for `QUICK_UNLOCK_WINDOW`. Within that window of the
+//! last authentication other locked conversations open without asking again.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use anyhow::Result;
+use crate::tr;
+
+pub const QUICK_UNLOCK_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct LockedConversation {
+    pub conversation_id: String,
+    pub locked_at: String,
+    /// Readable now, without authenticating again
+    pub unlocked: bool,
+}
+
+struct Session {
+    /// Unlocked conversations, by when they were last used
+    open: HashMap<String, Instant>,
+    last_authenticated: Option<Instant>,
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+fn with_session<T>(f: impl FnOnce(&mut Session) -> T) -> T {
+    let mut session = SESSION.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(session.get_or_insert_with(|| Session { open: HashMap::new(), last_authenticated: None }))
+}
+
+fn recent(at: Instant, now: Instant) -> bool {
+    now.saturating_duration_since(at) < QUICK_UNLOCK_WINDOW
+}
+
+/// Whether a locked conversation may be read; each read keeps it unlocked for another window
+pub fn is_open(conversation_id: &str) -> bool {
+    let now = Instant::now();
+    with_session(|session| {
+        session.open.retain(|_, used| recent(*used, now));
+        match session.open.get_mut(conversation_id) {
+            Some(used) => {
+                *used = now;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Whether the user authenticated recently enough to open another conversation without asking
+pub fn recently_authenticated() -> bool {
+    let now = Instant::now();
+    with_session(|session| session.last_authenticated.is_some_and(|at| recent(at, now)))
+}
+
+/// Record a successful authentication and unlock `conversation_id` for this session
+pub fn open(conversation_id: &str, authenticated: bool) {
+    let now = Instant::now();
+    with_session(|session| {
+        if authenticated {
+            session.last_authenticated = Some(now);
+        }
+        session.open.insert(conversation_id.to_string(), now);
+    })
+}
+
+/// Lock every conversation again and forget the last authentication
+pub fn close_all() {
+    with_session(|session| {
+        session.open.clear();
+        session.last_authenticated = None;
+    })
+}
+
+pub fn close(conversation_id: &str) {
+    with_session(|session| {
+        session.open.remove(conversation_id);
+    })
+}
+
+/// Ask the OS to authenticate the user, explaining why with `reason`. Blocks until the system
+/// dialog is dismissed.
+pub fn authenticate(reason: &str) -> Result<()> {
+    match run_authentication(reason) {
+        Ok(true) => {
+            log::info!("OS authentication succeeded");
+            Ok(())
+        }
+        Ok(false) => {
+            log::warn!("OS authentication failed or was cancelled");
+            Err(anyhow::anyhow!(tr!("os-auth-failed")))
+        }
+        Err(e) => {
+            log::error!("OS authentication unavailable: {}", e);
+            Err(anyhow::anyhow!(tr!("os-auth-unavailable")))
+        }
+    }
+}
+
+/// LocalAuthentication through JavaScript for Automation. The owner-authentication policy offers
+/// Touch ID or the user's own login password, so it works for accounts that aren't admins.
+#[cfg(target_os = "macos")]
+const LOCAL_AUTHENTICATION_SCRIPT: &str = r#"
+ObjC.import('LocalAuthentication');
+function run(argv) {
+    const ownerAuthentication = 2;
+    const context = $.LAContext.alloc.init;
+    if (!context.canEvaluatePolicyError(ownerAuthentication, null)) {
+        return 'unavailable';
+    }
+    let result = null;
+    context.evaluatePolicyLocalizedReasonReply(ownerAuthentication, argv[0], (success) => {
+        result = success ? 'verified' : 'failed';
+    });
+    while (result === null) {
+        $.NSRunLoop.currentRunLoop.runUntilDate($.NSDate.dateWithTimeIntervalSinceNow(0.1));
+    }
+    return result;
+}
+"#;
+
+#[cfg(target_os = "macos")]
+fn run_authentication(reason: &str) -> std::io::Result<bool> {
+    // The reason goes in as an argument, so it needs no quoting
+    let output = std::process::Command::new("osascript")
+        .args(["-l", "JavaScript", "-e", LOCAL_AUTHENTICATION_SCRIPT, reason])
+        .output()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "verified" => Ok(true),
+        "failed" => Ok(false),
+        _ => Err(std::io::Error::other(format!("LocalAuthentication: {}", String::from_utf8_lossy(&output.stderr).trim()))),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn run_authentication(reason: &str) -> std::io::Result<bool> {
+    // Windows Hello through the WinRT UserConsentVerifier, awaited from PowerShell
+    let script = format!(
+        "Add-Type -AssemblyName System.Runtime.WindowsRuntime; \
+         $asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {{ $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' }} | Select-Object -First 1; \
+         $verifier = [Windows.Security.Credentials.UI.UserConsentVerifier,Windows.Security.Credentials.UI,ContentType=WindowsRuntime]; \
+         $task = $asTask.MakeGenericMethod([Windows.Security.Credentials.UI.UserConsentVerificationResult]).Invoke($null, @($verifier::RequestVerificationAsync('{}'))); \
+         $task.Wait(-1) | Out-Null; \
+         if ($task.Result -eq 'Verified') {{ exit 0 }} else {{ exit 1 }}",
+        reason.replace('\'', "''"),
+    );
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()?;
+    Ok(status.success())
+}
+
+/// Installed with the Linux packages from `polkit/`; it asks for the user's own password
+/// (`auth_self`) rather than an administrator's
+#[cfg(target_os = "linux")]
+const POLKIT_ACTION: &str = "com.mcpswitchboard.ui.unlock-conversation";
+
+#[cfg(target_os = "linux")]
+fn run_authentication(_reason: &str) -> std::io::Result<bool> {
+    // The session's polkit agent shows the prompt, with the message from the action
+    let output = std::process::Command::new("pkcheck")
+        .args(["--action-id", POLKIT_ACTION, "--process", &std::process::id().to_string(), "--allow-user-interaction"])
+        .output()?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        // Not authorized, or the prompt was dismissed
+        Some(1) | Some(2) => Ok(false),
+        // Usually the action isn't installed or there's no agent
+        _ => Err(std::io::Error::other(format!("pkcheck: {}", String::from_utf8_lossy(&output.stderr).trim()))),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn run_authentication(_reason: &str) -> std::io::Result<bool> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "no OS authentication on this platform"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlocks_last_until_closed() {
+        let id = uuid::Uuid::new_v4().to_string();
+        assert!(!is_open(&id));
+        open(&id, false);
+        assert!(is_open(&id));
+        close(&id);
+        assert!(!is_open(&id));
+    }
+
+    #[test]
+    fn unlocks_lapse_after_the_window() {
+        let now = Instant::now();
+        assert!(recent(now, now + QUICK_UNLOCK_WINDOW / 2));
+        assert!(!recent(now, now + QUICK_UNLOCK_WINDOW));
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::cache::Cache;
+
+/// Tokens assumed per answer when estimating cost; the real length isn't known up front
+pub const ASSUMED_RESPONSE_TOKENS: u32 = 500;
+
+/// Provider prices in USD per million tokens
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Up-front estimate for answering one message with the current generation settings
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct CostEstimate {
+    pub requests: u32,
+    pub prompt_tokens: u32,
+    /// Assumes ASSUMED_RESPONSE_TOKENS per request
+    pub completion_tokens: u32,
+    /// None when the provider hasn't reported prices for every model involved
+    pub estimated_usd: Option<f64>,
+}
+
+/// One request that a generation mode will make
+pub struct PlannedRequest {
+    pub model: String,
+    pub prompt_tokens: u32,
+}
+
+/// Tokens a provider reported one request used
+#[derive(Clone, Debug, PartialEq)]
+pub struct Usage {
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// What an answer was estimated to cost and, once the provider reported its usage, what it did
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct MessageCost {
+    pub message_id: String,
+    pub model: String,
+    /// As the provider reported them, summed over every request; None when it didn't
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    pub estimated_usd: Option<f64>,
+    /// None when usage or a price is missing for any request
+    pub actual_usd: Option<f64>,
+    pub pricing: Option<ModelPricing>,
+}
+
+/// List prices by qualified model id prefix, for providers whose model lists carry no prices.
+/// The longest matching prefix wins, so dated and suffixed ids take their family's price.
+const LIST_PRICES: [(&str, ModelPricing); 33] = [
+    ("openai/gpt-4o", ModelPricing { input: 2.5, output: 10.0 }),
+    ("openai/gpt-4o-mini", ModelPricing { input: 0.15, output: 0.6 }),
+    ("openai/gpt-4.1", ModelPricing { input: 2.0, output: 8.0 }),
+    ("openai/gpt-4.1-mini", ModelPricing { input: 0.4, output: 1.6 }),
+    ("openai/gpt-4.1-nano", ModelPricing { input: 0.1, output: 0.4 }),
+    ("openai/gpt-5", ModelPricing { input: 1.25, output: 10.0 }),
+    ("openai/gpt-5-mini", ModelPricing { input: 0.25, output: 2.0 }),
+    ("openai/gpt-5-nano", ModelPricing { input: 0.05, output: 0.4 }),
+    ("openai/gpt-3.5-turbo", ModelPricing { input: 0.5, output: 1.5 }),
+    ("openai/o1", ModelPricing { input: 15.0, output: 60.0 }),
+    ("openai/o1-mini", ModelPricing { input: 1.1, output: 4.4 }),
+    ("openai/o3", ModelPricing { input: 2.0, output: 8.0 }),
+    ("openai/o3-mini", ModelPricing { input: 1.1, output: 4.4 }),
+    ("openai/o4-mini", ModelPricing { input: 1.1, output: 4.4 }),
+    ("anthropic/claude-opus-4", ModelPricing { input: 15.0, output: 75.0 }),
+    ("anthropic/claude-opus-4-5", ModelPricing { input: 5.0, output: 25.0 }),
+    ("anthropic/claude-sonnet-4", ModelPricing { input: 3.0, output: 15.0 }),
+    ("anthropic/claude-haiku-4-5", ModelPricing { input: 1.0, output: 5.0 }),
+    ("anthropic/claude-3-7-sonnet", ModelPricing { input: 3.0, output: 15.0 }),
+    ("anthropic/claude-3-5-sonnet", ModelPricing { input: 3.0, output: 15.0 }),
+    ("anthropic/claude-3-5-haiku", ModelPricing { input: 0.8, output: 4.0 }),
+    ("anthropic/claude-3-haiku", ModelPricing { input: 0.25, output: 1.25 }),
+    ("anthropic/claude-3-opus", ModelPricing { input: 15.0, output: 75.0 }),
+    ("groq/llama-3.3-70b-versatile", ModelPricing { input: 0.59, output: 0.79 }),
+    ("groq/llama-3.1-8b-instant", ModelPricing { input: 0.05, output: 0.08 }),
+    ("groq/meta-llama/llama-4-scout", ModelPricing { input: 0.11, output: 0.34 }),
+    ("groq/meta-llama/llama-4-maverick", ModelPricing { input: 0.2, output: 0.6 }),
+    ("groq/openai/gpt-oss-120b", ModelPricing { input: 0.15, output: 0.75 }),
+    ("groq/openai/gpt-oss-20b", ModelPricing { input: 0.1, output: 0.5 }),
+    ("groq/qwen/qwen3-32b", ModelPricing { input: 0.29, output: 0.59 }),
+    ("groq/gemma2-9b-it", ModelPricing { input: 0.2, output: 0.2 }),
+    ("groq/moonshotai/kimi-k2-instruct", ModelPricing { input: 1.0, output: 3.0 }),
+    // Local models cost nothing per token
+    ("ollama/", ModelPricing { input: 0.0, output: 0.0 }),
+];
+
+/// Prices by model, refreshed whenever the model list is fetched
+static PRICES: Cache<ModelPricing> = Cache::new(Duration::from_secs(24 * 3600), 4096);
+
+/// Record a model's prices as reported by the provider's model list
+pub fn remember_pricing(model: &str, pricing: ModelPricing) {
+    PRICES.insert(model, pricing);
+}
+
+/// The price the provider's model list reported for `model`, or else its list price
+pub fn model_pricing(model: &str) -> Option<ModelPricing> {
+    PRICES.get(model).or_else(|| list_pricing(model))
+}
+
+/// The list price of `model`, a qualified id, by the longest LIST_PRICES prefix it starts with
+pub fn list_pricing(model: &str) -> Option<ModelPricing> {
+    LIST_PRICES.iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|&(_, pricing)| pricing)
+}
+
+pub fn forget_pricing() -> usize {
+    PRICES.clear()
+}
+
+pub fn prune_pricing() -> usize {
+    PRICES.prune_expired()
+}
+
+pub fn pricing_usage() -> (u32, u64) {
+    PRICES.usage(|_| std::mem::size_of::<ModelPricing>() as u64)
+}
+
+/// Price of `requests` if each produced `completion_tokens`; None when a price is missing
+fn price(requests: &[PlannedRequest], completion_tokens: u32) -> Option<f64> {
+    let mut usd = Some(0.0);
+    for request in requests {
+        usd = match (usd, model_pricing(&request.model)) {
+            (Some(total), Some(pricing)) => Some(
+                total
+                    + request.prompt_tokens as f64 * pricing.input / 1_000_000.0
+                    + completion_tokens as f64 * pricing.output / 1_000_000.0,
+            ),
+            _ => None,
+        };
+    }
+    usd
+}
+
+pub fn estimate(requests: &[PlannedRequest]) -> CostEstimate {
+    CostEstimate {
+        requests: requests.len() as u32,
+        prompt_tokens: requests.iter().map(|r| r.prompt_tokens).sum(),
+        completion_tokens: ASSUMED_RESPONSE_TOKENS * requests.len() as u32,
+        estimated_usd: price(requests, ASSUMED_RESPONSE_TOKENS),
+    }
+}
+
+/// What a finished answer cost, counting its length against every request that went into it
+pub fn spent(requests: &[PlannedRequest], completion_tokens: u32) -> Option<f64> {
+    price(requests, completion_tokens)
+}
+
+/// What the requests behind `usage` cost at each model's price; None when a price is missing
+pub fn charged(usage: &[Usage]) -> Option<f64> {
+    usage.iter().try_fold(0.0, |total, usage| {
+        let pricing = model_pricing(&usage.model)?;
+        Some(total
+            + usage.prompt_tokens as f64 * pricing.input / 1_000_000.0
+            + usage.completion_tokens as f64 * pricing.output / 1_000_000.0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_list_prefix_wins() {
+        assert_eq!(list_pricing("openai/gpt-4o-mini-2024-07-18"), Some(ModelPricing { input: 0.15, output: 0.6 }));
+        assert_eq!(list_pricing("openai/gpt-4o-2024-11-20"), Some(ModelPricing { input: 2.5, output: 10.0 }));
+        assert_eq!(list_pricing("anthropic/claude-opus-4-5-20251101").map(|p| p.input), Some(5.0));
+        assert_eq!(list_pricing("anthropic/claude-opus-4-1-20250805").map(|p| p.input), Some(15.0));
+        assert_eq!(list_pricing("ollama/llama3.2:latest").map(|p| p.output), Some(0.0));
+        assert_eq!(list_pricing("meta-llama/Llama-3.3-70B-Instruct-Turbo"), None);
+    }
+
+    #[test]
+    fn reported_usage_is_charged_per_model() {
+        remember_pricing("test/priced-model", ModelPricing { input: 1.0, output: 2.0 });
+        let usage = |model: &str| Usage { model: model.to_string(), prompt_tokens: 1_000_000, completion_tokens: 500_000 };
+        assert_eq!(charged(&[usage("test/priced-model"), usage("ollama/llama3.2")]), Some(2.0));
+        assert_eq!(charged(&[usage("test/priced-model"), usage("test/unpriced-model")]), None);
+        assert_eq!(charged(&[]), Some(0.0));
+    }
+}
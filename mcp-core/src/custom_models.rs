@@ -0,0 +1,136 @@
+//! Models that don't appear in Together.ai's public model list: the user's fine-tuned models and
+//! dedicated endpoints. They're registered by hand, listed in the model picker alongside the
+//! public models, and can be health-checked, since a dedicated endpoint may be stopped or still
+//! starting.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::tr;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum CustomModelKind {
+    /// A model fine-tuned on the user's account, served from the shared API
+    FineTuned,
+    /// A model on hardware reserved for the user
+    DedicatedEndpoint,
+}
+
+/// The result of the last health check
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ModelHealth {
+    pub healthy: bool,
+    pub latency_ms: u32,
+    pub error: Option<String>,
+    pub checked_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct CustomModel {
+    /// The model name requests use, e.g. `user/Meta-Llama-3-8B-ft-abc123`
+    pub id: String,
+    pub display_name: String,
+    pub kind: CustomModelKind,
+    /// An OpenAI-compatible API base for endpoints not served from the shared API; None uses
+    /// Together's
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub context_length: Option<u32>,
+    #[serde(default)]
+    pub last_health: Option<ModelHealth>,
+}
+
+/// `model` trimmed, or a localized error if it has no id or an unusable base URL
+pub fn validate(mut model: CustomModel) -> Result<CustomModel, String> {
+    model.id = model.id.trim().to_string();
+    model.display_name = model.display_name.trim().to_string();
+    model.base_url = model.base_url
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty());
+    if model.id.is_empty() {
+        return Err(tr!("custom-model-id-missing"));
+    }
+    if let Some(url) = &model.base_url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(tr!("custom-model-url-invalid", url = url.clone()));
+        }
+    }
+    if model.display_name.is_empty() {
+        model.display_name = model.id.clone();
+    }
+    Ok(model)
+}
+
+/// The listed models followed by the custom ones not among them
+pub fn merge(mut listed: Vec<ModelInfo>, custom: &[CustomModel]) -> Vec<ModelInfo> {
+    for model in custom {
+        if listed.iter().any(|info| info.id == model.id) {
+            continue;
+        }
+        listed.push(ModelInfo {
+            id: model.id.clone(),
+            display_name: model.display_name.clone(),
+            organization: match model.kind {
+                CustomModelKind::FineTuned => "Fine-tuned".to_string(),
+                CustomModelKind::DedicatedEndpoint => "Dedicated endpoint".to_string(),
+            },
+            context_length: model.context_length,
+            pricing: None,
+        });
+    }
+    listed
+}
+
+/// The API base requests for `model` go to
+pub fn api_base<'a>(custom: &'a [CustomModel], model: &str, default: &'a str) -> &'a str {
+    custom.iter()
+        .find(|candidate| candidate.id == model)
+        .and_then(|candidate| candidate.base_url.as_deref())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(id: &str, base_url: Option<&str>) -> CustomModel {
+        CustomModel {
+            id: id.to_string(),
+            display_name: String::new(),
+            kind: CustomModelKind::DedicatedEndpoint,
+            base_url: base_url.map(str::to_string),
+            context_length: Some(8192),
+            last_health: None,
+        }
+    }
+
+    #[test]
+    fn custom_models_join_the_list_once_with_their_own_base() {
+        let listed = vec![ModelInfo {
+            id: "meta-llama/Llama-3-8b-chat-hf".to_string(),
+            display_name: "Llama 3 8B".to_string(),
+            organization: "Meta".to_string(),
+            context_length: Some(8192),
+            pricing: None,
+        }];
+        let registered = vec![
+            validate(custom(" me/llama-ft ", Some("https://me.together.ai/v1/"))).unwrap(),
+            validate(custom("meta-llama/Llama-3-8b-chat-hf", None)).unwrap(),
+        ];
+        assert_eq!(registered[0].display_name, "me/llama-ft");
+
+        let merged = merge(listed, &registered);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[1].organization, "Dedicated endpoint");
+
+        assert_eq!(api_base(&registered, "me/llama-ft", "https://api.together.xyz/v1"), "https://me.together.ai/v1");
+        assert_eq!(api_base(&registered, "other", "https://api.together.xyz/v1"), "https://api.together.xyz/v1");
+
+        assert!(validate(custom(" ", None)).is_err());
+        assert!(validate(custom("me/x", Some("ftp://host"))).is_err());
+    }
+}
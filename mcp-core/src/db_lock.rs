@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::events::{self, AppEvent};
+use crate::tr;
+
+/// Held exclusively by the instance that writes history; others open the database read-only
+const LOCK_FILE: &str = "history.lock";
+
+/// Writes made while read-only, one JSON line each, so they survive this instance exiting
+/// before it gets the lock. Whichever instance takes the lock next replays them.
+const QUEUE_FILE: &str = "history.queue.jsonl";
+
+/// The queue file while its writes are being replayed; left behind if replay is interrupted
+const REPLAYING_FILE: &str = "history.queue.replaying.jsonl";
+
+/// Most writes kept while read-only; further writes fail until the queue is replayed
+const MAX_QUEUED_WRITES: usize = 10_000;
+
+/// A write made while another instance held the database, replayed once this one gets the lock
+pub struct QueuedWrite {
+    pub sql: String,
+    pub params: Vec<rusqlite::types::Value>,
+}
+
+/// A queue file line
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    sql: String,
+    params: Vec<JournalValue>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum JournalValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<QueuedWrite> for JournalEntry {
+    fn from(write: QueuedWrite) -> Self {
+        use rusqlite::types::Value;
+        let params = write.params.into_iter()
+            .map(|value| match value {
+                Value::Null => JournalValue::Null,
+                Value::Integer(n) => JournalValue::Integer(n),
+                Value::Real(n) => JournalValue::Real(n),
+                Value::Text(text) => JournalValue::Text(text),
+                Value::Blob(blob) => JournalValue::Blob(blob),
+            })
+            .collect();
+        JournalEntry { sql: write.sql, params }
+    }
+}
+
+impl From<JournalEntry> for QueuedWrite {
+    fn from(entry: JournalEntry) -> Self {
+        use rusqlite::types::Value;
+        let params = entry.params.into_iter()
+            .map(|value| match value {
+                JournalValue::Null => Value::Null,
+                JournalValue::Integer(n) => Value::Integer(n),
+                JournalValue::Real(n) => Value::Real(n),
+                JournalValue::Text(text) => Value::Text(text),
+                JournalValue::Blob(blob) => Value::Blob(blob),
+            })
+            .collect();
+        QueuedWrite { sql: entry.sql, params }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct DatabaseStatus {
+    /// Another instance holds the history database
+    pub read_only: bool,
+    /// Writes waiting to be replayed once this instance gets the database back
+    pub queued_writes: u32,
+}
+
+#[derive(Default)]
+struct DataDirLock {
+    /// Kept open for the life of the process; closing it would release the lock
+    held: Option<File>,
+    /// Whether DatabaseLocked has been published since the lock was last held
+    reported: bool,
+}
+
+fn locks() -> &'static Mutex<HashMap<PathBuf, DataDirLock>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, DataDirLock>>> = OnceLock::new();
+    LOCKS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Try to become the instance that writes history in `data_dir`. Returns the writes queued while
+/// read-only, by this or any other instance, or None if another process holds the lock. Call
+/// [`replayed`] once they've been applied.
+pub fn acquire(data_dir: &Path) -> Result<Option<Vec<QueuedWrite>>> {
+    let mut locks = locks().lock().map_err(|_| anyhow::anyhow!("Database lock state is poisoned"))?;
+    let lock = locks.entry(data_dir.to_path_buf()).or_default();
+    if lock.held.is_some() {
+        return Ok(Some(take_queue(data_dir)?));
+    }
+
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(data_dir.join(LOCK_FILE))?;
+    match file.try_lock() {
+        Ok(()) => {
+            lock.held = Some(file);
+            let queued = take_queue(data_dir)?;
+            if lock.reported {
+                lock.reported = false;
+                log::info!("History database is writable again; replaying {} queued writes", queued.len());
+                events::publish(AppEvent::DatabaseUnlocked { replayed_writes: queued.len() as u32 });
+            }
+            Ok(Some(queued))
+        }
+        Err(TryLockError::WouldBlock) => {
+            if !lock.reported {
+                lock.reported = true;
+                log::warn!("History database is in use by another instance; opening read-only");
+                events::publish(AppEvent::DatabaseLocked);
+            }
+            Ok(None)
+        }
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Move the queue file aside and read it, along with any replay that was interrupted
+fn take_queue(data_dir: &Path) -> Result<Vec<QueuedWrite>> {
+    let queue = data_dir.join(QUEUE_FILE);
+    let replaying = data_dir.join(REPLAYING_FILE);
+    let mut writes = read_queue(&replaying)?;
+    if queue.exists() {
+        if writes.is_empty() {
+            std::fs::rename(&queue, &replaying)?;
+        } else {
+            let mut file = OpenOptions::new().append(true).open(&replaying)?;
+            file.write_all(&std::fs::read(&queue)?)?;
+            file.sync_data()?;
+            std::fs::remove_file(&queue)?;
+        }
+        writes = read_queue(&replaying)?;
+    }
+    Ok(writes)
+}
+
+fn read_queue(path: &Path) -> Result<Vec<QueuedWrite>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut writes = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<JournalEntry>(&line) {
+            Ok(entry) => writes.push(entry.into()),
+            Err(e) => log::warn!("Skipping unreadable queued history write: {}", e),
+        }
+    }
+    Ok(writes)
+}
+
+/// The writes returned by [`acquire`] have been applied; forget them
+pub fn replayed(data_dir: &Path) -> Result<()> {
+    match std::fs::remove_file(data_dir.join(REPLAYING_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Hold a write until an instance gets the lock
+pub fn queue(data_dir: &Path, write: QueuedWrite) -> Result<()> {
+    let _locks = locks().lock().map_err(|_| anyhow::anyhow!("Database lock state is poisoned"))?;
+    let path = data_dir.join(QUEUE_FILE);
+    if count_lines(&path) >= MAX_QUEUED_WRITES {
+        return Err(anyhow::anyhow!(tr!("history-queue-full")));
+    }
+    let mut line = serde_json::to_string(&JournalEntry::from(write))?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
+
+fn count_lines(path: &Path) -> usize {
+    File::open(path)
+        .map(|file| BufReader::new(file).lines().count())
+        .unwrap_or(0)
+}
+
+/// Writes held across every data directory this instance has opened and their size on disk
+pub fn queue_usage() -> (u32, u64) {
+    let Ok(locks) = locks().lock() else { return (0, 0) };
+    locks.keys()
+        .map(|data_dir| data_dir.join(QUEUE_FILE))
+        .fold((0, 0), |(count, bytes), path| {
+            let size = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            (count + count_lines(&path) as u32, bytes + size)
+        })
+}
+
+pub fn status(data_dir: &Path) -> Result<DatabaseStatus> {
+    let locks = locks().lock().map_err(|_| anyhow::anyhow!("Database lock state is poisoned"))?;
+    let read_only = locks.get(data_dir).is_some_and(|lock| lock.held.is_none());
+    Ok(DatabaseStatus { read_only, queued_writes: count_lines(&data_dir.join(QUEUE_FILE)) as u32 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::types::Value;
+
+    #[test]
+    fn queued_writes_outlive_the_instance_that_made_them() {
+        let dir = std::env::temp_dir().join(format!("mcp-db-lock-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let params = vec![Value::Null, Value::Integer(7), Value::Real(0.5), Value::Text("hi".into()), Value::Blob(vec![1, 2])];
+        queue(&dir, QueuedWrite { sql: "INSERT INTO a VALUES (?1, ?2, ?3, ?4, ?5)".into(), params: params.clone() }).unwrap();
+        queue(&dir, QueuedWrite { sql: "DELETE FROM a".into(), params: Vec::new() }).unwrap();
+        assert_eq!(status(&dir).unwrap().queued_writes, 2);
+
+        // Nothing is kept in memory, so a new instance sees exactly what's on disk
+        let queued = acquire(&dir).unwrap().unwrap();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].params, params);
+        assert_eq!(queued[1].sql, "DELETE FROM a");
+        assert_eq!(status(&dir).unwrap().queued_writes, 0);
+
+        // Until the replay is confirmed it's offered again
+        assert_eq!(acquire(&dir).unwrap().unwrap().len(), 2);
+        replayed(&dir).unwrap();
+        assert!(acquire(&dir).unwrap().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use async_openai::Client;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::CreateEmbeddingRequestArgs;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use crate::history::{HistoryMessage, HistoryStore};
+use crate::transcription::Transcript;
+
+/// Embedding model used to compare context chunks
+const EMBEDDING_MODEL: &str = "BAAI/bge-base-en-v1.5";
+
+/// Chunks at least this similar to an earlier chunk are dropped
+pub const DUPLICATE_SIMILARITY: f32 = 0.95;
+
+/// Target size of a transcript chunk
+const CHUNK_CHARS: usize = 800;
+
+/// A piece of retrieved context. `text` is what gets compared; `rendered` is what gets sent.
+pub struct ContextChunk {
+    pub source: String,
+    pub rendered: String,
+    pub text: String,
+}
+
+/// Split a transcript into runs of consecutive segments of about CHUNK_CHARS each
+pub fn transcript_chunks(transcript: &Transcript) -> Vec<ContextChunk> {
+    let mut chunks = Vec::new();
+    let mut rendered = String::new();
+    let mut text = String::new();
+    for line in transcript.context_lines() {
+        rendered.push_str(&line.rendered);
+        rendered.push('\n');
+        text.push_str(&line.text);
+        text.push(' ');
+        if text.len() >= CHUNK_CHARS {
+            chunks.push(ContextChunk {
+                source: transcript.source_file.clone(),
+                rendered: std::mem::take(&mut rendered),
+                text: std::mem::take(&mut text),
+            });
+        }
+    }
+    if !text.trim().is_empty() {
+        chunks.push(ContextChunk { source: transcript.source_file.clone(), rendered, text });
+    }
+    chunks
+}
+
+/// Drop chunks that repeat an earlier one, keeping the first copy. Near-duplicates are found by
+/// embedding similarity; if embeddings can't be fetched only exact repeats (ignoring case and
+/// whitespace) are dropped.
+pub async fn deduplicate(client: &Client<OpenAIConfig>, chunks: Vec<ContextChunk>) -> Vec<ContextChunk> {
+    if chunks.len() < 2 {
+        return chunks;
+    }
+    let keep = match embed(client, &chunks).await {
+        Ok(embeddings) => distinct_by_similarity(&embeddings, DUPLICATE_SIMILARITY),
+        Err(e) => {
+            log::warn!("Embedding failed, removing exact duplicates only: {}", e);
+            distinct_by_text(&chunks)
+        }
+    };
+    let before = chunks.len();
+    let kept: Vec<ContextChunk> = chunks.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(chunk, _)| chunk).collect();
+    log::info!("Kept {} of {} context chunks after deduplication", kept.len(), before);
+    kept
+}
+
+async fn embed(client: &Client<OpenAIConfig>, chunks: &[ContextChunk]) -> Result<Vec<Vec<f32>>> {
+    embed_texts(client, chunks.iter().map(|c| c.text.clone()).collect()).await
+}
+
+/// Inputs sent per embeddings request
+const EMBED_BATCH: usize = 100;
+
+/// Embeddings for `texts`, in the same order
+pub async fn embed_texts(client: &Client<OpenAIConfig>, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(EMBED_BATCH) {
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(EMBEDDING_MODEL)
+            .input(batch.to_vec())
+            .build()?;
+        let mut response = client.embeddings().create(request).await?;
+        if response.data.len() != batch.len() {
+            return Err(anyhow::anyhow!("Expected {} embeddings, got {}", batch.len(), response.data.len()));
+        }
+        response.data.sort_by_key(|e| e.index);
+        embeddings.extend(response.data.into_iter().map(|e| e.embedding));
+    }
+    Ok(embeddings)
+}
+
+/// What an embedding was made from, the model and the text sent, for matching stored ones
+pub fn text_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(format!("{}\n{}", EMBEDDING_MODEL, text).as_bytes()))
+}
+
+/// Embeddings of `prompts` from `texts`, in the same order. Those stored in the history are
+/// reused; the rest are fetched and stored for next time.
+pub async fn prompt_embeddings(client: &Client<OpenAIConfig>, data_dir: &Path, prompts: &[&HistoryMessage], texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+    let hashes: Vec<String> = texts.iter().map(|text| text_hash(text)).collect();
+    let mut embeddings = {
+        let store = HistoryStore::open(data_dir)?;
+        prompts.iter().zip(&hashes)
+            .map(|(prompt, hash)| store.prompt_embedding(&prompt.id, hash))
+            .collect::<Result<Vec<_>>>()?
+    };
+    let missing: Vec<usize> = (0..embeddings.len()).filter(|&i| embeddings[i].is_none()).collect();
+    if missing.is_empty() {
+        return Ok(embeddings.into_iter().flatten().collect());
+    }
+    log::info!("Embedding {} of {} prompts, {} were stored", missing.len(), prompts.len(), prompts.len() - missing.len());
+    let fetched = embed_texts(client, missing.iter().map(|&i| texts[i].clone()).collect()).await?;
+    let store = HistoryStore::open(data_dir)?;
+    store.batch(|store| {
+        for (&i, embedding) in missing.iter().zip(&fetched) {
+            store.save_prompt_embedding(&prompts[i].id, &hashes[i], embedding)?;
+        }
+        Ok(())
+    })?;
+    for (i, embedding) in missing.into_iter().zip(fetched) {
+        embeddings[i] = Some(embedding);
+    }
+    Ok(embeddings.into_iter().flatten().collect())
+}
+
+/// For each embedding, whether it is kept: false when it is within `threshold` of a kept one
+pub fn distinct_by_similarity(embeddings: &[Vec<f32>], threshold: f32) -> Vec<bool> {
+    let mut kept: Vec<&[f32]> = Vec::new();
+    embeddings.iter()
+        .map(|embedding| {
+            if kept.iter().any(|k| cosine_similarity(k, embedding) >= threshold) {
+                false
+            } else {
+                kept.push(embedding);
+                true
+            }
+        })
+        .collect()
+}
+
+/// Group embeddings around the first of each group: each joins the first group whose leader it
+/// is at least `threshold` similar to, or starts a new one. Returns member indexes by group.
+pub fn group_by_similarity(embeddings: &[Vec<f32>], threshold: f32) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, embedding) in embeddings.iter().enumerate() {
+        let group = groups.iter_mut()
+            .find(|group| cosine_similarity(&embeddings[group[0]], embedding) >= threshold);
+        match group {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    groups
+}
+
+fn distinct_by_text(chunks: &[ContextChunk]) -> Vec<bool> {
+    let mut seen = std::collections::HashSet::new();
+    chunks.iter()
+        .map(|chunk| seen.insert(chunk.text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()))
+        .collect()
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Render chunks as context text, with a heading whenever the source changes
+pub fn render(chunks: &[ContextChunk]) -> String {
+    let mut out = String::new();
+    let mut source = None;
+    for chunk in chunks {
+        if source != Some(&chunk.source) {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("Transcript of {}:\n", chunk.source));
+            source = Some(&chunk.source);
+        }
+        out.push_str(&chunk.rendered);
+    }
+    out
+}
@@ -0,0 +1,136 @@
+//! Demo mode: a canned provider and bundled sample history, so the app can be explored without
+//! an API key. Config and data live in `demo` subdirectories while it's on, so nothing done in
+//! the demo touches the real ones.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use futures::StreamExt;
+use serde::Deserialize;
+use crate::generation::MessageStream;
+use crate::history::{HistoryMessage, HistoryStore};
+use crate::{ModelInfo, StreamMessage};
+
+/// Set to 1 (or pass `--demo`) to start in demo mode, e.g. for screenshots and UI tests
+pub const DEMO_ENV: &str = "MCP_SWITCHBOARD_DEMO";
+
+pub const PROVIDER: &str = "demo";
+pub const MODEL: &str = "demo/switchboard-sample";
+
+/// Pause between streamed words, so the demo looks like a real response
+const WORD_DELAY: Duration = Duration::from_millis(30);
+
+const SAMPLE_HISTORY: &str = include_str!("../demo/sample_history.json");
+
+#[derive(Deserialize)]
+struct SampleConversation {
+    conversation_id: String,
+    messages: Vec<SampleMessage>,
+}
+
+#[derive(Deserialize)]
+struct SampleMessage {
+    role: String,
+    content: String,
+}
+
+fn flag() -> &'static AtomicBool {
+    static ACTIVE: OnceLock<AtomicBool> = OnceLock::new();
+    ACTIVE.get_or_init(|| AtomicBool::new(crate::environment::value(DEMO_ENV).is_some_and(|value| value == "1")))
+}
+
+pub fn active() -> bool {
+    flag().load(Ordering::Relaxed)
+}
+
+pub fn set_active(active: bool) {
+    log::info!("Demo mode {}", if active { "on" } else { "off" });
+    flag().store(active, Ordering::Relaxed);
+}
+
+pub fn models() -> Vec<ModelInfo> {
+    vec![ModelInfo {
+        id: MODEL.to_string(),
+        display_name: "Switchboard Demo".to_string(),
+        organization: "Demo".to_string(),
+        context_length: Some(8192),
+        pricing: None,
+    }]
+}
+
+/// Add the bundled sample conversations to `store` unless they're already there
+pub fn seed(store: &HistoryStore) -> anyhow::Result<u32> {
+    let conversations: Vec<SampleConversation> = serde_json::from_str(SAMPLE_HISTORY)?;
+    let mut added = 0;
+    store.batch(|store| {
+        for conversation in &conversations {
+            if !store.list(&conversation.conversation_id)?.is_empty() {
+                continue;
+            }
+            let mut parent_id = None;
+            for sample in &conversation.messages {
+                let model = (sample.role == "assistant").then(|| MODEL.to_string());
+                let message = HistoryMessage::new(&conversation.conversation_id, &sample.role, sample.content.clone(), model, parent_id.take());
+                store.insert(&message)?;
+                parent_id = Some(message.id);
+                added += 1;
+            }
+        }
+        Ok(())
+    })?;
+    log::info!("Seeded {} demo messages", added);
+    Ok(added)
+}
+
+fn canned_reply(prompt: &str) -> &'static str {
+    let prompt = prompt.to_lowercase();
+    let first_word = prompt.split_whitespace().next().unwrap_or_default().trim_matches(|c: char| !c.is_alphanumeric());
+    if ["hello", "hi", "hey"].contains(&first_word) {
+        "Hello! This is demo mode, so answers are canned rather than written by a model. Everything else \
+         works as usual: history, search, exports, templates and settings. Add an API key to talk to a real model."
+    } else if ["code", "rust", "function", "program"].iter().any(|word| prompt.contains(word)) {
+        "Here's a small example, since demo mode can't write code for you:\n\n```rust\nfn greet(name: &str) -> String {\n    \
+         format!(\"Hello, {}!\", name)\n}\n```\n\nWith an API key configured, the preferred model answers instead."
+    } else {
+        "This is a demo answer. In demo mode MCP Switchboard streams canned replies so you can explore the app \
+         without an API key. Try the generation modes, guardrails and export from the settings, or open the \
+         sample conversations in the history."
+    }
+}
+
+/// A canned answer to `prompt`, streamed a word at a time
+pub fn reply(prompt: &str) -> MessageStream {
+    let words: Vec<String> = canned_reply(prompt).split_inclusive(' ').map(str::to_string).collect();
+    Box::pin(futures::stream::iter(words).then(|word| async move {
+        tokio::time::sleep(WORD_DELAY).await;
+        StreamMessage::Content(word)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_history_parses() {
+        let conversations: Vec<SampleConversation> = serde_json::from_str(SAMPLE_HISTORY).unwrap();
+        assert!(!conversations.is_empty());
+        assert!(conversations.iter().all(|conversation| !conversation.messages.is_empty()));
+    }
+
+    #[test]
+    fn replies_match_the_prompt() {
+        assert!(canned_reply("Hi there!").starts_with("Hello!"));
+        assert!(canned_reply("Write a Rust function").contains("```rust"));
+        assert_eq!(canned_reply("this is sushi"), canned_reply("anything else"));
+    }
+
+    #[tokio::test]
+    async fn reply_streams_the_whole_answer() {
+        let streamed: String = reply("hello").map(|message| match message {
+            StreamMessage::Content(content) => content,
+            _ => String::new(),
+        }).collect().await;
+        assert_eq!(streamed, canned_reply("hello"));
+    }
+}
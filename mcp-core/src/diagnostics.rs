@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Heap totals from the counting allocator, only present in `diagnostics` builds
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct AllocatorStats {
+    pub current_bytes: u64,
+    pub peak_bytes: u64,
+    pub allocations: u64,
+    pub deallocations: u64,
+}
+
+/// Memory held by one in-process cache or queue. Sizes are estimates from the serialized size
+/// of the entries, not exact heap usage.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct SubsystemMemory {
+    pub name: String,
+    pub entries: u32,
+    pub approx_bytes: u64,
+}
+
+/// How often an in-process cache had what was asked for, since startup
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct CacheHits {
+    pub name: String,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct MemoryStats {
+    pub allocator: Option<AllocatorStats>,
+    /// Memory SQLite holds across all open history connections
+    pub sqlite_bytes: u64,
+    pub sqlite_peak_bytes: u64,
+    pub subsystems: Vec<SubsystemMemory>,
+    pub caches: Vec<CacheHits>,
+}
+
+pub fn memory_stats() -> MemoryStats {
+    let subsystems = vec![
+        subsystem("Config cache", crate::config::cache_usage()),
+        subsystem("Model list cache", crate::MODELS.usage(crate::metrics::payload_size)),
+        subsystem("Context lengths", crate::context::context_lengths_usage()),
+        subsystem("Model pricing", crate::cost::pricing_usage()),
+        subsystem("Template cache", crate::templates::INDEXED.usage(crate::metrics::payload_size)),
+        subsystem("Tool result cache", crate::tool_cache::RESULTS.usage(|result| result.len() as u64)),
+        subsystem("Queued history writes", crate::db_lock::queue_usage()),
+    ];
+    MemoryStats {
+        allocator: allocator_stats(),
+        // SAFETY: both only read SQLite's global memory counters
+        sqlite_bytes: unsafe { rusqlite::ffi::sqlite3_memory_used() }.max(0) as u64,
+        sqlite_peak_bytes: unsafe { rusqlite::ffi::sqlite3_memory_highwater(0) }.max(0) as u64,
+        subsystems,
+        caches: vec![
+            cache_hits("Model list cache", crate::MODELS.hit_counts()),
+            cache_hits("Template cache", crate::templates::INDEXED.hit_counts()),
+            cache_hits("Tool result cache", crate::tool_cache::RESULTS.hit_counts()),
+        ],
+    }
+}
+
+fn cache_hits(name: &str, (hits, misses): (u64, u64)) -> CacheHits {
+    CacheHits { name: name.to_string(), hits, misses }
+}
+
+fn subsystem(name: &str, (entries, approx_bytes): (u32, u64)) -> SubsystemMemory {
+    SubsystemMemory { name: name.to_string(), entries, approx_bytes }
+}
+
+#[cfg(not(feature = "diagnostics"))]
+fn allocator_stats() -> Option<AllocatorStats> {
+    None
+}
+
+#[cfg(feature = "diagnostics")]
+fn allocator_stats() -> Option<AllocatorStats> {
+    Some(counting::stats())
+}
+
+/// Global allocator that counts what passes through to the system allocator
+#[cfg(feature = "diagnostics")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use super::AllocatorStats;
+
+    static CURRENT: AtomicU64 = AtomicU64::new(0);
+    static PEAK: AtomicU64 = AtomicU64::new(0);
+    static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+    struct CountingAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocated(bytes: usize) {
+        let current = CURRENT.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn freed(bytes: usize) {
+        CURRENT.fetch_sub(bytes as u64, Ordering::Relaxed);
+    }
+
+    // SAFETY: every call is forwarded unchanged to System; only counters are updated around it
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+                allocated(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+                allocated(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            freed(layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                freed(layout.size());
+                allocated(new_size);
+            }
+            new_ptr
+        }
+    }
+
+    pub fn stats() -> AllocatorStats {
+        AllocatorStats {
+            current_bytes: CURRENT.load(Ordering::Relaxed),
+            peak_bytes: PEAK.load(Ordering::Relaxed),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+        }
+    }
+}
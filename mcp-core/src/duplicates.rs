@@ -0,0 +1,98 @@
+//! Conversations that are copies of one another, typically from importing the same export twice.
+//! They're found by comparing each conversation's first prompt, by embedding where the provider
+//! is available and by exact text otherwise, and can then be merged into one.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::history::HistoryMessage;
+
+/// Characters of each first prompt compared
+const OPENER_CHARS: usize = 500;
+
+const PREVIEW_CHARS: usize = 80;
+
+/// Conversations that start with the same prompt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct DuplicateConversations {
+    /// Oldest first, which is the one the others merge into by default
+    pub conversation_ids: Vec<String>,
+    /// The first prompt of the oldest conversation, shortened
+    pub preview: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct MergeSummary {
+    /// The conversation everything was merged into
+    pub conversation_id: String,
+    pub messages_moved: u32,
+    /// Messages identical to one already in the merged conversation
+    pub duplicates_dropped: u32,
+    pub transcripts_moved: u32,
+    pub captures_moved: u32,
+}
+
+pub fn opener_text(opener: &HistoryMessage) -> String {
+    opener.content.chars().take(OPENER_CHARS).collect()
+}
+
+fn normalized(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Groups of `openers` with the same text, ignoring case and whitespace
+pub fn group_by_text(openers: &[HistoryMessage]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut by_text: HashMap<String, usize> = HashMap::new();
+    for (i, opener) in openers.iter().enumerate() {
+        let text = normalized(&opener_text(opener));
+        match by_text.get(&text) {
+            Some(&group) => groups[group].push(i),
+            None => {
+                by_text.insert(text, groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+    groups
+}
+
+/// The groups with more than one conversation, as duplicates
+pub fn duplicates(openers: &[HistoryMessage], groups: Vec<Vec<usize>>) -> Vec<DuplicateConversations> {
+    groups.into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let first = openers[group[0]].content.split_whitespace().collect::<Vec<_>>().join(" ");
+            let preview = if first.chars().count() > PREVIEW_CHARS {
+                format!("{}…", first.chars().take(PREVIEW_CHARS).collect::<String>())
+            } else {
+                first
+            };
+            DuplicateConversations {
+                conversation_ids: group.iter().map(|&i| openers[i].conversation_id.clone()).collect(),
+                preview,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_repeats_are_grouped_ignoring_case_and_spacing() {
+        let openers = vec![
+            HistoryMessage::new("a", "user", "How do I  bake bread?".to_string(), None, None),
+            HistoryMessage::new("b", "user", "Something else".to_string(), None, None),
+            HistoryMessage::new("c", "user", "how do i bake bread?".to_string(), None, None),
+        ];
+        let found = duplicates(&openers, group_by_text(&openers));
+        assert_eq!(found, vec![DuplicateConversations {
+            conversation_ids: vec!["a".to_string(), "c".to_string()],
+            preview: "How do I bake bread?".to_string(),
+        }]);
+    }
+}
@@ -0,0 +1,140 @@
+//! OpenAI-compatible servers the user runs or hosts themselves: vLLM, LM Studio, llama.cpp's
+//! server and the like. Each is registered under a name, with an optional key and headers, and
+//! its models are listed as `endpoint/<name>/<model>`.
+
+use std::collections::BTreeMap;
+use async_openai::config::OpenAIConfig;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::providers::{self, Provider};
+use crate::tr;
+
+/// Sent as the key to servers that take none, since async-openai always sends one
+const NO_KEY: &str = "none";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct CompatibleEndpoint {
+    /// What its models are listed under, e.g. `lm-studio`
+    pub name: String,
+    /// The API base, e.g. `http://localhost:1234/v1`
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Sent with every request to the endpoint
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl CompatibleEndpoint {
+    pub fn api_key(&self) -> &str {
+        self.api_key.as_deref().unwrap_or(NO_KEY)
+    }
+
+    pub fn models_url(&self) -> String {
+        format!("{}/models", self.base_url)
+    }
+
+    /// A client configuration reaching the endpoint; its headers are sent by the HTTP client
+    pub fn config(&self) -> OpenAIConfig {
+        OpenAIConfig::new().with_api_base(&self.base_url).with_api_key(self.api_key())
+    }
+}
+
+/// `endpoint` trimmed, or a localized error if its name, address or headers can't be used
+pub fn validate(mut endpoint: CompatibleEndpoint) -> Result<CompatibleEndpoint, String> {
+    endpoint.name = endpoint.name.trim().to_string();
+    endpoint.base_url = endpoint.base_url.trim().trim_end_matches('/').to_string();
+    endpoint.api_key = endpoint.api_key.map(|key| key.trim().to_string()).filter(|key| !key.is_empty());
+    if endpoint.name.is_empty() || endpoint.name.contains('/') {
+        return Err(tr!("endpoint-name-invalid"));
+    }
+    if !endpoint.base_url.starts_with("https://") && !endpoint.base_url.starts_with("http://") {
+        return Err(tr!("custom-model-url-invalid", url = endpoint.base_url.clone()));
+    }
+    endpoint.headers = providers::validate_headers(endpoint.headers)?;
+    Ok(endpoint)
+}
+
+/// The endpoint serving `model` and the id the endpoint knows the model by
+pub fn resolve<'a, 'm>(endpoints: &'a [CompatibleEndpoint], model: &'m str) -> Option<(&'a CompatibleEndpoint, &'m str)> {
+    let (provider, rest) = providers::resolve(model);
+    let (name, upstream) = rest.split_once('/').filter(|_| provider == Provider::Endpoint)?;
+    endpoints.iter().find(|endpoint| endpoint.name == name).map(|endpoint| (endpoint, upstream))
+}
+
+/// The models in an endpoint's `GET /models` response. vLLM reports context lengths as
+/// `max_model_len`; llama.cpp and LM Studio report none.
+pub fn models(endpoint: &CompatibleEndpoint, response: &serde_json::Value) -> Result<Vec<ModelInfo>, String> {
+    let data = response["data"].as_array().ok_or_else(|| {
+        log::error!("Endpoint {} models response has no data array", endpoint.name);
+        tr!("endpoint-models-invalid", endpoint = endpoint.name.clone())
+    })?;
+    let mut models: Vec<ModelInfo> = data.iter()
+        .filter_map(|model| {
+            let upstream = model["id"].as_str()?;
+            let id = providers::qualified_id(Provider::Endpoint, &format!("{}/{}", endpoint.name, upstream));
+            let context_length = model["max_model_len"].as_u64()
+                .or_else(|| model["context_length"].as_u64())
+                .filter(|length| *length > 0)
+                .map(|length| length as u32);
+            if let Some(length) = context_length {
+                crate::context::remember_context_length(&id, length as usize);
+            }
+            Some(ModelInfo {
+                id,
+                display_name: upstream.to_string(),
+                organization: endpoint.name.clone(),
+                context_length,
+                pricing: None,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::config::Config;
+
+    fn entered(name: &str, base_url: &str) -> CompatibleEndpoint {
+        CompatibleEndpoint {
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+            api_key: Some(" ".to_string()),
+            headers: BTreeMap::from([("X-Team".to_string(), "research".to_string())]),
+        }
+    }
+
+    #[test]
+    fn endpoint_models_resolve_back_to_their_endpoint() {
+        let endpoints = vec![validate(entered(" vllm ", "http://gpu-box:8000/v1/")).unwrap()];
+        assert_eq!(endpoints[0].api_key, None);
+        assert_eq!(endpoints[0].config().url("/chat/completions"), "http://gpu-box:8000/v1/chat/completions");
+
+        let response = serde_json::json!({ "object": "list", "data": [
+            { "id": "Qwen/Qwen2.5-7B-Instruct", "object": "model", "owned_by": "vllm", "max_model_len": 32768 },
+        ]});
+        let listed = models(&endpoints[0], &response).unwrap();
+        assert_eq!(listed[0].id, "endpoint/vllm/Qwen/Qwen2.5-7B-Instruct");
+        assert_eq!(listed[0].context_length, Some(32768));
+
+        let (endpoint, upstream) = resolve(&endpoints, &listed[0].id).unwrap();
+        assert_eq!((endpoint.name.as_str(), upstream), ("vllm", "Qwen/Qwen2.5-7B-Instruct"));
+        assert!(resolve(&endpoints, "endpoint/lm-studio/qwen").is_none());
+        assert!(resolve(&endpoints, "vllm/Qwen/Qwen2.5-7B-Instruct").is_none());
+    }
+
+    #[test]
+    fn endpoints_need_a_plain_name_and_an_http_address() {
+        assert!(validate(entered("lm/studio", "http://localhost:1234/v1")).is_err());
+        assert!(validate(entered("lm-studio", "localhost:1234/v1")).is_err());
+        let mut reserved = entered("lm-studio", "http://localhost:1234/v1");
+        reserved.headers.insert("Authorization".to_string(), "Bearer x".to_string());
+        assert!(validate(reserved).is_err());
+        assert!(models(&entered("lm-studio", "http://localhost:1234/v1"), &serde_json::json!([])).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Settings that can come from outside the encrypted config: command-line flags, environment
+//! variables and a `.env` file. Everything that reads one goes through `var`, so development
+//! runs (`TOGETHERAI_API_KEY=... just dev`) and packaged builds resolve them the same way.
+//!
+//! Resolution order, highest first:
+//! 1. command-line flags (`--api-key <key>`, `--demo`, `--portable`), see `init_from_args`
+//! 2. the process environment
+//! 3. `.env` in the config directory, which never overrides the process environment
+//! 4. the system keychain, once there is one; nothing is stored there yet
+//! 5. the encrypted config file
+//!
+//! Steps 4 and 5 belong to `ConfigManager`; this module covers the first three.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+pub const API_KEY_VAR: &str = "TOGETHERAI_API_KEY";
+
+pub const OPENAI_API_KEY_VAR: &str = "OPENAI_API_KEY";
+
+pub const ANTHROPIC_API_KEY_VAR: &str = "ANTHROPIC_API_KEY";
+
+pub const OPENROUTER_API_KEY_VAR: &str = "OPENROUTER_API_KEY";
+
+pub const GROQ_API_KEY_VAR: &str = "GROQ_API_KEY";
+
+pub const AZURE_OPENAI_API_KEY_VAR: &str = "AZURE_OPENAI_API_KEY";
+
+pub const AWS_SECRET_ACCESS_KEY_VAR: &str = "AWS_SECRET_ACCESS_KEY";
+
+pub const AWS_ACCESS_KEY_ID_VAR: &str = "AWS_ACCESS_KEY_ID";
+
+pub const AWS_SESSION_TOKEN_VAR: &str = "AWS_SESSION_TOKEN";
+
+pub const AWS_REGION_VAR: &str = "AWS_REGION";
+
+pub const AWS_DEFAULT_REGION_VAR: &str = "AWS_DEFAULT_REGION";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum ValueSource {
+    CommandLine,
+    Environment,
+    DotEnv,
+    ConfigFile,
+}
+
+static COMMAND_LINE: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Record the flags that override settings, from the app's arguments (without the program name).
+/// Call once, before anything reads a setting; later calls are ignored.
+pub fn init_from_args(args: impl IntoIterator<Item = String>) {
+    let overrides = parse_args(args);
+    if !overrides.is_empty() {
+        log::info!("Command line sets {:?}", overrides.keys().collect::<Vec<_>>());
+    }
+    if COMMAND_LINE.set(overrides).is_err() {
+        log::warn!("Command-line settings were already initialized");
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> HashMap<String, String> {
+    let mut overrides = HashMap::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        match flag.as_str() {
+            "--api-key" => {
+                if let Some(key) = inline.or_else(|| args.next()) {
+                    overrides.insert(API_KEY_VAR.to_string(), key);
+                }
+            }
+            "--demo" => {
+                overrides.insert(crate::demo::DEMO_ENV.to_string(), "1".to_string());
+            }
+            "--portable" => {
+                overrides.insert(crate::portable::PORTABLE_ENV.to_string(), "1".to_string());
+            }
+            // Tauri and the OS pass their own flags; they're not ours to reject
+            _ => {}
+        }
+    }
+    overrides
+}
+
+fn dot_env() -> &'static HashMap<String, String> {
+    static DOT_ENV: OnceLock<HashMap<String, String>> = OnceLock::new();
+    DOT_ENV.get_or_init(|| {
+        let Some(path) = crate::platform::config_dir().map(|dir| dir.join(".env")) else {
+            return HashMap::new();
+        };
+        load_dot_env(&path)
+    })
+}
+
+fn load_dot_env(path: &Path) -> HashMap<String, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            let vars = parse_dot_env(&contents);
+            log::info!("Loaded {} settings from {:?}", vars.len(), path);
+            vars
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => {
+            log::warn!("Failed to read {:?}: {}", path, e);
+            HashMap::new()
+        }
+    }
+}
+
+/// `NAME=value` lines; blank lines, `#` comments and a leading `export` are allowed, and values
+/// may be wrapped in single or double quotes
+fn parse_dot_env(contents: &str) -> HashMap<String, String> {
+    contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|quote| value.strip_prefix(*quote).and_then(|v| v.strip_suffix(*quote)))
+                .unwrap_or(value);
+            Some((name.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// The value of `name` and where it came from, ignoring empty values
+pub fn var(name: &str) -> Option<(String, ValueSource)> {
+    var_before_dot_env(name)
+        .or_else(|| dot_env().get(name).cloned().filter(|value| !value.is_empty()).map(|value| (value, ValueSource::DotEnv)))
+}
+
+fn var_before_dot_env(name: &str) -> Option<(String, ValueSource)> {
+    let command_line = COMMAND_LINE.get().and_then(|overrides| overrides.get(name).cloned());
+    command_line.map(|value| (value, ValueSource::CommandLine))
+        .or_else(|| std::env::var(name).ok().map(|value| (value, ValueSource::Environment)))
+        .filter(|(value, _)| !value.is_empty())
+}
+
+/// `value` without the `.env` file, for settings that decide where the config directory is
+pub fn value_before_dot_env(name: &str) -> Option<String> {
+    var_before_dot_env(name).map(|(value, _)| value)
+}
+
+pub fn value(name: &str) -> Option<String> {
+    var(name).map(|(value, _)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> HashMap<String, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_take_a_separate_or_inline_value() {
+        assert_eq!(args(&["--api-key", "abc"]).get(API_KEY_VAR).map(String::as_str), Some("abc"));
+        assert_eq!(args(&["--api-key=abc"]).get(API_KEY_VAR).map(String::as_str), Some("abc"));
+        assert_eq!(args(&["--demo", "--unknown"]).get(crate::demo::DEMO_ENV).map(String::as_str), Some("1"));
+        assert_eq!(args(&["--portable"]).get(crate::portable::PORTABLE_ENV).map(String::as_str), Some("1"));
+        assert!(args(&["--api-key"]).is_empty());
+    }
+
+    #[test]
+    fn dot_env_handles_comments_quotes_and_export() {
+        let vars = parse_dot_env("# comment\n\nexport A=1\nB = \"two words\"\nC='x=y'\nnot a pair\n");
+        assert_eq!(vars.get("A").map(String::as_str), Some("1"));
+        assert_eq!(vars.get("B").map(String::as_str), Some("two words"));
+        assert_eq!(vars.get("C").map(String::as_str), Some("x=y"));
+        assert_eq!(vars.len(), 3);
+    }
+}
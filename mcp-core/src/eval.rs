@@ -0,0 +1,309 @@
+//! Judged evaluation runs over saved prompt suites. Each case's prompt is sent to every model in
+//! the suite, and a judge model scores the answer against the case's criteria. Runs are kept with
+//! their scores, so a model's quality can be followed over time and drift noticed.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use async_openai::Client;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::{ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+
+/// Highest score the judge can give
+pub const MAX_SCORE: f32 = 10.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct EvalCase {
+    pub prompt: String,
+    /// What a good answer does, for the judge to score against
+    pub criteria: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct EvalSuite {
+    pub name: String,
+    pub cases: Vec<EvalCase>,
+    /// Models whose answers are scored
+    pub models: Vec<String>,
+    pub judge_model: String,
+    /// Run automatically this often; None to run only on demand
+    pub interval_hours: Option<u32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// One model's answer to one case, as the judge scored it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct EvalResult {
+    pub case_index: u32,
+    pub model: String,
+    /// The case's prompt when it ran, in case the suite changes later
+    pub prompt: String,
+    pub output: String,
+    /// Out of MAX_SCORE; None when the model or the judge failed
+    pub score: Option<f32>,
+    pub reasoning: String,
+    pub error: Option<String>,
+    /// Answering and judging together
+    pub estimated_usd: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct EvalRun {
+    pub id: String,
+    pub suite_name: String,
+    pub judge_model: String,
+    pub scheduled: bool,
+    pub started_at: String,
+    /// None while the run is in progress, or if the app closed during it
+    pub finished_at: Option<String>,
+    pub results: Vec<EvalResult>,
+}
+
+/// A model's average over one run: a point on its score history
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ModelScore {
+    pub run_id: String,
+    pub started_at: String,
+    pub model: String,
+    /// None if none of its answers could be scored
+    pub average_score: Option<f32>,
+    pub scored: u32,
+    pub failed: u32,
+}
+
+/// `suite` trimmed and without blank entries, or a localized error saying what's missing
+pub fn validate(mut suite: EvalSuite) -> Result<EvalSuite, String> {
+    suite.name = suite.name.trim().to_string();
+    if suite.name.is_empty() {
+        return Err(tr!("eval-name-required"));
+    }
+    for case in &mut suite.cases {
+        case.prompt = case.prompt.trim().to_string();
+        case.criteria = case.criteria.trim().to_string();
+    }
+    suite.cases.retain(|case| !case.prompt.is_empty());
+    if suite.cases.is_empty() {
+        return Err(tr!("eval-no-cases"));
+    }
+    let mut seen = BTreeSet::new();
+    suite.models = suite.models.iter()
+        .map(|model| model.trim().to_string())
+        .filter(|model| !model.is_empty() && seen.insert(model.clone()))
+        .collect();
+    if suite.models.is_empty() {
+        return Err(tr!("eval-no-models"));
+    }
+    suite.judge_model = suite.judge_model.trim().to_string();
+    if suite.judge_model.is_empty() {
+        return Err(tr!("eval-no-judge"));
+    }
+    if suite.interval_hours == Some(0) {
+        return Err(tr!("eval-interval-invalid"));
+    }
+    Ok(suite)
+}
+
+/// Whether a scheduled suite last started at `last_started` should run again at `now`
+pub fn is_due(suite: &EvalSuite, last_started: Option<&str>, now: DateTime<Utc>) -> bool {
+    let Some(hours) = suite.interval_hours else {
+        return false;
+    };
+    let Some(last_started) = last_started else {
+        return true;
+    };
+    match DateTime::parse_from_rfc3339(last_started) {
+        Ok(last) => now.signed_duration_since(last) >= chrono::Duration::hours(i64::from(hours)),
+        Err(_) => true,
+    }
+}
+
+/// Mean of the scored results
+pub fn average_score<'a>(results: impl IntoIterator<Item = &'a EvalResult>) -> Option<f32> {
+    let scores: Vec<f32> = results.into_iter().filter_map(|result| result.score).collect();
+    (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32)
+}
+
+/// Each model's average in each of `runs`, in the order of `runs`
+pub fn model_scores(runs: &[EvalRun]) -> Vec<ModelScore> {
+    let mut scores = Vec::new();
+    for run in runs {
+        let mut by_model: BTreeMap<&str, Vec<&EvalResult>> = BTreeMap::new();
+        for result in &run.results {
+            by_model.entry(result.model.as_str()).or_default().push(result);
+        }
+        scores.extend(by_model.into_iter().map(|(model, results)| ModelScore {
+            run_id: run.id.clone(),
+            started_at: run.started_at.clone(),
+            model: model.to_string(),
+            average_score: average_score(results.iter().copied()),
+            scored: results.iter().filter(|result| result.score.is_some()).count() as u32,
+            failed: results.iter().filter(|result| result.score.is_none()).count() as u32,
+        }));
+    }
+    scores
+}
+
+/// Suites being run now, so a scheduled run doesn't start over a manual one
+static RUNNING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Marks a suite as running until dropped
+pub struct RunGuard(String);
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).remove(&self.0);
+    }
+}
+
+/// None if `suite_name` is already running
+pub fn begin_run(suite_name: &str) -> Option<RunGuard> {
+    let mut running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    running.insert(suite_name.to_string()).then(|| RunGuard(suite_name.to_string()))
+}
+
+/// A model's reply with the tokens it was billed for
+pub struct Completion {
+    pub text: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// Send `content` to `model` as a single user message
+pub async fn complete(client: &Client<OpenAIConfig>, model: &str, content: &str) -> anyhow::Result<Completion> {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages(vec![ChatCompletionRequestUserMessageArgs::default().content(content).build()?.into()])
+        .build()?;
+    let client = crate::routed_client(client, model).map_err(anyhow::Error::msg)?;
+    let (mut span, client) = crate::provider_span(&client, model);
+    let response = client.chat().create(crate::providers::upstream_request(request)).await.inspect_err(|e| span.fail(e.to_string()))?;
+    let text = response.choices.first()
+        .and_then(|choice| choice.message.content.clone())
+        .unwrap_or_default();
+    let (prompt_tokens, completion_tokens) = match response.usage {
+        Some(usage) => (usage.prompt_tokens, usage.completion_tokens),
+        None => (crate::context::estimate_tokens(content) as u32, crate::context::estimate_tokens(&text) as u32),
+    };
+    span.set("gen_ai.usage.input_tokens", i64::from(prompt_tokens));
+    span.set("gen_ai.usage.output_tokens", i64::from(completion_tokens));
+    Ok(Completion { text, prompt_tokens, completion_tokens })
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Verdict {
+    pub score: f32,
+    #[serde(default)]
+    pub reasoning: String,
+}
+
+/// Ask `judge_model` to score `output` as an answer to `case`
+pub async fn judge(
+    client: &Client<OpenAIConfig>,
+    judge_model: &str,
+    case: &EvalCase,
+    output: &str,
+) -> anyhow::Result<(Verdict, Completion)> {
+    let judging = complete(client, judge_model, &judge_prompt(case, output)).await?;
+    Ok((parse_verdict(&judging.text)?, judging))
+}
+
+fn judge_prompt(case: &EvalCase, output: &str) -> String {
+    let criteria = if case.criteria.is_empty() { "A correct, clear and helpful answer." } else { &case.criteria };
+    format!(
+        "You are grading an AI assistant's answer. Score how well it meets the criteria from 0 \
+         (not at all) to {max}, judging only against the criteria. Reply with only a JSON object \
+         such as {{\"score\": 7, \"reasoning\": \"one or two sentences\"}}.\n\n\
+         Criteria:\n{criteria}\n\nPrompt:\n{prompt}\n\nAnswer:\n{output}",
+        max = MAX_SCORE,
+        prompt = case.prompt,
+    )
+}
+
+fn parse_verdict(content: &str) -> anyhow::Result<Verdict> {
+    // Judges sometimes wrap the object in prose or a code fence
+    let start = content.find('{').ok_or_else(|| anyhow::anyhow!("No JSON object in the judge's reply"))?;
+    let end = content.rfind('}').ok_or_else(|| anyhow::anyhow!("No JSON object in the judge's reply"))?;
+    let mut verdict: Verdict = serde_json::from_str(&content[start..=end])?;
+    if !verdict.score.is_finite() {
+        anyhow::bail!("The judge's score isn't a number");
+    }
+    verdict.score = verdict.score.clamp(0.0, MAX_SCORE);
+    verdict.reasoning = verdict.reasoning.trim().to_string();
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suite(models: &[&str], interval_hours: Option<u32>) -> EvalSuite {
+        EvalSuite {
+            name: " Reasoning ".to_string(),
+            cases: vec![
+                EvalCase { prompt: " What is 2 + 2? ".to_string(), criteria: "Says 4".to_string() },
+                EvalCase { prompt: "  ".to_string(), criteria: String::new() },
+            ],
+            models: models.iter().map(|model| model.to_string()).collect(),
+            judge_model: "judge".to_string(),
+            interval_hours,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn suites_are_cleaned_up_and_verdicts_parsed() {
+        let cleaned = validate(suite(&["a", " a ", "b", ""], Some(24))).unwrap();
+        assert_eq!(cleaned.name, "Reasoning");
+        assert_eq!(cleaned.cases.len(), 1);
+        assert_eq!(cleaned.cases[0].prompt, "What is 2 + 2?");
+        assert_eq!(cleaned.models, vec!["a", "b"]);
+        assert!(validate(suite(&[" "], None)).is_err());
+        assert!(validate(suite(&["a"], Some(0))).is_err());
+
+        let verdict = parse_verdict("```json\n{\"score\": 12, \"reasoning\": \" Right answer. \"}\n```").unwrap();
+        assert_eq!(verdict, Verdict { score: MAX_SCORE, reasoning: "Right answer.".to_string() });
+        assert!(parse_verdict("Seven out of ten").is_err());
+    }
+
+    #[test]
+    fn scores_are_averaged_per_model_and_runs_fall_due() {
+        let result = |model: &str, score| EvalResult {
+            case_index: 0,
+            model: model.to_string(),
+            prompt: String::new(),
+            output: String::new(),
+            score,
+            reasoning: String::new(),
+            error: None,
+            estimated_usd: None,
+        };
+        let run = EvalRun {
+            id: "r".to_string(),
+            suite_name: "s".to_string(),
+            judge_model: "judge".to_string(),
+            scheduled: false,
+            started_at: "2026-01-01T00:00:00+00:00".to_string(),
+            finished_at: None,
+            results: vec![result("b", Some(6.0)), result("a", Some(4.0)), result("b", Some(8.0)), result("a", None)],
+        };
+        let scores = model_scores(std::slice::from_ref(&run));
+        assert_eq!(scores.iter().map(|s| (s.model.as_str(), s.average_score, s.failed)).collect::<Vec<_>>(),
+            vec![("a", Some(4.0), 1), ("b", Some(7.0), 0)]);
+
+        let now = DateTime::parse_from_rfc3339("2026-01-02T01:00:00+00:00").unwrap().with_timezone(&Utc);
+        assert!(is_due(&suite(&["a"], Some(24)), Some(&run.started_at), now));
+        assert!(!is_due(&suite(&["a"], Some(48)), Some(&run.started_at), now));
+        assert!(!is_due(&suite(&["a"], None), None, now));
+        assert!(is_due(&suite(&["a"], Some(1)), None, now));
+    }
+}
@@ -0,0 +1,240 @@
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use ts_rs::TS;
+use crate::accessibility::{CompletionStatus, LivePoliteness, MessageBoundary, MessageRole};
+use crate::appearance::AppearanceSettings;
+use crate::duplicates::MergeSummary;
+use crate::features::FeatureFlag;
+use crate::handoff::Handoff;
+use crate::health::ProviderHealth;
+use crate::low_power::LowPowerReason;
+use crate::maintenance::MaintenanceJob;
+use crate::modes::{AppMode, ModeBehavior};
+use crate::orchestration::OrchestrationEnd;
+use crate::pairing::PairedDevice;
+use crate::planner::TaskPlan;
+use crate::read_aloud::ReadAloudState;
+use crate::startup::Subsystem;
+use crate::status::StatusSnapshot;
+use crate::tool_limits::TruncationStrategy;
+
+/// Events buffered per subscriber before slow subscribers start missing events
+const BUS_CAPACITY: usize = 1024;
+
+/// Typed application event published on the internal bus.
+/// The desktop app forwards these to the webview; server mode exposes them over WebSocket.
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub enum AppEvent {
+    ChatStarted { request_id: String, model: String, conversation_id: String, message_id: String },
+    ChatContent { request_id: String, content: String },
+    /// `tokens_per_second` is the generation rate after the first token, for comparing providers
+    ChatCompleted { request_id: String, model: String, response_chars: u32, tokens_per_second: Option<f32> },
+    ChatError { request_id: String, error: String },
+    /// Where a message starts or ends, for driving ARIA live regions; `announcement` is localized text to speak
+    AccessibilityHint {
+        message_id: String,
+        role: MessageRole,
+        boundary: MessageBoundary,
+        status: CompletionStatus,
+        politeness: LivePoliteness,
+        announcement: String,
+    },
+    /// The provider rejected the context as too long and the request was retried with less history
+    ContextReduced { conversation_id: String, dropped_messages: u32, dropped_chars: u32, budget_tokens: u32 },
+    /// Old turns were compressed before sending; counts are estimated tokens
+    ContextCompressed { conversation_id: String, before_tokens: u32, after_tokens: u32 },
+    /// Near-duplicate chunks were left out of retrieved context
+    ContextDeduplicated { conversation_id: String, dropped_chunks: u32 },
+    /// Race mode picked the answer to keep
+    ModelRaceDecided { fast_model: String, preferred_model: String, winner: String },
+    /// DraftRefine mode finished the draft stage; `used` is false when refining fell back to a direct answer
+    DraftCompleted { draft_model: String, draft_chars: u32, used: bool },
+    /// BestOfN mode stored its candidates for `message_id`
+    CandidatesReady { message_id: String, count: u32 },
+    /// A response broke guardrail rules; `retrying` is false once the retries are used up
+    GuardrailViolated { violations: Vec<String>, attempt: u32, retrying: bool },
+    /// New long-term memories were learned from a conversation
+    MemoriesLearned { conversation_id: String, count: u32 },
+    /// The app mode changed; `behavior` is what the new mode allows
+    ModeChanged { from: AppMode, to: AppMode, behavior: ModeBehavior },
+    /// The config was encrypted with a key this machine can no longer derive (e.g. it was copied
+    /// from another machine) and was moved to `moved_to`; settings and the API key need entering again
+    ConfigUnreadable { moved_to: String },
+    /// A feature flag's effective value changed
+    FeatureFlagChanged { flag: FeatureFlag },
+    /// A conversation was locked behind OS authentication, or its lock was removed
+    ConversationLockChanged { conversation_id: String, locked: bool },
+    /// Answers completed in a conversation that wasn't on screen, or it was read; `unread` is its count now
+    UnreadChanged { conversation_id: String, unread: u32 },
+    /// Conversations were merged into `summary.conversation_id`; the `merged` ones no longer exist
+    ConversationsMerged { summary: MergeSummary, merged: Vec<String> },
+    /// An orchestrated conversation ended after `turns` turns, for `reason`
+    OrchestrationEnded { conversation_id: String, turns: u32, reason: OrchestrationEnd },
+    /// A conversation's task plan was made, or steps of it changed status; the plan as it is now
+    PlanUpdated { plan: TaskPlan },
+    /// A stop sequence or content filter cut the answer short and the stream was ended there
+    ResponseTruncated { message_id: String, truncated_by: String },
+    /// The read-aloud queue moved on, paused or stopped; the webview speaks `state.speaking`
+    ReadAloudChanged { state: ReadAloudState },
+    /// An eval suite began running; `total` answers will be generated and judged
+    EvalRunStarted { run_id: String, suite_name: String, total: u32 },
+    EvalProgress { run_id: String, done: u32, total: u32 },
+    /// Every answer of the run was judged, or the spend cap stopped it early
+    EvalRunCompleted { run_id: String, suite_name: String, average_score: Option<f32> },
+    /// A benchmark finished with `model`, the `done`th of `total`
+    BenchmarkProgress { report_id: String, model: String, done: u32, total: u32 },
+    /// Demo mode was switched on or off; config and history now come from a different place
+    DemoModeChanged { enabled: bool },
+    /// Appearance settings were saved or imported; every open window should restyle
+    AppearanceChanged { settings: AppearanceSettings },
+    BackupCompleted { path: String, scheduled: bool },
+    HistoryRestored { messages_added: u32, files_restored: u32 },
+    SyncCompleted { pushed: u32, pulled: u32, conflicts: u32 },
+    /// An idle maintenance job finished; `summary` says what it did
+    MaintenanceCompleted { job: MaintenanceJob, summary: String },
+    /// The low-power profile turned on or off; `reasons` is empty when it's off
+    LowPowerChanged { active: bool, reasons: Vec<LowPowerReason> },
+    /// A conversation export began; `export_id` is what `cancel_export` takes
+    ExportStarted { export_id: String, conversation_id: String, path: String, total: u32 },
+    ExportProgress { export_id: String, written: u32, total: u32 },
+    /// Another instance holds the history database; this one is read-only and queues its writes
+    DatabaseLocked,
+    DatabaseUnlocked { replayed_writes: u32 },
+    /// A subsystem deferred past launch finished starting
+    SubsystemReady { subsystem: Subsystem, elapsed_ms: u32 },
+    SubsystemFailed { subsystem: Subsystem, error: String },
+    /// A health check found a provider's state changed, e.g. from Healthy to Down
+    ProviderHealthChanged { health: ProviderHealth },
+    /// Something the status bar shows changed; the full snapshot, as `get_status` returns it
+    StatusChanged { status: StatusSnapshot },
+    ToolCalled { plugin: String, tool: String, success: bool },
+    /// A tool's result was over its size limit and was cut to `kept_chars` before being returned
+    ToolResultTruncated { plugin: String, tool: String, original_chars: u32, kept_chars: u32, strategy: TruncationStrategy },
+    /// An OAuth sign-in for an integration finished and its tokens are in the vault
+    OAuthCompleted { integration: String },
+    OAuthFailed { integration: String, error: String },
+    PluginsReloaded { count: u32 },
+    ScriptsReloaded { count: u32 },
+    TemplatesSynced { repositories: u32 },
+    ServerStarted { address: String },
+    ServerStopped,
+    /// A companion device exchanged a pairing code for a token
+    DevicePaired { device: PairedDevice },
+    DeviceUnpaired { device_id: String },
+    /// A conversation was handed from this device to another
+    HandoffSent { handoff: Handoff },
+    /// Another desktop handed a conversation to this one
+    HandoffReceived { handoff: Handoff },
+    /// The receiving device took the conversation over; published on both ends
+    HandoffAccepted { handoff: Handoff },
+}
+
+impl AppEvent {
+    /// Variant name, used by subscribers to filter event types
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppEvent::ChatStarted { .. } => "ChatStarted",
+            AppEvent::ChatContent { .. } => "ChatContent",
+            AppEvent::ChatCompleted { .. } => "ChatCompleted",
+            AppEvent::ChatError { .. } => "ChatError",
+            AppEvent::AccessibilityHint { .. } => "AccessibilityHint",
+            AppEvent::ContextReduced { .. } => "ContextReduced",
+            AppEvent::ContextCompressed { .. } => "ContextCompressed",
+            AppEvent::ContextDeduplicated { .. } => "ContextDeduplicated",
+            AppEvent::ModelRaceDecided { .. } => "ModelRaceDecided",
+            AppEvent::DraftCompleted { .. } => "DraftCompleted",
+            AppEvent::CandidatesReady { .. } => "CandidatesReady",
+            AppEvent::GuardrailViolated { .. } => "GuardrailViolated",
+            AppEvent::MemoriesLearned { .. } => "MemoriesLearned",
+            AppEvent::ModeChanged { .. } => "ModeChanged",
+            AppEvent::ConfigUnreadable { .. } => "ConfigUnreadable",
+            AppEvent::FeatureFlagChanged { .. } => "FeatureFlagChanged",
+            AppEvent::ConversationLockChanged { .. } => "ConversationLockChanged",
+            AppEvent::UnreadChanged { .. } => "UnreadChanged",
+            AppEvent::ConversationsMerged { .. } => "ConversationsMerged",
+            AppEvent::OrchestrationEnded { .. } => "OrchestrationEnded",
+            AppEvent::PlanUpdated { .. } => "PlanUpdated",
+            AppEvent::ResponseTruncated { .. } => "ResponseTruncated",
+            AppEvent::ReadAloudChanged { .. } => "ReadAloudChanged",
+            AppEvent::EvalRunStarted { .. } => "EvalRunStarted",
+            AppEvent::EvalProgress { .. } => "EvalProgress",
+            AppEvent::EvalRunCompleted { .. } => "EvalRunCompleted",
+            AppEvent::BenchmarkProgress { .. } => "BenchmarkProgress",
+            AppEvent::DemoModeChanged { .. } => "DemoModeChanged",
+            AppEvent::AppearanceChanged { .. } => "AppearanceChanged",
+            AppEvent::BackupCompleted { .. } => "BackupCompleted",
+            AppEvent::HistoryRestored { .. } => "HistoryRestored",
+            AppEvent::SyncCompleted { .. } => "SyncCompleted",
+            AppEvent::MaintenanceCompleted { .. } => "MaintenanceCompleted",
+            AppEvent::LowPowerChanged { .. } => "LowPowerChanged",
+            AppEvent::ExportStarted { .. } => "ExportStarted",
+            AppEvent::ExportProgress { .. } => "ExportProgress",
+            AppEvent::DatabaseLocked => "DatabaseLocked",
+            AppEvent::DatabaseUnlocked { .. } => "DatabaseUnlocked",
+            AppEvent::SubsystemReady { .. } => "SubsystemReady",
+            AppEvent::SubsystemFailed { .. } => "SubsystemFailed",
+            AppEvent::ProviderHealthChanged { .. } => "ProviderHealthChanged",
+            AppEvent::StatusChanged { .. } => "StatusChanged",
+            AppEvent::ToolCalled { .. } => "ToolCalled",
+            AppEvent::ToolResultTruncated { .. } => "ToolResultTruncated",
+            AppEvent::OAuthCompleted { .. } => "OAuthCompleted",
+            AppEvent::OAuthFailed { .. } => "OAuthFailed",
+            AppEvent::PluginsReloaded { .. } => "PluginsReloaded",
+            AppEvent::ScriptsReloaded { .. } => "ScriptsReloaded",
+            AppEvent::TemplatesSynced { .. } => "TemplatesSynced",
+            AppEvent::ServerStarted { .. } => "ServerStarted",
+            AppEvent::ServerStopped => "ServerStopped",
+            AppEvent::DevicePaired { .. } => "DevicePaired",
+            AppEvent::DeviceUnpaired { .. } => "DeviceUnpaired",
+            AppEvent::HandoffSent { .. } => "HandoffSent",
+            AppEvent::HandoffReceived { .. } => "HandoffReceived",
+            AppEvent::HandoffAccepted { .. } => "HandoffAccepted",
+        }
+    }
+
+    /// The conversation whose contents the event reveals, if it names one. A lock changing says
+    /// nothing of what's inside, so it isn't counted.
+    pub fn conversation_id(&self) -> Option<&str> {
+        match self {
+            AppEvent::ChatStarted { conversation_id, .. }
+            | AppEvent::ContextReduced { conversation_id, .. }
+            | AppEvent::ContextCompressed { conversation_id, .. }
+            | AppEvent::ContextDeduplicated { conversation_id, .. }
+            | AppEvent::MemoriesLearned { conversation_id, .. }
+            | AppEvent::UnreadChanged { conversation_id, .. }
+            | AppEvent::OrchestrationEnded { conversation_id, .. }
+            | AppEvent::ExportStarted { conversation_id, .. } => Some(conversation_id),
+            AppEvent::ConversationsMerged { summary, .. } => Some(&summary.conversation_id),
+            AppEvent::PlanUpdated { plan } => Some(&plan.conversation_id),
+            _ => None,
+        }
+    }
+
+    /// The chat request an answer event belongs to; its conversation is only named by the
+    /// request's ChatStarted
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            AppEvent::ChatContent { request_id, .. }
+            | AppEvent::ChatCompleted { request_id, .. }
+            | AppEvent::ChatError { request_id, .. } => Some(request_id),
+            _ => None,
+        }
+    }
+}
+
+fn bus() -> &'static broadcast::Sender<AppEvent> {
+    static BUS: OnceLock<broadcast::Sender<AppEvent>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(BUS_CAPACITY).0)
+}
+
+/// Publish an event to all current subscribers; a no-op when nobody is listening
+pub fn publish(event: AppEvent) {
+    let _ = bus().send(event);
+}
+
+/// Subscribe to all events published from now on
+pub fn subscribe() -> broadcast::Receiver<AppEvent> {
+    bus().subscribe()
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::events::{self, AppEvent};
+use crate::history::{HistoryMessage, HistoryStore};
+use crate::tr;
+
+/// Messages written between progress events
+const PROGRESS_EVERY: u32 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ExportSummary {
+    pub export_id: String,
+    pub path: String,
+    pub messages: u32,
+    pub bytes: u64,
+}
+
+/// Cancellation flags of exports in progress, by export id
+fn running() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    static RUNNING: std::sync::OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = std::sync::OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn running_count() -> u32 {
+    running().lock().map(|running| running.len() as u32).unwrap_or(0)
+}
+
+/// Ask a running export to stop; it removes its partial file and fails with "Export cancelled"
+pub fn cancel(export_id: &str) -> Result<()> {
+    let running = running().lock().map_err(|_| anyhow::anyhow!("Export state is poisoned"))?;
+    let flag = running.get(export_id).ok_or_else(|| anyhow::anyhow!(tr!("export-not-running", export_id = export_id)))?;
+    flag.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Write a conversation to `dest` one message at a time, so memory use doesn't grow with its
+/// length. Publishes ExportStarted, then ExportProgress every PROGRESS_EVERY messages.
+pub fn export(store: &HistoryStore, conversation_id: &str, dest: &Path, format: ExportFormat) -> Result<ExportSummary> {
+    let export_id = uuid::Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    running().lock().map_err(|_| anyhow::anyhow!("Export state is poisoned"))?
+        .insert(export_id.clone(), cancelled.clone());
+
+    let partial = dest.with_extension("partial");
+    let result = write_export(store, conversation_id, &partial, format, &export_id, &cancelled, dest);
+    if let Ok(mut running) = running().lock() {
+        running.remove(&export_id);
+    }
+    let messages = match result {
+        Ok(messages) => messages,
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&partial, dest)?;
+
+    let bytes = std::fs::metadata(dest)?.len();
+    log::info!("Exported {} messages of {} to {} ({} bytes)", messages, conversation_id, dest.display(), bytes);
+    Ok(ExportSummary { export_id, path: dest.display().to_string(), messages, bytes })
+}
+
+fn write_export(
+    store: &HistoryStore,
+    conversation_id: &str,
+    partial: &Path,
+    format: ExportFormat,
+    export_id: &str,
+    cancelled: &AtomicBool,
+    dest: &Path,
+) -> Result<u32> {
+    let total = store.message_count_in(conversation_id)?;
+    events::publish(AppEvent::ExportStarted {
+        export_id: export_id.to_string(),
+        conversation_id: conversation_id.to_string(),
+        path: dest.display().to_string(),
+        total,
+    });
+
+    if let Some(parent) = partial.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut out = BufWriter::new(File::create(partial)?);
+    let exported_at = chrono::Utc::now().to_rfc3339();
+    match format {
+        ExportFormat::Markdown => write!(out, "# Conversation {}\n\nExported {}\n", conversation_id, exported_at)?,
+        ExportFormat::Json => write!(
+            out,
+            "{{\"conversation_id\":{},\"exported_at\":{},\"messages\":[",
+            serde_json::to_string(conversation_id)?,
+            serde_json::to_string(&exported_at)?
+        )?,
+    }
+
+    let mut written = 0u32;
+    store.for_each_message(conversation_id, |message| {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(anyhow::anyhow!(tr!("export-cancelled")));
+        }
+        match format {
+            ExportFormat::Markdown => write_markdown(&mut out, message)?,
+            ExportFormat::Json => {
+                if written > 0 {
+                    out.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut out, message)?;
+            }
+        }
+        written += 1;
+        if written.is_multiple_of(PROGRESS_EVERY) {
+            events::publish(AppEvent::ExportProgress { export_id: export_id.to_string(), written, total });
+        }
+        Ok(())
+    })?;
+
+    if format == ExportFormat::Json {
+        out.write_all(b"]}")?;
+    }
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    events::publish(AppEvent::ExportProgress { export_id: export_id.to_string(), written, total: written });
+    Ok(written)
+}
+
+fn write_markdown(out: &mut impl Write, message: &HistoryMessage) -> Result<()> {
+    let role = match message.role.as_str() {
+        "user" => "User",
+        "assistant" => "Assistant",
+        "system" => "System",
+        other => other,
+    };
+    write!(out, "\n## {}", role)?;
+    if let Some(model) = &message.model {
+        write!(out, " ({})", model)?;
+    }
+    write!(out, "\n\n_{}_\n\n{}\n", message.created_at, message.content.trim_end())?;
+    Ok(())
+}
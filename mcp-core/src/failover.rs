@@ -0,0 +1,99 @@
+//! Falling back to other providers when one can't answer. Each provider can have a chain of
+//! models on other providers; when a request to it is rate limited, fails on the provider's
+//! side or gets no first token in time, the request is sent to the next model in the chain.
+//! Rejections that would fail anywhere (a bad key, a context overflow) aren't retried.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use crate::providers::{self, Provider};
+use crate::status;
+use crate::tr;
+
+/// How long a provider with a fallback gets to send its first token
+pub const FIRST_TOKEN_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Wording of errors that mean the provider, not the request, is the problem
+const TRANSIENT: [&str; 10] = [
+    "timed out",
+    "timeout",
+    "overloaded",
+    "server_error",
+    "internal server error",
+    "bad gateway",
+    "service unavailable",
+    "gateway timeout",
+    "error sending request",
+    "connection reset",
+];
+
+/// `chain` trimmed and without repeats, or a localized error if it would fall back to
+/// `provider` itself
+pub fn validate(provider: Provider, chain: Vec<String>) -> Result<Vec<String>, String> {
+    let mut validated: Vec<String> = Vec::new();
+    for model in chain {
+        let model = model.trim().to_string();
+        if model.is_empty() || validated.contains(&model) {
+            continue;
+        }
+        if providers::resolve(&model).0 == provider {
+            return Err(tr!("failover-same-provider", model = model, provider = provider.name()));
+        }
+        validated.push(model);
+    }
+    Ok(validated)
+}
+
+/// The models to try, in order, if `model` can't answer
+pub fn fallbacks<'a>(chains: &'a BTreeMap<String, Vec<String>>, model: &str) -> &'a [String] {
+    chains.get(providers::resolve(model).0.name()).map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Whether a request that failed with `error` should be sent to the next provider: rate
+/// limits, server errors (5xx) and timeouts
+pub fn should_fail_over(error: &str) -> bool {
+    let lowered = error.to_lowercase();
+    status::is_rate_limited(error)
+        || TRANSIENT.iter().any(|pattern| lowered.contains(pattern))
+        || http_status(error).is_some_and(|status| (500..600).contains(&status))
+}
+
+/// The HTTP status a provider error carries, as its code or leading its message
+fn http_status(error: &str) -> Option<u16> {
+    let code = error.rsplit_once("(code: ").map(|(_, code)| code).unwrap_or(error);
+    let digits: String = code.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok().filter(|_| digits.len() == 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_provider_side_failures_fall_over() {
+        assert!(should_fail_over("server_error: The server had an error (code: 500)"));
+        assert!(should_fail_over("Upstream error (code: 502)"));
+        assert!(should_fail_over("503 Service Unavailable: <html>"));
+        assert!(should_fail_over("Rate limit reached for requests (code: rate_limit_exceeded)"));
+        assert!(should_fail_over("overloaded_error: Overloaded (code: 529)"));
+        assert!(should_fail_over("http error: error sending request for url (https://api.groq.com/openai/v1/chat/completions)"));
+        assert!(!should_fail_over("invalid_request_error: This model's maximum context length is 8192 tokens (code: 400)"));
+        assert!(!should_fail_over("Incorrect API key provided (code: invalid_api_key)"));
+        assert!(!should_fail_over("Asked for 5000 tokens"));
+    }
+
+    #[test]
+    fn chains_fall_back_to_other_providers() {
+        let chain = validate(Provider::OpenAi, vec![
+            " groq/llama-3.3-70b-versatile ".to_string(),
+            "anthropic/claude-sonnet-4-5".to_string(),
+            "groq/llama-3.3-70b-versatile".to_string(),
+            String::new(),
+        ]).unwrap();
+        assert_eq!(chain, ["groq/llama-3.3-70b-versatile", "anthropic/claude-sonnet-4-5"]);
+        assert!(validate(Provider::OpenAi, vec!["openai/gpt-4o-mini".to_string()]).is_err());
+
+        let chains = BTreeMap::from([(Provider::OpenAi.name().to_string(), chain)]);
+        assert_eq!(fallbacks(&chains, "openai/gpt-4o").len(), 2);
+        assert!(fallbacks(&chains, "groq/llama-3.3-70b-versatile").is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::config::ConfigManager;
+use crate::tr;
+
+/// Experimental parts of the app that can be switched off without a new build
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, TS)]
+#[ts(export)]
+pub enum Feature {
+    /// The agent app mode, where tool calls run without asking
+    AgentMode,
+    /// The local HTTP API server
+    ApiServer,
+    /// WASM plugins and the commands and tools they register
+    Plugins,
+}
+
+pub const FEATURES: [Feature; 3] = [Feature::AgentMode, Feature::ApiServer, Feature::Plugins];
+
+/// Where a flag's current value comes from, highest precedence first
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum FlagSource {
+    /// `MCP_SWITCHBOARD_FEATURE_<NAME>`, from the environment or `.env`; set_feature_flag can't change it while it's set
+    Environment,
+    Config,
+    Default,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct FeatureFlag {
+    pub feature: Feature,
+    pub enabled: bool,
+    pub default_enabled: bool,
+    pub source: FlagSource,
+    /// Localized name, e.g. for the settings page and "turned off" errors
+    pub name: String,
+    pub description: String,
+}
+
+impl Feature {
+    /// Agent mode hasn't shipped yet; the others predate the flags and stay on
+    pub fn default_enabled(self) -> bool {
+        match self {
+            Feature::AgentMode => false,
+            Feature::ApiServer | Feature::Plugins => true,
+        }
+    }
+
+    fn env_var(self) -> &'static str {
+        match self {
+            Feature::AgentMode => "MCP_SWITCHBOARD_FEATURE_AGENT_MODE",
+            Feature::ApiServer => "MCP_SWITCHBOARD_FEATURE_API_SERVER",
+            Feature::Plugins => "MCP_SWITCHBOARD_FEATURE_PLUGINS",
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Feature::AgentMode => tr!("feature-agent-mode"),
+            Feature::ApiServer => tr!("feature-api-server"),
+            Feature::Plugins => tr!("feature-plugins"),
+        }
+    }
+
+    fn description(self) -> String {
+        match self {
+            Feature::AgentMode => tr!("feature-agent-mode-description"),
+            Feature::ApiServer => tr!("feature-api-server-description"),
+            Feature::Plugins => tr!("feature-plugins-description"),
+        }
+    }
+}
+
+fn parse_env(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// The flag for `feature` given its environment variable and the values saved in config
+pub fn resolve(feature: Feature, env: Option<&str>, saved: &BTreeMap<Feature, bool>) -> FeatureFlag {
+    let env = env.and_then(|value| {
+        let parsed = parse_env(value);
+        if parsed.is_none() {
+            log::warn!("Ignoring {}={:?}, expected 1 or 0", feature.env_var(), value);
+        }
+        parsed
+    });
+    let (enabled, source) = match (env, saved.get(&feature)) {
+        (Some(enabled), _) => (enabled, FlagSource::Environment),
+        (None, Some(&enabled)) => (enabled, FlagSource::Config),
+        (None, None) => (feature.default_enabled(), FlagSource::Default),
+    };
+    FeatureFlag {
+        feature,
+        enabled,
+        default_enabled: feature.default_enabled(),
+        source,
+        name: feature.name(),
+        description: feature.description(),
+    }
+}
+
+pub fn list(config_manager: &ConfigManager) -> anyhow::Result<Vec<FeatureFlag>> {
+    let saved = config_manager.get_feature_flags()?;
+    Ok(FEATURES.iter()
+        .map(|&feature| resolve(feature, crate::environment::value(feature.env_var()).as_deref(), &saved))
+        .collect())
+}
+
+/// Whether `feature` is on. If config can't be read the default applies, so a broken config
+/// doesn't switch experimental code on.
+pub fn enabled(feature: Feature) -> bool {
+    let saved = ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_feature_flags())
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read feature flags, using defaults: {}", e);
+            BTreeMap::new()
+        });
+    resolve(feature, crate::environment::value(feature.env_var()).as_deref(), &saved).enabled
+}
+
+/// Err with a localized message unless `feature` is on
+pub fn require(feature: Feature) -> Result<(), String> {
+    if enabled(feature) {
+        Ok(())
+    } else {
+        log::warn!("Refused use of {:?}, which is turned off", feature);
+        Err(tr!("feature-disabled", feature = feature.name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_beats_config_beats_default() {
+        let saved = BTreeMap::from([(Feature::Plugins, false)]);
+        let flag = resolve(Feature::Plugins, None, &saved);
+        assert_eq!((flag.enabled, flag.source), (false, FlagSource::Config));
+        let flag = resolve(Feature::Plugins, Some("1"), &saved);
+        assert_eq!((flag.enabled, flag.source), (true, FlagSource::Environment));
+        let flag = resolve(Feature::AgentMode, None, &saved);
+        assert_eq!((flag.enabled, flag.source), (false, FlagSource::Default));
+    }
+
+    #[test]
+    fn unrecognized_environment_values_are_ignored() {
+        let flag = resolve(Feature::ApiServer, Some("maybe"), &BTreeMap::new());
+        assert_eq!((flag.enabled, flag.source), (true, FlagSource::Default));
+        assert!(resolve(Feature::AgentMode, Some(" On "), &BTreeMap::new()).enabled);
+    }
+}
@@ -0,0 +1,274 @@
+//! LLM gateways a provider's requests can be routed through, for teams that already run one
+//! for keys, budgets and logging. Each preset knows where its gateway listens, which headers
+//! carry the gateway's key and virtual key, and whose names its model list uses:
+//!
+//! - LiteLLM is self-hosted. Its virtual key replaces the provider's key, and it lists the
+//!   model names from its own config.
+//! - Helicone has one host per provider. It takes the provider's key as usual, plus its own in
+//!   `Helicone-Auth`, and passes the provider's model list through.
+//! - Portkey takes its key in `x-portkey-api-key` and either a virtual key that stands in for
+//!   the provider's key, or the provider to forward the provider's key to.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::providers::{self, Provider};
+use crate::tr;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayPreset {
+    LiteLlm,
+    Helicone,
+    Portkey,
+}
+
+pub const PRESETS: [GatewayPreset; 3] = [GatewayPreset::LiteLlm, GatewayPreset::Helicone, GatewayPreset::Portkey];
+
+const PORTKEY_API_BASE: &str = "https://api.portkey.ai/v1";
+
+/// How a provider's requests reach it through a gateway
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct GatewayRoute {
+    pub preset: GatewayPreset,
+    /// Where the gateway listens; required for LiteLLM, and replaces the hosted gateways' own
+    /// address for self-hosted or regional deployments
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// The gateway's own key; for LiteLLM, the virtual key requests are made with
+    pub gateway_key: String,
+    /// A Portkey virtual key, standing in for the provider's key
+    #[serde(default)]
+    pub virtual_key: Option<String>,
+}
+
+/// What the settings screen shows about a preset before it's set up
+#[derive(Serialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct GatewayPresetInfo {
+    pub preset: GatewayPreset,
+    pub name: String,
+    pub default_base_url: Option<String>,
+    /// The header the gateway's key is sent in
+    pub key_header: String,
+    pub takes_virtual_key: bool,
+    /// Whether the provider's own key is still needed
+    pub needs_provider_key: bool,
+    /// Whether the model picker lists the gateway's names for models rather than the provider's
+    pub lists_own_models: bool,
+    pub providers: Vec<Provider>,
+}
+
+impl GatewayPreset {
+    pub fn name(self) -> &'static str {
+        match self {
+            GatewayPreset::LiteLlm => "LiteLLM",
+            GatewayPreset::Helicone => "Helicone",
+            GatewayPreset::Portkey => "Portkey",
+        }
+    }
+
+    /// The providers the gateway can route to. Azure deployments and custom endpoints have
+    /// addresses of their own, Ollama listens locally and Bedrock's requests are signed for its
+    /// own hosts, so none of them goes through a gateway.
+    pub fn supports(self, provider: Provider) -> bool {
+        match self {
+            GatewayPreset::Helicone => helicone_api_base(provider).is_some(),
+            GatewayPreset::LiteLlm | GatewayPreset::Portkey => !matches!(provider, Provider::Azure | Provider::Ollama | Provider::Endpoint | Provider::Bedrock),
+        }
+    }
+
+    /// Whether the gateway answers `GET /models` with its own names for models, in OpenAI's
+    /// format, rather than passing the provider's list through
+    pub fn lists_own_models(self) -> bool {
+        self == GatewayPreset::LiteLlm
+    }
+
+    pub fn info(self) -> GatewayPresetInfo {
+        GatewayPresetInfo {
+            preset: self,
+            name: self.name().to_string(),
+            default_base_url: match self {
+                GatewayPreset::LiteLlm => None,
+                GatewayPreset::Helicone => helicone_api_base(Provider::OpenAi).map(str::to_string),
+                GatewayPreset::Portkey => Some(PORTKEY_API_BASE.to_string()),
+            },
+            key_header: match self {
+                GatewayPreset::LiteLlm => "Authorization",
+                GatewayPreset::Helicone => "Helicone-Auth",
+                GatewayPreset::Portkey => "x-portkey-api-key",
+            }.to_string(),
+            takes_virtual_key: self == GatewayPreset::Portkey,
+            needs_provider_key: self == GatewayPreset::Helicone,
+            lists_own_models: self.lists_own_models(),
+            providers: providers::ALL.into_iter().filter(|provider| self.supports(*provider)).collect(),
+        }
+    }
+}
+
+fn helicone_api_base(provider: Provider) -> Option<&'static str> {
+    match provider {
+        Provider::Together => Some("https://together.helicone.ai/v1"),
+        Provider::OpenAi => Some("https://oai.helicone.ai/v1"),
+        Provider::Anthropic => Some("https://anthropic.helicone.ai/v1"),
+        Provider::OpenRouter => Some("https://openrouter.helicone.ai/api/v1"),
+        Provider::Groq => Some("https://groq.helicone.ai/openai/v1"),
+        Provider::Azure | Provider::Ollama | Provider::Endpoint | Provider::Bedrock => None,
+    }
+}
+
+/// The name Portkey knows `provider` by
+fn portkey_provider(provider: Provider) -> Option<&'static str> {
+    match provider {
+        Provider::Together => Some("together-ai"),
+        Provider::OpenAi => Some("openai"),
+        Provider::Anthropic => Some("anthropic"),
+        Provider::OpenRouter => Some("openrouter"),
+        Provider::Groq => Some("groq"),
+        Provider::Azure | Provider::Ollama | Provider::Endpoint | Provider::Bedrock => None,
+    }
+}
+
+/// `route` trimmed, or a localized error if it can't carry `provider`'s requests
+pub fn validate(provider: Provider, mut route: GatewayRoute) -> Result<GatewayRoute, String> {
+    let trim = |value: Option<String>| value.map(|v| v.trim().trim_end_matches('/').to_string()).filter(|v| !v.is_empty());
+    route.base_url = trim(route.base_url);
+    route.virtual_key = trim(route.virtual_key).filter(|_| route.preset == GatewayPreset::Portkey);
+    route.gateway_key = route.gateway_key.trim().to_string();
+    if !route.preset.supports(provider) {
+        return Err(tr!("gateway-provider-unsupported", gateway = route.preset.name(), provider = provider.name()));
+    }
+    if route.gateway_key.is_empty() {
+        return Err(tr!("gateway-key-missing", gateway = route.preset.name()));
+    }
+    match &route.base_url {
+        Some(url) if !url.starts_with("https://") && !url.starts_with("http://") => {
+            return Err(tr!("custom-model-url-invalid", url = url.clone()));
+        }
+        None if route.preset == GatewayPreset::LiteLlm => return Err(tr!("gateway-url-missing", gateway = route.preset.name())),
+        _ => {}
+    }
+    Ok(route)
+}
+
+/// Where `provider`'s API is reached through the gateway
+pub fn api_base(route: &GatewayRoute, provider: Provider) -> String {
+    match (&route.base_url, route.preset) {
+        // LiteLLM serves the OpenAI routes under /v1 as well as at its root
+        (Some(url), GatewayPreset::LiteLlm) if !url.ends_with("/v1") => format!("{}/v1", url),
+        (Some(url), _) => url.clone(),
+        (None, GatewayPreset::Helicone) => helicone_api_base(provider).unwrap_or(provider.api_base()).to_string(),
+        (None, _) => PORTKEY_API_BASE.to_string(),
+    }
+}
+
+/// The key requests are authorized with: LiteLLM's virtual key, or the provider's own, which
+/// a Portkey virtual key makes unnecessary
+pub fn api_key(route: &GatewayRoute, provider_key: Option<String>) -> Option<String> {
+    match route.preset {
+        GatewayPreset::LiteLlm => Some(route.gateway_key.clone()),
+        GatewayPreset::Helicone => provider_key,
+        GatewayPreset::Portkey => provider_key.or_else(|| route.virtual_key.as_ref().map(|_| "portkey-virtual-key".to_string())),
+    }
+}
+
+/// The headers the gateway needs besides the key
+pub fn headers(route: &GatewayRoute, provider: Provider) -> BTreeMap<String, String> {
+    let mut headers = BTreeMap::new();
+    match route.preset {
+        GatewayPreset::LiteLlm => {}
+        GatewayPreset::Helicone => {
+            headers.insert("Helicone-Auth".to_string(), format!("Bearer {}", route.gateway_key));
+        }
+        GatewayPreset::Portkey => {
+            headers.insert("x-portkey-api-key".to_string(), route.gateway_key.clone());
+            match (&route.virtual_key, portkey_provider(provider)) {
+                (Some(virtual_key), _) => headers.insert("x-portkey-virtual-key".to_string(), virtual_key.clone()),
+                (None, Some(name)) => headers.insert("x-portkey-provider".to_string(), name.to_string()),
+                (None, None) => None,
+            };
+        }
+    }
+    headers
+}
+
+/// The models in a gateway's own `GET /models` list, named as the gateway names them and
+/// listed under `provider`
+pub fn models(route: &GatewayRoute, provider: Provider, response: &serde_json::Value) -> Result<Vec<ModelInfo>, String> {
+    let data = response["data"].as_array().ok_or_else(|| {
+        log::error!("{} models response has no data array", route.preset.name());
+        "Invalid models response format".to_string()
+    })?;
+    let mut models: Vec<ModelInfo> = data.iter()
+        .filter_map(|model| model["id"].as_str())
+        .map(|id| ModelInfo {
+            id: providers::qualified_id(provider, id),
+            display_name: id.to_string(),
+            organization: route.preset.name().to_string(),
+            context_length: None,
+            pricing: None,
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(preset: GatewayPreset, base_url: Option<&str>, virtual_key: Option<&str>) -> GatewayRoute {
+        GatewayRoute {
+            preset,
+            base_url: base_url.map(str::to_string),
+            gateway_key: " gw-key ".to_string(),
+            virtual_key: virtual_key.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn each_preset_reaches_the_provider_its_own_way() {
+        let litellm = validate(Provider::Anthropic, route(GatewayPreset::LiteLlm, Some("http://litellm.internal:4000/"), None)).unwrap();
+        assert_eq!(api_base(&litellm, Provider::Anthropic), "http://litellm.internal:4000/v1");
+        assert_eq!(api_key(&litellm, None).as_deref(), Some("gw-key"));
+        assert!(headers(&litellm, Provider::Anthropic).is_empty());
+
+        let helicone = validate(Provider::Groq, route(GatewayPreset::Helicone, None, Some("ignored"))).unwrap();
+        assert_eq!(helicone.virtual_key, None);
+        assert_eq!(api_base(&helicone, Provider::Groq), "https://groq.helicone.ai/openai/v1");
+        assert_eq!(api_key(&helicone, Some("gsk".to_string())).as_deref(), Some("gsk"));
+        assert_eq!(headers(&helicone, Provider::Groq)["Helicone-Auth"], "Bearer gw-key");
+
+        let portkey = validate(Provider::OpenAi, route(GatewayPreset::Portkey, None, Some("openai-prod-1a2b"))).unwrap();
+        assert_eq!(api_base(&portkey, Provider::OpenAi), PORTKEY_API_BASE);
+        assert!(api_key(&portkey, None).is_some());
+        let sent = headers(&portkey, Provider::OpenAi);
+        assert_eq!((sent["x-portkey-api-key"].as_str(), sent["x-portkey-virtual-key"].as_str()), ("gw-key", "openai-prod-1a2b"));
+        let forwarded = headers(&validate(Provider::Together, route(GatewayPreset::Portkey, None, None)).unwrap(), Provider::Together);
+        assert_eq!(forwarded["x-portkey-provider"], "together-ai");
+        assert_eq!(providers::validate_headers(sent.clone()).unwrap(), sent);
+    }
+
+    #[test]
+    fn routes_need_what_their_gateway_needs() {
+        assert!(validate(Provider::OpenAi, route(GatewayPreset::LiteLlm, None, None)).is_err());
+        assert!(validate(Provider::Ollama, route(GatewayPreset::Helicone, None, None)).is_err());
+        assert!(validate(Provider::Azure, route(GatewayPreset::Portkey, None, None)).is_err());
+        let mut keyless = route(GatewayPreset::Portkey, None, None);
+        keyless.gateway_key = String::new();
+        assert!(validate(Provider::OpenAi, keyless).is_err());
+        assert!(!GatewayPreset::Helicone.info().providers.contains(&Provider::Azure));
+    }
+
+    #[test]
+    fn gateway_model_lists_keep_the_gateways_names() {
+        let response = serde_json::json!({ "data": [{ "id": "claude-team-default" }, { "id": "claude-fast" }] });
+        let litellm = route(GatewayPreset::LiteLlm, Some("http://localhost:4000"), None);
+        let listed = models(&litellm, Provider::Anthropic, &response).unwrap();
+        assert_eq!(listed[0].id, "anthropic/claude-fast");
+        assert_eq!(listed[0].organization, "LiteLLM");
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use ts_rs::TS;
+use crate::cost::{PlannedRequest, ASSUMED_RESPONSE_TOKENS};
+use crate::events::{self, AppEvent};
+use crate::guardrails::{self, Guardrails};
+use crate::history::Candidate;
+use crate::StreamMessage;
+
+pub type MessageStream = Pin<Box<dyn Stream<Item = StreamMessage> + Send>>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, TS)]
+#[ts(export)]
+pub enum GenerationMode {
+    /// One request to the preferred model
+    #[default]
+    Standard,
+    /// Ask `fast_model` and the preferred model at once; show the fast answer, then swap in the preferred one
+    Race,
+    /// `fast_model` writes a draft, then the preferred model critiques and rewrites it
+    DraftRefine,
+    /// Sample `sample_count` answers from the preferred model in parallel and keep the best
+    BestOfN,
+}
+
+/// How BestOfN picks the answer from its candidates
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, TS)]
+#[ts(export)]
+pub enum CandidateSelection {
+    /// The preferred model reads every candidate and writes the final answer
+    #[default]
+    Judge,
+    /// Show the first candidate; the user can switch to another from history
+    User,
+}
+
+/// Default number of BestOfN samples
+pub const DEFAULT_SAMPLE_COUNT: u32 = 3;
+
+/// Most BestOfN samples allowed per message
+pub const MAX_SAMPLE_COUNT: u32 = 8;
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct GenerationSettings {
+    pub mode: GenerationMode,
+    /// Cheap, low-latency model used by Race and DraftRefine modes
+    pub fast_model: Option<String>,
+    /// Persona id whose content is sent as the system prompt
+    #[serde(default)]
+    pub active_persona: Option<String>,
+    /// Modes that override `mode` while a given persona is active
+    #[serde(default)]
+    pub persona_modes: HashMap<String, GenerationMode>,
+    /// Answers sampled per message in BestOfN mode
+    #[serde(default = "default_sample_count")]
+    pub sample_count: u32,
+    #[serde(default)]
+    pub selection: CandidateSelection,
+    /// Record each token's probability, where the provider reports them, for the confidence view
+    #[serde(default)]
+    pub token_logprobs: bool,
+    /// Sent with each Standard answer to providers that take one, so the same prompt and
+    /// settings give the same answer; None to sample freely
+    #[serde(default)]
+    pub seed: Option<u32>,
+}
+
+fn default_sample_count() -> u32 {
+    DEFAULT_SAMPLE_COUNT
+}
+
+impl Default for GenerationSettings {
+    fn default() -> Self {
+        GenerationSettings {
+            mode: GenerationMode::Standard,
+            fast_model: None,
+            active_persona: None,
+            persona_modes: HashMap::new(),
+            sample_count: DEFAULT_SAMPLE_COUNT,
+            selection: CandidateSelection::Judge,
+            token_logprobs: false,
+            seed: None,
+        }
+    }
+}
+
+impl GenerationSettings {
+    /// The mode for the active persona, falling back to `mode`
+    pub fn effective_mode(&self) -> GenerationMode {
+        self.active_persona.as_ref()
+            .and_then(|persona| self.persona_modes.get(persona))
+            .copied()
+            .unwrap_or(self.mode)
+    }
+}
+
+/// Highest temperature a regeneration may ask for, as OpenAI-compatible providers accept
+pub const MAX_TEMPERATURE: f32 = 2.0;
+
+/// What to change when regenerating an answer; anything unset is as it would be for a new message
+#[derive(Serialize, Deserialize, Clone, Debug, Default, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct RegenerationOverrides {
+    pub model: Option<String>,
+    /// None for the provider's default
+    pub temperature: Option<f32>,
+    /// Ignored by providers that don't take a seed
+    pub seed: Option<u32>,
+}
+
+/// A replayed answer beside the original, and whether the two came out the same
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct Replay {
+    pub candidate: Candidate,
+    pub reproduced: bool,
+}
+
+/// The requests answering one message will make, for cost estimates. Modes that need a fast
+/// model fall back to Standard when it is unset or the same as `model`, as chat does.
+pub fn planned_requests(settings: &GenerationSettings, model: &str, prompt_tokens: u32) -> Vec<PlannedRequest> {
+    let request = |model: &str, prompt_tokens: u32| PlannedRequest { model: model.to_string(), prompt_tokens };
+    let fast_model = settings.fast_model.as_deref().filter(|fast| *fast != model);
+    match (settings.effective_mode(), fast_model) {
+        (GenerationMode::Race, Some(fast)) => vec![request(fast, prompt_tokens), request(model, prompt_tokens)],
+        (GenerationMode::DraftRefine, Some(fast)) => vec![
+            request(fast, prompt_tokens),
+            request(model, prompt_tokens + ASSUMED_RESPONSE_TOKENS),
+        ],
+        (GenerationMode::BestOfN, _) => {
+            let mut requests: Vec<_> = (0..settings.sample_count).map(|_| request(model, prompt_tokens)).collect();
+            if settings.selection == CandidateSelection::Judge {
+                requests.push(request(model, prompt_tokens + ASSUMED_RESPONSE_TOKENS * settings.sample_count));
+            }
+            requests
+        }
+        _ => vec![request(model, prompt_tokens)],
+    }
+}
+
+/// Prompt asking the preferred model to improve a draft of the answer to `prompt`
+pub fn refine_prompt(prompt: &str, draft: &str) -> String {
+    format!(
+        "{}\n\n---\nA draft answer to the request above follows. Check it for mistakes, gaps and weak \
+         writing, then reply with only the improved final answer. Do not mention the draft.\n\n{}",
+        prompt, draft
+    )
+}
+
+/// Prompt asking the preferred model to choose between or merge sampled answers to `prompt`
+pub fn judge_prompt(prompt: &str, candidates: &[String]) -> String {
+    let mut judge = format!(
+        "{}\n\n---\nSeveral candidate answers to the request above follow. Compare them for accuracy, \
+         completeness and clarity, then reply with only the best final answer, merging their strengths \
+         where that helps. Do not mention the candidates.",
+        prompt
+    );
+    for (i, candidate) in candidates.iter().enumerate() {
+        judge.push_str(&format!("\n\n### Candidate {}\n{}", i + 1, candidate));
+    }
+    judge
+}
+
+/// All text from a stream that must finish without an error
+pub async fn collect_text(mut stream: MessageStream) -> Result<String, String> {
+    let mut text = String::new();
+    while let Some(message) = stream.next().await {
+        match message {
+            StreamMessage::Content(content) => text.push_str(&content),
+            StreamMessage::Replace(replacement) => text = replacement,
+            StreamMessage::Error(e) => return Err(e),
+            StreamMessage::Complete => break,
+            StreamMessage::ProviderSwitched { .. } | StreamMessage::Usage { .. } | StreamMessage::AgentTurn { .. } => {}
+        }
+    }
+    Ok(text)
+}
+
+/// Run every sample to completion in parallel, then stream whatever `finish` builds from the
+/// answers that succeeded. Fails only if every sample fails.
+pub fn best_of_n<F, Fut>(samples: Vec<MessageStream>, finish: F) -> MessageStream
+where
+    F: FnOnce(Vec<String>) -> Fut + Send + 'static,
+    Fut: Future<Output = MessageStream> + Send + 'static,
+{
+    Box::pin(futures::stream::once(async move {
+        let requested = samples.len();
+        let mut candidates = Vec::new();
+        let mut last_error = None;
+        for result in futures::future::join_all(samples.into_iter().map(collect_text)).await {
+            match result {
+                Ok(text) if !text.trim().is_empty() => candidates.push(text),
+                Ok(_) => last_error = Some("Empty response".to_string()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        log::info!("{} of {} samples succeeded", candidates.len(), requested);
+        if candidates.is_empty() {
+            let error = last_error.unwrap_or_else(|| "No samples requested".to_string());
+            return Box::pin(futures::stream::once(async move { StreamMessage::Error(error) })) as MessageStream;
+        }
+        finish(candidates).await
+    }).flatten())
+}
+
+/// Buffer each response and check it against `guardrails`. A response that breaks a rule is
+/// never shown: `retry` is called with it and the corrective instructions, up to the configured
+/// number of times, and an error is returned if no attempt passes.
+pub fn guarded<F, Fut>(first: MessageStream, guardrails: Guardrails, mut retry: F) -> MessageStream
+where
+    F: FnMut(String, String) -> Fut + Send + 'static,
+    Fut: Future<Output = MessageStream> + Send + 'static,
+{
+    Box::pin(futures::stream::once(async move {
+        let mut stream = first;
+        let mut attempt = 0;
+        loop {
+            let text = match collect_text(stream).await {
+                Ok(text) => text,
+                Err(e) => return StreamMessage::Error(e),
+            };
+            let violations = guardrails.check(&text);
+            if violations.is_empty() {
+                return StreamMessage::Content(text);
+            }
+
+            attempt += 1;
+            let retrying = attempt <= guardrails.max_retries;
+            log::warn!("Response broke {} guardrail rules (attempt {})", violations.len(), attempt);
+            events::publish(AppEvent::GuardrailViolated { violations: violations.clone(), attempt, retrying });
+            if !retrying {
+                return StreamMessage::Error(format!("Response failed guardrails: {}", violations.join("; ")));
+            }
+            stream = retry(text, guardrails::correction_prompt(&violations)).await;
+        }
+    }))
+}
+
+/// Collect `draft` in full, then stream whatever `refine` builds from it.
+///
+/// Nothing from the draft is shown. If the draft fails or comes back empty, `refine` gets `None`
+/// and should answer the original prompt directly.
+pub fn draft_and_refine<F, Fut>(draft_model: String, draft: MessageStream, refine: F) -> MessageStream
+where
+    F: FnOnce(Option<String>) -> Fut + Send + 'static,
+    Fut: Future<Output = MessageStream> + Send + 'static,
+{
+    Box::pin(futures::stream::once(async move {
+        let draft = match collect_text(draft).await {
+            Err(e) => {
+                log::warn!("Draft from {} failed, answering without it: {}", draft_model, e);
+                None
+            }
+            Ok(text) if text.trim().is_empty() => {
+                log::warn!("Draft from {} was empty, answering without it", draft_model);
+                None
+            }
+            Ok(text) => Some(text),
+        };
+        events::publish(AppEvent::DraftCompleted {
+            draft_model,
+            draft_chars: draft.as_ref().map(|d| d.chars().count() as u32).unwrap_or(0),
+            used: draft.is_some(),
+        });
+        refine(draft).await
+    }).flatten())
+}
+
+/// Run the fast and preferred streams side by side.
+///
+/// Whichever produces the first token decides the race: if the preferred model is first the fast
+/// stream is dropped (cancelling its request) and the preferred answer streams as normal. Otherwise
+/// the fast answer streams immediately and, once the preferred answer is complete, the fast stream
+/// is dropped and a `Replace` carries the preferred answer. If one side fails the other is kept.
+/// Neither input should yield `Complete`; the returned stream ends when the race is decided.
+pub fn race(fast_model: String, fast: MessageStream, preferred_model: String, preferred: MessageStream) -> MessageStream {
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(run_race(fast_model, fast, preferred_model, preferred, tx));
+    Box::pin(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|message| (message, rx))
+    }))
+}
+
+async fn run_race(
+    fast_model: String,
+    mut fast: MessageStream,
+    preferred_model: String,
+    mut preferred: MessageStream,
+    tx: mpsc::Sender<StreamMessage>,
+) {
+    let mut fast_started = false;
+    let mut fast_done = false;
+    let mut fast_error = None;
+    let mut preferred_text = String::new();
+    // Passed on only if the preferred answer is the one shown
+    let mut preferred_switch = None;
+
+    let decide = |winner: &str| {
+        log::info!("Model race between {} and {} won by {}", fast_model, preferred_model, winner);
+        events::publish(AppEvent::ModelRaceDecided {
+            fast_model: fast_model.clone(),
+            preferred_model: preferred_model.clone(),
+            winner: winner.to_string(),
+        });
+    };
+
+    loop {
+        tokio::select! {
+            item = preferred.next() => match item {
+                Some(StreamMessage::Content(content)) if content.is_empty() => {}
+                Some(StreamMessage::Content(content)) if !fast_started => {
+                    // Preferred model answered first; the fast request is no longer needed
+                    drop(fast);
+                    decide(&preferred_model);
+                    if let Some(switched) = preferred_switch.take() {
+                        let _ = tx.send(switched).await;
+                    }
+                    if tx.send(StreamMessage::Content(content)).await.is_ok() {
+                        forward(preferred, &tx).await;
+                    }
+                    return;
+                }
+                Some(StreamMessage::Content(content)) => preferred_text.push_str(&content),
+                Some(StreamMessage::Replace(text)) => preferred_text = text,
+                Some(switched @ StreamMessage::ProviderSwitched { .. }) => preferred_switch = Some(switched),
+                // Both requests are paid for, whichever answer is shown
+                Some(usage @ StreamMessage::Usage { .. }) => {
+                    let _ = tx.send(usage).await;
+                }
+                // Only orchestrations mark turns, and they don't race
+                Some(StreamMessage::AgentTurn { .. }) => {}
+                Some(StreamMessage::Error(error)) => {
+                    log::warn!("Preferred model {} failed during race: {}", preferred_model, error);
+                    decide(&fast_model);
+                    if let Some(fast_error) = fast_error {
+                        let _ = tx.send(StreamMessage::Error(fast_error)).await;
+                    } else if !fast_done {
+                        forward(fast, &tx).await;
+                    }
+                    return;
+                }
+                Some(StreamMessage::Complete) | None => {
+                    drop(fast);
+                    decide(&preferred_model);
+                    if fast_started {
+                        if let Some(switched) = preferred_switch.take() {
+                            let _ = tx.send(switched).await;
+                        }
+                        let _ = tx.send(StreamMessage::Replace(preferred_text)).await;
+                    }
+                    return;
+                }
+            },
+            item = fast.next(), if !fast_done => match item {
+                Some(StreamMessage::Content(content)) if content.is_empty() => {}
+                Some(StreamMessage::Content(content)) => {
+                    fast_started = true;
+                    if tx.send(StreamMessage::Content(content)).await.is_err() {
+                        return;
+                    }
+                }
+                Some(StreamMessage::Error(error)) => {
+                    log::warn!("Fast model {} failed during race: {}", fast_model, error);
+                    fast_done = true;
+                    fast_error = Some(error);
+                    if !fast_started {
+                        // Nothing shown yet, so just stream the preferred answer
+                        decide(&preferred_model);
+                        if let Some(switched) = preferred_switch.take() {
+                            let _ = tx.send(switched).await;
+                        }
+                        forward(preferred, &tx).await;
+                        return;
+                    }
+                }
+                Some(usage @ StreamMessage::Usage { .. }) => {
+                    if tx.send(usage).await.is_err() {
+                        return;
+                    }
+                }
+                Some(_) | None => fast_done = true,
+            },
+        }
+    }
+}
+
+async fn forward(mut stream: MessageStream, tx: &mpsc::Sender<StreamMessage>) {
+    while let Some(message) = stream.next().await {
+        if tx.send(message).await.is_err() {
+            return;
+        }
+    }
+}
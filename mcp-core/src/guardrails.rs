@@ -0,0 +1,145 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+
+/// Corrective re-prompts allowed by default before a response is rejected
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+
+/// Rules every response must pass before it is shown. While enabled, responses are
+/// buffered rather than streamed so a failing answer never reaches the user.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct GuardrailConfig {
+    pub enabled: bool,
+    /// Regular expressions the response must not match
+    pub banned_patterns: Vec<String>,
+    /// JSON Schema the response must satisfy; a surrounding ```json fence is allowed
+    pub json_schema: Option<String>,
+    pub max_chars: Option<u32>,
+    /// Words that must not appear, matched case-insensitively as whole words
+    pub profanity: Vec<String>,
+    /// Corrective re-prompts before giving up
+    pub max_retries: u32,
+}
+
+impl Default for GuardrailConfig {
+    fn default() -> Self {
+        GuardrailConfig {
+            enabled: false,
+            banned_patterns: Vec::new(),
+            json_schema: None,
+            max_chars: None,
+            profanity: Vec::new(),
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+}
+
+/// A compiled `GuardrailConfig`
+pub struct Guardrails {
+    banned: Vec<Regex>,
+    profanity: Option<Regex>,
+    schema: Option<jsonschema::Validator>,
+    max_chars: Option<u32>,
+    pub max_retries: u32,
+}
+
+impl Guardrails {
+    pub fn compile(config: &GuardrailConfig) -> Result<Self> {
+        let banned = config.banned_patterns.iter()
+            .map(|pattern| Regex::new(pattern).map_err(|e| anyhow::anyhow!("Invalid banned pattern {}: {}", pattern, e)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let words: Vec<String> = config.profanity.iter()
+            .map(|word| word.trim())
+            .filter(|word| !word.is_empty())
+            .map(regex::escape)
+            .collect();
+        let profanity = if words.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|")))?)
+        };
+
+        let schema = match &config.json_schema {
+            Some(schema) => {
+                let schema: serde_json::Value = serde_json::from_str(schema)
+                    .map_err(|e| anyhow::anyhow!("JSON schema is not valid JSON: {}", e))?;
+                Some(jsonschema::validator_for(&schema).map_err(|e| anyhow::anyhow!("Invalid JSON schema: {}", e))?)
+            }
+            None => None,
+        };
+
+        Ok(Guardrails {
+            banned,
+            profanity,
+            schema,
+            max_chars: config.max_chars,
+            max_retries: config.max_retries,
+        })
+    }
+
+    /// Every rule the response breaks, as instructions the model can act on
+    pub fn check(&self, response: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        for pattern in &self.banned {
+            if let Some(found) = pattern.find(response) {
+                violations.push(format!("Remove \"{}\"; it matches the banned pattern {}", found.as_str(), pattern));
+            }
+        }
+
+        if let Some(profanity) = &self.profanity {
+            let mut words: Vec<String> = profanity.find_iter(response).map(|m| m.as_str().to_lowercase()).collect();
+            words.sort();
+            words.dedup();
+            if !words.is_empty() {
+                violations.push(format!("Do not use these words: {}", words.join(", ")));
+            }
+        }
+
+        if let Some(max_chars) = self.max_chars {
+            let length = response.chars().count();
+            if length > max_chars as usize {
+                violations.push(format!("Keep the answer under {} characters; it was {}", max_chars, length));
+            }
+        }
+
+        if let Some(schema) = &self.schema {
+            match serde_json::from_str::<serde_json::Value>(strip_json_fence(response)) {
+                Ok(value) => {
+                    let errors: Vec<String> = schema.iter_errors(&value)
+                        .map(|e| match e.instance_path.to_string() {
+                            path if path.is_empty() => e.to_string(),
+                            path => format!("{} at {}", e, path),
+                        })
+                        .collect();
+                    if !errors.is_empty() {
+                        violations.push(format!("The JSON does not match the required schema: {}", errors.join("; ")));
+                    }
+                }
+                Err(e) => violations.push(format!("Reply with only valid JSON; it failed to parse: {}", e)),
+            }
+        }
+
+        violations
+    }
+}
+
+/// Follow-up asking the model to fix the rules its last answer broke
+pub fn correction_prompt(violations: &[String]) -> String {
+    format!(
+        "Your previous answer broke these rules:\n- {}\nRewrite the complete answer so it follows every rule. Reply with only the corrected answer.",
+        violations.join("\n- ")
+    )
+}
+
+fn strip_json_fence(response: &str) -> &str {
+    let trimmed = response.trim();
+    trimmed.strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(trimmed)
+}
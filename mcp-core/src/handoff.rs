@@ -0,0 +1,110 @@
+//! Handing a live conversation over to another device. The handoff carries what isn't in the
+//! history yet: the unsent draft, the tools the session had, and an interrupted or still
+//! streaming answer for the new device to resume. Paired devices get it over their chat
+//! WebSocket; the user's other desktops get it as a sync record.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::history::{HistoryMessage, MessageStatus};
+use crate::plugins::{PluginInfo, PluginStatus};
+
+/// Handoffs not accepted within this long are no longer offered
+pub const PENDING_HOURS: i64 = 24;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum HandoffTransport {
+    /// To a paired device, over the API server
+    Server,
+    /// To another desktop, through the sync backend
+    Sync,
+}
+
+impl HandoffTransport {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HandoffTransport::Server => "server",
+            HandoffTransport::Sync => "sync",
+        }
+    }
+
+    pub fn parse(value: &str) -> HandoffTransport {
+        match value {
+            "sync" => HandoffTransport::Sync,
+            _ => HandoffTransport::Server,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct Handoff {
+    pub id: String,
+    pub conversation_id: String,
+    /// Sync device id of the sender, or its machine id without sync
+    pub from_device: String,
+    /// Paired device (token) id or sync device id
+    pub to_device: String,
+    pub transport: HandoffTransport,
+    /// Text typed but not sent yet
+    pub draft: String,
+    /// `plugin::tool` names the session could call
+    pub enabled_tools: Vec<String>,
+    /// An answer cut off or still streaming when the conversation left, for `resume_response`
+    pub resume_message_id: Option<String>,
+    pub sent_at: String,
+    pub accepted_at: Option<String>,
+}
+
+/// Tools of the plugins that are loaded, as `plugin::tool`
+pub fn enabled_tools(plugins: &[PluginInfo]) -> Vec<String> {
+    plugins.iter()
+        .filter(|plugin| plugin.status == PluginStatus::Loaded)
+        .flat_map(|plugin| plugin.tools.iter().map(move |tool| format!("{}::{}", plugin.name, tool.name)))
+        .collect()
+}
+
+/// The conversation's last message, if it's an answer that hasn't finished
+pub fn resume_target(messages: &[HistoryMessage]) -> Option<String> {
+    messages.last()
+        .filter(|message| message.role == "assistant" && message.status == MessageStatus::Partial)
+        .map(|message| message.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::PluginTool;
+
+    #[test]
+    fn carries_loaded_tools_and_the_unfinished_answer() {
+        let plugin = |name: &str, status| PluginInfo {
+            id: name.to_string(),
+            module_sha256: None,
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            requested_permissions: Vec::new(),
+            granted_permissions: Vec::new(),
+            status,
+            error: None,
+            commands: Vec::new(),
+            tools: vec![PluginTool {
+                plugin: name.to_string(),
+                name: "search".to_string(),
+                description: String::new(),
+                input_schema: None,
+            }],
+        };
+        let plugins = [plugin("web", PluginStatus::Loaded), plugin("files", PluginStatus::PendingApproval)];
+        assert_eq!(enabled_tools(&plugins), vec!["web::search"]);
+
+        let question = HistoryMessage::new("c", "user", "Hi".to_string(), None, None);
+        let mut answer = HistoryMessage::new("c", "assistant", "Hel".to_string(), None, Some(question.id.clone()));
+        answer.status = MessageStatus::Partial;
+        assert_eq!(resume_target(&[question.clone(), answer.clone()]), Some(answer.id.clone()));
+        answer.status = MessageStatus::Complete;
+        assert_eq!(resume_target(&[question.clone(), answer]), None);
+        assert_eq!(resume_target(&[question]), None);
+    }
+}
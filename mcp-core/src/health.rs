@@ -0,0 +1,166 @@
+//! Provider health, checked in the background so a provider that's down, rejecting its key or
+//! struggling shows in the status bar before a message is sent into it. Each configured provider
+//! is asked for something cheap, usually its model list, every few minutes. The last outcome of
+//! each is kept here with how long it took and how many checks in a row have failed.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::providers::Provider;
+
+/// How often providers are checked, stretched under the low-power profile
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How long a check waits for an answer before counting the provider as unreachable
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Answers slower than this count as degraded
+const SLOW_AFTER: Duration = Duration::from_secs(3);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum HealthState {
+    /// Not checked since launch
+    Unknown,
+    Healthy,
+    /// Answering, but slowly, with server errors or rate limiting
+    Degraded,
+    /// Unreachable, or rejecting the key
+    Down,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ProviderHealth {
+    pub provider: Provider,
+    pub state: HealthState,
+    /// How long the last check took to be answered
+    pub latency_ms: Option<u32>,
+    pub checked_at: Option<String>,
+    /// What was wrong at the last check, if anything
+    pub error: Option<String>,
+    /// Checks in a row that weren't healthy
+    pub failures: u32,
+}
+
+/// How a check went: the HTTP status it was answered with and how long that took, or why it
+/// wasn't answered
+pub type CheckOutcome = Result<(u16, Duration), String>;
+
+/// The state and error a check's outcome amounts to
+pub fn classify(outcome: &CheckOutcome) -> (HealthState, Option<String>) {
+    match outcome {
+        Err(e) => (HealthState::Down, Some(e.clone())),
+        Ok((401 | 403, _)) => (HealthState::Down, Some("The API key was rejected".to_string())),
+        Ok((429, _)) => (HealthState::Degraded, Some("Rate limited".to_string())),
+        Ok((status, _)) if *status >= 500 => (HealthState::Degraded, Some(format!("Server error {}", status))),
+        Ok((status, _)) if *status >= 400 => (HealthState::Down, Some(format!("Rejected with {}", status))),
+        Ok((_, latency)) if *latency > SLOW_AFTER => {
+            (HealthState::Degraded, Some(format!("Slow to answer ({:.1} s)", latency.as_secs_f64())))
+        }
+        Ok(_) => (HealthState::Healthy, None),
+    }
+}
+
+static HEALTH: Mutex<Vec<ProviderHealth>> = Mutex::new(Vec::new());
+
+/// Record a check of `provider`; its health as it is now, and whether its state changed
+pub fn record(provider: Provider, outcome: &CheckOutcome) -> (ProviderHealth, bool) {
+    let (state, error) = classify(outcome);
+    let Ok(mut health) = HEALTH.lock() else {
+        let health = ProviderHealth { provider, state, latency_ms: None, checked_at: None, error, failures: 0 };
+        return (health, false);
+    };
+    let previous = health.iter().position(|health| health.provider == provider).map(|index| health.remove(index));
+    let failures = match (state, &previous) {
+        (HealthState::Healthy, _) => 0,
+        (_, Some(previous)) => previous.failures + 1,
+        (_, None) => 1,
+    };
+    let current = ProviderHealth {
+        provider,
+        state,
+        latency_ms: outcome.as_ref().ok().map(|(_, latency)| latency.as_millis() as u32),
+        checked_at: Some(chrono::Utc::now().to_rfc3339()),
+        error,
+        failures,
+    };
+    health.push(current.clone());
+    let changed = previous.map(|previous| previous.state) != Some(state);
+    (current, changed)
+}
+
+/// Stop reporting providers that are no longer configured
+pub fn retain(configured: &[Provider]) {
+    if let Ok(mut health) = HEALTH.lock() {
+        health.retain(|health| configured.contains(&health.provider));
+    }
+}
+
+/// The health of `providers`, in their order; Unknown for any not checked yet
+pub fn of(providers: &[Provider]) -> Vec<ProviderHealth> {
+    let health = HEALTH.lock().map(|health| health.clone()).unwrap_or_default();
+    providers.iter()
+        .map(|provider| {
+            health.iter().find(|health| health.provider == *provider).cloned().unwrap_or(ProviderHealth {
+                provider: *provider,
+                state: HealthState::Unknown,
+                latency_ms: None,
+                checked_at: None,
+                error: None,
+                failures: 0,
+            })
+        })
+        .collect()
+}
+
+/// Names of the providers whose last check wasn't healthy, for the status bar
+pub fn unhealthy() -> Vec<String> {
+    let health = HEALTH.lock().map(|health| health.clone()).unwrap_or_default();
+    crate::providers::ALL.iter()
+        .filter(|provider| health.iter().any(|health| {
+            health.provider == **provider && matches!(health.state, HealthState::Degraded | HealthState::Down)
+        }))
+        .map(|provider| provider.name().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcomes_are_classified() {
+        let fast = Duration::from_millis(200);
+        assert_eq!(classify(&Ok((200, fast))).0, HealthState::Healthy);
+        assert_eq!(classify(&Ok((200, Duration::from_secs(5)))).0, HealthState::Degraded);
+        assert_eq!(classify(&Ok((429, fast))).0, HealthState::Degraded);
+        assert_eq!(classify(&Ok((503, fast))).0, HealthState::Degraded);
+        assert_eq!(classify(&Ok((401, fast))).0, HealthState::Down);
+        assert_eq!(classify(&Ok((404, fast))).0, HealthState::Down);
+        assert_eq!(classify(&Err("connection refused".to_string())), (HealthState::Down, Some("connection refused".to_string())));
+    }
+
+    #[test]
+    fn failures_count_until_a_healthy_check() {
+        // Health is kept for the whole process; no other test records any
+        let provider = Provider::Groq;
+        retain(&[]);
+        assert_eq!(of(&[provider])[0].state, HealthState::Unknown);
+
+        let (health, changed) = record(provider, &Err("timed out".to_string()));
+        assert!(changed);
+        assert_eq!((health.state, health.failures), (HealthState::Down, 1));
+        let (health, changed) = record(provider, &Ok((503, Duration::from_millis(100))));
+        assert!(changed);
+        assert_eq!(health.failures, 2);
+        assert_eq!(unhealthy(), ["groq"]);
+
+        let (health, changed) = record(provider, &Ok((200, Duration::from_millis(100))));
+        assert!(changed);
+        assert_eq!((health.failures, health.latency_ms), (0, Some(100)));
+        assert!(!record(provider, &Ok((200, Duration::from_millis(100)))).1);
+        assert!(unhealthy().is_empty());
+    }
+}
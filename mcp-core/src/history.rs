@@ -0,0 +1,1804 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, ToSql};
+use rusqlite::types::{ToSqlOutput, Value};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::db_lock;
+use crate::compression::{ConversationCompression, DEFAULT_COMPRESSION_RATIO};
+use crate::benchmark::{BenchmarkReport, BenchmarkResult};
+use crate::eval::{EvalResult, EvalRun, EvalSuite};
+use crate::generation::GenerationMode;
+use crate::handoff::{Handoff, HandoffTransport};
+use crate::logprobs::TokenLogprob;
+use crate::memory::Memory;
+use crate::migrations;
+use crate::planner::TaskPlan;
+use crate::starters::{ConversationSetup, ConversationStarter};
+use crate::stream_filters::ConversationFilters;
+use crate::storage::{Attachment, AttachmentKind};
+use crate::sync::{SyncDevice, SyncEntry};
+use crate::tool_stats::ToolInvocation;
+use crate::tr;
+
+const HISTORY_DB: &str = "history.db";
+
+/// Pages the write-ahead log may grow to before it is folded back into the database (~4 MB)
+const WAL_AUTOCHECKPOINT_PAGES: u32 = 1000;
+
+/// Size the log is truncated back to after a checkpoint, so one long burst doesn't leave it large
+const WAL_SIZE_LIMIT: i64 = 16 * 1024 * 1024;
+
+/// How long a statement waits on a lock held by another connection before failing
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum MessageStatus {
+    Complete,
+    /// The stream ended early; the content so far is kept and can be resumed
+    Partial,
+    /// A stop sequence or content filter cut the answer short; what came before it is kept
+    Truncated,
+}
+
+impl MessageStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            MessageStatus::Complete => "complete",
+            MessageStatus::Partial => "partial",
+            MessageStatus::Truncated => "truncated",
+        }
+    }
+
+    fn parse(value: &str) -> MessageStatus {
+        match value {
+            "partial" => MessageStatus::Partial,
+            "truncated" => MessageStatus::Truncated,
+            _ => MessageStatus::Complete,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct HistoryMessage {
+    pub id: String,
+    pub conversation_id: String,
+    pub role: String,
+    pub content: String,
+    pub model: Option<String>,
+    pub status: MessageStatus,
+    /// For assistant messages, the user message they answer
+    pub parent_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl HistoryMessage {
+    pub fn new(conversation_id: &str, role: &str, content: String, model: Option<String>, parent_id: Option<String>) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        HistoryMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: conversation_id.to_string(),
+            role: role.to_string(),
+            content,
+            model,
+            status: MessageStatus::Complete,
+            parent_id,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+/// A plugin tool invoked while a response was generating
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ToolCallRecord {
+    pub plugin: String,
+    pub tool: String,
+    pub success: bool,
+}
+
+/// Settings a response was generated with
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct GenerationParameters {
+    pub mode: GenerationMode,
+    pub max_tokens: Option<u32>,
+    pub fast_model: Option<String>,
+    pub sample_count: Option<u32>,
+    pub guardrails: bool,
+    pub memory: bool,
+    /// Set when a regeneration overrode the provider's default
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// The seed the provider was sent, for replaying the answer
+    #[serde(default)]
+    pub seed: Option<u32>,
+}
+
+/// How an assistant message was produced
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct MessageDetails {
+    pub message_id: String,
+    pub provider: String,
+    pub model: String,
+    pub parameters: GenerationParameters,
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// What went into the prompt besides the message itself, e.g. "6 earlier messages"
+    pub context_sources: Vec<String>,
+    pub first_token_ms: Option<u32>,
+    pub latency_ms: Option<u32>,
+    /// From the provider's prices and the answer's length; None where prices aren't known
+    #[serde(default)]
+    pub estimated_usd: Option<f64>,
+    /// Summed over every request, as the providers reported them once the answer finished
+    #[serde(default)]
+    pub prompt_tokens: Option<u32>,
+    #[serde(default)]
+    pub completion_tokens: Option<u32>,
+    /// From the reported usage; None when a request reported none or a price isn't known
+    #[serde(default)]
+    pub actual_usd: Option<f64>,
+    /// The stop sequence or content filter that cut the answer short
+    #[serde(default)]
+    pub truncated_by: Option<String>,
+    /// Each token's probability, when they were asked for and the provider reported them
+    #[serde(default)]
+    pub token_logprobs: Vec<TokenLogprob>,
+    /// Tags the request was sent with, e.g. a gateway's trace id or the client's project, kept
+    /// for auditing where answers came from
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// Tags `on_response_complete` scripts gave the answer once it finished
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Tags a request can carry into its answer's details
+pub const MAX_METADATA_TAGS: usize = 16;
+const MAX_METADATA_KEY_CHARS: usize = 64;
+const MAX_METADATA_VALUE_CHARS: usize = 512;
+
+/// Request metadata trimmed, or a localized error if there are too many tags or one is too long
+pub fn validate_metadata(metadata: BTreeMap<String, String>) -> Result<BTreeMap<String, String>, String> {
+    let metadata: BTreeMap<String, String> = metadata.into_iter()
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect();
+    if metadata.len() > MAX_METADATA_TAGS {
+        return Err(tr!("request-metadata-too-many", max = MAX_METADATA_TAGS));
+    }
+    if let Some((key, _)) = metadata.iter().find(|(key, value)| key.chars().count() > MAX_METADATA_KEY_CHARS || value.chars().count() > MAX_METADATA_VALUE_CHARS) {
+        return Err(tr!("request-metadata-too-long", key = key.clone()));
+    }
+    Ok(metadata)
+}
+
+/// A conversation with answers that completed while it wasn't on screen
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct UnreadConversation {
+    pub conversation_id: String,
+    pub unread: u32,
+    /// When the conversation was last read, if ever
+    pub last_read_at: Option<String>,
+}
+
+/// The conversation a message was in before it was merged into another
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct MessageOrigin {
+    pub message_id: String,
+    pub conversation_id: String,
+    pub merged_at: String,
+}
+
+/// One of several sampled answers to a message, kept for comparison
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct Candidate {
+    pub id: String,
+    /// The assistant message the candidate was sampled for
+    pub message_id: String,
+    pub model: String,
+    pub content: String,
+    /// Whether this candidate is the message's current content
+    pub selected: bool,
+    pub created_at: String,
+}
+
+/// Conversation history in `<data_dir>/history.db`
+pub struct HistoryStore {
+    conn: Connection,
+    data_dir: PathBuf,
+    /// Another instance holds the database; writes are queued instead of executed
+    read_only: bool,
+}
+
+impl HistoryStore {
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(data_dir)?;
+        let Some(queued) = db_lock::acquire(data_dir)? else {
+            let conn = Connection::open_with_flags(data_dir.join(HISTORY_DB), OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| anyhow::anyhow!(tr!("history-in-use", error = e.to_string())))?;
+            conn.busy_timeout(BUSY_TIMEOUT)?;
+            return Ok(HistoryStore { conn, data_dir: data_dir.to_path_buf(), read_only: true });
+        };
+
+        let mut conn = Connection::open(data_dir.join(HISTORY_DB))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // WAL lets reads carry on during a write, and in WAL mode NORMAL sync is still safe
+        // against corruption while skipping the fsync on every commit
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.pragma_update(None, "wal_autocheckpoint", WAL_AUTOCHECKPOINT_PAGES)?;
+        conn.pragma_update(None, "journal_size_limit", WAL_SIZE_LIMIT)?;
+        migrations::migrate(&mut conn, data_dir)?;
+        for write in queued {
+            if let Err(e) = conn.execute(&write.sql, rusqlite::params_from_iter(write.params)) {
+                log::warn!("Dropping queued history write that failed on replay: {}", e);
+            }
+        }
+        db_lock::replayed(data_dir)?;
+        Ok(HistoryStore { conn, data_dir: data_dir.to_path_buf(), read_only: false })
+    }
+
+    /// Execute a write, or queue it on disk while another instance holds the database. A queued
+    /// write reports one row changed, since whether it will match anything isn't known yet.
+    fn write(&self, sql: &str, params: &[&dyn ToSql]) -> Result<usize> {
+        if !self.read_only {
+            return Ok(self.conn.execute(sql, params)?);
+        }
+        let params = params.iter()
+            .map(|param| match param.to_sql()? {
+                ToSqlOutput::Borrowed(value) => Ok(value.into()),
+                ToSqlOutput::Owned(value) => Ok(value),
+                _ => Err(rusqlite::Error::ToSqlConversionFailure("unsupported parameter in a queued write".into())),
+            })
+            .collect::<rusqlite::Result<Vec<Value>>>()?;
+        db_lock::queue(&self.data_dir, db_lock::QueuedWrite { sql: sql.to_string(), params })?;
+        Ok(1)
+    }
+
+    /// Run several writes as one transaction, so they cost a single commit instead of one each
+    pub fn batch<T>(&self, writes: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        if self.read_only {
+            return writes(self);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        let result = writes(self)?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    /// For multi-statement changes that can't be queued
+    fn require_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(anyhow::anyhow!(tr!("history-read-only")));
+        }
+        Ok(())
+    }
+
+    pub fn insert(&self, message: &HistoryMessage) -> Result<()> {
+        self.write(
+            "INSERT INTO messages (id, conversation_id, role, content, model, status, parent_id, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                message.id,
+                message.conversation_id,
+                message.role,
+                message.content,
+                message.model,
+                message.status.as_str(),
+                message.parent_id,
+                message.created_at,
+                message.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_content(&self, id: &str, content: &str, status: MessageStatus) -> Result<()> {
+        let updated = self.write(
+            "UPDATE messages SET content = ?2, status = ?3, updated_at = ?4 WHERE id = ?1",
+            params![id, content, status.as_str(), chrono::Utc::now().to_rfc3339()],
+        )?;
+        if updated == 0 {
+            return Err(anyhow::anyhow!(tr!("history-message-not-found", id = id)));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<HistoryMessage>> {
+        Ok(self.conn
+            .query_row(
+                "SELECT id, conversation_id, role, content, model, status, parent_id, created_at, updated_at
+                 FROM messages WHERE id = ?1",
+                params![id],
+                row_to_message,
+            )
+            .optional()?)
+    }
+
+    pub fn list(&self, conversation_id: &str) -> Result<Vec<HistoryMessage>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, role, content, model, status, parent_id, created_at, updated_at
+             FROM messages WHERE conversation_id = ?1 ORDER BY created_at, rowid",
+        )?;
+        let messages = statement
+            .query_map(params![conversation_id], row_to_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Call `f` with each message of a conversation in order, without loading them all at once.
+    /// Stops at the first error `f` returns.
+    pub fn for_each_message<F>(&self, conversation_id: &str, mut f: F) -> Result<()>
+    where
+        F: FnMut(&HistoryMessage) -> Result<()>,
+    {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, role, content, model, status, parent_id, created_at, updated_at
+             FROM messages WHERE conversation_id = ?1 ORDER BY created_at, rowid",
+        )?;
+        let mut rows = statement.query(params![conversation_id])?;
+        while let Some(row) = rows.next()? {
+            f(&row_to_message(row)?)?;
+        }
+        Ok(())
+    }
+
+    /// Ids of conversations whose id starts with `prefix`, up to `limit`
+    pub fn conversations_starting_with(&self, prefix: &str, limit: u32) -> Result<Vec<String>> {
+        let mut statement = self.conn.prepare(
+            "SELECT DISTINCT conversation_id FROM messages WHERE substr(conversation_id, 1, length(?1)) = ?1 LIMIT ?2",
+        )?;
+        let ids = statement
+            .query_map(params![prefix, limit], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    pub fn message_count_in(&self, conversation_id: &str) -> Result<u32> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE conversation_id = ?1",
+            params![conversation_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Every message in every conversation
+    pub fn all_messages(&self) -> Result<Vec<HistoryMessage>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, role, content, model, status, parent_id, created_at, updated_at
+             FROM messages ORDER BY created_at, rowid",
+        )?;
+        let messages = statement
+            .query_map([], row_to_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Messages in every conversation created at or after `since`, an RFC 3339 timestamp
+    pub fn messages_since(&self, since: &str) -> Result<Vec<HistoryMessage>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, role, content, model, status, parent_id, created_at, updated_at
+             FROM messages WHERE created_at >= ?1 ORDER BY created_at, rowid",
+        )?;
+        let messages = statement
+            .query_map(params![since], row_to_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Insert a message or overwrite the stored copy, e.g. with one synced from another device
+    pub fn upsert_message(&self, message: &HistoryMessage) -> Result<()> {
+        self.write(
+            "INSERT OR REPLACE INTO messages (id, conversation_id, role, content, model, status, parent_id, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                message.id,
+                message.conversation_id,
+                message.role,
+                message.content,
+                message.model,
+                message.status.as_str(),
+                message.parent_id,
+                message.created_at,
+                message.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_message(&self, id: &str) -> Result<()> {
+        self.write("DELETE FROM messages WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn insert_candidate(&self, message_id: &str, model: &str, content: &str) -> Result<Candidate> {
+        let candidate = Candidate {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_id: message_id.to_string(),
+            model: model.to_string(),
+            content: content.to_string(),
+            selected: false,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.write(
+            "INSERT INTO candidates (id, message_id, model, content, selected, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                candidate.id,
+                candidate.message_id,
+                candidate.model,
+                candidate.content,
+                candidate.selected,
+                candidate.created_at,
+            ],
+        )?;
+        Ok(candidate)
+    }
+
+    /// Keep a message's current content as its selected candidate, unless one is already
+    /// selected, so choosing an alternative later doesn't lose it
+    pub fn keep_as_candidate(&self, message: &HistoryMessage) -> Result<()> {
+        let selected: Option<String> = self.conn
+            .query_row(
+                "SELECT id FROM candidates WHERE message_id = ?1 AND selected = 1",
+                params![message.id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if selected.is_none() {
+            let model = message.model.as_deref().unwrap_or_default();
+            let candidate = self.insert_candidate(&message.id, model, &message.content)?;
+            self.write("UPDATE candidates SET selected = 1 WHERE id = ?1", params![candidate.id])?;
+        }
+        Ok(())
+    }
+
+    pub fn save_candidate_details(&self, candidate_id: &str, details: &MessageDetails) -> Result<()> {
+        self.write(
+            "INSERT INTO candidate_details (candidate_id, details) VALUES (?1, ?2)
+             ON CONFLICT (candidate_id) DO UPDATE SET details = ?2",
+            params![candidate_id, serde_json::to_string(details)?],
+        )?;
+        Ok(())
+    }
+
+    /// How a regenerated candidate was produced; None for other candidates
+    pub fn candidate_details(&self, candidate_id: &str) -> Result<Option<MessageDetails>> {
+        let details: Option<String> = self.conn
+            .query_row(
+                "SELECT details FROM candidate_details WHERE candidate_id = ?1",
+                params![candidate_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(details.map(|details| serde_json::from_str(&details)).transpose()?)
+    }
+
+    pub fn list_candidates(&self, message_id: &str) -> Result<Vec<Candidate>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, message_id, model, content, selected, created_at
+             FROM candidates WHERE message_id = ?1 ORDER BY created_at, rowid",
+        )?;
+        let candidates = statement
+            .query_map(params![message_id], row_to_candidate)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(candidates)
+    }
+
+    /// Make a candidate the content of its message
+    pub fn candidate(&self, candidate_id: &str) -> Result<Candidate> {
+        self.conn
+            .query_row(
+                "SELECT id, message_id, model, content, selected, created_at FROM candidates WHERE id = ?1",
+                params![candidate_id],
+                row_to_candidate,
+            )
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!(tr!("history-candidate-not-found", id = candidate_id)))
+    }
+
+    pub fn select_candidate(&mut self, candidate_id: &str) -> Result<Candidate> {
+        self.require_writable()?;
+        let candidate = self.candidate(candidate_id)?;
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "UPDATE candidates SET selected = (id = ?2) WHERE message_id = ?1",
+            params![candidate.message_id, candidate.id],
+        )?;
+        tx.execute(
+            "UPDATE messages SET content = ?2, model = ?3, status = ?4, updated_at = ?5 WHERE id = ?1",
+            params![
+                candidate.message_id,
+                candidate.content,
+                candidate.model,
+                MessageStatus::Complete.as_str(),
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )?;
+        tx.commit()?;
+        Ok(Candidate { selected: true, ..candidate })
+    }
+
+    pub fn message_count(&self) -> Result<u32> {
+        Ok(self.conn.query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))?)
+    }
+
+    /// Consistent copy of the database at `path`, safe to take while the app is writing
+    pub fn snapshot_to(&self, path: &Path) -> Result<()> {
+        self.conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Copy rows from another history database that don't exist here, keeping local rows on
+    /// conflict. Returns the number of messages added.
+    pub fn merge_from(&mut self, path: &Path) -> Result<u32> {
+        self.require_writable()?;
+        self.conn.execute("ATTACH DATABASE ?1 AS incoming", params![path.to_string_lossy()])?;
+        let merged = (|| -> Result<u32> {
+            let tx = self.conn.transaction()?;
+            let added = tx.execute("INSERT OR IGNORE INTO messages SELECT * FROM incoming.messages", [])?;
+            for table in [
+                "candidates", "message_details", "memories", "conversation_compression", "attachments", "attachment_refs",
+                "conversation_locks", "message_origins", "conversation_starters", "starter_attachments", "starter_conversations",
+                "conversation_filters", "candidate_details", "eval_suites", "eval_runs", "eval_results", "handoffs",
+                "benchmark_reports", "benchmark_results", "tool_invocations", "conversation_reads",
+                "conversation_plans",
+            ] {
+                // Backups from older versions may predate a table
+                let present = tx
+                    .query_row("SELECT 1 FROM incoming.sqlite_master WHERE type = 'table' AND name = ?1", params![table], |_| Ok(()))
+                    .optional()?
+                    .is_some();
+                if present {
+                    tx.execute(&format!("INSERT OR IGNORE INTO {table} SELECT * FROM incoming.{table}"), [])?;
+                }
+            }
+            tx.commit()?;
+            Ok(added as u32)
+        })();
+        self.conn.execute("DETACH DATABASE incoming", [])?;
+        merged
+    }
+
+    /// Record how a message was produced, replacing any earlier record (e.g. before a resume)
+    pub fn save_details(&self, details: &MessageDetails) -> Result<()> {
+        self.write(
+            "INSERT INTO message_details (message_id, details) VALUES (?1, ?2)
+             ON CONFLICT (message_id) DO UPDATE SET details = ?2",
+            params![details.message_id, serde_json::to_string(details)?],
+        )?;
+        Ok(())
+    }
+
+    pub fn details(&self, message_id: &str) -> Result<Option<MessageDetails>> {
+        let details: Option<String> = self.conn
+            .query_row(
+                "SELECT details FROM message_details WHERE message_id = ?1",
+                params![message_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(details.map(|details| serde_json::from_str(&details)).transpose()?)
+    }
+
+    /// Answers a script tagged with `tag`, oldest first
+    pub fn tagged_messages(&self, tag: &str) -> Result<Vec<HistoryMessage>> {
+        let mut statement = self.conn.prepare(
+            "SELECT m.id, m.conversation_id, m.role, m.content, m.model, m.status, m.parent_id, m.created_at, m.updated_at
+             FROM messages m JOIN message_details d ON d.message_id = m.id
+             WHERE EXISTS (SELECT 1 FROM json_each(d.details, '$.tags') WHERE value = ?1)
+             ORDER BY m.created_at, m.rowid",
+        )?;
+        let messages = statement
+            .query_map(params![tag], row_to_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Spend since `since`, an RFC 3339 timestamp, on answers, regenerations, eval runs and
+    /// benchmarks; answers count what they actually cost where their usage was reported
+    pub fn spent_since(&self, since: &str) -> Result<f64> {
+        let mut statement = self.conn.prepare(
+            "SELECT d.details FROM message_details d JOIN messages m ON m.id = d.message_id WHERE m.created_at >= ?1
+             UNION ALL
+             SELECT d.details FROM candidate_details d JOIN candidates c ON c.id = d.candidate_id WHERE c.created_at >= ?1",
+        )?;
+        let details = statement
+            .query_map(params![since], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let evals: f64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(r.estimated_usd), 0) FROM eval_results r JOIN eval_runs e ON e.id = r.run_id
+             WHERE e.started_at >= ?1",
+            params![since],
+            |row| row.get(0),
+        )?;
+        let benchmarks: f64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(r.estimated_usd), 0) FROM benchmark_results r JOIN benchmark_reports b ON b.id = r.report_id
+             WHERE b.started_at >= ?1",
+            params![since],
+            |row| row.get(0),
+        )?;
+        Ok(details.iter()
+            .filter_map(|details| serde_json::from_str::<MessageDetails>(details).ok())
+            .filter_map(|details| details.actual_usd.or(details.estimated_usd))
+            .fold(evals + benchmarks, |total, usd| total + usd))
+    }
+
+    pub fn insert_memory(&self, memory: &Memory) -> Result<()> {
+        self.write(
+            "INSERT INTO memories (id, content, conversation_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![memory.id, memory.content, memory.conversation_id, memory.created_at],
+        )?;
+        Ok(())
+    }
+
+    /// All memories, oldest first
+    pub fn list_memories(&self) -> Result<Vec<Memory>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, content, conversation_id, created_at FROM memories ORDER BY created_at, rowid",
+        )?;
+        let memories = statement
+            .query_map([], |row| Ok(Memory {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                conversation_id: row.get(2)?,
+                created_at: row.get(3)?,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(memories)
+    }
+
+    pub fn upsert_memory(&self, memory: &Memory) -> Result<()> {
+        self.write(
+            "INSERT OR REPLACE INTO memories (id, content, conversation_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![memory.id, memory.content, memory.conversation_id, memory.created_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_memory(&self, id: &str) -> Result<()> {
+        if !self.remove_memory(id)? {
+            return Err(anyhow::anyhow!(tr!("history-memory-not-found", id = id)));
+        }
+        Ok(())
+    }
+
+    /// Delete a memory if it exists; returns whether it did. Not queued while read-only, since
+    /// whether it exists can't be known until the delete runs.
+    pub fn remove_memory(&self, id: &str) -> Result<bool> {
+        self.require_writable()?;
+        Ok(self.write("DELETE FROM memories WHERE id = ?1", params![id])? > 0)
+    }
+
+    pub fn clear_memories(&self) -> Result<u32> {
+        self.require_writable()?;
+        Ok(self.write("DELETE FROM memories", &[])? as u32)
+    }
+
+    /// Compression settings for a conversation; off unless set
+    pub fn compression(&self, conversation_id: &str) -> Result<ConversationCompression> {
+        let stored = self.conn
+            .query_row(
+                "SELECT enabled, ratio, last_before_tokens, last_after_tokens
+                 FROM conversation_compression WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| Ok(ConversationCompression {
+                    conversation_id: conversation_id.to_string(),
+                    enabled: row.get(0)?,
+                    ratio: row.get(1)?,
+                    last_before_tokens: row.get(2)?,
+                    last_after_tokens: row.get(3)?,
+                }),
+            )
+            .optional()?;
+        Ok(stored.unwrap_or_else(|| ConversationCompression {
+            conversation_id: conversation_id.to_string(),
+            enabled: false,
+            ratio: DEFAULT_COMPRESSION_RATIO,
+            last_before_tokens: None,
+            last_after_tokens: None,
+        }))
+    }
+
+    pub fn set_compression(&self, conversation_id: &str, enabled: bool, ratio: f32) -> Result<()> {
+        self.write(
+            "INSERT INTO conversation_compression (conversation_id, enabled, ratio) VALUES (?1, ?2, ?3)
+             ON CONFLICT (conversation_id) DO UPDATE SET enabled = ?2, ratio = ?3",
+            params![conversation_id, enabled, ratio],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_compression(&self, conversation_id: &str, before_tokens: u32, after_tokens: u32) -> Result<()> {
+        self.write(
+            "UPDATE conversation_compression SET last_before_tokens = ?2, last_after_tokens = ?3
+             WHERE conversation_id = ?1",
+            params![conversation_id, before_tokens, after_tokens],
+        )?;
+        Ok(())
+    }
+
+    /// The first prompt of every conversation, oldest conversation first
+    pub fn conversation_openers(&self) -> Result<Vec<HistoryMessage>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, role, content, model, status, parent_id, created_at, updated_at
+             FROM messages m
+             WHERE role = 'user' AND rowid = (
+                 SELECT rowid FROM messages WHERE conversation_id = m.conversation_id AND role = 'user'
+                 ORDER BY created_at, rowid LIMIT 1
+             )
+             ORDER BY created_at, rowid",
+        )?;
+        let messages = statement
+            .query_map([], row_to_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Move every message of the `from` conversations into `into`, where they fall in among its
+    /// own by timestamp. Messages identical to one already there (same role, content and time,
+    /// as repeated imports produce) are dropped; the rest record the conversation they came from.
+    /// Attachments, locks, the starter setup and filters move with them, though `into` keeps its
+    /// own setup and filters if it has them. Returns the messages moved and dropped.
+    pub fn merge_conversations(&mut self, into: &str, from: &[String]) -> Result<(u32, u32)> {
+        self.require_writable()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        let (mut moved, mut dropped) = (0, 0);
+        for source in from.iter().filter(|source| *source != into) {
+            let duplicates: Vec<String> = tx
+                .prepare(
+                    "SELECT s.id FROM messages s WHERE s.conversation_id = ?1 AND EXISTS (
+                         SELECT 1 FROM messages t WHERE t.conversation_id = ?2
+                         AND t.role = s.role AND t.content = s.content AND t.created_at = s.created_at
+                     )",
+                )?
+                .query_map(params![source, into], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for id in &duplicates {
+                tx.execute("DELETE FROM message_details WHERE message_id = ?1", params![id])?;
+                tx.execute(
+                    "DELETE FROM candidate_details WHERE candidate_id IN (SELECT id FROM candidates WHERE message_id = ?1)",
+                    params![id],
+                )?;
+                tx.execute("DELETE FROM candidates WHERE message_id = ?1", params![id])?;
+                tx.execute("DELETE FROM messages WHERE id = ?1", params![id])?;
+            }
+            dropped += duplicates.len() as u32;
+
+            tx.execute(
+                "INSERT OR IGNORE INTO message_origins (message_id, conversation_id, merged_at)
+                 SELECT id, conversation_id, ?2 FROM messages WHERE conversation_id = ?1",
+                params![source, now],
+            )?;
+            moved += tx.execute(
+                "UPDATE messages SET conversation_id = ?2, updated_at = ?3 WHERE conversation_id = ?1",
+                params![source, into, now],
+            )? as u32;
+            tx.execute(
+                "INSERT OR IGNORE INTO attachment_refs (attachment_id, conversation_id, created_at)
+                 SELECT attachment_id, ?2, created_at FROM attachment_refs WHERE conversation_id = ?1",
+                params![source, into],
+            )?;
+            tx.execute("DELETE FROM attachment_refs WHERE conversation_id = ?1", params![source])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO conversation_locks (conversation_id, locked_at)
+                 SELECT ?2, locked_at FROM conversation_locks WHERE conversation_id = ?1",
+                params![source, into],
+            )?;
+            tx.execute("DELETE FROM conversation_locks WHERE conversation_id = ?1", params![source])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO starter_conversations (conversation_id, starter_name, system_prompt, model, created_at)
+                 SELECT ?2, starter_name, system_prompt, model, created_at FROM starter_conversations WHERE conversation_id = ?1",
+                params![source, into],
+            )?;
+            tx.execute("DELETE FROM starter_conversations WHERE conversation_id = ?1", params![source])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO conversation_filters (conversation_id, stop_sequences, content_filters)
+                 SELECT ?2, stop_sequences, content_filters FROM conversation_filters WHERE conversation_id = ?1",
+                params![source, into],
+            )?;
+            tx.execute("DELETE FROM conversation_filters WHERE conversation_id = ?1", params![source])?;
+            tx.execute(
+                "INSERT INTO conversation_reads (conversation_id, unread, last_read_at)
+                 SELECT ?2, unread, last_read_at FROM conversation_reads WHERE conversation_id = ?1
+                 ON CONFLICT (conversation_id) DO UPDATE SET unread = unread + excluded.unread",
+                params![source, into],
+            )?;
+            tx.execute("DELETE FROM conversation_reads WHERE conversation_id = ?1", params![source])?;
+            // The target keeps its own plan; a source's plan moves only if the target has none
+            tx.execute(
+                "INSERT OR IGNORE INTO conversation_plans (conversation_id, goal, steps, created_at, updated_at)
+                 SELECT ?2, goal, steps, created_at, updated_at FROM conversation_plans WHERE conversation_id = ?1",
+                params![source, into],
+            )?;
+            tx.execute("DELETE FROM conversation_plans WHERE conversation_id = ?1", params![source])?;
+            tx.execute("DELETE FROM conversation_compression WHERE conversation_id = ?1", params![source])?;
+        }
+        tx.commit()?;
+        Ok((moved, dropped))
+    }
+
+    /// Where the messages merged into `conversation_id` came from
+    pub fn message_origins(&self, conversation_id: &str) -> Result<Vec<MessageOrigin>> {
+        let mut statement = self.conn.prepare(
+            "SELECT o.message_id, o.conversation_id, o.merged_at
+             FROM message_origins o JOIN messages m ON m.id = o.message_id
+             WHERE m.conversation_id = ?1 ORDER BY m.created_at, m.rowid",
+        )?;
+        let origins = statement
+            .query_map(params![conversation_id], |row| Ok(MessageOrigin {
+                message_id: row.get(0)?,
+                conversation_id: row.get(1)?,
+                merged_at: row.get(2)?,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(origins)
+    }
+
+    pub fn lock_conversation(&self, conversation_id: &str) -> Result<()> {
+        self.write(
+            "INSERT OR IGNORE INTO conversation_locks (conversation_id, locked_at) VALUES (?1, ?2)",
+            params![conversation_id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn unlock_conversation(&self, conversation_id: &str) -> Result<()> {
+        self.write("DELETE FROM conversation_locks WHERE conversation_id = ?1", params![conversation_id])?;
+        Ok(())
+    }
+
+    pub fn is_locked(&self, conversation_id: &str) -> Result<bool> {
+        let locked: Option<String> = self.conn
+            .query_row(
+                "SELECT locked_at FROM conversation_locks WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(locked.is_some())
+    }
+
+    /// Locked conversations with when they were locked, oldest first
+    pub fn locked_conversations(&self) -> Result<Vec<(String, String)>> {
+        let mut statement = self.conn.prepare(
+            "SELECT conversation_id, locked_at FROM conversation_locks ORDER BY locked_at, rowid",
+        )?;
+        let locks = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(locks)
+    }
+
+    /// Count one more answer in `conversation_id` as unread
+    pub fn mark_unread(&self, conversation_id: &str) -> Result<()> {
+        self.write(
+            "INSERT INTO conversation_reads (conversation_id, unread) VALUES (?1, 1)
+             ON CONFLICT (conversation_id) DO UPDATE SET unread = unread + 1",
+            params![conversation_id],
+        )?;
+        Ok(())
+    }
+
+    /// Clear `conversation_id`'s unread answers; returns how many there were
+    pub fn mark_read(&self, conversation_id: &str) -> Result<u32> {
+        let unread = self.unread_count(conversation_id)?;
+        self.write(
+            "INSERT INTO conversation_reads (conversation_id, unread, last_read_at) VALUES (?1, 0, ?2)
+             ON CONFLICT (conversation_id) DO UPDATE SET unread = 0, last_read_at = ?2",
+            params![conversation_id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(unread)
+    }
+
+    pub fn unread_count(&self, conversation_id: &str) -> Result<u32> {
+        let unread: Option<u32> = self.conn
+            .query_row(
+                "SELECT unread FROM conversation_reads WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(unread.unwrap_or(0))
+    }
+
+    /// Conversations with unread answers, most unread first
+    pub fn unread_conversations(&self) -> Result<Vec<UnreadConversation>> {
+        let mut statement = self.conn.prepare(
+            "SELECT conversation_id, unread, last_read_at FROM conversation_reads WHERE unread > 0
+             ORDER BY unread DESC, conversation_id",
+        )?;
+        let unread = statement
+            .query_map([], |row| Ok(UnreadConversation {
+                conversation_id: row.get(0)?,
+                unread: row.get(1)?,
+                last_read_at: row.get(2)?,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(unread)
+    }
+
+    /// Unread answers across every conversation, for the tray and status bar badge
+    pub fn unread_total(&self) -> Result<u32> {
+        Ok(self.conn.query_row("SELECT COALESCE(SUM(unread), 0) FROM conversation_reads", [], |row| row.get(0))?)
+    }
+
+    /// The stored embedding of prompt `message_id`, if it was made from the text `text_hash` names
+    pub fn prompt_embedding(&self, message_id: &str, text_hash: &str) -> Result<Option<Vec<f32>>> {
+        let embedding: Option<Vec<u8>> = self.conn
+            .query_row(
+                "SELECT embedding FROM prompt_embeddings WHERE message_id = ?1 AND text_hash = ?2",
+                params![message_id, text_hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(embedding.map(|bytes| {
+            bytes.chunks_exact(4).map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]])).collect()
+        }))
+    }
+
+    pub fn save_prompt_embedding(&self, message_id: &str, text_hash: &str, embedding: &[f32]) -> Result<()> {
+        let bytes: Vec<u8> = embedding.iter().flat_map(|value| value.to_le_bytes()).collect();
+        self.write(
+            "INSERT INTO prompt_embeddings (message_id, text_hash, embedding) VALUES (?1, ?2, ?3)
+             ON CONFLICT (message_id) DO UPDATE SET text_hash = ?2, embedding = ?3",
+            params![message_id, text_hash, bytes],
+        )?;
+        Ok(())
+    }
+
+    /// Up to `limit` prompts with no stored embedding, newest first. Prompts in locked
+    /// conversations are left out, so they're never sent anywhere in the background.
+    pub fn unembedded_prompts(&self, limit: u32) -> Result<Vec<HistoryMessage>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, role, content, model, status, parent_id, created_at, updated_at
+             FROM messages m
+             WHERE role = 'user' AND trim(content) != ''
+               AND NOT EXISTS (SELECT 1 FROM prompt_embeddings e WHERE e.message_id = m.id)
+               AND conversation_id NOT IN (SELECT conversation_id FROM conversation_locks)
+             ORDER BY created_at DESC, rowid DESC LIMIT ?1",
+        )?;
+        let messages = statement
+            .query_map(params![limit], row_to_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    /// Drop the embeddings of deleted prompts, then rebuild the database file to hand back the
+    /// space deleted rows left behind. Returns the bytes freed.
+    pub fn vacuum(&self) -> Result<u64> {
+        self.require_writable()?;
+        self.conn.execute("DELETE FROM prompt_embeddings WHERE message_id NOT IN (SELECT id FROM messages)", [])?;
+        let size = || -> Result<u64> {
+            let pages: u64 = self.conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+            let page_size: u64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            Ok(pages * page_size)
+        };
+        let before = size()?;
+        self.conn.execute_batch("VACUUM; PRAGMA optimize;")?;
+        Ok(before.saturating_sub(size()?))
+    }
+
+    /// Save a starter, replacing any with the same name
+    pub fn save_starter(&mut self, starter: &ConversationStarter) -> Result<()> {
+        self.require_writable()?;
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO conversation_starters (name, system_prompt, first_message, model, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![starter.name, starter.system_prompt, starter.first_message, starter.model, starter.created_at],
+        )?;
+        tx.execute("DELETE FROM starter_attachments WHERE starter_name = ?1", params![starter.name])?;
+        for attachment_id in &starter.attachment_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO starter_attachments (starter_name, attachment_id) VALUES (?1, ?2)",
+                params![starter.name, attachment_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn starter(&self, name: &str) -> Result<Option<ConversationStarter>> {
+        Ok(self.list_starters()?.into_iter().find(|starter| starter.name == name))
+    }
+
+    /// Every starter, by name
+    pub fn list_starters(&self) -> Result<Vec<ConversationStarter>> {
+        let mut statement = self.conn.prepare(
+            "SELECT name, system_prompt, first_message, model, created_at FROM conversation_starters ORDER BY name",
+        )?;
+        let mut starters = statement
+            .query_map([], |row| Ok(ConversationStarter {
+                name: row.get(0)?,
+                system_prompt: row.get(1)?,
+                first_message: row.get(2)?,
+                model: row.get(3)?,
+                attachment_ids: Vec::new(),
+                created_at: row.get(4)?,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut statement = self.conn.prepare(
+            "SELECT attachment_id FROM starter_attachments WHERE starter_name = ?1 ORDER BY rowid",
+        )?;
+        for starter in &mut starters {
+            starter.attachment_ids = statement
+                .query_map(params![starter.name], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+        Ok(starters)
+    }
+
+    /// Returns whether there was a starter by that name. Conversations already started from it
+    /// keep what they were set up with.
+    pub fn delete_starter(&mut self, name: &str) -> Result<bool> {
+        self.require_writable()?;
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM starter_attachments WHERE starter_name = ?1", params![name])?;
+        let deleted = tx.execute("DELETE FROM conversation_starters WHERE name = ?1", params![name])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Set up `conversation_id` from `starter`, attaching the given attachments to it
+    pub fn start_conversation(&mut self, conversation_id: &str, starter: &ConversationStarter, attachment_ids: &[String]) -> Result<()> {
+        self.require_writable()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO starter_conversations (conversation_id, starter_name, system_prompt, model, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![conversation_id, starter.name, starter.system_prompt, starter.model, now],
+        )?;
+        for attachment_id in attachment_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO attachment_refs (attachment_id, conversation_id, created_at) VALUES (?1, ?2, ?3)",
+                params![attachment_id, conversation_id, now],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// What `conversation_id` was started with, if it came from a starter
+    pub fn conversation_setup(&self, conversation_id: &str) -> Result<Option<ConversationSetup>> {
+        Ok(self.conn
+            .query_row(
+                "SELECT starter_name, system_prompt, model FROM starter_conversations WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| Ok(ConversationSetup {
+                    starter_name: row.get(0)?,
+                    system_prompt: row.get(1)?,
+                    model: row.get(2)?,
+                }),
+            )
+            .optional()?)
+    }
+
+    /// Stop sequences and content filters for `conversation_id`; empty if none were set
+    pub fn conversation_filters(&self, conversation_id: &str) -> Result<ConversationFilters> {
+        let stored: Option<(String, String)> = self.conn
+            .query_row(
+                "SELECT stop_sequences, content_filters FROM conversation_filters WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(match stored {
+            Some((stop_sequences, content_filters)) => ConversationFilters {
+                stop_sequences: serde_json::from_str(&stop_sequences)?,
+                content_filters: serde_json::from_str(&content_filters)?,
+            },
+            None => ConversationFilters::default(),
+        })
+    }
+
+    /// Replace the filters for `conversation_id`; empty filters remove the row
+    pub fn set_conversation_filters(&self, conversation_id: &str, filters: &ConversationFilters) -> Result<()> {
+        if filters.is_empty() {
+            self.write("DELETE FROM conversation_filters WHERE conversation_id = ?1", params![conversation_id])?;
+            return Ok(());
+        }
+        self.write(
+            "INSERT INTO conversation_filters (conversation_id, stop_sequences, content_filters) VALUES (?1, ?2, ?3)
+             ON CONFLICT (conversation_id) DO UPDATE SET stop_sequences = ?2, content_filters = ?3",
+            params![
+                conversation_id,
+                serde_json::to_string(&filters.stop_sequences)?,
+                serde_json::to_string(&filters.content_filters)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The task plan `conversation_id` is working through, if one was made
+    pub fn conversation_plan(&self, conversation_id: &str) -> Result<Option<TaskPlan>> {
+        let stored: Option<(String, String, String, String)> = self.conn
+            .query_row(
+                "SELECT goal, steps, created_at, updated_at FROM conversation_plans WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+        stored
+            .map(|(goal, steps, created_at, updated_at)| Ok(TaskPlan {
+                conversation_id: conversation_id.to_string(),
+                goal,
+                steps: serde_json::from_str(&steps)?,
+                created_at,
+                updated_at,
+            }))
+            .transpose()
+    }
+
+    /// Save `plan` as its conversation's, replacing any earlier one
+    pub fn save_conversation_plan(&self, plan: &TaskPlan) -> Result<()> {
+        self.write(
+            "INSERT INTO conversation_plans (conversation_id, goal, steps, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (conversation_id) DO UPDATE SET goal = ?2, steps = ?3, created_at = ?4, updated_at = ?5",
+            params![plan.conversation_id, plan.goal, serde_json::to_string(&plan.steps)?, plan.created_at, plan.updated_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn save_eval_suite(&self, suite: &EvalSuite) -> Result<()> {
+        self.write(
+            "INSERT OR REPLACE INTO eval_suites (name, cases, models, judge_model, interval_hours, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                suite.name,
+                serde_json::to_string(&suite.cases)?,
+                serde_json::to_string(&suite.models)?,
+                suite.judge_model,
+                suite.interval_hours,
+                suite.created_at,
+                suite.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn eval_suite(&self, name: &str) -> Result<Option<EvalSuite>> {
+        Ok(self.conn
+            .query_row(
+                "SELECT name, cases, models, judge_model, interval_hours, created_at, updated_at FROM eval_suites WHERE name = ?1",
+                params![name],
+                row_to_eval_suite,
+            )
+            .optional()?)
+    }
+
+    /// Every eval suite, by name
+    pub fn list_eval_suites(&self) -> Result<Vec<EvalSuite>> {
+        let mut statement = self.conn.prepare(
+            "SELECT name, cases, models, judge_model, interval_hours, created_at, updated_at FROM eval_suites ORDER BY name",
+        )?;
+        let suites = statement
+            .query_map([], row_to_eval_suite)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(suites)
+    }
+
+    /// Delete a suite with its runs; returns whether there was a suite by that name
+    pub fn delete_eval_suite(&mut self, name: &str) -> Result<bool> {
+        self.require_writable()?;
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM eval_results WHERE run_id IN (SELECT id FROM eval_runs WHERE suite_name = ?1)",
+            params![name],
+        )?;
+        tx.execute("DELETE FROM eval_runs WHERE suite_name = ?1", params![name])?;
+        let deleted = tx.execute("DELETE FROM eval_suites WHERE name = ?1", params![name])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Record a run as started; its results are saved as they come in
+    pub fn start_eval_run(&self, run: &EvalRun) -> Result<()> {
+        self.write(
+            "INSERT INTO eval_runs (id, suite_name, judge_model, scheduled, started_at, finished_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![run.id, run.suite_name, run.judge_model, run.scheduled, run.started_at, run.finished_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn save_eval_result(&self, run_id: &str, result: &EvalResult) -> Result<()> {
+        self.write(
+            "INSERT OR REPLACE INTO eval_results
+             (run_id, case_index, model, prompt, output, score, reasoning, error, estimated_usd)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                run_id,
+                result.case_index,
+                result.model,
+                result.prompt,
+                result.output,
+                result.score,
+                result.reasoning,
+                result.error,
+                result.estimated_usd,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn finish_eval_run(&self, run_id: &str, finished_at: &str) -> Result<()> {
+        self.write("UPDATE eval_runs SET finished_at = ?2 WHERE id = ?1", params![run_id, finished_at])?;
+        Ok(())
+    }
+
+    /// The newest `limit` runs of a suite with their results, newest first
+    pub fn list_eval_runs(&self, suite_name: &str, limit: u32) -> Result<Vec<EvalRun>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, suite_name, judge_model, scheduled, started_at, finished_at FROM eval_runs
+             WHERE suite_name = ?1 ORDER BY started_at DESC, rowid DESC LIMIT ?2",
+        )?;
+        let mut runs = statement
+            .query_map(params![suite_name, limit], |row| Ok(EvalRun {
+                id: row.get(0)?,
+                suite_name: row.get(1)?,
+                judge_model: row.get(2)?,
+                scheduled: row.get(3)?,
+                started_at: row.get(4)?,
+                finished_at: row.get(5)?,
+                results: Vec::new(),
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut statement = self.conn.prepare(
+            "SELECT case_index, model, prompt, output, score, reasoning, error, estimated_usd FROM eval_results
+             WHERE run_id = ?1 ORDER BY case_index, rowid",
+        )?;
+        for run in &mut runs {
+            run.results = statement
+                .query_map(params![run.id], |row| Ok(EvalResult {
+                    case_index: row.get(0)?,
+                    model: row.get(1)?,
+                    prompt: row.get(2)?,
+                    output: row.get(3)?,
+                    score: row.get(4)?,
+                    reasoning: row.get(5)?,
+                    error: row.get(6)?,
+                    estimated_usd: row.get(7)?,
+                }))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+        Ok(runs)
+    }
+
+    /// When the suite's newest run started, if it has run at all
+    pub fn last_eval_run_started(&self, suite_name: &str) -> Result<Option<String>> {
+        Ok(self.conn.query_row(
+            "SELECT MAX(started_at) FROM eval_runs WHERE suite_name = ?1",
+            params![suite_name],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Record a benchmark as started; its results are saved as each model finishes
+    pub fn start_benchmark(&self, report: &BenchmarkReport) -> Result<()> {
+        self.write(
+            "INSERT INTO benchmark_reports (id, prompt, started_at, finished_at) VALUES (?1, ?2, ?3, ?4)",
+            params![report.id, report.prompt, report.started_at, report.finished_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn save_benchmark_result(&self, report_id: &str, position: u32, result: &BenchmarkResult) -> Result<()> {
+        self.write(
+            "INSERT OR REPLACE INTO benchmark_results
+             (report_id, position, provider, model, ttft_ms, total_ms, tokens_per_second, output_tokens, output_chars, estimated_usd, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                report_id,
+                position,
+                result.provider,
+                result.model,
+                result.ttft_ms,
+                result.total_ms,
+                result.tokens_per_second,
+                result.output_tokens,
+                result.output_chars,
+                result.estimated_usd,
+                result.error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn finish_benchmark(&self, report_id: &str, finished_at: &str) -> Result<()> {
+        self.write("UPDATE benchmark_reports SET finished_at = ?2 WHERE id = ?1", params![report_id, finished_at])?;
+        Ok(())
+    }
+
+    /// The newest `limit` benchmark reports with their results, newest first
+    pub fn list_benchmarks(&self, limit: u32) -> Result<Vec<BenchmarkReport>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, prompt, started_at, finished_at FROM benchmark_reports ORDER BY started_at DESC, rowid DESC LIMIT ?1",
+        )?;
+        let mut reports = statement
+            .query_map(params![limit], |row| Ok(BenchmarkReport {
+                id: row.get(0)?,
+                prompt: row.get(1)?,
+                started_at: row.get(2)?,
+                finished_at: row.get(3)?,
+                results: Vec::new(),
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut statement = self.conn.prepare(
+            "SELECT provider, model, ttft_ms, total_ms, tokens_per_second, output_tokens, output_chars, estimated_usd, error
+             FROM benchmark_results WHERE report_id = ?1 ORDER BY position",
+        )?;
+        for report in &mut reports {
+            report.results = statement
+                .query_map(params![report.id], |row| Ok(BenchmarkResult {
+                    provider: row.get(0)?,
+                    model: row.get(1)?,
+                    ttft_ms: row.get(2)?,
+                    total_ms: row.get(3)?,
+                    tokens_per_second: row.get(4)?,
+                    output_tokens: row.get(5)?,
+                    output_chars: row.get(6)?,
+                    estimated_usd: row.get(7)?,
+                    error: row.get(8)?,
+                }))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+        Ok(reports)
+    }
+
+    pub fn record_tool_invocation(&self, invocation: &ToolInvocation) -> Result<()> {
+        self.write(
+            "INSERT INTO tool_invocations (plugin, tool, called_at, duration_ms, cached, error, result_tokens)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                invocation.plugin,
+                invocation.tool,
+                invocation.called_at,
+                invocation.duration_ms,
+                invocation.cached,
+                invocation.error,
+                invocation.result_tokens,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Every recorded tool call, oldest first
+    pub fn list_tool_invocations(&self) -> Result<Vec<ToolInvocation>> {
+        let mut statement = self.conn.prepare(
+            "SELECT plugin, tool, called_at, duration_ms, cached, error, result_tokens FROM tool_invocations
+             ORDER BY called_at, id",
+        )?;
+        let invocations = statement
+            .query_map([], |row| Ok(ToolInvocation {
+                plugin: row.get(0)?,
+                tool: row.get(1)?,
+                called_at: row.get(2)?,
+                duration_ms: row.get(3)?,
+                cached: row.get(4)?,
+                error: row.get(5)?,
+                result_tokens: row.get(6)?,
+            }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(invocations)
+    }
+
+    /// Delete a benchmark report; returns whether it existed
+    pub fn delete_benchmark(&mut self, id: &str) -> Result<bool> {
+        self.require_writable()?;
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM benchmark_results WHERE report_id = ?1", params![id])?;
+        let deleted = tx.execute("DELETE FROM benchmark_reports WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    pub fn save_handoff(&self, handoff: &Handoff) -> Result<()> {
+        self.write(
+            "INSERT OR REPLACE INTO handoffs
+             (id, conversation_id, from_device, to_device, transport, draft, enabled_tools, resume_message_id, sent_at, accepted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                handoff.id,
+                handoff.conversation_id,
+                handoff.from_device,
+                handoff.to_device,
+                handoff.transport.as_str(),
+                handoff.draft,
+                serde_json::to_string(&handoff.enabled_tools)?,
+                handoff.resume_message_id,
+                handoff.sent_at,
+                handoff.accepted_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn handoff(&self, id: &str) -> Result<Option<Handoff>> {
+        Ok(self.conn
+            .query_row(
+                "SELECT id, conversation_id, from_device, to_device, transport, draft, enabled_tools, resume_message_id, sent_at, accepted_at
+                 FROM handoffs WHERE id = ?1",
+                params![id],
+                row_to_handoff,
+            )
+            .optional()?)
+    }
+
+    /// Every handoff sent through `transport`, oldest first
+    pub fn list_handoffs(&self, transport: HandoffTransport) -> Result<Vec<Handoff>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, from_device, to_device, transport, draft, enabled_tools, resume_message_id, sent_at, accepted_at
+             FROM handoffs WHERE transport = ?1 ORDER BY sent_at",
+        )?;
+        let handoffs = statement
+            .query_map(params![transport.as_str()], row_to_handoff)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(handoffs)
+    }
+
+    /// Handoffs to `device` sent after `since` and not accepted yet, oldest first
+    pub fn pending_handoffs(&self, device: &str, since: &str) -> Result<Vec<Handoff>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, conversation_id, from_device, to_device, transport, draft, enabled_tools, resume_message_id, sent_at, accepted_at
+             FROM handoffs WHERE to_device = ?1 AND accepted_at IS NULL AND sent_at > ?2 ORDER BY sent_at",
+        )?;
+        let handoffs = statement
+            .query_map(params![device, since], row_to_handoff)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(handoffs)
+    }
+
+    pub fn delete_handoff(&self, id: &str) -> Result<()> {
+        self.write("DELETE FROM handoffs WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Ids of the attachments `conversation_id` refers to, in the order they were attached
+    pub fn conversation_attachment_ids(&self, conversation_id: &str) -> Result<Vec<String>> {
+        let mut statement = self.conn.prepare(
+            "SELECT attachment_id FROM attachment_refs WHERE conversation_id = ?1 ORDER BY created_at, rowid",
+        )?;
+        let ids = statement
+            .query_map(params![conversation_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ids)
+    }
+
+    /// Add an attachment to the manifest; a no-op if the same content is already there
+    pub fn insert_attachment(&self, attachment: &Attachment) -> Result<()> {
+        self.write(
+            "INSERT OR IGNORE INTO attachments (id, kind, file_name, stored_name, bytes, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                attachment.id,
+                attachment.kind.as_str(),
+                attachment.file_name,
+                attachment.path,
+                attachment.bytes,
+                attachment.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_attachment_reference(&self, attachment_id: &str, conversation_id: &str) -> Result<()> {
+        self.write(
+            "INSERT OR IGNORE INTO attachment_refs (attachment_id, conversation_id, created_at) VALUES (?1, ?2, ?3)",
+            params![attachment_id, conversation_id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_attachment_reference(&self, attachment_id: &str, conversation_id: &str) -> Result<()> {
+        let removed = self.write(
+            "DELETE FROM attachment_refs WHERE attachment_id = ?1 AND conversation_id = ?2",
+            params![attachment_id, conversation_id],
+        )?;
+        if removed == 0 {
+            return Err(anyhow::anyhow!(tr!("history-attachment-not-in-conversation", attachment_id = attachment_id, conversation_id = conversation_id)));
+        }
+        Ok(())
+    }
+
+    /// A manifest entry; `path` is the stored file name relative to the attachment directory
+    pub fn attachment(&self, id: &str) -> Result<Option<Attachment>> {
+        Ok(self.conn
+            .query_row(
+                "SELECT a.id, a.kind, a.file_name, a.stored_name, a.bytes, a.created_at, COUNT(r.conversation_id) + (
+                     SELECT COUNT(*) FROM starter_attachments s WHERE s.attachment_id = a.id
+                 )
+                 FROM attachments a LEFT JOIN attachment_refs r ON r.attachment_id = a.id
+                 WHERE a.id = ?1 GROUP BY a.id",
+                params![id],
+                row_to_attachment,
+            )
+            .optional()?)
+    }
+
+    /// All manifest entries with their reference counts, oldest first
+    pub fn list_attachments(&self) -> Result<Vec<Attachment>> {
+        let mut statement = self.conn.prepare(
+            "SELECT a.id, a.kind, a.file_name, a.stored_name, a.bytes, a.created_at, COUNT(r.conversation_id) + (
+                 SELECT COUNT(*) FROM starter_attachments s WHERE s.attachment_id = a.id
+             )
+             FROM attachments a LEFT JOIN attachment_refs r ON r.attachment_id = a.id
+             GROUP BY a.id ORDER BY a.created_at",
+        )?;
+        let attachments = statement
+            .query_map([], row_to_attachment)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(attachments)
+    }
+
+    pub fn delete_attachment(&self, id: &str) -> Result<()> {
+        self.write("DELETE FROM attachment_refs WHERE attachment_id = ?1", params![id])?;
+        self.write("DELETE FROM starter_attachments WHERE attachment_id = ?1", params![id])?;
+        self.write("DELETE FROM attachments WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn sync_entry(&self, key: &str) -> Result<Option<SyncEntry>> {
+        Ok(self.conn
+            .query_row(
+                "SELECT key, clock, hash, updated_at, device_id, pending FROM sync_state WHERE key = ?1",
+                params![key],
+                row_to_sync_entry,
+            )
+            .optional()?)
+    }
+
+    pub fn sync_entries(&self) -> Result<HashMap<String, SyncEntry>> {
+        let mut statement = self.conn.prepare("SELECT key, clock, hash, updated_at, device_id, pending FROM sync_state")?;
+        let entries = statement
+            .query_map([], row_to_sync_entry)?
+            .map(|entry| entry.map(|entry| (entry.key.clone(), entry)))
+            .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+        Ok(entries)
+    }
+
+    pub fn pending_sync_entries(&self) -> Result<Vec<SyncEntry>> {
+        let mut statement = self.conn.prepare(
+            "SELECT key, clock, hash, updated_at, device_id, pending FROM sync_state WHERE pending = 1",
+        )?;
+        let entries = statement
+            .query_map([], row_to_sync_entry)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    pub fn save_sync_entry(&self, entry: &SyncEntry) -> Result<()> {
+        self.write(
+            "INSERT OR REPLACE INTO sync_state (key, clock, hash, updated_at, device_id, pending)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.key,
+                serde_json::to_string(&entry.clock)?,
+                entry.hash,
+                entry.updated_at,
+                entry.device_id,
+                entry.pending,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_sync_pending<'a>(&mut self, keys: impl Iterator<Item = &'a str>) -> Result<()> {
+        self.require_writable()?;
+        let tx = self.conn.transaction()?;
+        for key in keys {
+            tx.execute("UPDATE sync_state SET pending = 0 WHERE key = ?1", params![key])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Whether the sync object `name` from another device has been applied here
+    pub fn is_sync_applied(&self, name: &str) -> Result<bool> {
+        Ok(self.conn
+            .query_row("SELECT 1 FROM sync_applied WHERE object = ?1", params![name], |_| Ok(()))
+            .optional()?
+            .is_some())
+    }
+
+    pub fn mark_sync_applied(&self, name: &str) -> Result<()> {
+        self.write(
+            "INSERT OR IGNORE INTO sync_applied (object, applied_at) VALUES (?1, ?2)",
+            params![name, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Note that a batch from `device_id` written at `seen_at` was applied
+    pub fn record_sync_device(&self, device_id: &str, seen_at: &str) -> Result<()> {
+        self.write(
+            "INSERT INTO sync_devices (device_id, last_seen_at) VALUES (?1, ?2)
+             ON CONFLICT (device_id) DO UPDATE SET last_seen_at = MAX(last_seen_at, excluded.last_seen_at)",
+            params![device_id, seen_at],
+        )?;
+        Ok(())
+    }
+
+    /// Other devices seen through sync, most recently seen first
+    pub fn sync_devices(&self) -> Result<Vec<SyncDevice>> {
+        let mut statement = self.conn.prepare("SELECT device_id, last_seen_at FROM sync_devices ORDER BY last_seen_at DESC")?;
+        let devices = statement
+            .query_map([], |row| Ok(SyncDevice { device_id: row.get(0)?, last_seen_at: row.get(1)? }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(devices)
+    }
+}
+
+fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    let kind: String = row.get(1)?;
+    Ok(Attachment {
+        id: row.get(0)?,
+        kind: AttachmentKind::parse(&kind),
+        file_name: row.get(2)?,
+        path: row.get(3)?,
+        bytes: row.get(4)?,
+        created_at: row.get(5)?,
+        references: row.get(6)?,
+    })
+}
+
+fn row_to_eval_suite(row: &rusqlite::Row) -> rusqlite::Result<EvalSuite> {
+    Ok(EvalSuite {
+        name: row.get(0)?,
+        cases: json_column(row, 1)?,
+        models: json_column(row, 2)?,
+        judge_model: row.get(3)?,
+        interval_hours: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn row_to_handoff(row: &rusqlite::Row) -> rusqlite::Result<Handoff> {
+    let transport: String = row.get(4)?;
+    Ok(Handoff {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        from_device: row.get(2)?,
+        to_device: row.get(3)?,
+        transport: HandoffTransport::parse(&transport),
+        draft: row.get(5)?,
+        enabled_tools: json_column(row, 6)?,
+        resume_message_id: row.get(7)?,
+        sent_at: row.get(8)?,
+        accepted_at: row.get(9)?,
+    })
+}
+
+fn json_column<T: serde::de::DeserializeOwned>(row: &rusqlite::Row, index: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(index)?;
+    serde_json::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn row_to_sync_entry(row: &rusqlite::Row) -> rusqlite::Result<SyncEntry> {
+    let clock: String = row.get(1)?;
+    Ok(SyncEntry {
+        key: row.get(0)?,
+        clock: serde_json::from_str(&clock)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?,
+        hash: row.get(2)?,
+        updated_at: row.get(3)?,
+        device_id: row.get(4)?,
+        pending: row.get(5)?,
+    })
+}
+
+fn row_to_candidate(row: &rusqlite::Row) -> rusqlite::Result<Candidate> {
+    Ok(Candidate {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        model: row.get(2)?,
+        content: row.get(3)?,
+        selected: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn row_to_message(row: &rusqlite::Row) -> rusqlite::Result<HistoryMessage> {
+    let status: String = row.get(5)?;
+    Ok(HistoryMessage {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        role: row.get(2)?,
+        content: row.get(3)?,
+        model: row.get(4)?,
+        status: MessageStatus::parse(&status),
+        parent_id: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The schema history.rs created inline before migrations were tracked
+    const INLINE_SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS messages (
+            id TEXT PRIMARY KEY, conversation_id TEXT NOT NULL, role TEXT NOT NULL, content TEXT NOT NULL,
+            model TEXT, status TEXT NOT NULL, parent_id TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages (conversation_id, created_at);
+        CREATE TABLE IF NOT EXISTS candidates (
+            id TEXT PRIMARY KEY, message_id TEXT NOT NULL, model TEXT NOT NULL, content TEXT NOT NULL,
+            selected INTEGER NOT NULL DEFAULT 0, created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_candidates_message ON candidates (message_id);
+        CREATE TABLE IF NOT EXISTS message_details (message_id TEXT PRIMARY KEY, details TEXT NOT NULL);
+        CREATE TABLE IF NOT EXISTS memories (
+            id TEXT PRIMARY KEY, content TEXT NOT NULL, conversation_id TEXT, created_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS conversation_compression (
+            conversation_id TEXT PRIMARY KEY, enabled INTEGER NOT NULL, ratio REAL NOT NULL,
+            last_before_tokens INTEGER, last_after_tokens INTEGER
+        );
+        CREATE TABLE IF NOT EXISTS attachments (
+            id TEXT PRIMARY KEY, kind TEXT NOT NULL, file_name TEXT NOT NULL, stored_name TEXT NOT NULL,
+            bytes INTEGER NOT NULL, created_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS attachment_refs (
+            attachment_id TEXT NOT NULL, conversation_id TEXT NOT NULL, created_at TEXT NOT NULL,
+            PRIMARY KEY (attachment_id, conversation_id)
+        );
+        CREATE TABLE IF NOT EXISTS sync_state (
+            key TEXT PRIMARY KEY, clock TEXT NOT NULL, hash TEXT NOT NULL, updated_at TEXT NOT NULL,
+            device_id TEXT NOT NULL, pending INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS sync_applied (object TEXT PRIMARY KEY, applied_at TEXT NOT NULL);";
+
+    #[test]
+    fn databases_from_before_migrations_upgrade_with_their_history() {
+        let dir = std::env::temp_dir().join(format!("mcp-history-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let message = HistoryMessage::new("c", "user", "from an old release".to_string(), None, None);
+        let conn = Connection::open(dir.join(HISTORY_DB)).unwrap();
+        conn.execute_batch(INLINE_SCHEMA).unwrap();
+        conn.execute(
+            "INSERT INTO messages (id, conversation_id, role, content, model, status, parent_id, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, NULL, 'complete', NULL, ?5, ?5)",
+            params![message.id, message.conversation_id, message.role, message.content, message.created_at],
+        ).unwrap();
+        drop(conn);
+
+        let store = HistoryStore::open(&dir).unwrap();
+        let messages = store.all_messages().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "from an old release");
+        // Later migrations' tables are there too
+        store.insert(&HistoryMessage::new("c", "assistant", "answer".to_string(), None, Some(message.id.clone()))).unwrap();
+        assert!(store.list_handoffs(HandoffTransport::Sync).unwrap().is_empty());
+        drop(store);
+
+        // The pre-migration copy has the old schema and the old message
+        let backups: Vec<String> = std::fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("history.db.before-v"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let backup = Connection::open(dir.join(&backups[0])).unwrap();
+        let count: u32 = backup.query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+        drop(backup);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
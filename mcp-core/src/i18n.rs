@@ -0,0 +1,164 @@
+//! Localized backend messages. Catalogues are Fluent files under `locales/`, compiled in; the UI
+//! gets the same files through `get_message_catalogue` so both sides format from one source.
+//! Use `tr!` for anything the user reads; log lines stay in English.
+
+use std::sync::{OnceLock, RwLock};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::FluentResource;
+use fluent_langneg::{negotiate_languages, NegotiationStrategy};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use unic_langid::LanguageIdentifier;
+use crate::config::ConfigManager;
+
+pub use fluent_bundle::FluentArgs;
+
+/// Shipped catalogues. The first is the fallback for messages missing from a translation, and
+/// the locale used when nothing else matches.
+const CATALOGUES: [(&str, &str); 3] = [
+    ("en-US", include_str!("../locales/en-US/main.ftl")),
+    ("de", include_str!("../locales/de/main.ftl")),
+    ("fr", include_str!("../locales/fr/main.ftl")),
+];
+
+/// A locale's Fluent source, for the UI to build its own bundle from
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct MessageCatalogue {
+    /// The negotiated locale messages are formatted in
+    pub locale: String,
+    pub available_locales: Vec<String>,
+    pub ftl: String,
+    /// The en-US source, for ids the locale's catalogue doesn't have
+    pub fallback_ftl: String,
+}
+
+struct Catalogue {
+    locale: LanguageIdentifier,
+    ftl: &'static str,
+    bundle: FluentBundle<FluentResource>,
+}
+
+fn catalogues() -> &'static [Catalogue] {
+    static LOADED: OnceLock<Vec<Catalogue>> = OnceLock::new();
+    LOADED.get_or_init(|| {
+        CATALOGUES.iter().map(|&(tag, ftl)| {
+            let locale: LanguageIdentifier = tag.parse().expect("shipped locale tags are valid");
+            let resource = FluentResource::try_new(ftl.to_string()).unwrap_or_else(|(resource, errors)| {
+                log::error!("Message catalogue {} has syntax errors: {:?}", tag, errors);
+                resource
+            });
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.clone()]);
+            // Bidi isolation marks end up as stray characters in plain-text errors
+            bundle.set_use_isolating(false);
+            if let Err(errors) = bundle.add_resource(resource) {
+                log::error!("Message catalogue {} has duplicate messages: {:?}", tag, errors);
+            }
+            Catalogue { locale, ftl, bundle }
+        }).collect()
+    })
+}
+
+fn catalogue_for(locale: &LanguageIdentifier) -> &'static Catalogue {
+    let catalogues = catalogues();
+    catalogues.iter().find(|catalogue| &catalogue.locale == locale).unwrap_or(&catalogues[0])
+}
+
+/// The locale messages are formatted in; None until first use, when it's resolved from config and the OS
+fn current() -> &'static RwLock<Option<LanguageIdentifier>> {
+    static CURRENT: RwLock<Option<LanguageIdentifier>> = RwLock::new(None);
+    &CURRENT
+}
+
+/// The best shipped locale for `requested`, in order of preference
+fn negotiate(requested: &[LanguageIdentifier]) -> LanguageIdentifier {
+    let available: Vec<LanguageIdentifier> = catalogues().iter().map(|catalogue| catalogue.locale.clone()).collect();
+    let default = catalogues()[0].locale.clone();
+    negotiate_languages(requested, &available, Some(&default), NegotiationStrategy::Filtering)
+        .first()
+        .map(|&locale| locale.clone())
+        .unwrap_or(default)
+}
+
+fn os_locales() -> Vec<LanguageIdentifier> {
+    sys_locale::get_locales().filter_map(|tag| tag.parse().ok()).collect()
+}
+
+/// The locale the user chose, or the OS's preferred one
+fn resolve() -> LanguageIdentifier {
+    let chosen = ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_locale())
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read locale from config: {}", e);
+            None
+        });
+    match chosen.and_then(|tag| tag.parse().ok()) {
+        Some(locale) => negotiate(&[locale]),
+        None => negotiate(&os_locales()),
+    }
+}
+
+pub fn locale() -> LanguageIdentifier {
+    if let Some(locale) = current().read().ok().and_then(|current| current.clone()) {
+        return locale;
+    }
+    let locale = resolve();
+    log::info!("Formatting messages in {}", locale);
+    if let Ok(mut current) = current().write() {
+        *current = Some(locale.clone());
+    }
+    locale
+}
+
+/// Switch to the best match for `tag`, or back to the OS locale for None. Returns the locale
+/// now in use, which may differ from the one asked for.
+pub fn set_locale(tag: Option<&str>) -> anyhow::Result<LanguageIdentifier> {
+    let locale = match tag {
+        Some(tag) => negotiate(&[tag.parse().map_err(|_| anyhow::anyhow!(crate::tr!("invalid-locale", locale = tag)))?]),
+        None => negotiate(&os_locales()),
+    };
+    log::info!("Switching messages to {} (asked for {:?})", locale, tag);
+    if let Ok(mut current) = current().write() {
+        *current = Some(locale.clone());
+    }
+    Ok(locale)
+}
+
+/// Format message `id` in the current locale, falling back to en-US and then to the id itself
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let locale = locale();
+    for catalogue in [catalogue_for(&locale), &catalogues()[0]] {
+        let Some(pattern) = catalogue.bundle.get_message(id).and_then(|message| message.value()) else { continue };
+        let mut errors = Vec::new();
+        let formatted = catalogue.bundle.format_pattern(pattern, args, &mut errors);
+        if !errors.is_empty() {
+            log::warn!("Formatting message {} in {} failed: {:?}", id, catalogue.locale, errors);
+        }
+        return formatted.into_owned();
+    }
+    log::warn!("No message {} in any catalogue", id);
+    id.to_string()
+}
+
+pub fn catalogue() -> MessageCatalogue {
+    let catalogue = catalogue_for(&locale());
+    MessageCatalogue {
+        locale: catalogue.locale.to_string(),
+        available_locales: catalogues().iter().map(|catalogue| catalogue.locale.to_string()).collect(),
+        ftl: catalogue.ftl.to_string(),
+        fallback_ftl: catalogues()[0].ftl.to_string(),
+    }
+}
+
+/// Format a catalogue message: `tr!("id")` or `tr!("id", name = value, ...)`
+#[macro_export]
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
@@ -0,0 +1,237 @@
+//! Statistics over the conversation history for the insights dashboard: activity per day, which
+//! models answered, how long answers run and what the user asks about most. Topics come from
+//! clustering prompt embeddings, so they need the provider; everything else is computed locally.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::history::{HistoryMessage, MessageStatus};
+
+/// Prompts at least this similar to a topic's first prompt belong to that topic
+pub const TOPIC_SIMILARITY: f32 = 0.8;
+
+/// Most recent prompts embedded for topics; older ones are left out to bound the request
+pub const TOPIC_SAMPLE: usize = 200;
+
+/// Characters of each prompt embedded, which is plenty to tell what it's about
+const TOPIC_PROMPT_CHARS: usize = 500;
+
+const TOP_TOPICS: usize = 5;
+
+const TOPIC_LABEL_CHARS: usize = 80;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum InsightsPeriod {
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl InsightsPeriod {
+    /// Start of the period ending at `now`; None for all of history
+    pub fn start(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            InsightsPeriod::Week => Some(now - Duration::days(7)),
+            InsightsPeriod::Month => Some(now - Duration::days(30)),
+            InsightsPeriod::Year => Some(now - Duration::days(365)),
+            InsightsPeriod::All => None,
+        }
+    }
+}
+
+/// Messages sent and received on one day (UTC)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct DailyActivity {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub prompts: u32,
+    pub responses: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ModelUsage {
+    pub model: String,
+    pub responses: u32,
+}
+
+/// Prompts that embed close together
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct Topic {
+    /// The prompt the others were grouped around, shortened
+    pub label: String,
+    pub prompts: u32,
+    pub conversations: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct HistoryInsights {
+    pub period: InsightsPeriod,
+    pub conversations: u32,
+    pub prompts: u32,
+    pub responses: u32,
+    /// Every day from the start of the period (or the first message) to today, quiet days included
+    pub daily: Vec<DailyActivity>,
+    /// Most responses first
+    pub models: Vec<ModelUsage>,
+    /// Over completed responses
+    pub average_response_chars: u32,
+    /// Estimated spend on responses in the period
+    pub estimated_usd: f64,
+    /// Largest first; empty when embeddings couldn't be fetched
+    pub topics: Vec<Topic>,
+}
+
+fn day_of(timestamp: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|at| at.with_timezone(&Utc).date_naive())
+}
+
+/// Everything but topics and spend, from the messages in the period
+pub fn summarize(period: InsightsPeriod, messages: &[HistoryMessage], now: DateTime<Utc>) -> HistoryInsights {
+    let mut per_day: BTreeMap<NaiveDate, (u32, u32)> = BTreeMap::new();
+    let mut models: HashMap<&str, u32> = HashMap::new();
+    let mut conversations = HashSet::new();
+    let (mut prompts, mut responses) = (0, 0);
+    let (mut response_chars, mut completed) = (0usize, 0usize);
+    for message in messages {
+        conversations.insert(message.conversation_id.as_str());
+        let day = day_of(&message.created_at);
+        match message.role.as_str() {
+            "user" => {
+                prompts += 1;
+                if let Some(day) = day {
+                    per_day.entry(day).or_default().0 += 1;
+                }
+            }
+            "assistant" => {
+                responses += 1;
+                if let Some(day) = day {
+                    per_day.entry(day).or_default().1 += 1;
+                }
+                if let Some(model) = &message.model {
+                    *models.entry(model.as_str()).or_default() += 1;
+                }
+                if message.status == MessageStatus::Complete {
+                    response_chars += message.content.chars().count();
+                    completed += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let today = now.date_naive();
+    let first = period.start(now).map(|start| start.date_naive()).or_else(|| per_day.keys().next().copied());
+    let daily = match first {
+        Some(first) => first.iter_days()
+            .take_while(|day| *day <= today)
+            .map(|day| {
+                let (prompts, responses) = per_day.get(&day).copied().unwrap_or_default();
+                DailyActivity { date: day.format("%Y-%m-%d").to_string(), prompts, responses }
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut models: Vec<ModelUsage> = models.into_iter()
+        .map(|(model, responses)| ModelUsage { model: model.to_string(), responses })
+        .collect();
+    models.sort_by(|a, b| b.responses.cmp(&a.responses).then_with(|| a.model.cmp(&b.model)));
+
+    HistoryInsights {
+        period,
+        conversations: conversations.len() as u32,
+        prompts,
+        responses,
+        daily,
+        models,
+        average_response_chars: response_chars.checked_div(completed).unwrap_or(0) as u32,
+        estimated_usd: 0.0,
+        topics: Vec::new(),
+    }
+}
+
+/// The prompts to find topics in: the most recent `TOPIC_SAMPLE` from conversations `readable`
+/// allows
+pub fn topic_prompts(messages: &[HistoryMessage], readable: impl Fn(&str) -> bool) -> Vec<&HistoryMessage> {
+    let mut prompts: Vec<&HistoryMessage> = messages.iter()
+        .filter(|message| message.role == "user" && !message.content.trim().is_empty())
+        .filter(|message| readable(&message.conversation_id))
+        .collect();
+    let skip = prompts.len().saturating_sub(TOPIC_SAMPLE);
+    prompts.drain(..skip);
+    prompts
+}
+
+pub fn topic_text(prompt: &HistoryMessage) -> String {
+    prompt.content.chars().take(TOPIC_PROMPT_CHARS).collect()
+}
+
+/// The largest groups of `prompts` as topics, given the prompts' embeddings
+pub fn topics(prompts: &[&HistoryMessage], embeddings: &[Vec<f32>]) -> Vec<Topic> {
+    let mut groups = crate::dedup::group_by_similarity(embeddings, TOPIC_SIMILARITY);
+    groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
+    groups.into_iter()
+        .take(TOP_TOPICS)
+        .map(|group| {
+            let leader = &prompts[group[0]].content;
+            let mut label: String = leader.split_whitespace().collect::<Vec<_>>().join(" ");
+            if label.chars().count() > TOPIC_LABEL_CHARS {
+                label = format!("{}…", label.chars().take(TOPIC_LABEL_CHARS).collect::<String>());
+            }
+            let conversations: HashSet<&str> = group.iter().map(|&i| prompts[i].conversation_id.as_str()).collect();
+            Topic { label, prompts: group.len() as u32, conversations: conversations.len() as u32 }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(conversation_id: &str, role: &str, content: &str, created_at: &str) -> HistoryMessage {
+        let mut message = HistoryMessage::new(conversation_id, role, content.to_string(), None, None);
+        if role == "assistant" {
+            message.model = Some("model-a".to_string());
+        }
+        message.created_at = created_at.to_string();
+        message
+    }
+
+    #[test]
+    fn counts_days_models_and_lengths() {
+        let now = DateTime::parse_from_rfc3339("2026-10-16T12:00:00+00:00").unwrap().with_timezone(&Utc);
+        let messages = vec![
+            message("a", "user", "hi", "2026-10-10T09:00:00+00:00"),
+            message("a", "assistant", "hello", "2026-10-10T09:00:01+00:00"),
+            message("b", "user", "again", "2026-10-12T09:00:00+00:00"),
+            message("b", "assistant", "hello there", "2026-10-12T09:00:01+00:00"),
+        ];
+        let insights = summarize(InsightsPeriod::Week, &messages, now);
+        assert_eq!((insights.conversations, insights.prompts, insights.responses), (2, 2, 2));
+        assert_eq!(insights.daily.len(), 8);
+        assert_eq!(insights.daily[0].date, "2026-10-09");
+        assert_eq!(insights.daily[1], DailyActivity { date: "2026-10-10".to_string(), prompts: 1, responses: 1 });
+        assert_eq!(insights.daily[2].prompts, 0);
+        assert_eq!(insights.models, vec![ModelUsage { model: "model-a".to_string(), responses: 2 }]);
+        assert_eq!(insights.average_response_chars, 8);
+    }
+
+    #[test]
+    fn similar_prompts_share_a_topic() {
+        let embeddings = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.95, 0.05], vec![0.9, 0.1]];
+        let prompts: Vec<HistoryMessage> = ["a", "b", "c", "d"].iter()
+            .map(|id| message(id, "user", &format!("prompt {}", id), "2026-10-10T09:00:00+00:00"))
+            .collect();
+        let prompts: Vec<&HistoryMessage> = prompts.iter().collect();
+        let topics = topics(&prompts, &embeddings);
+        assert_eq!(topics.len(), 2);
+        assert_eq!((topics[0].label.as_str(), topics[0].prompts, topics[0].conversations), ("prompt a", 3, 3));
+    }
+}
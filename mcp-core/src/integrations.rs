@@ -0,0 +1,290 @@
+//! Third-party services described by manifests rather than code. A manifest gives the service's
+//! base URL, how it authenticates, the scopes it needs and the endpoints it offers; one generic
+//! adapter turns a call to a named endpoint into an HTTP request with the integration's
+//! credential from the vault.
+//!
+//! GitHub, Slack and Brave Search ship with the app. More can be added, or the shipped ones
+//! replaced, with `<data_dir>/integrations/<name>.json`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+
+const BUILT_IN: [&str; 3] = [
+    include_str!("../integrations/github.json"),
+    include_str!("../integrations/slack.json"),
+    include_str!("../integrations/brave_search.json"),
+];
+
+const METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// Manifests, each with whether it ships with the app
+pub type Manifests = Vec<(IntegrationManifest, bool)>;
+
+/// How requests to a service are authenticated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+#[serde(tag = "type")]
+pub enum IntegrationAuth {
+    None,
+    /// A key sent as-is in `header`
+    ApiKey { header: String },
+    /// A token sent as `Authorization: Bearer <token>`
+    Bearer,
+    /// An OAuth 2.0 access token, sent as a bearer token
+    OAuth2 {
+        /// The app's registration with the service; sign-in needs one
+        #[serde(default)]
+        client_id: Option<String>,
+        authorization_url: Option<String>,
+        /// For signing in on devices without a browser, where the service supports it
+        device_authorization_url: Option<String>,
+        token_url: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct IntegrationEndpoint {
+    pub name: String,
+    pub method: String,
+    /// Relative to the base URL; `{name}` placeholders are filled from the call's parameters
+    pub path: String,
+    #[serde(default)]
+    pub description: String,
+    /// Scopes the endpoint needs, from the manifest's scopes
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct IntegrationManifest {
+    pub name: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub description: String,
+    pub base_url: String,
+    pub auth: IntegrationAuth,
+    /// Every scope the integration asks for
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub endpoints: Vec<IntegrationEndpoint>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct IntegrationInfo {
+    pub manifest: IntegrationManifest,
+    /// Shipped with the app rather than loaded from the data directory
+    pub built_in: bool,
+    /// A credential is stored in the vault; always true for integrations without auth
+    pub has_credential: bool,
+}
+
+/// A call to an endpoint, ready to send
+#[derive(Debug, PartialEq)]
+pub struct PreparedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/// Name of the vault secret holding an integration's credential; the integration owns it
+pub fn credential_name(integration: &str) -> String {
+    format!("{}.token", integration)
+}
+
+/// Name of the vault secret holding the refresh token of an OAuth integration
+pub fn refresh_token_name(integration: &str) -> String {
+    format!("{}.refresh_token", integration)
+}
+
+/// Name of the vault secret holding the client secret, for OAuth services that require one
+pub fn client_secret_name(integration: &str) -> String {
+    format!("{}.client_secret", integration)
+}
+
+/// Err describing the first problem with `manifest`
+pub fn validate(manifest: &IntegrationManifest) -> Result<()> {
+    let valid_name = !manifest.name.is_empty()
+        && manifest.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid_name {
+        anyhow::bail!("Integration name {:?} must be lowercase letters, digits, dashes or underscores", manifest.name);
+    }
+    if !manifest.base_url.starts_with("https://") && !manifest.base_url.starts_with("http://") {
+        anyhow::bail!("Integration {} has a base URL that isn't http:// or https://", manifest.name);
+    }
+    let mut seen = std::collections::BTreeSet::new();
+    for endpoint in &manifest.endpoints {
+        if !seen.insert(endpoint.name.as_str()) {
+            anyhow::bail!("Integration {} has more than one endpoint named {}", manifest.name, endpoint.name);
+        }
+        if !METHODS.contains(&endpoint.method.as_str()) {
+            anyhow::bail!("Endpoint {}::{} has unsupported method {}", manifest.name, endpoint.name, endpoint.method);
+        }
+        if !endpoint.path.starts_with('/') {
+            anyhow::bail!("Endpoint {}::{} has a path that doesn't start with /", manifest.name, endpoint.name);
+        }
+        if let Some(scope) = endpoint.scopes.iter().find(|scope| !manifest.scopes.contains(scope)) {
+            anyhow::bail!("Endpoint {}::{} needs scope {}, which the manifest doesn't ask for", manifest.name, endpoint.name, scope);
+        }
+    }
+    Ok(())
+}
+
+/// The shipped manifests, then those under `<data_dir>/integrations`, which replace a shipped
+/// one of the same name. Invalid manifests are logged and skipped.
+pub fn discover(data_dir: &Path) -> Result<Manifests> {
+    let mut found: BTreeMap<String, (IntegrationManifest, bool)> = BTreeMap::new();
+    for json in BUILT_IN {
+        let manifest: IntegrationManifest = serde_json::from_str(json)?;
+        validate(&manifest)?;
+        found.insert(manifest.name.clone(), (manifest, true));
+    }
+
+    let root = data_dir.join("integrations");
+    if root.is_dir() {
+        for entry in std::fs::read_dir(&root)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let manifest = serde_json::from_slice::<IntegrationManifest>(&std::fs::read(&path)?)
+                .map_err(anyhow::Error::from)
+                .and_then(|manifest| validate(&manifest).map(|()| manifest));
+            match manifest {
+                Ok(manifest) => {
+                    if found.get(&manifest.name).is_some_and(|(_, built_in)| *built_in) {
+                        log::info!("Integration manifest {:?} replaces the built-in {}", path, manifest.name);
+                    }
+                    found.insert(manifest.name.clone(), (manifest, false));
+                }
+                Err(e) => log::warn!("Skipping invalid integration manifest {:?}: {}", path, e),
+            }
+        }
+    }
+    Ok(found.into_values().collect())
+}
+
+fn loaded() -> &'static Mutex<Option<Manifests>> {
+    static LOADED: OnceLock<Mutex<Option<Manifests>>> = OnceLock::new();
+    LOADED.get_or_init(|| Mutex::new(None))
+}
+
+/// The manifests found by the last `reload`, or None before the first
+pub fn loaded_manifests() -> Option<Manifests> {
+    loaded().lock().ok().and_then(|loaded| loaded.clone())
+}
+
+/// Discover manifests again and keep them for later calls
+pub fn reload(data_dir: &Path) -> Result<Manifests> {
+    let manifests = discover(data_dir)?;
+    log::info!("Loaded {} integrations", manifests.len());
+    if let Ok(mut loaded) = loaded().lock() {
+        *loaded = Some(manifests.clone());
+    }
+    Ok(manifests)
+}
+
+/// Build the request for `endpoint` of `manifest`. Parameters fill the path's placeholders and
+/// the rest go in the query string; `credential` is required unless the service has no auth.
+pub fn prepare(
+    manifest: &IntegrationManifest,
+    endpoint: &str,
+    params: &BTreeMap<String, String>,
+    credential: Option<&str>,
+) -> Result<PreparedRequest> {
+    let endpoint = manifest.endpoints.iter()
+        .find(|candidate| candidate.name == endpoint)
+        .ok_or_else(|| anyhow::anyhow!("Integration {} has no endpoint {}", manifest.name, endpoint))?;
+
+    let mut path = endpoint.path.clone();
+    let mut query = Vec::new();
+    for (name, value) in params {
+        let placeholder = format!("{{{}}}", name);
+        if path.contains(&placeholder) {
+            path = path.replace(&placeholder, &encode_segment(value));
+        } else {
+            query.push((name.as_str(), value.as_str()));
+        }
+    }
+    if let Some(start) = path.find('{') {
+        let name = path[start + 1..].split('}').next().unwrap_or_default();
+        anyhow::bail!("Endpoint {}::{} needs the parameter {}", manifest.name, endpoint.name, name);
+    }
+    let mut url = reqwest::Url::parse(&format!("{}{}", manifest.base_url.trim_end_matches('/'), path))?;
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+
+    // Some services, GitHub among them, turn away requests without a user agent
+    let mut headers = vec![
+        ("Accept".to_string(), "application/json".to_string()),
+        ("User-Agent".to_string(), format!("mcp-switchboard/{}", env!("CARGO_PKG_VERSION"))),
+    ];
+    if manifest.auth != IntegrationAuth::None {
+        let credential = credential
+            .ok_or_else(|| anyhow::anyhow!("No credential is stored for integration {}", manifest.name))?;
+        headers.push(match &manifest.auth {
+            IntegrationAuth::ApiKey { header } => (header.clone(), credential.to_string()),
+            _ => ("Authorization".to_string(), format!("Bearer {}", credential)),
+        });
+    }
+    Ok(PreparedRequest { method: endpoint.method.clone(), url: url.to_string(), headers })
+}
+
+/// `value` percent-encoded for use as one path segment
+fn encode_segment(value: &str) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn shipped_manifests_are_valid() {
+        let manifests = discover(Path::new("/nonexistent")).unwrap();
+        let names: Vec<&str> = manifests.iter().map(|(manifest, _)| manifest.name.as_str()).collect();
+        assert_eq!(names, vec!["brave_search", "github", "slack"]);
+        assert!(manifests.iter().all(|(_, built_in)| *built_in));
+    }
+
+    #[test]
+    fn calls_fill_placeholders_query_and_auth() {
+        let github: IntegrationManifest = serde_json::from_str(BUILT_IN[0]).unwrap();
+        let request = prepare(&github, "list_issues", &params(&[("owner", "simbo1905"), ("repo", "a b"), ("state", "open")]), Some("gho_x")).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://api.github.com/repos/simbo1905/a%20b/issues?state=open");
+        assert!(request.headers.contains(&("Authorization".to_string(), "Bearer gho_x".to_string())));
+
+        assert!(prepare(&github, "list_issues", &params(&[("owner", "simbo1905")]), Some("gho_x")).is_err());
+        assert!(prepare(&github, "get_user", &params(&[]), None).is_err());
+        assert!(prepare(&github, "delete_everything", &params(&[]), Some("gho_x")).is_err());
+
+        let brave: IntegrationManifest = serde_json::from_str(BUILT_IN[2]).unwrap();
+        let request = prepare(&brave, "web_search", &params(&[("q", "rust & wasm")]), Some("key")).unwrap();
+        assert_eq!(request.url, "https://api.search.brave.com/res/v1/web/search?q=rust+%26+wasm");
+        assert!(request.headers.contains(&("X-Subscription-Token".to_string(), "key".to_string())));
+    }
+
+    #[test]
+    fn endpoints_may_only_use_declared_scopes() {
+        let mut github: IntegrationManifest = serde_json::from_str(BUILT_IN[0]).unwrap();
+        github.endpoints[0].scopes.push("admin:org".to_string());
+        assert!(validate(&github).is_err());
+    }
+}
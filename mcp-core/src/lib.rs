@@ -3,14 +3,177 @@ use futures::stream::{StreamExt, Stream};
 use async_openai::Client;
 use async_openai::config::OpenAIConfig;
 use std::pin::Pin;
+use sha2::Digest;
 use ts_rs::TS;
 
 // Re-export everything needed by consumers
 pub use config::ConfigManager;
 pub use build_info::{BuildInfo, DependencyInfo};
+pub use transcription::{Transcript, TranscriptSegment};
+pub use screen_capture::{CaptureAnalysis, CaptureRegion, ScreenCapture};
+pub use templates::{PromptTemplate, TemplateProvenance, TemplateSyncResult};
+pub use plugins::{PluginCommand, PluginInfo, PluginStatus, PluginTool};
+pub use scripting::ScriptInfo;
+pub use events::AppEvent;
+pub use server::ServerStatus;
+pub use history::{Candidate, GenerationParameters, HistoryMessage, MessageDetails, MessageOrigin, MessageStatus, ToolCallRecord, UnreadConversation};
+pub use generation::{CandidateSelection, GenerationMode, GenerationSettings, RegenerationOverrides, Replay};
+pub use cost::{CostEstimate, MessageCost, ModelPricing};
+pub use logprobs::{TokenLogprob, TopToken};
+pub use guardrails::GuardrailConfig;
+pub use compression::ConversationCompression;
+pub use memory::Memory;
+pub use storage::{Attachment, AttachmentKind, CleanupSummary, KindUsage, StorageUsage};
+pub use upload::UploadStatus;
+pub use export::{ExportFormat, ExportSummary};
+pub use backup::{BackupInfo, BackupSchedule, RestoreMode, RestoreSummary};
+pub use sync::{SyncConfig, SyncDevice, SyncReport};
+pub use sync_backend::SyncBackendConfig;
+pub use db_lock::DatabaseStatus;
+pub use cache::CacheScope;
+pub use metrics::{instrument, payload_size, IpcCommandStats};
+pub use startup::{Subsystem, SubsystemState, SubsystemStatus};
+pub use diagnostics::{AllocatorStats, CacheHits, MemoryStats, SubsystemMemory};
+pub use i18n::MessageCatalogue;
+pub use modes::{AppMode, ModeBehavior};
+pub use features::{Feature, FeatureFlag, FlagSource};
+pub use environment::ValueSource;
+pub use portable::PortableStatus;
+pub use system_defaults::SystemDefaultsInfo;
+pub use policy::{Policy, PolicyStatus};
+pub use conversation_lock::LockedConversation;
+pub use insights::{DailyActivity, HistoryInsights, InsightsPeriod, ModelUsage, Topic};
+pub use duplicates::{DuplicateConversations, MergeSummary};
+pub use starters::{ConversationStarter, StartedConversation};
+pub use onboarding::{OnboardingAction, OnboardingState, OnboardingStep};
+pub use status::{QueuedJobs, RateLimitState, StatusSnapshot};
+pub use appearance::{AppearanceSettings, MessageDensity, Theme};
+pub use read_aloud::{ReadAloudState, SpokenSentence};
+pub use stream_filters::ConversationFilters;
+pub use response_diff::{DiffKind, DiffSegment, ResponseDiff};
+pub use eval::{EvalCase, EvalResult, EvalRun, EvalSuite, ModelScore};
+pub use benchmark::{BenchmarkReport, BenchmarkResult};
+pub use pairing::{PairedDevice, PairingCode};
+pub use handoff::{Handoff, HandoffTransport};
+pub use telemetry::TelemetryConfig;
+pub use tool_limits::{ToolResultLimit, ToolResultLimits, TruncationStrategy};
+pub use tool_cache::ToolCacheSettings;
+pub use tool_calls::{ToolCall, ToolCallOutcome};
+pub use tool_stats::{ToolStats, ToolStatsReport};
+use tool_stats::ToolInvocation;
+pub use vault::SecretInfo;
+pub use integrations::{IntegrationAuth, IntegrationEndpoint, IntegrationInfo, IntegrationManifest};
+pub use oauth::DeviceAuthorization;
+pub use azure::AzureProfile;
+pub use bedrock::BedrockAccount;
+pub use gateways::{GatewayPreset, GatewayPresetInfo, GatewayRoute};
+pub use endpoints::CompatibleEndpoint;
+pub use maintenance::{JobStatus, MaintenanceJob, MaintenanceSettings, MaintenanceStatus};
+pub use low_power::{LowPowerMode, LowPowerReason, LowPowerSettings, LowPowerStatus};
+pub use capabilities::{ModelCapabilities, ProviderCapabilities};
+pub use mentions::{MentionKind, MentionSuggestion, MessageMentions};
+pub use orchestration::{OrchestrationAgent, OrchestrationEnd, OrchestrationPlan};
+pub use health::{HealthState, ProviderHealth};
+pub use planner::{PlanStep, PlanStepStatus, TaskPlan};
+pub use custom_models::{CustomModel, CustomModelKind, ModelHealth};
+pub use providers::{Provider, ProviderStatus};
+pub use accessibility::{AccessibilitySettings, CompletionStatus, LivePoliteness, MessageBoundary, MessageRole};
+use generation::MessageStream;
+pub use server_auth::{IssuedServerToken, RequestShaping, ServerNetworkConfig, ServerNetworkUpdate, ServerTlsConfig, ServerTokenInfo, TokenScope};
 
 mod config;
+mod environment;
+mod machine_id;
+mod platform;
+mod portable;
+mod system_defaults;
+mod policy;
+mod cache;
+mod metrics;
+mod startup;
+mod diagnostics;
+mod accessibility;
+mod appearance;
+mod status;
+mod modes;
+mod onboarding;
+mod demo;
+mod features;
+mod i18n;
 mod build_info;
+mod transcription;
+mod screen_capture;
+mod storage;
+mod templates;
+mod plugins;
+mod tool_limits;
+mod tool_cache;
+mod tool_calls;
+mod tool_stats;
+mod vault;
+mod integrations;
+mod oauth;
+mod custom_models;
+mod providers;
+mod anthropic;
+mod azure;
+mod bedrock;
+mod gateways;
+mod endpoints;
+mod failover;
+mod ollama;
+mod completions;
+mod logprobs;
+mod scripting;
+mod events;
+mod server;
+mod server_auth;
+mod openai_api;
+mod pairing;
+mod handoff;
+mod history;
+mod conversation_lock;
+mod insights;
+mod duplicates;
+mod starters;
+mod migrations;
+mod db_lock;
+mod context;
+mod generation;
+mod segmentation;
+mod visibility;
+mod power;
+mod maintenance;
+mod metered;
+mod low_power;
+mod capabilities;
+mod health;
+mod preprocess;
+mod mentions;
+mod orchestration;
+mod planner;
+mod cost;
+mod guardrails;
+mod redaction;
+mod read_aloud;
+mod stream_filters;
+mod stream_recording;
+mod sse;
+mod sigv4;
+mod response_diff;
+mod eval;
+mod benchmark;
+mod telemetry;
+mod compression;
+mod dedup;
+mod memory;
+mod upload;
+mod export;
+mod backup;
+mod sync;
+mod sync_backend;
+#[cfg(test)]
+mod streaming_bench;
 
 #[derive(Serialize, Deserialize, Clone, TS)]
 #[ts(export)]
@@ -18,8 +181,19 @@ pub struct ModelInfo {
     pub id: String,
     pub display_name: String,
     pub organization: String,
+    pub context_length: Option<u32>,
+    /// What the provider charges, where its model list says
+    #[serde(default)]
+    pub pricing: Option<cost::ModelPricing>,
 }
 
+/// Each provider's model list, by provider and API key digest
+static MODELS: cache::Cache<Vec<ModelInfo>> = cache::Cache::new(std::time::Duration::from_secs(10 * 60), 8);
+
+/// The conversation on screen while the window has focus; answers completing in any other
+/// conversation are counted as unread
+static FOCUSED_CONVERSATION: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
 #[derive(Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct ApiError {
@@ -28,12 +202,20 @@ pub struct ApiError {
 }
 
 // Stream message types for pure streaming API
-#[derive(Serialize, Clone, TS)]
+#[derive(Serialize, Clone, Debug, PartialEq, TS)]
 #[ts(export)]
 pub enum StreamMessage {
     Content(String),
     Error(String),
     Complete,
+    /// The full answer, replacing everything streamed so far (e.g. a raced model's better answer)
+    Replace(String),
+    /// `model` on provider `to` is answering because `from` couldn't, for `reason`
+    ProviderSwitched { from: String, to: String, model: String, reason: String },
+    /// Tokens one request to `model` used, as its provider reported them at the end of the stream
+    Usage { model: String, prompt_tokens: u32, completion_tokens: u32 },
+    /// In an orchestrated conversation, `agent` on `model` starts its turn, saved as `message_id`
+    AgentTurn { agent: String, model: String, message_id: String },
 }
 
 // Event payload types (for UI layer compatibility)
@@ -74,192 +256,7129 @@ pub async fn save_api_config(api_key: String) -> Result<(), String> {
     })
 }
 
-pub async fn has_api_config() -> Result<bool, String> {
-    log::info!("Frontend checking if API configuration exists");
-    log::info!("Current working directory: {:?}", std::env::current_dir());
-    log::info!("USER env var: {:?}", std::env::var("USER"));
-    
+/// Every provider with where its key comes from; one without a key can't be used
+pub async fn list_providers() -> Result<Vec<ProviderStatus>, String> {
     let config_manager = ConfigManager::new().map_err(|e| {
         log::error!("Failed to create config manager: {}", e);
         e.to_string()
     })?;
-    
-    log::info!("Config file path: {:?}", config_manager.get_config_path());
-    log::info!("Config file exists: {}", config_manager.get_config_path().exists());
-    log::info!("Environment variable TOGETHERAI_API_KEY set: {}", std::env::var("TOGETHERAI_API_KEY").is_ok());
-    
-    let has_config = config_manager.has_config();
-    log::info!("Final has_config result: {}", has_config);
-    Ok(has_config)
+    providers::ALL.into_iter()
+        .map(|provider| {
+            let endpoint = provider_endpoint(&config_manager, provider)?;
+            Ok(ProviderStatus {
+                provider,
+                name: provider.name().to_string(),
+                api_base: endpoint.as_ref().map_or(provider.api_base().to_string(), |(api_base, _)| api_base.clone()),
+                key_source: config_manager.get_provider_api_key_source(provider).map_err(|e| {
+                    log::error!("Failed to get {} API key: {}", provider.name(), e);
+                    e.to_string()
+                })?,
+                configured: endpoint.is_some(),
+            })
+        })
+        .collect()
 }
 
+/// Store the API key for `provider`, or with None remove it
+pub async fn set_provider_api_key(provider: Provider, api_key: Option<String>) -> Result<(), String> {
+    log::info!("Frontend requested to save the {} API key", provider.name());
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let api_key = api_key.map(|key| key.trim().to_string()).filter(|key| !key.is_empty());
+    config_manager.save_provider_api_key(provider, api_key).map_err(|e| {
+        log::error!("Failed to save {} API key: {}", provider.name(), e);
+        e.to_string()
+    })
+}
 
-pub async fn log_info(message: String) -> Result<(), String> {
-    log::info!("[Frontend] {}", message);
-    Ok(())
+/// The Ollama server in use, if any
+pub async fn get_ollama_base_url() -> Result<Option<String>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_ollama_base_url().map_err(|e| {
+        log::error!("Failed to get Ollama base URL: {}", e);
+        e.to_string()
+    })
 }
 
+/// Use the Ollama server at `base_url` (usually `http://localhost:11434`), or with None stop
+/// using Ollama
+pub async fn set_ollama_base_url(base_url: Option<String>) -> Result<(), String> {
+    let base_url = base_url.map(|url| url.trim().trim_end_matches('/').to_string()).filter(|url| !url.is_empty());
+    if let Some(url) = &base_url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(tr!("custom-model-url-invalid", url = url.clone()));
+        }
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_ollama_base_url(base_url).map_err(|e| {
+        log::error!("Failed to save Ollama base URL: {}", e);
+        e.to_string()
+    })
+}
 
-pub async fn get_available_models() -> Result<Vec<ModelInfo>, String> {
-    log::info!("Fetching available models from Together.ai API");
-    
+/// How long Ollama keeps a model loaded after a request, or None for Ollama's own default
+pub async fn get_ollama_keep_alive() -> Result<Option<String>, String> {
     let config_manager = ConfigManager::new().map_err(|e| {
         log::error!("Failed to create config manager: {}", e);
         e.to_string()
     })?;
-    let api_key = config_manager.get_api_key().map_err(|e| {
-        log::error!("Failed to get API key: {}", e);
+    config_manager.get_ollama_keep_alive().map_err(|e| {
+        log::error!("Failed to get Ollama keep-alive: {}", e);
+        e.to_string()
+    })
+}
+
+/// Keep Ollama models loaded for `keep_alive` after each request: seconds, a duration such as
+/// `30m`, or `-1` for as long as Ollama runs
+pub async fn set_ollama_keep_alive(keep_alive: Option<String>) -> Result<(), String> {
+    let keep_alive = keep_alive.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+    if let Some(value) = &keep_alive {
+        if !ollama::valid_keep_alive(value) {
+            return Err(tr!("ollama-keep-alive-invalid", value = value.clone()));
+        }
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
         e.to_string()
-    })?.ok_or_else(|| {
-        log::error!("No API key configured");
-        "No API key configured".to_string()
     })?;
+    config_manager.save_ollama_keep_alive(keep_alive).map_err(|e| {
+        log::error!("Failed to save Ollama keep-alive: {}", e);
+        e.to_string()
+    })
+}
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://api.together.xyz/v1/models")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .send()
-        .await
-        .map_err(|e| {
-            log::error!("Failed to fetch models: {}", e);
-            e.to_string()
-        })?;
+/// Load `model` into memory now if a local Ollama server runs it, so its first answer doesn't
+/// wait for the load. Returns whether there was anything to load.
+pub async fn preload_model(model: String) -> Result<bool, String> {
+    if providers::resolve(&model).0 != Provider::Ollama {
+        return Ok(false);
+    }
+    policy::check_provider(Provider::Ollama.name())?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let (api_base, _) = provider_endpoint(&config_manager, Provider::Ollama)?
+        .ok_or_else(|| tr!("no-provider-key", provider = Provider::Ollama.name()))?;
+    let keep_alive = config_manager.get_ollama_keep_alive().map_err(|e| {
+        log::error!("Failed to get Ollama keep-alive: {}", e);
+        e.to_string()
+    })?;
+    log::info!("Loading {} ahead of its first message", model);
+    ollama::preload(reqwest::Client::new(), &api_base, &model, keep_alive.as_deref()).await?;
+    Ok(true)
+}
 
-    let models: serde_json::Value = response.json().await.map_err(|e| {
-        log::error!("Failed to parse models response: {}", e);
+pub async fn get_bedrock_account() -> Result<Option<BedrockAccount>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
         e.to_string()
     })?;
+    config_manager.get_bedrock_account().map_err(|e| {
+        log::error!("Failed to get Bedrock account: {}", e);
+        e.to_string()
+    })
+}
 
-    let model_list = models.as_array().ok_or_else(|| {
-        log::error!("Models response is not an array");
-        "Invalid models response format".to_string()
+/// Reach Bedrock with the access key `account` names, whose secret is set as Bedrock's API key,
+/// or with None with the AWS environment variables
+pub async fn set_bedrock_account(account: Option<BedrockAccount>) -> Result<(), String> {
+    let account = account.map(bedrock::validate).transpose()?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
     })?;
+    config_manager.save_bedrock_account(account).map_err(|e| {
+        log::error!("Failed to save Bedrock account: {}", e);
+        e.to_string()
+    })?;
+    status::refresh();
+    Ok(())
+}
 
-    let mut result = Vec::new();
-    for model in model_list {
-        if let Some(id) = model["id"].as_str() {
-            let organization = model.get("organization")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown");
-            let display_name = model.get("display_name")
-                .and_then(|v| v.as_str())
-                .unwrap_or(id);
-            
-            result.push(ModelInfo {
-                id: id.to_string(),
-                display_name: display_name.to_string(),
-                organization: organization.to_string(),
+/// The credentials Bedrock is reached with, or None until there's an access key and its secret
+fn bedrock_credentials(config_manager: &ConfigManager) -> Result<Option<sigv4::Credentials>, String> {
+    let account = config_manager.get_bedrock_account().map_err(|e| {
+        log::error!("Failed to get Bedrock account: {}", e);
+        e.to_string()
+    })?;
+    let secret_access_key = config_manager.get_provider_api_key(Provider::Bedrock).map_err(|e| {
+        log::error!("Failed to get {} API key: {}", Provider::Bedrock.name(), e);
+        e.to_string()
+    })?;
+    Ok(bedrock::credentials(account, secret_access_key))
+}
+
+pub async fn get_provider_headers(provider: Provider) -> Result<std::collections::BTreeMap<String, String>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_provider_headers(provider).map_err(|e| {
+        log::error!("Failed to get {} headers: {}", provider.name(), e);
+        e.to_string()
+    })
+}
+
+/// Send `headers` with every request to `provider`, e.g. `HTTP-Referer` and `X-Title` for
+/// OpenRouter or a gateway's auth and tracing headers; empty headers send none
+pub async fn set_provider_headers(provider: Provider, headers: std::collections::BTreeMap<String, String>) -> Result<(), String> {
+    let headers = providers::validate_headers(headers)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_provider_headers(provider, headers).map_err(|e| {
+        log::error!("Failed to save {} headers: {}", provider.name(), e);
+        e.to_string()
+    })
+}
+
+/// The API base `provider` is reached at and the key to send it, or None if it isn't set up.
+/// Ollama needs no key but async-openai sends one, so it gets a placeholder. Compatible
+/// endpoints each have their own; the first registered stands for them all. Bedrock's requests
+/// are signed rather than carrying a key, so it gets its runtime API and access key id.
+fn provider_endpoint(config_manager: &ConfigManager, provider: Provider) -> Result<Option<(String, String)>, String> {
+    if provider == Provider::Endpoint {
+        return config_manager.get_compatible_endpoints()
+            .map(|endpoints| endpoints.first().map(|endpoint| (endpoint.base_url.clone(), endpoint.api_key().to_string())))
+            .map_err(|e| {
+                log::error!("Failed to get compatible endpoints: {}", e);
+                e.to_string()
+            });
+    }
+    if provider == Provider::Ollama {
+        return config_manager.get_ollama_base_url()
+            .map(|base_url| base_url.map(|base_url| (ollama::api_base(&base_url), provider.name().to_string())))
+            .map_err(|e| {
+                log::error!("Failed to get Ollama base URL: {}", e);
+                e.to_string()
             });
+    }
+    if provider == Provider::Bedrock {
+        return bedrock_credentials(config_manager)
+            .map(|credentials| credentials.map(|credentials| (bedrock::runtime_base(&credentials.region), credentials.access_key_id)));
+    }
+    let api_key = config_manager.get_provider_api_key(provider).map_err(|e| {
+        log::error!("Failed to get {} API key: {}", provider.name(), e);
+        e.to_string()
+    })?;
+    let gateway = config_manager.get_gateway(provider).map_err(|e| {
+        log::error!("Failed to get {} gateway: {}", provider.name(), e);
+        e.to_string()
+    })?;
+    Ok(match gateway {
+        Some(route) => gateways::api_key(&route, api_key).map(|api_key| (gateways::api_base(&route, provider), api_key)),
+        None => api_key.map(|api_key| (provider.api_base().to_string(), api_key)),
+    })
+}
+
+/// The providers that are set up and allowed by policy
+fn configured_providers(config_manager: &ConfigManager) -> Vec<Provider> {
+    providers::ALL.into_iter()
+        .filter(|provider| policy::check_provider(provider.name()).is_ok())
+        .filter(|provider| matches!(provider_endpoint(config_manager, *provider), Ok(Some(_))))
+        .collect()
+}
+
+
+/// The last health check of every configured provider, so the status bar can show those that are
+/// degraded or down before a message is sent to one
+pub async fn get_provider_health() -> Result<Vec<ProviderHealth>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    Ok(health::of(&configured_providers(&config_manager)))
+}
+
+
+/// Check every configured provider now rather than at the next scheduled check
+pub async fn check_provider_health() -> Result<Vec<ProviderHealth>, String> {
+    check_providers().await?;
+    get_provider_health().await
+}
+
+
+/// Check the configured providers every few minutes; spawn once at startup
+pub async fn run_health_schedule() {
+    loop {
+        if let Err(e) = check_providers().await {
+            log::warn!("Provider health checks failed: {}", e);
         }
+        tokio::time::sleep(low_power::poll_interval(health::CHECK_INTERVAL).await).await;
     }
+}
 
-    log::info!("Successfully fetched {} models", result.len());
-    Ok(result)
+
+async fn check_providers() -> Result<(), String> {
+    if demo::active() {
+        return Ok(());
+    }
+    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
+    let configured = configured_providers(&config_manager);
+    health::retain(&configured);
+    let checks = configured.iter().map(|&provider| {
+        let request = health_check_request(&config_manager, provider);
+        async move {
+            let request = match request {
+                Ok(Some(request)) => request,
+                Ok(None) => return None,
+                Err(e) => return Some((provider, Err(e))),
+            };
+            let started = std::time::Instant::now();
+            let outcome = match tokio::time::timeout(health::CHECK_TIMEOUT, request.send()).await {
+                Ok(Ok(response)) => Ok((response.status().as_u16(), started.elapsed())),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("No answer within {} s", health::CHECK_TIMEOUT.as_secs())),
+            };
+            Some((provider, outcome))
+        }
+    });
+    for (provider, outcome) in futures::future::join_all(checks).await.into_iter().flatten() {
+        let (health, changed) = health::record(provider, &outcome);
+        if changed {
+            log::info!("{} is now {:?}: {}", provider.name(), health.state, health.error.as_deref().unwrap_or("ok"));
+            events::publish(AppEvent::ProviderHealthChanged { health });
+        }
+    }
+    Ok(())
 }
 
 
-pub async fn get_current_model() -> Result<String, String> {
-    log::info!("Getting current preferred model");
+/// A cheap request to `provider` that needs its key, usually for its model list; None if
+/// there's nothing to ask, like Azure with no profiles
+fn health_check_request(config_manager: &ConfigManager, provider: Provider) -> Result<Option<reqwest::RequestBuilder>, String> {
+    let client = reqwest::Client::new();
+    let request = match provider {
+        Provider::Azure => {
+            let profiles = config_manager.get_azure_profiles().map_err(|e| e.to_string())?;
+            let Some(profile) = profiles.first() else {
+                return Ok(None);
+            };
+            let (_, api_key) = provider_endpoint(config_manager, provider)?
+                .ok_or_else(|| tr!("no-provider-key", provider = provider.name()))?;
+            client
+                .get(format!("{}/openai/models?api-version={}", profile.endpoint, profile.api_version))
+                .header("api-key", api_key)
+        }
+        Provider::Bedrock => match bedrock_credentials(config_manager)? {
+            Some(credentials) => bedrock::health_check(&client, &credentials)?,
+            None => return Ok(None),
+        },
+        Provider::Endpoint => {
+            let endpoints = config_manager.get_compatible_endpoints().map_err(|e| e.to_string())?;
+            let Some(endpoint) = endpoints.first() else {
+                return Ok(None);
+            };
+            client
+                .get(endpoint.models_url())
+                .header("Authorization", format!("Bearer {}", endpoint.api_key()))
+                .headers(providers::header_map(&endpoint.headers))
+        }
+        _ => {
+            let Some((api_base, api_key)) = provider_endpoint(config_manager, provider)? else {
+                return Ok(None);
+            };
+            let gateway = config_manager.get_gateway(provider).map_err(|e| e.to_string())?
+                .filter(|route| route.preset.lists_own_models());
+            match provider {
+                Provider::Anthropic if gateway.is_none() => client
+                    .get(format!("{}/models?limit=1", api_base))
+                    .header("x-api-key", api_key)
+                    .header("anthropic-version", anthropic::API_VERSION),
+                Provider::Ollama if gateway.is_none() => client.get(ollama::tags_url(&api_base)),
+                _ => client
+                    .get(format!("{}/models", api_base))
+                    .header("Authorization", format!("Bearer {}", api_key)),
+            }
+        }
+    };
+    Ok(Some(request.headers(provider_headers(provider))))
+}
+
+
+/// Where the administrator's defaults file is looked for and which settings it provides
+pub async fn get_system_defaults() -> Result<SystemDefaultsInfo, String> {
+    Ok(config::system_defaults_info())
+}
+
+
+/// The administrator's policy and this month's spend against its cap, so settings it locks can
+/// be shown as such
+pub async fn get_policy() -> Result<PolicyStatus, String> {
+    let path = policy::path();
     let config_manager = ConfigManager::new().map_err(|e| {
         log::error!("Failed to create config manager: {}", e);
         e.to_string()
     })?;
-    config_manager.get_preferred_model().map_err(|e| {
-        log::error!("Failed to get preferred model: {}", e);
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let spent_this_month_usd = store.spent_since(&policy::month_start()).map_err(|e| {
+        log::error!("Failed to total this month's spend: {}", e);
         e.to_string()
+    })?;
+    Ok(PolicyStatus {
+        path: path.exists().then(|| path.to_string_lossy().to_string()),
+        policy: policy::current().clone(),
+        spent_this_month_usd,
     })
 }
 
 
-pub async fn set_preferred_model(model: String) -> Result<(), String> {
-    log::info!("Setting preferred model to: {}", model);
+pub async fn get_portable_status() -> Result<PortableStatus, String> {
+    let root = portable::root();
     let config_manager = ConfigManager::new().map_err(|e| {
         log::error!("Failed to create config manager: {}", e);
         e.to_string()
     })?;
-    config_manager.save_preferred_model(model).map_err(|e| {
-        log::error!("Failed to save preferred model: {}", e);
-        e.to_string()
+    Ok(PortableStatus {
+        enabled: root.is_some(),
+        root: root.map(|root| root.to_string_lossy().to_string()),
+        unlocked: portable::unlocked(),
+        initialized: config_manager.get_config_path().exists(),
     })
 }
 
 
-pub async fn create_streaming_chat(
-    message: String,
-) -> Result<Pin<Box<dyn Stream<Item = StreamMessage> + Send>>, String> {
-    log::info!("Creating streaming chat for message");
-    
-    // Get API key from config
+/// Enter the passphrase for the portable config. Nothing that reads config works in portable
+/// mode until this succeeds; on first run it sets the passphrase.
+pub async fn unlock_portable(passphrase: String) -> Result<(), String> {
+    if !portable::active() {
+        return Err(tr!("portable-inactive"));
+    }
+    if passphrase.trim().is_empty() {
+        return Err(tr!("portable-empty-passphrase"));
+    }
     let config_manager = ConfigManager::new().map_err(|e| {
-        log::error!("Failed to create config manager for streaming: {}", e);
+        log::error!("Failed to create config manager: {}", e);
         e.to_string()
     })?;
-    let api_key = config_manager.get_api_key().map_err(|e| {
-        log::error!("Failed to get API key for streaming: {}", e);
+    config_manager.unlock_portable(passphrase).map_err(|e| e.to_string())?;
+    log::info!("Portable config unlocked");
+    status::refresh();
+    Ok(())
+}
+
+
+pub async fn change_portable_passphrase(passphrase: String) -> Result<(), String> {
+    if !portable::active() {
+        return Err(tr!("portable-inactive"));
+    }
+    if passphrase.trim().is_empty() {
+        return Err(tr!("portable-empty-passphrase"));
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
         e.to_string()
-    })?.ok_or_else(|| {
-        log::error!("No API key configured for streaming");
-        "No API key configured".to_string()
     })?;
-    let config = OpenAIConfig::new()
-        .with_api_key(api_key)
-        .with_api_base("https://api.together.xyz/v1");
+    config_manager.change_portable_passphrase(passphrase).map_err(|e| {
+        log::error!("Failed to change portable passphrase: {}", e);
+        e.to_string()
+    })
+}
 
-    let client = Client::with_config(config);
 
-    // Get preferred model
-    let model = config_manager.get_preferred_model().map_err(|e| {
-        log::error!("Failed to get preferred model for streaming: {}", e);
+/// Where the API key in use comes from, or None if there isn't one. Keys from the command line,
+/// environment or `.env` take precedence over the saved one, so the UI can say why editing it
+/// has no effect.
+pub async fn get_api_key_source() -> Result<Option<ValueSource>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
         e.to_string()
     })?;
-    log::info!("Using model for streaming: {}", model);
+    config_manager.get_api_key_source().map_err(|e| {
+        log::error!("Failed to get API key source: {}", e);
+        e.to_string()
+    })
+}
 
-    let request = async_openai::types::CreateChatCompletionRequestArgs::default()
-        .model(model)
-        .messages(vec![
-            async_openai::types::ChatCompletionRequestMessage::User(
-                async_openai::types::ChatCompletionRequestUserMessageArgs::default()
-                    .content(message)
-                    .build()
-                    .unwrap(),
-            ),
-        ])
-        .stream(true)
-        .build()
-        .map_err(|e| e.to_string())?;
+/// Re-encrypt the config, API key included, under a freshly generated key. History isn't
+/// encrypted at rest, and the sync key is shared with other devices, so neither changes.
+pub async fn rotate_encryption_key() -> Result<(), String> {
+    log::info!("Frontend requested config key rotation");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.rotate_encryption_key().map_err(|e| {
+        log::error!("Failed to rotate config key: {}", e);
+        e.to_string()
+    })
+}
 
-    let openai_stream = client
-        .chat()
-        .create_stream(request)
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn has_api_config() -> Result<bool, String> {
+    log::info!("Frontend checking if API configuration exists");
+    log::info!("Current working directory: {:?}", std::env::current_dir());
+    log::info!("User: {:?}", platform::user_name());
+    
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    
+    log::info!("Config file path: {:?}", config_manager.get_config_path());
+    log::info!("Config file exists: {}", config_manager.get_config_path().exists());
+    log::info!("API key from outside the config: {:?}", environment::var(environment::API_KEY_VAR).map(|(_, source)| source));
+    
+    let has_config = config_manager.has_config();
+    log::info!("Final has_config result: {}", has_config);
+    Ok(has_config)
+}
 
-    // Transform the OpenAI stream into our StreamMessage enum
-    let message_stream = openai_stream.map(|result| {
-        match result {
-            Ok(response) => {
-                if let Some(choice) = response.choices.first() {
-                    if let Some(content) = &choice.delta.content {
-                        StreamMessage::Content(content.clone())
-                    } else {
-                        // Empty content chunk, skip
-                        StreamMessage::Content(String::new())
-                    }
-                } else {
-                    StreamMessage::Content(String::new())
-                }
-            }
-            Err(e) => StreamMessage::Error(e.to_string())
-        }
-    }).chain(futures::stream::once(async { StreamMessage::Complete }));
 
-    Ok(Box::pin(message_stream))
+pub async fn log_info(message: String) -> Result<(), String> {
+    log::info!("[Frontend] {}", message);
+    Ok(())
 }
 
 
-pub async fn get_build_info() -> Result<BuildInfo, String> {
-    let build_info = BuildInfo::load().map_err(|e| e.to_string())?;
-    Ok(build_info)
-}
\ No newline at end of file
+pub async fn get_available_models() -> Result<Vec<ModelInfo>, String> {
+    log::info!("Fetching available models");
+    
+    if demo::active() {
+        return Ok(demo::models());
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let custom = config_manager.get_custom_models().map_err(|e| {
+        log::error!("Failed to get custom models: {}", e);
+        e.to_string()
+    })?;
+
+    // Every provider that's set up; one that fails is left out unless all do
+    let mut result = Vec::new();
+    let mut listed = false;
+    let mut first_error = None;
+    for provider in providers::ALL {
+        let (api_base, api_key) = match provider_endpoint(&config_manager, provider) {
+            Ok(Some(endpoint)) => endpoint,
+            Ok(None) => continue,
+            Err(e) => {
+                first_error.get_or_insert(e);
+                continue;
+            }
+        };
+        let models = match policy::check_provider(provider.name()) {
+            // Azure has no list of the deployments on a resource that a key can read
+            Ok(()) if provider == Provider::Azure => config_manager.get_azure_profiles()
+                .map(|profiles| azure::models(&profiles))
+                .map_err(|e| {
+                    log::error!("Failed to get Azure OpenAI profiles: {}", e);
+                    e.to_string()
+                }),
+            Ok(()) if provider == Provider::Bedrock => bedrock_models(&config_manager).await,
+            Ok(()) if provider == Provider::Endpoint => match config_manager.get_compatible_endpoints() {
+                Ok(endpoints) => compatible_endpoints_models(&endpoints).await,
+                Err(e) => {
+                    log::error!("Failed to get compatible endpoints: {}", e);
+                    Err(e.to_string())
+                }
+            },
+            Ok(()) => match config_manager.get_gateway(provider) {
+                Ok(gateway) => provider_models(provider, &api_base, &api_key, gateway.as_ref()).await,
+                Err(e) => {
+                    log::error!("Failed to get {} gateway: {}", provider.name(), e);
+                    Err(e.to_string())
+                }
+            },
+            Err(e) => Err(e),
+        };
+        match models {
+            Ok(models) => {
+                listed = true;
+                result.extend(models);
+            }
+            Err(e) => {
+                log::warn!("Leaving {} models out: {}", provider.name(), e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if !listed {
+        return Err(first_error.unwrap_or_else(|| {
+            log::error!("No API key configured");
+            tr!("no-api-key")
+        }));
+    }
+
+    for model in &custom {
+        if let Some(length) = model.context_length {
+            context::remember_context_length(&model.id, length as usize);
+        }
+    }
+    Ok(custom_models::merge(result, &custom))
+}
+
+
+/// `provider`'s model list, listed under the ids the model picker uses
+async fn provider_models(provider: Provider, api_base: &str, api_key: &str, gateway: Option<&gateways::GatewayRoute>) -> Result<Vec<ModelInfo>, String> {
+    // Keyed by a digest of the API base and key so switching keys (and so accounts), servers
+    // or gateways refetches
+    let identity = format!("{} {}", api_base, api_key);
+    let cache_key = format!("{}:{:x}", provider.name(), sha2::Sha256::digest(identity.as_bytes()));
+    let gateway = gateway.filter(|route| route.preset.lists_own_models());
+    if let Some(models) = MODELS.get(&cache_key) {
+        log::info!("Using {} cached {} models", models.len(), provider.name());
+        return Ok(models);
+    }
+
+    let client = reqwest::Client::new();
+    let request = match provider {
+        // Gateways with their own list serve it OpenAI's way
+        _ if gateway.is_some() => client
+            .get(format!("{}/models", api_base))
+            .header("Authorization", format!("Bearer {}", api_key)),
+        // Anthropic pages its list, 20 models at a time unless asked for more
+        Provider::Anthropic => client
+            .get(format!("{}/models?limit=1000", api_base))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", anthropic::API_VERSION),
+        // Ollama's own list has the model families its OpenAI-compatible one leaves out
+        Provider::Ollama => client.get(ollama::tags_url(api_base)),
+        _ => client
+            .get(format!("{}/models", api_base))
+            .header("Authorization", format!("Bearer {}", api_key)),
+    };
+    let response = request
+        .headers(provider_headers(provider))
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .map_err(|e| {
+            log::error!("Failed to fetch models: {}", e);
+            e.to_string()
+        })?;
+
+    let models: serde_json::Value = response.json().await.map_err(|e| {
+        log::error!("Failed to parse models response: {}", e);
+        e.to_string()
+    })?;
+
+    let result = match gateway {
+        // Named as the gateway names them, whichever provider it forwards to
+        Some(route) => gateways::models(route, provider, &models)?,
+        None => match provider {
+            Provider::Together => together_models(&models)?,
+            Provider::OpenAi => providers::openai_models(&models)?,
+            Provider::OpenRouter => providers::openrouter_models(&models)?,
+            Provider::Groq => providers::groq_models(&models)?,
+            Provider::Anthropic => anthropic::models(&models)?,
+            Provider::Ollama => ollama::models(&models)?,
+            // Listed from its profiles instead
+            Provider::Azure => Vec::new(),
+            // Listed endpoint by endpoint instead
+            Provider::Endpoint => Vec::new(),
+            // Listed with signed requests instead
+            Provider::Bedrock => Vec::new(),
+        },
+    };
+    log::info!("Successfully fetched {} {} models", result.len(), provider.name());
+    MODELS.insert(&cache_key, result.clone());
+    Ok(result)
+}
+
+
+/// The models Bedrock serves in the account's region, cached like other providers'
+async fn bedrock_models(config_manager: &ConfigManager) -> Result<Vec<ModelInfo>, String> {
+    let Some(credentials) = bedrock_credentials(config_manager)? else {
+        return Ok(Vec::new());
+    };
+    let identity = format!("{} {} {}", credentials.region, credentials.access_key_id, credentials.secret_access_key);
+    let cache_key = format!("{}:{:x}", Provider::Bedrock.name(), sha2::Sha256::digest(identity.as_bytes()));
+    if let Some(models) = MODELS.get(&cache_key) {
+        log::info!("Using {} cached {} models", models.len(), Provider::Bedrock.name());
+        return Ok(models);
+    }
+    let result = bedrock::list_models(&reqwest::Client::new(), &credentials).await.map_err(|e| {
+        log::error!("Failed to fetch Bedrock models: {}", e);
+        e
+    })?;
+    log::info!("Successfully fetched {} {} models", result.len(), Provider::Bedrock.name());
+    MODELS.insert(&cache_key, result.clone());
+    Ok(result)
+}
+
+
+/// The models of every registered compatible endpoint; one that can't be reached is left out
+/// unless all are
+async fn compatible_endpoints_models(endpoints: &[CompatibleEndpoint]) -> Result<Vec<ModelInfo>, String> {
+    let mut result = Vec::new();
+    let mut first_error = None;
+    for endpoint in endpoints {
+        match endpoint_models(endpoint, true).await {
+            Ok(models) => result.extend(models),
+            Err(e) => {
+                log::warn!("Leaving endpoint {} models out: {}", endpoint.name, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) if result.is_empty() => Err(e),
+        _ => Ok(result),
+    }
+}
+
+
+/// The models a compatible endpoint's `GET /models` lists, from the cache if `cached` and it
+/// has them
+async fn endpoint_models(endpoint: &CompatibleEndpoint, cached: bool) -> Result<Vec<ModelInfo>, String> {
+    let identity = format!("{} {}", endpoint.base_url, endpoint.api_key());
+    let cache_key = format!("{}/{}:{:x}", Provider::Endpoint.name(), endpoint.name, sha2::Sha256::digest(identity.as_bytes()));
+    if let Some(models) = MODELS.get(&cache_key).filter(|_| cached) {
+        log::info!("Using {} cached models of endpoint {}", models.len(), endpoint.name);
+        return Ok(models);
+    }
+
+    let response = reqwest::Client::new()
+        .get(endpoint.models_url())
+        .header("Authorization", format!("Bearer {}", endpoint.api_key()))
+        .headers(providers::header_map(&endpoint.headers))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| {
+            log::error!("Failed to fetch models of endpoint {}: {}", endpoint.name, e);
+            e.to_string()
+        })?;
+    let models: serde_json::Value = response.json().await.map_err(|e| {
+        log::error!("Failed to parse models response of endpoint {}: {}", endpoint.name, e);
+        e.to_string()
+    })?;
+    let result = endpoints::models(endpoint, &models)?;
+    log::info!("Successfully fetched {} models of endpoint {}", result.len(), endpoint.name);
+    MODELS.insert(&cache_key, result.clone());
+    Ok(result)
+}
+
+
+/// What each set-up provider's models can do (streaming, images, tools, JSON mode, embeddings),
+/// so controls a model can't use can be turned off before a request fails
+pub async fn get_provider_capabilities() -> Result<Vec<ProviderCapabilities>, String> {
+    let models = get_available_models().await?;
+    Ok(capabilities::by_provider(&models))
+}
+
+
+/// The models in Together.ai's model list, remembering their context lengths, prices and types
+fn together_models(models: &serde_json::Value) -> Result<Vec<ModelInfo>, String> {
+    let model_list = models.as_array().ok_or_else(|| {
+        log::error!("Models response is not an array");
+        "Invalid models response format".to_string()
+    })?;
+
+    let mut result = Vec::new();
+    for model in model_list {
+        if let Some(id) = model["id"].as_str() {
+            let organization = model.get("organization")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown");
+            let display_name = model.get("display_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or(id);
+            let id = providers::qualified_id(Provider::Together, id);
+            let context_length = model.get("context_length")
+                .and_then(|v| v.as_u64())
+                .filter(|length| *length > 0)
+                .map(|length| length as u32);
+            if let Some(length) = context_length {
+                context::remember_context_length(&id, length as usize);
+            }
+            if let Some(model_type) = model.get("type").and_then(|v| v.as_str()) {
+                completions::remember_model_type(&id, model_type);
+                capabilities::remember_model_type(&id, model_type);
+            }
+            // Together reports USD per million tokens
+            let price = |key: &str| model.get("pricing").and_then(|p| p.get(key)).and_then(|v| v.as_f64());
+            let pricing = match (price("input"), price("output")) {
+                (Some(input), Some(output)) => Some(cost::ModelPricing { input, output }),
+                _ => None,
+            };
+            if let Some(pricing) = pricing {
+                cost::remember_pricing(&id, pricing);
+            }
+            
+            result.push(ModelInfo {
+                id,
+                display_name: display_name.to_string(),
+                organization: organization.to_string(),
+                context_length,
+                pricing,
+            });
+        }
+    }
+    Ok(result)
+}
+
+
+pub async fn list_custom_models() -> Result<Vec<CustomModel>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_custom_models().map_err(|e| {
+        log::error!("Failed to get custom models: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Load custom models, apply `change` and persist them
+fn update_custom_models<T>(change: impl FnOnce(&mut Vec<CustomModel>) -> Result<T, String>) -> Result<T, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut models = config_manager.get_custom_models().map_err(|e| {
+        log::error!("Failed to get custom models: {}", e);
+        e.to_string()
+    })?;
+    let result = change(&mut models)?;
+    config_manager.save_custom_models(models).map_err(|e| {
+        log::error!("Failed to save custom models: {}", e);
+        e.to_string()
+    })?;
+    Ok(result)
+}
+
+
+pub async fn list_azure_profiles() -> Result<Vec<AzureProfile>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_azure_profiles().map_err(|e| {
+        log::error!("Failed to get Azure OpenAI profiles: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// The gateways providers can be routed through, and what each needs to be set up
+pub async fn list_gateway_presets() -> Result<Vec<GatewayPresetInfo>, String> {
+    Ok(gateways::PRESETS.into_iter().map(GatewayPreset::info).collect())
+}
+
+
+pub async fn get_gateway(provider: Provider) -> Result<Option<GatewayRoute>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_gateway(provider).map_err(|e| {
+        log::error!("Failed to get {} gateway: {}", provider.name(), e);
+        e.to_string()
+    })
+}
+
+
+/// Route `provider`'s requests through a LiteLLM, Helicone or Portkey gateway, or with None
+/// send them to the provider directly again
+pub async fn set_gateway(provider: Provider, route: Option<GatewayRoute>) -> Result<(), String> {
+    let route = route.map(|route| gateways::validate(provider, route)).transpose()?;
+    log::info!("Routing {} through {:?}", provider.name(), route.as_ref().map(|route| route.preset.name()));
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_gateway(provider, route).map_err(|e| {
+        log::error!("Failed to save {} gateway: {}", provider.name(), e);
+        e.to_string()
+    })?;
+    status::refresh();
+    Ok(())
+}
+
+
+/// The models `provider`'s requests fall back to, in order
+pub async fn get_failover_chain(provider: Provider) -> Result<Vec<String>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_failover_chains()
+        .map(|chains| chains.get(provider.name()).cloned().unwrap_or_default())
+        .map_err(|e| {
+            log::error!("Failed to get failover chains: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Fall back from `provider` to the models in `chain`, in order, when it's rate limited, failing
+/// or slow to answer; an empty chain turns failover off for it
+pub async fn set_failover_chain(provider: Provider, chain: Vec<String>) -> Result<(), String> {
+    let chain = failover::validate(provider, chain)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_failover_chain(provider, chain).map_err(|e| {
+        log::error!("Failed to save {} failover chain: {}", provider.name(), e);
+        e.to_string()
+    })
+}
+
+
+pub async fn list_compatible_endpoints() -> Result<Vec<CompatibleEndpoint>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_compatible_endpoints().map_err(|e| {
+        log::error!("Failed to get compatible endpoints: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Register an OpenAI-compatible server under its name, replacing one with the same name. It's
+/// only saved if its `/models` answers; the models it lists are returned.
+pub async fn save_compatible_endpoint(endpoint: CompatibleEndpoint) -> Result<Vec<ModelInfo>, String> {
+    let endpoint = endpoints::validate(endpoint)?;
+    policy::check_provider(Provider::Endpoint.name())?;
+    log::info!("Saving compatible endpoint {} at {}", endpoint.name, endpoint.base_url);
+    let models = endpoint_models(&endpoint, false).await.map_err(|e| {
+        tr!("endpoint-unreachable", endpoint = endpoint.name.clone(), error = e)
+    })?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut endpoints = config_manager.get_compatible_endpoints().map_err(|e| {
+        log::error!("Failed to get compatible endpoints: {}", e);
+        e.to_string()
+    })?;
+    endpoints.retain(|existing| existing.name != endpoint.name);
+    endpoints.push(endpoint);
+    config_manager.save_compatible_endpoints(endpoints).map_err(|e| {
+        log::error!("Failed to save compatible endpoints: {}", e);
+        e.to_string()
+    })?;
+    status::refresh();
+    Ok(models)
+}
+
+
+/// Remove a compatible endpoint; false if there was none by that name
+pub async fn remove_compatible_endpoint(name: String) -> Result<bool, String> {
+    log::info!("Removing compatible endpoint {}", name);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut endpoints = config_manager.get_compatible_endpoints().map_err(|e| {
+        log::error!("Failed to get compatible endpoints: {}", e);
+        e.to_string()
+    })?;
+    let before = endpoints.len();
+    endpoints.retain(|endpoint| endpoint.name != name);
+    let removed = endpoints.len() < before;
+    config_manager.save_compatible_endpoints(endpoints).map_err(|e| {
+        log::error!("Failed to save compatible endpoints: {}", e);
+        e.to_string()
+    })?;
+    status::refresh();
+    Ok(removed)
+}
+
+
+/// Add an Azure OpenAI deployment to the model picker, replacing a profile with the same name
+pub async fn save_azure_profile(profile: AzureProfile) -> Result<AzureProfile, String> {
+    let profile = azure::validate(profile)?;
+    log::info!("Saving Azure OpenAI profile {} for deployment {}", profile.name, profile.deployment);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut profiles = config_manager.get_azure_profiles().map_err(|e| {
+        log::error!("Failed to get Azure OpenAI profiles: {}", e);
+        e.to_string()
+    })?;
+    profiles.retain(|existing| existing.name != profile.name);
+    profiles.push(profile.clone());
+    config_manager.save_azure_profiles(profiles).map_err(|e| {
+        log::error!("Failed to save Azure OpenAI profiles: {}", e);
+        e.to_string()
+    })?;
+    Ok(profile)
+}
+
+
+/// Remove an Azure OpenAI profile; false if there was none by that name
+pub async fn remove_azure_profile(name: String) -> Result<bool, String> {
+    log::info!("Removing Azure OpenAI profile {}", name);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut profiles = config_manager.get_azure_profiles().map_err(|e| {
+        log::error!("Failed to get Azure OpenAI profiles: {}", e);
+        e.to_string()
+    })?;
+    let before = profiles.len();
+    profiles.retain(|profile| profile.name != name);
+    let removed = profiles.len() < before;
+    config_manager.save_azure_profiles(profiles).map_err(|e| {
+        log::error!("Failed to save Azure OpenAI profiles: {}", e);
+        e.to_string()
+    })?;
+    Ok(removed)
+}
+
+
+/// Add a fine-tuned model or dedicated endpoint to the model picker, replacing one with the same id
+pub async fn register_custom_model(model: CustomModel) -> Result<CustomModel, String> {
+    let model = custom_models::validate(model)?;
+    log::info!("Registering custom model {} ({:?})", model.id, model.kind);
+    update_custom_models(|models| {
+        models.retain(|existing| existing.id != model.id);
+        models.push(model.clone());
+        Ok(model)
+    })
+}
+
+
+/// Remove a custom model; returns whether it was registered
+pub async fn remove_custom_model(id: String) -> Result<bool, String> {
+    log::info!("Removing custom model {}", id);
+    update_custom_models(|models| {
+        let before = models.len();
+        models.retain(|model| model.id != id);
+        Ok(models.len() < before)
+    })
+}
+
+
+/// Send a one-token request to a custom model and keep the outcome with it. A failed check is
+/// reported in the returned health rather than as an error.
+pub async fn check_custom_model(id: String) -> Result<ModelHealth, String> {
+    log::info!("Checking custom model {}", id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let custom = config_manager.get_custom_models().map_err(|e| e.to_string())?;
+    if !custom.iter().any(|model| model.id == id) {
+        return Err(format!("Custom model {} not found", id));
+    }
+    let client = provider_client(&config_manager, &id)?;
+
+    // Together reads max_tokens; max_completion_tokens is OpenAI-only
+    #[allow(deprecated)]
+    let request = async_openai::types::ChatCompletionRequestUserMessageArgs::default()
+        .content("ping")
+        .build()
+        .and_then(|message| async_openai::types::CreateChatCompletionRequestArgs::default()
+            .model(id.as_str())
+            .messages(vec![message.into()])
+            .max_tokens(1u32)
+            .build())
+        .map_err(|e| e.to_string())?;
+    let (mut span, client) = provider_span(&client, &id);
+    span.set("switchboard.health_check", true);
+    let started = std::time::Instant::now();
+    let result = client.chat().create(request).await;
+    let latency_ms = started.elapsed().as_millis() as u32;
+    if let Err(e) = &result {
+        span.fail(e.to_string());
+        log::warn!("Health check of {} failed: {}", id, e);
+    }
+    let health = ModelHealth {
+        healthy: result.is_ok(),
+        latency_ms,
+        error: result.err().map(|e| e.to_string()),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    };
+    update_custom_models(|models| {
+        if let Some(model) = models.iter_mut().find(|model| model.id == id) {
+            model.last_health = Some(health.clone());
+        }
+        Ok(())
+    })?;
+    Ok(health)
+}
+
+
+/// Models from every provider that can be used now. A provider that can't be reached is left
+/// out, unless none can, which is an error.
+pub(crate) async fn model_catalogue() -> Result<Vec<openai_api::CatalogueEntry>, String> {
+    let providers = if demo::active() {
+        vec![(demo::PROVIDER, Ok(demo::models()))]
+    } else {
+        match get_available_models().await {
+            Ok(models) => {
+                let mut by_provider: Vec<(&str, Result<Vec<ModelInfo>, String>)> = Vec::new();
+                for provider in providers::ALL {
+                    let models: Vec<ModelInfo> = models.iter()
+                        .filter(|model| providers::resolve(&model.id).0 == provider)
+                        .cloned()
+                        .collect();
+                    if !models.is_empty() {
+                        by_provider.push((provider.name(), Ok(models)));
+                    }
+                }
+                by_provider
+            }
+            Err(e) => vec![(Provider::Together.name(), Err(e))],
+        }
+    };
+    let mut catalogue = Vec::new();
+    let mut first_error = None;
+    for (provider, models) in providers {
+        match models {
+            Ok(models) => catalogue.extend(models.into_iter().map(|model| openai_api::CatalogueEntry {
+                provider: provider.to_string(),
+                model,
+            })),
+            Err(e) => {
+                log::warn!("Leaving {} out of the model catalogue: {}", provider, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) if catalogue.is_empty() => Err(e),
+        _ => Ok(catalogue),
+    }
+}
+
+
+/// Drop cached data in `scope` so the next call refetches it; returns how many entries were dropped
+pub async fn invalidate_caches(scope: CacheScope) -> Result<u32, String> {
+    Ok(cache::invalidate(scope))
+}
+
+
+pub async fn get_current_model() -> Result<String, String> {
+    log::info!("Getting current preferred model");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_preferred_model().map_err(|e| {
+        log::error!("Failed to get preferred model: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn set_preferred_model(model: String) -> Result<(), String> {
+    log::info!("Setting preferred model to: {}", model);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_preferred_model(model.clone()).map_err(|e| {
+        log::error!("Failed to save preferred model: {}", e);
+        e.to_string()
+    })?;
+    status::refresh();
+    if providers::resolve(&model).0 == Provider::Ollama {
+        tokio::spawn(async move {
+            if let Err(e) = preload_model(model).await {
+                log::warn!("Failed to load the preferred model ahead of use: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+
+pub async fn get_generation_settings() -> Result<GenerationSettings, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_generation_settings().map_err(|e| {
+        log::error!("Failed to get generation settings: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn set_generation_settings(settings: GenerationSettings) -> Result<(), String> {
+    log::info!("Setting generation mode to {:?}", settings.mode);
+    let modes = std::iter::once(&settings.mode).chain(settings.persona_modes.values());
+    if modes.clone().any(|mode| *mode == GenerationMode::BestOfN)
+        && !(2..=generation::MAX_SAMPLE_COUNT).contains(&settings.sample_count)
+    {
+        return Err(format!("Best-of-N mode needs between 2 and {} samples", generation::MAX_SAMPLE_COUNT));
+    }
+    if settings.fast_model.is_none() {
+        for mode in modes {
+            match mode {
+                GenerationMode::Race => return Err("Race mode needs a fast model".to_string()),
+                GenerationMode::DraftRefine => return Err("Draft-and-refine mode needs a fast model to draft with".to_string()),
+                GenerationMode::Standard | GenerationMode::BestOfN => {}
+            }
+        }
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_generation_settings(settings).map_err(|e| {
+        log::error!("Failed to save generation settings: {}", e);
+        e.to_string()
+    })?;
+    status::refresh();
+    Ok(())
+}
+
+
+/// Send `message` and stream the answer; `metadata` tags are recorded with the answer's details.
+/// A model mentioned with `@` answers just this message and mentioned tools are asked for, as
+/// `parse_mentions` shows; shortcuts are expanded after, as `expand_message` shows.
+pub async fn create_streaming_chat(
+    message: String,
+    conversation_id: Option<String>,
+    metadata: std::collections::BTreeMap<String, String>,
+) -> Result<Pin<Box<dyn Stream<Item = StreamMessage> + Send>>, String> {
+    let metadata = history::validate_metadata(metadata)?;
+    // Before expanding, so a mention inside an inlined file isn't taken for one
+    let mentions = parse_mentions(message).await?;
+    let shaping = RequestShaping { metadata, model: mentions.model, tools: mentions.tools, ..RequestShaping::default() };
+    let message = expand_message(mentions.message).await?;
+    create_shaped_chat(message, conversation_id, &shaping).await
+}
+
+
+/// The models and loaded plugin tools a message can mention; no models when they can't be listed
+async fn mention_targets() -> (Vec<ModelInfo>, Vec<PluginTool>) {
+    startup::ensure(startup::Subsystem::Plugins).await;
+    let models = get_available_models().await.unwrap_or_else(|e| {
+        log::warn!("Failed to list models for mentions: {}", e);
+        Vec::new()
+    });
+    (models, mentions::loaded_tools(&plugins::loaded_plugins().unwrap_or_default()))
+}
+
+
+/// The model `message` is routed to and the tools it asks for with `@` mentions, and the
+/// message as it will be sent without them
+pub async fn parse_mentions(message: String) -> Result<MessageMentions, String> {
+    if !mentions::has_candidates(&message) {
+        return Ok(MessageMentions { message, ..MessageMentions::default() });
+    }
+    let (models, tools) = mention_targets().await;
+    Ok(mentions::parse(&message, &models, &tools))
+}
+
+
+/// Models and tools to offer while `@prefix` is being typed
+pub async fn suggest_mentions(prefix: String) -> Result<Vec<MentionSuggestion>, String> {
+    let (models, tools) = mention_targets().await;
+    Ok(mentions::suggest(&prefix, &models, &tools, mentions::MAX_SUGGESTIONS))
+}
+
+
+/// `message` as it will be sent: `;name` replaced by the saved expansion, `@~/path` by the
+/// file's content and `#` with a conversation id by a summary of that conversation
+pub async fn expand_message(message: String) -> Result<String, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let expansions = config_manager.get_text_expansions().map_err(|e| {
+        log::error!("Failed to get text expansions: {}", e);
+        e.to_string()
+    })?;
+    if preprocess::references(&message).is_empty() {
+        return Ok(message);
+    }
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    preprocess::expand(&message, &expansions, |prefix| {
+        // A prefix shared by two conversations is left as typed, like one matching none
+        let ids = store.conversations_starting_with(prefix, 2).map_err(|e| e.to_string())?;
+        let [conversation_id] = ids.as_slice() else { return Ok(None) };
+        if store.is_locked(conversation_id).map_err(|e| e.to_string())? && !conversation_lock::is_open(conversation_id) {
+            return Err(tr!("conversation-locked"));
+        }
+        let messages = store.list(conversation_id).map_err(|e| e.to_string())?;
+        Ok(Some(preprocess::conversation_summary(conversation_id, messages)))
+    })
+}
+
+
+pub async fn list_text_expansions() -> Result<std::collections::BTreeMap<String, String>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_text_expansions().map_err(|e| {
+        log::error!("Failed to get text expansions: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Save `text` to be typed in place of `;trigger`, replacing any saved under that trigger
+pub async fn save_text_expansion(trigger: String, text: String) -> Result<std::collections::BTreeMap<String, String>, String> {
+    let trigger = preprocess::validate_trigger(&trigger)?;
+    if text.trim().is_empty() {
+        return Err(tr!("expansion-text-missing"));
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut expansions = config_manager.get_text_expansions().map_err(|e| {
+        log::error!("Failed to get text expansions: {}", e);
+        e.to_string()
+    })?;
+    expansions.insert(trigger, text);
+    config_manager.save_text_expansions(expansions.clone()).map_err(|e| {
+        log::error!("Failed to save text expansions: {}", e);
+        e.to_string()
+    })?;
+    Ok(expansions)
+}
+
+
+pub async fn remove_text_expansion(trigger: String) -> Result<bool, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut expansions = config_manager.get_text_expansions().map_err(|e| {
+        log::error!("Failed to get text expansions: {}", e);
+        e.to_string()
+    })?;
+    if expansions.remove(trigger.trim().trim_start_matches(';')).is_none() {
+        return Ok(false);
+    }
+    config_manager.save_text_expansions(expansions).map_err(|e| {
+        log::error!("Failed to save text expansions: {}", e);
+        e.to_string()
+    })?;
+    Ok(true)
+}
+
+
+/// `create_streaming_chat` for an API server client, with its token's persona and model in
+/// place of the app's
+pub(crate) async fn create_shaped_chat(
+    message: String,
+    conversation_id: Option<String>,
+    shaping: &RequestShaping,
+) -> Result<Pin<Box<dyn Stream<Item = StreamMessage> + Send>>, String> {
+    log::info!("Creating streaming chat for message");
+    maintenance::note_activity();
+    let planning = planner::command(&message).map(str::to_string);
+    if planning.as_deref() == Some("") {
+        return Err(tr!("plan-goal-missing"));
+    }
+    
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager for streaming: {}", e);
+        e.to_string()
+    })?;
+    let app_mode = modes::behavior(config_manager.get_mode().map_err(|e| {
+        log::error!("Failed to get mode: {}", e);
+        e.to_string()
+    })?);
+    if !app_mode.send_messages {
+        return Err(tr!("mode-no-messages"));
+    }
+    if demo::active() {
+        policy::check_provider(demo::PROVIDER)?;
+        return demo_chat(&config_manager, message, conversation_id);
+    }
+    let (client, model) = streaming_client(&config_manager)?;
+
+    // User scripts and plugins with the transform_messages permission may rewrite the prompt
+    startup::ensure(startup::Subsystem::Scripts).await;
+    startup::ensure(startup::Subsystem::Plugins).await;
+    let message = scripting::on_message_send(message);
+    // Off the async runtime, so a slow plugin holds up only this message
+    let message = tokio::task::spawn_blocking(move || plugins::transform_message(message))
+        .await
+        .map_err(|e| {
+            log::error!("Plugin message transform failed: {}", e);
+            e.to_string()
+        })?;
+    let redaction_enabled = config_manager.get_redaction_enabled().map_err(|e| {
+        log::error!("Failed to get redaction setting: {}", e);
+        e.to_string()
+    })?;
+    let message = if redaction_enabled { redaction::redact(&message) } else { message };
+
+    let conversation_id = conversation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    require_unlocked(&config_manager, &conversation_id)?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    check_spend_cap(&store)?;
+    let setup = store.conversation_setup(&conversation_id).map_err(|e| {
+        log::error!("Failed to load conversation setup: {}", e);
+        e.to_string()
+    })?;
+    let model = shaping.model.clone()
+        .or_else(|| setup.as_ref().and_then(|setup| setup.model.clone()))
+        .unwrap_or(model);
+    let previous = store.list(&conversation_id).map_err(|e| {
+        log::error!("Failed to load conversation history: {}", e);
+        e.to_string()
+    })?;
+    let previous = compressed_history(&store, &conversation_id, previous, true);
+
+    // Record the exchange up front; the assistant message starts out partial until the stream completes
+    let user_message = history::HistoryMessage::new(&conversation_id, "user", message.clone(), None, None);
+    let mut assistant_message = history::HistoryMessage::new(
+        &conversation_id,
+        "assistant",
+        String::new(),
+        Some(model.clone()),
+        Some(user_message.id.clone()),
+    );
+    assistant_message.status = MessageStatus::Partial;
+    store.batch(|store| {
+        store.insert(&user_message)?;
+        store.insert(&assistant_message)
+    }).map_err(|e| {
+        log::error!("Failed to record message in history: {}", e);
+        e.to_string()
+    })?;
+    accessibility::announce(&user_message.id, MessageRole::User, MessageBoundary::End, CompletionStatus::Complete, tr!("a11y-message-sent"));
+
+    let mut settings = config_manager.get_generation_settings().map_err(|e| {
+        log::error!("Failed to get generation settings: {}", e);
+        e.to_string()
+    })?;
+    if app_mode.compare_answers {
+        // Compare mode shows every sample for the user to choose from rather than judging them
+        settings.selection = CandidateSelection::User;
+        settings.sample_count = settings.sample_count.max(2);
+    }
+    if shaping.model.is_some() {
+        // A client held to one model doesn't reach others by racing or drafting
+        settings.fast_model = None;
+    }
+    let setup = match &shaping.persona {
+        Some(persona) => {
+            settings.active_persona = Some(persona.clone());
+            setup.map(|setup| starters::ConversationSetup { system_prompt: None, ..setup })
+        }
+        None => setup,
+    };
+    let mut context_sources = Vec::new();
+    let persona = system_prompt(&config_manager, &settings, setup.as_ref(), &mut context_sources)?;
+    let persona = match mentions::tool_instruction(&shaping.tools) {
+        Some(instruction) => {
+            let names: Vec<&str> = shaping.tools.iter().map(|tool| tool.name.as_str()).collect();
+            context_sources.push(format!("asked to use {}", names.join(", ")));
+            Some(match persona {
+                Some(persona) => format!("{}\n\n{}", persona, instruction),
+                None => instruction,
+            })
+        }
+        None => persona,
+    };
+    let plan = store.conversation_plan(&conversation_id).map_err(|e| {
+        log::error!("Failed to load task plan: {}", e);
+        e.to_string()
+    })?;
+    let plan_instruction = match (&planning, &plan) {
+        (Some(goal), _) => {
+            context_sources.push("asked for a plan".to_string());
+            Some(planner::planning_instruction(goal))
+        }
+        (None, Some(plan)) => {
+            context_sources.push(format!("task plan of {} steps", plan.steps.len()));
+            Some(planner::progress_instruction(plan))
+        }
+        (None, None) => None,
+    };
+    let persona = match (persona, plan_instruction) {
+        (Some(persona), Some(instruction)) => Some(format!("{}\n\n{}", persona, instruction)),
+        (persona, instruction) => persona.or(instruction),
+    };
+    let budget = context::context_budget(context::model_context_length(&model));
+    let window = context::build_context(&previous, &message, budget).with_system(persona.as_deref());
+    let history_sent = window.messages.iter().filter(|(role, _)| role != "system").count() - 1;
+    if history_sent > 0 {
+        context_sources.push(format!("{} earlier messages", history_sent));
+    }
+    if window.dropped_messages > 0 {
+        context_sources.push(format!("{} older messages left out", window.dropped_messages));
+    }
+    if let Some(compression) = store.compression(&conversation_id).ok().filter(|c| c.enabled) {
+        context_sources.push(format!("history compressed to {:.0}%", compression.ratio * 100.0));
+    }
+    let mode = if app_mode.compare_answers { GenerationMode::BestOfN } else { settings.effective_mode() };
+    let prompt_tokens = window.messages.iter().map(|(_, content)| context::estimate_tokens(content)).sum::<usize>() as u32;
+    let settings_for_cost = settings.clone();
+    let guard_window = window.clone();
+    let fast_model_setting = settings.fast_model.clone();
+    let memory_model = settings.fast_model.clone().unwrap_or_else(|| model.clone());
+    let memory_client = client.clone();
+    let fast_model = settings.fast_model.filter(|fast| *fast != model);
+
+    let guardrail_config = config_manager.get_guardrails().map_err(|e| {
+        log::error!("Failed to get guardrails: {}", e);
+        e.to_string()
+    })?;
+    let guard = if guardrail_config.enabled {
+        let guardrails = guardrails::Guardrails::compile(&guardrail_config).map_err(|e| {
+            log::error!("Invalid guardrails: {}", e);
+            e.to_string()
+        })?;
+        Some((guardrails, client.clone(), model.clone(), guard_window.clone()))
+    } else {
+        None
+    };
+
+    // Only a single answer's tokens can be shown against it, and only a single answer is worth
+    // replaying, so the other modes neither record tokens nor send a seed
+    let token_logprobs = (settings.token_logprobs && mode == GenerationMode::Standard).then(logprobs::Collector::default);
+    let seed = settings.seed.filter(|_| mode == GenerationMode::Standard && providers::resolve(&model).0.takes_seed());
+    let messages = match (mode, fast_model) {
+        (GenerationMode::Race, Some(fast_model)) => {
+            // Both requests start inside the race so neither waits for the other's first token
+            log::info!("Racing {} against {}", fast_model, model);
+            let fast_request = chat_request(&fast_model, &window)?;
+            let fast_client = client.clone();
+            let fast = lazy_provider_messages(async move { open_provider_stream(&fast_client, fast_request).await });
+            let preferred = lazy_messages(open_with_failover(
+                client, model.clone(), previous, message.clone(), conversation_id.clone(), window, AnswerOptions::default(),
+            ));
+            generation::race(fast_model, fast, model.clone(), preferred)
+        }
+        (GenerationMode::DraftRefine, Some(draft_model)) => {
+            log::info!("Drafting with {} for {} to refine", draft_model, model);
+            let draft_request = chat_request(&draft_model, &window)?;
+            let draft_client = client.clone();
+            let draft = lazy_provider_messages(async move { open_provider_stream(&draft_client, draft_request).await });
+            let (refine_model, refine_conversation) = (model.clone(), conversation_id.clone());
+            let refine_original = message.clone();
+            generation::draft_and_refine(draft_model, draft, move |draft| async move {
+                let prompt = match draft {
+                    Some(draft) => generation::refine_prompt(&refine_original, &draft),
+                    None => refine_original,
+                };
+                let window = context::build_context(&previous, &prompt, budget).with_system(persona.as_deref());
+                lazy_messages(open_with_failover(
+                    client, refine_model, previous, prompt, refine_conversation, window, AnswerOptions::default(),
+                ))
+            })
+        }
+        (GenerationMode::BestOfN, _) => {
+            log::info!("Sampling {} answers from {}", settings.sample_count, model);
+            let samples = (0..settings.sample_count).map(|_| {
+                let request = chat_request(&model, &window);
+                let sample_client = client.clone();
+                lazy_provider_messages(async move { open_provider_stream(&sample_client, request?).await })
+            }).collect();
+            let data_dir = config_manager.get_data_dir().clone();
+            let message_id = assistant_message.id.clone();
+            let (judge_model, judge_conversation) = (model.clone(), conversation_id.clone());
+            let judge_original = message.clone();
+            let selection = settings.selection;
+            generation::best_of_n(samples, move |candidates| async move {
+                let stored = history::HistoryStore::open(&data_dir).and_then(|mut store| {
+                    let ids = store.batch(|store| {
+                        candidates.iter()
+                            .map(|candidate| Ok(store.insert_candidate(&message_id, &judge_model, candidate)?.id))
+                            .collect::<anyhow::Result<Vec<_>>>()
+                    })?;
+                    if selection == CandidateSelection::User {
+                        store.select_candidate(&ids[0])?;
+                    }
+                    Ok(())
+                });
+                if let Err(e) = stored {
+                    log::error!("Failed to store candidates: {}", e);
+                }
+                events::publish(AppEvent::CandidatesReady { message_id, count: candidates.len() as u32 });
+
+                match selection {
+                    CandidateSelection::User => {
+                        let first = candidates.into_iter().next().unwrap_or_default();
+                        Box::pin(futures::stream::once(async move { StreamMessage::Content(first) })) as MessageStream
+                    }
+                    CandidateSelection::Judge => {
+                        let prompt = generation::judge_prompt(&judge_original, &candidates);
+                        let window = context::build_context(&previous, &prompt, budget).with_system(persona.as_deref());
+                        lazy_messages(open_with_failover(
+                            client, judge_model, previous, prompt, judge_conversation, window, AnswerOptions::default(),
+                        ))
+                    }
+                }
+            })
+        }
+        _ => open_with_failover(
+            client, model.clone(), previous, message.clone(), conversation_id.clone(), window,
+            AnswerOptions { token_logprobs: token_logprobs.clone(), seed },
+        ).await?,
+    };
+
+    let messages = match guard {
+        Some((guardrails, client, guard_model, window)) => generation::guarded(messages, guardrails, move |rejected, correction| {
+            let mut window = window.clone();
+            window.messages.push(("assistant".to_string(), rejected));
+            window.messages.push(("user".to_string(), correction));
+            let request = chat_request(&guard_model, &window);
+            let client = client.clone();
+            async move {
+                lazy_provider_messages(async move { open_provider_stream(&client, request?).await })
+            }
+        }),
+        None => messages,
+    };
+
+    let memory_enabled = config_manager.get_memory_enabled().map_err(|e| {
+        log::error!("Failed to get memory setting: {}", e);
+        e.to_string()
+    })?;
+    let details = history::MessageDetails {
+        message_id: assistant_message.id.clone(),
+        provider: providers::resolve(&model).0.name().to_string(),
+        model: model.clone(),
+        parameters: history::GenerationParameters {
+            mode,
+            max_tokens: Some(context::max_response_tokens(&guard_window, context::model_context_length(&model))),
+            fast_model: fast_model_setting,
+            sample_count: (mode == GenerationMode::BestOfN).then_some(settings.sample_count),
+            guardrails: guardrail_config.enabled,
+            memory: memory_enabled,
+            seed,
+            ..Default::default()
+        },
+        context_sources,
+        metadata: shaping.metadata.clone(),
+        ..Default::default()
+    };
+    let messages = if memory_enabled {
+        let data_dir = config_manager.get_data_dir().clone();
+        let (prompt, memory_conversation) = (message.clone(), conversation_id.clone());
+        memory::on_answer(messages, move |answer| {
+            tokio::spawn(learn_memories(memory_client, memory_model, data_dir, memory_conversation, prompt, answer));
+        })
+    } else {
+        messages
+    };
+    let messages = if planning.is_some() || plan.is_some() {
+        let (data_dir, plan_conversation) = (config_manager.get_data_dir().clone(), conversation_id.clone());
+        memory::on_answer(messages, move |answer| record_plan(&data_dir, &plan_conversation, planning, &answer))
+    } else {
+        messages
+    };
+
+    let recorder = ResponseRecorder::new(config_manager.get_data_dir().clone(), String::new(), details)
+        .with_requests(generation::planned_requests(&settings_for_cost, &model, prompt_tokens))
+        .with_token_logprobs(token_logprobs);
+    Ok(track_stream(messages, model, conversation_id, message, recorder))
+}
+
+
+/// Start a conversation in which `plan`'s agents talk with each other toward its goal. The goal
+/// is saved as the user's message and every turn as an answer of its own, so the transcript
+/// reads back like any conversation; the turns stream one after another, each led by AgentTurn.
+pub async fn create_orchestrated_chat(
+    plan: OrchestrationPlan,
+    conversation_id: Option<String>,
+) -> Result<Pin<Box<dyn Stream<Item = StreamMessage> + Send>>, String> {
+    let plan = orchestration::validate(plan)?;
+    log::info!("Orchestrating {} agents for up to {} turns", plan.agents.len(), plan.max_turns);
+    maintenance::note_activity();
+
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager for streaming: {}", e);
+        e.to_string()
+    })?;
+    let app_mode = modes::behavior(config_manager.get_mode().map_err(|e| {
+        log::error!("Failed to get mode: {}", e);
+        e.to_string()
+    })?);
+    if !app_mode.send_messages {
+        return Err(tr!("mode-no-messages"));
+    }
+    let (client, preferred) = streaming_client(&config_manager)?;
+    let conversation_id = conversation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    require_unlocked(&config_manager, &conversation_id)?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    check_spend_cap(&store)?;
+
+    // Everything the agents need is looked up before the goal is saved, so a missing persona or
+    // a provider the policy blocks fails the whole plan
+    let mut agents = Vec::with_capacity(plan.agents.len());
+    for (index, agent) in plan.agents.iter().enumerate() {
+        let model = agent.model.clone().unwrap_or_else(|| preferred.clone());
+        policy::check_provider(providers::resolve(&model).0.name())?;
+        let persona = match &agent.persona {
+            Some(persona) => Some(persona_prompt(&config_manager, persona)?
+                .ok_or_else(|| tr!("orchestration-persona-unknown", persona = persona.clone()))?),
+            None => None,
+        };
+        agents.push((model, orchestration::agent_prompt(&plan, index, persona.as_deref())));
+    }
+    // Agents work through the conversation's task plan like any answer in it, marking steps
+    let task_plan = store.conversation_plan(&conversation_id).map_err(|e| {
+        log::error!("Failed to load task plan: {}", e);
+        e.to_string()
+    })?;
+    if let Some(task_plan) = &task_plan {
+        let instruction = planner::progress_instruction(task_plan);
+        for (_, system) in &mut agents {
+            system.push_str("\n\n");
+            system.push_str(&instruction);
+        }
+    }
+    if let Some(moderator) = &plan.moderator_model {
+        policy::check_provider(providers::resolve(moderator).0.name())?;
+    }
+
+    let user_message = history::HistoryMessage::new(&conversation_id, "user", plan.goal.clone(), None, None);
+    store.insert(&user_message).map_err(|e| {
+        log::error!("Failed to record message in history: {}", e);
+        e.to_string()
+    })?;
+    accessibility::announce(&user_message.id, MessageRole::User, MessageBoundary::End, CompletionStatus::Complete, tr!("a11y-message-sent"));
+
+    let run = Orchestration {
+        plan,
+        agents,
+        client,
+        conversation_id,
+        data_dir: config_manager.get_data_dir().clone(),
+        turns: Vec::new(),
+        parent_id: user_message.id,
+        speaking: None,
+        streamed: Default::default(),
+        planned: task_plan.is_some(),
+        ended: false,
+    };
+    let turns = futures::stream::unfold(run, |mut run| async move {
+        if run.ended {
+            return None;
+        }
+        let turn = run.next().await;
+        Some((turn, run))
+    });
+    Ok(Box::pin(turns.flatten()))
+}
+
+
+/// An orchestrated conversation under way
+struct Orchestration {
+    plan: OrchestrationPlan,
+    /// Each agent's model and system prompt, in the plan's order
+    agents: Vec<(String, String)>,
+    client: Client<OpenAIConfig>,
+    conversation_id: String,
+    data_dir: std::path::PathBuf,
+    turns: Vec<orchestration::Turn>,
+    /// The message the next turn answers
+    parent_id: String,
+    /// The agent whose turn is streaming
+    speaking: Option<usize>,
+    /// What the streaming turn has said so far, and whether it failed
+    streamed: std::sync::Arc<std::sync::Mutex<(String, bool)>>,
+    /// Whether the conversation has a task plan the turns mark steps of
+    planned: bool,
+    ended: bool,
+}
+
+impl Orchestration {
+    /// The messages of the next turn once the one before has finished, or the conversation's end
+    async fn next(&mut self) -> MessageStream {
+        if let Some(agent) = self.speaking.take() {
+            let (text, failed) = match self.streamed.lock() {
+                Ok(mut streamed) => std::mem::take(&mut *streamed),
+                Err(_) => (String::new(), true),
+            };
+            if failed {
+                return self.end(OrchestrationEnd::Failed);
+            }
+            if self.planned {
+                record_plan(&self.data_dir, &self.conversation_id, None, &text);
+            }
+            let stop = orchestration::says_stop_phrase(&self.plan, &text);
+            self.turns.push(orchestration::Turn { agent, text });
+            if stop {
+                return self.end(OrchestrationEnd::StopPhrase);
+            }
+            if let Some(moderator) = self.plan.moderator_model.clone().filter(|_| orchestration::round_complete(&self.plan, self.turns.len())) {
+                if self.goal_reached(&moderator).await {
+                    return self.end(OrchestrationEnd::Moderator);
+                }
+            }
+        }
+        if self.turns.len() >= self.plan.max_turns as usize {
+            return self.end(OrchestrationEnd::TurnLimit);
+        }
+        match self.start_turn() {
+            Ok(messages) => messages,
+            Err(e) => {
+                log::error!("Failed to start turn {} of orchestration: {}", self.turns.len() + 1, e);
+                Box::pin(futures::stream::once(async move { StreamMessage::Error(e) }).chain(self.end(OrchestrationEnd::Failed)))
+            }
+        }
+    }
+
+    fn end(&mut self, reason: OrchestrationEnd) -> MessageStream {
+        log::info!("Orchestration in {} ended after {} turns: {:?}", self.conversation_id, self.turns.len(), reason);
+        self.ended = true;
+        events::publish(AppEvent::OrchestrationEnded {
+            conversation_id: self.conversation_id.clone(),
+            turns: self.turns.len() as u32,
+            reason,
+        });
+        Box::pin(futures::stream::once(async { StreamMessage::Complete }))
+    }
+
+    /// Whether `moderator` judges the goal reached; a moderator that can't be asked lets the
+    /// conversation go on to its turn limit
+    async fn goal_reached(&self, moderator: &str) -> bool {
+        let prompt = orchestration::moderator_prompt(&self.plan, &self.turns);
+        let window = context::build_context(&[], &prompt, context::context_budget(context::model_context_length(moderator)));
+        let verdict = match chat_request(moderator, &window) {
+            Ok(request) => match open_provider_stream(&self.client, request).await {
+                Ok(stream) => generation::collect_text(provider_messages(stream)).await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        match verdict {
+            Ok(verdict) => {
+                log::info!("Moderator {} after {} turns: {}", moderator, self.turns.len(), verdict.trim());
+                orchestration::moderator_done(&verdict)
+            }
+            Err(e) => {
+                log::warn!("Moderator {} failed, carrying on: {}", moderator, e);
+                false
+            }
+        }
+    }
+
+    /// Save the next agent's answer as partial and stream it, led by AgentTurn
+    fn start_turn(&mut self) -> Result<MessageStream, String> {
+        let store = history::HistoryStore::open(&self.data_dir).map_err(|e| {
+            log::error!("Failed to open history: {}", e);
+            e.to_string()
+        })?;
+        check_spend_cap(&store)?;
+        let agent = orchestration::next_agent(&self.plan, self.turns.len());
+        let (model, system) = &self.agents[agent];
+        let name = self.plan.agents[agent].name.clone();
+        let window = orchestration::turn_window(
+            &self.plan, agent, &self.turns, system, context::context_budget(context::model_context_length(model)),
+        );
+        let request = chat_request(model, &window)?;
+        let prompt = window.messages.last().map(|(_, content)| content.clone()).unwrap_or_default();
+        let prompt_tokens = window.messages.iter().map(|(_, content)| context::estimate_tokens(content)).sum::<usize>() as u32;
+
+        let mut assistant_message = history::HistoryMessage::new(
+            &self.conversation_id,
+            "assistant",
+            String::new(),
+            Some(model.clone()),
+            Some(self.parent_id.clone()),
+        );
+        assistant_message.status = MessageStatus::Partial;
+        store.insert(&assistant_message).map_err(|e| {
+            log::error!("Failed to record message in history: {}", e);
+            e.to_string()
+        })?;
+        self.parent_id = assistant_message.id.clone();
+
+        let mut context_sources = vec![format!("agent {}", name)];
+        if let Some(persona) = &self.plan.agents[agent].persona {
+            context_sources.push(format!("persona {}", persona));
+        }
+        if window.dropped_messages > 0 {
+            context_sources.push(format!("{} older messages left out", window.dropped_messages));
+        }
+        let details = history::MessageDetails {
+            message_id: assistant_message.id.clone(),
+            provider: providers::resolve(model).0.name().to_string(),
+            model: model.clone(),
+            parameters: history::GenerationParameters {
+                max_tokens: Some(context::max_response_tokens(&window, context::model_context_length(model))),
+                ..Default::default()
+            },
+            context_sources,
+            metadata: std::collections::BTreeMap::from([("agent".to_string(), name.clone())]),
+            ..Default::default()
+        };
+        let recorder = ResponseRecorder::new(self.data_dir.clone(), String::new(), details)
+            .with_requests(vec![cost::PlannedRequest { model: model.clone(), prompt_tokens }]);
+        let client = self.client.clone();
+        let messages = lazy_provider_messages(async move { open_provider_stream(&client, request).await });
+        let messages = track_stream(messages, model.clone(), self.conversation_id.clone(), prompt, recorder);
+
+        // Each turn's Complete is held back; the conversation completes once, at its end
+        let streamed = self.streamed.clone();
+        let messages = messages.filter_map(move |message| {
+            if let Ok(mut streamed) = streamed.lock() {
+                match &message {
+                    StreamMessage::Content(content) => streamed.0.push_str(content),
+                    StreamMessage::Replace(text) => streamed.0 = text.clone(),
+                    StreamMessage::Error(_) => streamed.1 = true,
+                    _ => {}
+                }
+            }
+            futures::future::ready((message != StreamMessage::Complete).then_some(message))
+        });
+        self.speaking = Some(agent);
+        let turn = StreamMessage::AgentTurn { agent: name, model: model.clone(), message_id: assistant_message.id };
+        Ok(Box::pin(futures::stream::once(async move { turn }).chain(messages)))
+    }
+}
+
+
+/// Answer from the demo provider: the exchange is recorded like any other, but the reply is canned
+fn demo_chat(
+    config_manager: &ConfigManager,
+    message: String,
+    conversation_id: Option<String>,
+) -> Result<Pin<Box<dyn Stream<Item = StreamMessage> + Send>>, String> {
+    let conversation_id = conversation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let user_message = history::HistoryMessage::new(&conversation_id, "user", message.clone(), None, None);
+    let mut assistant_message = history::HistoryMessage::new(
+        &conversation_id,
+        "assistant",
+        String::new(),
+        Some(demo::MODEL.to_string()),
+        Some(user_message.id.clone()),
+    );
+    assistant_message.status = MessageStatus::Partial;
+    store.batch(|store| {
+        store.insert(&user_message)?;
+        store.insert(&assistant_message)
+    }).map_err(|e| {
+        log::error!("Failed to record message in history: {}", e);
+        e.to_string()
+    })?;
+    accessibility::announce(&user_message.id, MessageRole::User, MessageBoundary::End, CompletionStatus::Complete, tr!("a11y-message-sent"));
+
+    let details = history::MessageDetails {
+        message_id: assistant_message.id.clone(),
+        provider: demo::PROVIDER.to_string(),
+        model: demo::MODEL.to_string(),
+        ..Default::default()
+    };
+    let recorder = ResponseRecorder::new(config_manager.get_data_dir().clone(), String::new(), details);
+    Ok(track_stream(demo::reply(&message), demo::MODEL.to_string(), conversation_id, message, recorder))
+}
+
+
+/// Store any new durable facts from a finished exchange
+async fn learn_memories(
+    client: Client<OpenAIConfig>,
+    model: String,
+    data_dir: std::path::PathBuf,
+    conversation_id: String,
+    prompt: String,
+    answer: String,
+) {
+    let known = match history::HistoryStore::open(&data_dir).and_then(|store| store.list_memories()) {
+        Ok(known) => known,
+        Err(e) => {
+            log::error!("Failed to load memories: {}", e);
+            return;
+        }
+    };
+    let facts = match memory::extract(&client, &model, &prompt, &answer, &known).await {
+        Ok(facts) => facts,
+        Err(e) => {
+            log::warn!("Memory extraction failed: {}", e);
+            return;
+        }
+    };
+    if facts.is_empty() {
+        return;
+    }
+
+    let stored = history::HistoryStore::open(&data_dir).and_then(|store| {
+        store.batch(|store| {
+            for fact in &facts {
+                store.insert_memory(&memory::Memory::new(fact.clone(), Some(conversation_id.clone())))?;
+            }
+            Ok(())
+        })
+    });
+    match stored {
+        Ok(()) => {
+            log::info!("Learned {} memories", facts.len());
+            events::publish(AppEvent::MemoriesLearned { conversation_id, count: facts.len() as u32 });
+        }
+        Err(e) => log::error!("Failed to store memories: {}", e),
+    }
+}
+
+
+/// Save the plan in an answer to `/plan goal`, or, when `goal` is None, apply the step marks in
+/// an answer to the conversation's plan. An answer without a readable plan leaves any earlier
+/// plan in place.
+fn record_plan(data_dir: &std::path::Path, conversation_id: &str, goal: Option<String>, answer: &str) {
+    let store = match history::HistoryStore::open(data_dir) {
+        Ok(store) => store,
+        Err(e) => {
+            log::error!("Failed to open history: {}", e);
+            return;
+        }
+    };
+    let plan = match goal {
+        Some(goal) => match planner::parse(conversation_id, &goal, answer) {
+            Ok(plan) => plan,
+            Err(e) => {
+                log::warn!("No plan recorded for {}: {}", conversation_id, e);
+                return;
+            }
+        },
+        None => match store.conversation_plan(conversation_id) {
+            Ok(Some(mut plan)) => {
+                if planner::apply_marks(&mut plan, answer) == 0 {
+                    return;
+                }
+                plan
+            }
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Failed to load task plan: {}", e);
+                return;
+            }
+        },
+    };
+    match store.save_conversation_plan(&plan) {
+        Ok(()) => {
+            log::info!("Recorded plan of {} steps for {}", plan.steps.len(), conversation_id);
+            events::publish(AppEvent::PlanUpdated { plan });
+        }
+        Err(e) => log::error!("Failed to save task plan: {}", e),
+    }
+}
+
+pub async fn get_guardrails() -> Result<GuardrailConfig, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_guardrails().map_err(|e| {
+        log::error!("Failed to get guardrails: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn set_guardrails(guardrails: GuardrailConfig) -> Result<(), String> {
+    log::info!("Setting guardrails (enabled: {})", guardrails.enabled);
+    // Reject bad patterns and schemas now rather than on the next message
+    guardrails::Guardrails::compile(&guardrails).map_err(|e| e.to_string())?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_guardrails(guardrails).map_err(|e| {
+        log::error!("Failed to save guardrails: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Estimate what answering `message` will cost with the current generation settings
+pub async fn estimate_generation_cost(message: String, conversation_id: Option<String>) -> Result<CostEstimate, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let model = config_manager.get_preferred_model().map_err(|e| {
+        log::error!("Failed to get preferred model: {}", e);
+        e.to_string()
+    })?;
+    let settings = config_manager.get_generation_settings().map_err(|e| {
+        log::error!("Failed to get generation settings: {}", e);
+        e.to_string()
+    })?;
+
+    let (previous, setup) = match conversation_id {
+        Some(conversation_id) => {
+            require_unlocked(&config_manager, &conversation_id)?;
+            let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+                log::error!("Failed to open history: {}", e);
+                e.to_string()
+            })?;
+            let previous = store.list(&conversation_id).map_err(|e| {
+                log::error!("Failed to load conversation history: {}", e);
+                e.to_string()
+            })?;
+            let setup = store.conversation_setup(&conversation_id).map_err(|e| {
+                log::error!("Failed to load conversation setup: {}", e);
+                e.to_string()
+            })?;
+            (compressed_history(&store, &conversation_id, previous, false), setup)
+        }
+        None => (Vec::new(), None),
+    };
+    let model = setup.as_ref().and_then(|setup| setup.model.clone()).unwrap_or(model);
+    let persona = system_prompt(&config_manager, &settings, setup.as_ref(), &mut Vec::new())?;
+    let budget = context::context_budget(context::model_context_length(&model));
+    let window = context::build_context(&previous, &message, budget).with_system(persona.as_deref());
+    let prompt_tokens: usize = window.messages.iter().map(|(_, content)| context::estimate_tokens(content)).sum();
+
+    let estimate = cost::estimate(&generation::planned_requests(&settings, &model, prompt_tokens as u32));
+    log::info!(
+        "Estimated {} requests, {} prompt tokens, ${:?}",
+        estimate.requests, estimate.prompt_tokens, estimate.estimated_usd
+    );
+    Ok(estimate)
+}
+
+
+/// All sampled answers stored for an assistant message
+pub async fn list_candidates(message_id: String) -> Result<Vec<Candidate>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_message_unlocked(&config_manager, &message_id)?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_candidates(&message_id))
+        .map_err(|e| {
+            log::error!("Failed to list candidates: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Replace a message's content with one of its candidates
+pub async fn select_candidate(candidate_id: String) -> Result<Candidate, String> {
+    log::info!("Selecting candidate {}", candidate_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|mut store| store.select_candidate(&candidate_id))
+        .map_err(|e| {
+            log::error!("Failed to select candidate: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Word-level differences between two candidates of the same message, from `a` to `b`
+pub async fn diff_responses(a: String, b: String) -> Result<ResponseDiff, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let (from, to) = store.candidate(&a)
+        .and_then(|from| Ok((from, store.candidate(&b)?)))
+        .map_err(|e| {
+            log::error!("Failed to load candidates to compare: {}", e);
+            e.to_string()
+        })?;
+    if from.message_id != to.message_id {
+        return Err(tr!("diff-different-messages"));
+    }
+    require_message_unlocked(&config_manager, &from.message_id)?;
+    let diff = response_diff::diff(&from, &to);
+    log::debug!("Diffed {} against {}: +{} -{} words", a, b, diff.words_added, diff.words_removed);
+    Ok(diff)
+}
+
+
+/// Answer an assistant message's prompt again with a different model or temperature. The new
+/// answer is stored as an unselected candidate beside the original, which is kept as the
+/// selected one, so the two can be compared and either chosen with `select_candidate`.
+pub async fn regenerate_with(message_id: String, overrides: RegenerationOverrides) -> Result<Candidate, String> {
+    log::info!("Regenerating {} with {:?}", message_id, overrides);
+    if let Some(temperature) = overrides.temperature {
+        if !(0.0..=generation::MAX_TEMPERATURE).contains(&temperature) {
+            return Err(tr!("regenerate-temperature-range", max = generation::MAX_TEMPERATURE));
+        }
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let app_mode = modes::behavior(config_manager.get_mode().map_err(|e| {
+        log::error!("Failed to get mode: {}", e);
+        e.to_string()
+    })?);
+    if !app_mode.send_messages {
+        return Err(tr!("mode-no-messages"));
+    }
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    check_spend_cap(&store)?;
+    let original = store.get(&message_id)
+        .map_err(|e| e.to_string())?
+        .filter(|message| message.role == "assistant")
+        .ok_or_else(|| tr!("regenerate-not-an-answer"))?;
+    require_unlocked(&config_manager, &original.conversation_id)?;
+    let conversation = store.list(&original.conversation_id).map_err(|e| {
+        log::error!("Failed to load conversation history: {}", e);
+        e.to_string()
+    })?;
+    let Some(prompt_at) = conversation.iter().position(|message| Some(&message.id) == original.parent_id.as_ref()) else {
+        return Err(tr!("regenerate-not-an-answer"));
+    };
+    let prompt = conversation[prompt_at].content.clone();
+    let previous = compressed_history(&store, &original.conversation_id, conversation[..prompt_at].to_vec(), false);
+    let setup = store.conversation_setup(&original.conversation_id).map_err(|e| {
+        log::error!("Failed to load conversation setup: {}", e);
+        e.to_string()
+    })?;
+    let filters = store.conversation_filters(&original.conversation_id).map_err(|e| {
+        log::error!("Failed to load conversation filters: {}", e);
+        e.to_string()
+    })?;
+
+    let started = std::time::Instant::now();
+    let (provider, model, prompt_tokens, messages) = if demo::active() {
+        policy::check_provider(demo::PROVIDER)?;
+        (demo::PROVIDER, demo::MODEL.to_string(), 0, demo::reply(&prompt))
+    } else {
+        let (client, preferred) = streaming_client(&config_manager)?;
+        let model = overrides.model.clone()
+            .or_else(|| setup.as_ref().and_then(|setup| setup.model.clone()))
+            .unwrap_or(preferred);
+        let settings = config_manager.get_generation_settings().map_err(|e| {
+            log::error!("Failed to get generation settings: {}", e);
+            e.to_string()
+        })?;
+        let persona = system_prompt(&config_manager, &settings, setup.as_ref(), &mut Vec::new())?;
+        let budget = context::context_budget(context::model_context_length(&model));
+        let window = context::build_context(&previous, &prompt, budget).with_system(persona.as_deref());
+        let prompt_tokens = window.messages.iter().map(|(_, content)| context::estimate_tokens(content)).sum::<usize>() as u32;
+        let mut request = chat_request(&model, &window)?;
+        request.temperature = overrides.temperature;
+        request.seed = overrides.seed.map(i64::from);
+        let messages = provider_messages(open_provider_stream(&client, request).await?);
+        (providers::resolve(&model).0.name(), model, prompt_tokens, messages)
+    };
+    let (messages, truncation) = stream_filters::apply(messages, &filters);
+    let text = generation::collect_text(messages).await.map_err(|e| {
+        log::error!("Regeneration of {} failed: {}", message_id, e);
+        e
+    })?;
+
+    let requests = [cost::PlannedRequest { model: model.clone(), prompt_tokens }];
+    let details = history::MessageDetails {
+        message_id: message_id.clone(),
+        provider: provider.to_string(),
+        model: model.clone(),
+        parameters: history::GenerationParameters {
+            temperature: overrides.temperature,
+            seed: overrides.seed.filter(|_| providers::resolve(&model).0.takes_seed()),
+            ..Default::default()
+        },
+        context_sources: vec![format!("regenerated with {}", model)],
+        latency_ms: Some(started.elapsed().as_millis() as u32),
+        estimated_usd: (prompt_tokens > 0).then(|| cost::spent(&requests, context::estimate_tokens(&text) as u32)).flatten(),
+        truncated_by: truncation.get().cloned(),
+        ..Default::default()
+    };
+    let candidate = store.batch(|store| {
+        store.keep_as_candidate(&original)?;
+        let candidate = store.insert_candidate(&message_id, &model, &text)?;
+        store.save_candidate_details(&candidate.id, &details)?;
+        Ok(candidate)
+    }).map_err(|e| {
+        log::error!("Failed to store regenerated answer: {}", e);
+        e.to_string()
+    })?;
+    let count = store.list_candidates(&message_id).map(|candidates| candidates.len() as u32).unwrap_or(0);
+    events::publish(AppEvent::CandidatesReady { message_id, count });
+    log::info!("Stored regenerated answer {} from {}", candidate.id, model);
+    Ok(candidate)
+}
+
+
+/// Send an answer's prompt again with the model, temperature and seed it was generated with, to
+/// check whether the provider reproduces it. The replay is stored as a candidate, as a
+/// regeneration is; answers sent without a seed aren't expected to come out the same.
+pub async fn replay_message(message_id: String) -> Result<Replay, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let original = store.get(&message_id).map_err(|e| e.to_string())?.ok_or_else(|| tr!("regenerate-not-an-answer"))?;
+    let details = store.details(&message_id)
+        .map_err(|e| {
+            log::error!("Failed to get message details: {}", e);
+            e.to_string()
+        })?
+        .ok_or_else(|| tr!("replay-no-details"))?;
+    if details.parameters.seed.is_none() {
+        log::warn!("Replaying {} which was sent without a seed", message_id);
+    }
+    let overrides = RegenerationOverrides {
+        model: Some(details.model),
+        temperature: details.parameters.temperature,
+        seed: details.parameters.seed,
+    };
+    let candidate = regenerate_with(message_id, overrides).await?;
+    let reproduced = candidate.content == original.content;
+    log::info!("Replay {} {} the original", candidate.id, if reproduced { "matched" } else { "differed from" });
+    Ok(Replay { candidate, reproduced })
+}
+
+
+/// How a regenerated candidate was produced
+pub async fn get_candidate_details(candidate_id: String) -> Result<MessageDetails, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let details = store.candidate_details(&candidate_id)
+        .map_err(|e| {
+            log::error!("Failed to get candidate details: {}", e);
+            e.to_string()
+        })?
+        .ok_or_else(|| format!("No details recorded for candidate {}", candidate_id))?;
+    require_message_unlocked(&config_manager, &details.message_id)?;
+    Ok(details)
+}
+
+
+/// Which provider, model, settings and context produced an assistant message
+pub async fn get_message_details(id: String) -> Result<MessageDetails, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_message_unlocked(&config_manager, &id)?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.details(&id))
+        .map_err(|e| {
+            log::error!("Failed to get message details: {}", e);
+            e.to_string()
+        })?
+        .ok_or_else(|| format!("No details recorded for message {}", id))
+}
+
+
+/// Answers `on_response_complete` scripts tagged with `tag`, leaving out locked conversations
+/// that aren't open
+pub async fn find_tagged_messages(tag: String) -> Result<Vec<HistoryMessage>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let (messages, locked) = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| Ok((store.tagged_messages(tag.trim())?, store.locked_conversations()?)))
+        .map_err(|e| {
+            log::error!("Failed to find tagged messages: {}", e);
+            e.to_string()
+        })?;
+    let locked: std::collections::HashSet<String> = locked.into_iter().map(|(id, _)| id).collect();
+    Ok(messages.into_iter()
+        .filter(|message| !locked.contains(&message.conversation_id) || conversation_lock::is_open(&message.conversation_id))
+        .collect())
+}
+
+/// What answer `id` was estimated to cost and what it did, from the usage its provider reported
+pub async fn get_message_cost(id: String) -> Result<MessageCost, String> {
+    let details = get_message_details(id).await?;
+    Ok(MessageCost {
+        pricing: cost::model_pricing(&details.model),
+        message_id: details.message_id,
+        model: details.model,
+        prompt_tokens: details.prompt_tokens,
+        completion_tokens: details.completion_tokens,
+        estimated_usd: details.estimated_usd,
+        actual_usd: details.actual_usd,
+    })
+}
+
+
+/// Apply the conversation's compression setting to its history. When `record` is set the
+/// before/after token counts are saved and published.
+fn compressed_history(
+    store: &history::HistoryStore,
+    conversation_id: &str,
+    previous: Vec<HistoryMessage>,
+    record: bool,
+) -> Vec<HistoryMessage> {
+    let settings = match store.compression(conversation_id) {
+        Ok(settings) if settings.enabled => settings,
+        Ok(_) => return previous,
+        Err(e) => {
+            log::error!("Failed to read compression settings, sending full history: {}", e);
+            return previous;
+        }
+    };
+
+    let (previous, before_tokens, after_tokens) = compression::compress_history(previous, settings.ratio);
+    log::info!("Compressed history from {} to {} tokens", before_tokens, after_tokens);
+    if record {
+        if let Err(e) = store.record_compression(conversation_id, before_tokens, after_tokens) {
+            log::error!("Failed to record compression: {}", e);
+        }
+        events::publish(AppEvent::ContextCompressed {
+            conversation_id: conversation_id.to_string(),
+            before_tokens,
+            after_tokens,
+        });
+    }
+    previous
+}
+
+
+pub async fn get_conversation_compression(conversation_id: String) -> Result<ConversationCompression, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.compression(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to get compression settings: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Turn compression of old turns and transcript context on or off for a conversation
+pub async fn set_conversation_compression(
+    conversation_id: String,
+    enabled: bool,
+    ratio: Option<f32>,
+) -> Result<ConversationCompression, String> {
+    let ratio = ratio.unwrap_or(compression::DEFAULT_COMPRESSION_RATIO);
+    log::info!("Setting compression for {} to {} (ratio {})", conversation_id, enabled, ratio);
+    if !(0.1..=1.0).contains(&ratio) {
+        return Err("Compression ratio must be between 0.1 and 1.0".to_string());
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| {
+            store.set_compression(&conversation_id, enabled, ratio)?;
+            store.compression(&conversation_id)
+        })
+        .map_err(|e| {
+            log::error!("Failed to set compression: {}", e);
+            e.to_string()
+        })
+}
+
+
+pub async fn get_conversation_filters(conversation_id: String) -> Result<ConversationFilters, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.conversation_filters(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to get conversation filters: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Set the stop sequences and content filters checked against answers in a conversation, from
+/// the next answer on. Empty entries are dropped; an invalid pattern fails the whole update.
+pub async fn set_conversation_filters(conversation_id: String, filters: ConversationFilters) -> Result<ConversationFilters, String> {
+    let filters = stream_filters::validate(filters)?;
+    log::info!(
+        "Setting {} stop sequences and {} content filters for {}",
+        filters.stop_sequences.len(), filters.content_filters.len(), conversation_id
+    );
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.set_conversation_filters(&conversation_id, &filters))
+        .map_err(|e| {
+            log::error!("Failed to set conversation filters: {}", e);
+            e.to_string()
+        })?;
+    Ok(filters)
+}
+
+
+/// The task plan the conversation is working through, made by its latest `/plan` message and
+/// updated as answers mark steps; None if it has none
+pub async fn get_plan(conversation_id: String) -> Result<Option<TaskPlan>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.conversation_plan(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to get task plan: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Set the status of one step of the conversation's plan, as ticking it off in the checklist does
+pub async fn update_plan_step(conversation_id: String, step_id: String, status: PlanStepStatus) -> Result<TaskPlan, String> {
+    log::info!("Setting step {} of the plan for {} to {:?}", step_id, conversation_id, status);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let mut plan = store.conversation_plan(&conversation_id)
+        .map_err(|e| {
+            log::error!("Failed to get task plan: {}", e);
+            e.to_string()
+        })?
+        .ok_or_else(|| tr!("plan-not-found"))?;
+    if planner::set_status(&mut plan, &step_id, status)? {
+        store.save_conversation_plan(&plan).map_err(|e| {
+            log::error!("Failed to save task plan: {}", e);
+            e.to_string()
+        })?;
+        events::publish(AppEvent::PlanUpdated { plan: plan.clone() });
+    }
+    Ok(plan)
+}
+
+/// System prompt for a chat: the active persona, or the starter's prompt in a conversation begun
+/// from one, then remembered facts when memory is on.
+/// What was included is added to `sources` for the message details.
+fn system_prompt(
+    config_manager: &ConfigManager,
+    settings: &GenerationSettings,
+    setup: Option<&starters::ConversationSetup>,
+    sources: &mut Vec<String>,
+) -> Result<Option<String>, String> {
+    // A conversation begun from a starter keeps the starter's prompt in place of the persona
+    let persona = match (setup, &settings.active_persona) {
+        (Some(setup), _) if setup.system_prompt.is_some() => {
+            sources.push(format!("starter {}", setup.starter_name));
+            setup.system_prompt.clone()
+        }
+        (_, Some(persona)) => {
+            let prompt = persona_prompt(config_manager, persona)?;
+            if prompt.is_some() {
+                sources.push(format!("persona {}", persona));
+            }
+            prompt
+        }
+        (_, None) => None,
+    };
+    let memory_enabled = config_manager.get_memory_enabled().map_err(|e| {
+        log::error!("Failed to get memory setting: {}", e);
+        e.to_string()
+    })?;
+    let memories = if memory_enabled {
+        let memories = history::HistoryStore::open(config_manager.get_data_dir())
+            .and_then(|store| store.list_memories())
+            .map_err(|e| {
+                log::error!("Failed to load memories: {}", e);
+                e.to_string()
+            })?;
+        if !memories.is_empty() {
+            sources.push(format!("{} memories", memories.len()));
+        }
+        memory::system_prompt(&memories)
+    } else {
+        None
+    };
+    Ok(match (persona, memories) {
+        (Some(persona), Some(memories)) => Some(format!("{}\n\n{}", persona, memories)),
+        (persona, memories) => persona.or(memories),
+    })
+}
+
+
+pub async fn get_telemetry_config() -> Result<TelemetryConfig, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_telemetry_config().map_err(|e| {
+        log::error!("Failed to get telemetry config: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Turn OTLP trace export on or off; takes effect for the next span
+pub async fn set_telemetry_config(config: TelemetryConfig) -> Result<TelemetryConfig, String> {
+    let config = telemetry::validate(config)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_telemetry_config(config.clone()).map_err(|e| {
+        log::error!("Failed to save telemetry config: {}", e);
+        e.to_string()
+    })?;
+    telemetry::configure(config.clone());
+    Ok(config)
+}
+
+
+pub async fn get_redaction_enabled() -> Result<bool, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_redaction_enabled().map_err(|e| {
+        log::error!("Failed to get redaction setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Mask email addresses, card numbers and keys in prompts before they're sent. Fails while the
+/// policy forces redaction on.
+pub async fn set_redaction_enabled(enabled: bool) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_redaction_enabled(enabled).map_err(|e| {
+        log::error!("Failed to save redaction setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn get_stream_recording() -> Result<bool, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_record_streams().map_err(|e| {
+        log::error!("Failed to get stream recording setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Record every provider stream's chunks, as they arrive, to an NDJSON file per request under
+/// `<data_dir>/stream-recordings`
+pub async fn set_stream_recording(enabled: bool) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_record_streams(enabled).map_err(|e| {
+        log::error!("Failed to save stream recording setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn get_pause_hidden_streams() -> Result<bool, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_pause_hidden_streams().map_err(|e| {
+        log::error!("Failed to get hidden stream setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Hold streamed answers back from the window while it's minimized or hidden, sending them when
+/// it's shown again; they're still saved as they arrive
+pub async fn set_pause_hidden_streams(enabled: bool) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_pause_hidden_streams(enabled).map_err(|e| {
+        log::error!("Failed to save hidden stream setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Note whether the window can be seen, as the webview's visibility changes
+pub async fn set_window_visible(visible: bool) -> Result<(), String> {
+    log::debug!("Window is now {}", if visible { "visible" } else { "hidden" });
+    visibility::set_visible(visible);
+    Ok(())
+}
+
+
+pub async fn get_memory_enabled() -> Result<bool, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_memory_enabled().map_err(|e| {
+        log::error!("Failed to get memory setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Turn long-term memory on or off. Turning it off stops both learning and injection;
+/// stored memories are kept until cleared.
+pub async fn set_memory_enabled(enabled: bool) -> Result<(), String> {
+    log::info!("Setting memory enabled: {}", enabled);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_memory_enabled(enabled).map_err(|e| {
+        log::error!("Failed to save memory setting: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn list_memories() -> Result<Vec<Memory>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_memories())
+        .map_err(|e| {
+            log::error!("Failed to list memories: {}", e);
+            e.to_string()
+        })
+}
+
+
+pub async fn forget_memory(id: String) -> Result<(), String> {
+    log::info!("Forgetting memory {}", id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.delete_memory(&id))
+        .map_err(|e| {
+            log::error!("Failed to forget memory: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Delete every stored memory, returning how many were removed
+pub async fn clear_memories() -> Result<u32, String> {
+    log::info!("Clearing all memories");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.clear_memories())
+        .map_err(|e| {
+            log::error!("Failed to clear memories: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Content of the persona with id `persona` from the configured template repositories
+fn persona_prompt(config_manager: &ConfigManager, persona: &str) -> Result<Option<String>, String> {
+    let repositories = config_manager.get_template_repositories().map_err(|e| {
+        log::error!("Failed to get template repositories: {}", e);
+        e.to_string()
+    })?;
+    let templates = templates::TemplateLibrary::new(config_manager.get_data_dir())
+        .list(&repositories)
+        .map_err(|e| {
+            log::error!("Failed to list templates: {}", e);
+            e.to_string()
+        })?;
+    let content = templates.into_iter()
+        .find(|t| t.kind == "persona" && t.id == persona)
+        .map(|t| t.content);
+    if content.is_none() {
+        log::warn!("Active persona {} not found, sending no system prompt", persona);
+    }
+    Ok(content)
+}
+
+
+/// What's asked for only when a request's answer is the one shown on its own
+#[derive(Clone, Default)]
+struct AnswerOptions {
+    token_logprobs: Option<logprobs::Collector>,
+    seed: Option<u32>,
+}
+
+
+/// Open the answer from `model`, falling back along its provider's failover chain while the
+/// provider is rate limited, failing or slow to send its first token. A fallback's messages
+/// start with ProviderSwitched.
+async fn open_with_failover(
+    client: Client<OpenAIConfig>,
+    model: String,
+    previous: Vec<HistoryMessage>,
+    message: String,
+    conversation_id: String,
+    window: context::ContextWindow,
+    options: AnswerOptions,
+) -> Result<MessageStream, String> {
+    let fallbacks = ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_failover_chains())
+        .map(|chains| failover::fallbacks(&chains, &model).to_vec())
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read failover chains, not falling back: {}", e);
+            Vec::new()
+        });
+    let mut first_error = None;
+    let mut reason = String::new();
+    for (attempt, target) in std::iter::once(&model).chain(&fallbacks).enumerate() {
+        let last = attempt == fallbacks.len();
+        // Only some providers take a seed
+        let seed = options.seed.filter(|_| providers::resolve(target).0.takes_seed());
+        let open = open_with_overflow_retry(
+            client.clone(), target.clone(), previous.clone(), message.clone(), conversation_id.clone(), window.clone(),
+            AnswerOptions { seed, ..options.clone() },
+        );
+        let opened = if last {
+            open.await
+        } else {
+            tokio::time::timeout(failover::FIRST_TOKEN_TIMEOUT, open).await.unwrap_or_else(|_| {
+                Err(format!("{} timed out before its first token", target))
+            })
+        };
+        match opened {
+            Ok(stream) if attempt == 0 => return Ok(provider_messages(stream)),
+            Ok(stream) => {
+                let (from, to) = (providers::resolve(&model).0.name(), providers::resolve(target).0.name());
+                log::info!("{} answered in place of {}", target, model);
+                let switched = StreamMessage::ProviderSwitched { from: from.to_string(), to: to.to_string(), model: target.clone(), reason };
+                return Ok(Box::pin(futures::stream::once(async move { switched }).chain(provider_messages(stream))));
+            }
+            Err(e) if attempt == 0 && (last || !failover::should_fail_over(&e)) => return Err(e),
+            Err(e) => {
+                match fallbacks.get(attempt) {
+                    Some(next) => log::warn!("{} failed, falling back to {}: {}", target, next, e),
+                    None => log::warn!("{} failed too, giving up: {}", target, e),
+                }
+                first_error.get_or_insert_with(|| e.clone());
+                reason = e;
+            }
+        }
+    }
+    Err(first_error.unwrap_or(reason))
+}
+
+
+/// Open the stream for `window`; a context overflow is retried once with a tighter budget,
+/// dropping older history
+async fn open_with_overflow_retry(
+    client: Client<OpenAIConfig>,
+    model: String,
+    previous: Vec<HistoryMessage>,
+    message: String,
+    conversation_id: String,
+    window: context::ContextWindow,
+    options: AnswerOptions,
+) -> Result<ProviderStream, String> {
+    let request = |window: &context::ContextWindow| {
+        let mut request = chat_request(&model, window)?;
+        if options.token_logprobs.is_some() {
+            logprobs::request(&mut request);
+        }
+        request.seed = options.seed.map(i64::from);
+        Ok::<_, String>(request)
+    };
+    let opened = match open_provider_stream(&client, request(&window)?).await {
+        Err(e) if context::is_context_overflow(&e) => {
+            let budget = context::reduced_budget(&window);
+            let window = context::build_context(&previous, &message, budget).with_system(window.system());
+            log::warn!(
+                "Context overflow, retrying with {} tokens ({} messages, {} characters dropped)",
+                budget, window.dropped_messages, window.dropped_chars
+            );
+            events::publish(AppEvent::ContextReduced {
+                conversation_id,
+                dropped_messages: window.dropped_messages,
+                dropped_chars: window.dropped_chars,
+                budget_tokens: budget as u32,
+            });
+            open_provider_stream(&client, request(&window)?).await
+        }
+        other => other,
+    };
+    match options.token_logprobs {
+        Some(collector) => opened.map(|stream| logprobs::collect(stream, collector)),
+        None => opened,
+    }
+}
+
+
+/// Build a streaming request for the window, with max_tokens sized to the model's remaining context
+fn chat_request(model: &str, window: &context::ContextWindow) -> Result<async_openai::types::CreateChatCompletionRequest, String> {
+    let messages = window.messages.iter().map(|(role, content)| {
+        if role == "system" {
+            async_openai::types::ChatCompletionRequestMessage::System(
+                async_openai::types::ChatCompletionRequestSystemMessageArgs::default()
+                    .content(content.clone())
+                    .build()
+                    .unwrap(),
+            )
+        } else if role == "assistant" {
+            async_openai::types::ChatCompletionRequestMessage::Assistant(
+                async_openai::types::ChatCompletionRequestAssistantMessageArgs::default()
+                    .content(content.clone())
+                    .build()
+                    .unwrap(),
+            )
+        } else {
+            async_openai::types::ChatCompletionRequestMessage::User(
+                async_openai::types::ChatCompletionRequestUserMessageArgs::default()
+                    .content(content.clone())
+                    .build()
+                    .unwrap(),
+            )
+        }
+    }).collect::<Vec<_>>();
+
+    let max_tokens = context::max_response_tokens(window, context::model_context_length(model));
+    log::info!("Requesting up to {} tokens from {}", max_tokens, model);
+
+    // Together reads max_tokens; max_completion_tokens is OpenAI-only
+    #[allow(deprecated)]
+    async_openai::types::CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages(messages)
+        .max_tokens(max_tokens)
+        .stream(true)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+
+/// Continue an assistant message whose stream was interrupted. The model is re-prompted with
+/// the original question, the partial answer and the tail to continue from; the continuation is
+/// appended to the same history message.
+pub async fn resume_response(message_id: String) -> Result<Pin<Box<dyn Stream<Item = StreamMessage> + Send>>, String> {
+    log::info!("Resuming response {}", message_id);
+    // on_response_complete hooks run when the resumed stream finishes
+    startup::ensure(startup::Subsystem::Scripts).await;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager for resume: {}", e);
+        e.to_string()
+    })?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    check_spend_cap(&store)?;
+    let partial = store.get(&message_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Message {} not found", message_id))?;
+    if partial.role != "assistant" || partial.status != MessageStatus::Partial {
+        return Err(format!("Message {} is not an interrupted response", message_id));
+    }
+    require_unlocked(&config_manager, &partial.conversation_id)?;
+    let prompt = match &partial.parent_id {
+        Some(parent_id) => store.get(parent_id).map_err(|e| e.to_string())?.map(|m| m.content),
+        None => None,
+    }.ok_or_else(|| format!("Original prompt for message {} not found", message_id))?;
+
+    // Finished by the model that started it, which may not be the preferred one: an @mention,
+    // a race or a setup picks others
+    let (client, preferred) = streaming_client(&config_manager)?;
+    let model = partial.model.clone().unwrap_or(preferred);
+    let client = routed_client(&client, &model)?;
+
+    let tail: String = {
+        let chars: Vec<char> = partial.content.chars().collect();
+        chars[chars.len().saturating_sub(RESUME_TAIL_CHARS)..].iter().collect()
+    };
+    let mut messages = vec![("user".to_string(), prompt.clone())];
+    if !partial.content.is_empty() {
+        messages.push(("assistant".to_string(), partial.content.clone()));
+        messages.push(("user".to_string(), format!(
+            "Your previous answer was cut off. Continue exactly from where it stopped, without repeating anything. It ended with:\n{}",
+            tail
+        )));
+    }
+    let window = context::ContextWindow {
+        messages,
+        dropped_messages: 0,
+        dropped_chars: 0,
+        budget_tokens: context::model_context_length(&model),
+    };
+    let request = chat_request(&model, &window)?;
+
+    let max_tokens = Some(context::max_response_tokens(&window, context::model_context_length(&model)));
+    let existing = store.details(&partial.id).map_err(|e| {
+        log::error!("Failed to load details of {}: {}", partial.id, e);
+        e.to_string()
+    })?;
+    // What was recorded when the answer started is kept; timings are taken afresh for the rest
+    let mut details = existing.unwrap_or_else(|| history::MessageDetails {
+        message_id: partial.id.clone(),
+        provider: providers::resolve(&model).0.name().to_string(),
+        model: model.clone(),
+        parameters: history::GenerationParameters {
+            max_tokens,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    details.first_token_ms = None;
+    details.latency_ms = None;
+    details.context_sources.push("resumed from a partial answer".to_string());
+    let prompt_tokens = window.messages.iter().map(|(_, content)| context::estimate_tokens(content)).sum::<usize>() as u32;
+    let recorder = ResponseRecorder::new(config_manager.get_data_dir().clone(), partial.content.clone(), details)
+        .with_requests(vec![cost::PlannedRequest { model: model.clone(), prompt_tokens }]);
+    let messages = provider_messages(open_provider_stream(&client, request).await?);
+    Ok(track_stream(messages, model, partial.conversation_id, prompt, recorder))
+}
+
+
+/// Characters of the partial answer quoted back to the model when resuming
+const RESUME_TAIL_CHARS: usize = 200;
+
+/// Err once this month's spend reaches the policy's cap
+fn check_spend_cap(store: &history::HistoryStore) -> Result<(), String> {
+    if policy::current().max_monthly_spend_usd.is_none() {
+        return Ok(());
+    }
+    let spent = store.spent_since(&policy::month_start()).map_err(|e| {
+        log::error!("Failed to total this month's spend: {}", e);
+        e.to_string()
+    })?;
+    policy::check_spend(spent)
+}
+
+
+fn streaming_client(config_manager: &ConfigManager) -> Result<(Client<OpenAIConfig>, String), String> {
+    if demo::active() {
+        return Err(tr!("demo-unavailable"));
+    }
+    let model = config_manager.get_preferred_model().map_err(|e| {
+        log::error!("Failed to get preferred model for streaming: {}", e);
+        e.to_string()
+    })?;
+    let client = provider_client(config_manager, &model)?;
+    log::info!("Using model for streaming: {}", model);
+    Ok((client, model))
+}
+
+
+/// A client for the provider serving `model`, with that provider's key. Custom models are
+/// sent to their own API base.
+fn provider_client(config_manager: &ConfigManager, model: &str) -> Result<Client<OpenAIConfig>, String> {
+    let (provider, upstream) = providers::resolve(model);
+    policy::check_provider(provider.name())?;
+    if provider == Provider::Azure {
+        // Deployments need a client of their own, which only streamed chat builds
+        return Err(tr!("azure-chat-only"));
+    }
+    if provider == Provider::Bedrock {
+        // Requests are signed, which only streamed chat does
+        return Err(tr!("bedrock-chat-only"));
+    }
+    if provider == Provider::Endpoint {
+        let endpoints = config_manager.get_compatible_endpoints().map_err(|e| {
+            log::error!("Failed to get compatible endpoints: {}", e);
+            e.to_string()
+        })?;
+        let (endpoint, _) = endpoints::resolve(&endpoints, model).ok_or_else(|| {
+            log::error!("No compatible endpoint serves {}", model);
+            tr!("endpoint-unknown", model = model.to_string())
+        })?;
+        return with_headers(Client::with_config(endpoint.config()), provider, model_headers(model));
+    }
+    let (api_base, api_key) = provider_endpoint(config_manager, provider)?.ok_or_else(|| {
+        log::error!("No {} API key configured", provider.name());
+        tr!("no-provider-key", provider = provider.name())
+    })?;
+    let api_base = match provider {
+        Provider::Together => {
+            let custom = config_manager.get_custom_models().map_err(|e| {
+                log::error!("Failed to get custom models: {}", e);
+                e.to_string()
+            })?;
+            custom_models::api_base(&custom, upstream, &api_base).to_string()
+        }
+        _ => api_base,
+    };
+    let client = Client::with_config(OpenAIConfig::new().with_api_key(api_key).with_api_base(api_base));
+    with_headers(client, provider, provider_headers(provider))
+}
+
+
+/// `client`, sending `headers` with every request if there are any
+fn with_headers(client: Client<OpenAIConfig>, provider: Provider, headers: reqwest::header::HeaderMap) -> Result<Client<OpenAIConfig>, String> {
+    if headers.is_empty() {
+        return Ok(client);
+    }
+    let http_client = reqwest::Client::builder().default_headers(headers).build().map_err(|e| {
+        log::error!("Failed to build {} HTTP client: {}", provider.name(), e);
+        e.to_string()
+    })?;
+    Ok(client.with_http_client(http_client))
+}
+
+
+/// The headers sent with every request to `provider`: its gateway's, then the extra ones
+/// configured for it
+fn provider_headers(provider: Provider) -> reqwest::header::HeaderMap {
+    let headers = ConfigManager::new().and_then(|config_manager| {
+        let mut headers = config_manager.get_gateway(provider)?
+            .map(|route| gateways::headers(&route, provider))
+            .unwrap_or_default();
+        headers.extend(config_manager.get_provider_headers(provider)?);
+        Ok(headers)
+    });
+    match headers {
+        Ok(headers) => providers::header_map(&headers),
+        Err(e) => {
+            log::warn!("Failed to read {} headers, sending none: {}", provider.name(), e);
+            reqwest::header::HeaderMap::new()
+        }
+    }
+}
+
+/// The headers sent with every request for `model`: its provider's, and for a model of a
+/// compatible endpoint those registered with the endpoint
+fn model_headers(model: &str) -> reqwest::header::HeaderMap {
+    let (provider, _) = providers::resolve(model);
+    let mut headers = provider_headers(provider);
+    if provider == Provider::Endpoint {
+        match ConfigManager::new().and_then(|config_manager| config_manager.get_compatible_endpoints()) {
+            Ok(endpoints) => if let Some((endpoint, _)) = endpoints::resolve(&endpoints, model) {
+                headers.extend(providers::header_map(&endpoint.headers));
+            },
+            Err(e) => log::warn!("Failed to read headers of the endpoint serving {}, sending none: {}", model, e),
+        }
+    }
+    headers
+}
+
+
+/// A client for the Azure OpenAI deployment `model` is listed as
+fn azure_client(model: &str) -> Result<Client<async_openai::config::AzureConfig>, String> {
+    policy::check_provider(Provider::Azure.name())?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let profiles = config_manager.get_azure_profiles().map_err(|e| {
+        log::error!("Failed to get Azure OpenAI profiles: {}", e);
+        e.to_string()
+    })?;
+    let profile = azure::profile(&profiles, model)
+        .ok_or_else(|| tr!("azure-profile-unknown", profile = providers::resolve(model).1))?;
+    let (_, api_key) = provider_endpoint(&config_manager, Provider::Azure)?
+        .ok_or_else(|| tr!("no-provider-key", provider = Provider::Azure.name()))?;
+    Ok(Client::with_config(azure::config(profile, &api_key)))
+}
+
+
+/// `client` if it reaches the provider serving `model`, otherwise a client for that provider;
+/// a conversation's model, the fast model and the preferred one may each be another provider's.
+/// Only providers with a fixed API base can be told apart by it: Together's custom models and
+/// Ollama's configured server always get a fresh client.
+fn routed_client(client: &Client<OpenAIConfig>, model: &str) -> Result<Client<OpenAIConfig>, String> {
+    use async_openai::config::Config;
+    let provider = providers::resolve(model).0;
+    if matches!(provider, Provider::OpenAi | Provider::Anthropic) && client.config().api_base() == provider.api_base() {
+        return Ok(client.clone());
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    provider_client(&config_manager, model)
+}
+
+
+/// Saves the assistant message as the stream ends: complete on success, partial on error or
+/// when the consumer drops the stream mid-response (e.g. the window closed or the client cancelled)
+struct ResponseRecorder {
+    data_dir: std::path::PathBuf,
+    message_id: String,
+    text: String,
+    finished: bool,
+    failed: bool,
+    /// Provenance saved alongside the content, with timings and tool calls filled in
+    details: history::MessageDetails,
+    started: std::time::Instant,
+    tool_events: tokio::sync::broadcast::Receiver<AppEvent>,
+    /// Requests that went into the answer, priced once its length is known
+    requests: Vec<cost::PlannedRequest>,
+    /// Token counts the providers reported, one per request that finished
+    usage: Vec<cost::Usage>,
+    /// Characters already there when recording started, e.g. the part of a resumed answer
+    prefix_chars: usize,
+    /// Gathers the answer's token probabilities, when they were asked for
+    token_logprobs: Option<logprobs::Collector>,
+}
+
+impl ResponseRecorder {
+    fn new(data_dir: std::path::PathBuf, text: String, details: history::MessageDetails) -> Self {
+        let prefix_chars = text.chars().count();
+        ResponseRecorder {
+            data_dir,
+            message_id: details.message_id.clone(),
+            text,
+            finished: false,
+            failed: false,
+            details,
+            started: std::time::Instant::now(),
+            tool_events: events::subscribe(),
+            requests: Vec::new(),
+            usage: Vec::new(),
+            prefix_chars,
+            token_logprobs: None,
+        }
+    }
+
+    fn with_requests(mut self, requests: Vec<cost::PlannedRequest>) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    fn with_token_logprobs(mut self, collector: Option<logprobs::Collector>) -> Self {
+        self.token_logprobs = collector;
+        self
+    }
+
+    fn first_token(&mut self) {
+        if self.details.first_token_ms.is_none() {
+            self.details.first_token_ms = Some(self.started.elapsed().as_millis() as u32);
+        }
+    }
+
+    /// How fast the answer was generated once it started, from its estimated token count
+    fn tokens_per_second(&self) -> Option<f32> {
+        let answer: String = self.text.chars().skip(self.prefix_chars).collect();
+        benchmark::tokens_per_second(context::estimate_tokens(&answer) as u32, self.details.first_token_ms?, self.details.latency_ms?)
+    }
+
+    fn save(&mut self, status: MessageStatus) {
+        self.finished = true;
+        while let Ok(event) = self.tool_events.try_recv() {
+            if let AppEvent::ToolCalled { plugin, tool, success } = event {
+                self.details.tool_calls.push(history::ToolCallRecord { plugin, tool, success });
+            }
+        }
+        self.details.latency_ms = Some(self.started.elapsed().as_millis() as u32);
+        if !self.requests.is_empty() {
+            let answer: String = self.text.chars().skip(self.prefix_chars).collect();
+            self.details.estimated_usd = cost::spent(&self.requests, context::estimate_tokens(&answer) as u32);
+        }
+        if !self.usage.is_empty() {
+            self.details.prompt_tokens = Some(self.usage.iter().map(|usage| usage.prompt_tokens).sum());
+            self.details.completion_tokens = Some(self.usage.iter().map(|usage| usage.completion_tokens).sum());
+            // A request that reported nothing, like a race's dropped loser, leaves the cost unknown
+            self.details.actual_usd = if self.usage.len() >= self.requests.len() { cost::charged(&self.usage) } else { None };
+        }
+        if let Some(collector) = &self.token_logprobs {
+            self.details.token_logprobs.extend(collector.take());
+        }
+
+        // Content and stats go in one commit; nothing is written per chunk while streaming
+        let result = history::HistoryStore::open(&self.data_dir).and_then(|store| {
+            store.batch(|store| {
+                store.update_content(&self.message_id, &self.text, status)?;
+                store.save_details(&self.details)
+            })
+        });
+        if let Err(e) = result {
+            log::error!("Failed to save response {} to history: {}", self.message_id, e);
+        }
+    }
+}
+
+impl Drop for ResponseRecorder {
+    fn drop(&mut self) {
+        if !self.finished {
+            log::warn!("Stream for {} ended early, keeping partial response", self.message_id);
+            self.save(MessageStatus::Partial);
+        }
+    }
+}
+
+
+type ProviderStream = Pin<Box<dyn Stream<Item = Result<
+    async_openai::types::CreateChatCompletionStreamResponse,
+    async_openai::error::OpenAIError,
+>> + Send>>;
+
+/// Start the provider stream and wait for the first chunk, so request-level rejections
+/// (bad key, context overflow) surface as an error here rather than mid-stream
+async fn open_provider_stream(
+    client: &Client<OpenAIConfig>,
+    request: async_openai::types::CreateChatCompletionRequest,
+) -> Result<ProviderStream, String> {
+    let provider = providers::resolve(&request.model).0;
+    // Azure and Bedrock are streamed through clients of their own, below
+    let client = if matches!(provider, Provider::Azure | Provider::Bedrock) { client.clone() } else { routed_client(client, &request.model)? };
+    let (mut span, client) = provider_span(&client, &request.model);
+    if let Some(temperature) = request.temperature {
+        span.set("gen_ai.request.temperature", f64::from(temperature));
+    }
+    let completion_only = completions::is_completion_only(&request.model);
+    let model = request.model.clone();
+    let request = providers::upstream_request(request);
+    let started = std::time::Instant::now();
+    let opened = if matches!(provider, Provider::Anthropic | Provider::Ollama) {
+        let endpoint = ConfigManager::new()
+            .map_err(|e| e.to_string())
+            .and_then(|config_manager| provider_endpoint(&config_manager, provider))
+            .and_then(|endpoint| endpoint.ok_or_else(|| tr!("no-provider-key", provider = provider.name())));
+        let http_client = telemetry::http_client(&span, provider_headers(provider)).unwrap_or_default();
+        match endpoint {
+            Ok((api_base, api_key)) if provider == Provider::Anthropic => anthropic::create_stream(http_client, &api_base, &api_key, &request).await,
+            Ok((api_base, _)) => {
+                let keep_alive = ConfigManager::new().and_then(|config_manager| config_manager.get_ollama_keep_alive()).unwrap_or_else(|e| {
+                    log::warn!("Failed to get Ollama keep-alive: {}", e);
+                    None
+                });
+                ollama::create_stream(http_client, &api_base, &request, keep_alive.as_deref()).await
+            }
+            Err(e) => {
+                span.fail(e.clone());
+                return Err(e);
+            }
+        }
+    } else if provider == Provider::Azure {
+        match azure_client(&model) {
+            Ok(azure) => sse::create_chat_stream(telemetry::http_client(&span, provider_headers(provider)).unwrap_or_default(), azure.config(), &request).await,
+            Err(e) => {
+                span.fail(e.clone());
+                return Err(e);
+            }
+        }
+    } else if provider == Provider::Bedrock {
+        let credentials = policy::check_provider(provider.name())
+            .and_then(|()| ConfigManager::new().map_err(|e| e.to_string()))
+            .and_then(|config_manager| bedrock_credentials(&config_manager))
+            .and_then(|credentials| credentials.ok_or_else(|| tr!("no-provider-key", provider = provider.name())));
+        match credentials {
+            Ok(credentials) => bedrock::create_stream(telemetry::http_client(&span, provider_headers(provider)).unwrap_or_default(), &credentials, &request).await,
+            Err(e) => {
+                span.fail(e.clone());
+                return Err(e);
+            }
+        }
+    } else if completion_only {
+        span.set("gen_ai.operation.name", "text_completion");
+        match completions::completion_request(&request) {
+            Ok(request) => client.completions().create_stream(request).await
+                .map(|stream| -> ProviderStream {
+                    Box::pin(stream.map(|chunk| chunk.and_then(completions::as_chat_chunk)))
+                }),
+            Err(e) => Err(e),
+        }
+    } else {
+        sse::create_chat_stream(telemetry::http_client(&span, model_headers(&model)).unwrap_or_default(), client.config(), &request).await
+    };
+    let mut stream = match opened {
+        Ok(stream) => stream,
+        Err(e) => {
+            status::record_provider_error(&e.to_string());
+            span.fail(e.to_string());
+            return Err(e.to_string());
+        }
+    };
+    if let Some(transcript) = stream_transcript(&model) {
+        stream = stream_recording::record(stream, transcript);
+    }
+    // Usage is priced by the id the model is listed under, not the one the provider echoes
+    let listed = model.clone();
+    let mut stream: ProviderStream = Box::pin(stream.map(move |chunk| chunk.map(|mut chunk| {
+        if chunk.usage.is_some() {
+            chunk.model = listed.clone();
+        }
+        chunk
+    })));
+    match stream.next().await {
+        Some(Err(e)) => {
+            status::record_provider_error(&e.to_string());
+            span.fail(e.to_string());
+            Err(e.to_string())
+        }
+        Some(Ok(first)) => {
+            span.set("switchboard.first_chunk_ms", started.elapsed().as_millis() as i64);
+            let stream = futures::stream::once(async { Ok(first) }).chain(stream);
+            Ok(Box::pin(telemetry::traced(stream, span)))
+        }
+        None => Ok(Box::pin(telemetry::traced(stream, span))),
+    }
+}
+
+
+/// A new transcript for a stream from `model`, while stream recording is on
+fn stream_transcript(model: &str) -> Option<std::path::PathBuf> {
+    let config_manager = ConfigManager::new().ok()?;
+    if !config_manager.get_record_streams().unwrap_or(false) {
+        return None;
+    }
+    stream_recording::start(config_manager.get_data_dir(), model)
+        .inspect_err(|e| log::warn!("Failed to start stream transcript: {}", e))
+        .ok()
+}
+
+
+/// A span for a chat request to `model`, and a client that passes its trace context on
+fn provider_span(client: &Client<OpenAIConfig>, model: &str) -> (telemetry::Span, Client<OpenAIConfig>) {
+    let mut span = telemetry::Span::start(format!("chat {}", model), telemetry::SpanKind::Client);
+    span.set("gen_ai.operation.name", "chat");
+    span.set("gen_ai.system", providers::resolve(model).0.name());
+    span.set("gen_ai.request.model", model.to_string());
+    let client = match telemetry::http_client(&span, model_headers(model)) {
+        Some(http_client) => client.clone().with_http_client(http_client),
+        None => client.clone(),
+    };
+    (span, client)
+}
+
+
+/// Transform the OpenAI stream into our StreamMessage enum
+fn provider_messages(openai_stream: ProviderStream) -> MessageStream {
+    Box::pin(openai_stream.flat_map(|result| {
+        let messages = match result {
+            Ok(response) => {
+                // Empty content chunks are passed on empty and skipped downstream
+                let content = response.choices.first()
+                    .and_then(|choice| choice.delta.content.clone())
+                    .unwrap_or_default();
+                let usage = response.usage.map(|usage| StreamMessage::Usage {
+                    model: response.model,
+                    prompt_tokens: usage.prompt_tokens,
+                    completion_tokens: usage.completion_tokens,
+                });
+                [Some(StreamMessage::Content(content)), usage]
+            }
+            Err(e) => {
+                status::record_provider_error(&e.to_string());
+                [Some(StreamMessage::Error(e.to_string())), None]
+            }
+        };
+        futures::stream::iter(messages.into_iter().flatten())
+    }))
+}
+
+
+/// Messages from a stream that is only opened when first polled; an open failure becomes an Error
+fn lazy_provider_messages(
+    open: impl std::future::Future<Output = Result<ProviderStream, String>> + Send + 'static,
+) -> MessageStream {
+    lazy_messages(async move { open.await.map(provider_messages) })
+}
+
+
+/// Like `lazy_provider_messages`, for streams already turned into messages
+fn lazy_messages(
+    open: impl std::future::Future<Output = Result<MessageStream, String>> + Send + 'static,
+) -> MessageStream {
+    Box::pin(futures::stream::once(open).flat_map(|result| -> MessageStream {
+        match result {
+            Ok(stream) => stream,
+            Err(e) => Box::pin(futures::stream::once(async move { StreamMessage::Error(e) })),
+        }
+    }))
+}
+
+
+fn track_stream(
+    message_stream: MessageStream,
+    model: String,
+    conversation_id: String,
+    prompt: String,
+    recorder: ResponseRecorder,
+) -> MessageStream {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let message_id = recorder.message_id.clone();
+    let filters = history::HistoryStore::open(&recorder.data_dir)
+        .and_then(|store| store.conversation_filters(&conversation_id))
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to load conversation filters, streaming unfiltered: {}", e);
+            ConversationFilters::default()
+        });
+    events::publish(AppEvent::ChatStarted {
+        request_id: request_id.clone(),
+        model: model.clone(),
+        conversation_id: conversation_id.clone(),
+        message_id: message_id.clone(),
+    });
+    accessibility::announce(&message_id, MessageRole::Assistant, MessageBoundary::Start, CompletionStatus::Streaming, tr!("a11y-response-started", model = model.as_str()));
+
+    let accessibility_settings = ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_accessibility_settings())
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read accessibility settings, streaming token by token: {}", e);
+            AccessibilitySettings::default()
+        });
+    let plain_stream = accessibility_settings.plain_stream;
+    // Sentences are queued for speaking as they complete, rather than once the answer is done
+    let reader = accessibility_settings.read_aloud.then(|| {
+        read_aloud::begin(&message_id);
+        std::sync::Arc::new(std::sync::Mutex::new(read_aloud::SentenceSplitter::default()))
+    });
+    let finishing_reader = reader.clone();
+
+    let responding = modes::Responding::start();
+
+    // Stop sequences and content filters see the raw chunks, so they can cut between any two characters
+    let (message_stream, truncation) = if filters.is_empty() {
+        (message_stream, stream_filters::Truncation::default())
+    } else {
+        stream_filters::apply(message_stream, &filters)
+    };
+
+    // Publish chunks on the event bus and collect the response for history and on_response_complete hooks.
+    // Chunks are first re-cut so none ends inside a grapheme cluster or word.
+    let message_stream = segmentation::coalesce(message_stream);
+    let message_stream = if plain_stream { accessibility::whole_message(message_stream) } else { message_stream };
+    let recorder = std::sync::Arc::new(std::sync::Mutex::new(recorder));
+    let collector = recorder.clone();
+    let stream_request_id = request_id.clone();
+    let stream_message_id = message_id.clone();
+    let message_stream = message_stream.inspect(move |message| {
+        match message {
+            StreamMessage::Content(content) if !content.is_empty() => {
+                if let Ok(mut recorder) = collector.lock() {
+                    recorder.first_token();
+                    recorder.text.push_str(content);
+                }
+                events::publish(AppEvent::ChatContent {
+                    request_id: stream_request_id.clone(),
+                    content: content.clone(),
+                });
+                if let Some(Ok(mut splitter)) = reader.as_ref().map(|reader| reader.lock()) {
+                    read_aloud::push(&stream_message_id, splitter.push(content));
+                }
+            }
+            StreamMessage::Replace(text) => {
+                if let Ok(mut recorder) = collector.lock() {
+                    recorder.text = text.clone();
+                }
+                if let Some(Ok(mut splitter)) = reader.as_ref().map(|reader| reader.lock()) {
+                    // The replacement is read from its start
+                    splitter.clear();
+                    read_aloud::begin(&stream_message_id);
+                    read_aloud::push(&stream_message_id, splitter.push(text));
+                }
+                accessibility::announce(&stream_message_id, MessageRole::Assistant, MessageBoundary::Start, CompletionStatus::Replaced, tr!("a11y-response-replaced"));
+            }
+            StreamMessage::Usage { model, prompt_tokens, completion_tokens } => {
+                if let Ok(mut recorder) = collector.lock() {
+                    recorder.usage.push(cost::Usage { model: model.clone(), prompt_tokens: *prompt_tokens, completion_tokens: *completion_tokens });
+                }
+            }
+            // Saved as answered by the provider that actually answered
+            StreamMessage::ProviderSwitched { to, model, .. } => {
+                if let Ok(mut recorder) = collector.lock() {
+                    recorder.details.provider = to.clone();
+                    recorder.details.model = model.clone();
+                }
+            }
+            StreamMessage::Error(error) => {
+                if let Ok(mut recorder) = collector.lock() {
+                    recorder.failed = true;
+                    recorder.save(MessageStatus::Partial);
+                }
+                events::publish(AppEvent::ChatError {
+                    request_id: stream_request_id.clone(),
+                    error: error.clone(),
+                });
+                accessibility::announce(&stream_message_id, MessageRole::Assistant, MessageBoundary::End, CompletionStatus::Failed, tr!("a11y-response-failed", error = error.as_str()));
+            }
+            _ => {}
+        }
+    }).chain(futures::stream::once(async move {
+        let _responding = responding;
+        let Ok(mut recorder) = recorder.lock() else {
+            return StreamMessage::Complete;
+        };
+        if recorder.failed {
+            return StreamMessage::Complete;
+        }
+        recorder.details.tags = scripting::on_response_complete(&prompt, &recorder.text);
+        if !recorder.details.tags.is_empty() {
+            log::info!("Scripts tagged response {}: {:?}", message_id, recorder.details.tags);
+        }
+        match truncation.get() {
+            Some(reason) => {
+                recorder.details.truncated_by = Some(reason.clone());
+                recorder.save(MessageStatus::Truncated);
+                events::publish(AppEvent::ResponseTruncated { message_id: message_id.clone(), truncated_by: reason.clone() });
+            }
+            None => recorder.save(MessageStatus::Complete),
+        }
+        count_unread(&recorder.data_dir, &conversation_id);
+        if let Some(Ok(mut splitter)) = finishing_reader.as_ref().map(|reader| reader.lock()) {
+            read_aloud::push(&message_id, splitter.finish().into_iter().collect());
+        }
+        events::publish(AppEvent::ChatCompleted {
+            request_id,
+            // The fallback's, if the answer came from one
+            model: recorder.details.model.clone(),
+            response_chars: recorder.text.chars().count() as u32,
+            tokens_per_second: recorder.tokens_per_second(),
+        });
+        accessibility::announce(&message_id, MessageRole::Assistant, MessageBoundary::End, CompletionStatus::Complete, tr!("a11y-response-complete"));
+        StreamMessage::Complete
+    }));
+
+    Box::pin(message_stream)
+}
+
+
+/// Usage statistics over `period` for the insights dashboard. Topics are found by clustering
+/// prompt embeddings and are left out when the provider can't be reached; prompts from locked
+/// conversations are never sent for them.
+pub async fn get_history_insights(period: InsightsPeriod) -> Result<HistoryInsights, String> {
+    log::info!("Computing history insights for {:?}", period);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let now = chrono::Utc::now();
+    let since = period.start(now).map(|start| start.to_rfc3339()).unwrap_or_default();
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let (messages, spent, locked) = store.messages_since(&since)
+        .and_then(|messages| Ok((messages, store.spent_since(&since)?, store.locked_conversations()?)))
+        .map_err(|e| {
+            log::error!("Failed to load history for insights: {}", e);
+            e.to_string()
+        })?;
+    drop(store);
+
+    let mut result = insights::summarize(period, &messages, now);
+    result.estimated_usd = spent;
+
+    let locked: std::collections::HashSet<String> = locked.into_iter().map(|(id, _)| id).collect();
+    let prompts = insights::topic_prompts(&messages, |conversation_id| {
+        !locked.contains(conversation_id) || conversation_lock::is_open(conversation_id)
+    });
+    if prompts.len() >= 2 {
+        let redact = config_manager.get_redaction_enabled().map_err(|e| {
+            log::error!("Failed to get redaction setting: {}", e);
+            e.to_string()
+        })?;
+        let texts = prompts.iter()
+            .map(|prompt| insights::topic_text(prompt))
+            .map(|text| if redact { redaction::redact(&text) } else { text })
+            .collect();
+        match streaming_client(&config_manager) {
+            Ok((client, _)) => match dedup::prompt_embeddings(&client, config_manager.get_data_dir(), &prompts, texts).await {
+                Ok(embeddings) => result.topics = insights::topics(&prompts, &embeddings),
+                Err(e) => log::warn!("Embedding failed, leaving topics out of insights: {}", e),
+            },
+            Err(e) => log::info!("No provider for topic embeddings, leaving topics out: {}", e),
+        }
+    }
+    Ok(result)
+}
+
+
+/// Conversations that start with the same prompt, e.g. from importing the same export twice.
+/// First prompts are compared by embedding, or by exact text when the provider can't be
+/// reached; locked conversations are left out until unlocked.
+pub async fn find_duplicate_conversations() -> Result<Vec<DuplicateConversations>, String> {
+    log::info!("Looking for duplicate conversations");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let (openers, locked) = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| Ok((store.conversation_openers()?, store.locked_conversations()?)))
+        .map_err(|e| {
+            log::error!("Failed to load conversations: {}", e);
+            e.to_string()
+        })?;
+    let locked: std::collections::HashSet<String> = locked.into_iter().map(|(id, _)| id).collect();
+    let openers: Vec<HistoryMessage> = openers.into_iter()
+        .filter(|opener| !locked.contains(&opener.conversation_id) || conversation_lock::is_open(&opener.conversation_id))
+        .collect();
+    if openers.len() < 2 {
+        return Ok(Vec::new());
+    }
+
+    let redact = config_manager.get_redaction_enabled().map_err(|e| {
+        log::error!("Failed to get redaction setting: {}", e);
+        e.to_string()
+    })?;
+    let texts = openers.iter()
+        .map(duplicates::opener_text)
+        .map(|text| if redact { redaction::redact(&text) } else { text })
+        .collect();
+    let opener_refs: Vec<&HistoryMessage> = openers.iter().collect();
+    let groups = match streaming_client(&config_manager) {
+        Ok((client, _)) => match dedup::prompt_embeddings(&client, config_manager.get_data_dir(), &opener_refs, texts).await {
+            Ok(embeddings) => dedup::group_by_similarity(&embeddings, dedup::DUPLICATE_SIMILARITY),
+            Err(e) => {
+                log::warn!("Embedding failed, matching exact first prompts only: {}", e);
+                duplicates::group_by_text(&openers)
+            }
+        },
+        Err(_) => duplicates::group_by_text(&openers),
+    };
+    let found = duplicates::duplicates(&openers, groups);
+    log::info!("Found {} sets of duplicate conversations", found.len());
+    Ok(found)
+}
+
+
+/// Merge conversations into the first of `conversation_ids`. Messages interleave by timestamp,
+/// each keeping a record of the conversation it came from (see `list_message_origins`), and
+/// transcripts, captures, attachments and locks move along with them.
+pub async fn merge_conversations(conversation_ids: Vec<String>) -> Result<MergeSummary, String> {
+    let mut ids: Vec<String> = Vec::new();
+    for id in conversation_ids {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.len() < 2 {
+        return Err(tr!("merge-needs-two"));
+    }
+    log::info!("Merging conversations {:?}", ids);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    for id in &ids {
+        require_unlocked(&config_manager, id)?;
+    }
+    let (into, from) = (ids[0].clone(), ids[1..].to_vec());
+    let data_dir = config_manager.get_data_dir();
+    let (messages_moved, duplicates_dropped) = history::HistoryStore::open(data_dir)
+        .and_then(|mut store| store.merge_conversations(&into, &from))
+        .map_err(|e| {
+            log::error!("Failed to merge conversations: {}", e);
+            e.to_string()
+        })?;
+
+    let (mut transcripts_moved, mut captures_moved) = (0, 0);
+    for source in &from {
+        let moved = transcription::TranscriptStore::new(data_dir).reassign(source, &into)
+            .and_then(|transcripts| Ok((transcripts, screen_capture::CaptureStore::new(data_dir).reassign(source, &into)?)));
+        match moved {
+            Ok((transcripts, captures)) => {
+                transcripts_moved += transcripts;
+                captures_moved += captures;
+            }
+            Err(e) => log::error!("Failed to move media from conversation {}: {}", source, e),
+        }
+    }
+    let summary = MergeSummary { conversation_id: into, messages_moved, duplicates_dropped, transcripts_moved, captures_moved };
+    log::info!(
+        "Merged {} conversations: {} messages moved, {} duplicates dropped",
+        from.len(), summary.messages_moved, summary.duplicates_dropped
+    );
+    events::publish(AppEvent::ConversationsMerged { summary: summary.clone(), merged: from });
+    status::refresh();
+    Ok(summary)
+}
+
+
+/// Which conversation each merged message in `conversation_id` originally belonged to
+pub async fn list_message_origins(conversation_id: String) -> Result<Vec<MessageOrigin>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.message_origins(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to list message origins: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Save how `conversation_id` began as a starter named `name`: its first message, the system
+/// prompt and model it was answered with, and its attachments. A starter of the same name is replaced.
+pub async fn save_as_starter(conversation_id: String, name: String) -> Result<ConversationStarter, String> {
+    let name = starters::starter_name(&name)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    let mut store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let messages = store.list(&conversation_id).map_err(|e| {
+        log::error!("Failed to load conversation history: {}", e);
+        e.to_string()
+    })?;
+    let first_message = messages.iter()
+        .find(|message| message.role == "user")
+        .map(|message| message.content.clone())
+        .ok_or_else(|| tr!("starter-empty-conversation"))?;
+    let first_response = messages.iter().find(|message| message.role == "assistant");
+
+    let setup = store.conversation_setup(&conversation_id).map_err(|e| {
+        log::error!("Failed to load conversation setup: {}", e);
+        e.to_string()
+    })?;
+    let system_prompt = match &setup {
+        Some(setup) => setup.system_prompt.clone(),
+        None => {
+            let details = match first_response {
+                Some(response) => store.details(&response.id).map_err(|e| {
+                    log::error!("Failed to load message details: {}", e);
+                    e.to_string()
+                })?,
+                None => None,
+            };
+            match details.as_ref().and_then(starters::persona_in) {
+                Some(persona) => persona_prompt(&config_manager, persona)?,
+                None => None,
+            }
+        }
+    };
+    // The demo model only exists in demo mode, so a starter saved there uses the preferred model
+    let model = setup.and_then(|setup| setup.model)
+        .or_else(|| first_response.and_then(|response| response.model.clone()))
+        .filter(|model| model != demo::MODEL);
+    let attachment_ids = store.conversation_attachment_ids(&conversation_id).map_err(|e| {
+        log::error!("Failed to list conversation attachments: {}", e);
+        e.to_string()
+    })?;
+
+    let starter = ConversationStarter {
+        name,
+        system_prompt,
+        first_message,
+        model,
+        attachment_ids,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    store.save_starter(&starter).map_err(|e| {
+        log::error!("Failed to save starter: {}", e);
+        e.to_string()
+    })?;
+    log::info!("Saved conversation {} as starter {}", conversation_id, starter.name);
+    Ok(starter)
+}
+
+
+pub async fn list_starters() -> Result<Vec<ConversationStarter>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_starters())
+        .map_err(|e| {
+            log::error!("Failed to list starters: {}", e);
+            e.to_string()
+        })
+}
+
+
+pub async fn delete_starter(name: String) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let deleted = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|mut store| store.delete_starter(&name))
+        .map_err(|e| {
+            log::error!("Failed to delete starter: {}", e);
+            e.to_string()
+        })?;
+    if !deleted {
+        return Err(tr!("starter-not-found", name = name.as_str()));
+    }
+    log::info!("Deleted starter {}", name);
+    Ok(())
+}
+
+
+/// Start a new conversation from the starter `name`. Its system prompt and model apply to every
+/// message sent in the conversation; the first message is returned for the UI to send.
+pub async fn new_from_starter(name: String) -> Result<StartedConversation, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir();
+    let mut store = history::HistoryStore::open(data_dir).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let starter = store.starter(&name)
+        .map_err(|e| {
+            log::error!("Failed to load starter: {}", e);
+            e.to_string()
+        })?
+        .ok_or_else(|| tr!("starter-not-found", name = name.as_str()))?;
+
+    let attachment_store = storage::AttachmentStore::new(data_dir);
+    let mut attachments = Vec::new();
+    for id in &starter.attachment_ids {
+        match attachment_store.get(&store, id).map_err(|e| e.to_string())? {
+            Some(attachment) => attachments.push(attachment),
+            None => log::warn!("Starter {} refers to attachment {}, which no longer exists", name, id),
+        }
+    }
+    let attachment_ids: Vec<String> = attachments.iter().map(|attachment| attachment.id.clone()).collect();
+
+    let conversation_id = uuid::Uuid::new_v4().to_string();
+    store.start_conversation(&conversation_id, &starter, &attachment_ids).map_err(|e| {
+        log::error!("Failed to start conversation from starter: {}", e);
+        e.to_string()
+    })?;
+    log::info!("Started conversation {} from starter {}", conversation_id, name);
+    Ok(StartedConversation {
+        conversation_id,
+        first_message: starter.first_message,
+        attachments,
+    })
+}
+
+
+/// Err while `conversation_id` is locked and hasn't been unlocked this session
+fn require_unlocked(config_manager: &ConfigManager, conversation_id: &str) -> Result<(), String> {
+    let locked = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.is_locked(conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to check conversation lock: {}", e);
+            e.to_string()
+        })?;
+    if locked && !conversation_lock::is_open(conversation_id) {
+        log::warn!("Refused access to locked conversation {}", conversation_id);
+        return Err(tr!("conversation-locked"));
+    }
+    Ok(())
+}
+
+
+/// Whether `conversation_id` is locked and hasn't been unlocked this session; true if that
+/// can't be checked
+pub(crate) fn conversation_hidden(conversation_id: &str) -> bool {
+    let locked = ConfigManager::new()
+        .and_then(|config_manager| history::HistoryStore::open(config_manager.get_data_dir()))
+        .and_then(|store| store.is_locked(conversation_id));
+    match locked {
+        Ok(locked) => locked && !conversation_lock::is_open(conversation_id),
+        Err(e) => {
+            log::error!("Failed to check conversation lock: {}", e);
+            true
+        }
+    }
+}
+
+/// `require_unlocked` for the conversation `message_id` belongs to
+fn require_message_unlocked(config_manager: &ConfigManager, message_id: &str) -> Result<(), String> {
+    let message = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.get(message_id))
+        .map_err(|e| {
+            log::error!("Failed to load message: {}", e);
+            e.to_string()
+        })?;
+    match message {
+        Some(message) => require_unlocked(config_manager, &message.conversation_id),
+        None => Ok(()),
+    }
+}
+
+
+/// Tell the core which conversation is on screen, or None when the window loses focus or no
+/// conversation is open. Focusing a conversation reads it.
+pub async fn set_focused_conversation(conversation_id: Option<String>) -> Result<(), String> {
+    log::debug!("Focused conversation is now {:?}", conversation_id);
+    if let Ok(mut focused) = FOCUSED_CONVERSATION.lock() {
+        focused.clone_from(&conversation_id);
+    }
+    match conversation_id {
+        Some(conversation_id) => mark_read(conversation_id).await,
+        None => Ok(()),
+    }
+}
+
+
+/// Clear a conversation's unread answers
+pub async fn mark_read(conversation_id: String) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let was_unread = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.mark_read(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to mark conversation {} read: {}", conversation_id, e);
+            e.to_string()
+        })?;
+    if was_unread > 0 {
+        events::publish(AppEvent::UnreadChanged { conversation_id, unread: 0 });
+    }
+    Ok(())
+}
+
+
+pub async fn get_unread_conversations() -> Result<Vec<UnreadConversation>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.unread_conversations())
+        .map_err(|e| {
+            log::error!("Failed to get unread conversations: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Unread answers across every conversation, for the tray and status bar badge
+pub async fn get_unread_count() -> Result<u32, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.unread_total())
+        .map_err(|e| {
+            log::error!("Failed to count unread answers: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Count an answer completed in `conversation_id` as unread, unless the conversation is on screen
+fn count_unread(data_dir: &std::path::Path, conversation_id: &str) {
+    if FOCUSED_CONVERSATION.lock().is_ok_and(|focused| focused.as_deref() == Some(conversation_id)) {
+        return;
+    }
+    let counted = history::HistoryStore::open(data_dir).and_then(|store| {
+        store.mark_unread(conversation_id)?;
+        store.unread_count(conversation_id)
+    });
+    match counted {
+        Ok(unread) => events::publish(AppEvent::UnreadChanged { conversation_id: conversation_id.to_string(), unread }),
+        Err(e) => log::warn!("Failed to count answer in {} as unread: {}", conversation_id, e),
+    }
+}
+
+
+/// Lock a conversation behind OS authentication. It's locked straight away, even if it was
+/// unlocked this session.
+pub async fn lock_conversation(conversation_id: String) -> Result<(), String> {
+    log::info!("Locking conversation {}", conversation_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.lock_conversation(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to lock conversation: {}", e);
+            e.to_string()
+        })?;
+    conversation_lock::close(&conversation_id);
+    events::publish(AppEvent::ConversationLockChanged { conversation_id, locked: true });
+    Ok(())
+}
+
+
+/// Make a locked conversation readable for this session, asking the OS to authenticate the user
+/// unless they did so within the quick-unlock window
+pub async fn unlock_conversation(conversation_id: String) -> Result<(), String> {
+    if conversation_lock::is_open(&conversation_id) {
+        return Ok(());
+    }
+    if conversation_lock::recently_authenticated() {
+        log::info!("Quick-unlocking conversation {}", conversation_id);
+        conversation_lock::open(&conversation_id, false);
+        return Ok(());
+    }
+    let reason = tr!("conversation-unlock-reason");
+    tokio::task::spawn_blocking(move || conversation_lock::authenticate(&reason))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    conversation_lock::open(&conversation_id, true);
+    Ok(())
+}
+
+
+/// Take the lock off a conversation for good, which needs the same authentication as unlocking
+pub async fn remove_conversation_lock(conversation_id: String) -> Result<(), String> {
+    unlock_conversation(conversation_id.clone()).await?;
+    log::info!("Removing lock from conversation {}", conversation_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.unlock_conversation(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to remove conversation lock: {}", e);
+            e.to_string()
+        })?;
+    events::publish(AppEvent::ConversationLockChanged { conversation_id, locked: false });
+    Ok(())
+}
+
+
+/// Lock every unlocked conversation again now, e.g. when the window is hidden
+pub async fn relock_conversations() -> Result<(), String> {
+    log::info!("Relocking all conversations");
+    conversation_lock::close_all();
+    Ok(())
+}
+
+
+pub async fn list_locked_conversations() -> Result<Vec<LockedConversation>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let locks = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.locked_conversations())
+        .map_err(|e| {
+            log::error!("Failed to list locked conversations: {}", e);
+            e.to_string()
+        })?;
+    Ok(locks.into_iter()
+        .map(|(conversation_id, locked_at)| LockedConversation {
+            unlocked: conversation_lock::is_open(&conversation_id),
+            conversation_id,
+            locked_at,
+        })
+        .collect())
+}
+
+
+pub async fn list_conversation_messages(conversation_id: String) -> Result<Vec<HistoryMessage>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list(&conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to list conversation messages: {}", e);
+            e.to_string()
+        })
+}
+
+
+
+/// Write a conversation to `path` as Markdown or JSON, streaming message by message. Progress is
+/// published as ExportStarted/ExportProgress events; `cancel_export` stops it.
+pub async fn export_conversation(conversation_id: String, path: String, format: ExportFormat) -> Result<ExportSummary, String> {
+    log::info!("Exporting conversation {} to {} ({:?})", conversation_id, path, format);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    let data_dir = config_manager.get_data_dir().clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let store = history::HistoryStore::open(&data_dir)?;
+        export::export(&store, &conversation_id, std::path::Path::new(&path), format)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    status::refresh();
+    result.map_err(|e| {
+        log::error!("Export failed: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn cancel_export(export_id: String) -> Result<(), String> {
+    log::info!("Cancelling export {}", export_id);
+    export::cancel(&export_id).map_err(|e| e.to_string())
+}
+
+/// Heap, SQLite and per-cache memory use. Allocator totals are only filled in when built with
+/// the `diagnostics` feature.
+pub async fn get_memory_stats() -> Result<MemoryStats, String> {
+    Ok(diagnostics::memory_stats())
+}
+
+
+/// Timing and payload sizes of every IPC command called since startup, slowest in total first
+pub async fn get_ipc_stats() -> Result<Vec<IpcCommandStats>, String> {
+    Ok(metrics::ipc_stats())
+}
+
+
+pub async fn get_build_info() -> Result<BuildInfo, String> {
+    let build_info = BuildInfo::load().map_err(|e| e.to_string())?;
+    Ok(build_info)
+}
+
+
+pub async fn ingest_media_file(path: String, conversation_id: String) -> Result<Transcript, String> {
+    log::info!("Ingesting media file {} for conversation {}", path, conversation_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let api_key = config_manager.get_api_key().map_err(|e| {
+        log::error!("Failed to get API key: {}", e);
+        e.to_string()
+    })?.ok_or_else(|| {
+        log::error!("No API key configured");
+        tr!("no-api-key")
+    })?;
+
+    let mut transcript = transcription::transcribe_file(api_key, std::path::Path::new(&path), &conversation_id)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to transcribe {}: {}", path, e);
+            e.to_string()
+        })?;
+
+    let data_dir = config_manager.get_data_dir().clone();
+    let kind = if transcript.media_kind == "video" { storage::AttachmentKind::Video } else { storage::AttachmentKind::Audio };
+    let (source, conversation) = (path.clone(), conversation_id.clone());
+    let attachment = tokio::task::spawn_blocking(move || {
+        let store = history::HistoryStore::open(&data_dir)?;
+        storage::AttachmentStore::new(&data_dir).import(&store, std::path::Path::new(&source), &conversation, kind)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| {
+        log::error!("Failed to store {}: {}", path, e);
+        e.to_string()
+    })?;
+    transcript.attachment_id = Some(attachment.id);
+
+    transcription::TranscriptStore::new(config_manager.get_data_dir())
+        .save(&transcript)
+        .map_err(|e| {
+            log::error!("Failed to save transcript: {}", e);
+            e.to_string()
+        })?;
+
+    log::info!("Transcribed {} into {} segments", path, transcript.segments.len());
+    Ok(transcript)
+}
+
+
+pub async fn list_transcripts(conversation_id: String) -> Result<Vec<Transcript>, String> {
+    log::info!("Listing transcripts for conversation {}", conversation_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    transcription::TranscriptStore::new(config_manager.get_data_dir())
+        .list(&conversation_id)
+        .map_err(|e| {
+            log::error!("Failed to list transcripts: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Timestamped transcript text for a conversation, for summarization and Q&A prompts
+pub async fn get_transcript_context(conversation_id: String) -> Result<String, String> {
+    let compression = get_conversation_compression(conversation_id.clone()).await?;
+    let transcripts = list_transcripts(conversation_id.clone()).await?;
+    let chunks: Vec<_> = transcripts.iter().flat_map(dedup::transcript_chunks).collect();
+
+    // The same recording ingested twice shouldn't cost the context budget twice
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let before = chunks.len();
+    let chunks = match streaming_client(&config_manager) {
+        Ok((client, _)) => dedup::deduplicate(&client, chunks).await,
+        Err(_) => chunks,
+    };
+    if chunks.len() < before {
+        events::publish(AppEvent::ContextDeduplicated {
+            conversation_id,
+            dropped_chunks: (before - chunks.len()) as u32,
+        });
+    }
+
+    let context = dedup::render(&chunks);
+    if compression.enabled {
+        return Ok(compression::compress(&context, compression.ratio));
+    }
+    Ok(context)
+}
+
+
+pub async fn capture_screen_region(
+    conversation_id: String,
+    region: Option<CaptureRegion>,
+    analysis: CaptureAnalysis,
+) -> Result<ScreenCapture, String> {
+    log::info!("Capturing screen for conversation {}", conversation_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let store = screen_capture::CaptureStore::new(config_manager.get_data_dir());
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let image_path = store.image_path(&conversation_id, &id).map_err(|e| {
+        log::error!("Failed to prepare capture directory: {}", e);
+        e.to_string()
+    })?;
+
+    let capture_path = image_path.clone();
+    tokio::task::spawn_blocking(move || screen_capture::capture_to_file(region.as_ref(), &capture_path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            log::error!("Failed to capture screen: {}", e);
+            e.to_string()
+        })?;
+
+    let text = match analysis {
+        CaptureAnalysis::None => None,
+        CaptureAnalysis::Ocr => {
+            let ocr_path = image_path.clone();
+            let text = tokio::task::spawn_blocking(move || screen_capture::run_ocr(&ocr_path))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| {
+                    log::error!("Failed to OCR screenshot: {}", e);
+                    e.to_string()
+                })?;
+            Some(text)
+        }
+        CaptureAnalysis::Vision => {
+            let api_key = config_manager.get_api_key().map_err(|e| {
+                log::error!("Failed to get API key: {}", e);
+                e.to_string()
+            })?.ok_or_else(|| {
+                log::error!("No API key configured");
+                tr!("no-api-key")
+            })?;
+            let description = screen_capture::describe_with_vision(api_key, &image_path)
+                .await
+                .map_err(|e| {
+                    log::error!("Failed to describe screenshot: {}", e);
+                    e.to_string()
+                })?;
+            Some(description)
+        }
+    };
+
+    // Move the image into the attachment store now that OCR or vision is done with it
+    let data_dir = config_manager.get_data_dir().clone();
+    let conversation = conversation_id.clone();
+    let attachment = tokio::task::spawn_blocking(move || {
+        let store = history::HistoryStore::open(&data_dir)?;
+        storage::AttachmentStore::new(&data_dir).adopt(&store, &image_path, &conversation, storage::AttachmentKind::Image)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| {
+        log::error!("Failed to store screenshot: {}", e);
+        e.to_string()
+    })?;
+
+    let capture = ScreenCapture {
+        id,
+        conversation_id,
+        image_path: attachment.path,
+        analysis,
+        text,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    store.save(&capture).map_err(|e| {
+        log::error!("Failed to save capture metadata: {}", e);
+        e.to_string()
+    })?;
+
+    log::info!("Screen capture saved to: {}", capture.image_path);
+    Ok(capture)
+}
+
+
+pub async fn list_screen_captures(conversation_id: String) -> Result<Vec<ScreenCapture>, String> {
+    log::info!("Listing screen captures for conversation {}", conversation_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    screen_capture::CaptureStore::new(config_manager.get_data_dir())
+        .list(&conversation_id)
+        .map_err(|e| {
+            log::error!("Failed to list screen captures: {}", e);
+            e.to_string()
+        })
+}
+
+
+
+/// Whether history is read-only because another instance has it open. Checking retries the
+/// lock, so this also picks the database back up once the other instance exits.
+pub async fn get_database_status() -> Result<DatabaseStatus, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    tokio::task::spawn_blocking(move || {
+        history::HistoryStore::open(&data_dir)?;
+        db_lock::status(&data_dir)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| {
+        log::error!("Failed to check database status: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Disk used by attachments (by kind), the history database and other app data
+pub async fn get_storage_usage() -> Result<StorageUsage, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    tokio::task::spawn_blocking(move || {
+        let store = history::HistoryStore::open(&data_dir)?;
+        storage::AttachmentStore::new(&data_dir).usage(&store)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| {
+        log::error!("Failed to measure storage: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Remove an attachment from a conversation; the file is kept until no conversation refers to it
+/// and the next cleanup runs
+pub async fn detach_attachment(attachment_id: String, conversation_id: String) -> Result<(), String> {
+    log::info!("Detaching attachment {} from conversation {}", attachment_id, conversation_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.remove_attachment_reference(&attachment_id, &conversation_id))
+        .map_err(|e| {
+            log::error!("Failed to detach attachment: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Delete attachments no conversation refers to and stray files in the attachment store
+pub async fn cleanup_orphaned_attachments() -> Result<CleanupSummary, String> {
+    log::info!("Cleaning up orphaned attachments");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    tokio::task::spawn_blocking(move || {
+        let store = history::HistoryStore::open(&data_dir)?;
+        storage::AttachmentStore::new(&data_dir).cleanup(&store)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| {
+        log::error!("Attachment cleanup failed: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Start a chunked upload of a file the frontend holds, or resume an unfinished one for the same
+/// file. `sha256` is the hex digest of the whole file; `kind` is guessed from the name if omitted.
+pub async fn begin_upload(
+    conversation_id: String,
+    file_name: String,
+    total_bytes: u64,
+    sha256: String,
+    kind: Option<AttachmentKind>,
+) -> Result<UploadStatus, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    tokio::task::spawn_blocking(move || upload::begin(&data_dir, &conversation_id, &file_name, total_bytes, &sha256, kind))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            log::error!("Failed to begin upload: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Send one base64-encoded chunk of an upload, with the hex SHA-256 of its decoded bytes
+pub async fn upload_chunk(upload_id: String, index: u32, data: String, sha256: String) -> Result<UploadStatus, String> {
+    let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data)
+        .map_err(|e| format!("Chunk {} is not valid base64: {}", index, e))?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    tokio::task::spawn_blocking(move || upload::write_chunk(&data_dir, &upload_id, index, &bytes, &sha256))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            log::warn!("Rejected upload chunk {}: {}", index, e);
+            e.to_string()
+        })
+}
+
+
+pub async fn get_upload_status(upload_id: String) -> Result<UploadStatus, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    upload::status(config_manager.get_data_dir(), &upload_id).map_err(|e| e.to_string())
+}
+
+
+/// Verify a complete upload and store it as an attachment of its conversation
+pub async fn finish_upload(upload_id: String) -> Result<Attachment, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    tokio::task::spawn_blocking(move || {
+        let store = history::HistoryStore::open(&data_dir)?;
+        upload::finish(&data_dir, &store, &upload_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| {
+        log::error!("Failed to finish upload: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn cancel_upload(upload_id: String) -> Result<(), String> {
+    log::info!("Cancelling upload {}", upload_id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    upload::cancel(config_manager.get_data_dir(), &upload_id).map_err(|e| e.to_string())
+}
+
+pub async fn get_template_repositories() -> Result<Vec<String>, String> {
+    log::info!("Getting template repositories");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_template_repositories().map_err(|e| {
+        log::error!("Failed to get template repositories: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn add_template_repository(repository: String) -> Result<TemplateSyncResult, String> {
+    log::info!("Adding template repository: {}", repository);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut repositories = config_manager.get_template_repositories().map_err(|e| {
+        log::error!("Failed to get template repositories: {}", e);
+        e.to_string()
+    })?;
+
+    let result = templates::TemplateLibrary::new(config_manager.get_data_dir())
+        .sync(&repository)
+        .await;
+    if !result.success {
+        return Err(result.error.unwrap_or_else(|| "Template sync failed".to_string()));
+    }
+
+    if !repositories.contains(&repository) {
+        repositories.push(repository);
+        config_manager.save_template_repositories(repositories).map_err(|e| {
+            log::error!("Failed to save template repositories: {}", e);
+            e.to_string()
+        })?;
+    }
+    Ok(result)
+}
+
+
+pub async fn remove_template_repository(repository: String) -> Result<(), String> {
+    log::info!("Removing template repository: {}", repository);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut repositories = config_manager.get_template_repositories().map_err(|e| {
+        log::error!("Failed to get template repositories: {}", e);
+        e.to_string()
+    })?;
+    repositories.retain(|r| r != &repository);
+    config_manager.save_template_repositories(repositories).map_err(|e| {
+        log::error!("Failed to save template repositories: {}", e);
+        e.to_string()
+    })?;
+    templates::TemplateLibrary::new(config_manager.get_data_dir())
+        .remove(&repository)
+        .map_err(|e| {
+            log::error!("Failed to remove template checkout: {}", e);
+            e.to_string()
+        })
+}
+
+
+pub async fn sync_template_repositories() -> Result<Vec<TemplateSyncResult>, String> {
+    log::info!("Syncing template repositories");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let repositories = config_manager.get_template_repositories().map_err(|e| {
+        log::error!("Failed to get template repositories: {}", e);
+        e.to_string()
+    })?;
+
+    let library = templates::TemplateLibrary::new(config_manager.get_data_dir());
+    let mut results = Vec::new();
+    for repository in &repositories {
+        results.push(library.sync(repository).await);
+    }
+    log::info!("Synced {} template repositories", results.len());
+    events::publish(AppEvent::TemplatesSynced { repositories: results.len() as u32 });
+    Ok(results)
+}
+
+
+pub async fn list_templates() -> Result<Vec<PromptTemplate>, String> {
+    log::info!("Listing templates");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let repositories = config_manager.get_template_repositories().map_err(|e| {
+        log::error!("Failed to get template repositories: {}", e);
+        e.to_string()
+    })?;
+    templates::TemplateLibrary::new(config_manager.get_data_dir())
+        .list(&repositories)
+        .map_err(|e| {
+            log::error!("Failed to list templates: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Background loop that keeps template repositories up to date; spawn once at startup
+pub async fn run_template_sync_schedule() {
+    let interval = std::time::Duration::from_secs(templates::DEFAULT_SYNC_INTERVAL_SECS);
+    loop {
+        if low_power::active().await {
+            log::debug!("Low-power profile is on, putting the template sync off");
+        } else if let Err(e) = sync_template_repositories().await {
+            log::warn!("Scheduled template sync failed: {}", e);
+        }
+        tokio::time::sleep(low_power::poll_interval(interval).await).await;
+    }
+}
+
+
+/// Write a compressed, checksummed archive of history and attachments to `path`
+pub async fn backup_history(path: String) -> Result<BackupInfo, String> {
+    log::info!("Backing up history to {}", path);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    let info = tokio::task::spawn_blocking(move || backup::backup(&data_dir, std::path::Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            log::error!("Backup failed: {}", e);
+            e.to_string()
+        })?;
+    events::publish(AppEvent::BackupCompleted { path: info.path.clone(), scheduled: false });
+    Ok(info)
+}
+
+
+/// Restore history and attachments from a backup made by `backup_history`
+pub async fn restore_history(path: String, mode: RestoreMode) -> Result<RestoreSummary, String> {
+    log::info!("Restoring history from {} ({:?})", path, mode);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let data_dir = config_manager.get_data_dir().clone();
+    let summary = tokio::task::spawn_blocking(move || backup::restore(&data_dir, std::path::Path::new(&path), mode))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            log::error!("Restore failed: {}", e);
+            e.to_string()
+        })?;
+    events::publish(AppEvent::HistoryRestored {
+        messages_added: summary.messages_added,
+        files_restored: summary.files_restored,
+    });
+    Ok(summary)
+}
+
+
+pub async fn get_backup_schedule() -> Result<BackupSchedule, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_backup_schedule().map_err(|e| {
+        log::error!("Failed to get backup schedule: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn set_backup_schedule(schedule: BackupSchedule) -> Result<(), String> {
+    log::info!("Setting backup schedule (enabled: {})", schedule.enabled);
+    if schedule.enabled && schedule.directory.trim().is_empty() {
+        return Err("Scheduled backups need a directory".to_string());
+    }
+    if schedule.interval_hours == 0 || schedule.keep == 0 {
+        return Err("Backup interval and number kept must be at least 1".to_string());
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_backup_schedule(schedule).map_err(|e| {
+        log::error!("Failed to save backup schedule: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Background loop that takes scheduled backups when they are due; spawn once at startup
+pub async fn run_backup_schedule() {
+    let check_interval = std::time::Duration::from_secs(15 * 60);
+    loop {
+        if let Err(e) = scheduled_backup().await {
+            log::warn!("Scheduled backup failed: {}", e);
+        }
+        tokio::time::sleep(low_power::poll_interval(check_interval).await).await;
+    }
+}
+
+async fn scheduled_backup() -> Result<(), String> {
+    let schedule = get_backup_schedule().await?;
+    if !schedule.enabled {
+        return Ok(());
+    }
+    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
+    let data_dir = config_manager.get_data_dir().clone();
+    let path = tokio::task::spawn_blocking(move || -> anyhow::Result<Option<String>> {
+        if !backup::is_due(&schedule)? {
+            return Ok(None);
+        }
+        let directory = std::path::Path::new(&schedule.directory);
+        let info = backup::backup(&data_dir, &backup::scheduled_path(directory))?;
+        backup::prune(directory, schedule.keep)?;
+        Ok(Some(info.path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    if let Some(path) = path {
+        events::publish(AppEvent::BackupCompleted { path, scheduled: true });
+    }
+    Ok(())
+}
+
+
+pub async fn get_maintenance_settings() -> Result<MaintenanceSettings, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_maintenance_settings().map_err(|e| {
+        log::error!("Failed to get maintenance settings: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn set_maintenance_settings(settings: MaintenanceSettings) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_maintenance_settings(settings).map_err(|e| {
+        log::error!("Failed to save maintenance settings: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Idle maintenance: whether jobs may run now, what's running, and when each job last ran and
+/// what it did
+pub async fn get_maintenance_status() -> Result<MaintenanceStatus, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let settings = config_manager.get_maintenance_settings().map_err(|e| {
+        log::error!("Failed to get maintenance settings: {}", e);
+        e.to_string()
+    })?;
+    let last_runs = config_manager.get_maintenance_runs().map_err(|e| {
+        log::error!("Failed to get maintenance runs: {}", e);
+        e.to_string()
+    })?;
+    let on_battery = tokio::task::spawn_blocking(power::on_battery).await.map_err(|e| e.to_string())?;
+    Ok(maintenance::status(settings, &last_runs, modes::responding(), on_battery))
+}
+
+
+/// Note that the user typed, clicked or scrolled, putting idle maintenance off
+pub async fn note_user_activity() -> Result<(), String> {
+    maintenance::note_activity();
+    Ok(())
+}
+
+
+/// Whether the low-power profile is on and why, with the battery and connection readings
+pub async fn get_low_power_status() -> Result<LowPowerStatus, String> {
+    low_power::status().await.map_err(|e| {
+        log::error!("Failed to get low-power status: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn get_low_power_settings() -> Result<LowPowerSettings, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_low_power_settings().map_err(|e| {
+        log::error!("Failed to get low-power settings: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn set_low_power_settings(settings: LowPowerSettings) -> Result<LowPowerStatus, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_low_power_settings(settings).map_err(|e| {
+        log::error!("Failed to save low-power settings: {}", e);
+        e.to_string()
+    })?;
+    get_low_power_status().await
+}
+
+
+/// Force the low-power profile on or off until the app quits, whatever the settings say; None
+/// follows the settings again
+pub async fn set_low_power_override(active: Option<bool>) -> Result<LowPowerStatus, String> {
+    log::info!("Low-power profile overridden for this session: {:?}", active);
+    low_power::set_session_override(active);
+    get_low_power_status().await
+}
+
+
+/// Background loop that runs due maintenance jobs, one at a time, while the user is idle and
+/// the machine on mains power; spawn once at startup
+pub async fn run_maintenance_schedule() {
+    let check_interval = std::time::Duration::from_secs(60);
+    loop {
+        tokio::time::sleep(low_power::poll_interval(check_interval).await).await;
+        if let Err(e) = scheduled_maintenance().await {
+            log::warn!("Idle maintenance failed: {}", e);
+        }
+    }
+}
+
+async fn scheduled_maintenance() -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
+    let settings = config_manager.get_maintenance_settings().map_err(|e| e.to_string())?;
+    // Reading the power source can start a process, so it's left until the user is idle
+    if !settings.enabled || maintenance::idle_for() < std::time::Duration::from_secs(u64::from(settings.idle_minutes) * 60) {
+        return Ok(());
+    }
+    if low_power::active().await {
+        return Ok(());
+    }
+    let on_battery = tokio::task::spawn_blocking(power::on_battery).await.map_err(|e| e.to_string())?;
+    if !maintenance::can_run(&settings, maintenance::idle_for(), modes::responding(), on_battery) {
+        return Ok(());
+    }
+    let last_runs = config_manager.get_maintenance_runs().map_err(|e| e.to_string())?;
+    let Some(job) = maintenance::next_due(&last_runs, chrono::Utc::now()) else {
+        return Ok(());
+    };
+    if !maintenance::start(job) {
+        return Ok(());
+    }
+    log::info!("Running idle maintenance job {:?}", job);
+    let started = std::time::Instant::now();
+    let result = run_maintenance_job(&config_manager, job).await;
+    let outcome = result.as_ref().map(|(summary, _)| summary.clone()).map_err(String::clone);
+    maintenance::finish(job, &outcome, started.elapsed());
+    let (summary, finished) = result?;
+    // A job cut short stays due, to carry on at the next check
+    if finished {
+        config_manager.save_maintenance_run(job, chrono::Utc::now().to_rfc3339()).map_err(|e| e.to_string())?;
+    }
+    log::info!("Idle maintenance job {:?} done in {:?}: {}", job, started.elapsed(), summary);
+    events::publish(AppEvent::MaintenanceCompleted { job, summary });
+    Ok(())
+}
+
+/// Run `job`, returning what it did and whether it got through everything
+async fn run_maintenance_job(config_manager: &ConfigManager, job: MaintenanceJob) -> Result<(String, bool), String> {
+    match job {
+        MaintenanceJob::Vacuum => {
+            let data_dir = config_manager.get_data_dir().clone();
+            let freed = tokio::task::spawn_blocking(move || history::HistoryStore::open(&data_dir)?.vacuum())
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())?;
+            Ok((tr!("maintenance-vacuumed", megabytes = format!("{:.1}", freed as f64 / 1_048_576.0)), true))
+        }
+        MaintenanceJob::PruneCaches => Ok((tr!("maintenance-caches-pruned", entries = cache::prune_expired()), true)),
+        MaintenanceJob::BackfillEmbeddings => {
+            const BATCH: u32 = 100;
+            let Ok((client, _)) = streaming_client(config_manager) else {
+                return Ok((tr!("maintenance-no-provider"), true));
+            };
+            let prompts = history::HistoryStore::open(config_manager.get_data_dir())
+                .and_then(|store| store.unembedded_prompts(BATCH))
+                .map_err(|e| e.to_string())?;
+            if prompts.is_empty() {
+                return Ok((tr!("maintenance-embedded", prompts = 0), true));
+            }
+            let redact = config_manager.get_redaction_enabled().map_err(|e| e.to_string())?;
+            let texts = prompts.iter()
+                .map(insights::topic_text)
+                .map(|text| if redact { redaction::redact(&text) } else { text })
+                .collect();
+            let prompt_refs: Vec<&HistoryMessage> = prompts.iter().collect();
+            dedup::prompt_embeddings(&client, config_manager.get_data_dir(), &prompt_refs, texts)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok((tr!("maintenance-embedded", prompts = prompts.len()), prompts.len() < BATCH as usize))
+        }
+        MaintenanceJob::RefreshModels => {
+            if !config_manager.has_config() {
+                return Ok((tr!("maintenance-no-provider"), true));
+            }
+            cache::invalidate(CacheScope::Models);
+            let models = get_available_models().await?;
+            Ok((tr!("maintenance-models-refreshed", models = models.len()), true))
+        }
+    }
+}
+
+
+/// Create or replace an eval suite; blank cases and models are dropped
+pub async fn save_eval_suite(suite: EvalSuite) -> Result<EvalSuite, String> {
+    let mut suite = eval::validate(suite)?;
+    log::info!("Saving eval suite {} ({} cases, {} models)", suite.name, suite.cases.len(), suite.models.len());
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    let now = chrono::Utc::now().to_rfc3339();
+    suite.created_at = match store.eval_suite(&suite.name) {
+        Ok(Some(existing)) => existing.created_at,
+        _ => now.clone(),
+    };
+    suite.updated_at = now;
+    store.save_eval_suite(&suite).map_err(|e| {
+        log::error!("Failed to save eval suite: {}", e);
+        e.to_string()
+    })?;
+    Ok(suite)
+}
+
+
+pub async fn list_eval_suites() -> Result<Vec<EvalSuite>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_eval_suites())
+        .map_err(|e| {
+            log::error!("Failed to list eval suites: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Delete a suite and its score history; returns whether it existed
+pub async fn delete_eval_suite(name: String) -> Result<bool, String> {
+    log::info!("Deleting eval suite {}", name);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|mut store| store.delete_eval_suite(&name))
+        .map_err(|e| {
+            log::error!("Failed to delete eval suite: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Run a suite now: every case against every model, each answer scored by the judge model
+pub async fn run_eval_suite(name: String) -> Result<EvalRun, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let suite = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.eval_suite(&name))
+        .map_err(|e| {
+            log::error!("Failed to load eval suite: {}", e);
+            e.to_string()
+        })?
+        .ok_or_else(|| tr!("eval-suite-not-found", name = name.as_str()))?;
+    run_suite(&config_manager, suite, false).await
+}
+
+
+/// A suite's newest runs with every answer and score, newest first (20 unless `limit` is given)
+pub async fn list_eval_runs(suite_name: String, limit: Option<u32>) -> Result<Vec<EvalRun>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_eval_runs(&suite_name, limit.unwrap_or(20)))
+        .map_err(|e| {
+            log::error!("Failed to list eval runs: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Each model's average score in each finished run of a suite, oldest first, for charting drift
+pub async fn get_eval_scores(suite_name: String) -> Result<Vec<ModelScore>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut runs = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_eval_runs(&suite_name, u32::MAX))
+        .map_err(|e| {
+            log::error!("Failed to load eval runs: {}", e);
+            e.to_string()
+        })?;
+    runs.retain(|run| run.finished_at.is_some());
+    runs.reverse();
+    Ok(eval::model_scores(&runs))
+}
+
+
+async fn run_suite(config_manager: &ConfigManager, suite: EvalSuite, scheduled: bool) -> Result<EvalRun, String> {
+    let _running = eval::begin_run(&suite.name).ok_or_else(|| tr!("eval-already-running", name = suite.name.as_str()))?;
+    let (client, _) = streaming_client(config_manager)?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    check_spend_cap(&store)?;
+
+    let total = (suite.cases.len() * suite.models.len()) as u32;
+    log::info!("Running eval suite {} ({} answers, judged by {})", suite.name, total, suite.judge_model);
+    let mut run = EvalRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        suite_name: suite.name.clone(),
+        judge_model: suite.judge_model.clone(),
+        scheduled,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        finished_at: None,
+        results: Vec::new(),
+    };
+    store.start_eval_run(&run).map_err(|e| {
+        log::error!("Failed to record eval run: {}", e);
+        e.to_string()
+    })?;
+    events::publish(AppEvent::EvalRunStarted { run_id: run.id.clone(), suite_name: suite.name.clone(), total });
+
+    'cases: for (index, case) in suite.cases.iter().enumerate() {
+        for model in &suite.models {
+            if let Err(e) = check_spend_cap(&store) {
+                log::warn!("Stopping eval run {} early: {}", run.id, e);
+                break 'cases;
+            }
+            let result = eval_case(&client, &suite.judge_model, index as u32, case, model).await;
+            if let Err(e) = store.save_eval_result(&run.id, &result) {
+                log::error!("Failed to save eval result: {}", e);
+            }
+            run.results.push(result);
+            events::publish(AppEvent::EvalProgress { run_id: run.id.clone(), done: run.results.len() as u32, total });
+        }
+    }
+
+    let finished_at = chrono::Utc::now().to_rfc3339();
+    if let Err(e) = store.finish_eval_run(&run.id, &finished_at) {
+        log::error!("Failed to finish eval run: {}", e);
+    }
+    run.finished_at = Some(finished_at);
+    let average_score = eval::average_score(&run.results);
+    log::info!("Eval run {} finished, average score {:?}", run.id, average_score);
+    events::publish(AppEvent::EvalRunCompleted { run_id: run.id.clone(), suite_name: suite.name, average_score });
+    Ok(run)
+}
+
+/// Answer one case with `model` and have the judge score it. Failures are kept in the result
+/// rather than ending the run.
+async fn eval_case(client: &Client<OpenAIConfig>, judge_model: &str, case_index: u32, case: &EvalCase, model: &str) -> EvalResult {
+    let mut result = EvalResult {
+        case_index,
+        model: model.to_string(),
+        prompt: case.prompt.clone(),
+        output: String::new(),
+        score: None,
+        reasoning: String::new(),
+        error: None,
+        estimated_usd: None,
+    };
+    let cost = |model: &str, completion: &eval::Completion| cost::spent(
+        &[cost::PlannedRequest { model: model.to_string(), prompt_tokens: completion.prompt_tokens }],
+        completion.completion_tokens,
+    );
+    let answer = match eval::complete(client, model, &case.prompt).await {
+        Ok(answer) => answer,
+        Err(e) => {
+            log::warn!("{} failed on eval case {}: {}", model, case_index, e);
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.estimated_usd = cost(model, &answer);
+    result.output = answer.text;
+    match eval::judge(client, judge_model, case, &result.output).await {
+        Ok((verdict, judging)) => {
+            result.score = Some(verdict.score);
+            result.reasoning = verdict.reasoning;
+            result.estimated_usd = match (result.estimated_usd, cost(judge_model, &judging)) {
+                (Some(answering), Some(judging)) => Some(answering + judging),
+                (answering, judging) => answering.or(judging),
+            };
+        }
+        Err(e) => {
+            log::warn!("Judging eval case {} for {} failed: {}", case_index, model, e);
+            result.error = Some(tr!("eval-judge-failed", error = e.to_string()));
+        }
+    }
+    result
+}
+
+
+/// Background loop that runs scheduled eval suites when they are due; spawn once at startup
+pub async fn run_eval_schedule() {
+    let check_interval = std::time::Duration::from_secs(15 * 60);
+    loop {
+        if low_power::active().await {
+            log::debug!("Low-power profile is on, putting scheduled evals off");
+        } else if let Err(e) = scheduled_evals().await {
+            log::warn!("Scheduled eval check failed: {}", e);
+        }
+        tokio::time::sleep(low_power::poll_interval(check_interval).await).await;
+    }
+}
+
+async fn scheduled_evals() -> Result<(), String> {
+    if demo::active() {
+        return Ok(());
+    }
+    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
+    let due: Vec<EvalSuite> = {
+        let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| e.to_string())?;
+        let now = chrono::Utc::now();
+        let mut due = Vec::new();
+        for suite in store.list_eval_suites().map_err(|e| e.to_string())? {
+            let last_started = store.last_eval_run_started(&suite.name).map_err(|e| e.to_string())?;
+            if eval::is_due(&suite, last_started.as_deref(), now) {
+                due.push(suite);
+            }
+        }
+        due
+    };
+    for suite in due {
+        let name = suite.name.clone();
+        if let Err(e) = run_suite(&config_manager, suite, true).await {
+            log::warn!("Scheduled eval run of {} failed: {}", name, e);
+        }
+    }
+    Ok(())
+}
+
+
+/// Stream the same prompt (a standard one unless `prompt` is given) from each of `models` in turn,
+/// timing the first token and the generation rate, and keep the report. Models are named by
+/// their catalogue id, with or without the provider prefix; one that fails or isn't offered is
+/// reported as such rather than ending the benchmark.
+pub async fn benchmark_providers(prompt: Option<String>, models: Vec<String>) -> Result<BenchmarkReport, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let (client, _) = streaming_client(&config_manager)?;
+    let catalogue = model_catalogue().await?;
+    let targets = benchmark::targets(&models, &catalogue)?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    check_spend_cap(&store)?;
+
+    let mut report = BenchmarkReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        prompt: benchmark::prompt(prompt),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        finished_at: None,
+        results: Vec::new(),
+    };
+    let total = targets.len() as u32;
+    log::info!("Benchmarking {} models", total);
+    store.start_benchmark(&report).map_err(|e| {
+        log::error!("Failed to record benchmark: {}", e);
+        e.to_string()
+    })?;
+
+    // One model at a time, so they don't compete for the connection
+    for target in targets {
+        if let Err(e) = check_spend_cap(&store) {
+            log::warn!("Stopping benchmark {} early: {}", report.id, e);
+            break;
+        }
+        let result = match target {
+            benchmark::Target::Found(entry) => benchmark_model(&client, &entry, &report.prompt).await,
+            benchmark::Target::Unknown(model) => BenchmarkResult {
+                provider: String::new(),
+                error: Some(tr!("benchmark-model-not-found", model = model.as_str())),
+                model,
+                ttft_ms: None,
+                total_ms: None,
+                tokens_per_second: None,
+                output_tokens: 0,
+                output_chars: 0,
+                estimated_usd: None,
+            },
+        };
+        if let Err(e) = store.save_benchmark_result(&report.id, report.results.len() as u32, &result) {
+            log::error!("Failed to save benchmark result: {}", e);
+        }
+        let model = result.model.clone();
+        report.results.push(result);
+        events::publish(AppEvent::BenchmarkProgress { report_id: report.id.clone(), model, done: report.results.len() as u32, total });
+    }
+
+    let finished_at = chrono::Utc::now().to_rfc3339();
+    if let Err(e) = store.finish_benchmark(&report.id, &finished_at) {
+        log::error!("Failed to finish benchmark: {}", e);
+    }
+    report.finished_at = Some(finished_at);
+    if let Some(fastest) = benchmark::ranked(&report.results).first().filter(|result| result.error.is_none()) {
+        log::info!("Benchmark {} finished, fastest first token from {} in {:?} ms", report.id, fastest.model, fastest.ttft_ms);
+    }
+    Ok(report)
+}
+
+/// Stream `prompt` from one model and time it. Failures are kept in the result.
+async fn benchmark_model(client: &Client<OpenAIConfig>, entry: &openai_api::CatalogueEntry, prompt: &str) -> BenchmarkResult {
+    let model = entry.model.id.clone();
+    let mut result = BenchmarkResult {
+        provider: entry.provider.clone(),
+        model: entry.id(),
+        ttft_ms: None,
+        total_ms: None,
+        tokens_per_second: None,
+        output_tokens: 0,
+        output_chars: 0,
+        estimated_usd: None,
+        error: None,
+    };
+    // Together reads max_tokens; max_completion_tokens is OpenAI-only
+    #[allow(deprecated)]
+    let request = async_openai::types::ChatCompletionRequestUserMessageArgs::default()
+        .content(prompt)
+        .build()
+        .and_then(|message| async_openai::types::CreateChatCompletionRequestArgs::default()
+            .model(model.as_str())
+            .messages(vec![message.into()])
+            .max_tokens(benchmark::MAX_TOKENS)
+            .stream(true)
+            .build());
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+
+    let client = match routed_client(client, &model) {
+        Ok(client) => client,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+    let (mut span, client) = provider_span(&client, &model);
+    span.set("switchboard.benchmark", true);
+    let started = std::time::Instant::now();
+    let mut stream = match client.chat().create_stream(providers::upstream_request(request)).await {
+        Ok(stream) => telemetry::traced(stream, span),
+        Err(e) => {
+            span.fail(e.to_string());
+            log::warn!("Benchmark of {} failed: {}", model, e);
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let mut text = String::new();
+    let mut usage = None;
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => {
+                if let Some(content) = chunk.choices.first().and_then(|choice| choice.delta.content.as_deref()) {
+                    if !content.is_empty() && result.ttft_ms.is_none() {
+                        result.ttft_ms = Some(started.elapsed().as_millis() as u32);
+                    }
+                    text.push_str(content);
+                }
+                usage = chunk.usage.or(usage);
+            }
+            Err(e) => {
+                log::warn!("Benchmark of {} failed mid-stream: {}", model, e);
+                result.error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+    let total_ms = started.elapsed().as_millis() as u32;
+    let (prompt_tokens, output_tokens) = match usage {
+        Some(usage) => (usage.prompt_tokens, usage.completion_tokens),
+        None => (context::estimate_tokens(prompt) as u32, context::estimate_tokens(&text) as u32),
+    };
+    result.total_ms = Some(total_ms);
+    result.output_tokens = output_tokens;
+    result.output_chars = text.chars().count() as u32;
+    result.tokens_per_second = result.ttft_ms.and_then(|ttft_ms| benchmark::tokens_per_second(output_tokens, ttft_ms, total_ms));
+    result.estimated_usd = cost::spent(&[cost::PlannedRequest { model, prompt_tokens }], output_tokens);
+    result
+}
+
+
+/// The newest benchmark reports, newest first (20 unless `limit` is given)
+pub async fn list_benchmark_reports(limit: Option<u32>) -> Result<Vec<BenchmarkReport>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_benchmarks(limit.unwrap_or(20)))
+        .map_err(|e| {
+            log::error!("Failed to list benchmark reports: {}", e);
+            e.to_string()
+        })
+}
+
+
+/// Delete a benchmark report; returns whether it existed
+pub async fn delete_benchmark_report(id: String) -> Result<bool, String> {
+    log::info!("Deleting benchmark report {}", id);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|mut store| store.delete_benchmark(&id))
+        .map_err(|e| {
+            log::error!("Failed to delete benchmark report: {}", e);
+            e.to_string()
+        })
+}
+
+
+
+pub async fn get_sync_config() -> Result<SyncConfig, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_sync_config().map_err(|e| {
+        log::error!("Failed to get sync config: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Save sync settings. A device id is assigned on first save; the key must come from
+/// `generate_sync_key` on one device and be copied to the others.
+pub async fn set_sync_config(config: SyncConfig) -> Result<SyncConfig, String> {
+    log::info!("Setting sync config (enabled: {})", config.enabled);
+    if config.enabled {
+        if config.backend.is_none() {
+            return Err("Sync needs a storage backend".to_string());
+        }
+        let key = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, config.encryption_key.trim())
+            .map_err(|_| "Sync key is not valid base64".to_string())?;
+        if key.len() != 32 {
+            return Err("Sync key must be 32 bytes".to_string());
+        }
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let current = config_manager.get_sync_config().map_err(|e| e.to_string())?;
+    let config = SyncConfig {
+        device_id: if current.device_id.is_empty() { uuid::Uuid::new_v4().to_string() } else { current.device_id },
+        last_synced_at: current.last_synced_at,
+        ..config
+    };
+    config_manager.save_sync_config(config.clone()).map_err(|e| {
+        log::error!("Failed to save sync config: {}", e);
+        e.to_string()
+    })?;
+    Ok(config)
+}
+
+
+/// New random sync encryption key, to be shared with the user's other devices
+pub async fn generate_sync_key() -> Result<String, String> {
+    Ok(sync::generate_key())
+}
+
+
+/// Exchange history, memories and shared settings with the configured sync backend
+pub async fn sync_now() -> Result<SyncReport, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let config = config_manager.get_sync_config().map_err(|e| e.to_string())?;
+    if !config.enabled {
+        return Err("Sync is not enabled".to_string());
+    }
+    let mut store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| e.to_string())?;
+    let report = sync::sync(&config, &config_manager, &mut store).await.map_err(|e| {
+        log::error!("Sync failed: {}", e);
+        e.to_string()
+    })?;
+    // Reload in case settings were changed by the sync itself
+    let config = config_manager.get_sync_config().map_err(|e| e.to_string())?;
+    config_manager
+        .save_sync_config(SyncConfig { last_synced_at: Some(report.synced_at.clone()), ..config })
+        .map_err(|e| e.to_string())?;
+    events::publish(AppEvent::SyncCompleted {
+        pushed: report.pushed,
+        pulled: report.pulled,
+        conflicts: report.conflicts,
+    });
+    Ok(report)
+}
+
+
+/// The user's other devices that have synced through the same backend
+pub async fn list_sync_devices() -> Result<Vec<SyncDevice>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| e.to_string())?;
+    store.sync_devices().map_err(|e| e.to_string())
+}
+
+
+/// Background loop that syncs on the configured interval; spawn once at startup
+pub async fn run_sync_schedule() {
+    let check_interval = std::time::Duration::from_secs(60);
+    loop {
+        tokio::time::sleep(low_power::poll_interval(check_interval).await).await;
+        let Ok(config) = get_sync_config().await else { continue };
+        if !config.enabled || config.interval_minutes == 0 {
+            continue;
+        }
+        // Manual syncs still go through; scheduled ones catch up once the profile is off
+        if low_power::active().await {
+            continue;
+        }
+        let due = config.last_synced_at
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(&at).ok())
+            .is_none_or(|at| chrono::Utc::now().signed_duration_since(at).num_minutes() >= i64::from(config.interval_minutes));
+        if due {
+            if let Err(e) = sync_now().await {
+                log::warn!("Scheduled sync failed: {}", e);
+            }
+        }
+    }
+}
+
+pub async fn reload_plugins() -> Result<Vec<PluginInfo>, String> {
+    if !features::enabled(Feature::Plugins) {
+        log::info!("Plugins are turned off, unloading them");
+        plugins::unload().map_err(|e| e.to_string())?;
+        events::publish(AppEvent::PluginsReloaded { count: 0 });
+        return Ok(Vec::new());
+    }
+    log::info!("Reloading plugins");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let grants = config_manager.get_plugin_grants().map_err(|e| {
+        log::error!("Failed to get plugin grants: {}", e);
+        e.to_string()
+    })?;
+    let infos = plugins::reload(config_manager.get_data_dir(), &grants).map_err(|e| {
+        log::error!("Failed to reload plugins: {}", e);
+        e.to_string()
+    })?;
+    events::publish(AppEvent::PluginsReloaded { count: infos.len() as u32 });
+    Ok(infos)
+}
+
+
+pub async fn list_plugins() -> Result<Vec<PluginInfo>, String> {
+    match plugins::loaded_plugins() {
+        Some(infos) => Ok(infos),
+        None => reload_plugins().await,
+    }
+}
+
+
+pub async fn list_integrations() -> Result<Vec<IntegrationInfo>, String> {
+    let manifests = match integrations::loaded_manifests() {
+        Some(manifests) => manifests,
+        None => return reload_integrations().await,
+    };
+    integration_infos(manifests)
+}
+
+
+/// Read integration manifests again, e.g. after one was added to the data directory
+pub async fn reload_integrations() -> Result<Vec<IntegrationInfo>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let manifests = integrations::reload(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to load integrations: {}", e);
+        e.to_string()
+    })?;
+    integration_infos(manifests)
+}
+
+
+fn integration_infos(manifests: integrations::Manifests) -> Result<Vec<IntegrationInfo>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let secrets = config_manager.get_secrets().map_err(|e| {
+        log::error!("Failed to get secrets: {}", e);
+        e.to_string()
+    })?;
+    Ok(manifests.into_iter()
+        .map(|(manifest, built_in)| IntegrationInfo {
+            has_credential: manifest.auth == IntegrationAuth::None
+                || vault::read(&secrets, &vault::integration_owner(&manifest.name), &integrations::credential_name(&manifest.name)).is_some(),
+            manifest,
+            built_in,
+        })
+        .collect())
+}
+
+
+/// Call `endpoint` of an integration with its stored credential. `params` fill the endpoint's
+/// path placeholders, with the rest sent as query parameters; `body` is sent as JSON. Returns
+/// the response body, as a string if it isn't JSON. An OAuth access token the service turns
+/// away is refreshed once and the call retried.
+pub async fn call_integration(
+    integration: String,
+    endpoint: String,
+    params: std::collections::BTreeMap<String, String>,
+    body: Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    log::info!("Calling integration endpoint {}::{}", integration, endpoint);
+    let manifest = find_integration(&integration).await?;
+    let mut refreshed = false;
+    loop {
+        let credential = vault::read_stored(&vault::integration_owner(&integration), &integrations::credential_name(&integration)).map_err(|e| {
+            log::error!("Failed to read the credential of {}: {}", integration, e);
+            e.to_string()
+        })?;
+        let prepared = integrations::prepare(&manifest, &endpoint, &params, credential.as_deref()).map_err(|e| {
+            log::error!("Failed to prepare call to {}::{}: {}", integration, endpoint, e);
+            e.to_string()
+        })?;
+
+        let method = reqwest::Method::from_bytes(prepared.method.as_bytes()).map_err(|e| e.to_string())?;
+        let mut request = reqwest::Client::new().request(method, &prepared.url);
+        for (name, value) in &prepared.headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        let response = request.send().await.map_err(|e| {
+            log::error!("Integration call {}::{} failed: {}", integration, endpoint, e);
+            e.to_string()
+        })?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| e.to_string())?;
+        if status == reqwest::StatusCode::UNAUTHORIZED && !refreshed && matches!(manifest.auth, IntegrationAuth::OAuth2 { .. }) {
+            log::info!("{} turned away its access token, refreshing", integration);
+            refresh_integration_token(&manifest).await?;
+            refreshed = true;
+            continue;
+        }
+        if !status.is_success() {
+            log::error!("Integration call {}::{} returned {}", integration, endpoint, status);
+            return Err(tr!("integration-call-failed", integration = integration.as_str(), status = status.to_string(), body = text));
+        }
+        return Ok(serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text)));
+    }
+}
+
+
+async fn find_integration(integration: &str) -> Result<IntegrationManifest, String> {
+    startup::ensure(startup::Subsystem::Integrations).await;
+    integrations::loaded_manifests()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(manifest, _)| manifest)
+        .find(|manifest| manifest.name == integration)
+        .ok_or_else(|| tr!("integration-not-found", name = integration))
+}
+
+
+fn oauth_client(manifest: &IntegrationManifest) -> Result<oauth::OAuthClient, String> {
+    let client_secret = vault::read_stored(&vault::integration_owner(&manifest.name), &integrations::client_secret_name(&manifest.name)).map_err(|e| {
+        log::error!("Failed to read the client secret of {}: {}", manifest.name, e);
+        e.to_string()
+    })?;
+    oauth::OAuthClient::for_integration(manifest, client_secret).map_err(|e| e.to_string())
+}
+
+
+/// Keep an integration's tokens in the vault. A reply without a refresh token keeps the old one,
+/// since services that don't rotate refresh tokens leave them out.
+fn store_oauth_tokens(integration: &str, tokens: oauth::Tokens) -> Result<(), String> {
+    update_secrets(|secrets| {
+        vault::store(secrets, &integrations::credential_name(integration), &vault::integration_owner(integration), tokens.access_token)?;
+        if let Some(refresh_token) = tokens.refresh_token {
+            vault::store(secrets, &integrations::refresh_token_name(integration), &vault::integration_owner(integration), refresh_token)?;
+        }
+        Ok(())
+    })
+}
+
+
+async fn refresh_integration_token(manifest: &IntegrationManifest) -> Result<(), String> {
+    let client = oauth_client(manifest)?;
+    let refresh_token = vault::read_stored(&vault::integration_owner(&manifest.name), &integrations::refresh_token_name(&manifest.name))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| tr!("oauth-no-refresh-token", integration = manifest.display_name.as_str()))?;
+    let tokens = oauth::refresh(&client, &refresh_token).await.map_err(|e| {
+        log::error!("Failed to refresh the token of {}: {}", manifest.name, e);
+        e.to_string()
+    })?;
+    store_oauth_tokens(&manifest.name, tokens)
+}
+
+
+/// Get a new access token for an OAuth integration with its stored refresh token
+pub async fn refresh_oauth_token(integration: String) -> Result<(), String> {
+    log::info!("Refreshing the OAuth token of {}", integration);
+    let manifest = find_integration(&integration).await?;
+    refresh_integration_token(&manifest).await
+}
+
+
+/// Begin a device code sign-in. The returned code is for the user to enter on the service's
+/// verification page; polling carries on in the background and ends in `OAuthCompleted` or
+/// `OAuthFailed`.
+pub async fn start_oauth_device_flow(integration: String) -> Result<DeviceAuthorization, String> {
+    log::info!("Starting device sign-in for {}", integration);
+    let manifest = find_integration(&integration).await?;
+    let client = oauth_client(&manifest)?;
+    let code = oauth::request_device_code(&client).await.map_err(|e| {
+        log::error!("Failed to get a device code for {}: {}", integration, e);
+        e.to_string()
+    })?;
+    let authorization = DeviceAuthorization {
+        integration: integration.clone(),
+        user_code: code.user_code.clone(),
+        verification_uri: code.verification_uri.clone(),
+        verification_uri_complete: code.verification_uri_complete.clone(),
+        expires_in_secs: code.expires_in,
+    };
+    tokio::spawn(async move {
+        let result = oauth::poll_device_code(&client, &code).await.map_err(|e| e.to_string());
+        finish_oauth(integration, result);
+    });
+    Ok(authorization)
+}
+
+
+/// Begin a browser sign-in with PKCE: the service's sign-in page opens in the default browser,
+/// which is sent back to a listener on the loopback address. Returns the page's address, to
+/// show in case the browser didn't open; finishes with `OAuthCompleted` or `OAuthFailed`.
+pub async fn start_oauth_browser_flow(integration: String) -> Result<String, String> {
+    log::info!("Starting browser sign-in for {}", integration);
+    let manifest = find_integration(&integration).await?;
+    let client = oauth_client(&manifest)?;
+    let callback = oauth::Callback::bind().await.map_err(|e| {
+        log::error!("Failed to listen for the sign-in redirect: {}", e);
+        e.to_string()
+    })?;
+    let (verifier, challenge) = oauth::pkce_pair();
+    let state = oauth::random_token(16);
+    let redirect_uri = callback.redirect_uri.clone();
+    let url = oauth::authorization_url(&client, &redirect_uri, &state, &challenge).map_err(|e| e.to_string())?;
+    if let Err(e) = oauth::open_in_browser(&url) {
+        log::warn!("Failed to open the browser for {}: {}", integration, e);
+    }
+    tokio::spawn(async move {
+        let result = async {
+            let code = callback.code(&state).await?;
+            oauth::exchange_code(&client, &code, &verifier, &redirect_uri).await
+        }.await.map_err(|e| e.to_string());
+        finish_oauth(integration, result);
+    });
+    Ok(url)
+}
+
+
+fn finish_oauth(integration: String, tokens: Result<oauth::Tokens, String>) {
+    match tokens.and_then(|tokens| store_oauth_tokens(&integration, tokens)) {
+        Ok(()) => {
+            log::info!("Signed in to {}", integration);
+            events::publish(AppEvent::OAuthCompleted { integration });
+        }
+        Err(error) => {
+            log::error!("Sign-in to {} failed: {}", integration, error);
+            events::publish(AppEvent::OAuthFailed { integration, error });
+        }
+    }
+}
+
+
+/// Record the user's answer to the first-load permissions prompt and load the plugin. The
+/// answer covers the plugin's module as it is now; a changed module is asked about again.
+pub async fn approve_plugin(id: String, permissions: Vec<String>) -> Result<Vec<PluginInfo>, String> {
+    log::info!("Approving plugin {} with permissions {:?}", id, permissions);
+    let key = plugin_grant_key(&id).await?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_plugin_grant(key, Some(permissions)).map_err(|e| {
+        log::error!("Failed to save plugin grant: {}", e);
+        e.to_string()
+    })?;
+    reload_plugins().await
+}
+
+
+pub async fn revoke_plugin(id: String) -> Result<Vec<PluginInfo>, String> {
+    log::info!("Revoking plugin {}", id);
+    let key = plugin_grant_key(&id).await?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_plugin_grant(key, None).map_err(|e| {
+        log::error!("Failed to remove plugin grant: {}", e);
+        e.to_string()
+    })?;
+    reload_plugins().await
+}
+
+/// The grant key of the discovered plugin in directory `id`, for its module as it is now
+async fn plugin_grant_key(id: &str) -> Result<String, String> {
+    list_plugins().await?
+        .into_iter()
+        .find(|info| info.id == id)
+        .and_then(|info| Some(plugins::grant_key(&info.id, info.module_sha256.as_deref()?)))
+        .ok_or_else(|| {
+            log::error!("No plugin with a readable module in {}", id);
+            tr!("plugin-not-found", id = id)
+        })
+}
+
+
+pub async fn invoke_plugin_command(plugin: String, command: String, input: serde_json::Value) -> Result<String, String> {
+    log::info!("Invoking plugin command {}::{}", plugin, command);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    features::require(Feature::Plugins)?;
+    let mode = config_manager.get_mode().map_err(|e| e.to_string())?;
+    if !modes::behavior(mode).plugin_commands {
+        return Err(tr!("mode-no-plugin-commands"));
+    }
+    startup::ensure(startup::Subsystem::Plugins).await;
+    let (call_plugin, call_command) = (plugin.clone(), command.clone());
+    tokio::task::spawn_blocking(move || plugins::invoke(&call_plugin, "command", &call_command, input))
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e)))
+        .map_err(|e| {
+            log::error!("Plugin command {}::{} failed: {}", plugin, command, e);
+            e.to_string()
+        })
+}
+
+
+/// Run a plugin-registered tool, subject to on_tool_call script hooks
+pub async fn invoke_plugin_tool(plugin: String, tool: String, input: serde_json::Value) -> Result<String, String> {
+    log::info!("Invoking plugin tool {}::{}", plugin, tool);
+    features::require(Feature::Plugins)?;
+    startup::ensure(startup::Subsystem::Scripts).await;
+    startup::ensure(startup::Subsystem::Plugins).await;
+    scripting::check_tool_call(&tool, &input.to_string()).map_err(|e| {
+        log::warn!("{}", e);
+        e
+    })?;
+    let mut span = telemetry::Span::start(format!("execute_tool {}", tool), telemetry::SpanKind::Internal);
+    span.set("gen_ai.operation.name", "execute_tool");
+    span.set("gen_ai.tool.name", tool.clone());
+    span.set("switchboard.plugin", plugin.clone());
+
+    let (called_at, started) = (chrono::Utc::now().to_rfc3339(), std::time::Instant::now());
+    let cache_ttl = ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_tool_cache_settings())
+        .map(|settings| settings.ttl_for(&plugin, &tool))
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read tool cache settings, not caching: {}", e);
+            None
+        });
+    let cache_key = tool_cache::key(&plugin, &tool, &input);
+    if let Some(result) = cache_ttl.and_then(|ttl| tool_cache::RESULTS.get_fresh(&cache_key, ttl)) {
+        log::info!("Reusing cached result of {}::{}", plugin, tool);
+        span.set("switchboard.cache_hit", true);
+        events::publish(AppEvent::ToolCalled { plugin: plugin.clone(), tool: tool.clone(), success: true });
+        let result = limit_tool_result(&plugin, &tool, result);
+        record_tool_invocation(&plugin, &tool, called_at, started, true, Ok(&result));
+        return Ok(result);
+    }
+
+    // Off the async runtime, so calls made together by invoke_plugin_tools overlap
+    let (call_plugin, call_tool) = (plugin.clone(), tool.clone());
+    let result = tokio::task::spawn_blocking(move || plugins::invoke(&call_plugin, "tool", &call_tool, input))
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e)));
+    if let Err(e) = &result {
+        span.fail(e.to_string());
+    }
+    events::publish(AppEvent::ToolCalled {
+        plugin: plugin.clone(),
+        tool: tool.clone(),
+        success: result.is_ok(),
+    });
+    let result = result.map_err(|e| {
+        log::error!("Plugin tool {}::{} failed: {}", plugin, tool, e);
+        e.to_string()
+    });
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            record_tool_invocation(&plugin, &tool, called_at, started, false, Err(&e));
+            return Err(e);
+        }
+    };
+    // The full result is cached, so a later change to the tool's size limit still applies
+    if cache_ttl.is_some() {
+        tool_cache::RESULTS.insert(&cache_key, result.clone());
+    }
+    let result = limit_tool_result(&plugin, &tool, result);
+    record_tool_invocation(&plugin, &tool, called_at, started, false, Ok(&result));
+    Ok(result)
+}
+
+/// Add a call to the tool statistics; a failure to record is logged rather than failing the call
+fn record_tool_invocation(
+    plugin: &str,
+    tool: &str,
+    called_at: String,
+    started: std::time::Instant,
+    cached: bool,
+    result: Result<&str, &str>,
+) {
+    let invocation = ToolInvocation {
+        plugin: plugin.to_string(),
+        tool: tool.to_string(),
+        called_at,
+        duration_ms: started.elapsed().as_millis() as u32,
+        cached,
+        error: result.err().map(str::to_string),
+        result_tokens: result.map(|text| context::estimate_tokens(text) as u32).unwrap_or(0),
+    };
+    let recorded = ConfigManager::new()
+        .and_then(|config_manager| history::HistoryStore::open(config_manager.get_data_dir()))
+        .and_then(|store| store.record_tool_invocation(&invocation));
+    if let Err(e) = recorded {
+        log::warn!("Failed to record call of {}::{}: {}", plugin, tool, e);
+    }
+}
+
+/// Calls, failures, latency and result size for each plugin and each tool, over every recorded call
+pub async fn get_tool_stats() -> Result<ToolStatsReport, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.list_tool_invocations())
+        .map(|invocations| tool_stats::summarize(&invocations))
+        .map_err(|e| {
+            log::error!("Failed to read tool statistics: {}", e);
+            e.to_string()
+        })
+}
+
+/// Run the tool calls of one agent step at the same time, up to the configured number at once.
+/// Each call goes through the same hooks, cache and limits as `invoke_plugin_tool`; outcomes come
+/// back in the order of `calls`, with a failed call's error in its outcome.
+pub async fn invoke_plugin_tools(calls: Vec<ToolCall>) -> Result<Vec<ToolCallOutcome>, String> {
+    let max_parallel = ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_max_parallel_tools())
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read tool parallelism, using the default: {}", e);
+            tool_calls::DEFAULT_MAX_PARALLEL
+        });
+    log::info!("Running {} tool calls, {} at a time", calls.len(), max_parallel);
+    Ok(tool_calls::run_all(calls, max_parallel, |call| async move {
+        let started = std::time::Instant::now();
+        let result = invoke_plugin_tool(call.plugin.clone(), call.tool.clone(), call.input).await;
+        ToolCallOutcome {
+            plugin: call.plugin,
+            tool: call.tool,
+            duration_ms: started.elapsed().as_millis() as u32,
+            error: result.as_ref().err().cloned(),
+            result: result.ok(),
+        }
+    }).await)
+}
+
+
+pub async fn get_max_parallel_tools() -> Result<u32, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_max_parallel_tools().map_err(|e| {
+        log::error!("Failed to get tool parallelism: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Set how many tool calls from one agent step run at once
+pub async fn set_max_parallel_tools(max_parallel: u32) -> Result<(), String> {
+    let max_parallel = tool_calls::validate_max_parallel(max_parallel)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_max_parallel_tools(max_parallel).map_err(|e| {
+        log::error!("Failed to save tool parallelism: {}", e);
+        e.to_string()
+    })
+}
+
+/// `result` cut down to the tool's size limit, marked where content was removed
+fn limit_tool_result(plugin: &str, tool: &str, result: String) -> String {
+    let limits = ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_tool_result_limits())
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read tool result limits, using the defaults: {}", e);
+            ToolResultLimits::default()
+        });
+    let limit = limits.limit_for(plugin, tool);
+    let Some(truncated) = tool_limits::truncate(&result, limit) else {
+        return result;
+    };
+    log::info!(
+        "Truncated result of {}::{} from {} to {} characters ({:?})",
+        plugin, tool, truncated.original_chars, truncated.kept_chars, limit.strategy,
+    );
+    events::publish(AppEvent::ToolResultTruncated {
+        plugin: plugin.to_string(),
+        tool: tool.to_string(),
+        original_chars: truncated.original_chars,
+        kept_chars: truncated.kept_chars,
+        strategy: limit.strategy,
+    });
+    truncated.text
+}
+
+
+pub async fn get_tool_result_limits() -> Result<ToolResultLimits, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_tool_result_limits().map_err(|e| {
+        log::error!("Failed to get tool result limits: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Set the size limits on tool results, by default and per tool
+pub async fn set_tool_result_limits(limits: ToolResultLimits) -> Result<ToolResultLimits, String> {
+    let limits = tool_limits::validate(limits)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_tool_result_limits(limits.clone()).map_err(|e| {
+        log::error!("Failed to save tool result limits: {}", e);
+        e.to_string()
+    })?;
+    Ok(limits)
+}
+
+
+pub async fn get_tool_cache_settings() -> Result<ToolCacheSettings, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_tool_cache_settings().map_err(|e| {
+        log::error!("Failed to get tool cache settings: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Set how long each tool's results are reused for identical calls. Results already cached
+/// stay until they're older than the new TTL.
+pub async fn set_tool_cache_settings(settings: ToolCacheSettings) -> Result<ToolCacheSettings, String> {
+    let settings = tool_cache::validate(settings)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_tool_cache_settings(settings.clone()).map_err(|e| {
+        log::error!("Failed to save tool cache settings: {}", e);
+        e.to_string()
+    })?;
+    Ok(settings)
+}
+
+
+pub async fn reload_scripts() -> Result<Vec<ScriptInfo>, String> {
+    log::info!("Reloading scripts");
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let infos = scripting::reload(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to reload scripts: {}", e);
+        e.to_string()
+    })?;
+    events::publish(AppEvent::ScriptsReloaded { count: infos.len() as u32 });
+    Ok(infos)
+}
+
+
+pub async fn list_scripts() -> Result<Vec<ScriptInfo>, String> {
+    match scripting::loaded_scripts() {
+        Some(infos) => Ok(infos),
+        None => reload_scripts().await,
+    }
+}
+
+
+/// Start the local API server on the configured address (127.0.0.1 by default).
+/// A LAN address is refused until the user has confirmed the exposure warning.
+pub async fn start_server(port: Option<u16>) -> Result<ServerStatus, String> {
+    features::require(Feature::ApiServer)?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let network = config_manager.get_server_network().map_err(|e| {
+        log::error!("Failed to get server network config: {}", e);
+        e.to_string()
+    })?;
+    let bind_ip = network.bind_ip().map_err(|e| e.to_string())?;
+    if !bind_ip.is_loopback() && !network.lan_exposure_confirmed {
+        return Err(format!("Binding to {} has not been confirmed; update the network settings first", bind_ip));
+    }
+    let allowlist = network.parsed_allowlist().map_err(|e| e.to_string())?;
+    let origins = network.parsed_origins().map_err(|e| e.to_string())?;
+    let address = std::net::SocketAddr::new(bind_ip, port.unwrap_or(server::DEFAULT_PORT));
+    log::info!("Starting API server on {}", address);
+    let tokens = config_manager.get_server_tokens().map_err(|e| {
+        log::error!("Failed to get server tokens: {}", e);
+        e.to_string()
+    })?;
+    let tls = config_manager.get_server_tls().map_err(|e| {
+        log::error!("Failed to get server TLS config: {}", e);
+        e.to_string()
+    })?;
+    let auth_enabled = config_manager.get_server_auth_enabled().map_err(|e| {
+        log::error!("Failed to get server authentication setting: {}", e);
+        e.to_string()
+    })?;
+    server::set_auth_enabled(auth_enabled);
+    server::set_tokens(tokens);
+    server::set_allowlist(allowlist);
+    server::set_allowed_origins(origins);
+    server::start(address, tls).await.map_err(|e| {
+        log::error!("Failed to start API server: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn stop_server() -> Result<(), String> {
+    log::info!("Stopping API server");
+    server::stop().map_err(|e| {
+        log::error!("Failed to stop API server: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn get_server_status() -> Result<ServerStatus, String> {
+    Ok(server::status())
+}
+
+
+pub async fn list_server_tokens() -> Result<Vec<ServerTokenInfo>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let tokens = config_manager.get_server_tokens().map_err(|e| {
+        log::error!("Failed to get server tokens: {}", e);
+        e.to_string()
+    })?;
+    Ok(tokens.iter().map(|t| t.info()).collect())
+}
+
+
+pub async fn list_secrets() -> Result<Vec<SecretInfo>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let secrets = config_manager.get_secrets().map_err(|e| {
+        log::error!("Failed to get secrets: {}", e);
+        e.to_string()
+    })?;
+    Ok(secrets.iter().map(|secret| secret.info()).collect())
+}
+
+
+/// Load secrets, apply `change` and persist them
+fn update_secrets<T>(change: impl FnOnce(&mut Vec<vault::Secret>) -> Result<T, String>) -> Result<T, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut secrets = config_manager.get_secrets().map_err(|e| {
+        log::error!("Failed to get secrets: {}", e);
+        e.to_string()
+    })?;
+    let result = change(&mut secrets)?;
+    config_manager.save_secrets(secrets).map_err(|e| {
+        log::error!("Failed to save secrets: {}", e);
+        e.to_string()
+    })?;
+    Ok(result)
+}
+
+
+/// Store a secret that only `owner` can read, replacing any secret of the same name. The owner
+/// is `plugin:<plugin directory>` or `integration:<integration name>`.
+pub async fn set_secret(name: String, owner: String, value: String) -> Result<SecretInfo, String> {
+    log::info!("Storing secret {} for {}", name, owner);
+    update_secrets(|secrets| vault::store(secrets, &name, &owner, value))
+}
+
+
+/// Delete a secret; returns whether it existed
+pub async fn delete_secret(name: String) -> Result<bool, String> {
+    log::info!("Deleting secret {}", name);
+    update_secrets(|secrets| {
+        let before = secrets.len();
+        secrets.retain(|secret| secret.name != name);
+        Ok(secrets.len() < before)
+    })
+}
+
+
+/// Load tokens, apply `change`, persist, and push the new set to a running server
+fn update_server_tokens<T>(change: impl FnOnce(&mut Vec<server_auth::ServerToken>) -> Result<T, String>) -> Result<T, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut tokens = config_manager.get_server_tokens().map_err(|e| {
+        log::error!("Failed to get server tokens: {}", e);
+        e.to_string()
+    })?;
+    let result = change(&mut tokens)?;
+    config_manager.save_server_tokens(tokens.clone()).map_err(|e| {
+        log::error!("Failed to save server tokens: {}", e);
+        e.to_string()
+    })?;
+    if !tokens.is_empty() {
+        server::set_auth_enabled(true);
+    }
+    server::set_tokens(tokens);
+    Ok(result)
+}
+
+
+/// Turn authentication of every API request on, or back off. It turns itself on with the first
+/// token and stays on when the last is revoked; it can only be turned off with no tokens left,
+/// after which /v1 requests are accepted without one.
+pub async fn set_server_auth_enabled(enabled: bool) -> Result<ServerStatus, String> {
+    log::info!("Setting server authentication to {}", enabled);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let tokens = config_manager.get_server_tokens().map_err(|e| {
+        log::error!("Failed to get server tokens: {}", e);
+        e.to_string()
+    })?;
+    if !enabled && !tokens.is_empty() {
+        return Err("Revoke every server token before turning authentication off".to_string());
+    }
+    config_manager.save_server_auth_enabled(enabled).map_err(|e| {
+        log::error!("Failed to save server authentication setting: {}", e);
+        e.to_string()
+    })?;
+    server::set_auth_enabled(enabled);
+    Ok(server::status())
+}
+
+
+/// Create a bearer token for the API server; the secret is only returned here
+pub async fn create_server_token(name: String, scope: TokenScope) -> Result<IssuedServerToken, String> {
+    log::info!("Creating {:?} server token {}", scope, name);
+    update_server_tokens(|tokens| {
+        let (token, secret) = server_auth::ServerToken::issue(name, scope);
+        let issued = IssuedServerToken { info: token.info(), token: secret };
+        tokens.push(token);
+        Ok(issued)
+    })
+}
+
+
+/// Issue a new secret for an existing token; the old secret stops working immediately
+pub async fn rotate_server_token(id: String) -> Result<IssuedServerToken, String> {
+    log::info!("Rotating server token {}", id);
+    update_server_tokens(|tokens| {
+        let token = tokens.iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("Server token {} not found", id))?;
+        let secret = token.rotate();
+        Ok(IssuedServerToken { info: token.info(), token: secret })
+    })
+}
+
+
+/// Set the persona and model every chat request made with a token uses, and the metadata its
+/// answers are tagged with; empty shaping lets the token's requests follow the app's settings again
+pub async fn set_server_token_shaping(id: String, shaping: RequestShaping) -> Result<ServerTokenInfo, String> {
+    let mut shaping = shaping.trimmed();
+    shaping.metadata = history::validate_metadata(shaping.metadata)?;
+    log::info!("Shaping requests of server token {} with {:?}", id, shaping);
+    if let Some(persona) = &shaping.persona {
+        let config_manager = ConfigManager::new().map_err(|e| {
+            log::error!("Failed to create config manager: {}", e);
+            e.to_string()
+        })?;
+        if persona_prompt(&config_manager, persona)?.is_none() {
+            return Err(tr!("shaping-persona-unknown", persona = persona.as_str()));
+        }
+    }
+    update_server_tokens(|tokens| {
+        let token = tokens.iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| tr!("shaping-token-not-found", id = id.as_str()))?;
+        token.shaping = shaping;
+        Ok(token.info())
+    })
+}
+
+
+pub async fn revoke_server_token(id: String) -> Result<(), String> {
+    log::info!("Revoking server token {}", id);
+    update_server_tokens(|tokens| {
+        let before = tokens.len();
+        tokens.retain(|t| t.id != id);
+        if tokens.len() == before {
+            return Err(format!("Server token {} not found", id));
+        }
+        Ok(())
+    })
+}
+
+
+/// Start pairing a companion device: a code, valid for a few minutes, that the device sends to
+/// `POST /pair` to get a token with `scope` (chat by default). The URI is meant for a QR code.
+pub async fn start_pairing(scope: Option<TokenScope>) -> Result<PairingCode, String> {
+    let scope = scope.unwrap_or(TokenScope::Chat);
+    let status = server::status();
+    let Some(address) = status.address.as_deref().and_then(|a| a.parse::<std::net::SocketAddr>().ok()) else {
+        return Err(tr!("pairing-server-not-running"));
+    };
+    let server_url = pairing::server_url(address, status.tls).map_err(|e| e.to_string())?;
+    let fingerprint = if status.tls {
+        let config_manager = ConfigManager::new().map_err(|e| {
+            log::error!("Failed to create config manager: {}", e);
+            e.to_string()
+        })?;
+        let tls = config_manager.get_server_tls().map_err(|e| {
+            log::error!("Failed to get server TLS config: {}", e);
+            e.to_string()
+        })?;
+        tls.map(|tls| server_auth::certificate_fingerprint(&tls)).transpose().map_err(|e| e.to_string())?
+    } else {
+        None
+    };
+    log::info!("Starting {:?} device pairing via {}", scope, server_url);
+    let code = pairing::issue(scope);
+    Ok(PairingCode {
+        uri: pairing::pairing_uri(&server_url, &code, fingerprint.as_deref()),
+        code,
+        scope,
+        expires_at: (chrono::Utc::now() + pairing::PAIRING_TTL).to_rfc3339(),
+    })
+}
+
+
+/// Issue a token to the device presenting a pairing code; called by the API server
+pub(crate) fn complete_pairing(request: pairing::PairingRequest) -> Result<pairing::PairingResponse, String> {
+    let scope = pairing::redeem(&request.code).ok_or_else(|| "Invalid or expired pairing code".to_string())?;
+    let name: String = request.device_name.trim().chars().take(64).collect();
+    let name = if name.is_empty() { "Companion device".to_string() } else { name };
+    let (device, response) = update_server_tokens(|tokens| {
+        let (mut token, secret) = server_auth::ServerToken::issue(name, scope);
+        token.paired_at = Some(token.created_at.clone());
+        let device = PairedDevice {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            scope,
+            paired_at: token.created_at.clone(),
+        };
+        let response = pairing::PairingResponse { device_id: token.id.clone(), token: secret, scope };
+        tokens.push(token);
+        Ok((device, response))
+    })?;
+    log::info!("Paired {:?} device {} ({})", scope, device.name, device.id);
+    events::publish(AppEvent::DevicePaired { device });
+    Ok(response)
+}
+
+
+fn paired_device(token: &server_auth::ServerToken) -> Option<PairedDevice> {
+    Some(PairedDevice {
+        id: token.id.clone(),
+        name: token.name.clone(),
+        scope: token.scope,
+        paired_at: token.paired_at.clone()?,
+    })
+}
+
+
+pub async fn list_paired_devices() -> Result<Vec<PairedDevice>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let tokens = config_manager.get_server_tokens().map_err(|e| {
+        log::error!("Failed to get server tokens: {}", e);
+        e.to_string()
+    })?;
+    Ok(tokens.iter().filter_map(paired_device).collect())
+}
+
+
+/// Revoke a paired device's token; it has to pair again to reconnect
+pub async fn revoke_paired_device(id: String) -> Result<(), String> {
+    log::info!("Revoking paired device {}", id);
+    update_server_tokens(|tokens| {
+        let before = tokens.len();
+        tokens.retain(|t| t.id != id || t.paired_at.is_none());
+        if tokens.len() == before {
+            return Err(tr!("pairing-device-not-found", id = id.as_str()));
+        }
+        Ok(())
+    })?;
+    events::publish(AppEvent::DeviceUnpaired { device_id: id });
+    Ok(())
+}
+
+
+/// Hand a conversation over to a paired device or, with sync on, another of the user's
+/// desktops. The other device gets the draft, the session's tools and any answer left to
+/// resume; this device should stop streaming and let the conversation go.
+pub async fn handoff_conversation(conversation_id: String, device_id: String, draft: Option<String>) -> Result<Handoff, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_unlocked(&config_manager, &conversation_id)?;
+    let sync = config_manager.get_sync_config().map_err(|e| e.to_string())?;
+    let tokens = config_manager.get_server_tokens().map_err(|e| {
+        log::error!("Failed to get server tokens: {}", e);
+        e.to_string()
+    })?;
+    let handoff = {
+        let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+            log::error!("Failed to open history: {}", e);
+            e.to_string()
+        })?;
+        let messages = store.list(&conversation_id).map_err(|e| e.to_string())?;
+        let draft = draft.unwrap_or_default();
+        if messages.is_empty() && draft.trim().is_empty() {
+            return Err(tr!("handoff-nothing"));
+        }
+        let transport = if tokens.iter().any(|t| t.id == device_id && t.paired_at.is_some()) {
+            if !server::status().running {
+                return Err(tr!("handoff-server-not-running"));
+            }
+            HandoffTransport::Server
+        } else if sync.enabled
+            && device_id != sync.device_id
+            && store.sync_devices().map_err(|e| e.to_string())?.iter().any(|device| device.device_id == device_id)
+        {
+            HandoffTransport::Sync
+        } else {
+            return Err(tr!("handoff-unknown-device", device = device_id.as_str()));
+        };
+        let handoff = Handoff {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id,
+            from_device: this_device(&sync),
+            to_device: device_id,
+            transport,
+            draft,
+            enabled_tools: handoff::enabled_tools(&plugins::loaded_plugins().unwrap_or_default()),
+            resume_message_id: handoff::resume_target(&messages),
+            sent_at: chrono::Utc::now().to_rfc3339(),
+            accepted_at: None,
+        };
+        store.save_handoff(&handoff).map_err(|e| {
+            log::error!("Failed to save handoff: {}", e);
+            e.to_string()
+        })?;
+        handoff
+    };
+    log::info!("Handing conversation {} to {:?} device {}", handoff.conversation_id, handoff.transport, handoff.to_device);
+    events::publish(AppEvent::HandoffSent { handoff: handoff.clone() });
+    if handoff.transport == HandoffTransport::Sync {
+        if let Err(e) = sync_now().await {
+            log::warn!("Handoff {} goes out with the next sync: {}", handoff.id, e);
+        }
+    }
+    Ok(handoff)
+}
+
+
+/// This device as handoffs name it: its sync device id, or its machine id without sync
+fn this_device(sync: &SyncConfig) -> String {
+    if !sync.device_id.is_empty() {
+        return sync.device_id.clone();
+    }
+    machine_id::machine_id().unwrap_or_else(|| "desktop".to_string())
+}
+
+
+/// Conversations other desktops handed to this one that haven't been taken over yet
+pub async fn list_handoffs() -> Result<Vec<Handoff>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let sync = config_manager.get_sync_config().map_err(|e| e.to_string())?;
+    if sync.device_id.is_empty() {
+        return Ok(Vec::new());
+    }
+    pending_handoffs(&config_manager, &sync.device_id)
+}
+
+
+fn pending_handoffs(config_manager: &ConfigManager, device: &str) -> Result<Vec<Handoff>, String> {
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| e.to_string())?;
+    let since = (chrono::Utc::now() - chrono::Duration::hours(handoff::PENDING_HOURS)).to_rfc3339();
+    store.pending_handoffs(device, &since).map_err(|e| {
+        log::error!("Failed to list handoffs: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Take over a conversation another desktop handed to this one. The sender hears of it with
+/// the next sync, which is started straight away.
+pub async fn accept_handoff(handoff_id: String) -> Result<Handoff, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let sync = config_manager.get_sync_config().map_err(|e| e.to_string())?;
+    let handoff = mark_handoff_accepted(&config_manager, &handoff_id, &sync.device_id, HandoffTransport::Sync)?;
+    if let Err(e) = sync_now().await {
+        log::warn!("Acceptance of handoff {} goes out with the next sync: {}", handoff_id, e);
+    }
+    Ok(handoff)
+}
+
+
+/// Handoffs waiting for a paired device, for its chat WebSocket to deliver
+pub(crate) fn pending_paired_handoffs(device_id: &str) -> Result<Vec<Handoff>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
+    let mut handoffs = pending_handoffs(&config_manager, device_id)?;
+    handoffs.retain(|handoff| handoff.transport == HandoffTransport::Server);
+    Ok(handoffs)
+}
+
+
+/// A paired device taking over a conversation handed to it; called by the API server
+pub(crate) fn accept_paired_handoff(device_id: &str, handoff_id: &str) -> Result<Handoff, String> {
+    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
+    mark_handoff_accepted(&config_manager, handoff_id, device_id, HandoffTransport::Server)
+}
+
+
+fn mark_handoff_accepted(
+    config_manager: &ConfigManager,
+    handoff_id: &str,
+    device: &str,
+    transport: HandoffTransport,
+) -> Result<Handoff, String> {
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| e.to_string())?;
+    let mut handoff = store.handoff(handoff_id)
+        .map_err(|e| e.to_string())?
+        .filter(|handoff| handoff.to_device == device && handoff.transport == transport)
+        .ok_or_else(|| tr!("handoff-not-found", id = handoff_id))?;
+    if handoff.accepted_at.is_some() {
+        return Err(tr!("handoff-already-accepted"));
+    }
+    handoff.accepted_at = Some(chrono::Utc::now().to_rfc3339());
+    store.save_handoff(&handoff).map_err(|e| {
+        log::error!("Failed to save handoff: {}", e);
+        e.to_string()
+    })?;
+    log::info!("Device {} took over conversation {}", device, handoff.conversation_id);
+    events::publish(AppEvent::HandoffAccepted { handoff: handoff.clone() });
+    Ok(handoff)
+}
+
+
+pub async fn get_server_tls() -> Result<Option<ServerTlsConfig>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_server_tls().map_err(|e| {
+        log::error!("Failed to get server TLS config: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn get_server_network() -> Result<ServerNetworkConfig, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_server_network().map_err(|e| {
+        log::error!("Failed to get server network config: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Change the bind address, client allowlist and the web origins allowed to open WebSockets.
+/// Binding to a non-loopback address needs an allowlist and a second call with
+/// `confirm_lan_exposure` after the user has seen the warning. The allowlist and origins apply
+/// to a running server immediately; a new bind address on the next start.
+pub async fn set_server_network(network: ServerNetworkConfig, confirm_lan_exposure: bool) -> Result<ServerNetworkUpdate, String> {
+    let lan_exposed = network.is_lan_exposed().map_err(|e| e.to_string())?;
+    let allowlist = network.parsed_allowlist().map_err(|e| e.to_string())?;
+    let origins = network.parsed_origins().map_err(|e| e.to_string())?;
+    if lan_exposed && allowlist.is_empty() {
+        return Err("Binding to a LAN address requires at least one allowed client IP or subnet".to_string());
+    }
+
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    if lan_exposed && !confirm_lan_exposure {
+        let auth_enabled = config_manager.get_server_auth_enabled().unwrap_or(false);
+        let tls_enabled = config_manager.get_server_tls().map(|t| t.is_some()).unwrap_or(false);
+        log::warn!("LAN binding to {} requested; waiting for confirmation", network.bind_address);
+        return Ok(ServerNetworkUpdate {
+            applied: false,
+            confirmation_required: true,
+            warning: Some(server_auth::lan_exposure_warning(&network, auth_enabled, tls_enabled)),
+        });
+    }
+
+    let network = ServerNetworkConfig {
+        lan_exposure_confirmed: lan_exposed,
+        allowed_origins: origins.clone(),
+        ..network
+    };
+    config_manager.save_server_network(network).map_err(|e| {
+        log::error!("Failed to save server network config: {}", e);
+        e.to_string()
+    })?;
+    server::set_allowlist(allowlist);
+    server::set_allowed_origins(origins);
+    Ok(ServerNetworkUpdate {
+        applied: true,
+        confirmation_required: false,
+        warning: None,
+    })
+}
+
+
+/// Set (or clear with None) the TLS certs used the next time the server starts.
+/// The certs are validated before saving so a bad path doesn't surface only at startup.
+pub async fn set_server_tls(tls: Option<ServerTlsConfig>) -> Result<(), String> {
+    if let Some(tls) = &tls {
+        server_auth::build_tls_config(tls).map_err(|e| {
+            log::error!("Invalid server TLS config: {}", e);
+            e.to_string()
+        })?;
+    }
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_server_tls(tls).map_err(|e| {
+        log::error!("Failed to save server TLS config: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn get_accessibility_settings() -> Result<AccessibilitySettings, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_accessibility_settings().map_err(|e| {
+        log::error!("Failed to get accessibility settings: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Takes effect from the next response, except that turning reading aloud off stops it at once
+pub async fn set_accessibility_settings(settings: AccessibilitySettings) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let read_aloud = settings.read_aloud;
+    config_manager.save_accessibility_settings(settings).map_err(|e| {
+        log::error!("Failed to save accessibility settings: {}", e);
+        e.to_string()
+    })?;
+    if !read_aloud {
+        read_aloud::stop();
+    }
+    Ok(())
+}
+
+
+pub async fn get_read_aloud_state() -> Result<ReadAloudState, String> {
+    Ok(read_aloud::state())
+}
+
+
+/// Read a message from history aloud, in place of whatever is being read
+pub async fn read_message_aloud(message_id: String) -> Result<ReadAloudState, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    require_message_unlocked(&config_manager, &message_id)?;
+    let message = history::HistoryStore::open(config_manager.get_data_dir())
+        .and_then(|store| store.get(&message_id))
+        .map_err(|e| {
+            log::error!("Failed to load message: {}", e);
+            e.to_string()
+        })?
+        .ok_or_else(|| format!("Message {} not found", message_id))?;
+    let mut splitter = read_aloud::SentenceSplitter::default();
+    let mut sentences = splitter.push(&message.content);
+    sentences.extend(splitter.finish());
+    read_aloud::begin(&message_id);
+    read_aloud::push(&message_id, sentences);
+    Ok(read_aloud::state())
+}
+
+
+/// The webview finished speaking sentence `index` of `message_id`; the next one follows
+pub async fn read_aloud_spoken(message_id: String, index: u32) -> Result<ReadAloudState, String> {
+    Ok(read_aloud::spoken(&message_id, index))
+}
+
+
+/// Stop the current sentence and go on to the next
+pub async fn skip_read_aloud() -> Result<ReadAloudState, String> {
+    Ok(read_aloud::skip())
+}
+
+
+pub async fn pause_read_aloud() -> Result<ReadAloudState, String> {
+    Ok(read_aloud::pause())
+}
+
+
+pub async fn resume_read_aloud() -> Result<ReadAloudState, String> {
+    Ok(read_aloud::resume())
+}
+
+
+/// Stop reading and drop everything queued
+pub async fn stop_read_aloud() -> Result<ReadAloudState, String> {
+    Ok(read_aloud::stop())
+}
+
+
+pub async fn get_appearance() -> Result<AppearanceSettings, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_appearance().map_err(|e| {
+        log::error!("Failed to get appearance: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Save appearance settings and publish AppearanceChanged so every window picks them up
+pub async fn set_appearance(settings: AppearanceSettings) -> Result<(), String> {
+    settings.validate()?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_appearance(settings.clone()).map_err(|e| {
+        log::error!("Failed to save appearance: {}", e);
+        e.to_string()
+    })?;
+    events::publish(AppEvent::AppearanceChanged { settings });
+    Ok(())
+}
+
+
+/// Write portable preferences (appearance, generation, guardrails and the like, but no keys or
+/// tokens) to `path` as JSON, for carrying settings across reinstalls and machines
+pub async fn export_settings(path: String) -> Result<(), String> {
+    log::info!("Exporting settings to {}", path);
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let settings = config_manager.export_settings().map_err(|e| {
+        log::error!("Failed to read settings: {}", e);
+        e.to_string()
+    })?;
+    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| {
+        log::error!("Failed to write settings to {}: {}", path, e);
+        e.to_string()
+    })
+}
+
+
+/// Apply settings written by `export_settings`; secrets and machine-specific settings are kept
+pub async fn import_settings(path: String) -> Result<(), String> {
+    log::info!("Importing settings from {}", path);
+    let json = std::fs::read_to_string(&path).map_err(|e| {
+        log::error!("Failed to read settings from {}: {}", path, e);
+        e.to_string()
+    })?;
+    let settings: config::PortableSettings = serde_json::from_str(&json)
+        .map_err(|e| tr!("settings-unreadable", error = e.to_string()))?;
+    settings.appearance.validate()?;
+    let (appearance, locale) = (settings.appearance.clone(), settings.locale.clone());
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.import_settings(settings).map_err(|e| {
+        log::error!("Failed to import settings: {}", e);
+        e.to_string()
+    })?;
+    i18n::set_locale(locale.as_deref()).map_err(|e| e.to_string())?;
+    events::publish(AppEvent::AppearanceChanged { settings: appearance });
+    status::refresh();
+    Ok(())
+}
+
+
+pub async fn get_onboarding_state() -> Result<OnboardingState, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_onboarding().map(|progress| progress.state()).map_err(|e| {
+        log::error!("Failed to get onboarding progress: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Apply one first-run action. Actions for another step are refused; an action that fails
+/// (say, a key the provider rejects) stays on its step with the error in the returned state.
+pub async fn advance_onboarding(action: OnboardingAction) -> Result<OnboardingState, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let mut progress = config_manager.get_onboarding().map_err(|e| {
+        log::error!("Failed to get onboarding progress: {}", e);
+        e.to_string()
+    })?;
+    let next = onboarding::transition(progress.step, &action)?;
+    let applied = match &action {
+        OnboardingAction::ChooseProvider { provider } => {
+            progress.provider = Some(provider.clone());
+            Ok(())
+        }
+        OnboardingAction::EnterKey { api_key } => {
+            // Ollama takes its server's address where other providers take a key
+            match progress.provider.as_deref().and_then(Provider::from_name).unwrap_or(Provider::Together) {
+                Provider::Ollama => set_ollama_base_url(Some(api_key.clone())).await,
+                provider => set_provider_api_key(provider, Some(api_key.clone())).await,
+            }
+        }
+        OnboardingAction::Validate => get_available_models().await.map(|_| ()),
+        OnboardingAction::PickModel { model } => set_preferred_model(model.clone()).await,
+        OnboardingAction::Skip | OnboardingAction::Back => Ok(()),
+    };
+    match applied {
+        Ok(()) => {
+            log::info!("Onboarding moved from {:?} to {:?}", progress.step, next);
+            progress.step = next;
+            progress.error = None;
+            if next == OnboardingStep::Complete {
+                progress.completed_at = Some(chrono::Utc::now().to_rfc3339());
+            }
+        }
+        Err(e) => {
+            log::warn!("Onboarding step {:?} failed: {}", progress.step, e);
+            progress.error = Some(e);
+        }
+    }
+    let state = progress.state();
+    config_manager.save_onboarding(progress).map_err(|e| {
+        log::error!("Failed to save onboarding progress: {}", e);
+        e.to_string()
+    })?;
+    Ok(state)
+}
+
+
+pub async fn get_mode() -> Result<ModeBehavior, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.get_mode().map(modes::behavior).map_err(|e| {
+        log::error!("Failed to get mode: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Switch app mode if the transition rules allow it, publishing ModeChanged. Returns the
+/// behaviors of the mode now in effect.
+pub async fn set_mode(mode: AppMode) -> Result<ModeBehavior, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let from = config_manager.get_mode().map_err(|e| {
+        log::error!("Failed to get mode: {}", e);
+        e.to_string()
+    })?;
+    if mode == AppMode::Agent {
+        features::require(Feature::AgentMode)?;
+        // Whether agent mode is allowed depends on the tools plugins provide
+        startup::ensure(startup::Subsystem::Plugins).await;
+    }
+    modes::check_transition(from, mode, &modes::context()).map_err(|e| {
+        log::warn!("Refused to switch from {:?} to {:?} mode: {}", from, mode, e);
+        e
+    })?;
+    let behavior = modes::behavior(mode);
+    if from != mode {
+        log::info!("Switching from {:?} to {:?} mode", from, mode);
+        config_manager.save_mode(mode).map_err(|e| {
+            log::error!("Failed to save mode: {}", e);
+            e.to_string()
+        })?;
+        events::publish(AppEvent::ModeChanged { from, to: mode, behavior: behavior.clone() });
+        status::refresh();
+    }
+    Ok(behavior)
+}
+
+
+/// Mode, provider, model, tool sources, background jobs and rate limiting in one snapshot.
+/// StatusChanged events carry the same snapshot whenever it changes.
+pub async fn get_status() -> Result<StatusSnapshot, String> {
+    status::snapshot().map_err(|e| {
+        log::error!("Failed to get status: {}", e);
+        e.to_string()
+    })
+}
+
+
+pub async fn list_feature_flags() -> Result<Vec<FeatureFlag>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    features::list(&config_manager).map_err(|e| {
+        log::error!("Failed to get feature flags: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Turn `feature` on or off, or back to its default with None. Turning a feature off also stops
+/// what it gates: agent mode falls back to chat, the API server stops and plugins are unloaded.
+pub async fn set_feature_flag(feature: Feature, enabled: Option<bool>) -> Result<FeatureFlag, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let before = features::enabled(feature);
+    config_manager.save_feature_flag(feature, enabled).map_err(|e| {
+        log::error!("Failed to save feature flag: {}", e);
+        e.to_string()
+    })?;
+    let flag = features::list(&config_manager).map_err(|e| {
+        log::error!("Failed to get feature flags: {}", e);
+        e.to_string()
+    })?.into_iter().find(|flag| flag.feature == feature).ok_or_else(|| tr!("feature-unknown", feature = format!("{:?}", feature)))?;
+    if flag.enabled == before {
+        return Ok(flag);
+    }
+
+    log::info!("Feature {:?} turned {}", feature, if flag.enabled { "on" } else { "off" });
+    match feature {
+        Feature::AgentMode if !flag.enabled => {
+            let from = config_manager.get_mode().map_err(|e| e.to_string())?;
+            if from == AppMode::Agent {
+                config_manager.save_mode(AppMode::Chat).map_err(|e| {
+                    log::error!("Failed to save mode: {}", e);
+                    e.to_string()
+                })?;
+                events::publish(AppEvent::ModeChanged { from, to: AppMode::Chat, behavior: modes::behavior(AppMode::Chat) });
+            }
+        }
+        Feature::ApiServer if !flag.enabled && server::status().running => {
+            server::stop().map_err(|e| {
+                log::error!("Failed to stop API server: {}", e);
+                e.to_string()
+            })?;
+        }
+        Feature::Plugins => {
+            reload_plugins().await?;
+        }
+        _ => {}
+    }
+    events::publish(AppEvent::FeatureFlagChanged { flag: flag.clone() });
+    status::refresh();
+    Ok(flag)
+}
+
+
+pub async fn get_demo_mode() -> Result<bool, String> {
+    Ok(demo::active())
+}
+
+
+/// Switch demo mode, which answers with canned replies instead of a provider and keeps its own
+/// config and history. Turning it on adds the sample conversations to the demo history.
+pub async fn set_demo_mode(enabled: bool) -> Result<(), String> {
+    if enabled == demo::active() {
+        return Ok(());
+    }
+    if enabled {
+        policy::check_provider(demo::PROVIDER)?;
+    }
+    demo::set_active(enabled);
+    if enabled {
+        seed_demo_history()?;
+    }
+    events::publish(AppEvent::DemoModeChanged { enabled });
+    status::refresh();
+    Ok(())
+}
+
+
+fn seed_demo_history() -> Result<u32, String> {
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    let store = history::HistoryStore::open(config_manager.get_data_dir()).map_err(|e| {
+        log::error!("Failed to open history: {}", e);
+        e.to_string()
+    })?;
+    demo::seed(&store).map_err(|e| {
+        log::error!("Failed to seed demo history: {}", e);
+        e.to_string()
+    })
+}
+
+
+/// Start deferred subsystems once the window is up; spawn once at startup
+pub async fn run_startup() {
+    if demo::active() {
+        // Started in demo mode from the environment
+        let _ = seed_demo_history();
+    }
+    tokio::spawn(status::watch());
+    startup::run().await
+}
+
+
+/// Readiness of the subsystems started after launch
+pub async fn get_startup_status() -> Result<Vec<SubsystemStatus>, String> {
+    Ok(startup::status())
+}
+
+
+/// Format backend messages in `locale`, or follow the OS again for None. Returns the locale
+/// actually used, the closest one with a catalogue.
+pub async fn set_locale(locale: Option<String>) -> Result<String, String> {
+    let negotiated = i18n::set_locale(locale.as_deref()).map_err(|e| e.to_string())?;
+    let config_manager = ConfigManager::new().map_err(|e| {
+        log::error!("Failed to create config manager: {}", e);
+        e.to_string()
+    })?;
+    config_manager.save_locale(locale).map_err(|e| {
+        log::error!("Failed to save locale: {}", e);
+        e.to_string()
+    })?;
+    Ok(negotiated.to_string())
+}
+
+
+/// The Fluent catalogue for the current locale, so the UI formats from the same messages
+pub async fn get_message_catalogue() -> Result<MessageCatalogue, String> {
+    Ok(i18n::catalogue())
+}
+
+
+/// Apply settings given on the command line (`--api-key`, `--demo`, `--portable`); call first thing in main,
+/// with the arguments after the program name
+pub fn init_environment(args: impl IntoIterator<Item = String>) {
+    environment::init_from_args(args)
+}
+
+
+/// Subscribe to the internal event bus (used by the desktop app to forward events to the UI)
+pub fn subscribe_events() -> tokio::sync::broadcast::Receiver<AppEvent> {
+    events::subscribe()
+}
+
+
+/// A chat stream for the window, held back while the window is hidden if the user asked for that
+pub fn hold_while_hidden(stream: MessageStream) -> MessageStream {
+    visibility::hold_while_hidden(stream)
+}
+
+
+/// Whether streamed content is being held back from the hidden window, so the desktop app
+/// needn't forward it as events either
+pub fn holding_chat_events() -> bool {
+    visibility::holding()
+}
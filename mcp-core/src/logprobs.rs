@@ -0,0 +1,134 @@
+//! Per-token log probabilities, for a confidence heatmap of an answer and for finding where a
+//! model was guessing. They're asked for only when the user turns them on, and only from
+//! providers that report them; the tokens are gathered from the provider stream as it passes
+//! and saved with the message details.
+
+use std::sync::{Arc, Mutex};
+use async_openai::types::{ChatChoiceLogprobs, CreateChatCompletionRequest};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::providers;
+
+/// Alternatives reported for each token
+pub const TOP_LOGPROBS: u8 = 5;
+
+/// Tokens kept per answer; the rest of a very long answer goes unrecorded
+pub const MAX_TOKENS: usize = 8192;
+
+/// A likely alternative at a token's position
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct TopToken {
+    pub token: String,
+    pub logprob: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct TokenLogprob {
+    pub token: String,
+    /// Natural log of the token's probability; -9999 when it was outside the top 20
+    pub logprob: f32,
+    pub top: Vec<TopToken>,
+}
+
+impl TokenLogprob {
+    pub fn probability(&self) -> f32 {
+        self.logprob.exp()
+    }
+}
+
+/// Ask for log probabilities in `request`, if its provider reports them
+pub fn request(request: &mut CreateChatCompletionRequest) {
+    if providers::resolve(&request.model).0.reports_logprobs() {
+        request.logprobs = Some(true);
+        request.top_logprobs = Some(TOP_LOGPROBS);
+    }
+}
+
+/// The tokens gathered from a stream so far, shared with whoever saves the answer
+#[derive(Clone, Default)]
+pub struct Collector(Arc<Mutex<Vec<TokenLogprob>>>);
+
+impl Collector {
+    fn extend(&self, logprobs: &ChatChoiceLogprobs) {
+        let Ok(mut tokens) = self.0.lock() else { return };
+        let room = MAX_TOKENS.saturating_sub(tokens.len());
+        tokens.extend(logprobs.content.iter().flatten().take(room).map(|token| TokenLogprob {
+            token: token.token.clone(),
+            logprob: token.logprob,
+            top: token.top_logprobs.iter()
+                .map(|top| TopToken { token: top.token.clone(), logprob: top.logprob })
+                .collect(),
+        }));
+    }
+
+    pub fn take(&self) -> Vec<TokenLogprob> {
+        self.0.lock().map(|mut tokens| std::mem::take(&mut *tokens)).unwrap_or_default()
+    }
+}
+
+/// `stream` unchanged, with the log probabilities of its first choice gathered into `collector`
+pub fn collect(stream: crate::ProviderStream, collector: Collector) -> crate::ProviderStream {
+    Box::pin(stream.inspect(move |chunk| {
+        let logprobs = chunk.as_ref().ok()
+            .and_then(|chunk| chunk.choices.first())
+            .and_then(|choice| choice.logprobs.as_ref());
+        if let Some(logprobs) = logprobs {
+            collector.extend(logprobs);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::types::CreateChatCompletionStreamResponse;
+
+    fn chunk(tokens: serde_json::Value) -> CreateChatCompletionStreamResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": "c1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+            "choices": [{ "index": 0, "delta": { "content": "x" }, "finish_reason": null, "logprobs": { "content": tokens, "refusal": null } }],
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn tokens_are_gathered_as_the_stream_passes() {
+        let chunks = vec![
+            Ok(chunk(serde_json::json!([{ "token": "Paris", "logprob": -0.01, "bytes": null, "top_logprobs": [
+                { "token": "Paris", "logprob": -0.01, "bytes": null },
+                { "token": "Lyon", "logprob": -4.6, "bytes": null },
+            ] }]))),
+            Ok(chunk(serde_json::json!([{ "token": ".", "logprob": -0.7, "bytes": null, "top_logprobs": [] }]))),
+        ];
+        let collector = Collector::default();
+        let stream = collect(Box::pin(futures::stream::iter(chunks)), collector.clone());
+        assert_eq!(stream.count().await, 2);
+
+        let tokens = collector.take();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].top[1], TopToken { token: "Lyon".to_string(), logprob: -4.6 });
+        assert!(tokens[0].probability() > 0.98 && tokens[1].probability() < 0.5);
+        assert!(collector.take().is_empty());
+    }
+
+    #[test]
+    fn only_providers_that_report_them_are_asked() {
+        let mut request = async_openai::types::CreateChatCompletionRequestArgs::default()
+            .model("openai/gpt-4o")
+            .messages(Vec::new())
+            .build()
+            .unwrap();
+        super::request(&mut request);
+        assert_eq!((request.logprobs, request.top_logprobs), (Some(true), Some(TOP_LOGPROBS)));
+
+        request = async_openai::types::CreateChatCompletionRequestArgs::default()
+            .model("anthropic/claude-sonnet-4-5")
+            .messages(Vec::new())
+            .build()
+            .unwrap();
+        super::request(&mut request);
+        assert_eq!(request.logprobs, None);
+    }
+}
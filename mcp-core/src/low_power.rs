@@ -0,0 +1,180 @@
+//! The low-power profile, for laptops away from the charger or on a metered connection. While
+//! it's on, subsystems aren't warmed after launch but start on first use, the background loops
+//! check far less often, and scheduled syncs, template syncs, evals and idle maintenance wait
+//! until it's off. Syncing by hand still works. It follows the battery and connection by
+//! default, can be forced on or off in the settings, and overridden for the rest of the session.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::config::ConfigManager;
+use crate::events::{self, AppEvent};
+
+/// How long a reading of the battery and connection is reused; reading them starts processes
+const READING_TTL: Duration = Duration::from_secs(60);
+
+/// How many times longer the background loops wait between checks while the profile is on
+const POLL_STRETCH: u32 = 4;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum LowPowerMode {
+    /// On while running on battery or a metered connection, as the settings below allow
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct LowPowerSettings {
+    pub mode: LowPowerMode,
+    /// In Auto, turn the profile on when running on battery
+    pub on_battery: bool,
+    /// In Auto, turn the profile on when the connection is metered
+    pub on_metered: bool,
+}
+
+impl Default for LowPowerSettings {
+    fn default() -> Self {
+        LowPowerSettings { mode: LowPowerMode::Auto, on_battery: true, on_metered: true }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum LowPowerReason {
+    Battery,
+    Metered,
+    /// The mode is Always
+    Settings,
+    /// Turned on for this session
+    Session,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct LowPowerStatus {
+    pub active: bool,
+    /// Why it's on; empty when it's off
+    pub reasons: Vec<LowPowerReason>,
+    pub settings: LowPowerSettings,
+    /// Set for this session only, over the settings; None follows them
+    pub session_override: Option<bool>,
+    /// None where it can't be told
+    pub on_battery: Option<bool>,
+    pub metered: Option<bool>,
+}
+
+struct Reading {
+    at: Instant,
+    on_battery: Option<bool>,
+    metered: Option<bool>,
+}
+
+static SESSION_OVERRIDE: Mutex<Option<bool>> = Mutex::new(None);
+static READING: Mutex<Option<Reading>> = Mutex::new(None);
+/// Whether the profile was on when last checked, so changes are published once
+static ACTIVE: Mutex<bool> = Mutex::new(false);
+
+/// Why the profile is on given the settings, the session's override and the power source and
+/// connection; empty when it's off
+pub fn reasons(settings: &LowPowerSettings, session_override: Option<bool>, on_battery: Option<bool>, metered: Option<bool>) -> Vec<LowPowerReason> {
+    match (session_override, settings.mode) {
+        (Some(true), _) => vec![LowPowerReason::Session],
+        (Some(false), _) | (None, LowPowerMode::Never) => Vec::new(),
+        (None, LowPowerMode::Always) => vec![LowPowerReason::Settings],
+        (None, LowPowerMode::Auto) => {
+            let mut reasons = Vec::new();
+            if settings.on_battery && on_battery == Some(true) {
+                reasons.push(LowPowerReason::Battery);
+            }
+            if settings.on_metered && metered == Some(true) {
+                reasons.push(LowPowerReason::Metered);
+            }
+            reasons
+        }
+    }
+}
+
+/// Force the profile on or off until the app quits; None follows the settings again
+pub fn set_session_override(active: Option<bool>) {
+    if let Ok(mut session_override) = SESSION_OVERRIDE.lock() {
+        *session_override = active;
+    }
+}
+
+fn session_override() -> Option<bool> {
+    SESSION_OVERRIDE.lock().ok().and_then(|session_override| *session_override)
+}
+
+/// The power source and connection, read again once the last reading is READING_TTL old
+async fn reading() -> (Option<bool>, Option<bool>) {
+    if let Ok(reading) = READING.lock() {
+        if let Some(reading) = reading.as_ref().filter(|reading| reading.at.elapsed() < READING_TTL) {
+            return (reading.on_battery, reading.metered);
+        }
+    }
+    let (on_battery, metered) = tokio::task::spawn_blocking(|| (crate::power::on_battery(), crate::metered::metered()))
+        .await
+        .unwrap_or((None, None));
+    if let Ok(mut reading) = READING.lock() {
+        *reading = Some(Reading { at: Instant::now(), on_battery, metered });
+    }
+    (on_battery, metered)
+}
+
+pub async fn status() -> anyhow::Result<LowPowerStatus> {
+    let settings = ConfigManager::new()?.get_low_power_settings()?;
+    let session_override = session_override();
+    let (on_battery, metered) = match (session_override, settings.mode) {
+        // Nothing to read when the answer doesn't depend on it
+        (None, LowPowerMode::Auto) => reading().await,
+        _ => (None, None),
+    };
+    let reasons = reasons(&settings, session_override, on_battery, metered);
+    let active = !reasons.is_empty();
+    let changed = ACTIVE.lock().map(|mut was| std::mem::replace(&mut *was, active) != active).unwrap_or(false);
+    if changed {
+        log::info!("Low-power profile {} ({:?})", if active { "on" } else { "off" }, reasons);
+        events::publish(AppEvent::LowPowerChanged { active, reasons: reasons.clone() });
+    }
+    Ok(LowPowerStatus { active, reasons, settings, session_override, on_battery, metered })
+}
+
+/// Whether the profile is on; off if the settings can't be read
+pub async fn active() -> bool {
+    status().await
+        .map(|status| status.active)
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to check the low-power profile, leaving it off: {}", e);
+            false
+        })
+}
+
+/// `interval` between a background loop's checks, stretched while the profile is on
+pub async fn poll_interval(interval: Duration) -> Duration {
+    if active().await { interval * POLL_STRETCH } else { interval }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_override_beats_settings_and_readings() {
+        let auto = LowPowerSettings::default();
+        assert_eq!(reasons(&auto, None, Some(true), Some(true)), [LowPowerReason::Battery, LowPowerReason::Metered]);
+        assert_eq!(reasons(&auto, None, None, Some(false)), []);
+        assert_eq!(reasons(&LowPowerSettings { on_battery: false, ..auto.clone() }, None, Some(true), None), []);
+        assert_eq!(reasons(&auto, Some(false), Some(true), Some(true)), []);
+        assert_eq!(reasons(&auto, Some(true), Some(false), None), [LowPowerReason::Session]);
+
+        let always = LowPowerSettings { mode: LowPowerMode::Always, ..auto.clone() };
+        assert_eq!(reasons(&always, None, Some(false), Some(false)), [LowPowerReason::Settings]);
+        assert_eq!(reasons(&always, Some(false), None, None), []);
+        let never = LowPowerSettings { mode: LowPowerMode::Never, ..auto };
+        assert_eq!(reasons(&never, None, Some(true), Some(true)), []);
+    }
+}
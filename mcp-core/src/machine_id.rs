@@ -0,0 +1,45 @@
+//! A stable identifier for this machine, which the config key is derived from. Unlike the
+//! hostname and user name it survives renames.
+
+/// The OS's machine identifier, or None where there isn't one to read
+pub fn machine_id() -> Option<String> {
+    let id = read_machine_id()?;
+    let id = id.trim();
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn read_machine_id() -> Option<String> {
+    // systemd's id, falling back to D-Bus's copy on systems without systemd
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"].iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+}
+
+#[cfg(target_os = "macos")]
+fn read_machine_id() -> Option<String> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()?;
+    // "IOPlatformUUID" = "01234567-89AB-CDEF-0123-456789ABCDEF"
+    String::from_utf8_lossy(&output.stdout).lines()
+        .find(|line| line.contains("\"IOPlatformUUID\""))
+        .and_then(|line| line.rsplit('"').nth(1).map(str::to_string))
+}
+
+#[cfg(target_os = "windows")]
+fn read_machine_id() -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args(["query", r"HKLM\SOFTWARE\Microsoft\Cryptography", "/v", "MachineGuid"])
+        .output()
+        .ok()?;
+    // "    MachineGuid    REG_SZ    01234567-89ab-cdef-0123-456789abcdef"
+    String::from_utf8_lossy(&output.stdout).lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().last().map(str::to_string))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read_machine_id() -> Option<String> {
+    None
+}
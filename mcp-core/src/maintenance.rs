@@ -0,0 +1,221 @@
+//! Housekeeping left for when the user is away: rebuilding the history database, pruning
+//! caches, embedding prompts for insights and refreshing the model lists. A job runs only once
+//! nothing has been typed or sent for a while, no answer is streaming and the machine is on
+//! mains power, one job at a time so the user coming back never waits long.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, TS)]
+#[ts(export)]
+pub enum MaintenanceJob {
+    /// Rebuild the history database, handing back the space deleted conversations left
+    Vacuum,
+    /// Drop expired entries from the in-memory caches
+    PruneCaches,
+    /// Embed prompts that have no stored embedding yet, for insights and duplicate detection
+    BackfillEmbeddings,
+    /// Fetch the providers' model lists, prices and context lengths again
+    RefreshModels,
+}
+
+/// Every job, in the order due ones are run
+pub const JOBS: [MaintenanceJob; 4] = [
+    MaintenanceJob::PruneCaches,
+    MaintenanceJob::RefreshModels,
+    MaintenanceJob::BackfillEmbeddings,
+    MaintenanceJob::Vacuum,
+];
+
+impl MaintenanceJob {
+    /// How long after a run the job is due again
+    pub fn interval(self) -> Duration {
+        match self {
+            MaintenanceJob::Vacuum => Duration::from_secs(7 * 24 * 3600),
+            MaintenanceJob::PruneCaches => Duration::from_secs(3600),
+            MaintenanceJob::BackfillEmbeddings => Duration::from_secs(3600),
+            MaintenanceJob::RefreshModels => Duration::from_secs(6 * 3600),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    /// Minutes without activity before jobs start
+    pub idle_minutes: u32,
+    /// Run jobs on battery power too, unless the low-power profile is on
+    #[serde(default)]
+    pub on_battery: bool,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        MaintenanceSettings { enabled: true, idle_minutes: 5, on_battery: false }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct JobStatus {
+    pub job: MaintenanceJob,
+    /// When the job last finished, in this or an earlier session
+    pub last_run_at: Option<String>,
+    pub due: bool,
+    /// What the last run this session did, e.g. "Freed 12 MB"
+    pub last_summary: Option<String>,
+    pub last_error: Option<String>,
+    pub last_duration_ms: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct MaintenanceStatus {
+    pub settings: MaintenanceSettings,
+    /// Seconds since the user last did anything
+    pub idle_secs: u32,
+    /// Whether jobs may run now: idle long enough, nothing streaming and on mains power
+    pub can_run: bool,
+    /// None where the power source can't be told
+    pub on_battery: Option<bool>,
+    pub running: Option<MaintenanceJob>,
+    pub jobs: Vec<JobStatus>,
+}
+
+struct LastRun {
+    summary: Option<String>,
+    error: Option<String>,
+    duration_ms: u32,
+}
+
+struct State {
+    last_activity: Instant,
+    running: Option<MaintenanceJob>,
+    runs: BTreeMap<MaintenanceJob, LastRun>,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+fn with_state<T>(f: impl FnOnce(&mut State) -> T) -> Option<T> {
+    let mut state = STATE.lock().ok()?;
+    let state = state.get_or_insert_with(|| State { last_activity: Instant::now(), running: None, runs: BTreeMap::new() });
+    Some(f(state))
+}
+
+/// Note that the user did something, putting maintenance off until they're idle again
+pub fn note_activity() {
+    with_state(|state| state.last_activity = Instant::now());
+}
+
+pub fn idle_for() -> Duration {
+    with_state(|state| state.last_activity.elapsed()).unwrap_or_default()
+}
+
+/// Whether jobs may run, given how long the user has been idle, whether an answer is streaming
+/// and the power source
+pub fn can_run(settings: &MaintenanceSettings, idle_for: Duration, responding: bool, on_battery: Option<bool>) -> bool {
+    settings.enabled
+        && idle_for >= Duration::from_secs(u64::from(settings.idle_minutes) * 60)
+        && !responding
+        && (settings.on_battery || on_battery != Some(true))
+}
+
+/// Whether `job`, last run at `last_run` (RFC 3339), is due at `now`
+pub fn is_due(job: MaintenanceJob, last_run: Option<&str>, now: chrono::DateTime<chrono::Utc>) -> bool {
+    let Some(last_run) = last_run.and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok()) else {
+        return true;
+    };
+    let interval = chrono::Duration::from_std(job.interval()).unwrap_or(chrono::Duration::MAX);
+    now.signed_duration_since(last_run) >= interval
+}
+
+/// The first job in JOBS order that's due
+pub fn next_due(last_runs: &BTreeMap<MaintenanceJob, String>, now: chrono::DateTime<chrono::Utc>) -> Option<MaintenanceJob> {
+    JOBS.into_iter().find(|&job| is_due(job, last_runs.get(&job).map(String::as_str), now))
+}
+
+/// Mark `job` as running; false if another job already is
+pub fn start(job: MaintenanceJob) -> bool {
+    with_state(|state| state.running.is_none() && { state.running = Some(job); true }).unwrap_or(false)
+}
+
+/// Record how a run of `job` went and mark nothing running
+pub fn finish(job: MaintenanceJob, result: &Result<String, String>, elapsed: Duration) {
+    with_state(|state| {
+        state.running = None;
+        state.runs.insert(job, LastRun {
+            summary: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().cloned(),
+            duration_ms: elapsed.as_millis() as u32,
+        });
+    });
+}
+
+pub fn status(settings: MaintenanceSettings, last_runs: &BTreeMap<MaintenanceJob, String>, responding: bool, on_battery: Option<bool>) -> MaintenanceStatus {
+    let now = chrono::Utc::now();
+    let idle_for = idle_for();
+    let (running, jobs) = with_state(|state| {
+        let jobs = JOBS.into_iter()
+            .map(|job| {
+                let last_run_at = last_runs.get(&job).cloned();
+                let run = state.runs.get(&job);
+                JobStatus {
+                    job,
+                    due: is_due(job, last_run_at.as_deref(), now),
+                    last_run_at,
+                    last_summary: run.and_then(|run| run.summary.clone()),
+                    last_error: run.and_then(|run| run.error.clone()),
+                    last_duration_ms: run.map(|run| run.duration_ms),
+                }
+            })
+            .collect();
+        (state.running, jobs)
+    }).unwrap_or_default();
+    MaintenanceStatus {
+        can_run: can_run(&settings, idle_for, responding, on_battery),
+        settings,
+        idle_secs: idle_for.as_secs() as u32,
+        on_battery,
+        running,
+        jobs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_wait_for_an_idle_user_on_mains_power() {
+        let settings = MaintenanceSettings::default();
+        let idle = Duration::from_secs(6 * 60);
+        assert!(can_run(&settings, idle, false, Some(false)));
+        assert!(can_run(&settings, idle, false, None));
+        assert!(!can_run(&settings, Duration::from_secs(60), false, Some(false)));
+        assert!(!can_run(&settings, idle, true, Some(false)));
+        assert!(!can_run(&settings, idle, false, Some(true)));
+        assert!(can_run(&MaintenanceSettings { on_battery: true, ..settings.clone() }, idle, false, Some(true)));
+        assert!(!can_run(&MaintenanceSettings { enabled: false, ..settings }, idle, false, Some(false)));
+    }
+
+    #[test]
+    fn jobs_are_due_once_their_interval_has_passed() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-03-10T12:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        let mut last_runs = BTreeMap::new();
+        assert_eq!(next_due(&last_runs, now), Some(MaintenanceJob::PruneCaches));
+
+        last_runs.insert(MaintenanceJob::PruneCaches, "2026-03-10T11:30:00Z".to_string());
+        last_runs.insert(MaintenanceJob::RefreshModels, "2026-03-10T11:00:00Z".to_string());
+        last_runs.insert(MaintenanceJob::BackfillEmbeddings, "2026-03-10T10:59:00Z".to_string());
+        assert_eq!(next_due(&last_runs, now), Some(MaintenanceJob::BackfillEmbeddings));
+
+        last_runs.insert(MaintenanceJob::BackfillEmbeddings, "2026-03-10T11:30:00Z".to_string());
+        last_runs.insert(MaintenanceJob::Vacuum, "2026-03-05T12:00:00Z".to_string());
+        assert_eq!(next_due(&last_runs, now), None);
+        assert!(is_due(MaintenanceJob::Vacuum, Some("2026-03-03T11:00:00Z"), now));
+    }
+}
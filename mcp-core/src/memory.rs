@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+use async_openai::Client;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::{ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::generation::MessageStream;
+use crate::StreamMessage;
+
+/// Most memories injected into a system prompt, newest first
+const MAX_INJECTED_MEMORIES: usize = 50;
+
+/// A durable fact or preference learned from a conversation
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct Memory {
+    pub id: String,
+    pub content: String,
+    /// Conversation the fact was learned from
+    pub conversation_id: Option<String>,
+    pub created_at: String,
+}
+
+impl Memory {
+    pub fn new(content: String, conversation_id: Option<String>) -> Self {
+        Memory {
+            id: uuid::Uuid::new_v4().to_string(),
+            content,
+            conversation_id,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// System prompt section listing what is remembered about the user
+pub fn system_prompt(memories: &[Memory]) -> Option<String> {
+    if memories.is_empty() {
+        return None;
+    }
+    let facts: Vec<String> = memories.iter()
+        .rev()
+        .take(MAX_INJECTED_MEMORIES)
+        .map(|m| format!("- {}", m.content))
+        .collect();
+    Some(format!(
+        "Things you remember about the user from earlier conversations:\n{}\nUse them when relevant; don't mention this list.",
+        facts.join("\n")
+    ))
+}
+
+/// Call `on_answer` with the full answer once `stream` ends without an error
+pub fn on_answer<F>(stream: MessageStream, on_answer: F) -> MessageStream
+where
+    F: FnOnce(String) + Send + 'static,
+{
+    let answer = Arc::new(Mutex::new(Some(String::new())));
+    let collector = answer.clone();
+    Box::pin(stream.inspect(move |message| {
+        let Ok(mut answer) = collector.lock() else { return };
+        match message {
+            StreamMessage::Content(content) => {
+                if let Some(text) = answer.as_mut() {
+                    text.push_str(content);
+                }
+            }
+            StreamMessage::Replace(replacement) => {
+                if let Some(text) = answer.as_mut() {
+                    *text = replacement.clone();
+                }
+            }
+            StreamMessage::Error(_) => *answer = None,
+            StreamMessage::Complete | StreamMessage::ProviderSwitched { .. } | StreamMessage::Usage { .. } | StreamMessage::AgentTurn { .. } => {}
+        }
+    }).chain(futures::stream::once(async move {
+        if let Some(text) = answer.lock().ok().and_then(|mut answer| answer.take()) {
+            on_answer(text);
+        }
+    }).filter_map(|()| async { None })))
+}
+
+/// Ask `model` for new durable facts in one exchange, given what is already remembered
+pub async fn extract(
+    client: &Client<OpenAIConfig>,
+    model: &str,
+    prompt: &str,
+    answer: &str,
+    known: &[Memory],
+) -> Result<Vec<String>> {
+    let known = known.iter().map(|m| format!("- {}", m.content)).collect::<Vec<_>>().join("\n");
+    let instruction = format!(
+        "Extract durable facts about the user from this exchange: preferences, their projects, tools \
+         they use, and how they like answers. Skip anything temporary or specific to this one \
+         question, and anything already known. Reply with only a JSON array of short third-person \
+         statements such as [\"User prefers TypeScript\"], or [] if there is nothing new.\n\n\
+         Already known:\n{}\n\nUser: {}\n\nAssistant: {}",
+        if known.is_empty() { "(nothing)".to_string() } else { known },
+        prompt,
+        answer
+    );
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages(vec![ChatCompletionRequestUserMessageArgs::default().content(instruction).build()?.into()])
+        .build()?;
+    let response = client.chat().create(request).await?;
+    let content = response.choices.first()
+        .and_then(|choice| choice.message.content.clone())
+        .unwrap_or_default();
+    parse_facts(&content)
+}
+
+fn parse_facts(content: &str) -> Result<Vec<String>> {
+    // Models sometimes wrap the array in prose or a code fence
+    let start = content.find('[').ok_or_else(|| anyhow::anyhow!("No JSON array in memory extraction"))?;
+    let end = content.rfind(']').ok_or_else(|| anyhow::anyhow!("No JSON array in memory extraction"))?;
+    let facts: Vec<String> = serde_json::from_str(&content[start..=end])?;
+    Ok(facts.into_iter()
+        .map(|fact| fact.trim().to_string())
+        .filter(|fact| !fact.is_empty())
+        .collect())
+}
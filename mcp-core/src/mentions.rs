@@ -0,0 +1,233 @@
+//! `@` mentions of models and plugin tools in an outgoing message. `@gpt-4o` sends just that
+//! message to the model, leaving the conversation's model as it was; `@search` tells the model
+//! to call the tool in its next step and hands the tool to the UI for its agent loop. A mention
+//! is a model's id (with or without the provider in front) or a tool's name, optionally after
+//! its plugin as `@plugin/tool`; anything else, like `@alice`, is left as typed. File
+//! references (`@~/notes.md`, `@/tmp/a.txt`) start with `~`, `/` or a quote and never match.
+
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::plugins::{PluginInfo, PluginStatus, PluginTool};
+use crate::providers;
+
+/// Most suggestions offered for one prefix
+pub const MAX_SUGGESTIONS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum MentionKind {
+    Model,
+    Tool,
+}
+
+/// A model or tool the composer can offer while a mention is being typed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct MentionSuggestion {
+    pub kind: MentionKind,
+    /// What to put in the message, `@` included
+    pub mention: String,
+    pub label: String,
+    /// The model's organization or the tool's description
+    pub detail: String,
+}
+
+/// What a message's mentions ask for
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct MessageMentions {
+    /// The message as it's sent: model mentions removed, tool mentions left as the tool's name
+    pub message: String,
+    /// The qualified id of the model answering this message, if one was mentioned
+    pub model: Option<String>,
+    /// Tools the model is told to call in its next step, in the order they were mentioned
+    pub tools: Vec<PluginTool>,
+}
+
+fn pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?:^|[\s(\[])(@[A-Za-z0-9][A-Za-z0-9._:/-]*)").expect("valid mention pattern"))
+}
+
+/// The names mentioned in `message` with where each mention is, `@` included
+fn candidates(message: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    pattern().captures_iter(message)
+        .filter_map(|captures| {
+            let matched = captures.get(1)?;
+            // Punctuation closing a sentence isn't part of the name
+            let name = matched.as_str()[1..].trim_end_matches(['.', ',', ':', '/']);
+            (!name.is_empty()).then(|| (matched.start()..matched.start() + 1 + name.len(), name))
+        })
+        .collect()
+}
+
+/// Whether `message` has anything that could be a mention, so the model list and tools are only
+/// looked up when it might
+pub fn has_candidates(message: &str) -> bool {
+    !candidates(message).is_empty()
+}
+
+/// The tools of the plugins that loaded, the only ones a message can mention
+pub fn loaded_tools(plugins: &[PluginInfo]) -> Vec<PluginTool> {
+    plugins.iter()
+        .filter(|plugin| plugin.status == PluginStatus::Loaded)
+        .flat_map(|plugin| plugin.tools.iter().cloned())
+        .collect()
+}
+
+/// The one model `name` refers to: an exact id, else a unique match of the id without its
+/// provider or of its last segment
+fn find_model<'a>(name: &str, models: &'a [ModelInfo]) -> Option<&'a ModelInfo> {
+    let name = name.to_lowercase();
+    if let Some(model) = models.iter().find(|model| model.id.to_lowercase() == name) {
+        return Some(model);
+    }
+    let mut matches = models.iter().filter(|model| {
+        let upstream = providers::resolve(&model.id).1.to_lowercase();
+        upstream == name || upstream.rsplit('/').next() == Some(name.as_str())
+    });
+    let model = matches.next()?;
+    matches.next().is_none().then_some(model)
+}
+
+/// The one tool `name` refers to, by its name or as `plugin/tool`
+fn find_tool<'a>(name: &str, tools: &'a [PluginTool]) -> Option<&'a PluginTool> {
+    let mut matches = tools.iter().filter(|tool| {
+        tool.name.eq_ignore_ascii_case(name) || format!("{}/{}", tool.plugin, tool.name).eq_ignore_ascii_case(name)
+    });
+    let tool = matches.next()?;
+    matches.next().is_none().then_some(tool)
+}
+
+/// The mentions in `message` among `models` and `tools`. Only the first model mentioned routes
+/// the message; later ones are left as typed.
+pub fn parse(message: &str, models: &[ModelInfo], tools: &[PluginTool]) -> MessageMentions {
+    let mut mentions = MessageMentions::default();
+    let mut copied = 0;
+    for (range, name) in candidates(message) {
+        let mut end = range.end;
+        // Models first, since `@plugin/tool` reads like a model id
+        let replacement = match (mentions.model.is_none(), find_model(name, models)) {
+            (true, Some(model)) => {
+                mentions.model = Some(model.id.clone());
+                // Along with the space after it
+                if message[end..].starts_with(' ') {
+                    end += 1;
+                }
+                Some(String::new())
+            }
+            _ => find_tool(name, tools).map(|tool| {
+                if !mentions.tools.contains(tool) {
+                    mentions.tools.push(tool.clone());
+                }
+                tool.name.clone()
+            }),
+        };
+        if let Some(replacement) = replacement {
+            mentions.message.push_str(&message[copied..range.start]);
+            mentions.message.push_str(&replacement);
+            copied = end;
+        }
+    }
+    mentions.message.push_str(&message[copied..]);
+    if mentions.model.is_some() {
+        // A mention at the end leaves the space before it behind
+        mentions.message.truncate(mentions.message.trim_end().len());
+    }
+    mentions
+}
+
+/// The instruction sent with a message that mentions `tools`, or None if it mentions none
+pub fn tool_instruction(tools: &[PluginTool]) -> Option<String> {
+    if tools.is_empty() {
+        return None;
+    }
+    let listed = tools.iter()
+        .map(|tool| match tool.description.trim() {
+            "" => format!("- {} (from the {} plugin)", tool.name, tool.plugin),
+            description => format!("- {} (from the {} plugin): {}", tool.name, tool.plugin, description),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(format!(
+        "The user asked for these tools to be used for their message. Call them in your next step rather than answering from memory:\n{}",
+        listed
+    ))
+}
+
+/// Models and tools whose mention starts with `prefix` (typed without the `@`), tools first
+/// since there are fewer of them, at most `limit` in all
+pub fn suggest(prefix: &str, models: &[ModelInfo], tools: &[PluginTool], limit: usize) -> Vec<MentionSuggestion> {
+    let prefix = prefix.trim_start_matches('@').to_lowercase();
+    let tool_suggestions = tools.iter()
+        .filter(|tool| tool.name.to_lowercase().starts_with(&prefix))
+        .map(|tool| MentionSuggestion {
+            kind: MentionKind::Tool,
+            // Qualified only where another plugin has a tool of the same name
+            mention: match find_tool(&tool.name, tools) {
+                Some(_) => format!("@{}", tool.name),
+                None => format!("@{}/{}", tool.plugin, tool.name),
+            },
+            label: tool.name.clone(),
+            detail: tool.description.clone(),
+        });
+    let model_suggestions = models.iter()
+        .filter(|model| {
+            let upstream = providers::resolve(&model.id).1.to_lowercase();
+            model.id.to_lowercase().starts_with(&prefix)
+                || upstream.starts_with(&prefix)
+                || upstream.rsplit('/').next().is_some_and(|last| last.starts_with(&prefix))
+        })
+        .map(|model| MentionSuggestion {
+            kind: MentionKind::Model,
+            mention: format!("@{}", model.id),
+            label: model.display_name.clone(),
+            detail: model.organization.clone(),
+        });
+    tool_suggestions.chain(model_suggestions).take(limit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str) -> ModelInfo {
+        ModelInfo { id: id.to_string(), display_name: id.to_string(), organization: "Test".to_string(), context_length: None, pricing: None }
+    }
+
+    fn tool(plugin: &str, name: &str) -> PluginTool {
+        PluginTool { plugin: plugin.to_string(), name: name.to_string(), description: String::new(), input_schema: None }
+    }
+
+    #[test]
+    fn mentions_route_to_models_and_hint_tools() {
+        let models = [model("openai/gpt-4o"), model("groq/openai/gpt-oss-120b"), model("together/openai/gpt-oss-120b")];
+        let tools = [tool("web", "search"), tool("files", "search"), tool("web", "fetch")];
+
+        let mentions = parse("@gpt-4o please @fetch this, then ask @alice. See @~/notes.md", &models, &tools);
+        assert_eq!(mentions.model.as_deref(), Some("openai/gpt-4o"));
+        assert_eq!(mentions.tools, [tool("web", "fetch")]);
+        assert_eq!(mentions.message, "please fetch this, then ask @alice. See @~/notes.md");
+
+        // Ambiguous names are left as typed until qualified
+        let mentions = parse("@gpt-oss-120b @search @web/search", &models, &tools);
+        assert_eq!(mentions.model, None);
+        assert_eq!(mentions.tools, [tool("web", "search")]);
+        assert_eq!(mentions.message, "@gpt-oss-120b @search search");
+        assert_eq!(parse("@groq/openai/gpt-oss-120b hi", &models, &tools).model.as_deref(), Some("groq/openai/gpt-oss-120b"));
+    }
+
+    #[test]
+    fn suggestions_qualify_ambiguous_tools() {
+        let models = [model("openai/gpt-4o"), model("anthropic/claude-sonnet-4-5")];
+        let tools = [tool("web", "search"), tool("files", "search"), tool("web", "fetch")];
+        let mentions: Vec<String> = suggest("@s", &models, &tools, 10).into_iter().map(|s| s.mention).collect();
+        assert_eq!(mentions, ["@web/search", "@files/search"]);
+        let mentions: Vec<String> = suggest("gpt", &models, &tools, 10).into_iter().map(|s| s.mention).collect();
+        assert_eq!(mentions, ["@openai/gpt-4o"]);
+        assert_eq!(suggest("", &models, &tools, 2).len(), 2);
+    }
+}
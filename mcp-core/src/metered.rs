@@ -0,0 +1,73 @@
+//! Whether the internet connection is metered (a phone hotspot, a capped mobile plan), so
+//! background network traffic can wait. Asked of NetworkManager on Linux and of the connection
+//! profile's cost on Windows; macOS doesn't expose it, so there it's never known.
+
+/// True on a metered connection, false on an unmetered one, None if it can't be told
+pub fn metered() -> Option<bool> {
+    read_metered()
+}
+
+#[cfg(target_os = "linux")]
+fn read_metered() -> Option<bool> {
+    let output = std::process::Command::new("busctl")
+        .args(["get-property", "org.freedesktop.NetworkManager", "/org/freedesktop/NetworkManager", "org.freedesktop.NetworkManager", "Metered"])
+        .output()
+        .ok()?;
+    networkmanager_metered(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// NetworkManager's `Metered` property prints as e.g. "u 4": 1 and 3 are metered (known and
+/// guessed), 2 and 4 aren't, 0 is unknown
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn networkmanager_metered(output: &str) -> Option<bool> {
+    match output.trim().strip_prefix("u ")?.parse::<u32>().ok()? {
+        1 | 3 => Some(true),
+        2 | 4 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_metered() -> Option<bool> {
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "[Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime]::GetInternetConnectionProfile().GetConnectionCost().NetworkCostType",
+        ])
+        .output()
+        .ok()?;
+    network_cost_metered(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `NetworkCostType` is Unrestricted, Fixed (a data cap), Variable (paid by the byte) or Unknown
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn network_cost_metered(output: &str) -> Option<bool> {
+    match output.trim() {
+        "Unrestricted" => Some(false),
+        "Fixed" | "Variable" => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn read_metered() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guessed_costs_count_as_known() {
+        assert_eq!(networkmanager_metered("u 3\n"), Some(true));
+        assert_eq!(networkmanager_metered("u 4\n"), Some(false));
+        assert_eq!(networkmanager_metered("u 0\n"), None);
+        assert_eq!(networkmanager_metered(""), None);
+        assert_eq!(network_cost_metered("Variable\r\n"), Some(true));
+        assert_eq!(network_cost_metered("Unrestricted\r\n"), Some(false));
+        assert_eq!(network_cost_metered("Unknown\r\n"), None);
+    }
+}
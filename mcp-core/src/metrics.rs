@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Commands slower than this are logged as they finish
+const SLOW_COMMAND_MS: f64 = 1_000.0;
+
+/// Timing and payload sizes of one IPC command since startup. Sizes are of the arguments and
+/// result as JSON, which is close to what crosses the bridge.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct IpcCommandStats {
+    pub command: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    pub last_ms: f64,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    pub max_response_bytes: u64,
+}
+
+fn stats() -> &'static Mutex<HashMap<&'static str, IpcCommandStats>> {
+    static STATS: OnceLock<Mutex<HashMap<&'static str, IpcCommandStats>>> = OnceLock::new();
+    STATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Counts serialized bytes without keeping them
+struct ByteCounter(u64);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Size of `value` as JSON
+pub fn payload_size<T: Serialize + ?Sized>(value: &T) -> u64 {
+    let mut counter = ByteCounter(0);
+    match serde_json::to_writer(&mut counter, value) {
+        Ok(()) => counter.0,
+        Err(_) => 0,
+    }
+}
+
+/// Run a command, recording how long it took and how large its result was under `command`
+pub async fn instrument<T: Serialize>(
+    command: &'static str,
+    request_bytes: u64,
+    call: impl Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    let started = Instant::now();
+    let result = call.await;
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let response_bytes = match &result {
+        Ok(value) => payload_size(value),
+        Err(error) => payload_size(error),
+    };
+    if elapsed_ms > SLOW_COMMAND_MS {
+        log::warn!("Command {} took {:.0} ms", command, elapsed_ms);
+    }
+
+    if let Ok(mut stats) = stats().lock() {
+        let entry = stats.entry(command).or_insert_with(|| IpcCommandStats {
+            command: command.to_string(),
+            calls: 0,
+            errors: 0,
+            total_ms: 0.0,
+            mean_ms: 0.0,
+            max_ms: 0.0,
+            last_ms: 0.0,
+            request_bytes: 0,
+            response_bytes: 0,
+            max_response_bytes: 0,
+        });
+        entry.calls += 1;
+        entry.errors += u64::from(result.is_err());
+        entry.total_ms += elapsed_ms;
+        entry.mean_ms = entry.total_ms / entry.calls as f64;
+        entry.max_ms = entry.max_ms.max(elapsed_ms);
+        entry.last_ms = elapsed_ms;
+        entry.request_bytes += request_bytes;
+        entry.response_bytes += response_bytes;
+        entry.max_response_bytes = entry.max_response_bytes.max(response_bytes);
+    }
+    result
+}
+
+/// Stats for every command called so far, the most time-consuming first
+pub fn ipc_stats() -> Vec<IpcCommandStats> {
+    let Ok(stats) = stats().lock() else { return Vec::new() };
+    let mut stats: Vec<IpcCommandStats> = stats.values().cloned().collect();
+    stats.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+    stats
+}
@@ -0,0 +1,208 @@
+use std::path::Path;
+use rusqlite::{params, Connection, TransactionBehavior};
+use sha2::{Sha256, Digest};
+use anyhow::Result;
+use crate::tr;
+
+/// A schema change, applied once and in order. Never edit a shipped migration; add a new one.
+struct Migration {
+    version: u32,
+    name: &'static str,
+    sql: &'static str,
+}
+
+/// Schema of the history database. The early migrations use IF NOT EXISTS because databases
+/// created before migrations were tracked already have those tables.
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "messages", sql: include_str!("../migrations/0001_messages.sql") },
+    Migration { version: 2, name: "candidates", sql: include_str!("../migrations/0002_candidates.sql") },
+    Migration { version: 3, name: "message_details", sql: include_str!("../migrations/0003_message_details.sql") },
+    Migration { version: 4, name: "memories", sql: include_str!("../migrations/0004_memories.sql") },
+    Migration { version: 5, name: "conversation_compression", sql: include_str!("../migrations/0005_conversation_compression.sql") },
+    Migration { version: 6, name: "sync", sql: include_str!("../migrations/0006_sync.sql") },
+    Migration { version: 7, name: "attachments", sql: include_str!("../migrations/0007_attachments.sql") },
+    Migration { version: 8, name: "conversation_locks", sql: include_str!("../migrations/0008_conversation_locks.sql") },
+    Migration { version: 9, name: "message_origins", sql: include_str!("../migrations/0009_message_origins.sql") },
+    Migration { version: 10, name: "conversation_starters", sql: include_str!("../migrations/0010_conversation_starters.sql") },
+    Migration { version: 11, name: "conversation_filters", sql: include_str!("../migrations/0011_conversation_filters.sql") },
+    Migration { version: 12, name: "candidate_details", sql: include_str!("../migrations/0012_candidate_details.sql") },
+    Migration { version: 13, name: "evals", sql: include_str!("../migrations/0013_evals.sql") },
+    Migration { version: 14, name: "handoffs", sql: include_str!("../migrations/0014_handoffs.sql") },
+    Migration { version: 15, name: "benchmarks", sql: include_str!("../migrations/0015_benchmarks.sql") },
+    Migration { version: 16, name: "tool_invocations", sql: include_str!("../migrations/0016_tool_invocations.sql") },
+    Migration { version: 17, name: "conversation_reads", sql: include_str!("../migrations/0017_conversation_reads.sql") },
+    Migration { version: 18, name: "prompt_embeddings", sql: include_str!("../migrations/0018_prompt_embeddings.sql") },
+    Migration { version: 19, name: "conversation_plans", sql: include_str!("../migrations/0019_conversation_plans.sql") },
+];
+
+/// Bring the database up to the latest schema. Before changing an existing database its
+/// integrity is checked and a copy is kept next to it as `history.db.before-v<N>`.
+pub fn migrate(conn: &mut Connection, data_dir: &Path) -> Result<()> {
+    let existing: u32 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name != 'schema_migrations'",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        );",
+    )?;
+
+    let applied = applied(conn)?;
+    let latest = MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
+    for (version, checksum) in &applied {
+        match MIGRATIONS.iter().find(|m| m.version == *version) {
+            Some(migration) if checksum_of(migration) != *checksum => {
+                return Err(anyhow::anyhow!(tr!("history-migration-mismatch", version = *version, name = migration.name)));
+            }
+            Some(_) => {}
+            None => {
+                return Err(anyhow::anyhow!(tr!("history-schema-too-new", version = *version, latest = latest)));
+            }
+        }
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| !applied.iter().any(|(v, _)| *v == m.version)).collect();
+    let Some(target) = pending.last().map(|m| m.version) else {
+        return Ok(());
+    };
+
+    if existing > 0 {
+        check_integrity(conn)?;
+        let backup = data_dir.join(format!("history.db.before-v{}", target));
+        let _ = std::fs::remove_file(&backup);
+        conn.execute("VACUUM INTO ?1", params![backup.to_string_lossy()])?;
+        log::info!("Backed up history database to {} before migrating", backup.display());
+    }
+
+    for migration in pending {
+        // Immediate so a second instance opening at the same time waits instead of racing
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let done: bool = tx.query_row(
+            "SELECT COUNT(*) > 0 FROM schema_migrations WHERE version = ?1",
+            params![migration.version],
+            |row| row.get(0),
+        )?;
+        if !done {
+            tx.execute_batch(migration.sql)
+                .map_err(|e| anyhow::anyhow!(tr!("history-migration-failed", version = migration.version, name = migration.name, error = e.to_string())))?;
+            tx.execute(
+                "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?1, ?2, ?3, ?4)",
+                params![migration.version, migration.name, checksum_of(migration), chrono::Utc::now().to_rfc3339()],
+            )?;
+            log::info!("Applied history database migration {} ({})", migration.version, migration.name);
+        }
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn applied(conn: &Connection) -> Result<Vec<(u32, String)>> {
+    let mut statement = conn.prepare("SELECT version, checksum FROM schema_migrations ORDER BY version")?;
+    let applied = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(applied)
+}
+
+fn check_integrity(conn: &Connection) -> Result<()> {
+    let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if result != "ok" {
+        return Err(anyhow::anyhow!(tr!("history-integrity-failed", result = result.as_str())));
+    }
+    Ok(())
+}
+
+fn checksum_of(migration: &Migration) -> String {
+    format!("{:x}", Sha256::digest(migration.sql.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_data_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mcp-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn latest() -> u32 {
+        MIGRATIONS.last().unwrap().version
+    }
+
+    #[test]
+    fn new_databases_get_every_migration_and_no_backup() {
+        let dir = temp_data_dir();
+        let mut conn = Connection::open(dir.join("history.db")).unwrap();
+        migrate(&mut conn, &dir).unwrap();
+        let versions: Vec<u32> = applied(&conn).unwrap().into_iter().map(|(version, _)| version).collect();
+        assert_eq!(versions, MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>());
+        assert!(!dir.join(format!("history.db.before-v{}", latest())).exists());
+
+        // Nothing left to do the second time
+        migrate(&mut conn, &dir).unwrap();
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn edited_migrations_are_refused() {
+        let dir = temp_data_dir();
+        let mut conn = Connection::open(dir.join("history.db")).unwrap();
+        migrate(&mut conn, &dir).unwrap();
+        conn.execute("UPDATE schema_migrations SET checksum = 'tampered' WHERE version = 3", []).unwrap();
+
+        let error = migrate(&mut conn, &dir).unwrap_err();
+        assert_eq!(error.to_string(), tr!("history-migration-mismatch", version = 3, name = "message_details"));
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn schemas_from_a_newer_release_are_refused() {
+        let dir = temp_data_dir();
+        let mut conn = Connection::open(dir.join("history.db")).unwrap();
+        migrate(&mut conn, &dir).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?1, 'future', '', '')",
+            params![latest() + 1],
+        ).unwrap();
+
+        let error = migrate(&mut conn, &dir).unwrap_err();
+        assert_eq!(error.to_string(), tr!("history-schema-too-new", version = latest() + 1, latest = latest()));
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn damaged_databases_are_not_migrated() {
+        let dir = temp_data_dir();
+        let path = dir.join("history.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE notes (a INTEGER, b INTEGER);
+             CREATE INDEX idx_notes ON notes (a);
+             INSERT INTO notes VALUES (1, 10), (2, 20);
+             PRAGMA writable_schema = ON;
+             UPDATE sqlite_master SET sql = 'CREATE INDEX idx_notes ON notes (b)' WHERE name = 'idx_notes';
+             PRAGMA writable_schema = OFF;",
+        ).unwrap();
+        drop(conn);
+
+        // The index no longer matches its table, as after a crash on a disk that lies about fsync
+        let mut conn = Connection::open(&path).unwrap();
+        let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0)).unwrap();
+        assert_ne!(result, "ok");
+        let error = migrate(&mut conn, &dir).unwrap_err();
+        assert_eq!(error.to_string(), tr!("history-integrity-failed", result = result.as_str()));
+        assert!(!dir.join(format!("history.db.before-v{}", latest())).exists());
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+
+/// What the app is doing with the user's input. Each mode fixes a set of behaviors (see
+/// `behavior`), and `check_transition` decides which switches are allowed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, TS)]
+#[ts(export)]
+pub enum AppMode {
+    /// Messages go to the model; tools run only when the user invokes them
+    #[default]
+    Chat,
+    /// Input runs plugin commands instead of being sent as a message
+    Command,
+    /// Messages go to the model and tool calls run without asking
+    Agent,
+    /// Each message is answered several times and the answers are shown side by side
+    Compare,
+}
+
+/// Behaviors a mode governs, so the UI doesn't decide them itself
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ModeBehavior {
+    pub mode: AppMode,
+    /// Input is sent to the model as a chat message
+    pub send_messages: bool,
+    /// Plugin commands can be run
+    pub plugin_commands: bool,
+    /// Tool calls run without asking for confirmation
+    pub auto_execute_tools: bool,
+    /// Answers are sampled as candidates for the user to compare, whatever the generation mode
+    pub compare_answers: bool,
+    /// Localized hint for the status bar
+    pub status_hint: String,
+}
+
+/// What the transition rules need to know about the rest of the app
+pub struct ModeContext {
+    /// A loaded plugin provides at least one tool
+    pub tools_available: bool,
+    /// A response is streaming
+    pub responding: bool,
+}
+
+pub fn behavior(mode: AppMode) -> ModeBehavior {
+    let (send_messages, plugin_commands, auto_execute_tools, compare_answers, status_hint) = match mode {
+        AppMode::Chat => (true, true, false, false, tr!("mode-chat-hint")),
+        AppMode::Command => (false, true, false, false, tr!("mode-command-hint")),
+        AppMode::Agent => (true, true, true, false, tr!("mode-agent-hint")),
+        AppMode::Compare => (true, false, false, true, tr!("mode-compare-hint")),
+    };
+    ModeBehavior { mode, send_messages, plugin_commands, auto_execute_tools, compare_answers, status_hint }
+}
+
+/// Whether the app may switch from `from` to `to`
+pub fn check_transition(from: AppMode, to: AppMode, context: &ModeContext) -> Result<(), String> {
+    if from == to {
+        return Ok(());
+    }
+    // Tools already running unattended must not change their rules mid-response
+    if context.responding && (from == AppMode::Agent || to == AppMode::Agent) {
+        return Err(tr!("mode-busy"));
+    }
+    if to == AppMode::Agent && !context.tools_available {
+        return Err(tr!("mode-agent-needs-tools"));
+    }
+    Ok(())
+}
+
+static RESPONDING: AtomicU32 = AtomicU32::new(0);
+
+/// Counts a streaming response for as long as it's held
+pub struct Responding(());
+
+impl Responding {
+    pub fn start() -> Self {
+        RESPONDING.fetch_add(1, Ordering::Relaxed);
+        Responding(())
+    }
+}
+
+impl Drop for Responding {
+    fn drop(&mut self) {
+        RESPONDING.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Whether an answer is streaming right now
+pub fn responding() -> bool {
+    RESPONDING.load(Ordering::Relaxed) > 0
+}
+
+pub fn context() -> ModeContext {
+    let tools_available = crate::plugins::loaded_plugins()
+        .is_some_and(|plugins| plugins.iter().any(|plugin| !plugin.tools.is_empty()));
+    ModeContext { tools_available, responding: responding() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [AppMode; 4] = [AppMode::Chat, AppMode::Command, AppMode::Agent, AppMode::Compare];
+
+    fn context(tools_available: bool, responding: bool) -> ModeContext {
+        ModeContext { tools_available, responding }
+    }
+
+    #[test]
+    fn any_switch_is_allowed_when_idle_with_tools() {
+        for from in MODES {
+            for to in MODES {
+                assert!(check_transition(from, to, &context(true, false)).is_ok(), "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn agent_mode_needs_tools() {
+        assert!(check_transition(AppMode::Chat, AppMode::Agent, &context(false, false)).is_err());
+        // Leaving agent mode is always possible once idle, even if its tools went away
+        assert!(check_transition(AppMode::Agent, AppMode::Chat, &context(false, false)).is_ok());
+    }
+
+    #[test]
+    fn agent_mode_is_fixed_while_responding() {
+        assert!(check_transition(AppMode::Chat, AppMode::Agent, &context(true, true)).is_err());
+        assert!(check_transition(AppMode::Agent, AppMode::Compare, &context(true, true)).is_err());
+        assert!(check_transition(AppMode::Chat, AppMode::Compare, &context(true, true)).is_ok());
+        assert!(check_transition(AppMode::Agent, AppMode::Agent, &context(true, true)).is_ok());
+    }
+
+    #[test]
+    fn only_agent_mode_auto_executes_tools() {
+        for mode in MODES {
+            assert_eq!(behavior(mode).auto_execute_tools, mode == AppMode::Agent);
+        }
+        assert!(!behavior(AppMode::Command).send_messages);
+        assert!(behavior(AppMode::Compare).compare_answers);
+    }
+
+    #[test]
+    fn responding_is_counted_while_held() {
+        let before = RESPONDING.load(Ordering::Relaxed);
+        let responding = Responding::start();
+        assert!(RESPONDING.load(Ordering::Relaxed) > before);
+        drop(responding);
+        assert_eq!(RESPONDING.load(Ordering::Relaxed), before);
+    }
+}
@@ -0,0 +1,341 @@
+//! OAuth 2.0 sign-in for services that need it, in two flavours: the device code flow (RFC 8628),
+//! where the user enters a short code on the service's site, and the authorization code flow
+//! with PKCE (RFC 7636), where the browser is sent to the service and back to a one-shot listener
+//! on the loopback address. Either way the tokens end up in the secrets vault, owned by the
+//! integration, and a refresh token is used to get a new access token once the old one lapses.
+
+use std::time::Duration;
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use anyhow::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use ts_rs::TS;
+use crate::integrations::{IntegrationAuth, IntegrationManifest};
+
+/// How long the browser flow waits for the redirect back
+pub const BROWSER_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Path the browser is sent back to on the loopback listener
+const CALLBACK_PATH: &str = "/oauth/callback";
+
+/// Where and as whom to ask for tokens
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthClient {
+    pub client_id: String,
+    /// Only for services that require one even with PKCE
+    pub client_secret: Option<String>,
+    pub authorization_url: Option<String>,
+    pub device_authorization_url: Option<String>,
+    pub token_url: String,
+    pub scopes: Vec<String>,
+}
+
+impl OAuthClient {
+    /// The client for an integration whose manifest uses OAuth 2.0 and names a client id
+    pub fn for_integration(manifest: &IntegrationManifest, client_secret: Option<String>) -> Result<Self> {
+        let IntegrationAuth::OAuth2 { client_id, authorization_url, device_authorization_url, token_url } = &manifest.auth else {
+            anyhow::bail!("Integration {} doesn't sign in with OAuth", manifest.name);
+        };
+        let client_id = client_id.clone().ok_or_else(|| {
+            anyhow::anyhow!("Integration {} has no client_id; add one to its manifest in the integrations folder", manifest.name)
+        })?;
+        Ok(OAuthClient {
+            client_id,
+            client_secret,
+            authorization_url: authorization_url.clone(),
+            device_authorization_url: device_authorization_url.clone(),
+            token_url: token_url.clone(),
+            scopes: manifest.scopes.clone(),
+        })
+    }
+}
+
+/// What the user needs to finish a device code sign-in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct DeviceAuthorization {
+    pub integration: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// The verification page with the code filled in, where the service offers one
+    pub verification_uri_complete: Option<String>,
+    pub expires_in_secs: u32,
+}
+
+/// The device code grant, as the service hands it out
+#[derive(Deserialize, Clone, Debug)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    // GitHub says `verification_url`
+    #[serde(alias = "verification_url")]
+    pub verification_uri: String,
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u32,
+    #[serde(default = "default_interval")]
+    pub interval: u32,
+}
+
+fn default_interval() -> u32 {
+    5
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Tokens {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_in: Option<u32>,
+}
+
+/// A token endpoint's answer
+#[derive(Debug, PartialEq)]
+enum TokenReply {
+    Granted(Tokens),
+    /// The user hasn't finished on the verification page yet
+    Pending,
+    /// Polling too fast; wait longer between polls
+    SlowDown,
+    Failed(String),
+}
+
+/// Read a token endpoint's JSON answer, which reports errors in the body, sometimes with 200
+fn parse_token_reply(body: &serde_json::Value) -> TokenReply {
+    if let Some(error) = body.get("error").and_then(|error| error.as_str()) {
+        return match error {
+            "authorization_pending" => TokenReply::Pending,
+            "slow_down" => TokenReply::SlowDown,
+            _ => {
+                let description = body.get("error_description").and_then(|d| d.as_str()).unwrap_or(error);
+                TokenReply::Failed(description.to_string())
+            }
+        };
+    }
+    match serde_json::from_value::<Tokens>(body.clone()) {
+        Ok(tokens) => TokenReply::Granted(tokens),
+        Err(e) => TokenReply::Failed(format!("Unexpected token response: {}", e)),
+    }
+}
+
+/// A PKCE verifier and its S256 challenge
+pub fn pkce_pair() -> (String, String) {
+    let verifier = random_token(32);
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    (verifier, challenge)
+}
+
+/// `bytes` random bytes, base64url-encoded
+pub fn random_token(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buffer);
+    URL_SAFE_NO_PAD.encode(buffer)
+}
+
+/// The page the browser is sent to for the authorization code flow
+pub fn authorization_url(client: &OAuthClient, redirect_uri: &str, state: &str, challenge: &str) -> Result<String> {
+    let base = client.authorization_url.as_deref()
+        .ok_or_else(|| anyhow::anyhow!("This service has no browser sign-in"))?;
+    let mut url = reqwest::Url::parse(base)?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &client.client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("scope", &client.scopes.join(" "))
+        .append_pair("state", state)
+        .append_pair("code_challenge", challenge)
+        .append_pair("code_challenge_method", "S256");
+    Ok(url.to_string())
+}
+
+async fn post_form(client: &OAuthClient, url: &str, mut form: Vec<(&str, String)>) -> Result<serde_json::Value> {
+    form.push(("client_id", client.client_id.clone()));
+    if let Some(secret) = &client.client_secret {
+        form.push(("client_secret", secret.clone()));
+    }
+    let response = reqwest::Client::new()
+        .post(url)
+        // GitHub answers in form encoding unless asked for JSON
+        .header("Accept", "application/json")
+        .form(&form)
+        .send()
+        .await?;
+    Ok(response.json().await?)
+}
+
+/// Ask for a device code to show the user
+pub async fn request_device_code(client: &OAuthClient) -> Result<DeviceCode> {
+    let url = client.device_authorization_url.as_deref()
+        .ok_or_else(|| anyhow::anyhow!("This service has no device sign-in"))?;
+    let body = post_form(client, url, vec![("scope", client.scopes.join(" "))]).await?;
+    if let Some(error) = body.get("error").and_then(|error| error.as_str()) {
+        anyhow::bail!("Device sign-in was refused: {}", error);
+    }
+    Ok(serde_json::from_value(body)?)
+}
+
+/// Poll until the user approves the device, the code expires or the service refuses
+pub async fn poll_device_code(client: &OAuthClient, code: &DeviceCode) -> Result<Tokens> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(code.expires_in as u64);
+    let mut interval = Duration::from_secs(code.interval.max(1) as u64);
+    loop {
+        tokio::time::sleep(interval).await;
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("The sign-in code expired before it was entered");
+        }
+        let body = post_form(client, &client.token_url, vec![
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code".to_string()),
+            ("device_code", code.device_code.clone()),
+        ]).await?;
+        match parse_token_reply(&body) {
+            TokenReply::Granted(tokens) => return Ok(tokens),
+            TokenReply::Pending => {}
+            TokenReply::SlowDown => interval += Duration::from_secs(5),
+            TokenReply::Failed(error) => anyhow::bail!(error),
+        }
+    }
+}
+
+/// Trade the code from the browser redirect for tokens
+pub async fn exchange_code(client: &OAuthClient, code: &str, verifier: &str, redirect_uri: &str) -> Result<Tokens> {
+    let body = post_form(client, &client.token_url, vec![
+        ("grant_type", "authorization_code".to_string()),
+        ("code", code.to_string()),
+        ("code_verifier", verifier.to_string()),
+        ("redirect_uri", redirect_uri.to_string()),
+    ]).await?;
+    match parse_token_reply(&body) {
+        TokenReply::Granted(tokens) => Ok(tokens),
+        TokenReply::Failed(error) => anyhow::bail!(error),
+        TokenReply::Pending | TokenReply::SlowDown => anyhow::bail!("Unexpected token response"),
+    }
+}
+
+/// A new access token from a refresh token. Services that rotate refresh tokens return a new one.
+pub async fn refresh(client: &OAuthClient, refresh_token: &str) -> Result<Tokens> {
+    let body = post_form(client, &client.token_url, vec![
+        ("grant_type", "refresh_token".to_string()),
+        ("refresh_token", refresh_token.to_string()),
+    ]).await?;
+    match parse_token_reply(&body) {
+        TokenReply::Granted(tokens) => Ok(tokens),
+        TokenReply::Failed(error) => anyhow::bail!(error),
+        TokenReply::Pending | TokenReply::SlowDown => anyhow::bail!("Unexpected token response"),
+    }
+}
+
+/// A listener on a free loopback port for the browser to be sent back to
+pub struct Callback {
+    listener: tokio::net::TcpListener,
+    pub redirect_uri: String,
+}
+
+impl Callback {
+    pub async fn bind() -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await?;
+        let redirect_uri = format!("http://127.0.0.1:{}{}", listener.local_addr()?.port(), CALLBACK_PATH);
+        Ok(Callback { listener, redirect_uri })
+    }
+
+    /// Wait for the redirect and return its code, once its state matches `state`. Requests for
+    /// anything else, like the browser's favicon, are answered with 404 and ignored.
+    pub async fn code(self, state: &str) -> Result<String> {
+        tokio::time::timeout(BROWSER_TIMEOUT, async {
+            loop {
+                let (mut stream, _) = self.listener.accept().await?;
+                let mut buffer = vec![0u8; 8192];
+                let read = stream.read(&mut buffer).await?;
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+                let target = request.split_whitespace().nth(1).unwrap_or_default();
+                let Some(result) = parse_callback(target, state) else {
+                    stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await?;
+                    continue;
+                };
+                let page = match &result {
+                    Ok(_) => "Signed in. You can close this tab and return to MCP Switchboard.",
+                    Err(_) => "Sign-in failed. You can close this tab and try again from MCP Switchboard.",
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    page.len(), page,
+                );
+                stream.write_all(response.as_bytes()).await?;
+                return result;
+            }
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Gave up waiting for the browser sign-in"))?
+    }
+}
+
+/// The code from a redirect to `target`, an error the service sent instead, or None if `target`
+/// isn't the callback
+fn parse_callback(target: &str, state: &str) -> Option<Result<String>> {
+    let url = reqwest::Url::parse(&format!("http://127.0.0.1{}", target)).ok()?;
+    if url.path() != CALLBACK_PATH {
+        return None;
+    }
+    let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.to_string());
+    if param("state").as_deref() != Some(state) {
+        return Some(Err(anyhow::anyhow!("The sign-in redirect didn't match the request")));
+    }
+    if let Some(error) = param("error") {
+        return Some(Err(anyhow::anyhow!("Sign-in was refused: {}", param("error_description").unwrap_or(error))));
+    }
+    Some(param("code").ok_or_else(|| anyhow::anyhow!("The sign-in redirect had no code")))
+}
+
+/// Open `url` in the default browser
+pub fn open_in_browser(url: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let status = std::process::Command::new("open").arg(url).status()?;
+    #[cfg(target_os = "windows")]
+    let status = std::process::Command::new("rundll32").args(["url.dll,FileProtocolHandler", url]).status()?;
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let status = std::process::Command::new("xdg-open").arg(url).status()?;
+    if !status.success() {
+        anyhow::bail!("Couldn't open a browser ({})", status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkce_challenge_is_the_hashed_verifier() {
+        let (verifier, challenge) = pkce_pair();
+        assert_eq!(verifier.len(), 43);
+        assert_eq!(challenge, URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())));
+        assert_ne!(pkce_pair().0, verifier);
+    }
+
+    #[test]
+    fn token_replies_are_read_from_the_body() {
+        let granted = serde_json::json!({"access_token": "a", "refresh_token": "r", "expires_in": 3600, "token_type": "bearer"});
+        assert_eq!(parse_token_reply(&granted), TokenReply::Granted(Tokens {
+            access_token: "a".to_string(),
+            refresh_token: Some("r".to_string()),
+            expires_in: Some(3600),
+        }));
+        assert_eq!(parse_token_reply(&serde_json::json!({"error": "authorization_pending"})), TokenReply::Pending);
+        assert_eq!(parse_token_reply(&serde_json::json!({"error": "slow_down"})), TokenReply::SlowDown);
+        assert_eq!(
+            parse_token_reply(&serde_json::json!({"error": "access_denied", "error_description": "The user said no"})),
+            TokenReply::Failed("The user said no".to_string()),
+        );
+    }
+
+    #[test]
+    fn callbacks_need_the_matching_state() {
+        assert_eq!(parse_callback("/oauth/callback?code=abc&state=s1", "s1").unwrap().unwrap(), "abc");
+        assert!(parse_callback("/oauth/callback?code=abc&state=other", "s1").unwrap().is_err());
+        assert!(parse_callback("/oauth/callback?error=access_denied&state=s1", "s1").unwrap().is_err());
+        assert!(parse_callback("/favicon.ico", "s1").is_none());
+    }
+}
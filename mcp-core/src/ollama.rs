@@ -0,0 +1,316 @@
+//! A local Ollama server, so the app works with no network at all. Models are discovered with
+//! `/api/tags` and chat is streamed from `/api/chat`, whose reply is newline-delimited JSON
+//! rather than SSE; each line is turned into a chat chunk so the rest of the streaming path
+//! doesn't need to know. Other requests go to Ollama's OpenAI-compatible API under `/v1`.
+//!
+//! Ollama unloads a model once it's been idle for a while, and loading it again holds up the
+//! first token by seconds. Chat requests carry the configured `keep_alive`, and a model is
+//! loaded ahead of its first message with an empty `/api/generate` request.
+
+use async_openai::error::{ApiError, OpenAIError};
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionStreamResponse, Stop};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use crate::ModelInfo;
+use crate::providers::{self, Provider};
+
+/// The OpenAI-compatible API of the server at `base_url`
+pub fn api_base(base_url: &str) -> String {
+    format!("{}/v1", base_url.trim_end_matches('/'))
+}
+
+/// The server a `/v1` API base belongs to
+fn base_url(api_base: &str) -> &str {
+    api_base.trim_end_matches('/').trim_end_matches("/v1")
+}
+
+/// Splits a byte stream into lines, holding a partial line until its newline arrives
+#[derive(Default)]
+pub struct LineDecoder {
+    buffer: Vec<u8>,
+}
+
+impl LineDecoder {
+    /// The non-empty lines completed by `bytes`
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+        let mut lines = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+}
+
+/// Whether `keep_alive` is something Ollama accepts: seconds, or a duration such as `30m` or
+/// `1h30m`. Negative values keep the model loaded for ever and zero unloads it straight away.
+pub fn valid_keep_alive(keep_alive: &str) -> bool {
+    let duration = keep_alive.strip_prefix('-').unwrap_or(keep_alive);
+    if !duration.is_empty() && duration.bytes().all(|byte| byte.is_ascii_digit()) {
+        return true;
+    }
+    let mut rest = duration;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        if digits == 0 || rest[..digits].parse::<f64>().is_err() {
+            return false;
+        }
+        rest = &rest[digits..];
+        let Some(unit) = ["ns", "us", "ms", "s", "m", "h"].into_iter().find(|unit| rest.starts_with(unit)) else {
+            return false;
+        };
+        rest = &rest[unit.len()..];
+    }
+    !duration.is_empty()
+}
+
+/// `keep_alive` as Ollama reads it: plain seconds as a number, durations as a string
+fn keep_alive_value(keep_alive: &str) -> Value {
+    keep_alive.parse::<i64>().map_or_else(|_| json!(keep_alive), |seconds| json!(seconds))
+}
+
+/// The `/api/chat` request equivalent to a chat request
+pub fn chat_request(chat: &CreateChatCompletionRequest, keep_alive: Option<&str>) -> Value {
+    let messages: Vec<Value> = chat.messages.iter()
+        .map(|message| serde_json::to_value(message).unwrap_or_default())
+        .map(|message| json!({
+            "role": message["role"].as_str().unwrap_or("user"),
+            "content": providers::message_text(&message),
+        }))
+        .collect();
+    let mut options = serde_json::Map::new();
+    #[allow(deprecated)]
+    if let Some(max_tokens) = chat.max_completion_tokens.or(chat.max_tokens) {
+        options.insert("num_predict".to_string(), json!(max_tokens));
+    }
+    if let Some(temperature) = chat.temperature {
+        options.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(top_p) = chat.top_p {
+        options.insert("top_p".to_string(), json!(top_p));
+    }
+    if let Some(seed) = chat.seed {
+        options.insert("seed".to_string(), json!(seed));
+    }
+    match &chat.stop {
+        Some(Stop::String(stop)) => { options.insert("stop".to_string(), json!([stop])); }
+        Some(Stop::StringArray(stops)) => { options.insert("stop".to_string(), json!(stops)); }
+        None => {}
+    }
+    let mut request = json!({
+        "model": providers::resolve(&chat.model).1,
+        "messages": messages,
+        "stream": true,
+        "options": options,
+    });
+    if let Some(keep_alive) = keep_alive {
+        request["keep_alive"] = keep_alive_value(keep_alive);
+    }
+    request
+}
+
+/// The `/api/generate` request that loads `model` without generating anything
+pub fn preload_request(model: &str, keep_alive: Option<&str>) -> Value {
+    let mut request = json!({ "model": providers::resolve(model).1 });
+    if let Some(keep_alive) = keep_alive {
+        request["keep_alive"] = keep_alive_value(keep_alive);
+    }
+    request
+}
+
+/// The chat chunk a line of the `/api/chat` stream amounts to, if any
+pub fn chunk(id: &str, line: &str) -> Option<Result<CreateChatCompletionStreamResponse, OpenAIError>> {
+    let data: Value = match serde_json::from_str(line) {
+        Ok(data) => data,
+        Err(e) => return Some(Err(OpenAIError::JSONDeserialize(e))),
+    };
+    if let Some(error) = data["error"].as_str() {
+        return Some(Err(OpenAIError::ApiError(api_error(error))));
+    }
+    let done = data["done"].as_bool().unwrap_or(false);
+    let content = data["message"]["content"].as_str().unwrap_or_default();
+    if content.is_empty() && !done {
+        return None;
+    }
+    let (finish_reason, usage) = if done {
+        let (prompt_tokens, completion_tokens) = (data["prompt_eval_count"].as_u64().unwrap_or(0), data["eval_count"].as_u64().unwrap_or(0));
+        let finish_reason = match data["done_reason"].as_str() {
+            Some("length") => "length",
+            _ => "stop",
+        };
+        (Some(finish_reason), json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": completion_tokens,
+            "total_tokens": prompt_tokens + completion_tokens,
+        }))
+    } else {
+        (None, Value::Null)
+    };
+    let delta = if content.is_empty() { json!({}) } else { json!({ "content": content }) };
+    Some(serde_json::from_value(json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": chrono::Utc::now().timestamp(),
+        "model": data["model"],
+        "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+        "usage": usage,
+    }))
+    .map_err(OpenAIError::JSONDeserialize))
+}
+
+fn api_error(message: &str) -> ApiError {
+    ApiError { message: message.to_string(), r#type: None, param: None, code: None }
+}
+
+/// Send `chat` to the Ollama server behind `api_base` and stream the reply as chat chunks
+pub async fn create_stream(
+    http_client: reqwest::Client,
+    api_base: &str,
+    chat: &CreateChatCompletionRequest,
+    keep_alive: Option<&str>,
+) -> Result<crate::ProviderStream, OpenAIError> {
+    let response = http_client
+        .post(format!("{}/api/chat", base_url(api_base)))
+        .json(&chat_request(chat, keep_alive))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        log::warn!("Ollama refused the request with {}", status);
+        return Err(OpenAIError::ApiError(api_error(body["error"].as_str().unwrap_or("Ollama error"))));
+    }
+
+    let id = format!("ollama-{}", uuid::Uuid::new_v4());
+    let mut decoder = LineDecoder::default();
+    let chunks = response.bytes_stream()
+        .map(move |bytes| match bytes {
+            Ok(bytes) => decoder.push(&bytes).iter().filter_map(|line| chunk(&id, line)).collect(),
+            Err(e) => vec![Err(OpenAIError::Reqwest(e))],
+        })
+        .flat_map(futures::stream::iter);
+    Ok(Box::pin(chunks))
+}
+
+/// Have the Ollama server behind `api_base` load `model` into memory, returning once it has
+pub async fn preload(http_client: reqwest::Client, api_base: &str, model: &str, keep_alive: Option<&str>) -> Result<(), String> {
+    let response = http_client
+        .post(format!("{}/api/generate", base_url(api_base)))
+        .json(&preload_request(model, keep_alive))
+        .send()
+        .await
+        .map_err(|e| {
+            log::warn!("Failed to reach Ollama to load {}: {}", model, e);
+            e.to_string()
+        })?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        log::warn!("Ollama refused to load {} with {}", model, status);
+        return Err(body["error"].as_str().unwrap_or("Ollama error").to_string());
+    }
+    Ok(())
+}
+
+/// The URL the server at `api_base` lists its models at
+pub fn tags_url(api_base: &str) -> String {
+    format!("{}/api/tags", base_url(api_base))
+}
+
+/// The models in a response from `/api/tags`
+pub fn models(response: &Value) -> Result<Vec<ModelInfo>, String> {
+    let list = response["models"].as_array().ok_or_else(|| {
+        log::error!("Ollama tags response has no models array");
+        "Invalid models response format".to_string()
+    })?;
+    Ok(list.iter()
+        .filter_map(|model| {
+            let name = model["name"].as_str()?;
+            let family = model["details"]["family"].as_str().unwrap_or("Local");
+            Some(ModelInfo {
+                id: providers::qualified_id(Provider::Ollama, name),
+                display_name: name.to_string(),
+                organization: family.to_string(),
+                context_length: None,
+                pricing: None,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::types::{ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs};
+
+    #[test]
+    fn chat_requests_carry_sampling_in_options() {
+        #[allow(deprecated)]
+        let chat = CreateChatCompletionRequestArgs::default()
+            .model("ollama/llama3.2:latest")
+            .messages(vec![
+                ChatCompletionRequestSystemMessageArgs::default().content("Be brief.").build().unwrap().into(),
+                ChatCompletionRequestUserMessageArgs::default().content("Hi").build().unwrap().into(),
+            ])
+            .max_tokens(64u32)
+            .temperature(0.3)
+            .build()
+            .unwrap();
+        let request = chat_request(&chat, None);
+        assert_eq!(request["model"], "llama3.2:latest");
+        assert_eq!(request["messages"], json!([
+            { "role": "system", "content": "Be brief." },
+            { "role": "user", "content": "Hi" },
+        ]));
+        assert_eq!(request["options"]["num_predict"], 64);
+        assert!(request["options"].get("seed").is_none());
+        assert!(request.get("keep_alive").is_none());
+        assert_eq!(chat_request(&chat, Some("30m"))["keep_alive"], "30m");
+    }
+
+    #[test]
+    fn keep_alive_is_seconds_or_a_duration() {
+        for keep_alive in ["0", "-1", "300", "30m", "1h30m", "1.5h", "-1m"] {
+            assert!(valid_keep_alive(keep_alive), "{}", keep_alive);
+        }
+        for keep_alive in ["", "-", "m", "30 minutes", "5d", "1h30"] {
+            assert!(!valid_keep_alive(keep_alive), "{}", keep_alive);
+        }
+        assert_eq!(preload_request("ollama/llama3.2:latest", Some("-1")), json!({ "model": "llama3.2:latest", "keep_alive": -1 }));
+        assert_eq!(preload_request("ollama/llama3.2:latest", None), json!({ "model": "llama3.2:latest" }));
+    }
+
+    #[test]
+    fn stream_lines_become_chat_chunks() {
+        let mut decoder = LineDecoder::default();
+        let first = decoder.push(b"{\"model\":\"llama3.2\",\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n{\"model\":");
+        assert_eq!(first.len(), 1);
+        let rest = decoder.push(b"\"llama3.2\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":9,\"eval_count\":3}\n");
+        assert_eq!(rest.len(), 1);
+
+        let text = chunk("c1", &first[0]).unwrap().unwrap();
+        assert_eq!(text.choices[0].delta.content.as_deref(), Some("Hel"));
+        let done = chunk("c1", &rest[0]).unwrap().unwrap();
+        assert_eq!(done.choices[0].finish_reason, Some(async_openai::types::FinishReason::Stop));
+        assert_eq!(done.usage.map(|usage| usage.total_tokens), Some(12));
+
+        assert!(chunk("c1", "{\"message\":{\"content\":\"\"},\"done\":false}").is_none());
+        assert!(matches!(chunk("c1", "{\"error\":\"model not found\"}"), Some(Err(OpenAIError::ApiError(e))) if e.message == "model not found"));
+    }
+
+    #[test]
+    fn tags_list_local_models() {
+        let response = json!({ "models": [
+            { "name": "llama3.2:latest", "details": { "family": "llama" } },
+            { "name": "qwen2.5-coder:7b", "details": {} },
+        ] });
+        let models = models(&response).unwrap();
+        assert_eq!(models[0].id, "ollama/llama3.2:latest");
+        assert_eq!(models[1].organization, "Local");
+        assert_eq!(tags_url(&api_base("http://localhost:11434/")), "http://localhost:11434/api/tags");
+    }
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+use crate::providers::{self, Provider};
+
+/// First-run steps, in order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, TS)]
+#[ts(export)]
+pub enum OnboardingStep {
+    #[default]
+    ChooseProvider,
+    EnterKey,
+    /// The key is checked by fetching the provider's model list
+    ValidateKey,
+    PickModel,
+    /// Optional; there are no MCP servers to connect yet, so this can only be skipped
+    McpServers,
+    Complete,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub enum OnboardingAction {
+    ChooseProvider { provider: String },
+    EnterKey { api_key: String },
+    Validate,
+    PickModel { model: String },
+    /// Move past an optional step
+    Skip,
+    Back,
+}
+
+/// Progress through the first-run flow, kept in config so it survives restarts
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OnboardingProgress {
+    pub step: OnboardingStep,
+    pub provider: Option<String>,
+    /// Why the last action failed, shown on the current step until the next action
+    pub error: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct OnboardingState {
+    pub step: OnboardingStep,
+    pub provider: Option<String>,
+    pub providers: Vec<String>,
+    /// The current step can be skipped
+    pub optional: bool,
+    pub error: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+impl OnboardingProgress {
+    pub fn state(&self) -> OnboardingState {
+        OnboardingState {
+            step: self.step,
+            provider: self.provider.clone(),
+            providers: providers::ALL.iter().map(|provider| provider.name().to_string()).collect(),
+            optional: self.step == OnboardingStep::McpServers,
+            error: self.error.clone(),
+            completed_at: self.completed_at.clone(),
+        }
+    }
+}
+
+fn previous(step: OnboardingStep) -> OnboardingStep {
+    match step {
+        OnboardingStep::ChooseProvider | OnboardingStep::EnterKey => OnboardingStep::ChooseProvider,
+        // Validation failing means the key needs changing, so going back skips over it
+        OnboardingStep::ValidateKey | OnboardingStep::PickModel => OnboardingStep::EnterKey,
+        OnboardingStep::McpServers => OnboardingStep::PickModel,
+        OnboardingStep::Complete => OnboardingStep::Complete,
+    }
+}
+
+/// The step `action` leads to from `step`, before any side effect of the action has run.
+/// Actions that don't belong to the current step are refused.
+pub fn transition(step: OnboardingStep, action: &OnboardingAction) -> Result<OnboardingStep, String> {
+    match (step, action) {
+        (OnboardingStep::Complete, _) => Err(tr!("onboarding-complete")),
+        (step, OnboardingAction::Back) => Ok(previous(step)),
+        (OnboardingStep::ChooseProvider, OnboardingAction::ChooseProvider { provider }) => {
+            if Provider::from_name(provider).is_some() {
+                Ok(OnboardingStep::EnterKey)
+            } else {
+                Err(tr!("onboarding-unknown-provider", provider = provider.as_str()))
+            }
+        }
+        (OnboardingStep::EnterKey, OnboardingAction::EnterKey { api_key }) => {
+            if api_key.trim().is_empty() {
+                Err(tr!("onboarding-empty-key"))
+            } else {
+                Ok(OnboardingStep::ValidateKey)
+            }
+        }
+        (OnboardingStep::ValidateKey, OnboardingAction::Validate) => Ok(OnboardingStep::PickModel),
+        (OnboardingStep::PickModel, OnboardingAction::PickModel { model }) if !model.trim().is_empty() => Ok(OnboardingStep::McpServers),
+        (OnboardingStep::McpServers, OnboardingAction::Skip) => Ok(OnboardingStep::Complete),
+        (step, _) => Err(tr!("onboarding-wrong-step", step = format!("{:?}", step))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance(step: OnboardingStep, action: OnboardingAction) -> Result<OnboardingStep, String> {
+        transition(step, &action)
+    }
+
+    #[test]
+    fn walks_through_every_step() {
+        let mut step = OnboardingStep::default();
+        for action in [
+            OnboardingAction::ChooseProvider { provider: Provider::OpenAi.name().to_string() },
+            OnboardingAction::EnterKey { api_key: "key".to_string() },
+            OnboardingAction::Validate,
+            OnboardingAction::PickModel { model: "model".to_string() },
+            OnboardingAction::Skip,
+        ] {
+            step = advance(step, action).unwrap();
+        }
+        assert_eq!(step, OnboardingStep::Complete);
+    }
+
+    #[test]
+    fn refuses_actions_for_other_steps() {
+        assert!(advance(OnboardingStep::ChooseProvider, OnboardingAction::Validate).is_err());
+        assert!(advance(OnboardingStep::EnterKey, OnboardingAction::Skip).is_err());
+        assert!(advance(OnboardingStep::PickModel, OnboardingAction::PickModel { model: " ".to_string() }).is_err());
+        assert!(advance(OnboardingStep::EnterKey, OnboardingAction::EnterKey { api_key: String::new() }).is_err());
+        assert!(advance(OnboardingStep::ChooseProvider, OnboardingAction::ChooseProvider { provider: "nope".to_string() }).is_err());
+        assert!(advance(OnboardingStep::Complete, OnboardingAction::Back).is_err());
+    }
+
+    #[test]
+    fn back_returns_to_key_entry_after_validation() {
+        assert_eq!(advance(OnboardingStep::ValidateKey, OnboardingAction::Back), Ok(OnboardingStep::EnterKey));
+        assert_eq!(advance(OnboardingStep::PickModel, OnboardingAction::Back), Ok(OnboardingStep::EnterKey));
+        assert_eq!(advance(OnboardingStep::McpServers, OnboardingAction::Back), Ok(OnboardingStep::PickModel));
+        assert_eq!(advance(OnboardingStep::ChooseProvider, OnboardingAction::Back), Ok(OnboardingStep::ChooseProvider));
+    }
+}
@@ -0,0 +1,121 @@
+//! The OpenAI-compatible endpoints of the local API server, so tools built for the OpenAI API
+//! can be pointed at the switchboard. Models from every provider are listed together, with ids
+//! namespaced by provider (`together/meta-llama/Llama-3-8b-chat-hf`) so the same model offered
+//! by two providers stays distinguishable.
+
+use axum::Json;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use crate::ModelInfo;
+use crate::server_auth::RequestShaping;
+
+/// A model in the catalogue of every usable provider
+#[derive(Clone)]
+pub struct CatalogueEntry {
+    pub provider: String,
+    pub model: ModelInfo,
+}
+
+impl CatalogueEntry {
+    pub fn id(&self) -> String {
+        namespaced_id(&self.provider, &self.model.id)
+    }
+}
+
+/// `provider/model_id`, unless the provider's own ids already carry its name
+pub fn namespaced_id(provider: &str, model_id: &str) -> String {
+    match model_id.strip_prefix(provider) {
+        Some(rest) if rest.starts_with('/') => model_id.to_string(),
+        _ => format!("{}/{}", provider, model_id),
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ModelObject {
+    pub id: String,
+    pub object: &'static str,
+    /// Providers don't report one, so always 0
+    pub created: i64,
+    pub owned_by: String,
+}
+
+impl From<&CatalogueEntry> for ModelObject {
+    fn from(entry: &CatalogueEntry) -> Self {
+        ModelObject {
+            id: entry.id(),
+            object: "model",
+            created: 0,
+            owned_by: entry.model.organization.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ModelList {
+    pub object: &'static str,
+    pub data: Vec<ModelObject>,
+}
+
+pub fn model_list(catalogue: &[CatalogueEntry]) -> ModelList {
+    ModelList { object: "list", data: catalogue.iter().map(ModelObject::from).collect() }
+}
+
+/// `catalogue` narrowed to the model a client is held to, named by its namespaced or plain id
+pub fn shaped(catalogue: Vec<CatalogueEntry>, shaping: &RequestShaping) -> Vec<CatalogueEntry> {
+    match &shaping.model {
+        Some(model) => catalogue.into_iter().filter(|entry| entry.model.id == *model || entry.id() == *model).collect(),
+        None => catalogue,
+    }
+}
+
+/// The catalogue entry a namespaced id names
+pub fn find<'a>(catalogue: &'a [CatalogueEntry], id: &str) -> Option<&'a CatalogueEntry> {
+    catalogue.iter().find(|entry| entry.id() == id)
+}
+
+/// An error in the shape OpenAI clients parse: `{"error": {"message", "type", "code"}}`
+pub fn error(status: StatusCode, kind: &str, code: Option<&str>, message: &str) -> Response {
+    let body = serde_json::json!({
+        "error": { "message": message, "type": kind, "param": null, "code": code },
+    });
+    (status, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(provider: &str, id: &str) -> CatalogueEntry {
+        CatalogueEntry {
+            provider: provider.to_string(),
+            model: ModelInfo {
+                id: id.to_string(),
+                display_name: id.to_string(),
+                organization: "Meta".to_string(),
+                context_length: None,
+                pricing: None,
+            },
+        }
+    }
+
+    #[test]
+    fn ids_are_namespaced_once_by_provider() {
+        let catalogue = [entry("together", "meta-llama/Llama-3-8b-chat-hf"), entry("demo", "demo/switchboard-sample")];
+        let list = model_list(&catalogue);
+        assert_eq!(list.data[0], ModelObject {
+            id: "together/meta-llama/Llama-3-8b-chat-hf".to_string(),
+            object: "model",
+            created: 0,
+            owned_by: "Meta".to_string(),
+        });
+        assert_eq!(list.data[1].id, "demo/switchboard-sample");
+        assert_eq!(namespaced_id("together", "togetherai-model"), "together/togetherai-model");
+        assert!(find(&catalogue, "together/meta-llama/Llama-3-8b-chat-hf").is_some());
+        assert!(find(&catalogue, "meta-llama/Llama-3-8b-chat-hf").is_none());
+
+        let held = RequestShaping { model: Some("meta-llama/Llama-3-8b-chat-hf".to_string()), ..Default::default() };
+        assert_eq!(shaped(catalogue.to_vec(), &held).len(), 1);
+        assert_eq!(shaped(catalogue.to_vec(), &RequestShaping::default()).len(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+//! Orchestrated conversations, where two or more agents talk with each other toward a goal the
+//! user sets. Each agent is a persona on a model of its own, and they speak in turn, round after
+//! round. Every agent sees the others' turns as the user speaking, prefixed with their names, and
+//! its own as its answers. The conversation ends at its turn limit, when an agent says the stop
+//! phrase, or when the moderator model, which reads the transcript after each round, judges the
+//! goal reached.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::context::{self, ContextWindow};
+use crate::history::HistoryMessage;
+use crate::tr;
+
+/// Most turns one orchestration takes, across all its agents
+pub const MAX_TURNS: u32 = 40;
+
+/// Most agents one orchestration has
+pub const MAX_AGENTS: usize = 6;
+
+/// One participant: who the others address and what it speaks as
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct OrchestrationAgent {
+    /// What the other agents call it, e.g. "Critic"
+    pub name: String,
+    /// Id of the persona template it speaks as, if any
+    pub persona: Option<String>,
+    /// Qualified id of the model it runs on; the preferred model if None
+    pub model: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct OrchestrationPlan {
+    /// What the agents are working toward, saved as the user's message
+    pub goal: String,
+    /// In speaking order
+    pub agents: Vec<OrchestrationAgent>,
+    /// Turns in all, after which the conversation stops however far it got
+    pub max_turns: u32,
+    /// Model that reads the transcript after each round and ends the conversation once it judges
+    /// the goal reached
+    pub moderator_model: Option<String>,
+    /// Ends the conversation when an agent says it, e.g. "GOAL REACHED"
+    pub stop_phrase: Option<String>,
+}
+
+/// Why an orchestrated conversation ended
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum OrchestrationEnd {
+    StopPhrase,
+    Moderator,
+    TurnLimit,
+    /// A turn failed or the spend cap was reached; the turns before it are kept
+    Failed,
+}
+
+/// A finished turn
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    /// Index of the agent that spoke
+    pub agent: usize,
+    pub text: String,
+}
+
+fn optional(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// `plan` trimmed, or a localized error if it has no goal, too few or too many agents, agents
+/// without names or sharing one, or a turn limit out of range
+pub fn validate(plan: OrchestrationPlan) -> Result<OrchestrationPlan, String> {
+    let goal = plan.goal.trim().to_string();
+    if goal.is_empty() {
+        return Err(tr!("orchestration-goal-missing"));
+    }
+    if !(2..=MAX_AGENTS).contains(&plan.agents.len()) {
+        return Err(tr!("orchestration-agent-count", max = MAX_AGENTS));
+    }
+    if !(1..=MAX_TURNS).contains(&plan.max_turns) {
+        return Err(tr!("orchestration-turns-invalid", max = MAX_TURNS));
+    }
+    let mut agents: Vec<OrchestrationAgent> = Vec::with_capacity(plan.agents.len());
+    for agent in plan.agents {
+        let name = agent.name.trim().to_string();
+        if name.is_empty() {
+            return Err(tr!("orchestration-agent-name-missing"));
+        }
+        if agents.iter().any(|other| other.name.eq_ignore_ascii_case(&name)) {
+            return Err(tr!("orchestration-agent-name-taken", name = name));
+        }
+        agents.push(OrchestrationAgent { name, persona: optional(agent.persona), model: optional(agent.model) });
+    }
+    Ok(OrchestrationPlan {
+        goal,
+        agents,
+        max_turns: plan.max_turns,
+        moderator_model: optional(plan.moderator_model),
+        stop_phrase: optional(plan.stop_phrase),
+    })
+}
+
+/// The agent whose turn follows `turns_taken` turns
+pub fn next_agent(plan: &OrchestrationPlan, turns_taken: usize) -> usize {
+    turns_taken % plan.agents.len()
+}
+
+/// Whether every agent has spoken as often as the others, so the moderator reads a whole round
+pub fn round_complete(plan: &OrchestrationPlan, turns_taken: usize) -> bool {
+    turns_taken > 0 && turns_taken.is_multiple_of(plan.agents.len())
+}
+
+/// The system prompt of `agent`: its persona's, then who it's talking with and toward what
+pub fn agent_prompt(plan: &OrchestrationPlan, agent: usize, persona: Option<&str>) -> String {
+    let name = &plan.agents[agent].name;
+    let others: Vec<&str> = plan.agents.iter()
+        .enumerate()
+        .filter(|(index, _)| *index != agent)
+        .map(|(_, other)| other.name.as_str())
+        .collect();
+    let mut prompt = format!(
+        "You are {} in a conversation with {} toward this goal: {}\n\
+         Speak only as {}, one turn at a time, and build on what the others said rather than repeating it.",
+        name,
+        others.join(", "),
+        plan.goal,
+        name
+    );
+    if let Some(stop_phrase) = &plan.stop_phrase {
+        prompt.push_str(&format!(" Once the goal has been reached, end your turn with {}.", stop_phrase));
+    }
+    match persona {
+        Some(persona) => format!("{}\n\n{}", persona, prompt),
+        None => prompt,
+    }
+}
+
+/// What `agent` is sent for its turn after `turns`: the goal and the others' turns as the user's
+/// messages, named, and its own as its answers, cut to `budget` tokens like any conversation
+pub fn turn_window(plan: &OrchestrationPlan, agent: usize, turns: &[Turn], system: &str, budget: usize) -> ContextWindow {
+    let mut messages: Vec<(&str, String)> = vec![("user", format!("The goal: {}", plan.goal))];
+    for turn in turns {
+        let (role, text) = if turn.agent == agent {
+            ("assistant", turn.text.clone())
+        } else {
+            ("user", format!("{}: {}", plan.agents[turn.agent].name, turn.text))
+        };
+        match messages.last_mut() {
+            Some((last_role, last_text)) if *last_role == role => {
+                last_text.push_str("\n\n");
+                last_text.push_str(&text);
+            }
+            _ => messages.push((role, text)),
+        }
+    }
+    let prompt = match messages.last() {
+        Some(("user", _)) => messages.pop().map(|(_, text)| text).unwrap_or_default(),
+        _ => "Continue.".to_string(),
+    };
+    let history: Vec<HistoryMessage> = messages.into_iter()
+        .map(|(role, text)| HistoryMessage::new("", role, text, None, None))
+        .collect();
+    context::build_context(&history, &prompt, budget).with_system(Some(system))
+}
+
+/// Whether `text` says the plan's stop phrase, in any case
+pub fn says_stop_phrase(plan: &OrchestrationPlan, text: &str) -> bool {
+    plan.stop_phrase.as_ref().is_some_and(|stop_phrase| text.to_lowercase().contains(&stop_phrase.to_lowercase()))
+}
+
+/// What the moderator is asked after a round
+pub fn moderator_prompt(plan: &OrchestrationPlan, turns: &[Turn]) -> String {
+    let names: Vec<&str> = plan.agents.iter().map(|agent| agent.name.as_str()).collect();
+    let transcript = turns.iter()
+        .map(|turn| format!("{}: {}", plan.agents[turn.agent].name, turn.text))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    format!(
+        "You are moderating a conversation between {} toward this goal: {}\n\n\
+         Transcript:\n{}\n\n\
+         Has the goal been reached? Reply with DONE if it has or CONTINUE if it hasn't, then one sentence on why.",
+        names.join(", "),
+        plan.goal,
+        transcript
+    )
+}
+
+/// Whether the moderator's answer ends the conversation
+pub fn moderator_done(answer: &str) -> bool {
+    answer.trim_start_matches(|c: char| !c.is_alphanumeric())
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .is_some_and(|word| word.eq_ignore_ascii_case("done"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(name: &str) -> OrchestrationAgent {
+        OrchestrationAgent { name: name.to_string(), persona: None, model: None }
+    }
+
+    fn plan(agents: &[&str]) -> OrchestrationPlan {
+        OrchestrationPlan {
+            goal: "Name the project".to_string(),
+            agents: agents.iter().map(|name| agent(name)).collect(),
+            max_turns: 6,
+            moderator_model: Some(" ".to_string()),
+            stop_phrase: Some(" AGREED ".to_string()),
+        }
+    }
+
+    #[test]
+    fn plans_need_a_goal_and_distinct_agents() {
+        let valid = validate(plan(&[" Poet ", "Critic"])).unwrap();
+        assert_eq!(valid.agents[0].name, "Poet");
+        assert_eq!((valid.moderator_model, valid.stop_phrase.as_deref()), (None, Some("AGREED")));
+        assert!(validate(plan(&["Poet"])).is_err());
+        assert!(validate(plan(&["Poet", "poet"])).is_err());
+        assert!(validate(plan(&["Poet", " "])).is_err());
+        assert!(validate(OrchestrationPlan { goal: " ".to_string(), ..plan(&["Poet", "Critic"]) }).is_err());
+        assert!(validate(OrchestrationPlan { max_turns: MAX_TURNS + 1, ..plan(&["Poet", "Critic"]) }).is_err());
+    }
+
+    #[test]
+    fn agents_see_the_others_as_the_user() {
+        let plan = validate(plan(&["Poet", "Critic", "Editor"])).unwrap();
+        let turns = [
+            Turn { agent: 0, text: "Lantern".to_string() },
+            Turn { agent: 1, text: "Too soft".to_string() },
+            Turn { agent: 2, text: "Try Beacon".to_string() },
+        ];
+        assert_eq!(next_agent(&plan, turns.len()), 0);
+        assert!(round_complete(&plan, turns.len()) && !round_complete(&plan, 0));
+
+        let window = turn_window(&plan, 0, &turns, &agent_prompt(&plan, 0, Some("Be lyrical.")), 10_000);
+        let roles: Vec<&str> = window.messages.iter().map(|(role, _)| role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user"]);
+        assert!(window.messages[0].1.starts_with("Be lyrical.\n\nYou are Poet in a conversation with Critic, Editor"));
+        assert_eq!(window.messages[3].1, "Critic: Too soft\n\nEditor: Try Beacon");
+
+        let window = turn_window(&plan, 1, &turns[..1], "", 10_000);
+        assert_eq!(window.messages.last().unwrap().1, "The goal: Name the project\n\nPoet: Lantern");
+    }
+
+    #[test]
+    fn stop_phrases_and_moderators_end_conversations() {
+        let plan = validate(plan(&["Poet", "Critic"])).unwrap();
+        assert!(says_stop_phrase(&plan, "Beacon it is. Agreed."));
+        assert!(!says_stop_phrase(&plan, "Not yet"));
+        assert!(moderator_done("**DONE** - they settled on Beacon"));
+        assert!(!moderator_done("CONTINUE: nothing is done yet"));
+        assert!(moderator_prompt(&plan, &[Turn { agent: 1, text: "No".to_string() }]).contains("Critic: No"));
+    }
+}
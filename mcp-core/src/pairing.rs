@@ -0,0 +1,194 @@
+//! Pairing companion devices with the API server. The desktop shows a short-lived code as a QR
+//! code; a phone or browser on the LAN sends it to `POST /pair` and gets a token of its own back,
+//! so nobody copies a secret by hand or sets up client certificates. Each code works once.
+
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::server_auth::TokenScope;
+use crate::tr;
+
+/// How long a pairing code can be used for
+pub const PAIRING_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Wrong codes tolerated before every pending code is dropped, so codes can't be guessed
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+
+/// Without letters easily misread for digits (I, L, O, U)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTVWXYZ23456789";
+const CODE_LENGTH: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct PairingCode {
+    /// For typing in by hand, as `ABCD-EFGH`
+    pub code: String,
+    /// For the QR code: the server's address, the code and, with TLS, the fingerprint of the
+    /// server certificate for the companion to pin
+    pub uri: String,
+    pub scope: TokenScope,
+    pub expires_at: String,
+}
+
+/// A companion device holding a token issued by pairing
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct PairedDevice {
+    /// The id of the device's token
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    pub paired_at: String,
+}
+
+/// Body of `POST /pair`
+#[derive(Deserialize)]
+pub struct PairingRequest {
+    pub code: String,
+    #[serde(default)]
+    pub device_name: String,
+}
+
+/// Reply to a successful `POST /pair`; the token is never shown again
+#[derive(Serialize)]
+pub struct PairingResponse {
+    pub device_id: String,
+    pub token: String,
+    pub scope: TokenScope,
+}
+
+struct Pending {
+    code: String,
+    scope: TokenScope,
+    expires: Instant,
+}
+
+struct Pairings {
+    pending: Vec<Pending>,
+    failed_attempts: u32,
+}
+
+static PAIRINGS: Mutex<Pairings> = Mutex::new(Pairings { pending: Vec::new(), failed_attempts: 0 });
+
+fn pairings() -> std::sync::MutexGuard<'static, Pairings> {
+    PAIRINGS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A new code for a device to pair with `scope`, as shown to the user
+pub fn issue(scope: TokenScope) -> String {
+    let code: String = (0..CODE_LENGTH).map(|_| random_code_char()).collect();
+    let mut pairings = pairings();
+    let now = Instant::now();
+    pairings.pending.retain(|pending| pending.expires > now);
+    pairings.pending.push(Pending { code: code.clone(), scope, expires: now + PAIRING_TTL });
+    pairings.failed_attempts = 0;
+    format!("{}-{}", &code[..CODE_LENGTH / 2], &code[CODE_LENGTH / 2..])
+}
+
+/// A character from the code alphabet, each equally likely. Draws past the last whole multiple
+/// of the alphabet's length are redrawn, as wrapping them around would favour the first few.
+fn random_code_char() -> char {
+    let len = CODE_ALPHABET.len() as u64;
+    let limit = (1u64 << 32) / len * len;
+    loop {
+        let draw = u64::from(OsRng.next_u32());
+        if draw < limit {
+            return CODE_ALPHABET[(draw % len) as usize] as char;
+        }
+    }
+}
+
+/// The scope a code was issued for, using it up; None if it's unknown or expired
+pub fn redeem(code: &str) -> Option<TokenScope> {
+    let code: String = code.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_uppercase()).collect();
+    let mut pairings = pairings();
+    let now = Instant::now();
+    pairings.pending.retain(|pending| pending.expires > now);
+    if let Some(at) = pairings.pending.iter().position(|pending| pending.code == code) {
+        return Some(pairings.pending.remove(at).scope);
+    }
+    pairings.failed_attempts += 1;
+    if pairings.failed_attempts >= MAX_FAILED_ATTEMPTS {
+        log::warn!("{} wrong pairing codes; dropping every pending code", pairings.failed_attempts);
+        pairings.pending.clear();
+        pairings.failed_attempts = 0;
+    }
+    None
+}
+
+/// Base URL companions reach the server at. A server listening on every interface is given
+/// this machine's address on the network its default route uses.
+pub fn server_url(address: SocketAddr, tls: bool) -> Result<String> {
+    let ip = if address.ip().is_unspecified() { local_network_ip(address.ip())? } else { address.ip() };
+    if ip.is_loopback() {
+        anyhow::bail!(tr!("pairing-loopback-only"));
+    }
+    Ok(format!("{}://{}", if tls { "https" } else { "http" }, SocketAddr::new(ip, address.port())))
+}
+
+fn local_network_ip(unspecified: IpAddr) -> Result<IpAddr> {
+    // Connecting a UDP socket sends nothing; it only picks the route and so the local address
+    let (bind, probe) = match unspecified {
+        IpAddr::V4(_) => ("0.0.0.0:0", "192.0.2.1:9"),
+        IpAddr::V6(_) => ("[::]:0", "[2001:db8::1]:9"),
+    };
+    let socket = UdpSocket::bind(bind)?;
+    socket.connect(probe)?;
+    Ok(socket.local_addr()?.ip())
+}
+
+/// `mcp-switchboard://pair?server=…&code=…[&fingerprint=…]`
+pub fn pairing_uri(server_url: &str, code: &str, fingerprint: Option<&str>) -> String {
+    let mut uri = format!("mcp-switchboard://pair?server={}&code={}", percent_encode(server_url), percent_encode(code));
+    if let Some(fingerprint) = fingerprint {
+        uri.push_str(&format!("&fingerprint={}", percent_encode(fingerprint)));
+    }
+    uri
+}
+
+fn percent_encode(value: &str) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_work_once_and_guessing_drops_them() {
+        let code = issue(TokenScope::Chat);
+        assert_eq!(code.len(), CODE_LENGTH + 1);
+        assert_eq!(redeem(&format!(" {} ", code.to_lowercase())), Some(TokenScope::Chat));
+        assert_eq!(redeem(&code), None);
+
+        let code = issue(TokenScope::Admin);
+        for _ in 0..MAX_FAILED_ATTEMPTS {
+            assert_eq!(redeem("WRONG-CODE"), None);
+        }
+        assert_eq!(redeem(&code), None);
+
+        assert_eq!(
+            pairing_uri("https://192.168.1.20:8765", "ABCD-EFGH", Some("ab:cd")),
+            "mcp-switchboard://pair?server=https%3A%2F%2F192.168.1.20%3A8765&code=ABCD-EFGH&fingerprint=ab%3Acd",
+        );
+        assert!(server_url("127.0.0.1:8765".parse().unwrap(), false).is_err());
+        assert_eq!(server_url("192.168.1.20:8765".parse().unwrap(), true).unwrap(), "https://192.168.1.20:8765");
+    }
+
+    #[test]
+    fn codes_draw_on_the_whole_alphabet() {
+        let drawn: std::collections::HashSet<char> = (0..3000).map(|_| random_code_char()).collect();
+        assert_eq!(drawn.len(), CODE_ALPHABET.len());
+        assert!(drawn.iter().all(|c| CODE_ALPHABET.contains(&(*c as u8))));
+    }
+}
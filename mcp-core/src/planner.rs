@@ -0,0 +1,297 @@
+//! Task plans, kept with a conversation as a checklist the UI shows beside it. Sending
+//! `/plan <goal>` asks the model to break the goal into steps that may depend on each other, and
+//! the plan it answers with is saved in place of any earlier one. From then on every answer in
+//! the conversation, an orchestrated agent's turns included, is shown the plan and told to mark
+//! the steps it starts, finishes or gives up on with `[step <id>: <status>]`, and those marks
+//! update the saved plan.
+
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+
+/// What a message starts with to ask for a plan
+pub const COMMAND: &str = "/plan";
+
+/// Most steps one plan has
+pub const MAX_STEPS: usize = 30;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum PlanStepStatus {
+    Pending,
+    InProgress,
+    Done,
+    /// Can't go on until something outside the plan changes
+    Blocked,
+    /// Left out on purpose
+    Skipped,
+}
+
+impl PlanStepStatus {
+    /// How the status is written in a step mark
+    fn label(self) -> &'static str {
+        match self {
+            PlanStepStatus::Pending => "pending",
+            PlanStepStatus::InProgress => "in progress",
+            PlanStepStatus::Done => "done",
+            PlanStepStatus::Blocked => "blocked",
+            PlanStepStatus::Skipped => "skipped",
+        }
+    }
+
+    fn parse(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().replace(['_', '-'], " ").as_str() {
+            "pending" | "todo" => Some(PlanStepStatus::Pending),
+            "in progress" | "started" => Some(PlanStepStatus::InProgress),
+            "done" | "complete" | "completed" => Some(PlanStepStatus::Done),
+            "blocked" => Some(PlanStepStatus::Blocked),
+            "skipped" => Some(PlanStepStatus::Skipped),
+            _ => None,
+        }
+    }
+
+    /// Whether the steps that depend on this one can start
+    fn settled(self) -> bool {
+        matches!(self, PlanStepStatus::Done | PlanStepStatus::Skipped)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct PlanStep {
+    /// Short and unique within the plan, e.g. "3"
+    pub id: String,
+    pub title: String,
+    pub detail: Option<String>,
+    /// Ids of the steps that have to be done first
+    pub depends_on: Vec<String>,
+    pub status: PlanStepStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct TaskPlan {
+    pub conversation_id: String,
+    pub goal: String,
+    /// Each after the steps it depends on
+    pub steps: Vec<PlanStep>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A step as the model writes it; ids may come as numbers and anything but the title may be left out
+#[derive(Deserialize)]
+struct DraftStep {
+    id: Option<serde_json::Value>,
+    title: String,
+    detail: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Draft {
+    steps: Vec<DraftStep>,
+}
+
+fn id_of(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(id) => Some(id.trim().to_string()).filter(|id| !id.is_empty()),
+        serde_json::Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// The goal of a `/plan` message, or None for any other message. The goal is empty when the
+/// command was sent alone.
+pub fn command(message: &str) -> Option<&str> {
+    let rest = message.trim_start().strip_prefix(COMMAND)?;
+    match rest.chars().next() {
+        None => Some(""),
+        Some(c) if c.is_whitespace() => Some(rest.trim()),
+        Some(_) => None,
+    }
+}
+
+/// What the model is told when asked for a plan toward `goal`
+pub fn planning_instruction(goal: &str) -> String {
+    format!(
+        "The user asked for a plan toward this goal: {}\n\
+         Break it into concrete steps. Reply with a sentence or two on the approach, then the plan as JSON in a \
+         ```json code block, shaped like {{\"steps\": [{{\"id\": \"1\", \"title\": \"...\", \"detail\": \"...\", \"depends_on\": []}}]}}. \
+         Keep ids short, list each step after the steps it depends on, and use at most {} steps.",
+        goal, MAX_STEPS
+    )
+}
+
+/// What every later answer in a conversation with `plan` is told: the checklist as it stands
+/// and how to mark progress on it
+pub fn progress_instruction(plan: &TaskPlan) -> String {
+    let checklist = plan.steps.iter()
+        .map(|step| {
+            let mut line = format!("- [step {}: {}] {}", step.id, step.status.label(), step.title);
+            if !step.depends_on.is_empty() {
+                line.push_str(&format!(" (after {})", step.depends_on.join(", ")));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let ready: Vec<&str> = ready(plan).iter().map(|step| step.id.as_str()).collect();
+    let ready = if ready.is_empty() { String::new() } else { format!("Ready to start: {}\n", ready.join(", ")) };
+    format!(
+        "This conversation is working through a plan toward: {}\n{}\n{}\
+         When your answer starts, finishes or gives up on a step, say so on a line of its own as \
+         [step <id>: in progress], [step <id>: done], [step <id>: blocked] or [step <id>: skipped].",
+        plan.goal, checklist, ready
+    )
+}
+
+/// The plan in a model's answer to `planning_instruction`, every step pending. Fails with a
+/// localized error if there is no plan in the answer, it has no steps or too many, or its steps
+/// share an id or depend on steps that don't exist or on each other in a circle.
+pub fn parse(conversation_id: &str, goal: &str, answer: &str) -> Result<TaskPlan, String> {
+    // The plan comes in prose and a code fence; the object spans the first brace to the last
+    let (Some(start), Some(end)) = (answer.find('{'), answer.rfind('}')) else {
+        return Err(tr!("plan-not-in-answer"));
+    };
+    let draft: Draft = serde_json::from_str(answer.get(start..=end).unwrap_or_default()).map_err(|e| {
+        log::warn!("Unreadable plan in answer: {}", e);
+        tr!("plan-not-in-answer")
+    })?;
+    if draft.steps.is_empty() || draft.steps.len() > MAX_STEPS {
+        return Err(tr!("plan-step-count", max = MAX_STEPS));
+    }
+
+    let mut steps: Vec<PlanStep> = Vec::with_capacity(draft.steps.len());
+    for (index, step) in draft.steps.into_iter().enumerate() {
+        let id = step.id.as_ref().and_then(id_of).unwrap_or_else(|| (index + 1).to_string());
+        if steps.iter().any(|other| other.id == id) {
+            return Err(tr!("plan-step-duplicate", id = id));
+        }
+        steps.push(PlanStep {
+            id,
+            title: step.title.trim().to_string(),
+            detail: step.detail.map(|detail| detail.trim().to_string()).filter(|detail| !detail.is_empty()),
+            depends_on: step.depends_on.iter().filter_map(id_of).collect(),
+            status: PlanStepStatus::Pending,
+        });
+    }
+    for step in &steps {
+        if let Some(missing) = step.depends_on.iter().find(|id| !steps.iter().any(|other| other.id == **id)) {
+            return Err(tr!("plan-dependency-unknown", step = step.id.clone(), dependency = missing.clone()));
+        }
+    }
+
+    // Order the steps so each follows its dependencies; whatever can't be placed is in a cycle
+    let mut ordered: Vec<PlanStep> = Vec::with_capacity(steps.len());
+    while !steps.is_empty() {
+        let Some(index) = steps.iter().position(|step| {
+            step.depends_on.iter().all(|id| ordered.iter().any(|placed| placed.id == *id))
+        }) else {
+            return Err(tr!("plan-dependency-cycle", step = steps[0].id.clone()));
+        };
+        ordered.push(steps.remove(index));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    Ok(TaskPlan {
+        conversation_id: conversation_id.to_string(),
+        goal: goal.trim().to_string(),
+        steps: ordered,
+        created_at: now.clone(),
+        updated_at: now,
+    })
+}
+
+/// Set the status of step `id`; whether it changed, or a localized error if there's no such step
+pub fn set_status(plan: &mut TaskPlan, id: &str, status: PlanStepStatus) -> Result<bool, String> {
+    let step = plan.steps.iter_mut()
+        .find(|step| step.id == id.trim())
+        .ok_or_else(|| tr!("plan-step-unknown", id = id.trim().to_string()))?;
+    if step.status == status {
+        return Ok(false);
+    }
+    step.status = status;
+    plan.updated_at = chrono::Utc::now().to_rfc3339();
+    Ok(true)
+}
+
+fn mark_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?i)\[step\s+([^\]:]+?)\s*:\s*([a-z _-]+?)\s*\]").expect("valid step mark pattern"))
+}
+
+/// Apply the `[step <id>: <status>]` marks in `answer`, later marks winning; how many steps
+/// changed. Marks for steps the plan doesn't have, or in a status it doesn't know, are ignored.
+pub fn apply_marks(plan: &mut TaskPlan, answer: &str) -> u32 {
+    let mut changed = Vec::new();
+    for mark in mark_pattern().captures_iter(answer) {
+        let Some(status) = PlanStepStatus::parse(&mark[2]) else { continue };
+        if let Ok(true) = set_status(plan, &mark[1], status) {
+            changed.push(mark[1].trim().to_string());
+        }
+    }
+    changed.sort();
+    changed.dedup();
+    changed.len() as u32
+}
+
+/// Steps not started whose dependencies are all done or skipped
+pub fn ready(plan: &TaskPlan) -> Vec<&PlanStep> {
+    plan.steps.iter()
+        .filter(|step| step.status == PlanStepStatus::Pending)
+        .filter(|step| step.depends_on.iter().all(|id| {
+            plan.steps.iter().any(|other| other.id == *id && other.status.settled())
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWER: &str = "Here's how I'd go about it:\n\n```json\n{\"steps\": [\n\
+        {\"id\": 1, \"title\": \"Pick a name\", \"depends_on\": []},\n\
+        {\"id\": \"3\", \"title\": \"Announce it\", \"detail\": \" \", \"depends_on\": [\"2\", 1]},\n\
+        {\"id\": \"2\", \"title\": \"Register the domain\", \"depends_on\": [\"1\"]}\n]}\n```";
+
+    #[test]
+    fn only_plan_commands_are_taken() {
+        assert_eq!(command("/plan  Launch the site "), Some("Launch the site"));
+        assert_eq!(command(" /plan"), Some(""));
+        assert_eq!(command("/planet facts"), None);
+        assert_eq!(command("Make a /plan"), None);
+    }
+
+    #[test]
+    fn plans_are_ordered_and_checked() {
+        let plan = parse("c1", " Launch ", ANSWER).unwrap();
+        let ids: Vec<&str> = plan.steps.iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+        assert_eq!((plan.goal.as_str(), plan.steps[2].detail.as_ref()), ("Launch", None));
+        assert_eq!(plan.steps[2].depends_on, ["2", "1"]);
+
+        assert!(parse("c1", "Launch", "No plan, sorry").is_err());
+        assert!(parse("c1", "Launch", "{\"steps\": []}").is_err());
+        assert!(parse("c1", "Launch", &ANSWER.replace("[\"1\"]", "[\"4\"]")).is_err());
+        assert!(parse("c1", "Launch", &ANSWER.replace("[\"1\"]", "[\"3\"]")).is_err());
+        assert!(parse("c1", "Launch", &ANSWER.replace("\"id\": \"3\"", "\"id\": \"2\"")).is_err());
+    }
+
+    #[test]
+    fn marks_in_answers_update_steps() {
+        let mut plan = parse("c1", "Launch", ANSWER).unwrap();
+        assert_eq!(ready(&plan).len(), 1);
+        let answer = "Lantern it is.\n[step 1: in progress]\n[Step 1: Done]\n[step 2: in_progress]\n[step 9: done]\n[step 3: later]";
+        assert_eq!(apply_marks(&mut plan, answer), 2);
+        assert_eq!(plan.steps[0].status, PlanStepStatus::Done);
+        assert_eq!(plan.steps[1].status, PlanStepStatus::InProgress);
+        assert!(ready(&plan).is_empty());
+        assert_eq!(apply_marks(&mut plan, "[step 1: done]"), 0);
+        assert!(set_status(&mut plan, "9", PlanStepStatus::Done).is_err());
+        assert!(progress_instruction(&plan).contains("- [step 3: pending] Announce it (after 2, 1)"));
+    }
+}
@@ -0,0 +1,186 @@
+//! Who and where the app is running: user name, hostname, and the base config and data
+//! directories. Everything that needs one asks here, so Windows (no `$USER`), Flatpak and Snap
+//! sandboxes and headless CI (often no `$HOME` or XDG variables) all resolve the same way.
+
+use std::path::PathBuf;
+
+/// Directory name under the base config and data directories
+pub const APP_DIR: &str = "mcp-switchboard";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sandbox {
+    None,
+    /// Flatpak already points the XDG variables inside `~/.var/app/<id>`
+    Flatpak,
+    /// Snap's `$HOME` is per revision, so files go in `$SNAP_USER_COMMON`, which survives refreshes
+    Snap,
+}
+
+/// Base directories, before the app's own subdirectory is added
+#[derive(Clone, Debug, PartialEq)]
+pub struct BaseDirs {
+    pub config: PathBuf,
+    pub data: PathBuf,
+}
+
+type Lookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn sandbox_from(lookup: Lookup) -> Sandbox {
+    if lookup("FLATPAK_ID").is_some() {
+        Sandbox::Flatpak
+    } else if lookup("SNAP_USER_COMMON").is_some() {
+        Sandbox::Snap
+    } else {
+        Sandbox::None
+    }
+}
+
+pub fn sandbox() -> Sandbox {
+    sandbox_from(&env_var)
+}
+
+/// The login name: `USER` on Unix, `USERNAME` on Windows, `LOGNAME` where only that is set
+fn user_name_from(lookup: Lookup) -> Option<String> {
+    ["USER", "USERNAME", "LOGNAME"].iter().find_map(|name| lookup(name))
+}
+
+pub fn user_name() -> Option<String> {
+    user_name_from(&env_var)
+}
+
+/// This machine's hostname. Containers and CI runners sometimes report an empty one, in which
+/// case the `HOSTNAME` or `COMPUTERNAME` variable is used, then "unknown".
+pub fn hostname() -> String {
+    let hostname = gethostname::gethostname().to_string_lossy().trim().to_string();
+    if !hostname.is_empty() {
+        return hostname;
+    }
+    env_var("HOSTNAME").or_else(|| env_var("COMPUTERNAME")).unwrap_or_else(|| "unknown".to_string())
+}
+
+/// `system` is what the `dirs` crate found, which needs `$HOME` or the Windows known folders
+fn base_dirs_from(sandbox: Sandbox, lookup: Lookup, system: Option<BaseDirs>) -> Option<BaseDirs> {
+    if sandbox == Sandbox::Snap {
+        if let Some(common) = lookup("SNAP_USER_COMMON") {
+            let common = PathBuf::from(common);
+            return Some(BaseDirs { config: common.join("config"), data: common.join("data") });
+        }
+    }
+    if let Some(system) = system {
+        return Some(system);
+    }
+    // Headless runs without a home the OS knows about; fall back to the variables directly
+    if let Some(appdata) = lookup("APPDATA") {
+        let local = lookup("LOCALAPPDATA").unwrap_or_else(|| appdata.clone());
+        return Some(BaseDirs { config: PathBuf::from(appdata), data: PathBuf::from(local) });
+    }
+    let home = PathBuf::from(lookup("HOME")?);
+    let config = lookup("XDG_CONFIG_HOME").map(PathBuf::from).unwrap_or_else(|| home.join(".config"));
+    let data = lookup("XDG_DATA_HOME").map(PathBuf::from).unwrap_or_else(|| home.join(".local").join("share"));
+    Some(BaseDirs { config, data })
+}
+
+fn base_dirs() -> Option<BaseDirs> {
+    let system = dirs::config_dir().zip(dirs::data_dir()).map(|(config, data)| BaseDirs { config, data });
+    base_dirs_from(sandbox(), &env_var, system)
+}
+
+/// The app's config directory, e.g. `~/.config/mcp-switchboard`, or `config` in the portable folder
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(root) = crate::portable::root() {
+        return Some(root.join("config"));
+    }
+    base_dirs().map(|dirs| dirs.config.join(APP_DIR))
+}
+
+/// The app's data directory, e.g. `~/.local/share/mcp-switchboard`, or `data` in the portable folder
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(root) = crate::portable::root() {
+        return Some(root.join("data"));
+    }
+    base_dirs().map(|dirs| dirs.data.join(APP_DIR))
+}
+
+/// Where an administrator keeps machine-wide files such as the defaults and the policy
+pub fn system_dir() -> PathBuf {
+    if cfg!(windows) {
+        let program_data = std::env::var("ProgramData").unwrap_or_else(|_| r"C:\ProgramData".to_string());
+        PathBuf::from(program_data).join(APP_DIR)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support").join(APP_DIR)
+    } else {
+        PathBuf::from("/etc").join(APP_DIR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn system() -> Option<BaseDirs> {
+        Some(BaseDirs { config: PathBuf::from("/system/config"), data: PathBuf::from("/system/data") })
+    }
+
+    #[test]
+    fn user_name_falls_back_to_windows_and_logname() {
+        assert_eq!(user_name_from(&lookup(&[("USER", "unix"), ("USERNAME", "win")])).as_deref(), Some("unix"));
+        assert_eq!(user_name_from(&lookup(&[("USERNAME", "win"), ("LOGNAME", "log")])).as_deref(), Some("win"));
+        assert_eq!(user_name_from(&lookup(&[("LOGNAME", "log")])).as_deref(), Some("log"));
+        assert_eq!(user_name_from(&lookup(&[])), None);
+    }
+
+    #[test]
+    fn snap_files_live_in_the_common_directory() {
+        let env = lookup(&[("SNAP_USER_COMMON", "/home/u/snap/app/common"), ("HOME", "/home/u/snap/app/12")]);
+        assert_eq!(sandbox_from(&env), Sandbox::Snap);
+        let dirs = base_dirs_from(Sandbox::Snap, &env, system()).unwrap();
+        assert_eq!(dirs.config, PathBuf::from("/home/u/snap/app/common/config"));
+        assert_eq!(dirs.data, PathBuf::from("/home/u/snap/app/common/data"));
+    }
+
+    #[test]
+    fn flatpak_uses_the_system_directories() {
+        let env = lookup(&[("FLATPAK_ID", "io.example.App")]);
+        assert_eq!(sandbox_from(&env), Sandbox::Flatpak);
+        assert_eq!(base_dirs_from(Sandbox::Flatpak, &env, system()), system());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn headless_unix_falls_back_to_home() {
+        let env = lookup(&[("HOME", "/ci")]);
+        let dirs = base_dirs_from(Sandbox::None, &env, None).unwrap();
+        assert_eq!(dirs.config, PathBuf::from("/ci/.config"));
+        assert_eq!(dirs.data, PathBuf::from("/ci/.local/share"));
+        let env = lookup(&[("HOME", "/ci"), ("XDG_DATA_HOME", "/xdg/data")]);
+        assert_eq!(base_dirs_from(Sandbox::None, &env, None).unwrap().data, PathBuf::from("/xdg/data"));
+        assert_eq!(base_dirs_from(Sandbox::None, &lookup(&[]), None), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn headless_windows_falls_back_to_appdata() {
+        let env = lookup(&[("APPDATA", r"C:\Users\u\AppData\Roaming"), ("LOCALAPPDATA", r"C:\Users\u\AppData\Local")]);
+        let dirs = base_dirs_from(Sandbox::None, &env, None).unwrap();
+        assert_eq!(dirs.config, PathBuf::from(r"C:\Users\u\AppData\Roaming"));
+        assert_eq!(dirs.data, PathBuf::from(r"C:\Users\u\AppData\Local"));
+    }
+
+    #[test]
+    fn resolves_on_this_machine() {
+        // Every supported target, CI included, must end up with somewhere to keep config
+        assert!(config_dir().is_some_and(|dir| dir.ends_with(APP_DIR)));
+        assert!(data_dir().is_some_and(|dir| dir.ends_with(APP_DIR)));
+        assert!(!hostname().is_empty());
+    }
+}
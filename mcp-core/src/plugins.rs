@@ -0,0 +1,630 @@
+//! WASM plugin host.
+//!
+//! Plugins live in `<data_dir>/plugins/<name>/` with a `plugin.json` manifest and a `.wasm` module.
+//! A module must export `memory` and `alloc(len: i32) -> i32`, and may export:
+//! - `init()` – called once after loading; may call the registration imports below
+//! - `transform_message(ptr: i32, len: i32) -> i64` – rewrite an outgoing message
+//! - `handle(ptr: i32, len: i32) -> i64` – run a registered command or tool; input is
+//!   `{"kind": "command"|"tool", "name": ..., "input": ...}`
+//!
+//! Returned `i64` values pack an output buffer as `(ptr << 32) | len`.
+//! Imports come from the `switchboard` namespace and are only linked when the user granted the
+//! matching permission, so a module importing an ungranted capability fails to load:
+//! - `log(ptr, len)` – always available
+//! - `register_command(ptr, len)` – JSON `{name, description}`, needs `register_commands`
+//! - `register_tool(ptr, len)` – JSON `{name, description, input_schema}`, needs `register_tools`
+//! - `get_secret(ptr, len) -> i64` – the vault secret with that name, as a packed buffer, or -1
+//!   unless the secret exists and is owned by the plugin's directory; needs `read_secrets`
+//!
+//! `transform_message` is only invoked with the `transform_messages` permission.
+//!
+//! Grants are keyed by the plugin's directory and a hash of its module, never by the name a
+//! manifest gives itself, so a changed module or a copy elsewhere is asked about again.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+
+pub const PERMISSION_REGISTER_COMMANDS: &str = "register_commands";
+pub const PERMISSION_REGISTER_TOOLS: &str = "register_tools";
+pub const PERMISSION_TRANSFORM_MESSAGES: &str = "transform_messages";
+pub const PERMISSION_READ_SECRETS: &str = "read_secrets";
+
+const KNOWN_PERMISSIONS: &[&str] = &[
+    PERMISSION_REGISTER_COMMANDS,
+    PERMISSION_REGISTER_TOOLS,
+    PERMISSION_TRANSFORM_MESSAGES,
+    PERMISSION_READ_SECRETS,
+];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub wasm: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+pub enum PluginStatus {
+    /// Discovered but the user has not yet approved its permissions
+    PendingApproval,
+    Loaded,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PluginCommand {
+    #[serde(default)]
+    pub plugin: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct PluginTool {
+    #[serde(default)]
+    pub plugin: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub input_schema: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PluginInfo {
+    /// The plugin's directory name, which its manifest can't choose
+    pub id: String,
+    /// SHA-256 of the plugin's module, or None if it can't be read
+    pub module_sha256: Option<String>,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub requested_permissions: Vec<String>,
+    pub granted_permissions: Vec<String>,
+    pub status: PluginStatus,
+    pub error: Option<String>,
+    pub commands: Vec<PluginCommand>,
+    pub tools: Vec<PluginTool>,
+}
+
+/// Find all plugin manifests under `<data_dir>/plugins`
+pub fn discover(data_dir: &Path) -> Result<Vec<(PathBuf, PluginManifest)>> {
+    let root = data_dir.join("plugins");
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(&root)? {
+        let dir = entry?.path();
+        let manifest_path = dir.join("plugin.json");
+        if !manifest_path.is_file() {
+            continue;
+        }
+        match serde_json::from_slice::<PluginManifest>(&std::fs::read(&manifest_path)?) {
+            Ok(manifest) => {
+                for permission in &manifest.permissions {
+                    if !KNOWN_PERMISSIONS.contains(&permission.as_str()) {
+                        log::warn!("Plugin {} requests unknown permission {}", manifest.name, permission);
+                    }
+                }
+                found.push((dir, manifest));
+            }
+            Err(e) => log::warn!("Skipping invalid plugin manifest {:?}: {}", manifest_path, e),
+        }
+    }
+    found.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    Ok(found)
+}
+
+/// The permissions a plugin both asked for and was given
+fn granted_permissions(requested: &[String], granted: &[String]) -> Vec<String> {
+    requested.iter().filter(|p| granted.contains(p)).cloned().collect()
+}
+
+/// What the user's approval of a plugin is stored under
+pub fn grant_key(id: &str, module_sha256: &str) -> String {
+    format!("{}#{}", id, module_sha256)
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+    Ok(format!("{:x}", Sha256::digest(std::fs::read(path)?)))
+}
+
+/// `wasm` resolved within the plugin's own directory. Symlinks are followed first, so neither
+/// an absolute path, `..` nor a link can point a manifest at a module elsewhere.
+fn module_path(dir: &Path, wasm: &str) -> Result<PathBuf> {
+    let dir = dir.canonicalize()?;
+    let path = dir.join(wasm).canonicalize()
+        .map_err(|e| anyhow::anyhow!("Plugin module {} can't be read: {}", wasm, e))?;
+    if !path.starts_with(&dir) {
+        anyhow::bail!("Plugin module {} is outside the plugin's directory", wasm);
+    }
+    Ok(path)
+}
+
+#[derive(Default)]
+struct Registry {
+    host: runtime::PluginHost,
+    infos: Option<Vec<PluginInfo>>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Status of plugins as of the last reload, or None if plugins were never loaded
+pub fn loaded_plugins() -> Option<Vec<PluginInfo>> {
+    registry().lock().ok()?.infos.clone()
+}
+
+/// (Re)load every discovered plugin. Plugins without a grant for their directory and module are
+/// reported as pending approval; only permissions that were both requested and granted are
+/// handed to the module.
+pub fn reload(data_dir: &Path, grants: &std::collections::HashMap<String, Vec<String>>) -> Result<Vec<PluginInfo>> {
+    let mut registry = registry().lock().map_err(|_| anyhow::anyhow!("Plugin host lock poisoned"))?;
+    registry.host.clear();
+
+    let discovered = discover(data_dir)?;
+    let mut infos = Vec::new();
+    for (dir, manifest) in &discovered {
+        let id = dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let module = module_path(dir, &manifest.wasm).and_then(|wasm| Ok((file_sha256(&wasm)?, wasm)));
+        let mut info = PluginInfo {
+            id: id.clone(),
+            module_sha256: module.as_ref().ok().map(|(sha256, _)| sha256.clone()),
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            description: manifest.description.clone(),
+            requested_permissions: manifest.permissions.clone(),
+            granted_permissions: Vec::new(),
+            status: PluginStatus::PendingApproval,
+            error: None,
+            commands: Vec::new(),
+            tools: Vec::new(),
+        };
+
+        // Commands, tools and secrets are found by name, so two plugins can't share one
+        if discovered.iter().filter(|(_, other)| other.name == manifest.name).count() > 1 {
+            log::error!("More than one plugin is named {}; loading none of them", manifest.name);
+            info.status = PluginStatus::Failed;
+            info.error = Some(format!("Another plugin is also named {}", manifest.name));
+            infos.push(info);
+            continue;
+        }
+        let (sha256, wasm) = match module {
+            Ok(module) => module,
+            Err(e) => {
+                log::error!("Failed to load plugin {}: {}", manifest.name, e);
+                info.status = PluginStatus::Failed;
+                info.error = Some(e.to_string());
+                infos.push(info);
+                continue;
+            }
+        };
+
+        if let Some(granted) = grants.get(&grant_key(&id, &sha256)) {
+            info.granted_permissions = granted_permissions(&manifest.permissions, granted);
+            match registry.host.load(&wasm, &manifest.name, &crate::vault::plugin_owner(&id), &info.granted_permissions) {
+                Ok((commands, tools)) => {
+                    log::info!("Loaded plugin {} v{}", manifest.name, manifest.version);
+                    info.status = PluginStatus::Loaded;
+                    info.commands = commands;
+                    info.tools = tools;
+                }
+                Err(e) => {
+                    log::error!("Failed to load plugin {}: {}", manifest.name, e);
+                    info.status = PluginStatus::Failed;
+                    info.error = Some(e.to_string());
+                }
+            }
+        }
+        infos.push(info);
+    }
+    registry.infos = Some(infos.clone());
+    Ok(infos)
+}
+
+/// Unload every plugin, as when the plugins feature is turned off
+pub fn unload() -> Result<()> {
+    let mut registry = registry().lock().map_err(|_| anyhow::anyhow!("Plugin host lock poisoned"))?;
+    registry.host.clear();
+    registry.infos = Some(Vec::new());
+    Ok(())
+}
+
+/// Run the outgoing message through every loaded plugin allowed to transform messages.
+/// A failing plugin is logged and skipped rather than blocking the chat.
+pub fn transform_message(message: String) -> String {
+    let Ok(mut registry) = registry().lock() else {
+        return message;
+    };
+    registry.host.transform_message(message)
+}
+
+/// Err unless `plugin` is loaded and registered the command or tool `name`. Registering needs
+/// its permission, so this also keeps a plugin without it from being dispatched to.
+fn require_registered(infos: &[PluginInfo], plugin: &str, kind: &str, name: &str) -> Result<()> {
+    let info = infos.iter()
+        .find(|info| info.name == plugin && info.status == PluginStatus::Loaded)
+        .ok_or_else(|| anyhow::anyhow!("Plugin not loaded: {}", plugin))?;
+    let registered = match kind {
+        "command" => info.commands.iter().any(|command| command.name == name),
+        "tool" => info.tools.iter().any(|tool| tool.name == name),
+        _ => false,
+    };
+    if !registered {
+        anyhow::bail!("Plugin {} has no {} named {}", plugin, kind, name);
+    }
+    Ok(())
+}
+
+/// Dispatch a registered command or tool to its plugin. The registry is only held to find the
+/// plugin, so calls into different plugins can run at the same time.
+pub fn invoke(plugin: &str, kind: &str, name: &str, input: serde_json::Value) -> Result<String> {
+    let handle = {
+        let registry = registry().lock().map_err(|_| anyhow::anyhow!("Plugin host lock poisoned"))?;
+        require_registered(registry.infos.as_deref().unwrap_or_default(), plugin, kind, name)?;
+        registry.host.handle_for(plugin)?
+    };
+    let request = serde_json::json!({ "kind": kind, "name": name, "input": input });
+    handle.handle(&request.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn data_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("mcp-plugins-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn add_plugin(data_dir: &Path, id: &str, name: &str, wasm: &str) -> PathBuf {
+        let dir = data_dir.join("plugins").join(id);
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = serde_json::json!({ "name": name, "version": "1.0.0", "wasm": wasm, "permissions": ["register_tools", "read_secrets"] });
+        std::fs::write(dir.join("plugin.json"), manifest.to_string()).unwrap();
+        dir
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn permissions_are_those_both_requested_and_granted() {
+        let requested = strings(&["register_tools", "read_secrets"]);
+        assert_eq!(granted_permissions(&requested, &strings(&["read_secrets", "transform_messages"])), strings(&["read_secrets"]));
+        assert!(granted_permissions(&requested, &[]).is_empty());
+        assert!(granted_permissions(&[], &requested).is_empty());
+    }
+
+    #[test]
+    fn modules_outside_the_plugin_directory_are_refused() {
+        let data_dir = data_dir();
+        let outside = add_plugin(&data_dir, "outside", "outside", "module.wasm");
+        std::fs::write(outside.join("module.wasm"), b"\0asm").unwrap();
+        add_plugin(&data_dir, "parent", "parent", "../outside/module.wasm");
+        add_plugin(&data_dir, "absolute", "absolute", &outside.join("module.wasm").to_string_lossy());
+
+        let infos = reload(&data_dir, &HashMap::new()).unwrap();
+        for id in ["parent", "absolute"] {
+            let info = infos.iter().find(|info| info.id == id).unwrap();
+            assert!(info.status == PluginStatus::Failed, "{}", id);
+            assert!(info.error.as_deref().unwrap().contains("outside the plugin's directory"), "{}", id);
+        }
+        assert!(infos.iter().find(|info| info.id == "outside").unwrap().status == PluginStatus::PendingApproval);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn grants_cover_one_directory_and_module() {
+        let data_dir = data_dir();
+        let dir = add_plugin(&data_dir, "approved", "search", "module.wasm");
+        std::fs::write(dir.join("module.wasm"), b"\0asm v1").unwrap();
+        let sha256 = reload(&data_dir, &HashMap::new()).unwrap()[0].module_sha256.clone().unwrap();
+        let grants = HashMap::from([(grant_key("approved", &sha256), strings(&["register_tools"]))]);
+
+        // Granted, so loading is attempted; these bytes aren't a real module
+        let info = &reload(&data_dir, &grants).unwrap()[0];
+        assert!(info.status == PluginStatus::Failed);
+        assert_eq!(info.granted_permissions, strings(&["register_tools"]));
+
+        // A changed module, or the same one under another directory, needs approving again
+        std::fs::write(dir.join("module.wasm"), b"\0asm v2").unwrap();
+        assert!(reload(&data_dir, &grants).unwrap()[0].status == PluginStatus::PendingApproval);
+        std::fs::write(dir.join("module.wasm"), b"\0asm v1").unwrap();
+        std::fs::rename(&dir, data_dir.join("plugins").join("copied")).unwrap();
+        assert!(reload(&data_dir, &grants).unwrap()[0].status == PluginStatus::PendingApproval);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn plugins_sharing_a_name_are_refused() {
+        let data_dir = data_dir();
+        for id in ["first", "second"] {
+            let dir = add_plugin(&data_dir, id, "github", "module.wasm");
+            std::fs::write(dir.join("module.wasm"), id).unwrap();
+        }
+        let infos = reload(&data_dir, &HashMap::new()).unwrap();
+        assert_eq!(infos.len(), 2);
+        assert!(infos.iter().all(|info| info.status == PluginStatus::Failed));
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn only_registered_commands_and_tools_are_dispatched() {
+        let info = PluginInfo {
+            id: "notes".to_string(),
+            module_sha256: None,
+            name: "notes".to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            requested_permissions: Vec::new(),
+            granted_permissions: Vec::new(),
+            status: PluginStatus::Loaded,
+            error: None,
+            commands: vec![PluginCommand { plugin: "notes".to_string(), name: "save".to_string(), description: String::new() }],
+            tools: Vec::new(),
+        };
+        let infos = [info];
+        assert!(require_registered(&infos, "notes", "command", "save").is_ok());
+        assert!(require_registered(&infos, "notes", "command", "delete_everything").is_err());
+        assert!(require_registered(&infos, "notes", "tool", "save").is_err());
+        assert!(require_registered(&infos, "other", "command", "save").is_err());
+
+        let pending = [PluginInfo { status: PluginStatus::PendingApproval, ..infos[0].clone() }];
+        assert!(require_registered(&pending, "notes", "command", "save").is_err());
+    }
+}
+
+#[cfg(feature = "plugins")]
+mod runtime {
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use anyhow::Result;
+    use wasmtime::{Caller, Config, Engine, Instance, Linker, Memory, Module, Store};
+    use super::{PluginCommand, PluginTool};
+    use super::{PERMISSION_READ_SECRETS, PERMISSION_REGISTER_COMMANDS, PERMISSION_REGISTER_TOOLS, PERMISSION_TRANSFORM_MESSAGES};
+
+    /// Instruction budget per call into a plugin, so a runaway module can't hang the app
+    const FUEL_PER_CALL: u64 = 500_000_000;
+
+    struct HostState {
+        plugin: String,
+        secret_owner: String,
+        commands: Vec<PluginCommand>,
+        tools: Vec<PluginTool>,
+    }
+
+    struct LoadedPlugin {
+        store: Store<HostState>,
+        instance: Instance,
+        can_transform: bool,
+    }
+
+    /// A loaded plugin, usable without holding the host; calls into one plugin take turns
+    pub struct PluginHandle(Arc<Mutex<LoadedPlugin>>);
+
+    impl PluginHandle {
+        pub fn handle(&self, request: &str) -> Result<String> {
+            let mut plugin = self.0.lock().map_err(|_| anyhow::anyhow!("Plugin lock poisoned"))?;
+            call_with_string(&mut plugin, "handle", request)
+        }
+    }
+
+    #[derive(Default)]
+    pub struct PluginHost {
+        engine: Option<Engine>,
+        plugins: HashMap<String, Arc<Mutex<LoadedPlugin>>>,
+        load_order: Vec<String>,
+    }
+
+    impl PluginHost {
+        pub fn clear(&mut self) {
+            self.plugins.clear();
+            self.load_order.clear();
+        }
+
+        fn engine(&mut self) -> Result<Engine> {
+            if let Some(engine) = &self.engine {
+                return Ok(engine.clone());
+            }
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config)?;
+            self.engine = Some(engine.clone());
+            Ok(engine)
+        }
+
+        /// Load the module at `wasm`; its secrets are those stored for `secret_owner`
+        pub fn load(&mut self, wasm: &Path, name: &str, secret_owner: &str, permissions: &[String]) -> Result<(Vec<PluginCommand>, Vec<PluginTool>)> {
+            let engine = self.engine()?;
+            let module = Module::from_file(&engine, wasm)?;
+
+            let mut linker: Linker<HostState> = Linker::new(&engine);
+            linker.func_wrap("switchboard", "log", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<()> {
+                let text = read_guest_string(&mut caller, ptr, len)?;
+                log::info!("[plugin:{}] {}", caller.data().plugin, text);
+                Ok(())
+            })?;
+            if permissions.iter().any(|p| p == PERMISSION_REGISTER_COMMANDS) {
+                linker.func_wrap("switchboard", "register_command", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<()> {
+                    let json = read_guest_string(&mut caller, ptr, len)?;
+                    let mut command: PluginCommand = serde_json::from_str(&json)?;
+                    command.plugin = caller.data().plugin.clone();
+                    caller.data_mut().commands.push(command);
+                    Ok(())
+                })?;
+            }
+            if permissions.iter().any(|p| p == PERMISSION_REGISTER_TOOLS) {
+                linker.func_wrap("switchboard", "register_tool", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<()> {
+                    let json = read_guest_string(&mut caller, ptr, len)?;
+                    let mut tool: PluginTool = serde_json::from_str(&json)?;
+                    tool.plugin = caller.data().plugin.clone();
+                    caller.data_mut().tools.push(tool);
+                    Ok(())
+                })?;
+            }
+            if permissions.iter().any(|p| p == PERMISSION_READ_SECRETS) {
+                linker.func_wrap("switchboard", "get_secret", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<i64> {
+                    let name = read_guest_string(&mut caller, ptr, len)?;
+                    let (plugin, owner) = (caller.data().plugin.clone(), caller.data().secret_owner.clone());
+                    match crate::vault::read_stored(&owner, &name)? {
+                        Some(value) => write_guest_string(&mut caller, &value),
+                        None => {
+                            log::warn!("Plugin {} asked for secret {}, which it doesn't own", plugin, name);
+                            Ok(-1)
+                        }
+                    }
+                })?;
+            }
+
+            let state = HostState {
+                plugin: name.to_string(),
+                secret_owner: secret_owner.to_string(),
+                commands: Vec::new(),
+                tools: Vec::new(),
+            };
+            let mut store = Store::new(&engine, state);
+            store.set_fuel(FUEL_PER_CALL)?;
+            let instance = linker.instantiate(&mut store, &module)
+                .map_err(|e| anyhow::anyhow!("{} (does the plugin need a permission that was not granted?)", e))?;
+
+            if let Ok(init) = instance.get_typed_func::<(), ()>(&mut store, "init") {
+                store.set_fuel(FUEL_PER_CALL)?;
+                init.call(&mut store, ())?;
+            }
+
+            let registered = (store.data().commands.clone(), store.data().tools.clone());
+            self.plugins.insert(name.to_string(), Arc::new(Mutex::new(LoadedPlugin {
+                store,
+                instance,
+                can_transform: permissions.iter().any(|p| p == PERMISSION_TRANSFORM_MESSAGES),
+            })));
+            self.load_order.push(name.to_string());
+            Ok(registered)
+        }
+
+        pub fn transform_message(&mut self, mut message: String) -> String {
+            for name in self.load_order.clone() {
+                let Some(Ok(mut plugin)) = self.plugins.get(&name).map(|plugin| plugin.lock()) else { continue };
+                if !plugin.can_transform {
+                    continue;
+                }
+                match call_with_string(&mut plugin, "transform_message", &message) {
+                    Ok(transformed) => message = transformed,
+                    Err(e) => log::warn!("Plugin {} failed to transform message: {}", name, e),
+                }
+            }
+            message
+        }
+
+        pub fn handle_for(&self, plugin: &str) -> Result<PluginHandle> {
+            self.plugins.get(plugin)
+                .map(|loaded| PluginHandle(loaded.clone()))
+                .ok_or_else(|| anyhow::anyhow!("Plugin not loaded: {}", plugin))
+        }
+    }
+
+    fn guest_memory(caller: &mut Caller<'_, HostState>) -> Result<Memory> {
+        caller.get_export("memory")
+            .and_then(|e| e.into_memory())
+            .ok_or_else(|| anyhow::anyhow!("Plugin does not export memory"))
+    }
+
+    fn read_guest_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String> {
+        let memory = guest_memory(caller)?;
+        let data = memory.data(&caller);
+        let start = ptr as u32 as usize;
+        let end = start.checked_add(len as u32 as usize)
+            .filter(|end| *end <= data.len())
+            .ok_or_else(|| anyhow::anyhow!("Plugin passed an out-of-bounds buffer"))?;
+        Ok(String::from_utf8_lossy(&data[start..end]).to_string())
+    }
+
+    /// Copy `text` into a buffer from the guest's `alloc`, returning it packed as `(ptr << 32) | len`
+    fn write_guest_string(caller: &mut Caller<'_, HostState>, text: &str) -> Result<i64> {
+        let alloc = caller.get_export("alloc")
+            .and_then(|e| e.into_func())
+            .ok_or_else(|| anyhow::anyhow!("Plugin does not export alloc"))?
+            .typed::<i32, i32>(&*caller)?;
+        let bytes = text.as_bytes();
+        let ptr = alloc.call(&mut *caller, bytes.len() as i32)?;
+        let memory = guest_memory(caller)?;
+        memory.write(&mut *caller, ptr as u32 as usize, bytes)?;
+        Ok(((ptr as u32 as i64) << 32) | bytes.len() as i64)
+    }
+
+    fn call_with_string(plugin: &mut LoadedPlugin, export: &str, input: &str) -> Result<String> {
+        let store = &mut plugin.store;
+        store.set_fuel(FUEL_PER_CALL)?;
+        let memory = plugin.instance.get_memory(&mut *store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("Plugin does not export memory"))?;
+        let alloc = plugin.instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+        let func = plugin.instance.get_typed_func::<(i32, i32), i64>(&mut *store, export)?;
+
+        let bytes = input.as_bytes();
+        let ptr = alloc.call(&mut *store, bytes.len() as i32)?;
+        memory.write(&mut *store, ptr as u32 as usize, bytes)?;
+        let packed = func.call(&mut *store, (ptr, bytes.len() as i32))?;
+
+        let out_ptr = (packed as u64 >> 32) as usize;
+        let out_len = (packed as u64 & 0xffff_ffff) as usize;
+        let data = memory.data(&*store);
+        let output = data.get(out_ptr..out_ptr.saturating_add(out_len))
+            .ok_or_else(|| anyhow::anyhow!("Plugin returned an out-of-bounds buffer"))?;
+        Ok(String::from_utf8_lossy(output).to_string())
+    }
+}
+
+#[cfg(not(feature = "plugins"))]
+mod runtime {
+    use std::path::Path;
+    use anyhow::Result;
+    use super::{PluginCommand, PluginTool};
+
+    /// Stand-in used when mcp-core is built without the `plugins` feature
+    #[derive(Default)]
+    pub struct PluginHost;
+
+    pub enum PluginHandle {}
+
+    impl PluginHandle {
+        pub fn handle(&self, _request: &str) -> Result<String> {
+            match *self {}
+        }
+    }
+
+    impl PluginHost {
+        pub fn clear(&mut self) {}
+
+        pub fn load(&mut self, _wasm: &Path, _name: &str, _secret_owner: &str, _permissions: &[String]) -> Result<(Vec<PluginCommand>, Vec<PluginTool>)> {
+            Err(anyhow::anyhow!("Plugin support is not enabled in this build"))
+        }
+
+        pub fn transform_message(&mut self, message: String) -> String {
+            message
+        }
+
+        pub fn handle_for(&self, plugin: &str) -> Result<PluginHandle> {
+            Err(anyhow::anyhow!("Plugin not loaded: {}", plugin))
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Restrictions an administrator enforces on a managed machine, from a read-only JSON file beside
+//! the system defaults, e.g. `/etc/mcp-switchboard/policy.json`:
+//!
+//! ```json
+//! {
+//!   "disallowed_providers": ["together"],
+//!   "max_monthly_spend_usd": 50,
+//!   "force_redaction": true,
+//!   "locked_settings": ["preferred_model", "server_network"]
+//! }
+//! ```
+//!
+//! A locked setting keeps the administrator's default, or the built-in one, and saving any other
+//! value fails. Unlike the defaults file, the policy can't be pointed elsewhere by an environment
+//! variable and still applies in portable mode, since either would let a user step around it.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use chrono::{Datelike, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use ts_rs::TS;
+use crate::system_defaults::LAYERED_KEYS;
+use crate::tr;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct Policy {
+    /// Provider ids as recorded in message details, e.g. "together" or "demo"
+    pub disallowed_providers: Vec<String>,
+    /// Estimated spend per calendar month (UTC) after which no more requests are sent
+    pub max_monthly_spend_usd: Option<f64>,
+    /// Prompts are always redacted, whatever the user's setting
+    pub force_redaction: bool,
+    /// Config settings the user can't change; only those the defaults file could set are accepted
+    pub locked_settings: Vec<String>,
+}
+
+/// The policy in force, for the settings UI to disable what it locks
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct PolicyStatus {
+    /// None when there is no policy file
+    pub path: Option<String>,
+    pub policy: Policy,
+    /// Estimated spend so far this month, from message details
+    pub spent_this_month_usd: f64,
+}
+
+pub fn path() -> PathBuf {
+    crate::platform::system_dir().join("policy.json")
+}
+
+/// The policy in the file at `path`; a missing file means no restrictions
+pub fn read(path: &Path) -> Policy {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Policy::default(),
+        Err(e) => {
+            log::warn!("Failed to read policy {:?}: {}", path, e);
+            return Policy::default();
+        }
+    };
+    let mut policy = match serde_json::from_str::<Policy>(&contents) {
+        Ok(policy) => policy,
+        Err(e) => {
+            log::error!("Ignoring invalid policy {:?}: {}", path, e);
+            return Policy::default();
+        }
+    };
+    policy.locked_settings.retain(|key| {
+        let lockable = LAYERED_KEYS.contains(&key.as_str());
+        if !lockable {
+            log::warn!("Ignoring lock on {} in policy {:?}; it isn't a shared setting", key, path);
+        }
+        lockable
+    });
+    log::info!("Loaded policy from {:?}: {:?}", path, policy);
+    policy
+}
+
+/// The policy in force, read once at first use
+pub fn current() -> &'static Policy {
+    static POLICY: OnceLock<Policy> = OnceLock::new();
+    POLICY.get_or_init(|| read(&path()))
+}
+
+/// Err with a localized message if the policy disallows `provider`
+pub fn check_provider(provider: &str) -> Result<(), String> {
+    if current().disallowed_providers.iter().any(|disallowed| disallowed == provider) {
+        log::warn!("Refused use of provider {}, which the policy disallows", provider);
+        return Err(tr!("policy-provider-disallowed", provider = provider));
+    }
+    Ok(())
+}
+
+/// Err with a localized message once `spent` reaches the monthly cap
+pub fn check_spend(spent: f64) -> Result<(), String> {
+    match current().max_monthly_spend_usd {
+        Some(cap) if spent >= cap => {
+            log::warn!("Refused request: ${:.2} spent this month against a cap of ${:.2}", spent, cap);
+            Err(tr!("policy-spend-cap", cap = format!("{:.2}", cap)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Start of the current month in UTC, in the RFC 3339 form history timestamps use
+pub fn month_start() -> String {
+    let now = chrono::Utc::now();
+    chrono::Utc
+        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+        .to_rfc3339()
+}
+
+/// Put the locked settings in `config` back to their value in `defaults`, and apply forced ones
+pub fn enforce(config: &mut Map<String, Value>, defaults: &Map<String, Value>, policy: &Policy) {
+    for key in &policy.locked_settings {
+        match defaults.get(key) {
+            Some(value) => config.insert(key.clone(), value.clone()),
+            None => config.remove(key),
+        };
+    }
+    if policy.force_redaction {
+        config.insert("redaction_enabled".to_string(), Value::Bool(true));
+    }
+}
+
+/// The first setting `config` has at a value the policy doesn't allow, given what `enforce` made of it
+pub fn violation<'a>(config: &'a Map<String, Value>, enforced: &Map<String, Value>) -> Option<&'a str> {
+    config.iter()
+        .find(|(key, value)| enforced.get(*key) != Some(*value))
+        .map(|(key, _)| key.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn locked_settings_keep_their_default() {
+        let policy = Policy {
+            force_redaction: true,
+            locked_settings: vec!["preferred_model".to_string()],
+            ..Policy::default()
+        };
+        let defaults = map(json!({ "preferred_model": "admin-model", "memory_enabled": false }));
+        let mut config = map(json!({ "preferred_model": "mine", "memory_enabled": true, "redaction_enabled": false }));
+        let requested = config.clone();
+        enforce(&mut config, &defaults, &policy);
+        assert_eq!(config["preferred_model"], "admin-model");
+        assert_eq!(config["memory_enabled"], true);
+        assert_eq!(config["redaction_enabled"], true);
+        assert_eq!(violation(&requested, &config), Some("preferred_model"));
+        assert_eq!(violation(&config, &config), None);
+    }
+
+    #[test]
+    fn unknown_locks_and_invalid_files_are_ignored() {
+        let path = std::env::temp_dir().join(format!("mcp-policy-test-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{ "locked_settings": ["together_ai_api_key", "locale"], "max_monthly_spend_usd": 5 }"#).unwrap();
+        let policy = read(&path);
+        assert_eq!(policy.locked_settings, vec!["locale"]);
+        assert_eq!(policy.max_monthly_spend_usd, Some(5.0));
+        std::fs::write(&path, r#"{ "max_monthly_spend_usd": "lots" }"#).unwrap();
+        assert_eq!(read(&path), Policy::default());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,118 @@
+//! Portable mode: config, history and media all live in a folder beside the executable, e.g.
+//! on a USB stick, and the config key comes from a passphrase instead of the machine, since the
+//! folder moves between machines. It's on when a marker file sits next to the executable, or
+//! with `--portable` / `MCP_SWITCHBOARD_PORTABLE=1`.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+
+pub const PORTABLE_ENV: &str = "MCP_SWITCHBOARD_PORTABLE";
+
+/// Empty file next to the executable that turns portable mode on
+pub const MARKER_FILE: &str = "mcp-switchboard.portable";
+
+/// Folder next to the executable that holds everything in portable mode
+pub const DATA_FOLDER: &str = "mcp-switchboard-data";
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct PortableStatus {
+    pub enabled: bool,
+    /// The folder holding config and data
+    pub root: Option<String>,
+    /// The passphrase has been entered this session
+    pub unlocked: bool,
+    /// A config exists, so unlocking checks the passphrase against it instead of setting a new one
+    pub initialized: bool,
+}
+
+fn root_for(exe_dir: &Path, requested: bool) -> Option<PathBuf> {
+    (requested || exe_dir.join(MARKER_FILE).exists()).then(|| exe_dir.join(DATA_FOLDER))
+}
+
+/// The portable folder, or None when not in portable mode. Fixed at first use.
+pub fn root() -> Option<PathBuf> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        // Not from .env, which is itself looked up in the config directory this decides
+        let requested = crate::environment::value_before_dot_env(PORTABLE_ENV).is_some_and(|value| value == "1");
+        let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        let root = root_for(&exe_dir, requested);
+        if let Some(root) = &root {
+            log::info!("Portable mode, keeping everything in {:?}", root);
+        }
+        root
+    }).clone()
+}
+
+pub fn active() -> bool {
+    root().is_some()
+}
+
+struct Unlocked {
+    passphrase: String,
+    /// The last key derived, by salt, since derivation is deliberately slow
+    derived: Option<(Vec<u8>, [u8; 32])>,
+}
+
+static UNLOCKED: Mutex<Option<Unlocked>> = Mutex::new(None);
+
+pub fn set_passphrase(passphrase: Option<String>) {
+    if let Ok(mut unlocked) = UNLOCKED.lock() {
+        *unlocked = passphrase.map(|passphrase| Unlocked { passphrase, derived: None });
+    }
+}
+
+pub fn unlocked() -> bool {
+    UNLOCKED.lock().is_ok_and(|unlocked| unlocked.is_some())
+}
+
+fn derive(passphrase: &str, salt: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// The config key for `salt` from the session's passphrase
+pub fn key(salt: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let mut unlocked = UNLOCKED.lock().map_err(|_| anyhow::anyhow!("Passphrase lock poisoned"))?;
+    let unlocked = unlocked.as_mut().ok_or_else(|| anyhow::anyhow!(tr!("portable-locked")))?;
+    if let Some((derived_salt, key)) = &unlocked.derived {
+        if derived_salt == salt {
+            return Ok(*key);
+        }
+    }
+    let key = derive(&unlocked.passphrase, salt)?;
+    unlocked.derived = Some((salt.to_vec(), key));
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_file_or_request_turns_portable_mode_on() {
+        let dir = std::env::temp_dir().join(format!("mcp-portable-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(root_for(&dir, false), None);
+        assert_eq!(root_for(&dir, true), Some(dir.join(DATA_FOLDER)));
+        std::fs::write(dir.join(MARKER_FILE), "").unwrap();
+        assert_eq!(root_for(&dir, false), Some(dir.join(DATA_FOLDER)));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keys_depend_on_passphrase_and_salt() {
+        let salt = [1u8; 32];
+        let key = derive("correct horse", &salt).unwrap();
+        assert_eq!(key, derive("correct horse", &salt).unwrap());
+        assert_ne!(key, derive("wrong horse", &salt).unwrap());
+        assert_ne!(key, derive("correct horse", &[2u8; 32]).unwrap());
+    }
+}
@@ -0,0 +1,71 @@
+//! Whether the machine is running on battery, so background work can wait for mains power.
+//! Read from `/sys/class/power_supply` on Linux, `pmset` on macOS and the battery's WMI class
+//! on Windows. Desktops, and machines where none of those answer, count as on mains power.
+
+/// True on battery, false on mains power, None if it can't be told
+pub fn on_battery() -> Option<bool> {
+    read_on_battery()
+}
+
+#[cfg(target_os = "linux")]
+fn read_on_battery() -> Option<bool> {
+    let supplies = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    let mut has_battery = false;
+    for supply in supplies.flatten() {
+        let path = supply.path();
+        let read = |name: &str| std::fs::read_to_string(path.join(name)).map(|value| value.trim().to_string()).ok();
+        match read("type").as_deref() {
+            // Any adapter online means mains power, whatever the batteries say
+            Some("Mains") | Some("USB") if read("online").as_deref() == Some("1") => return Some(false),
+            Some("Battery") if read("scope").as_deref() != Some("Device") => {
+                has_battery = true;
+                if read("status").as_deref() == Some("Discharging") {
+                    return Some(true);
+                }
+            }
+            _ => {}
+        }
+    }
+    has_battery.then_some(false)
+}
+
+#[cfg(target_os = "macos")]
+fn read_on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    pmset_on_battery(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `pmset -g batt` starts with e.g. "Now drawing from 'Battery Power'"
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn pmset_on_battery(output: &str) -> Option<bool> {
+    let source = output.lines().next()?.split('\'').nth(1)?;
+    Some(source == "Battery Power")
+}
+
+#[cfg(target_os = "windows")]
+fn read_on_battery() -> Option<bool> {
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", "(Get-CimInstance Win32_Battery).BatteryStatus"])
+        .output()
+        .ok()?;
+    // 1 is discharging; the rest are charging, charged or on AC. Desktops print nothing.
+    let status: u16 = String::from_utf8_lossy(&output.stdout).lines().next()?.trim().parse().ok()?;
+    Some(status == 1)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read_on_battery() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pmset_reports_the_power_source_first() {
+        assert_eq!(pmset_on_battery("Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t82%; discharging;"), Some(true));
+        assert_eq!(pmset_on_battery("Now drawing from 'AC Power'\n -InternalBattery-0 (id=1234)\t100%; charged;"), Some(false));
+        assert_eq!(pmset_on_battery(""), None);
+    }
+}
@@ -0,0 +1,182 @@
+//! Shortcuts in an outgoing message, expanded before it's sent: `;name` is replaced by the text
+//! saved under that name, `@~/notes.md` or `@"/path with spaces.txt"` by the file's content,
+//! and `#` with a conversation id (or its first eight characters or more) by a summary of that
+//! conversation. A shortcut has to start a word, so email addresses and headings pass through.
+//! Only messages typed in the app are expanded; API server clients can't read local files.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use regex::Regex;
+use crate::compression;
+use crate::history::HistoryMessage;
+use crate::tr;
+
+/// Files larger than this aren't inlined
+pub const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+/// Share of an earlier conversation's text its summary keeps
+const SUMMARY_RATIO: f32 = 0.3;
+
+/// Longest summary inlined, in characters
+const MAX_SUMMARY_CHARS: usize = 4000;
+
+/// Shortest conversation id prefix a `#` reference may use
+const MIN_CONVERSATION_PREFIX: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub enum Reference<'a> {
+    Expansion(&'a str),
+    File(&'a str),
+    Conversation(&'a str),
+}
+
+fn pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r#"(?:^|[\s(\[])(;[A-Za-z0-9_-]+|@"[^"\n]+"|@\S+|#[0-9A-Za-z-]+)"#).expect("valid shortcut pattern")
+    })
+}
+
+/// A trigger with any leading `;` and surrounding space removed, or a localized error if it has
+/// characters a shortcut can't
+pub fn validate_trigger(trigger: &str) -> Result<String, String> {
+    let trigger = trigger.trim().trim_start_matches(';');
+    if trigger.is_empty() || !trigger.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(tr!("expansion-trigger-invalid", trigger = trigger.to_string()));
+    }
+    Ok(trigger.to_string())
+}
+
+/// The shortcuts in `message` with where each is, in order
+pub fn references(message: &str) -> Vec<(std::ops::Range<usize>, Reference<'_>)> {
+    pattern().captures_iter(message)
+        .filter_map(|captures| {
+            let matched = captures.get(1)?;
+            let text = matched.as_str();
+            let reference = match text.split_at(1) {
+                (";", name) => Reference::Expansion(name),
+                ("@", quoted) if quoted.starts_with('"') => Reference::File(quoted.trim_matches('"')),
+                ("@", path) => {
+                    // Punctuation closing a sentence isn't part of the path
+                    let path = path.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']']);
+                    if !(path.starts_with("~/") || path.starts_with("~\\") || Path::new(path).is_absolute()) {
+                        return None;
+                    }
+                    return Some((matched.start()..matched.start() + 1 + path.len(), Reference::File(path)));
+                }
+                ("#", id) if id.len() >= MIN_CONVERSATION_PREFIX => Reference::Conversation(id),
+                _ => return None,
+            };
+            Some((matched.range(), reference))
+        })
+        .collect()
+}
+
+/// `message` with its shortcuts expanded. Expansions without saved text and conversation
+/// references that match nothing are left as typed; a file that can't be inlined is an error.
+pub fn expand(
+    message: &str,
+    expansions: &BTreeMap<String, String>,
+    mut conversation: impl FnMut(&str) -> Result<Option<String>, String>,
+) -> Result<String, String> {
+    let mut expanded = String::with_capacity(message.len());
+    let mut copied = 0;
+    for (range, reference) in references(message) {
+        let replacement = match reference {
+            Reference::Expansion(name) => expansions.get(name).cloned(),
+            Reference::File(path) => Some(inline_file(path)?),
+            Reference::Conversation(id) => conversation(id)?,
+        };
+        if let Some(replacement) = replacement {
+            expanded.push_str(&message[copied..range.start]);
+            expanded.push_str(&replacement);
+            copied = range.end;
+        }
+    }
+    expanded.push_str(&message[copied..]);
+    Ok(expanded)
+}
+
+fn resolve_path(path: &str) -> PathBuf {
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The file at `path` as a fenced block headed by its path
+fn inline_file(path: &str) -> Result<String, String> {
+    let resolved = resolve_path(path);
+    let metadata = std::fs::metadata(&resolved)
+        .map_err(|e| tr!("preprocess-file-unreadable", path = path.to_string(), error = e.to_string()))?;
+    if !metadata.is_file() {
+        return Err(tr!("preprocess-file-unreadable", path = path.to_string(), error = "not a file".to_string()));
+    }
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(tr!("preprocess-file-too-large", path = path.to_string(), limit_kb = MAX_FILE_BYTES / 1024));
+    }
+    let bytes = std::fs::read(&resolved)
+        .map_err(|e| tr!("preprocess-file-unreadable", path = path.to_string(), error = e.to_string()))?;
+    let content = String::from_utf8(bytes).map_err(|_| tr!("preprocess-file-not-text", path = path.to_string()))?;
+    Ok(fenced(&format!("File {}:", path), &content))
+}
+
+/// `content` under `heading` in a code fence longer than any backtick run inside it
+fn fenced(heading: &str, content: &str) -> String {
+    let longest_run = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("\n\n{}\n{}\n{}\n{}\n\n", heading, fence, content.trim_end(), fence)
+}
+
+/// A summary of conversation `conversation_id` for inlining: its turns shortened the way
+/// history compression shortens them, cut at MAX_SUMMARY_CHARS
+pub fn conversation_summary(conversation_id: &str, messages: Vec<HistoryMessage>) -> String {
+    let turns: Vec<HistoryMessage> = messages.into_iter()
+        .filter(|message| matches!(message.role.as_str(), "user" | "assistant") && !message.content.trim().is_empty())
+        .collect();
+    let (turns, _, _) = compression::compress_history(turns, SUMMARY_RATIO);
+    let mut transcript = turns.iter()
+        .map(|message| format!("{}: {}", if message.role == "user" { "User" } else { "Assistant" }, message.content.trim()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Some((cut, _)) = transcript.char_indices().nth(MAX_SUMMARY_CHARS) {
+        transcript.truncate(cut);
+        transcript.push('…');
+    }
+    fenced(&format!("Summary of conversation {}:", conversation_id), &transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcuts_start_a_word() {
+        let message = "Mail me@example.com; see @~/notes.md. Also @alice, #1234, (#0f3c2a9e-77) and ;sig";
+        assert_eq!(
+            references(message).into_iter().map(|(_, reference)| reference).collect::<Vec<_>>(),
+            [Reference::File("~/notes.md"), Reference::Conversation("0f3c2a9e-77"), Reference::Expansion("sig")]
+        );
+        assert_eq!(references(r#"Read @"/tmp/a b.txt" please"#)[0].1, Reference::File("/tmp/a b.txt"));
+    }
+
+    #[test]
+    fn known_shortcuts_are_expanded_in_place() {
+        let directory = std::env::temp_dir().join(format!("mcp-preprocess-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("snippet.rs");
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        let expansions = BTreeMap::from([("sig".to_string(), "Best, Sam".to_string())]);
+        let message = format!("Review @{} ;sig ;unknown #0f3c2a9e", file.display());
+        let expanded = expand(&message, &expansions, |id| Ok((id == "0f3c2a9e").then(|| "[summary]".to_string()))).unwrap();
+        assert_eq!(
+            expanded,
+            format!("Review \n\nFile {}:\n```\nfn main() {{}}\n```\n\n Best, Sam ;unknown [summary]", file.display())
+        );
+        assert!(expand("@/no/such/file.txt", &expansions, |_| Ok(None)).is_err());
+        assert_eq!(validate_trigger(" ;sig "), Ok("sig".to_string()));
+        assert!(validate_trigger("two words").is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
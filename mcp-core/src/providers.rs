@@ -0,0 +1,421 @@
+//! The APIs models are served from. Together.ai is the default; other providers are used for
+//! models whose id is qualified with the provider's name (`openai/gpt-4o`,
+//! `anthropic/claude-sonnet-4-5`, `openrouter/openai/gpt-4o`, `groq/llama-3.3-70b-versatile`),
+//! which is how the model picker lists them. Together ids that already look qualified
+//! (Together hosts `openai/gpt-oss-120b`) are listed as `together/<id>` so they stay
+//! Together's. A local Ollama server is a provider too, reached at the base URL in the config
+//! rather than with a key, and Amazon Bedrock is reached with an AWS access key.
+
+use std::collections::BTreeMap;
+use async_openai::types::{ChatCompletionStreamOptions, CreateChatCompletionRequest};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::ModelInfo;
+use crate::cost::ModelPricing;
+use crate::environment::{self, ValueSource};
+use crate::tr;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    Together,
+    OpenAi,
+    Anthropic,
+    OpenRouter,
+    Azure,
+    Groq,
+    Ollama,
+    Endpoint,
+    Bedrock,
+}
+
+/// Every provider, the default first
+pub const ALL: [Provider; 9] = [
+    Provider::Together,
+    Provider::OpenAi,
+    Provider::Anthropic,
+    Provider::OpenRouter,
+    Provider::Azure,
+    Provider::Groq,
+    Provider::Ollama,
+    Provider::Endpoint,
+    Provider::Bedrock,
+];
+
+/// Prefixes of OpenAI model ids that chat completions accept
+const OPENAI_CHAT_PREFIXES: [&str; 5] = ["gpt-", "chatgpt-", "o1", "o3", "o4"];
+
+/// OpenAI models with a chat-like id that need another API (realtime, audio, images, ...)
+const OPENAI_NON_CHAT: [&str; 7] = ["realtime", "audio", "transcribe", "tts", "image", "instruct", "search"];
+
+/// Headers the app sets itself, which configured extra headers may not replace
+const RESERVED_HEADERS: [&str; 8] = ["authorization", "api-key", "x-api-key", "anthropic-version", "content-type", "content-length", "host", "traceparent"];
+
+impl Provider {
+    /// The name used in qualified model ids, policies and message details
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Together => "together",
+            Provider::OpenAi => "openai",
+            Provider::Anthropic => "anthropic",
+            Provider::OpenRouter => "openrouter",
+            Provider::Azure => "azure",
+            Provider::Groq => "groq",
+            Provider::Ollama => "ollama",
+            Provider::Endpoint => "endpoint",
+            Provider::Bedrock => "bedrock",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Provider> {
+        ALL.into_iter().find(|provider| provider.name() == name)
+    }
+
+    /// The provider's API. Anthropic's also offers OpenAI-compatible chat completions, used
+    /// for everything but streamed chat, which goes to its Messages API. Ollama's is where it
+    /// listens by default; the config can point elsewhere. Azure's is the domain resources are
+    /// under, since each profile names its own, and a custom endpoint's is vLLM's default, since
+    /// each registered endpoint names its own. Bedrock's is in the default region; the account
+    /// names the one used.
+    pub fn api_base(self) -> &'static str {
+        match self {
+            Provider::Together => "https://api.together.xyz/v1",
+            Provider::OpenAi => "https://api.openai.com/v1",
+            Provider::Anthropic => "https://api.anthropic.com/v1",
+            Provider::OpenRouter => "https://openrouter.ai/api/v1",
+            Provider::Azure => "https://openai.azure.com",
+            Provider::Groq => "https://api.groq.com/openai/v1",
+            Provider::Ollama => "http://localhost:11434/v1",
+            Provider::Endpoint => "http://localhost:8000/v1",
+            Provider::Bedrock => "https://bedrock-runtime.us-east-1.amazonaws.com",
+        }
+    }
+
+    /// Whether sampling can be seeded; Anthropic and Bedrock's Converse API have no seed parameter
+    pub fn takes_seed(self) -> bool {
+        !matches!(self, Provider::Anthropic | Provider::Bedrock)
+    }
+
+    /// Whether chat completions report per-token log probabilities when asked
+    pub fn reports_logprobs(self) -> bool {
+        self == Provider::OpenAi
+    }
+
+    /// Whether the provider has an embeddings API the app can call; Anthropic, OpenRouter and
+    /// Groq don't, and Bedrock's isn't OpenAI-compatible
+    pub fn serves_embeddings(self) -> bool {
+        !matches!(self, Provider::Anthropic | Provider::OpenRouter | Provider::Groq | Provider::Bedrock)
+    }
+
+    /// The environment variable (or `.env` entry) a key for the provider can come from; None
+    /// for Ollama and custom endpoints, which keep theirs in the config if they take one
+    pub fn key_var(self) -> Option<&'static str> {
+        match self {
+            Provider::Together => Some(environment::API_KEY_VAR),
+            Provider::OpenAi => Some(environment::OPENAI_API_KEY_VAR),
+            Provider::Anthropic => Some(environment::ANTHROPIC_API_KEY_VAR),
+            Provider::OpenRouter => Some(environment::OPENROUTER_API_KEY_VAR),
+            Provider::Azure => Some(environment::AZURE_OPENAI_API_KEY_VAR),
+            Provider::Groq => Some(environment::GROQ_API_KEY_VAR),
+            // The secret half of the access key; the key id and region are set up separately
+            Provider::Bedrock => Some(environment::AWS_SECRET_ACCESS_KEY_VAR),
+            Provider::Ollama | Provider::Endpoint => None,
+        }
+    }
+}
+
+/// Whether a provider can be used, and where its key comes from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ProviderStatus {
+    pub provider: Provider,
+    pub name: String,
+    pub api_base: String,
+    pub key_source: Option<ValueSource>,
+    /// Has a key, or for Ollama a base URL
+    pub configured: bool,
+}
+
+/// Extra headers for a provider trimmed, or a localized error naming one that can't be sent or
+/// would replace a header the app sets
+pub fn validate_headers(headers: BTreeMap<String, String>) -> Result<BTreeMap<String, String>, String> {
+    let mut valid = BTreeMap::new();
+    for (name, value) in headers {
+        let (name, value) = (name.trim().to_string(), value.trim().to_string());
+        if name.is_empty() {
+            continue;
+        }
+        if RESERVED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            return Err(tr!("provider-header-reserved", name = name));
+        }
+        if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(&value).is_err() {
+            return Err(tr!("provider-header-invalid", name = name));
+        }
+        valid.insert(name, value);
+    }
+    Ok(valid)
+}
+
+/// `headers`, as validated by `validate_headers`, ready to send
+pub fn header_map(headers: &BTreeMap<String, String>) -> HeaderMap {
+    headers.iter()
+        .filter_map(|(name, value)| Some((HeaderName::from_bytes(name.as_bytes()).ok()?, HeaderValue::from_str(value).ok()?)))
+        .collect()
+}
+
+/// The provider serving `model` and the id its API knows the model by
+pub fn resolve(model: &str) -> (Provider, &str) {
+    for provider in ALL {
+        if let Some(upstream) = model.strip_prefix(provider.name()).and_then(|rest| rest.strip_prefix('/')) {
+            return (provider, upstream);
+        }
+    }
+    (Provider::Together, model)
+}
+
+/// The id the model picker lists `provider`'s `model_id` under, which `resolve` turns back
+pub fn qualified_id(provider: Provider, model_id: &str) -> String {
+    if provider == Provider::Together && resolve(model_id) == (Provider::Together, model_id) {
+        model_id.to_string()
+    } else {
+        format!("{}/{}", provider.name(), model_id)
+    }
+}
+
+/// `request` as the provider serving its model expects it: the model named by its upstream id
+/// (for a custom endpoint, without the endpoint's name), and for OpenAI and Azure the token
+/// limit in `max_completion_tokens`, since their reasoning models refuse `max_tokens`
+pub fn upstream_request(mut request: CreateChatCompletionRequest) -> CreateChatCompletionRequest {
+    let (provider, upstream) = resolve(&request.model);
+    let upstream = match provider {
+        Provider::Endpoint => upstream.split_once('/').map_or(upstream, |(_, model)| model),
+        _ => upstream,
+    };
+    request.model = upstream.to_string();
+    if matches!(provider, Provider::OpenAi | Provider::Azure) {
+        #[allow(deprecated)]
+        if let Some(max_tokens) = request.max_tokens.take() {
+            request.max_completion_tokens = request.max_completion_tokens.or(Some(max_tokens));
+        }
+    }
+    // Usage comes in a last chunk of its own, for pricing the answer; compatible endpoints may
+    // not know the option, and Anthropic, Bedrock and Ollama report usage unasked
+    if request.stream == Some(true) && !matches!(provider, Provider::Endpoint | Provider::Anthropic | Provider::Bedrock | Provider::Ollama) {
+        request.stream_options = Some(ChatCompletionStreamOptions { include_usage: true });
+    }
+    request
+}
+
+/// The models in a response from OpenRouter's `GET /models`, remembering their context lengths
+/// and prices. OpenRouter prices per token, as decimal strings; routers that price by the
+/// model they pick report -1 and are listed without a price.
+pub fn openrouter_models(response: &serde_json::Value) -> Result<Vec<ModelInfo>, String> {
+    let data = response["data"].as_array().ok_or_else(|| {
+        log::error!("OpenRouter models response has no data array");
+        "Invalid models response format".to_string()
+    })?;
+    let per_million = |price: &serde_json::Value| price.as_str()
+        .and_then(|price| price.parse::<f64>().ok())
+        .filter(|price| *price >= 0.0)
+        .map(|price| price * 1_000_000.0);
+    Ok(data.iter()
+        .filter_map(|model| {
+            let upstream = model["id"].as_str()?;
+            let id = qualified_id(Provider::OpenRouter, upstream);
+            let name = model["name"].as_str().unwrap_or(upstream);
+            // Names read "OpenAI: GPT-4o"
+            let (organization, display_name) = name.split_once(": ")
+                .unwrap_or((upstream.split('/').next().unwrap_or("OpenRouter"), name));
+            let context_length = model["context_length"].as_u64().filter(|length| *length > 0).map(|length| length as u32);
+            if let Some(length) = context_length {
+                crate::context::remember_context_length(&id, length as usize);
+            }
+            let pricing = match (per_million(&model["pricing"]["prompt"]), per_million(&model["pricing"]["completion"])) {
+                (Some(input), Some(output)) => Some(ModelPricing { input, output }),
+                _ => None,
+            };
+            if let Some(pricing) = pricing {
+                crate::cost::remember_pricing(&id, pricing);
+            }
+            crate::capabilities::remember_openrouter(&id, model);
+            Some(ModelInfo {
+                id,
+                display_name: display_name.to_string(),
+                organization: organization.to_string(),
+                context_length,
+                pricing,
+            })
+        })
+        .collect())
+}
+
+/// The text of a chat message, with the parts of a multi-part message on separate lines
+pub fn message_text(message: &serde_json::Value) -> String {
+    match &message["content"] {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(parts) => parts.iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// The chat models in a response from Groq's `GET /models`, remembering their context windows.
+/// Groq lists its speech-to-text models alongside them, and models it has switched off.
+pub fn groq_models(response: &serde_json::Value) -> Result<Vec<ModelInfo>, String> {
+    let data = response["data"].as_array().ok_or_else(|| {
+        log::error!("Groq models response has no data array");
+        "Invalid models response format".to_string()
+    })?;
+    let mut models: Vec<ModelInfo> = data.iter()
+        .filter(|model| model["active"].as_bool() != Some(false))
+        .filter_map(|model| {
+            let upstream = model["id"].as_str()?;
+            if upstream.contains("whisper") || upstream.contains("tts") || upstream.contains("guard") {
+                return None;
+            }
+            let id = qualified_id(Provider::Groq, upstream);
+            let context_length = model["context_window"].as_u64().filter(|length| *length > 0).map(|length| length as u32);
+            if let Some(length) = context_length {
+                crate::context::remember_context_length(&id, length as usize);
+            }
+            Some(ModelInfo {
+                id,
+                display_name: upstream.to_string(),
+                organization: model["owned_by"].as_str().unwrap_or("Groq").to_string(),
+                context_length,
+                pricing: None,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+/// The chat models in a response from OpenAI's `GET /models`
+pub fn openai_models(response: &serde_json::Value) -> Result<Vec<ModelInfo>, String> {
+    let data = response["data"].as_array().ok_or_else(|| {
+        log::error!("OpenAI models response has no data array");
+        "Invalid models response format".to_string()
+    })?;
+    let mut models: Vec<ModelInfo> = data.iter()
+        .filter_map(|model| model["id"].as_str())
+        .filter(|id| OPENAI_CHAT_PREFIXES.iter().any(|prefix| id.starts_with(prefix)))
+        .filter(|id| !OPENAI_NON_CHAT.iter().any(|kind| id.contains(kind)))
+        .map(|id| ModelInfo {
+            id: qualified_id(Provider::OpenAi, id),
+            display_name: id.to_string(),
+            organization: "OpenAI".to_string(),
+            context_length: None,
+            pricing: None,
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qualified_ids_resolve_back_to_their_provider() {
+        assert_eq!(resolve("meta-llama/Llama-3-8b-chat-hf"), (Provider::Together, "meta-llama/Llama-3-8b-chat-hf"));
+        assert_eq!(resolve("openai/gpt-4o"), (Provider::OpenAi, "gpt-4o"));
+        assert_eq!(resolve("openaix/model"), (Provider::Together, "openaix/model"));
+
+        for (provider, id) in [
+            (Provider::Together, "meta-llama/Llama-3-8b-chat-hf"),
+            (Provider::Together, "openai/gpt-oss-120b"),
+            (Provider::OpenAi, "gpt-4o"),
+        ] {
+            assert_eq!(resolve(&qualified_id(provider, id)), (provider, id));
+        }
+        assert_eq!(qualified_id(Provider::Together, "openai/gpt-oss-120b"), "together/openai/gpt-oss-120b");
+        assert_eq!(resolve("ollama/llama3.2:latest"), (Provider::Ollama, "llama3.2:latest"));
+    }
+
+    #[test]
+    fn openai_requests_use_upstream_ids_and_completion_token_limits() {
+        #[allow(deprecated)]
+        let request = async_openai::types::CreateChatCompletionRequestArgs::default()
+            .model("openai/o3-mini")
+            .messages(Vec::new())
+            .max_tokens(100u32)
+            .stream(true)
+            .build()
+            .unwrap();
+        let request = upstream_request(request);
+        assert_eq!(request.model, "o3-mini");
+        assert_eq!(request.stream_options, Some(ChatCompletionStreamOptions { include_usage: true }));
+        #[allow(deprecated)]
+        let max_tokens = request.max_tokens;
+        assert_eq!((max_tokens, request.max_completion_tokens), (None, Some(100)));
+    }
+
+    #[test]
+    fn extra_headers_cannot_replace_the_apps_own() {
+        let entered = BTreeMap::from([
+            (" HTTP-Referer ".to_string(), " https://example.com ".to_string()),
+            ("Helicone-Property-Team".to_string(), "research".to_string()),
+            (" ".to_string(), "ignored".to_string()),
+        ]);
+        let headers = validate_headers(entered).unwrap();
+        assert_eq!(headers.get("HTTP-Referer").map(String::as_str), Some("https://example.com"));
+        assert_eq!(header_map(&headers)["helicone-property-team"], "research");
+        assert_eq!(header_map(&headers).len(), 2);
+
+        assert!(validate_headers(BTreeMap::from([("Authorization".to_string(), "Bearer x".to_string())])).is_err());
+        assert!(validate_headers(BTreeMap::from([("Bad Name".to_string(), "x".to_string())])).is_err());
+        assert!(validate_headers(BTreeMap::from([("X-Title".to_string(), "two\nlines".to_string())])).is_err());
+    }
+
+    #[test]
+    fn only_active_chat_models_are_listed_from_groq() {
+        let response = serde_json::json!({ "object": "list", "data": [
+            { "id": "llama-3.3-70b-versatile", "object": "model", "owned_by": "Meta", "active": true, "context_window": 131072 },
+            { "id": "whisper-large-v3", "object": "model", "owned_by": "OpenAI", "active": true, "context_window": 448 },
+            { "id": "mixtral-8x7b-32768", "object": "model", "owned_by": "Mistral AI", "active": false, "context_window": 32768 },
+        ]});
+        let models = groq_models(&response).unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "groq/llama-3.3-70b-versatile");
+        assert_eq!((models[0].organization.as_str(), models[0].context_length), ("Meta", Some(131072)));
+        assert_eq!(resolve(&models[0].id), (Provider::Groq, "llama-3.3-70b-versatile"));
+    }
+
+    #[test]
+    fn only_chat_models_are_listed_from_openai() {
+        let response = serde_json::json!({"object": "list", "data": [
+            {"id": "whisper-1", "object": "model"},
+            {"id": "gpt-4o-mini", "object": "model"},
+            {"id": "gpt-4o-realtime-preview", "object": "model"},
+            {"id": "o3-mini", "object": "model"},
+            {"id": "text-embedding-3-small", "object": "model"},
+        ]});
+        let ids: Vec<String> = openai_models(&response).unwrap().into_iter().map(|model| model.id).collect();
+        assert_eq!(ids, vec!["openai/gpt-4o-mini", "openai/o3-mini"]);
+        assert!(openai_models(&serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn openrouter_models_carry_prices_per_million_tokens() {
+        let response = serde_json::json!({"data": [
+            {"id": "openai/gpt-4o", "name": "OpenAI: GPT-4o", "context_length": 128000,
+             "pricing": {"prompt": "0.0000025", "completion": "0.00001"}},
+            {"id": "openrouter/auto", "name": "Auto Router", "context_length": 2000000,
+             "pricing": {"prompt": "-1", "completion": "-1"}},
+        ]});
+        let models = openrouter_models(&response).unwrap();
+        assert_eq!(models[0].id, "openrouter/openai/gpt-4o");
+        assert_eq!(resolve(&models[0].id), (Provider::OpenRouter, "openai/gpt-4o"));
+        assert_eq!((models[0].organization.as_str(), models[0].display_name.as_str()), ("OpenAI", "GPT-4o"));
+        let pricing = models[0].pricing.unwrap();
+        assert!((pricing.input - 2.5).abs() < 1e-9 && (pricing.output - 10.0).abs() < 1e-9);
+        assert_eq!(models[0].context_length, Some(128000));
+        assert_eq!(models[1].pricing, None);
+    }
+}
@@ -0,0 +1,259 @@
+//! Reading answers aloud while they stream. Text is cut into sentences as it arrives, so speech
+//! starts with the first sentence instead of waiting for the whole answer. The webview does the
+//! speaking, with the system voice: it's handed one sentence at a time and reports back once each
+//! has been spoken, which is what lets the queue pause and skip.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::events::{self, AppEvent};
+
+/// Longest run held back waiting for a sentence to end, e.g. in a code block; past this it is
+/// cut at the last space
+const MAX_SENTENCE_CHARS: usize = 300;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct SpokenSentence {
+    pub message_id: String,
+    /// Position in the answer, from 0
+    pub index: u32,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, TS)]
+#[ts(export)]
+pub struct ReadAloudState {
+    /// The answer being read
+    pub message_id: Option<String>,
+    /// What the webview should be speaking now; None when there is nothing to say yet
+    pub speaking: Option<SpokenSentence>,
+    /// Sentences waiting after the current one
+    pub queued: u32,
+    pub paused: bool,
+}
+
+/// Buffers streamed text and releases it a sentence at a time
+#[derive(Default)]
+pub struct SentenceSplitter {
+    pending: String,
+}
+
+impl SentenceSplitter {
+    /// Add `chunk` and return the sentences it completed
+    pub fn push(&mut self, chunk: &str) -> Vec<String> {
+        self.pending.push_str(chunk);
+        let mut sentences = Vec::new();
+        // The last sentence may still grow, so it stays back until another begins
+        if let Some((split, _)) = self.pending.split_sentence_bound_indices().last() {
+            if split > 0 {
+                let rest = self.pending.split_off(split);
+                let complete = std::mem::replace(&mut self.pending, rest);
+                sentences.extend(complete.split_sentence_bounds().filter_map(speakable));
+            }
+        }
+        while let Some((limit, _)) = self.pending.char_indices().nth(MAX_SENTENCE_CHARS) {
+            let cut = self.pending[..limit].char_indices()
+                .rev()
+                .find(|(_, c)| c.is_whitespace())
+                .map(|(at, c)| at + c.len_utf8())
+                .unwrap_or(limit);
+            let rest = self.pending.split_off(cut);
+            sentences.extend(speakable(&std::mem::replace(&mut self.pending, rest)));
+        }
+        sentences
+    }
+
+    /// Whatever is held back, for when no more text is coming
+    pub fn finish(&mut self) -> Option<String> {
+        speakable(&std::mem::take(&mut self.pending))
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// `text` as it should be spoken, or None if there's nothing to say
+fn speakable(text: &str) -> Option<String> {
+    let text = text.trim();
+    text.chars().any(char::is_alphanumeric).then(|| text.to_string())
+}
+
+#[derive(Default)]
+struct Queue {
+    message_id: Option<String>,
+    next_index: u32,
+    waiting: VecDeque<SpokenSentence>,
+    speaking: Option<SpokenSentence>,
+    paused: bool,
+}
+
+impl Queue {
+    fn state(&self) -> ReadAloudState {
+        ReadAloudState {
+            message_id: self.message_id.clone(),
+            speaking: self.speaking.clone(),
+            queued: self.waiting.len() as u32,
+            paused: self.paused,
+        }
+    }
+
+    /// Start reading `message_id`, dropping whatever was being read. Pausing carries over.
+    fn begin(&mut self, message_id: &str) {
+        *self = Queue { message_id: Some(message_id.to_string()), paused: self.paused, ..Queue::default() };
+    }
+
+    /// Returns whether the state changed
+    fn push(&mut self, message_id: &str, text: String) -> bool {
+        if self.message_id.as_deref() != Some(message_id) {
+            return false;
+        }
+        self.waiting.push_back(SpokenSentence { message_id: message_id.to_string(), index: self.next_index, text });
+        self.next_index += 1;
+        self.advance();
+        true
+    }
+
+    fn advance(&mut self) {
+        if self.speaking.is_none() && !self.paused {
+            self.speaking = self.waiting.pop_front();
+        }
+    }
+
+    /// The webview finished speaking a sentence; anything but the current one is stale
+    fn spoken(&mut self, message_id: &str, index: u32) -> bool {
+        let current = self.speaking.as_ref().is_some_and(|s| s.message_id == message_id && s.index == index);
+        if current {
+            self.speaking = None;
+            self.advance();
+        }
+        current
+    }
+
+    fn skip(&mut self) {
+        self.speaking = None;
+        self.advance();
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Carries on with the current sentence if there is one, otherwise the next
+    fn resume(&mut self) {
+        self.paused = false;
+        self.advance();
+    }
+}
+
+static QUEUE: Mutex<Option<Queue>> = Mutex::new(None);
+
+/// Apply `f` to the queue and publish the resulting state if it changed
+fn update(f: impl FnOnce(&mut Queue) -> bool) -> ReadAloudState {
+    let mut queue = QUEUE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let queue = queue.get_or_insert_with(Queue::default);
+    let changed = f(queue);
+    let state = queue.state();
+    if changed {
+        events::publish(AppEvent::ReadAloudChanged { state: state.clone() });
+    }
+    state
+}
+
+pub fn state() -> ReadAloudState {
+    update(|_| false)
+}
+
+pub fn begin(message_id: &str) -> ReadAloudState {
+    log::debug!("Reading {} aloud", message_id);
+    update(|queue| {
+        queue.begin(message_id);
+        true
+    })
+}
+
+/// Queue sentences of `message_id`; ignored once another answer is being read
+pub fn push(message_id: &str, sentences: Vec<String>) {
+    if sentences.is_empty() {
+        return;
+    }
+    update(|queue| {
+        let mut changed = false;
+        for text in sentences {
+            changed |= queue.push(message_id, text);
+        }
+        changed
+    });
+}
+
+pub fn spoken(message_id: &str, index: u32) -> ReadAloudState {
+    update(|queue| queue.spoken(message_id, index))
+}
+
+pub fn skip() -> ReadAloudState {
+    update(|queue| {
+        queue.skip();
+        true
+    })
+}
+
+pub fn pause() -> ReadAloudState {
+    update(|queue| {
+        queue.pause();
+        true
+    })
+}
+
+pub fn resume() -> ReadAloudState {
+    update(|queue| {
+        queue.resume();
+        true
+    })
+}
+
+pub fn stop() -> ReadAloudState {
+    update(|queue| {
+        *queue = Queue::default();
+        true
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentences_are_released_once_the_next_begins() {
+        let mut splitter = SentenceSplitter::default();
+        assert_eq!(splitter.push("Hello th"), Vec::<String>::new());
+        assert_eq!(splitter.push("ere. How are"), vec!["Hello there."]);
+        assert_eq!(splitter.push(" you? Fine.\n\n```"), vec!["How are you?", "Fine."]);
+        assert_eq!(splitter.finish(), None);
+        let sentences = splitter.push(&"word ".repeat(130));
+        assert_eq!(sentences.len(), 2);
+        assert!(sentences.iter().all(|sentence| sentence.chars().count() <= MAX_SENTENCE_CHARS));
+        assert!(splitter.finish().is_some());
+    }
+
+    #[test]
+    fn queue_pauses_skips_and_ignores_stale_reports() {
+        let mut queue = Queue::default();
+        queue.begin("m");
+        queue.push("m", "One.".to_string());
+        queue.push("m", "Two.".to_string());
+        queue.push("m", "Three.".to_string());
+        assert_eq!(queue.state().speaking.map(|s| s.index), Some(0));
+        assert!(!queue.spoken("m", 1));
+        assert!(queue.spoken("m", 0));
+        queue.pause();
+        assert_eq!(queue.state().speaking.map(|s| s.text), Some("Two.".to_string()));
+        queue.skip();
+        assert_eq!(queue.state().speaking, None);
+        queue.resume();
+        assert_eq!(queue.state().speaking.map(|s| s.text), Some("Three.".to_string()));
+        assert!(!queue.push("other", "Ignored.".to_string()));
+    }
+}
@@ -0,0 +1,70 @@
+//! Masks personal data and secrets in a prompt before it is sent to a provider: email addresses,
+//! payment card numbers and API keys or access tokens. The masked prompt is also what history
+//! keeps, so the record matches what left the machine.
+
+use std::sync::OnceLock;
+use regex::Regex;
+
+fn patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}", "[email]"),
+            // Key formats with a recognisable prefix: OpenAI-style, Together, GitHub, AWS, Slack, JWTs
+            (
+                r"\b(?:sk-[A-Za-z0-9_-]{16,}|tgp_v1_[A-Za-z0-9_-]{16,}|gh[pousr]_[A-Za-z0-9]{20,}|AKIA[0-9A-Z]{16}|xox[abprs]-[A-Za-z0-9-]{10,}|eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]+)",
+                "[secret]",
+            ),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid redaction pattern"), replacement))
+        .collect()
+    })
+}
+
+fn card_numbers() -> &'static Regex {
+    static CARDS: OnceLock<Regex> = OnceLock::new();
+    CARDS.get_or_init(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("valid card pattern"))
+}
+
+/// Whether `digits` pass the Luhn check every card number does, so order numbers and the like
+/// are left alone
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits.iter().rev().enumerate().map(|(i, &digit)| {
+        if i % 2 == 1 {
+            let doubled = digit * 2;
+            if doubled > 9 { doubled - 9 } else { doubled }
+        } else {
+            digit
+        }
+    }).sum();
+    sum.is_multiple_of(10)
+}
+
+pub fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    for (pattern, replacement) in patterns() {
+        text = pattern.replace_all(&text, *replacement).into_owned();
+    }
+    card_numbers().replace_all(&text, |found: &regex::Captures| {
+        let digits: Vec<u32> = found[0].chars().filter_map(|c| c.to_digit(10)).collect();
+        if luhn(&digits) { "[card number]".to_string() } else { found[0].to_string() }
+    }).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_emails_and_keys() {
+        let redacted = redact("Mail jane.doe@example.co.uk, key sk-abcdefghijklmnop1234 or ghp_abcdefghijklmnopqrstuv12");
+        assert_eq!(redacted, "Mail [email], key [secret] or [secret]");
+    }
+
+    #[test]
+    fn masks_only_valid_card_numbers() {
+        assert_eq!(redact("Card 4111 1111 1111 1111 expires soon"), "Card [card number] expires soon");
+        assert_eq!(redact("Order 1234-5678-9012-3456 shipped"), "Order 1234-5678-9012-3456 shipped");
+    }
+}
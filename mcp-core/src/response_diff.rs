@@ -0,0 +1,240 @@
+//! Word-level differences between two alternative answers to the same message, for showing
+//! exactly where regenerations, compare-mode samples or best-of-N candidates part ways.
+//!
+//! Answers are split at Unicode word boundaries and compared with Myers' algorithm. Whitespace
+//! between two changes is folded into them, so a reworded phrase reads as one removal and one
+//! addition rather than alternating single words.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::history::Candidate;
+
+/// Past this many edits the answers are treated as entirely different, which bounds the memory
+/// the comparison takes
+const MAX_EDITS: usize = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum DiffKind {
+    Same,
+    /// Only in the second answer
+    Added,
+    /// Only in the first answer
+    Removed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct DiffSegment {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ResponseDiff {
+    pub from_candidate: String,
+    pub from_model: String,
+    pub to_candidate: String,
+    pub to_model: String,
+    /// Both answers in order: Same and Removed segments make up the first, Same and Added the second
+    pub segments: Vec<DiffSegment>,
+    pub words_added: u32,
+    pub words_removed: u32,
+}
+
+pub fn diff(from: &Candidate, to: &Candidate) -> ResponseDiff {
+    let a: Vec<&str> = from.content.split_word_bounds().collect();
+    let b: Vec<&str> = to.content.split_word_bounds().collect();
+    let edits = edit_script(&a, &b);
+    let count = |kind| edits.iter().filter(|(k, token)| *k == kind && is_word(token)).count() as u32;
+    ResponseDiff {
+        from_candidate: from.id.clone(),
+        from_model: from.model.clone(),
+        to_candidate: to.id.clone(),
+        to_model: to.model.clone(),
+        words_added: count(DiffKind::Added),
+        words_removed: count(DiffKind::Removed),
+        segments: segments(&edits),
+    }
+}
+
+fn is_word(token: &str) -> bool {
+    token.chars().any(char::is_alphanumeric)
+}
+
+/// The tokens of both sides in order, each marked as kept, added or removed
+fn edit_script<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(DiffKind, &'a str)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (middle_a, middle_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut edits: Vec<(DiffKind, &str)> = a[..prefix].iter().map(|token| (DiffKind::Same, *token)).collect();
+    match myers(middle_a, middle_b) {
+        Some(middle) => edits.extend(middle),
+        None => {
+            edits.extend(middle_a.iter().map(|token| (DiffKind::Removed, *token)));
+            edits.extend(middle_b.iter().map(|token| (DiffKind::Added, *token)));
+        }
+    }
+    edits.extend(a[a.len() - suffix..].iter().map(|token| (DiffKind::Same, *token)));
+    edits
+}
+
+/// Shortest edit script from `a` to `b`, or None if it takes more than MAX_EDITS
+fn myers<'a>(a: &[&'a str], b: &[&'a str]) -> Option<Vec<(DiffKind, &'a str)>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    if max == 0 {
+        return Some(Vec::new());
+    }
+    // v[k] is the furthest x reached on diagonal k = x - y; trace[d] keeps diagonals -d..=d
+    // after d edits, for walking back
+    let at = |k: isize| (k + max as isize) as usize;
+    let mut v = vec![0isize; 2 * max + 1];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=max.min(MAX_EDITS) as isize {
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]);
+            let mut x = if down { v[at(k + 1)] } else { v[at(k - 1)] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                trace.push(v[at(-d)..=at(d)].to_vec());
+                return Some(backtrack(a, b, &trace));
+            }
+        }
+        trace.push(v[at(-d)..=at(d)].to_vec());
+    }
+    None
+}
+
+fn backtrack<'a>(a: &[&'a str], b: &[&'a str], trace: &[Vec<isize>]) -> Vec<(DiffKind, &'a str)> {
+    let (mut x, mut y) = (a.len() as isize, b.len() as isize);
+    let mut edits = Vec::new();
+    for d in (1..trace.len() as isize).rev() {
+        let previous = &trace[d as usize - 1];
+        let reached = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let down = k == -d || (k != d && reached(k - 1) < reached(k + 1));
+        let previous_k = if down { k + 1 } else { k - 1 };
+        let previous_x = reached(previous_k);
+        let snake_start = if down { previous_x } else { previous_x + 1 };
+        while x > snake_start {
+            edits.push((DiffKind::Same, a[x as usize - 1]));
+            x -= 1;
+            y -= 1;
+        }
+        if down {
+            edits.push((DiffKind::Added, b[y as usize - 1]));
+        } else {
+            edits.push((DiffKind::Removed, a[x as usize - 1]));
+        }
+        x = previous_x;
+        y = previous_x - previous_k;
+    }
+    while x > 0 {
+        edits.push((DiffKind::Same, a[x as usize - 1]));
+        x -= 1;
+    }
+    edits.reverse();
+    edits
+}
+
+/// Join runs of tokens into segments, folding whitespace between changes into the changes
+fn segments(edits: &[(DiffKind, &str)]) -> Vec<DiffSegment> {
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    let mut removed = String::new();
+    let mut added = String::new();
+    for (i, (kind, token)) in edits.iter().enumerate() {
+        match kind {
+            DiffKind::Removed => removed.push_str(token),
+            DiffKind::Added => added.push_str(token),
+            DiffKind::Same => {
+                let between_changes = token.trim().is_empty()
+                    && !(removed.is_empty() && added.is_empty())
+                    && edits.get(i + 1).is_some_and(|(next, _)| *next != DiffKind::Same);
+                if between_changes {
+                    removed.push_str(token);
+                    added.push_str(token);
+                    continue;
+                }
+                flush(&mut segments, &mut removed, &mut added);
+                extend(&mut segments, DiffKind::Same, token);
+            }
+        }
+    }
+    flush(&mut segments, &mut removed, &mut added);
+    segments
+}
+
+fn flush(segments: &mut Vec<DiffSegment>, removed: &mut String, added: &mut String) {
+    if !removed.is_empty() {
+        extend(segments, DiffKind::Removed, &std::mem::take(removed));
+    }
+    if !added.is_empty() {
+        extend(segments, DiffKind::Added, &std::mem::take(added));
+    }
+}
+
+fn extend(segments: &mut Vec<DiffSegment>, kind: DiffKind, text: &str) {
+    match segments.last_mut() {
+        Some(last) if last.kind == kind => last.text.push_str(text),
+        _ => segments.push(DiffSegment { kind, text: text.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(id: &str, content: &str) -> Candidate {
+        Candidate {
+            id: id.to_string(),
+            message_id: "m".to_string(),
+            model: format!("model-{}", id),
+            content: content.to_string(),
+            selected: false,
+            created_at: String::new(),
+        }
+    }
+
+    fn side(diff: &ResponseDiff, skip: DiffKind) -> String {
+        diff.segments.iter().filter(|segment| segment.kind != skip).map(|segment| segment.text.as_str()).collect()
+    }
+
+    #[test]
+    fn reworded_phrases_become_one_change() {
+        let from = candidate("a", "Rust is a fast and safe language.");
+        let to = candidate("b", "Rust is a very quick, safe language!");
+        let changes = diff(&from, &to);
+        assert_eq!(changes.segments, vec![
+            DiffSegment { kind: DiffKind::Same, text: "Rust is a ".to_string() },
+            DiffSegment { kind: DiffKind::Removed, text: "fast and".to_string() },
+            DiffSegment { kind: DiffKind::Added, text: "very quick,".to_string() },
+            DiffSegment { kind: DiffKind::Same, text: " safe language".to_string() },
+            DiffSegment { kind: DiffKind::Removed, text: ".".to_string() },
+            DiffSegment { kind: DiffKind::Added, text: "!".to_string() },
+        ]);
+        assert_eq!((changes.words_added, changes.words_removed), (2, 2));
+        assert_eq!(side(&changes, DiffKind::Added), from.content);
+        assert_eq!(side(&changes, DiffKind::Removed), to.content);
+    }
+
+    #[test]
+    fn both_answers_can_be_rebuilt() {
+        let from = candidate("a", "one two three four five six seven\n\neight nine");
+        let to = candidate("b", "zero one three four five six eight nine ten");
+        let changes = diff(&from, &to);
+        assert_eq!(side(&changes, DiffKind::Added), from.content);
+        assert_eq!(side(&changes, DiffKind::Removed), to.content);
+        let same = diff(&from, &from);
+        assert_eq!(same.segments, vec![DiffSegment { kind: DiffKind::Same, text: from.content.clone() }]);
+        assert_eq!(diff(&candidate("a", ""), &to).words_added, 9);
+    }
+}
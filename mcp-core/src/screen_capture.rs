@@ -0,0 +1,289 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use async_openai::Client;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPartImage,
+    ChatCompletionRequestMessageContentPartText, ChatCompletionRequestUserMessageArgs,
+    ChatCompletionRequestUserMessageContent, ChatCompletionRequestUserMessageContentPart,
+    CreateChatCompletionRequestArgs, CreateChatCompletionResponse, ImageUrl,
+};
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::storage;
+
+/// Vision model used to describe screenshots
+const VISION_MODEL: &str = "meta-llama/Llama-3.2-11B-Vision-Instruct-Turbo";
+
+const VISION_PROMPT: &str = "Describe this screenshot. Transcribe any visible text verbatim, \
+and if it shows an error dialog or message, explain what it means and how to resolve it.";
+
+/// Screen rectangle in logical pixels; capture the full screen when omitted
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct CaptureRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What to do with the image after it has been captured
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+pub enum CaptureAnalysis {
+    None,
+    Ocr,
+    Vision,
+}
+
+/// A screenshot attached to a conversation, with any extracted text or description
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ScreenCapture {
+    pub id: String,
+    pub conversation_id: String,
+    pub image_path: String,
+    pub analysis: CaptureAnalysis,
+    pub text: Option<String>,
+    pub created_at: String,
+}
+
+/// Take a screenshot with the platform tool and write it as PNG to `output`
+pub fn capture_to_file(region: Option<&CaptureRegion>, output: &Path) -> Result<()> {
+    let out = output.to_string_lossy().to_string();
+    let attempts = platform_commands(region, &out);
+
+    for (program, args) in &attempts {
+        log::info!("Trying screenshot tool: {}", program);
+        match Command::new(program).args(args).status() {
+            Ok(status) if status.success() && output.exists() => return Ok(()),
+            Ok(status) => log::warn!("{} exited with {}", program, status),
+            Err(e) => log::warn!("{} unavailable: {}", program, e),
+        }
+    }
+
+    Err(anyhow::anyhow!("No working screenshot tool found (tried: {})",
+        attempts.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>().join(", ")))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_commands(region: Option<&CaptureRegion>, out: &str) -> Vec<(String, Vec<String>)> {
+    let mut args = vec!["-x".to_string()];
+    if let Some(r) = region {
+        args.push(format!("-R{},{},{},{}", r.x, r.y, r.width, r.height));
+    }
+    args.push(out.to_string());
+    vec![("screencapture".to_string(), args)]
+}
+
+#[cfg(target_os = "windows")]
+fn platform_commands(region: Option<&CaptureRegion>, out: &str) -> Vec<(String, Vec<String>)> {
+    let (x, y, w, h) = match region {
+        Some(r) => (r.x.to_string(), r.y.to_string(), r.width.to_string(), r.height.to_string()),
+        None => (
+            "$b.Left".to_string(),
+            "$b.Top".to_string(),
+            "$b.Width".to_string(),
+            "$b.Height".to_string(),
+        ),
+    };
+    // Single quotes in a PowerShell literal are escaped by doubling them
+    let out = out.replace('\'', "''");
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; \
+         $b = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+         $bmp = New-Object System.Drawing.Bitmap({w}, {h}); \
+         $g = [System.Drawing.Graphics]::FromImage($bmp); \
+         $g.CopyFromScreen({x}, {y}, 0, 0, $bmp.Size); \
+         $bmp.Save('{out}', [System.Drawing.Imaging.ImageFormat]::Png)"
+    );
+    vec![("powershell".to_string(), vec!["-NoProfile".to_string(), "-Command".to_string(), script])]
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn platform_commands(region: Option<&CaptureRegion>, out: &str) -> Vec<(String, Vec<String>)> {
+    // Wayland first (grim), then common X11 tools
+    match region {
+        Some(r) => vec![
+            ("grim".to_string(), vec!["-g".to_string(), format!("{},{} {}x{}", r.x, r.y, r.width, r.height), out.to_string()]),
+            ("maim".to_string(), vec!["-g".to_string(), format!("{}x{}+{}+{}", r.width, r.height, r.x, r.y), out.to_string()]),
+            ("import".to_string(), vec![
+                "-window".to_string(), "root".to_string(),
+                "-crop".to_string(), format!("{}x{}+{}+{}", r.width, r.height, r.x, r.y),
+                out.to_string(),
+            ]),
+        ],
+        None => vec![
+            ("grim".to_string(), vec![out.to_string()]),
+            ("gnome-screenshot".to_string(), vec!["-f".to_string(), out.to_string()]),
+            ("maim".to_string(), vec![out.to_string()]),
+            ("import".to_string(), vec!["-window".to_string(), "root".to_string(), out.to_string()]),
+        ],
+    }
+}
+
+/// Extract text from an image with the locally installed `tesseract` CLI
+pub fn run_ocr(image: &Path) -> Result<String> {
+    let output = Command::new("tesseract")
+        .arg(image)
+        .arg("stdout")
+        .output()
+        .map_err(|e| anyhow::anyhow!("tesseract is not available: {}", e))?;
+    ocr_text(output.status.success(), &output.stdout, &output.stderr)
+}
+
+/// The text tesseract printed, or what it complained about
+fn ocr_text(success: bool, stdout: &[u8], stderr: &[u8]) -> Result<String> {
+    if !success {
+        return Err(anyhow::anyhow!("OCR failed: {}", String::from_utf8_lossy(stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(stdout).trim().to_string())
+}
+
+/// Ask a vision model to describe the screenshot
+pub async fn describe_with_vision(api_key: String, image: &Path) -> Result<String> {
+    let encoded = general_purpose::STANDARD.encode(std::fs::read(image)?);
+    let config = OpenAIConfig::new()
+        .with_api_key(api_key)
+        .with_api_base("https://api.together.xyz/v1");
+    let client = Client::with_config(config);
+
+    let content = ChatCompletionRequestUserMessageContent::Array(vec![
+        ChatCompletionRequestUserMessageContentPart::Text(ChatCompletionRequestMessageContentPartText {
+            text: VISION_PROMPT.to_string(),
+        }),
+        ChatCompletionRequestUserMessageContentPart::ImageUrl(ChatCompletionRequestMessageContentPartImage {
+            image_url: ImageUrl {
+                url: format!("data:image/png;base64,{}", encoded),
+                detail: None,
+            },
+        }),
+    ]);
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(VISION_MODEL)
+        .messages(vec![ChatCompletionRequestMessage::User(
+            ChatCompletionRequestUserMessageArgs::default().content(content).build()?,
+        )])
+        .build()?;
+
+    vision_text(client.chat().create(request).await?)
+}
+
+fn vision_text(response: CreateChatCompletionResponse) -> Result<String> {
+    response.choices.first()
+        .and_then(|choice| choice.message.content.clone())
+        .ok_or_else(|| anyhow::anyhow!("Vision model returned no content"))
+}
+
+/// File-backed storage of screenshots and their metadata, one directory per conversation
+pub struct CaptureStore {
+    root: PathBuf,
+}
+
+impl CaptureStore {
+    pub fn new(data_dir: &Path) -> Self {
+        CaptureStore {
+            root: data_dir.join("captures"),
+        }
+    }
+
+    /// Path for a new capture image, creating the conversation directory
+    pub fn image_path(&self, conversation_id: &str, capture_id: &str) -> Result<PathBuf> {
+        let dir = storage::conversation_dir(&self.root, conversation_id)?;
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!("{}.png", capture_id)))
+    }
+
+    pub fn save(&self, capture: &ScreenCapture) -> Result<()> {
+        let dir = storage::conversation_dir(&self.root, &capture.conversation_id)?;
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(format!("{}.json", capture.id)), serde_json::to_vec_pretty(capture)?)?;
+        Ok(())
+    }
+
+    pub fn list(&self, conversation_id: &str) -> Result<Vec<ScreenCapture>> {
+        let dir = storage::conversation_dir(&self.root, conversation_id)?;
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut captures = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                captures.push(serde_json::from_slice::<ScreenCapture>(&std::fs::read(&path)?)?);
+            }
+        }
+        captures.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(captures)
+    }
+
+    /// Move a conversation's captures, images included, to another conversation
+    pub fn reassign(&self, from: &str, to: &str) -> Result<u32> {
+        let captures = self.list(from)?;
+        for capture in &captures {
+            let image_path = self.image_path(to, &capture.id)?;
+            if Path::new(&capture.image_path).exists() {
+                std::fs::rename(&capture.image_path, &image_path)?;
+            }
+            self.save(&ScreenCapture {
+                conversation_id: to.to_string(),
+                image_path: image_path.to_string_lossy().to_string(),
+                ..capture.clone()
+            })?;
+        }
+        if !captures.is_empty() {
+            std::fs::remove_dir_all(storage::conversation_dir(&self.root, from)?)?;
+        }
+        Ok(captures.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(choices: serde_json::Value) -> CreateChatCompletionResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": "r",
+            "object": "chat.completion",
+            "created": 0,
+            "model": VISION_MODEL,
+            "choices": choices,
+        })).unwrap()
+    }
+
+    #[test]
+    fn ocr_output_is_trimmed_and_failures_carry_stderr() {
+        assert_eq!(ocr_text(true, b"\n  Error 404: Not Found \n\n", b"").unwrap(), "Error 404: Not Found");
+        assert_eq!(ocr_text(true, &[b'o', b'k', 0xFF], b"").unwrap(), "ok\u{FFFD}");
+        let error = ocr_text(false, b"", b" Error opening data file\n").unwrap_err();
+        assert_eq!(error.to_string(), "OCR failed: Error opening data file");
+    }
+
+    #[test]
+    fn the_first_choice_describes_the_screenshot() {
+        let described = response(serde_json::json!([
+            { "index": 0, "message": { "role": "assistant", "content": "A settings dialog" }, "finish_reason": "stop" },
+            { "index": 1, "message": { "role": "assistant", "content": "Something else" }, "finish_reason": "stop" },
+        ]));
+        assert_eq!(vision_text(described).unwrap(), "A settings dialog");
+
+        let empty = response(serde_json::json!([
+            { "index": 0, "message": { "role": "assistant", "content": null }, "finish_reason": "stop" },
+        ]));
+        assert!(vision_text(empty).is_err());
+        assert!(vision_text(response(serde_json::json!([]))).is_err());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn quotes_in_the_output_path_stay_inside_the_powershell_literal() {
+        let (_, args) = platform_commands(None, r"C:\Users\o'brien\shot.png").remove(0);
+        assert!(args[2].contains(r"$bmp.Save('C:\Users\o''brien\shot.png',"));
+    }
+}
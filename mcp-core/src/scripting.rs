@@ -0,0 +1,243 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+
+/// Hook functions a script may define
+pub const HOOK_MESSAGE_SEND: &str = "on_message_send";
+pub const HOOK_RESPONSE_COMPLETE: &str = "on_response_complete";
+pub const HOOK_TOOL_CALL: &str = "on_tool_call";
+
+const HOOKS: &[&str] = &[HOOK_MESSAGE_SEND, HOOK_RESPONSE_COMPLETE, HOOK_TOOL_CALL];
+
+/// Operation budget per hook call, so a buggy loop can't stall the chat
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ScriptInfo {
+    pub name: String,
+    pub path: String,
+    pub hooks: Vec<String>,
+    pub error: Option<String>,
+}
+
+struct LoadedScript {
+    name: String,
+    ast: AST,
+}
+
+struct ScriptHost {
+    engine: Engine,
+    scripts: Vec<LoadedScript>,
+    infos: Option<Vec<ScriptInfo>>,
+}
+
+fn host() -> &'static Mutex<ScriptHost> {
+    static HOST: OnceLock<Mutex<ScriptHost>> = OnceLock::new();
+    HOST.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| log::info!("[script] {}", text));
+        Mutex::new(ScriptHost {
+            engine,
+            scripts: Vec::new(),
+            infos: None,
+        })
+    })
+}
+
+fn scripts_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("scripts")
+}
+
+/// Compile every `*.rhai` file in `<data_dir>/scripts`, replacing previously loaded scripts
+pub fn reload(data_dir: &Path) -> Result<Vec<ScriptInfo>> {
+    let mut host = host().lock().map_err(|_| anyhow::anyhow!("Script host lock poisoned"))?;
+    host.scripts.clear();
+
+    let dir = scripts_dir(data_dir);
+    let mut paths = Vec::new();
+    if dir.is_dir() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "rhai") {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    let mut infos = Vec::new();
+    for path in paths {
+        let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let mut info = ScriptInfo {
+            name: name.clone(),
+            path: path.to_string_lossy().to_string(),
+            hooks: Vec::new(),
+            error: None,
+        };
+
+        match host.engine.compile_file(path.clone()) {
+            Ok(ast) => {
+                info.hooks = ast.iter_functions()
+                    .map(|f| f.name.to_string())
+                    .filter(|f| HOOKS.contains(&f.as_str()))
+                    .collect();
+                log::info!("Loaded script {} with hooks {:?}", name, info.hooks);
+                host.scripts.push(LoadedScript { name, ast });
+            }
+            Err(e) => {
+                log::error!("Failed to compile script {:?}: {}", path, e);
+                info.error = Some(e.to_string());
+            }
+        }
+        infos.push(info);
+    }
+
+    host.infos = Some(infos.clone());
+    Ok(infos)
+}
+
+/// Scripts as of the last reload, or None if scripts were never loaded
+pub fn loaded_scripts() -> Option<Vec<ScriptInfo>> {
+    host().lock().ok()?.infos.clone()
+}
+
+/// Run a hook in every script that defines it, in file name order.
+/// Script errors are logged and skipped so a broken script never blocks the chat.
+fn run_hook(hook: &str, args: Vec<Dynamic>, mut each: impl FnMut(&str, Dynamic)) {
+    let Ok(host) = host().lock() else {
+        return;
+    };
+    for script in &host.scripts {
+        if !script.ast.iter_functions().any(|f| f.name == hook) {
+            continue;
+        }
+        let mut scope = Scope::new();
+        match host.engine.call_fn::<Dynamic>(&mut scope, &script.ast, hook, args.clone()) {
+            Ok(result) => each(&script.name, result),
+            Err(e) => log::warn!("Script {} failed in {}: {}", script.name, hook, e),
+        }
+    }
+}
+
+/// `on_message_send(message)` may return a rewritten prompt; returning nothing keeps it
+pub fn on_message_send(message: String) -> String {
+    let mut current = message;
+    let mut rewrites = Vec::new();
+    run_hook(HOOK_MESSAGE_SEND, vec![Dynamic::from(current.clone())], |script, result| {
+        if result.is_string() {
+            rewrites.push((script.to_string(), result.into_string().unwrap_or_default()));
+        }
+    });
+    // Each script sees the original message; the last rewrite wins
+    for (script, rewritten) in rewrites {
+        log::info!("Script {} rewrote the outgoing message", script);
+        current = rewritten;
+    }
+    current
+}
+
+/// `on_response_complete(message, response)` may return a tag or an array of tags
+pub fn on_response_complete(message: &str, response: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    run_hook(
+        HOOK_RESPONSE_COMPLETE,
+        vec![Dynamic::from(message.to_string()), Dynamic::from(response.to_string())],
+        |_, result| {
+            if result.is_string() {
+                tags.extend(result.into_string().ok());
+            } else if result.is_array() {
+                tags.extend(result.into_array().unwrap_or_default().into_iter().map(|t| t.to_string()));
+            }
+        },
+    );
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// `on_tool_call(tool, args_json)` blocks the call by returning `false` or a reason string
+pub fn check_tool_call(tool: &str, args_json: &str) -> Result<(), String> {
+    let mut blocked = None;
+    run_hook(
+        HOOK_TOOL_CALL,
+        vec![Dynamic::from(tool.to_string()), Dynamic::from(args_json.to_string())],
+        |script, result| {
+            if blocked.is_some() {
+                return;
+            }
+            if result.as_bool() == Ok(false) {
+                blocked = Some(format!("Tool call {} blocked by script {}", tool, script));
+            } else if result.is_string() {
+                blocked = Some(format!("Tool call {} blocked by script {}: {}", tool, script, result));
+            }
+        },
+    );
+    match blocked {
+        Some(reason) => Err(reason),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The script host is shared by the whole process, so tests loading scripts take turns
+    static HOST_IN_USE: Mutex<()> = Mutex::new(());
+
+    fn load(script: &str) -> (std::sync::MutexGuard<'static, ()>, Vec<ScriptInfo>) {
+        let guard = HOST_IN_USE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let data_dir = std::env::temp_dir().join(format!("mcp-scripting-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(scripts_dir(&data_dir)).unwrap();
+        std::fs::write(scripts_dir(&data_dir).join("test.rhai"), script).unwrap();
+        let infos = reload(&data_dir).unwrap();
+        let _ = std::fs::remove_dir_all(&data_dir);
+        (guard, infos)
+    }
+
+    #[test]
+    fn message_send_rewrites_the_prompt() {
+        let (_guard, infos) = load(r#"fn on_message_send(message) { if message == "skip" { return; } message + "!" }"#);
+        assert_eq!(infos[0].hooks, [HOOK_MESSAGE_SEND]);
+        assert_eq!(on_message_send("hello".to_string()), "hello!");
+        assert_eq!(on_message_send("skip".to_string()), "skip");
+    }
+
+    #[test]
+    fn response_complete_tags_answers() {
+        let (_guard, _) = load(r#"
+            fn on_response_complete(message, response) {
+                if response.contains("```") { ["code", "long"] } else if message == "hi" { "greeting" } else { () }
+            }
+        "#);
+        assert_eq!(on_response_complete("q", "```rust```"), ["code", "long"]);
+        assert_eq!(on_response_complete("hi", "Hello"), ["greeting"]);
+        assert!(on_response_complete("q", "plain").is_empty());
+    }
+
+    #[test]
+    fn tool_calls_can_be_blocked() {
+        let (_guard, _) = load(r#"
+            fn on_tool_call(tool, args) {
+                if tool == "delete" { return false; }
+                if args.contains("secret") { return "no secrets"; }
+                true
+            }
+        "#);
+        assert!(check_tool_call("search", "{}").is_ok());
+        assert!(check_tool_call("delete", "{}").is_err());
+        assert!(check_tool_call("search", r#"{"q": "secret"}"#).unwrap_err().ends_with("no secrets"));
+    }
+
+    #[test]
+    fn broken_scripts_are_reported_and_skipped() {
+        let (_guard, infos) = load("fn on_message_send(message) {");
+        assert!(infos[0].error.is_some());
+        assert_eq!(on_message_send("hello".to_string()), "hello");
+    }
+}
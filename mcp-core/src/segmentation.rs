@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+use futures::StreamExt;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::generation::MessageStream;
+use crate::StreamMessage;
+
+/// Longest run held back waiting for a word to end; past this only the last grapheme cluster is kept
+const MAX_HELD_BYTES: usize = 64;
+
+/// Buffers streamed text so chunks end on a grapheme cluster and, where the text has them, a word
+/// boundary. Providers split tokens mid-cluster (an emoji ZWJ sequence, a flag's two regional
+/// indicators, a base letter and its combining marks), which renders as broken glyphs until the
+/// rest arrives; cursive scripts like Arabic also reshape when a word is cut. CJK ideographs are
+/// each their own word, so they still flow a character at a time.
+#[derive(Default)]
+pub struct Segmenter {
+    pending: String,
+}
+
+impl Segmenter {
+    /// Add `chunk` and return the text now safe to show, if any
+    pub fn push(&mut self, chunk: &str) -> Option<String> {
+        self.pending.push_str(chunk);
+        // The last segment may still grow, so it stays back until something follows it
+        let mut split = self.pending.split_word_bound_indices().next_back().map(|(start, _)| start).unwrap_or(0);
+        if split == 0 && self.pending.len() > MAX_HELD_BYTES {
+            split = self.pending.grapheme_indices(true).next_back().map(|(start, _)| start).unwrap_or(0);
+        }
+        if split == 0 {
+            return None;
+        }
+        let rest = self.pending.split_off(split);
+        Some(std::mem::replace(&mut self.pending, rest))
+    }
+
+    /// Whatever is held back, for when no more text is coming
+    pub fn finish(&mut self) -> Option<String> {
+        (!self.pending.is_empty()).then(|| std::mem::take(&mut self.pending))
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+struct Coalescer {
+    stream: Option<MessageStream>,
+    segmenter: Segmenter,
+    ready: VecDeque<StreamMessage>,
+}
+
+/// Re-chunk the Content of `stream` on cluster and word boundaries. Held text is released before
+/// an Error or Complete and when the stream ends; a Replace supersedes it.
+pub fn coalesce(stream: MessageStream) -> MessageStream {
+    let coalescer = Coalescer { stream: Some(stream), segmenter: Segmenter::default(), ready: VecDeque::new() };
+    Box::pin(futures::stream::unfold(coalescer, |mut coalescer| async move {
+        loop {
+            if let Some(message) = coalescer.ready.pop_front() {
+                return Some((message, coalescer));
+            }
+            let message = coalescer.stream.as_mut()?.next().await;
+            match message {
+                Some(StreamMessage::Content(chunk)) => {
+                    coalescer.ready.extend(coalescer.segmenter.push(&chunk).map(StreamMessage::Content));
+                }
+                Some(StreamMessage::Replace(text)) => {
+                    coalescer.segmenter.clear();
+                    coalescer.ready.push_back(StreamMessage::Replace(text));
+                }
+                // Usage isn't text, so the word being cut is left to finish
+                Some(usage @ StreamMessage::Usage { .. }) => coalescer.ready.push_back(usage),
+                Some(message) => {
+                    coalescer.ready.extend(coalescer.segmenter.finish().map(StreamMessage::Content));
+                    coalescer.ready.push_back(message);
+                }
+                None => {
+                    coalescer.stream = None;
+                    coalescer.ready.extend(coalescer.segmenter.finish().map(StreamMessage::Content));
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stream `chunks` through a Segmenter, returning what was emitted chunk by chunk
+    fn segment(chunks: &[&str]) -> Vec<String> {
+        let mut segmenter = Segmenter::default();
+        let mut emitted: Vec<String> = chunks.iter().filter_map(|chunk| segmenter.push(chunk)).collect();
+        emitted.extend(segmenter.finish());
+        emitted
+    }
+
+    /// Every emitted chunk must start and end on a grapheme boundary of the whole text
+    fn assert_whole_clusters(text: &str, emitted: &[String]) {
+        assert_eq!(emitted.concat(), text);
+        let boundaries: Vec<usize> = text.grapheme_indices(true).map(|(start, _)| start).chain([text.len()]).collect();
+        let mut offset = 0;
+        for chunk in emitted {
+            offset += chunk.len();
+            assert!(boundaries.contains(&offset), "chunk {:?} ends inside a grapheme cluster of {:?}", chunk, text);
+        }
+    }
+
+    /// Split `text` at every char boundary pair and check nothing is ever cut mid-cluster
+    fn assert_all_splits(text: &str) {
+        let cuts: Vec<usize> = text.char_indices().map(|(i, _)| i).skip(1).collect();
+        for (i, &first) in cuts.iter().enumerate() {
+            for &second in &cuts[i..] {
+                let chunks = [&text[..first], &text[first..second], &text[second..]];
+                assert_whole_clusters(text, &segment(&chunks));
+            }
+        }
+    }
+
+    #[test]
+    fn zwj_emoji_is_not_split() {
+        // Family: man, ZWJ, woman, ZWJ, girl
+        let family = "Hi \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467} there";
+        let emitted = segment(&["Hi \u{1F468}\u{200D}", "\u{1F469}\u{200D}", "\u{1F467} there"]);
+        assert_whole_clusters(family, &emitted);
+        assert!(emitted.iter().any(|chunk| chunk.contains("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}")));
+        assert_all_splits(family);
+    }
+
+    #[test]
+    fn flags_and_skin_tones_are_not_split() {
+        assert_all_splits("\u{1F1EF}\u{1F1F5}\u{1F1E9}\u{1F1EA} ok");
+        assert_all_splits("wave \u{1F44B}\u{1F3FD} bye");
+        let emitted = segment(&["go \u{1F1EF}", "\u{1F1F5} now"]);
+        assert!(!emitted.iter().any(|chunk| chunk.ends_with('\u{1F1EF}')));
+    }
+
+    #[test]
+    fn cjk_flows_per_character() {
+        let emitted = segment(&["你好", "世界", "。"]);
+        assert_eq!(emitted.concat(), "你好世界。");
+        // Each ideograph is its own word, so only the last one of a chunk is held back
+        assert_eq!(emitted[0], "你");
+        assert_all_splits("日本語のテキスト");
+    }
+
+    #[test]
+    fn hangul_jamo_stay_with_their_syllable() {
+        // 한 written as conjoining jamo: ᄒ ᅡ ᆫ
+        let text = "\u{1112}\u{1161}\u{11AB}\u{1100}\u{1173}\u{11AF}";
+        let emitted = segment(&["\u{1112}", "\u{1161}", "\u{11AB}\u{1100}\u{1173}", "\u{11AF}"]);
+        assert_whole_clusters(text, &emitted);
+        assert_all_splits(text);
+    }
+
+    #[test]
+    fn rtl_words_are_emitted_whole() {
+        // Arabic joins letters within a word, so half a word shapes differently than the whole
+        let emitted = segment(&["مرحب", "ا بالعا", "لم"]);
+        assert_eq!(emitted.concat(), "مرحبا بالعالم");
+        assert!(!emitted.contains(&"مرحب".to_string()));
+        assert!(emitted.iter().any(|chunk| chunk.starts_with("مرحبا")));
+    }
+
+    #[test]
+    fn rtl_combining_marks_are_not_split() {
+        // Hebrew with niqqud and Arabic with harakat
+        assert_all_splits("שָׁלוֹם עוֹלָם");
+        assert_all_splits("السَّلَامُ عَلَيْكُم");
+    }
+
+    #[test]
+    fn long_runs_fall_back_to_clusters() {
+        let blob = "x".repeat(MAX_HELD_BYTES * 2);
+        let mut segmenter = Segmenter::default();
+        let emitted = segmenter.push(&blob).expect("a long run is not held back whole");
+        assert_eq!(emitted.len(), blob.len() - 1);
+        assert_eq!(segmenter.finish().as_deref(), Some("x"));
+    }
+
+    #[tokio::test]
+    async fn coalesce_flushes_before_errors_and_at_the_end() {
+        let messages = vec![
+            StreamMessage::Content("Hello wor".to_string()),
+            StreamMessage::Content("ld".to_string()),
+            StreamMessage::Error("boom".to_string()),
+        ];
+        let stream: MessageStream = Box::pin(futures::stream::iter(messages));
+        let out: Vec<StreamMessage> = coalesce(stream).collect().await;
+        let text: String = out.iter().filter_map(|m| match m { StreamMessage::Content(c) => Some(c.as_str()), _ => None }).collect();
+        assert_eq!(text, "Hello world");
+        assert!(matches!(out.last(), Some(StreamMessage::Error(_))));
+
+        let stream: MessageStream = Box::pin(futures::stream::iter(vec![StreamMessage::Content("né".to_string())]));
+        let out: Vec<StreamMessage> = coalesce(stream).collect().await;
+        assert!(matches!(out.as_slice(), [StreamMessage::Content(c)] if c == "né"));
+    }
+
+    #[tokio::test]
+    async fn coalesce_drops_held_text_on_replace() {
+        let messages = vec![
+            StreamMessage::Content("draft tex".to_string()),
+            StreamMessage::Replace("final".to_string()),
+        ];
+        let stream: MessageStream = Box::pin(futures::stream::iter(messages));
+        let out: Vec<StreamMessage> = coalesce(stream).collect().await;
+        assert!(matches!(out.as_slice(), [StreamMessage::Content(a), StreamMessage::Replace(b)] if a == "draft " && b == "final"));
+    }
+}
@@ -0,0 +1,715 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use axum::Router;
+use axum::Json;
+use axum::extract::{ConnectInfo, Extension, Path, Query, Request, State};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use futures::StreamExt;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, oneshot};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::events::{self, AppEvent};
+use crate::generation::MessageStream;
+use crate::handoff::HandoffTransport;
+use crate::openai_api;
+use crate::telemetry;
+use crate::pairing::PairingRequest;
+use crate::server_auth::{self, RequestShaping, ServerTlsConfig, ServerToken, TokenScope};
+use crate::StreamMessage;
+
+/// Default port for the local API server
+pub const DEFAULT_PORT: u16 = 8765;
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+    pub started_at: Option<String>,
+    pub tls: bool,
+    pub mutual_tls: bool,
+    pub auth_required: bool,
+    pub lan_exposed: bool,
+}
+
+enum Shutdown {
+    Plain(oneshot::Sender<()>),
+    Tls(axum_server::Handle),
+}
+
+struct RunningServer {
+    address: SocketAddr,
+    started_at: String,
+    tls: bool,
+    mutual_tls: bool,
+    shutdown: Shutdown,
+}
+
+fn running() -> &'static Mutex<Option<RunningServer>> {
+    static RUNNING: OnceLock<Mutex<Option<RunningServer>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(None))
+}
+
+pub fn status() -> ServerStatus {
+    match running().lock().ok().as_deref() {
+        Some(Some(server)) => ServerStatus {
+            running: true,
+            address: Some(server.address.to_string()),
+            started_at: Some(server.started_at.clone()),
+            tls: server.tls,
+            mutual_tls: server.mutual_tls,
+            auth_required: auth_required(),
+            lan_exposed: !server.address.ip().is_loopback(),
+        },
+        _ => ServerStatus {
+            running: false,
+            address: None,
+            started_at: None,
+            tls: false,
+            mutual_tls: false,
+            auth_required: auth_required(),
+            lan_exposed: false,
+        },
+    }
+}
+
+fn tokens() -> &'static RwLock<Vec<ServerToken>> {
+    static TOKENS: OnceLock<RwLock<Vec<ServerToken>>> = OnceLock::new();
+    TOKENS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Replace the accepted tokens; takes effect immediately for new connections
+pub fn set_tokens(new_tokens: Vec<ServerToken>) {
+    if let Ok(mut current) = tokens().write() {
+        *current = new_tokens;
+    }
+}
+
+fn allowlist() -> &'static RwLock<Vec<IpNet>> {
+    static ALLOWLIST: OnceLock<RwLock<Vec<IpNet>>> = OnceLock::new();
+    ALLOWLIST.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Replace the remote clients allowed to connect; loopback clients are always allowed
+pub fn set_allowlist(new_allowlist: Vec<IpNet>) {
+    if let Ok(mut current) = allowlist().write() {
+        *current = new_allowlist;
+    }
+}
+
+static AUTH_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Authenticate every request from now on, whether or not any token exists. Revoking the last
+/// token then locks clients out rather than letting everyone in.
+pub fn set_auth_enabled(enabled: bool) {
+    AUTH_ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+fn allowed_origins() -> &'static RwLock<Vec<String>> {
+    static ALLOWED_ORIGINS: OnceLock<RwLock<Vec<String>>> = OnceLock::new();
+    ALLOWED_ORIGINS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Replace the web page origins allowed to open the WebSocket endpoints
+pub fn set_allowed_origins(new_origins: Vec<String>) {
+    if let Ok(mut current) = allowed_origins().write() {
+        *current = new_origins;
+    }
+}
+
+/// Requests to the /v1 endpoints are only authenticated once authentication is on, which
+/// creating the first token turns on for good; the WebSocket endpoints always need a token
+fn auth_required() -> bool {
+    AUTH_ENABLED.load(Ordering::SeqCst) || tokens().read().map(|t| !t.is_empty()).unwrap_or(true)
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/ws/events", get(events_handler))
+        .route_layer(middleware::from_fn_with_state(TokenScope::Admin, require_scope))
+        .merge(
+            Router::new()
+                .route("/ws/chat", get(chat_handler))
+                .route("/v1/models", get(models_handler))
+                .route("/v1/models/{*id}", get(model_handler))
+                .route_layer(middleware::from_fn(shape_requests))
+                .route_layer(middleware::from_fn_with_state(TokenScope::Chat, require_scope)),
+        )
+        .route("/health", get(|| async { "ok" }))
+        .route("/pair", post(pair_handler))
+        .layer(middleware::from_fn(require_allowed_origin))
+        .layer(middleware::from_fn(require_allowed_client))
+        .layer(middleware::from_fn(trace_requests))
+}
+
+/// A server span per request, continuing the caller's trace when it sends `traceparent`
+async fn trace_requests(ConnectInfo(client): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    let traceparent = request.headers().get("traceparent").and_then(|v| v.to_str().ok());
+    let path = request.uri().path().to_string();
+    let mut span = telemetry::Span::continuing(
+        format!("{} {}", request.method(), path),
+        telemetry::SpanKind::Server,
+        traceparent,
+    );
+    span.set("http.request.method", request.method().to_string());
+    span.set("url.path", path);
+    span.set("client.address", client.ip().to_string());
+    let response = next.run(request).await;
+    span.set("http.response.status_code", i64::from(response.status().as_u16()));
+    if response.status().is_server_error() {
+        span.fail(response.status().to_string());
+    }
+    response
+}
+
+async fn require_allowed_client(ConnectInfo(client): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    let allowed = allowlist().read()
+        .map(|list| server_auth::is_client_allowed(&list, client.ip()))
+        .unwrap_or(false);
+    if !allowed {
+        log::warn!("Rejected API request from {} (not in allowlist)", client);
+        return (StatusCode::FORBIDDEN, "Client address not allowed").into_response();
+    }
+    next.run(request).await
+}
+
+/// Refuse WebSocket upgrades from web pages whose origin isn't allowed, so a page the user
+/// happens to visit can't open the event stream or chat through localhost
+async fn require_allowed_origin(request: Request, next: Next) -> Response {
+    let upgrade = request.headers()
+        .get(axum::http::header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    if upgrade || request.uri().path().starts_with("/ws/") {
+        let origin = request.headers().get(axum::http::header::ORIGIN).map(|v| v.to_str().unwrap_or("invalid"));
+        let allowed = allowed_origins().read()
+            .map(|origins| server_auth::is_origin_allowed(&origins, origin))
+            .unwrap_or(false);
+        if !allowed {
+            log::warn!("Rejected WebSocket upgrade from origin {:?} (not allowed)", origin);
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Id of the token a request was authenticated with, for the handlers and layers after
+/// `require_scope`
+#[derive(Clone)]
+struct ClientToken(String);
+
+/// Accept `Authorization: Bearer <token>`, or `?token=` for browser WebSocket clients
+/// which cannot set headers
+async fn require_scope(State(required): State<TokenScope>, mut request: Request, next: Next) -> Response {
+    if !auth_required() && !request.uri().path().starts_with("/ws/") {
+        return next.run(request).await;
+    }
+
+    let header_token = request.headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.trim().to_string());
+    let query_token = request.uri().query().and_then(|query| {
+        query.split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "token")
+            .map(|(_, value)| value.to_string())
+    });
+
+    let Some(secret) = header_token.or(query_token) else {
+        return (StatusCode::UNAUTHORIZED, "Missing bearer token").into_response();
+    };
+    let token = tokens().read().ok()
+        .and_then(|t| server_auth::authenticate(&t, &secret).map(|token| (token.id.clone(), token.scope)));
+    match token {
+        Some((id, scope)) if scope.allows(required) => {
+            request.extensions_mut().insert(ClientToken(id));
+            next.run(request).await
+        }
+        Some(_) => (StatusCode::FORBIDDEN, "Token scope does not allow this endpoint").into_response(),
+        None => (StatusCode::UNAUTHORIZED, "Invalid bearer token").into_response(),
+    }
+}
+
+/// Attach the shaping set for the client's token, so chat requests get its persona and model
+/// whatever the app is set to. Unauthenticated /v1 requests, allowed while no token exists, get none.
+async fn shape_requests(mut request: Request, next: Next) -> Response {
+    let shaping = request.extensions().get::<ClientToken>()
+        .and_then(|ClientToken(id)| {
+            tokens().read().ok()?.iter().find(|token| token.id == *id).map(|token| token.shaping.clone())
+        })
+        .unwrap_or_default();
+    if shaping != RequestShaping::default() {
+        log::debug!("Shaping API request to {} with {:?}", request.uri().path(), shaping);
+    }
+    request.extensions_mut().insert(shaping);
+    next.run(request).await
+}
+
+/// Bind the server and serve in the background until `stop` is called.
+/// With a TLS config the server only speaks HTTPS/WSS, and requires client certificates
+/// when a client CA is configured.
+pub async fn start(address: SocketAddr, tls: Option<ServerTlsConfig>) -> Result<ServerStatus> {
+    if status().running {
+        return Err(anyhow::anyhow!("Server is already running"));
+    }
+
+    let rustls_config = tls.as_ref().map(server_auth::build_tls_config).transpose()?;
+    let mutual_tls = tls.as_ref().is_some_and(|t| t.client_ca_path.is_some());
+
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    let bound = listener.local_addr()?;
+
+    let shutdown = match rustls_config {
+        Some(config) => {
+            let handle = axum_server::Handle::new();
+            let config = axum_server::tls_rustls::RustlsConfig::from_config(Arc::new(config));
+            let server = axum_server::from_tcp_rustls(listener.into_std()?, config).handle(handle.clone());
+            tokio::spawn(async move {
+                if let Err(e) = server.serve(router().into_make_service_with_connect_info::<SocketAddr>()).await {
+                    log::error!("API server error: {}", e);
+                }
+                log::info!("API server on {} stopped", bound);
+            });
+            Shutdown::Tls(handle)
+        }
+        None => {
+            let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+            tokio::spawn(async move {
+                let shutdown = async {
+                    let _ = shutdown_rx.await;
+                };
+                if let Err(e) = axum::serve(listener, router().into_make_service_with_connect_info::<SocketAddr>())
+                    .with_graceful_shutdown(shutdown).await {
+                    log::error!("API server error: {}", e);
+                }
+                log::info!("API server on {} stopped", bound);
+            });
+            Shutdown::Plain(shutdown_tx)
+        }
+    };
+
+    *running().lock().map_err(|_| anyhow::anyhow!("Server state lock poisoned"))? = Some(RunningServer {
+        address: bound,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        tls: tls.is_some(),
+        mutual_tls,
+        shutdown,
+    });
+    log::info!("API server listening on {} (tls: {}, mutual tls: {})", bound, tls.is_some(), mutual_tls);
+    if !auth_required() {
+        log::warn!("API server authentication is off; /v1 requests are not authenticated and /ws requests are refused");
+    }
+    if !bound.ip().is_loopback() {
+        log::warn!("API server is exposed on the network at {}", bound);
+    }
+    events::publish(AppEvent::ServerStarted { address: bound.to_string() });
+    Ok(status())
+}
+
+pub fn stop() -> Result<()> {
+    let server = running().lock()
+        .map_err(|_| anyhow::anyhow!("Server state lock poisoned"))?
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("Server is not running"))?;
+    match server.shutdown {
+        Shutdown::Plain(sender) => {
+            let _ = sender.send(());
+        }
+        Shutdown::Tls(handle) => handle.graceful_shutdown(None),
+    }
+    events::publish(AppEvent::ServerStopped);
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    /// Comma-separated AppEvent variant names; all events when omitted
+    types: Option<String>,
+}
+
+async fn events_handler(ws: WebSocketUpgrade, Query(query): Query<EventsQuery>) -> impl IntoResponse {
+    let filter: Option<HashSet<String>> = query.types
+        .map(|types| types.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect());
+    ws.on_upgrade(move |socket| stream_events(socket, filter))
+}
+
+/// Whether to keep `event` from a subscriber because it reveals a locked conversation that
+/// hasn't been unlocked. Answer events only carry their request, so they're sent only for
+/// requests whose ChatStarted was seen and allowed; `visible_requests` tracks those.
+fn withheld(event: &AppEvent, visible_requests: &mut HashSet<String>, hidden: impl Fn(&str) -> bool) -> bool {
+    if let Some(request_id) = event.request_id() {
+        let visible = visible_requests.contains(request_id);
+        if matches!(event, AppEvent::ChatCompleted { .. } | AppEvent::ChatError { .. }) {
+            visible_requests.remove(request_id);
+        }
+        return !visible;
+    }
+    let Some(conversation_id) = event.conversation_id() else { return false };
+    if hidden(conversation_id) {
+        return true;
+    }
+    if let AppEvent::ChatStarted { request_id, .. } = event {
+        visible_requests.insert(request_id.clone());
+    }
+    false
+}
+
+async fn stream_events(mut socket: WebSocket, filter: Option<HashSet<String>>) {
+    let mut receiver = events::subscribe();
+    let mut visible_requests = HashSet::new();
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    if withheld(&event, &mut visible_requests, crate::conversation_hidden) {
+                        continue;
+                    }
+                    if filter.as_ref().is_some_and(|types| !types.contains(event.kind())) {
+                        continue;
+                    }
+                    let Ok(json) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("Event subscriber lagged, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Exchange a pairing code for a token of the device's own. Unauthenticated, since the code is
+/// the credential; it still has to come from an allowed address.
+async fn pair_handler(ConnectInfo(client): ConnectInfo<SocketAddr>, Json(request): Json<PairingRequest>) -> Response {
+    match crate::complete_pairing(request) {
+        Ok(paired) => Json(paired).into_response(),
+        Err(e) => {
+            log::warn!("Pairing attempt from {} failed: {}", client, e);
+            (StatusCode::FORBIDDEN, e).into_response()
+        }
+    }
+}
+
+/// The models of every usable provider, as OpenAI's `GET /v1/models` lists them. A client
+/// held to one model only sees that one.
+async fn models_handler(Extension(shaping): Extension<RequestShaping>) -> Response {
+    match crate::model_catalogue().await {
+        Ok(catalogue) => Json(openai_api::model_list(&openai_api::shaped(catalogue, &shaping))).into_response(),
+        Err(e) => openai_api::error(StatusCode::BAD_GATEWAY, "api_error", None, &e),
+    }
+}
+
+async fn model_handler(Extension(shaping): Extension<RequestShaping>, Path(id): Path<String>) -> Response {
+    let catalogue = match crate::model_catalogue().await {
+        Ok(catalogue) => openai_api::shaped(catalogue, &shaping),
+        Err(e) => return openai_api::error(StatusCode::BAD_GATEWAY, "api_error", None, &e),
+    };
+    match openai_api::find(&catalogue, &id) {
+        Some(entry) => Json(openai_api::ModelObject::from(entry)).into_response(),
+        None => openai_api::error(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            Some("model_not_found"),
+            &format!("The model '{}' does not exist", id),
+        ),
+    }
+}
+
+/// Frames a /ws/chat client may send
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ChatClientFrame {
+    Chat {
+        message: String,
+        #[serde(default)]
+        conversation_id: Option<String>,
+        /// Tags recorded with the answer, on top of the token's
+        #[serde(default)]
+        metadata: std::collections::BTreeMap<String, String>,
+    },
+    Resume { message_id: String },
+    Cancel,
+    /// Take over a conversation handed to this device
+    AcceptHandoff { handoff_id: String },
+}
+
+async fn chat_handler(
+    ws: WebSocketUpgrade,
+    Extension(shaping): Extension<RequestShaping>,
+    client: Option<Extension<ClientToken>>,
+) -> impl IntoResponse {
+    let device = client.map(|Extension(ClientToken(id))| id);
+    ws.on_upgrade(move |socket| chat_session(socket, shaping, device))
+}
+
+async fn send_frame<T: Serialize>(socket: &mut WebSocket, frame: &T) -> bool {
+    match serde_json::to_string(frame) {
+        Ok(json) => socket.send(Message::Text(json.into())).await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// One WebSocket is one chat session: the server greets with the session id, then for each
+/// `chat` frame streams StreamMessage frames until Complete/Error. A `cancel` frame drops the
+/// in-flight provider stream, or stops it being opened if the provider hasn't answered yet, and
+/// is acknowledged with an Error("Cancelled") frame. Every chat is shaped by the client token's
+/// `shaping`.
+///
+/// Conversations handed to the client's `device` arrive as `{"handoff": …}` frames, waiting
+/// ones right after the greeting; `accept_handoff` is answered with `{"handoff_accepted": …}`.
+async fn chat_session(mut socket: WebSocket, shaping: RequestShaping, device: Option<String>) {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    log::info!("Chat WebSocket session {} opened", session_id);
+    if !send_frame(&mut socket, &serde_json::json!({ "session_id": session_id })).await {
+        return;
+    }
+    let mut bus = events::subscribe();
+    if let Some(device) = &device {
+        for handoff in crate::pending_paired_handoffs(device).unwrap_or_default() {
+            if !send_frame(&mut socket, &serde_json::json!({ "handoff": handoff })).await {
+                return;
+            }
+        }
+    }
+
+    let mut active: Option<MessageStream> = None;
+    // Opening a stream waits for the provider to connect, so it runs apart from the session and
+    // a cancel frame can abort it
+    let mut opening: Option<tokio::task::JoinHandle<Result<MessageStream, String>>> = None;
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                let text = match incoming {
+                    None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                };
+                let reply = match serde_json::from_str::<ChatClientFrame>(&text) {
+                    Ok(ChatClientFrame::Chat { .. } | ChatClientFrame::Resume { .. }) if active.is_some() || opening.is_some() => {
+                        Some(StreamMessage::Error("A response is already streaming".to_string()))
+                    }
+                    Ok(ChatClientFrame::Chat { message, conversation_id, metadata }) => {
+                        let mut shaping = shaping.clone();
+                        shaping.metadata.extend(metadata);
+                        match crate::history::validate_metadata(shaping.metadata) {
+                            Ok(metadata) => {
+                                shaping.metadata = metadata;
+                                opening = Some(tokio::spawn(async move {
+                                    crate::create_shaped_chat(message, conversation_id, &shaping).await
+                                }));
+                                None
+                            }
+                            Err(e) => Some(StreamMessage::Error(e)),
+                        }
+                    }
+                    Ok(ChatClientFrame::Resume { message_id }) => {
+                        opening = Some(tokio::spawn(crate::resume_response(message_id)));
+                        None
+                    }
+                    Ok(ChatClientFrame::Cancel) => {
+                        if let Some(task) = opening.take() {
+                            task.abort();
+                            log::info!("Chat session {} cancelled a stream still opening", session_id);
+                        }
+                        if active.take().is_some() {
+                            log::info!("Chat session {} cancelled the active stream", session_id);
+                        }
+                        Some(StreamMessage::Error("Cancelled".to_string()))
+                    }
+                    Ok(ChatClientFrame::AcceptHandoff { handoff_id }) => {
+                        let accepted = match &device {
+                            Some(device) => crate::accept_paired_handoff(device, &handoff_id),
+                            None => Err("Only a paired device can take over a conversation".to_string()),
+                        };
+                        match accepted {
+                            Ok(handoff) => {
+                                if !send_frame(&mut socket, &serde_json::json!({ "handoff_accepted": handoff })).await {
+                                    break;
+                                }
+                                None
+                            }
+                            Err(e) => Some(StreamMessage::Error(e)),
+                        }
+                    }
+                    Err(e) => Some(StreamMessage::Error(format!("Invalid frame: {}", e))),
+                };
+                if let Some(reply) = reply {
+                    if !send_frame(&mut socket, &reply).await {
+                        break;
+                    }
+                }
+            }
+            event = bus.recv(), if device.is_some() => {
+                let handoff = match event {
+                    Ok(AppEvent::HandoffSent { handoff }) => handoff,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    _ => continue,
+                };
+                if handoff.transport == HandoffTransport::Server && Some(&handoff.to_device) == device.as_ref()
+                    && !send_frame(&mut socket, &serde_json::json!({ "handoff": handoff })).await
+                {
+                    break;
+                }
+            }
+            opened = async { opening.as_mut().expect("guarded by select condition").await }, if opening.is_some() => {
+                opening = None;
+                let failure = match opened {
+                    Ok(Ok(stream)) => {
+                        active = Some(stream);
+                        continue;
+                    }
+                    Ok(Err(e)) => e,
+                    Err(e) => {
+                        log::error!("Chat session {} failed to open a stream: {}", session_id, e);
+                        e.to_string()
+                    }
+                };
+                if !send_frame(&mut socket, &StreamMessage::Error(failure)).await {
+                    break;
+                }
+            }
+            next = async { active.as_mut().expect("guarded by select condition").next().await }, if active.is_some() => {
+                let Some(message) = next else {
+                    active = None;
+                    continue;
+                };
+                if let StreamMessage::Content(content) = &message {
+                    if content.is_empty() {
+                        continue;
+                    }
+                }
+                let finished = matches!(message, StreamMessage::Complete | StreamMessage::Error(_));
+                if !send_frame(&mut socket, &message).await {
+                    break;
+                }
+                if finished {
+                    active = None;
+                }
+            }
+        }
+    }
+    if let Some(task) = opening {
+        task.abort();
+    }
+    log::info!("Chat WebSocket session {} closed", session_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tokens and origins are shared by the whole process, so tests setting them take turns
+    static SETTINGS_IN_USE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    async fn serve() -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router().into_make_service_with_connect_info::<SocketAddr>()).await;
+        });
+        address
+    }
+
+    async fn status_of(address: SocketAddr, path: &str, headers: &[(&str, &str)]) -> u16 {
+        let mut request = reqwest::Client::new().get(format!("http://{}{}", address, path));
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.send().await.unwrap().status().as_u16()
+    }
+
+    #[test]
+    fn events_from_locked_conversations_are_withheld() {
+        let hidden = |conversation_id: &str| conversation_id == "locked";
+        let started = |request_id: &str, conversation_id: &str| AppEvent::ChatStarted {
+            request_id: request_id.to_string(),
+            model: "m".to_string(),
+            conversation_id: conversation_id.to_string(),
+            message_id: "msg".to_string(),
+        };
+        let content = |request_id: &str| AppEvent::ChatContent { request_id: request_id.to_string(), content: "secret".to_string() };
+        let mut visible = HashSet::new();
+
+        assert!(withheld(&started("r1", "locked"), &mut visible, hidden));
+        assert!(withheld(&content("r1"), &mut visible, hidden));
+        assert!(!withheld(&started("r2", "open"), &mut visible, hidden));
+        assert!(!withheld(&content("r2"), &mut visible, hidden));
+        let completed = AppEvent::ChatCompleted { request_id: "r2".to_string(), model: "m".to_string(), response_chars: 6, tokens_per_second: None };
+        assert!(!withheld(&completed, &mut visible, hidden));
+        assert!(visible.is_empty());
+
+        // Answers to requests started before subscribing can't be placed, so they're held back
+        assert!(withheld(&content("r0"), &mut visible, hidden));
+        assert!(withheld(&AppEvent::UnreadChanged { conversation_id: "locked".to_string(), unread: 1 }, &mut visible, hidden));
+        assert!(!withheld(&AppEvent::ConversationLockChanged { conversation_id: "locked".to_string(), locked: true }, &mut visible, hidden));
+        assert!(!withheld(&AppEvent::ServerStopped, &mut visible, hidden));
+    }
+
+    #[tokio::test]
+    async fn websockets_need_an_allowed_origin_and_a_token() {
+        let _guard = SETTINGS_IN_USE.lock().await;
+        set_auth_enabled(false);
+        set_tokens(Vec::new());
+        set_allowed_origins(vec!["https://app.example.com".to_string()]);
+        let address = serve().await;
+
+        assert_eq!(status_of(address, "/ws/events", &[("origin", "https://evil.example")]).await, 403);
+        assert_eq!(status_of(address, "/ws/chat", &[("origin", "null")]).await, 403);
+        // No token exists yet, and the WebSocket endpoints still refuse to open without one
+        assert_eq!(status_of(address, "/ws/events", &[]).await, 401);
+        assert_eq!(status_of(address, "/ws/chat", &[("origin", "https://app.example.com")]).await, 401);
+        assert_eq!(status_of(address, "/health", &[("origin", "https://evil.example")]).await, 200);
+    }
+
+    #[tokio::test]
+    async fn revoking_the_last_token_keeps_requests_authenticated() {
+        let _guard = SETTINGS_IN_USE.lock().await;
+        set_allowed_origins(Vec::new());
+        let (token, secret) = ServerToken::issue("editor".to_string(), TokenScope::Admin);
+        set_auth_enabled(true);
+        set_tokens(vec![token]);
+        let address = serve().await;
+        let bearer = format!("Bearer {}", secret);
+        // A valid token gets past authentication to the upgrade, which a plain GET doesn't make
+        assert_ne!(status_of(address, "/ws/events", &[("authorization", &bearer)]).await, 401);
+
+        set_tokens(Vec::new());
+        assert_eq!(status_of(address, "/ws/events", &[("authorization", &bearer)]).await, 401);
+        assert_eq!(status_of(address, "/v1/models", &[("authorization", &bearer)]).await, 401);
+        assert_eq!(status_of(address, "/v1/models", &[]).await, 401);
+        assert!(status().auth_required);
+        set_auth_enabled(false);
+    }
+
+    #[test]
+    fn origins_are_matched_as_browsers_send_them() {
+        let network = server_auth::ServerNetworkConfig {
+            allowed_origins: vec!["HTTPS://App.Example.com/".to_string(), "http://localhost:5173".to_string()],
+            ..Default::default()
+        };
+        let origins = network.parsed_origins().unwrap();
+        assert_eq!(origins, ["https://app.example.com", "http://localhost:5173"]);
+        assert!(server_auth::is_origin_allowed(&origins, None));
+        assert!(server_auth::is_origin_allowed(&origins, Some("http://localhost:5173")));
+        assert!(!server_auth::is_origin_allowed(&origins, Some("http://localhost:5174")));
+        let path = server_auth::ServerNetworkConfig { allowed_origins: vec!["https://a.example/app".to_string()], ..Default::default() };
+        assert!(path.parsed_origins().is_err());
+    }
+}
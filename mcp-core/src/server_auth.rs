@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use ipnet::IpNet;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::pki_types::pem::PemObject;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use anyhow::Result;
+use ts_rs::TS;
+
+/// What a bearer token may do on the local API server
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum TokenScope {
+    /// /ws/chat and the OpenAI-compatible /v1 endpoints
+    Chat,
+    /// Every endpoint, including the event stream
+    Admin,
+}
+
+impl TokenScope {
+    pub fn allows(self, required: TokenScope) -> bool {
+        self == TokenScope::Admin || self == required
+    }
+}
+
+/// How chat requests made with a token are shaped, in place of the app's own settings, e.g. so
+/// a code editor's token always gets the coding persona on a cheaper model
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct RequestShaping {
+    /// Persona for every conversation the client has, in place of the active one
+    pub persona: Option<String>,
+    /// The only model the client's requests use, including for racing and drafting
+    pub model: Option<String>,
+    /// Tags recorded with every answer the client gets, alongside any its requests carry
+    pub metadata: BTreeMap<String, String>,
+    /// Tools one message mentioned, which the model is told to call; never part of a token's
+    /// shaping
+    #[serde(skip)]
+    #[ts(skip)]
+    pub tools: Vec<crate::plugins::PluginTool>,
+}
+
+impl RequestShaping {
+    /// Blank entries removed
+    pub fn trimmed(self) -> Self {
+        let trim = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        RequestShaping { persona: trim(self.persona), model: trim(self.model), metadata: self.metadata, tools: self.tools }
+    }
+}
+
+/// A token as persisted in config; only the SHA-256 of the secret is stored
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerToken {
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    pub token_hash: String,
+    pub created_at: String,
+    pub rotated_at: Option<String>,
+    #[serde(default)]
+    pub shaping: RequestShaping,
+    /// Set for tokens a companion device received by pairing
+    #[serde(default)]
+    pub paired_at: Option<String>,
+}
+
+/// Token metadata safe to show in the UI
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ServerTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    pub created_at: String,
+    pub rotated_at: Option<String>,
+    pub shaping: RequestShaping,
+}
+
+/// Returned when a token is created or rotated; the secret is never shown again
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct IssuedServerToken {
+    pub info: ServerTokenInfo,
+    pub token: String,
+}
+
+/// User-provided PEM files. Setting `client_ca_path` turns on mutual TLS.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ServerTlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+    pub client_ca_path: Option<String>,
+}
+
+/// Where the API server listens and which remote clients may connect.
+/// Loopback clients are always allowed; anyone else must match `allowlist`.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ServerNetworkConfig {
+    pub bind_address: String,
+    /// Client IPs (`192.168.1.20`) or subnets (`192.168.1.0/24`)
+    pub allowlist: Vec<String>,
+    /// Set once the user has accepted the LAN exposure warning for this bind address
+    pub lan_exposure_confirmed: bool,
+    /// Web page origins (`https://app.example.com`) allowed to open the WebSocket endpoints.
+    /// Browsers send the page's origin with every upgrade; native clients send none.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for ServerNetworkConfig {
+    fn default() -> Self {
+        ServerNetworkConfig {
+            bind_address: "127.0.0.1".to_string(),
+            allowlist: Vec::new(),
+            lan_exposure_confirmed: false,
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+/// Outcome of changing network settings; LAN bindings need a second, confirmed call
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct ServerNetworkUpdate {
+    pub applied: bool,
+    pub confirmation_required: bool,
+    pub warning: Option<String>,
+}
+
+impl ServerNetworkConfig {
+    pub fn bind_ip(&self) -> Result<IpAddr> {
+        self.bind_address.trim().parse()
+            .map_err(|_| anyhow::anyhow!("Invalid bind address: {}", self.bind_address))
+    }
+
+    /// True when the server would be reachable from other machines
+    pub fn is_lan_exposed(&self) -> Result<bool> {
+        Ok(!self.bind_ip()?.is_loopback())
+    }
+
+    pub fn parsed_allowlist(&self) -> Result<Vec<IpNet>> {
+        self.allowlist.iter().map(|entry| parse_allow_entry(entry)).collect()
+    }
+
+    /// The allowed origins as browsers send them: scheme, host and any port, lowercase
+    pub fn parsed_origins(&self) -> Result<Vec<String>> {
+        self.allowed_origins.iter().map(|origin| parse_origin(origin)).collect()
+    }
+}
+
+fn parse_origin(origin: &str) -> Result<String> {
+    let invalid = || anyhow::anyhow!("Invalid allowed origin: {}", origin);
+    let url = reqwest::Url::parse(origin.trim()).map_err(|_| invalid())?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() || !matches!(url.path(), "" | "/") {
+        return Err(invalid());
+    }
+    Ok(url.origin().ascii_serialization())
+}
+
+/// Whether a WebSocket upgrade sent with `origin` may go ahead. Browsers always send the page's
+/// origin, so without one the client isn't a web page; a page must be on `allowed`.
+pub fn is_origin_allowed(allowed: &[String], origin: Option<&str>) -> bool {
+    match origin {
+        None => true,
+        Some(origin) => allowed.iter().any(|allowed| allowed.eq_ignore_ascii_case(origin.trim())),
+    }
+}
+
+fn parse_allow_entry(entry: &str) -> Result<IpNet> {
+    let entry = entry.trim();
+    entry.parse::<IpNet>()
+        .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| anyhow::anyhow!("Invalid allowlist entry: {}", entry))
+}
+
+pub fn is_client_allowed(allowlist: &[IpNet], client: IpAddr) -> bool {
+    // IPv4 clients of a dual-stack listener show up as ::ffff:a.b.c.d
+    let client = match client {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(client),
+        v4 => v4,
+    };
+    client.is_loopback() || allowlist.iter().any(|net| net.contains(&client))
+}
+
+/// The text shown before the user confirms binding to a non-loopback address
+pub fn lan_exposure_warning(network: &ServerNetworkConfig, auth_enabled: bool, tls_enabled: bool) -> String {
+    let mut warning = format!(
+        "Binding to {} exposes the switchboard API to your network. Allowed clients: {}.",
+        network.bind_address,
+        network.allowlist.join(", ")
+    );
+    if !auth_enabled {
+        warning.push_str(" Authentication is off, so allowed clients can use the /v1 endpoints with your API key without a token.");
+    }
+    if !tls_enabled {
+        warning.push_str(" TLS is not configured, so traffic including prompts and tokens is sent in plain text.");
+    }
+    warning
+}
+
+impl ServerToken {
+    /// Create a token with a fresh secret, returning the stored form and the plaintext secret
+    pub fn issue(name: String, scope: TokenScope) -> (ServerToken, String) {
+        let secret = new_secret();
+        let token = ServerToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            scope,
+            token_hash: hash_token(&secret),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            rotated_at: None,
+            shaping: RequestShaping::default(),
+            paired_at: None,
+        };
+        (token, secret)
+    }
+
+    /// Replace the secret, keeping id, name and scope
+    pub fn rotate(&mut self) -> String {
+        let secret = new_secret();
+        self.token_hash = hash_token(&secret);
+        self.rotated_at = Some(chrono::Utc::now().to_rfc3339());
+        secret
+    }
+
+    pub fn info(&self) -> ServerTokenInfo {
+        ServerTokenInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            scope: self.scope,
+            created_at: self.created_at.clone(),
+            rotated_at: self.rotated_at.clone(),
+            shaping: self.shaping.clone(),
+        }
+    }
+}
+
+fn new_secret() -> String {
+    format!(
+        "mcps_{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+pub fn hash_token(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// The token matching `secret`, if any
+pub fn authenticate<'a>(tokens: &'a [ServerToken], secret: &str) -> Option<&'a ServerToken> {
+    let hash = hash_token(secret);
+    tokens.iter().find(|t| t.token_hash == hash)
+}
+
+/// SHA-256 of the server's leaf certificate as colon-separated hex, for clients to pin
+pub fn certificate_fingerprint(tls: &ServerTlsConfig) -> Result<String> {
+    let cert = CertificateDer::pem_file_iter(&tls.cert_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read certificate {}: {}", tls.cert_path, e))?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No certificate in {}", tls.cert_path))?
+        .map_err(|e| anyhow::anyhow!("Invalid certificate {}: {}", tls.cert_path, e))?;
+    Ok(Sha256::digest(cert.as_ref()).iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(":"))
+}
+
+/// Build the rustls server config, requiring client certificates when a client CA is set
+pub fn build_tls_config(tls: &ServerTlsConfig) -> Result<rustls::ServerConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let certs = CertificateDer::pem_file_iter(&tls.cert_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read certificate {}: {}", tls.cert_path, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("Invalid certificate {}: {}", tls.cert_path, e))?;
+    let key = PrivateKeyDer::from_pem_file(&tls.key_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read private key {}: {}", tls.key_path, e))?;
+
+    let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let builder = match &tls.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = rustls::RootCertStore::empty();
+            for cert in CertificateDer::pem_file_iter(ca_path)
+                .map_err(|e| anyhow::anyhow!("Failed to read client CA {}: {}", ca_path, e))?
+            {
+                roots.add(cert.map_err(|e| anyhow::anyhow!("Invalid client CA {}: {}", ca_path, e))?)?;
+            }
+            let verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid client CA {}: {}", ca_path, e))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder.with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
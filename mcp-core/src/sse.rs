@@ -0,0 +1,352 @@
+//! Server-sent event streams, read tolerantly. Providers that claim OpenAI compatibility still
+//! differ in what they stream: keep-alive comments, JSON split across network chunks or two
+//! objects in one event, finish reasons OpenAI never sends (`eos`, `end_turn`), errors sent as
+//! an ordinary event mid-answer, and a last event with no blank line after it. A strict parser
+//! fails the answer on the first of these; here each event is normalized into a chat chunk
+//! where it can be, skipped with a warning where it can't, and an error only when the provider
+//! reports one.
+
+use std::collections::VecDeque;
+use async_openai::config::Config;
+use async_openai::error::{ApiError, OpenAIError};
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionStreamResponse};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use crate::ProviderStream;
+
+/// One server-sent event
+#[derive(Debug, Default, PartialEq)]
+pub struct Event {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Splits a byte stream into events. Chunks may end anywhere, even inside a character, so
+/// bytes are held until the blank line that ends their event arrives.
+#[derive(Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    /// The events completed by `bytes`
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.buffer.extend(bytes.iter().filter(|byte| **byte != b'\r'));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|pair| pair == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            events.extend(parse_block(&block));
+        }
+        events
+    }
+
+    /// The event left when the stream ends without the blank line that should close it
+    pub fn finish(&mut self) -> Option<Event> {
+        let block = std::mem::take(&mut self.buffer);
+        parse_block(&block)
+    }
+}
+
+fn parse_block(block: &[u8]) -> Option<Event> {
+    let block = String::from_utf8_lossy(block);
+    let mut event = Event::default();
+    let mut data = Vec::new();
+    for line in block.lines() {
+        // Lines starting with a colon are comments, used as keep-alives
+        if let Some(name) = line.strip_prefix("event:") {
+            event.event = Some(name.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    if event.event.is_none() && data.is_empty() {
+        return None;
+    }
+    event.data = data.join("\n");
+    Some(event)
+}
+
+/// What an event of a chat completions stream amounts to
+#[derive(Debug)]
+pub enum ChatEvent {
+    Chunk(Box<CreateChatCompletionStreamResponse>),
+    Error(ApiError),
+    /// The stream's end, `[DONE]`
+    Done,
+    /// Nothing usable, and why
+    Skipped(String),
+}
+
+/// The chat events in `event`; usually one, more when a provider sent several objects at once
+pub fn chat_events(event: &Event) -> Vec<ChatEvent> {
+    let data = event.data.trim();
+    if data.is_empty() {
+        return Vec::new();
+    }
+    if data.eq_ignore_ascii_case("[DONE]") {
+        return vec![ChatEvent::Done];
+    }
+    let mut events = Vec::new();
+    for value in serde_json::Deserializer::from_str(data).into_iter::<Value>() {
+        match value {
+            Ok(value) => events.push(chat_event(event.event.as_deref(), value)),
+            Err(e) => {
+                events.push(ChatEvent::Skipped(format!("unreadable JSON ({}): {}", e, preview(data))));
+                break;
+            }
+        }
+    }
+    events
+}
+
+fn chat_event(name: Option<&str>, mut value: Value) -> ChatEvent {
+    if value.get("error").is_some_and(|error| !error.is_null()) || name == Some("error") {
+        return ChatEvent::Error(api_error(&value));
+    }
+    if !value["choices"].is_array() && value.get("usage").is_none() {
+        return ChatEvent::Skipped(format!("not a chat chunk: {}", preview(&value.to_string())));
+    }
+    normalize(&mut value);
+    match serde_json::from_value(value.clone()) {
+        Ok(chunk) => ChatEvent::Chunk(Box::new(chunk)),
+        Err(e) => ChatEvent::Skipped(format!("unexpected chunk shape ({}): {}", e, preview(&value.to_string()))),
+    }
+}
+
+/// Fill in what some providers leave out and map their values onto OpenAI's
+fn normalize(chunk: &mut Value) {
+    for (field, default) in [("id", json!("")), ("object", json!("chat.completion.chunk")), ("model", json!("")), ("choices", json!([]))] {
+        if chunk[field].is_null() {
+            chunk[field] = default;
+        }
+    }
+    if !chunk["created"].is_u64() {
+        chunk["created"] = json!(chrono::Utc::now().timestamp());
+    }
+    if let Some(choices) = chunk["choices"].as_array_mut() {
+        for (index, choice) in choices.iter_mut().enumerate() {
+            if !choice["index"].is_u64() {
+                choice["index"] = json!(index);
+            }
+            if !choice["delta"].is_object() {
+                // Completion-style chunks carry their text outside a delta
+                choice["delta"] = match choice["text"].as_str() {
+                    Some(text) => json!({ "content": text }),
+                    None => json!({}),
+                };
+            }
+            if let Some(role) = choice["delta"]["role"].as_str() {
+                if !matches!(role, "system" | "user" | "assistant" | "tool" | "function") {
+                    choice["delta"]["role"] = Value::Null;
+                }
+            }
+            choice["finish_reason"] = match choice["finish_reason"].as_str() {
+                Some(reason) if !reason.is_empty() => json!(finish_reason(reason)),
+                _ => Value::Null,
+            };
+        }
+    }
+    if let Some(usage) = chunk.get_mut("usage").filter(|usage| usage.is_object()) {
+        let prompt = usage["prompt_tokens"].as_u64().unwrap_or(0);
+        let completion = usage["completion_tokens"].as_u64().unwrap_or(0);
+        usage["prompt_tokens"] = json!(prompt);
+        usage["completion_tokens"] = json!(completion);
+        if !usage["total_tokens"].is_u64() {
+            usage["total_tokens"] = json!(prompt + completion);
+        }
+    }
+}
+
+/// The OpenAI finish reason closest to one a provider sent
+fn finish_reason(reason: &str) -> &'static str {
+    match reason.to_ascii_lowercase().as_str() {
+        "length" | "max_tokens" | "model_length" => "length",
+        "tool_calls" | "tool_use" => "tool_calls",
+        "function_call" => "function_call",
+        "content_filter" | "safety" | "refusal" | "recitation" => "content_filter",
+        // stop, eos, eos_token, end_turn, stop_sequence and anything new
+        _ => "stop",
+    }
+}
+
+/// The error in a provider's error body or error event
+pub fn api_error(body: &Value) -> ApiError {
+    let error = if body["error"].is_null() { body } else { &body["error"] };
+    let message = error.as_str()
+        .or_else(|| error["message"].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| error.to_string());
+    let code = match &error["code"] {
+        Value::String(code) => Some(code.clone()),
+        Value::Number(code) => Some(code.to_string()),
+        _ => None,
+    };
+    ApiError { message, r#type: error["type"].as_str().map(str::to_string), param: None, code }
+}
+
+fn preview(text: &str) -> String {
+    text.chars().take(120).collect()
+}
+
+/// Send `request` to the chat completions API `config` reaches and stream the reply, read with
+/// the tolerant parser above rather than async-openai's strict one
+pub async fn create_chat_stream(
+    http_client: reqwest::Client,
+    config: &impl Config,
+    request: &CreateChatCompletionRequest,
+) -> Result<ProviderStream, OpenAIError> {
+    let mut body = serde_json::to_value(request).map_err(OpenAIError::JSONDeserialize)?;
+    body["stream"] = json!(true);
+    let response = http_client
+        .post(config.url("/chat/completions"))
+        .query(&config.query())
+        .headers(config.headers())
+        .json(&body)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        log::warn!("Provider refused the request with {}", status);
+        let mut error = match serde_json::from_str::<Value>(&text) {
+            Ok(body) => api_error(&body),
+            Err(_) => ApiError { message: format!("{}: {}", status, preview(&text)), r#type: None, param: None, code: None },
+        };
+        // So failover can tell rate limits and server errors from rejected requests
+        error.code.get_or_insert_with(|| status.as_u16().to_string());
+        return Err(OpenAIError::ApiError(error));
+    }
+    Ok(read_chat_stream(response.bytes_stream()))
+}
+
+/// The chat chunks in an SSE byte stream, up to `[DONE]` or the stream's end
+pub fn read_chat_stream<B, E>(body: impl futures::Stream<Item = Result<B, E>> + Send + 'static) -> ProviderStream
+where
+    B: AsRef<[u8]>,
+    E: Into<OpenAIError>,
+{
+    struct Reader<S> {
+        body: S,
+        decoder: Decoder,
+        pending: VecDeque<Result<CreateChatCompletionStreamResponse, OpenAIError>>,
+        finished: bool,
+    }
+
+    impl<S> Reader<S> {
+        fn queue(&mut self, events: impl IntoIterator<Item = Event>) {
+            for event in events {
+                for parsed in chat_events(&event) {
+                    match parsed {
+                        ChatEvent::Chunk(chunk) => self.pending.push_back(Ok(*chunk)),
+                        ChatEvent::Error(e) => self.pending.push_back(Err(OpenAIError::ApiError(e))),
+                        ChatEvent::Skipped(reason) => log::warn!("Skipped a stream event: {}", reason),
+                        ChatEvent::Done => {
+                            self.finished = true;
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let reader = Reader { body: Box::pin(body), decoder: Decoder::default(), pending: VecDeque::new(), finished: false };
+    Box::pin(futures::stream::unfold(reader, |mut reader| async move {
+        loop {
+            if let Some(item) = reader.pending.pop_front() {
+                return Some((item, reader));
+            }
+            if reader.finished {
+                return None;
+            }
+            match reader.body.next().await {
+                Some(Ok(bytes)) => {
+                    let events = reader.decoder.push(bytes.as_ref());
+                    reader.queue(events);
+                }
+                Some(Err(e)) => {
+                    reader.finished = true;
+                    return Some((Err(e.into()), reader));
+                }
+                None => {
+                    let last = reader.decoder.finish();
+                    reader.queue(last);
+                    reader.finished = true;
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::types::FinishReason;
+
+    /// Captured streams, replayed in network chunks of `size` bytes
+    async fn replay(captured: &str, size: usize) -> Vec<Result<CreateChatCompletionStreamResponse, OpenAIError>> {
+        let chunks: Vec<Result<Vec<u8>, OpenAIError>> = captured.as_bytes().chunks(size).map(|chunk| Ok(chunk.to_vec())).collect();
+        read_chat_stream(futures::stream::iter(chunks)).collect().await
+    }
+
+    fn text(items: &[Result<CreateChatCompletionStreamResponse, OpenAIError>]) -> String {
+        items.iter()
+            .filter_map(|item| item.as_ref().ok())
+            .filter_map(|chunk| chunk.choices.first()?.delta.content.clone())
+            .collect()
+    }
+
+    #[test]
+    fn events_split_across_chunks_are_decoded_once_complete() {
+        let mut decoder = Decoder::default();
+        let stream = "event: ping\ndata: {\"type\": \"ping\"}\n\n: keep-alive\n\nevent: content_block_delta\r\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"h\u{e9}\"}}\r\n\r\n";
+        let bytes = stream.as_bytes();
+        // Split inside the two-byte é
+        let split = stream.find('\u{e9}').unwrap() + 1;
+        let first = decoder.push(&bytes[..split]);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].event.as_deref(), Some("ping"));
+        let second = decoder.push(&bytes[split..]);
+        assert_eq!(second.len(), 1);
+        assert!(second[0].data.contains("h\u{e9}"));
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[tokio::test]
+    async fn together_finish_reasons_are_mapped() {
+        for size in [7, 64, 4096] {
+            let items = replay(include_str!("../fixtures/sse/together-eos.sse"), size).await;
+            assert!(items.iter().all(Result::is_ok));
+            assert_eq!(text(&items), "Hello there!");
+            let last = items.last().unwrap().as_ref().unwrap();
+            assert_eq!(last.choices[0].finish_reason, Some(FinishReason::Stop));
+            assert_eq!(last.usage.as_ref().map(|usage| usage.total_tokens), Some(15));
+        }
+    }
+
+    #[tokio::test]
+    async fn keep_alives_and_mid_answer_errors() {
+        let items = replay(include_str!("../fixtures/sse/openrouter-keepalive-error.sse"), 13).await;
+        assert_eq!(text(&items), "The answer");
+        let error = items.last().unwrap().as_ref().unwrap_err();
+        assert!(matches!(error, OpenAIError::ApiError(e) if e.message == "Provider returned error" && e.code.as_deref() == Some("502")));
+    }
+
+    #[tokio::test]
+    async fn malformed_events_are_skipped_not_fatal() {
+        let items = replay(include_str!("../fixtures/sse/malformed.sse"), 5).await;
+        assert!(items.iter().all(Result::is_ok));
+        // A truncated object and a non-chunk are skipped; two objects in one event are both
+        // kept, and the last event arrives without its closing blank line
+        assert_eq!(text(&items), "one two three four");
+        let last = items.last().unwrap().as_ref().unwrap();
+        assert_eq!(last.choices[0].finish_reason, Some(FinishReason::Length));
+    }
+
+    #[test]
+    fn nothing_follows_done() {
+        let done = Event { event: None, data: " [DONE] ".to_string() };
+        assert!(matches!(chat_events(&done)[..], [ChatEvent::Done]));
+        assert!(chat_events(&Event::default()).is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+//! Saved conversation starters: the system prompt, first message, model and attachments to begin
+//! a conversation with. Where a prompt template fills in a single message, a starter sets up the
+//! whole conversation, and is saved from one the user already had.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::history::MessageDetails;
+use crate::storage::Attachment;
+use crate::tr;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ConversationStarter {
+    pub name: String,
+    /// Sent instead of the active persona's prompt
+    pub system_prompt: Option<String>,
+    pub first_message: String,
+    /// None to use the preferred model
+    pub model: Option<String>,
+    pub attachment_ids: Vec<String>,
+    pub created_at: String,
+}
+
+/// A new conversation set up from a starter; nothing has been sent yet
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct StartedConversation {
+    pub conversation_id: String,
+    /// For the UI to put in the composer, where the user can edit it before sending
+    pub first_message: String,
+    /// The starter's attachments, less any deleted since it was saved
+    pub attachments: Vec<Attachment>,
+}
+
+/// What a conversation started from a starter uses in place of the global settings
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversationSetup {
+    pub starter_name: String,
+    pub system_prompt: Option<String>,
+    pub model: Option<String>,
+}
+
+/// `name` trimmed, or a localized error if nothing is left
+pub fn starter_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(tr!("starter-name-required"));
+    }
+    Ok(name.to_string())
+}
+
+/// The persona a response was generated with, from the context sources it recorded
+pub fn persona_in(details: &MessageDetails) -> Option<&str> {
+    details.context_sources.iter().find_map(|source| source.strip_prefix("persona "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_persona_and_trims_names() {
+        let details = MessageDetails {
+            message_id: "m".to_string(),
+            provider: "demo".to_string(),
+            model: "model-a".to_string(),
+            context_sources: vec!["2 memories".to_string(), "persona reviewer".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(persona_in(&details), Some("reviewer"));
+        assert_eq!(starter_name("  Code review "), Ok("Code review".to_string()));
+        assert!(starter_name("   ").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::config::ConfigManager;
+use crate::events::{self, AppEvent};
+
+/// Time after launch left to the window before subsystems are warmed in the background
+const IDLE_DELAY: Duration = Duration::from_millis(1500);
+
+/// Parts of the app started after the window rather than before it, in the order they are warmed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, TS)]
+#[ts(export)]
+pub enum Subsystem {
+    /// User scripts with message and tool-call hooks
+    Scripts,
+    /// WASM plugins and the tools they register
+    Plugins,
+    /// Manifests of third-party services
+    Integrations,
+    /// Template, backup, sync, eval and provider health background loops, and trace export
+    Schedules,
+    /// The provider's model list, along with context lengths and pricing
+    Models,
+}
+
+const SUBSYSTEMS: [Subsystem; 5] = [
+    Subsystem::Scripts,
+    Subsystem::Plugins,
+    Subsystem::Integrations,
+    Subsystem::Schedules,
+    Subsystem::Models,
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum SubsystemState {
+    Pending,
+    Starting,
+    Ready,
+    /// Starting failed; the next use tries again
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct SubsystemStatus {
+    pub subsystem: Subsystem,
+    pub state: SubsystemState,
+    pub elapsed_ms: Option<u32>,
+    pub error: Option<String>,
+}
+
+fn statuses() -> &'static Mutex<BTreeMap<Subsystem, SubsystemStatus>> {
+    static STATUSES: OnceLock<Mutex<BTreeMap<Subsystem, SubsystemStatus>>> = OnceLock::new();
+    STATUSES.get_or_init(|| {
+        let statuses = SUBSYSTEMS.iter()
+            .map(|&subsystem| (subsystem, SubsystemStatus { subsystem, state: SubsystemState::Pending, elapsed_ms: None, error: None }))
+            .collect();
+        Mutex::new(statuses)
+    })
+}
+
+/// Held while a subsystem starts, so the idle warm-up and a first use don't start it twice.
+/// Indexed by position in SUBSYSTEMS.
+static STARTING: [tokio::sync::Mutex<()>; SUBSYSTEMS.len()] = [const { tokio::sync::Mutex::const_new(()) }; SUBSYSTEMS.len()];
+
+fn set_status(subsystem: Subsystem, state: SubsystemState, elapsed_ms: Option<u32>, error: Option<String>) {
+    if let Ok(mut statuses) = statuses().lock() {
+        statuses.insert(subsystem, SubsystemStatus { subsystem, state, elapsed_ms, error });
+    }
+}
+
+fn state_of(subsystem: Subsystem) -> SubsystemState {
+    statuses().lock().ok()
+        .and_then(|statuses| statuses.get(&subsystem).map(|status| status.state))
+        .unwrap_or(SubsystemState::Pending)
+}
+
+pub fn status() -> Vec<SubsystemStatus> {
+    statuses().lock().map(|statuses| statuses.values().cloned().collect()).unwrap_or_default()
+}
+
+/// Start `subsystem` now if it hasn't been yet. Failures are recorded and published rather than
+/// returned, since callers carry on without the subsystem.
+pub async fn ensure(subsystem: Subsystem) {
+    if state_of(subsystem) == SubsystemState::Ready {
+        return;
+    }
+    let _starting = STARTING[subsystem as usize].lock().await;
+    if state_of(subsystem) == SubsystemState::Ready {
+        return;
+    }
+
+    set_status(subsystem, SubsystemState::Starting, None, None);
+    let started = Instant::now();
+    let result = start(subsystem).await;
+    let elapsed_ms = started.elapsed().as_millis() as u32;
+    match result {
+        Ok(()) => {
+            log::info!("{:?} ready after {} ms", subsystem, elapsed_ms);
+            set_status(subsystem, SubsystemState::Ready, Some(elapsed_ms), None);
+            events::publish(AppEvent::SubsystemReady { subsystem, elapsed_ms });
+        }
+        Err(error) => {
+            log::error!("Failed to start {:?}: {}", subsystem, error);
+            set_status(subsystem, SubsystemState::Failed, Some(elapsed_ms), Some(error.clone()));
+            events::publish(AppEvent::SubsystemFailed { subsystem, error });
+        }
+    }
+}
+
+async fn start(subsystem: Subsystem) -> Result<(), String> {
+    match subsystem {
+        // The list commands load on first call and reuse what's loaded after
+        Subsystem::Scripts => crate::list_scripts().await.map(|_| ()),
+        Subsystem::Plugins => crate::list_plugins().await.map(|_| ()),
+        Subsystem::Integrations => crate::list_integrations().await.map(|_| ()),
+        Subsystem::Schedules => {
+            tokio::spawn(crate::run_template_sync_schedule());
+            tokio::spawn(crate::run_backup_schedule());
+            tokio::spawn(crate::run_sync_schedule());
+            tokio::spawn(crate::run_eval_schedule());
+            tokio::spawn(crate::run_maintenance_schedule());
+            tokio::spawn(crate::run_health_schedule());
+            tokio::spawn(crate::telemetry::run_export());
+            Ok(())
+        }
+        Subsystem::Models => {
+            // Nothing to fetch until the user has entered a key
+            let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
+            if !config_manager.has_config() {
+                return Ok(());
+            }
+            crate::get_available_models().await.map(|_| ())
+        }
+    }
+}
+
+/// Warm every subsystem once the window has had IDLE_DELAY to itself; spawn once at startup.
+/// Anything used before then starts on first use instead.
+/// Under the low-power profile only the background loops are started; the rest wait for their
+/// first use.
+pub async fn run() {
+    tokio::time::sleep(IDLE_DELAY).await;
+    let low_power = crate::low_power::active().await;
+    if low_power {
+        log::info!("Low-power profile is on, leaving subsystems to start on first use");
+    }
+    for subsystem in SUBSYSTEMS {
+        if low_power && subsystem != Subsystem::Schedules {
+            continue;
+        }
+        ensure(subsystem).await;
+    }
+}
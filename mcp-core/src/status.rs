@@ -0,0 +1,191 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::config::ConfigManager;
+use crate::events::{self, AppEvent};
+use crate::generation::GenerationMode;
+use crate::modes::{self, AppMode};
+use crate::startup::SubsystemState;
+
+/// How long after a rate-limit rejection the provider is still reported as limited
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct RateLimitState {
+    /// The provider rejected a request for rate limiting within the last minute
+    pub limited: bool,
+    /// Seconds until `limited` clears, while it's set
+    pub cooldown_secs: Option<u32>,
+    /// Rate-limit rejections since startup
+    pub hits: u32,
+    pub last_hit_at: Option<String>,
+}
+
+/// Work waiting or in progress in the background
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct QueuedJobs {
+    pub exports: u32,
+    /// History writes held while another instance has the database
+    pub history_writes: u32,
+    /// Deferred subsystems not started yet
+    pub pending_subsystems: u32,
+}
+
+/// Everything the status bar shows, in one call
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct StatusSnapshot {
+    pub app_mode: AppMode,
+    /// The current mode's status bar hint
+    pub mode_hint: String,
+    pub mode: GenerationMode,
+    pub provider: String,
+    pub model: String,
+    /// Loaded plugins that provide tools. There are no external MCP server connections yet, so
+    /// these are the only tool sources.
+    pub tool_providers: Vec<String>,
+    pub api_server_running: bool,
+    pub history_read_only: bool,
+    pub jobs: QueuedJobs,
+    pub rate_limit: RateLimitState,
+    /// Configured providers whose last health check found them degraded or down
+    pub unhealthy_providers: Vec<String>,
+    /// Answers completed in conversations that weren't on screen, for the tray and status bar badge
+    pub unread: u32,
+}
+
+struct RateLimitHits {
+    hits: u32,
+    last_hit: Option<(Instant, String)>,
+}
+
+static RATE_LIMIT: Mutex<RateLimitHits> = Mutex::new(RateLimitHits { hits: 0, last_hit: None });
+
+/// Last snapshot published as StatusChanged, so unchanged snapshots aren't sent again
+static LAST: Mutex<Option<StatusSnapshot>> = Mutex::new(None);
+
+pub fn is_rate_limited(error: &str) -> bool {
+    let error = error.to_lowercase();
+    ["rate limit", "rate_limit", "too many requests", "429"].iter().any(|pattern| error.contains(pattern))
+}
+
+/// Note a failed provider request; rate-limit rejections update the status, and it's
+/// refreshed again when the cooldown is over
+pub fn record_provider_error(error: &str) {
+    if !is_rate_limited(error) {
+        return;
+    }
+    log::warn!("Provider is rate limiting requests: {}", error);
+    if let Ok(mut rate_limit) = RATE_LIMIT.lock() {
+        rate_limit.hits += 1;
+        rate_limit.last_hit = Some((Instant::now(), chrono::Utc::now().to_rfc3339()));
+    }
+    refresh();
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        runtime.spawn(async {
+            tokio::time::sleep(RATE_LIMIT_COOLDOWN).await;
+            refresh();
+        });
+    }
+}
+
+fn rate_limit_state() -> RateLimitState {
+    let Ok(rate_limit) = RATE_LIMIT.lock() else {
+        return RateLimitState { limited: false, cooldown_secs: None, hits: 0, last_hit_at: None };
+    };
+    let remaining = rate_limit.last_hit.as_ref()
+        .and_then(|(at, _)| RATE_LIMIT_COOLDOWN.checked_sub(at.elapsed()))
+        .filter(|remaining| !remaining.is_zero());
+    RateLimitState {
+        limited: remaining.is_some(),
+        cooldown_secs: remaining.map(|remaining| remaining.as_secs_f64().ceil() as u32),
+        hits: rate_limit.hits,
+        last_hit_at: rate_limit.last_hit.as_ref().map(|(_, at)| at.clone()),
+    }
+}
+
+pub fn snapshot() -> anyhow::Result<StatusSnapshot> {
+    let config_manager = ConfigManager::new()?;
+    let database = crate::db_lock::status(config_manager.get_data_dir())?;
+    let tool_providers = crate::plugins::loaded_plugins().unwrap_or_default().into_iter()
+        .filter(|plugin| !plugin.tools.is_empty())
+        .map(|plugin| plugin.name)
+        .collect();
+    let pending_subsystems = crate::startup::status().iter()
+        .filter(|status| status.state != SubsystemState::Ready)
+        .count() as u32;
+    let app_mode = config_manager.get_mode()?;
+    let model = if crate::demo::active() { crate::demo::MODEL.to_string() } else { config_manager.get_preferred_model()? };
+    Ok(StatusSnapshot {
+        app_mode,
+        mode_hint: modes::behavior(app_mode).status_hint,
+        mode: config_manager.get_generation_settings()?.effective_mode(),
+        provider: if crate::demo::active() { crate::demo::PROVIDER.to_string() } else { crate::providers::resolve(&model).0.name().to_string() },
+        model,
+        tool_providers,
+        api_server_running: crate::server::status().running,
+        history_read_only: database.read_only,
+        jobs: QueuedJobs {
+            exports: crate::export::running_count(),
+            history_writes: database.queued_writes,
+            pending_subsystems,
+        },
+        rate_limit: rate_limit_state(),
+        unhealthy_providers: crate::health::unhealthy(),
+        unread: crate::history::HistoryStore::open(config_manager.get_data_dir())?.unread_total()?,
+    })
+}
+
+/// Take a new snapshot and publish StatusChanged if it differs from the last one
+pub fn refresh() {
+    let snapshot = match snapshot() {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            log::warn!("Failed to take status snapshot: {}", e);
+            return;
+        }
+    };
+    let Ok(mut last) = LAST.lock() else { return };
+    if last.as_ref() == Some(&snapshot) {
+        return;
+    }
+    *last = Some(snapshot.clone());
+    drop(last);
+    events::publish(AppEvent::StatusChanged { status: snapshot });
+}
+
+/// Refresh the status whenever an event that can change it is published; spawn once at startup
+pub async fn watch() {
+    let mut events = events::subscribe();
+    refresh();
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if affects_status(&event) {
+                    refresh();
+                }
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => refresh(),
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+fn affects_status(event: &AppEvent) -> bool {
+    matches!(
+        event,
+        AppEvent::ExportStarted { .. }
+            | AppEvent::DatabaseLocked
+            | AppEvent::DatabaseUnlocked { .. }
+            | AppEvent::SubsystemReady { .. }
+            | AppEvent::SubsystemFailed { .. }
+            | AppEvent::PluginsReloaded { .. }
+            | AppEvent::ServerStarted { .. }
+            | AppEvent::ServerStopped
+            | AppEvent::UnreadChanged { .. }
+            | AppEvent::ProviderHealthChanged { .. }
+    )
+}
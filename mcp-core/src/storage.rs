@@ -0,0 +1,316 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::history::HistoryStore;
+
+/// Data directory holding attachment files, named by content hash
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+/// Resolve the per-conversation subdirectory of a data root (e.g. `transcripts/<id>`).
+/// Conversation ids become directory names, so anything path-like is refused.
+pub fn conversation_dir(root: &Path, conversation_id: &str) -> Result<PathBuf> {
+    if conversation_id.is_empty() || conversation_id.contains(['/', '\\']) || conversation_id.contains("..") {
+        return Err(anyhow::anyhow!("Invalid conversation id: {}", conversation_id));
+    }
+    Ok(root.join(conversation_id))
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export)]
+pub enum AttachmentKind {
+    Image,
+    Audio,
+    Video,
+    File,
+}
+
+impl AttachmentKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttachmentKind::Image => "image",
+            AttachmentKind::Audio => "audio",
+            AttachmentKind::Video => "video",
+            AttachmentKind::File => "file",
+        }
+    }
+
+    /// Guess the kind from a file extension
+    pub fn from_path(path: &Path) -> AttachmentKind {
+        const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg"];
+        match crate::transcription::media_kind(path) {
+            Some("audio") => AttachmentKind::Audio,
+            Some("video") => AttachmentKind::Video,
+            _ if path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str())) => AttachmentKind::Image,
+            _ => AttachmentKind::File,
+        }
+    }
+
+    pub fn parse(value: &str) -> AttachmentKind {
+        match value {
+            "image" => AttachmentKind::Image,
+            "audio" => AttachmentKind::Audio,
+            "video" => AttachmentKind::Video,
+            _ => AttachmentKind::File,
+        }
+    }
+}
+
+/// A stored file. Identical content is stored once, however many conversations refer to it.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct Attachment {
+    /// SHA-256 of the content
+    pub id: String,
+    pub kind: AttachmentKind,
+    /// Name of the file it was imported from
+    pub file_name: String,
+    /// Absolute path of the stored copy
+    pub path: String,
+    pub bytes: u64,
+    /// Number of conversations and starters referring to it; 0 means it can be cleaned up
+    pub references: u32,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct KindUsage {
+    pub kind: AttachmentKind,
+    pub count: u32,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct StorageUsage {
+    pub attachments: Vec<KindUsage>,
+    pub history_bytes: u64,
+    /// Transcripts, capture metadata and other app data outside the attachment store
+    pub other_bytes: u64,
+    /// Attachments no conversation refers to, plus files missing from the manifest
+    pub orphaned_count: u32,
+    pub orphaned_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct CleanupSummary {
+    pub files_removed: u32,
+    pub bytes_freed: u64,
+    /// Manifest entries dropped because their file had disappeared
+    pub missing_entries: u32,
+}
+
+/// Content-addressed attachment files under `<data_dir>/attachments`, with the manifest and
+/// conversation references kept in the history database
+pub struct AttachmentStore {
+    data_dir: PathBuf,
+    root: PathBuf,
+}
+
+impl AttachmentStore {
+    pub fn new(data_dir: &Path) -> Self {
+        AttachmentStore {
+            data_dir: data_dir.to_path_buf(),
+            root: data_dir.join(ATTACHMENTS_DIR),
+        }
+    }
+
+    /// Copy `source` into the store and refer to it from `conversation_id`
+    pub fn import(&self, store: &HistoryStore, source: &Path, conversation_id: &str, kind: AttachmentKind) -> Result<Attachment> {
+        self.add(store, source, conversation_id, kind, false)
+    }
+
+    /// Like `import`, but moves `source` into the store instead of copying it
+    pub fn adopt(&self, store: &HistoryStore, source: &Path, conversation_id: &str, kind: AttachmentKind) -> Result<Attachment> {
+        self.add(store, source, conversation_id, kind, true)
+    }
+
+    fn add(&self, store: &HistoryStore, source: &Path, conversation_id: &str, kind: AttachmentKind, take: bool) -> Result<Attachment> {
+        conversation_dir(&self.root, conversation_id)?;
+        let (bytes, id) = digest_file(source)?;
+        let path = self.root.join(match source.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => format!("{}.{}", id, ext.to_lowercase()),
+            None => id.clone(),
+        });
+
+        std::fs::create_dir_all(&self.root)?;
+        if path.exists() {
+            if take {
+                std::fs::remove_file(source)?;
+            }
+        } else if !(take && std::fs::rename(source, &path).is_ok()) {
+            // Copy when importing, or when a move would cross filesystems
+            let partial = path.with_extension("partial");
+            std::fs::copy(source, &partial)?;
+            std::fs::rename(&partial, &path)?;
+            if take {
+                std::fs::remove_file(source)?;
+            }
+        }
+
+        let file_name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| id.clone());
+        let stored_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        store.insert_attachment(&Attachment {
+            id: id.clone(),
+            kind,
+            file_name,
+            path: stored_name,
+            bytes,
+            references: 0,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        })?;
+        store.add_attachment_reference(&id, conversation_id)?;
+        log::info!("Stored attachment {} for conversation {}", path.display(), conversation_id);
+        self.get(store, &id)?.ok_or_else(|| anyhow::anyhow!("Attachment {} not found", id))
+    }
+
+    pub fn get(&self, store: &HistoryStore, id: &str) -> Result<Option<Attachment>> {
+        Ok(store.attachment(id)?.map(|a| self.resolve(a)))
+    }
+
+    pub fn list(&self, store: &HistoryStore) -> Result<Vec<Attachment>> {
+        Ok(store.list_attachments()?.into_iter().map(|a| self.resolve(a)).collect())
+    }
+
+    /// The manifest stores names relative to the store so the data directory can move
+    fn resolve(&self, attachment: Attachment) -> Attachment {
+        Attachment { path: self.root.join(&attachment.path).to_string_lossy().to_string(), ..attachment }
+    }
+
+    pub fn usage(&self, store: &HistoryStore) -> Result<StorageUsage> {
+        let attachments = self.list(store)?;
+        let mut by_kind: Vec<KindUsage> = Vec::new();
+        for attachment in &attachments {
+            match by_kind.iter_mut().find(|usage| usage.kind == attachment.kind) {
+                Some(usage) => {
+                    usage.count += 1;
+                    usage.bytes += attachment.bytes;
+                }
+                None => by_kind.push(KindUsage { kind: attachment.kind, count: 1, bytes: attachment.bytes }),
+            }
+        }
+
+        let orphaned: Vec<&Attachment> = unreferenced(&attachments).collect();
+        let unlisted = self.unlisted_files(&attachments)?;
+        let orphaned_bytes = orphaned.iter().map(|a| a.bytes).sum::<u64>()
+            + unlisted.iter().map(|(_, bytes)| bytes).sum::<u64>();
+
+        let history_bytes = std::fs::read_dir(&self.data_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("history.db"))
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum::<u64>();
+        let total_bytes = dir_size(&self.data_dir)?;
+        let attachment_bytes = dir_size(&self.root)?;
+        Ok(StorageUsage {
+            attachments: by_kind,
+            history_bytes,
+            other_bytes: total_bytes.saturating_sub(history_bytes + attachment_bytes),
+            orphaned_count: (orphaned.len() + unlisted.len()) as u32,
+            orphaned_bytes,
+            total_bytes,
+        })
+    }
+
+    /// Delete attachments no conversation refers to and files the manifest doesn't know about,
+    /// and drop manifest entries whose file is gone
+    pub fn cleanup(&self, store: &HistoryStore) -> Result<CleanupSummary> {
+        let attachments = self.list(store)?;
+        let mut summary = CleanupSummary { files_removed: 0, bytes_freed: 0, missing_entries: 0 };
+        for attachment in attachments.iter().filter(|a| !Path::new(&a.path).exists()) {
+            store.delete_attachment(&attachment.id)?;
+            summary.missing_entries += 1;
+        }
+
+        for attachment in unreferenced(&attachments) {
+            std::fs::remove_file(&attachment.path)?;
+            store.delete_attachment(&attachment.id)?;
+            summary.files_removed += 1;
+            summary.bytes_freed += attachment.bytes;
+        }
+        for (path, bytes) in self.unlisted_files(&attachments)? {
+            std::fs::remove_file(&path)?;
+            summary.files_removed += 1;
+            summary.bytes_freed += bytes;
+        }
+        log::info!(
+            "Attachment cleanup removed {} files ({} bytes) and {} missing entries",
+            summary.files_removed, summary.bytes_freed, summary.missing_entries
+        );
+        Ok(summary)
+    }
+
+    /// Files in the store with no manifest entry, with their sizes
+    fn unlisted_files(&self, attachments: &[Attachment]) -> Result<Vec<(PathBuf, u64)>> {
+        let mut unlisted = Vec::new();
+        if !self.root.exists() {
+            return Ok(unlisted);
+        }
+        let known: std::collections::HashSet<PathBuf> = attachments.iter().map(|a| PathBuf::from(&a.path)).collect();
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_file() && !known.contains(&path) && !is_recent_partial(&path) {
+                unlisted.push((path, entry.metadata()?.len()));
+            }
+        }
+        Ok(unlisted)
+    }
+}
+
+/// Attachments no conversation refers to whose file still exists
+fn unreferenced(attachments: &[Attachment]) -> impl Iterator<Item = &Attachment> {
+    attachments.iter().filter(|a| a.references == 0 && Path::new(&a.path).exists())
+}
+
+/// A copy that may still be in progress
+fn is_recent_partial(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "partial")
+        && std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age.as_secs() < 3600)
+}
+
+fn dir_size(dir: &Path) -> Result<u64> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut total = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            total += dir_size(&entry.path())?;
+        } else {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Size and SHA-256 of a file
+pub fn digest_file(path: &Path) -> Result<(u64, String)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
@@ -0,0 +1,241 @@
+//! Stop sequences and content filters set per conversation, checked against the answer as it
+//! streams rather than left to the provider. The stream is cut the moment one matches: nothing
+//! from the match on reaches the UI, the request is dropped, and the message is kept as truncated.
+//!
+//! Text that could be the start of a stop sequence is held back until the next chunk settles it,
+//! so a stop sequence is never shown even when it arrives split across tokens. Content filters are
+//! regular expressions and can only be judged once they match, so when one matches text already
+//! shown, the answer is replaced with the text before it.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, OnceLock};
+use futures::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::generation::MessageStream;
+use crate::StreamMessage;
+use crate::tr;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct ConversationFilters {
+    /// Literal text that ends the answer where it appears
+    pub stop_sequences: Vec<String>,
+    /// Regular expressions that end the answer where they match
+    pub content_filters: Vec<String>,
+}
+
+impl ConversationFilters {
+    pub fn is_empty(&self) -> bool {
+        self.stop_sequences.is_empty() && self.content_filters.is_empty()
+    }
+}
+
+/// `filters` without empty entries, or a localized error naming the first invalid pattern
+pub fn validate(mut filters: ConversationFilters) -> Result<ConversationFilters, String> {
+    filters.stop_sequences.retain(|stop| !stop.is_empty());
+    filters.content_filters.retain(|pattern| !pattern.trim().is_empty());
+    for pattern in &filters.content_filters {
+        if let Err(e) = Regex::new(pattern) {
+            return Err(tr!("filter-invalid-pattern", pattern = pattern.as_str(), error = e.to_string()));
+        }
+    }
+    Ok(filters)
+}
+
+/// Checks streamed text against a conversation's filters and passes on what may be shown
+pub struct StreamFilter {
+    stop_sequences: Vec<String>,
+    patterns: Vec<(String, Regex)>,
+    text: String,
+    /// Bytes of `text` passed on so far
+    sent: usize,
+    truncated_by: Option<String>,
+}
+
+impl StreamFilter {
+    pub fn new(filters: &ConversationFilters) -> Self {
+        let patterns = filters.content_filters.iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some((pattern.clone(), regex)),
+                Err(e) => {
+                    log::warn!("Ignoring invalid content filter {:?}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        StreamFilter {
+            stop_sequences: filters.stop_sequences.iter().filter(|stop| !stop.is_empty()).cloned().collect(),
+            patterns,
+            text: String::new(),
+            sent: 0,
+            truncated_by: None,
+        }
+    }
+
+    /// What cut the answer short, e.g. `stop sequence "END"`; once set, nothing more is accepted
+    pub fn truncated_by(&self) -> Option<&str> {
+        self.truncated_by.as_deref()
+    }
+
+    /// Add a streamed chunk and return what can be shown now
+    pub fn push(&mut self, chunk: &str) -> Option<StreamMessage> {
+        if self.truncated_by.is_some() {
+            return None;
+        }
+        self.text.push_str(chunk);
+        if self.cut() && self.sent > self.text.len() {
+            self.sent = self.text.len();
+            return Some(StreamMessage::Replace(self.text.clone()));
+        }
+        let safe = self.text.len() - self.held_back();
+        self.release(safe)
+    }
+
+    /// Start over with `text` as the whole answer so far
+    pub fn replace(&mut self, text: String) -> StreamMessage {
+        self.text = text;
+        self.cut();
+        self.sent = self.text.len() - self.held_back();
+        StreamMessage::Replace(self.text[..self.sent].to_string())
+    }
+
+    /// Whatever is held back, for when no more text is coming
+    pub fn flush(&mut self) -> Option<StreamMessage> {
+        self.release(self.text.len())
+    }
+
+    fn release(&mut self, to: usize) -> Option<StreamMessage> {
+        if to <= self.sent {
+            return None;
+        }
+        let content = self.text[self.sent..to].to_string();
+        self.sent = to;
+        Some(StreamMessage::Content(content))
+    }
+
+    /// Truncate the text before the earliest match, if any; returns whether there was one
+    fn cut(&mut self) -> bool {
+        // Held-back text means a stop sequence can't begin in what was already sent
+        let from = self.sent.min(self.text.len());
+        let stops = self.stop_sequences.iter().filter_map(|stop| {
+            self.text[from..].find(stop.as_str()).map(|at| (from + at, format!("stop sequence {:?}", stop)))
+        });
+        let filters = self.patterns.iter().filter_map(|(pattern, regex)| {
+            regex.find(&self.text).map(|found| (found.start(), format!("content filter {:?}", pattern)))
+        });
+        let Some((at, reason)) = stops.chain(filters).min_by_key(|(at, _)| *at) else {
+            return false;
+        };
+        log::info!("Cutting answer short at byte {}: matched {}", at, reason);
+        self.text.truncate(at);
+        self.truncated_by = Some(reason);
+        true
+    }
+
+    /// Length of the longest end of the text that could be the start of a stop sequence
+    fn held_back(&self) -> usize {
+        if self.truncated_by.is_some() {
+            return 0;
+        }
+        self.stop_sequences.iter()
+            .flat_map(|stop| (1..stop.len()).filter(|&len| stop.is_char_boundary(len)).map(move |len| &stop[..len]))
+            .filter(|prefix| self.text.ends_with(prefix))
+            .map(str::len)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Set to what cut the answer short, if a filter did
+pub type Truncation = Arc<OnceLock<String>>;
+
+struct Filtering {
+    stream: Option<MessageStream>,
+    filter: StreamFilter,
+    ready: VecDeque<StreamMessage>,
+    truncation: Truncation,
+}
+
+/// Apply `filters` to the Content and Replace messages of `stream`. Once one matches, the
+/// underlying stream is dropped and the returned `Truncation` says why.
+pub fn apply(stream: MessageStream, filters: &ConversationFilters) -> (MessageStream, Truncation) {
+    let truncation = Truncation::default();
+    let filtering = Filtering {
+        stream: Some(stream),
+        filter: StreamFilter::new(filters),
+        ready: VecDeque::new(),
+        truncation: truncation.clone(),
+    };
+    let stream = Box::pin(futures::stream::unfold(filtering, |mut filtering| async move {
+        loop {
+            if let Some(message) = filtering.ready.pop_front() {
+                return Some((message, filtering));
+            }
+            match filtering.stream.as_mut()?.next().await {
+                Some(StreamMessage::Content(content)) => filtering.ready.extend(filtering.filter.push(&content)),
+                Some(StreamMessage::Replace(text)) => filtering.ready.push_back(filtering.filter.replace(text)),
+                // Held-back text may still turn out to be a stop sequence
+                Some(usage @ StreamMessage::Usage { .. }) => filtering.ready.push_back(usage),
+                Some(message) => {
+                    filtering.ready.extend(filtering.filter.flush());
+                    filtering.ready.push_back(message);
+                }
+                None => {
+                    filtering.stream = None;
+                    filtering.ready.extend(filtering.filter.flush());
+                }
+            }
+            if let Some(reason) = filtering.filter.truncated_by() {
+                let _ = filtering.truncation.set(reason.to_string());
+                filtering.stream = None;
+            }
+        }
+    }));
+    (stream, truncation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shown(filter: &mut StreamFilter, chunks: &[&str]) -> String {
+        let mut text = String::new();
+        for chunk in chunks {
+            match filter.push(chunk) {
+                Some(StreamMessage::Content(content)) => text.push_str(&content),
+                Some(StreamMessage::Replace(replacement)) => text = replacement,
+                _ => {}
+            }
+        }
+        if let Some(StreamMessage::Content(content)) = filter.flush() {
+            text.push_str(&content);
+        }
+        text
+    }
+
+    #[test]
+    fn stop_sequences_split_across_chunks_are_never_shown() {
+        let filters = ConversationFilters { stop_sequences: vec!["###".to_string()], ..Default::default() };
+        let mut filter = StreamFilter::new(&filters);
+        assert_eq!(filter.push("one #"), Some(StreamMessage::Content("one ".to_string())));
+        assert_eq!(shown(&mut filter, &["# two", " ##", "# three"]), "## two ");
+        assert_eq!(filter.truncated_by(), Some("stop sequence \"###\""));
+
+        let mut filter = StreamFilter::new(&filters);
+        assert_eq!(shown(&mut filter, &["a #", "b ##"]), "a #b ##");
+        assert_eq!(filter.truncated_by(), None);
+    }
+
+    #[test]
+    fn content_filters_replace_text_already_shown() {
+        let filters = ConversationFilters { content_filters: vec![r"password: \S+".to_string()], ..Default::default() };
+        let mut filter = StreamFilter::new(&filters);
+        assert_eq!(filter.push("The password: "), Some(StreamMessage::Content("The password: ".to_string())));
+        assert_eq!(filter.push("hunter2 ok"), Some(StreamMessage::Replace("The ".to_string())));
+        assert_eq!(filter.push("more"), None);
+        assert!(validate(ConversationFilters { content_filters: vec!["(".to_string()], ..Default::default() }).is_err());
+    }
+}
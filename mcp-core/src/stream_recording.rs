@@ -0,0 +1,170 @@
+//! Raw stream recording: when turned on, every chunk a provider stream yields is appended, with
+//! when it arrived, to an NDJSON file per request under `<data_dir>/stream-recordings`. Lines
+//! are written as the chunks arrive, so a stream that hangs or crashes the app still leaves a
+//! transcript to look at. A transcript can be replayed as a provider stream, which is how test
+//! fixtures reproduce a provider's glitches.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use async_openai::types::CreateChatCompletionStreamResponse;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use crate::ProviderStream;
+
+/// Transcripts kept; the oldest are removed as new ones are started
+pub const MAX_RECORDINGS: usize = 200;
+
+/// One line of a transcript
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TranscriptEntry {
+    /// Since the stream opened
+    pub elapsed_ms: u64,
+    pub received_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<CreateChatCompletionStreamResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn directory(data_dir: &Path) -> PathBuf {
+    data_dir.join("stream-recordings")
+}
+
+/// Start a transcript for a request to `model`, removing the oldest beyond `MAX_RECORDINGS`
+pub fn start(data_dir: &Path, model: &str) -> std::io::Result<PathBuf> {
+    let dir = directory(data_dir);
+    fs::create_dir_all(&dir)?;
+    prune(&dir, MAX_RECORDINGS.saturating_sub(1))?;
+    let model: String = model.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' }).collect();
+    let name = format!("{}-{}-{}.ndjson", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"), model, uuid::Uuid::new_v4());
+    Ok(dir.join(name))
+}
+
+/// Remove the oldest transcripts in `dir` until at most `keep` remain. Names start with their
+/// start time, so they sort oldest first.
+fn prune(dir: &Path, keep: usize) -> std::io::Result<()> {
+    let mut names: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ndjson"))
+        .collect();
+    if names.len() <= keep {
+        return Ok(());
+    }
+    names.sort();
+    for path in &names[..names.len() - keep] {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// `stream` unchanged, with each chunk or error appended to the transcript at `path` as it passes
+pub fn record(stream: ProviderStream, path: PathBuf) -> ProviderStream {
+    let started = Instant::now();
+    let mut file: Option<LineWriter<File>> = match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => Some(LineWriter::new(file)),
+        Err(e) => {
+            log::warn!("Not recording stream to {}: {}", path.display(), e);
+            None
+        }
+    };
+    log::debug!("Recording stream to {}", path.display());
+    Box::pin(stream.inspect(move |item| {
+        let Some(writer) = file.as_mut() else { return };
+        let entry = TranscriptEntry {
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            received_at: chrono::Utc::now().to_rfc3339(),
+            chunk: item.as_ref().ok().cloned(),
+            error: item.as_ref().err().map(|e| e.to_string()),
+        };
+        let written = serde_json::to_string(&entry)
+            .map_err(std::io::Error::other)
+            .and_then(|line| writeln!(writer, "{}", line));
+        if let Err(e) = written {
+            log::warn!("Stopped recording stream to {}: {}", path.display(), e);
+            file = None;
+        }
+    }))
+}
+
+/// The entries of a transcript; a line cut off by a crash mid-write ends it
+#[cfg(test)]
+pub fn read(path: &Path) -> std::io::Result<Vec<TranscriptEntry>> {
+    let mut entries = Vec::new();
+    use std::io::BufRead;
+    for line in std::io::BufReader::new(File::open(path)?).lines() {
+        match serde_json::from_str(&line?) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                log::warn!("Transcript {} ends with an unreadable line: {}", path.display(), e);
+                break;
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// A provider stream yielding a transcript's chunks and errors again, without the delays; test
+/// fixtures are recorded transcripts replayed this way
+#[cfg(test)]
+pub fn replay(entries: Vec<TranscriptEntry>) -> ProviderStream {
+    Box::pin(futures::stream::iter(entries.into_iter().filter_map(|entry| match (entry.chunk, entry.error) {
+        (Some(chunk), _) => Some(Ok(chunk)),
+        (None, Some(message)) => Some(Err(async_openai::error::OpenAIError::ApiError(async_openai::error::ApiError { message, r#type: None, param: None, code: None }))),
+        (None, None) => None,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::error::OpenAIError;
+
+    fn chunk(content: &str) -> CreateChatCompletionStreamResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": "c1", "object": "chat.completion.chunk", "created": 1, "model": "m",
+            "choices": [{ "index": 0, "delta": { "content": content }, "finish_reason": null }],
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn recorded_streams_replay_the_same_items() {
+        let data_dir = std::env::temp_dir().join(format!("mcp-stream-recording-{}", uuid::Uuid::new_v4()));
+        let path = start(&data_dir, "openai/gpt-4o").unwrap();
+        assert!(path.file_name().unwrap().to_str().unwrap().contains("openai_gpt-4o"));
+
+        let items = vec![
+            Ok(chunk("Hel")),
+            Ok(chunk("lo")),
+            Err(OpenAIError::StreamError("connection reset".to_string())),
+        ];
+        let passed: Vec<_> = record(Box::pin(futures::stream::iter(items)), path.clone()).collect().await;
+        assert_eq!(passed.len(), 3);
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"elapsed_ms\":").unwrap();
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[2].error.as_deref().unwrap().contains("connection reset"));
+
+        let replayed: Vec<_> = replay(entries).collect().await;
+        assert_eq!(replayed[1].as_ref().unwrap().choices[0].delta.content.as_deref(), Some("lo"));
+        assert!(replayed[2].is_err());
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn only_the_newest_transcripts_are_kept() {
+        let dir = std::env::temp_dir().join(format!("mcp-stream-recording-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["1.ndjson", "2.ndjson", "3.ndjson", "notes.txt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        prune(&dir, 2).unwrap();
+        let mut left: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+        left.sort();
+        assert_eq!(left, vec!["2.ndjson", "3.ndjson", "notes.txt"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
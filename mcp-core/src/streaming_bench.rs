@@ -0,0 +1,84 @@
+//! Benchmark showing that recording responses to history doesn't slow streaming. Ignored by
+//! default since it measures timing; run it with
+//! `cargo test -p mcp-core --release streaming_bench -- --ignored --nocapture`.
+
+use std::time::{Duration, Instant};
+use futures::stream::StreamExt;
+use crate::generation::MessageStream;
+use crate::history::{self, HistoryMessage, HistoryStore, MessageStatus};
+use crate::{track_stream, ResponseRecorder, StreamMessage};
+
+const MESSAGES: usize = 200;
+const CHUNKS_PER_MESSAGE: usize = 500;
+
+fn chunks() -> MessageStream {
+    Box::pin(futures::stream::iter((0..CHUNKS_PER_MESSAGE).map(|i| StreamMessage::Content(format!("token{} ", i)))))
+}
+
+fn details(message_id: &str) -> history::MessageDetails {
+    history::MessageDetails {
+        message_id: message_id.to_string(),
+        provider: "bench".to_string(),
+        model: "bench-model".to_string(),
+        parameters: history::GenerationParameters {
+            max_tokens: Some(1024),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Time until the last chunk reaches the consumer, and until the stream ends. `after_chunks`
+/// runs once every chunk has arrived, before the stream is polled for its end.
+async fn drain(mut stream: MessageStream, after_chunks: impl FnOnce()) -> (Duration, Duration) {
+    let started = Instant::now();
+    for _ in 0..CHUNKS_PER_MESSAGE {
+        assert!(matches!(stream.next().await, Some(StreamMessage::Content(_))));
+    }
+    let last_chunk = started.elapsed();
+    after_chunks();
+    while stream.next().await.is_some() {}
+    (last_chunk, started.elapsed())
+}
+
+#[tokio::test]
+#[ignore]
+async fn streaming_bench() {
+    let data_dir = std::env::temp_dir().join(format!("mcp-streaming-bench-{}", uuid::Uuid::new_v4()));
+    let store = HistoryStore::open(&data_dir).unwrap();
+
+    let mut bare = (Duration::ZERO, Duration::ZERO);
+    let mut recorded = (Duration::ZERO, Duration::ZERO);
+    for _ in 0..MESSAGES {
+        let (chunks_done, total) = drain(chunks(), || {}).await;
+        bare = (bare.0 + chunks_done, bare.1 + total);
+
+        let mut message = HistoryMessage::new("bench", "assistant", String::new(), None, None);
+        message.status = MessageStatus::Partial;
+        store.insert(&message).unwrap();
+        let recorder = ResponseRecorder::new(data_dir.clone(), String::new(), details(&message.id));
+        let stream = track_stream(chunks(), "bench-model".to_string(), "bench".to_string(), String::new(), recorder);
+        let (chunks_done, total) = drain(stream, || {
+            let stored = store.get(&message.id).unwrap().unwrap();
+            assert!(stored.content.is_empty(), "history was written while chunks were streaming");
+        }).await;
+        recorded = (recorded.0 + chunks_done, recorded.1 + total);
+    }
+
+    let saved = store.list("bench").unwrap();
+    assert_eq!(saved.len(), MESSAGES);
+    assert!(saved.iter().all(|m| m.status == MessageStatus::Complete && m.content.starts_with("token0 ")));
+
+    let per_message = |d: Duration| d / MESSAGES as u32;
+    println!("{} messages of {} chunks", MESSAGES, CHUNKS_PER_MESSAGE);
+    println!("  bare stream:     last chunk {:?}, end {:?} per message", per_message(bare.0), per_message(bare.1));
+    println!("  recorded stream: last chunk {:?}, end {:?} per message", per_message(recorded.0), per_message(recorded.1));
+    println!("  save after last chunk: {:?} per message", per_message(recorded.1 - recorded.0));
+
+    // Nothing is written while chunks flow (checked above); the single batched commit
+    // afterwards has to stay well under what a user would notice
+    assert!(per_message(recorded.1 - recorded.0) < Duration::from_millis(20), "saving a response is too slow");
+
+    drop(store);
+    let _ = std::fs::remove_dir_all(&data_dir);
+}
@@ -0,0 +1,548 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use aes_gcm::{Aes256Gcm, Key, Nonce, KeyInit};
+use aes_gcm::aead::{Aead, OsRng, AeadCore};
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::config::ConfigManager;
+use crate::events::{self, AppEvent};
+use crate::generation::GenerationSettings;
+use crate::guardrails::GuardrailConfig;
+use crate::handoff::{Handoff, HandoffTransport};
+use crate::history::{HistoryMessage, HistoryStore};
+use crate::memory::Memory;
+use crate::sync_backend::{SyncBackend, SyncBackendConfig};
+use crate::tr;
+
+/// Suffix of the encrypted batch objects written to the backend
+const OBJECT_SUFFIX: &str = ".sync";
+
+const SETTINGS_KEY: &str = "config:settings";
+
+/// Held for a whole sync so a manual sync and the scheduled one don't interleave
+static SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Per-device sync settings. `encryption_key` encrypts everything written to the backend and
+/// must be the same on each device; `device_id` identifies this device in vector clocks.
+#[derive(Serialize, Deserialize, Clone, Default, TS)]
+#[ts(export)]
+pub struct SyncConfig {
+    pub enabled: bool,
+    pub backend: Option<SyncBackendConfig>,
+    /// Base64 of 32 random bytes, from `generate_sync_key`
+    pub encryption_key: String,
+    pub device_id: String,
+    /// Minutes between automatic syncs; 0 syncs only on request
+    pub interval_minutes: u32,
+    pub last_synced_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct SyncReport {
+    pub pushed: u32,
+    pub pulled: u32,
+    /// Records changed on two devices since they last synced; the newer edit was kept
+    pub conflicts: u32,
+    pub synced_at: String,
+}
+
+/// Counter per device of the edits that led to a record's current value
+pub type VectorClock = BTreeMap<String, u64>;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Causality {
+    Before,
+    After,
+    Equal,
+    Concurrent,
+}
+
+/// How clock `a` relates to clock `b`
+fn compare(a: &VectorClock, b: &VectorClock) -> Causality {
+    let mut less = false;
+    let mut greater = false;
+    for device in a.keys().chain(b.keys()) {
+        match a.get(device).unwrap_or(&0).cmp(b.get(device).unwrap_or(&0)) {
+            Ordering::Less => less = true,
+            Ordering::Greater => greater = true,
+            Ordering::Equal => {}
+        }
+    }
+    match (less, greater) {
+        (false, false) => Causality::Equal,
+        (true, false) => Causality::Before,
+        (false, true) => Causality::After,
+        (true, true) => Causality::Concurrent,
+    }
+}
+
+fn merge(a: &VectorClock, b: &VectorClock) -> VectorClock {
+    let mut merged = a.clone();
+    for (device, counter) in b {
+        let entry = merged.entry(device.clone()).or_default();
+        *entry = (*entry).max(*counter);
+    }
+    merged
+}
+
+/// Another device syncing with the same backend
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct SyncDevice {
+    pub device_id: String,
+    /// When its newest batch that reached this device was written
+    pub last_seen_at: String,
+}
+
+/// Sync state of one record as this device last saw it
+pub struct SyncEntry {
+    pub key: String,
+    pub clock: VectorClock,
+    /// Hash of the synced payload; empty for a deletion
+    pub hash: String,
+    pub updated_at: String,
+    pub device_id: String,
+    /// Changed locally (or merged) since the last push
+    pub pending: bool,
+}
+
+/// A record as written to the backend; a `None` payload marks a deletion
+#[derive(Serialize, Deserialize)]
+struct SyncRecord {
+    key: String,
+    clock: VectorClock,
+    updated_at: String,
+    device_id: String,
+    payload: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyncBatch {
+    device_id: String,
+    created_at: String,
+    records: Vec<SyncRecord>,
+}
+
+/// The part of the config that follows the user between devices. API keys, server and sync
+/// settings stay per device.
+#[derive(Serialize, Deserialize)]
+struct SyncedSettings {
+    preferred_model: String,
+    generation: GenerationSettings,
+    guardrails: GuardrailConfig,
+    memory_enabled: bool,
+}
+
+/// New random key for encrypting sync data
+pub fn generate_key() -> String {
+    general_purpose::STANDARD.encode(Aes256Gcm::generate_key(&mut OsRng))
+}
+
+/// Pull and apply other devices' changes, then push this device's changes since the last sync.
+/// Messages, memories, shared settings and handoffs between desktops are synced.
+pub async fn sync(config: &SyncConfig, config_manager: &ConfigManager, store: &mut HistoryStore) -> Result<SyncReport> {
+    let _guard = SYNC_LOCK.lock().await;
+    let backend = SyncBackend::open(config.backend.as_ref().ok_or_else(|| anyhow::anyhow!(tr!("sync-no-backend")))?)?;
+    let cipher = cipher(&config.encryption_key)?;
+    let device = config.device_id.as_str();
+    if device.is_empty() {
+        return Err(anyhow::anyhow!("Sync has no device id"));
+    }
+
+    // Stamp local edits first so they are compared against incoming ones by clock
+    let mut local = local_records(config_manager, store)?;
+    stage_local_changes(device, &local, store)?;
+
+    let mut report = SyncReport { pushed: 0, pulled: 0, conflicts: 0, synced_at: String::new() };
+    let own_prefix = format!("{}-", device);
+    for name in backend.list(OBJECT_SUFFIX).await? {
+        if name.starts_with(&own_prefix) || store.is_sync_applied(&name)? {
+            continue;
+        }
+        let batch: SyncBatch = serde_json::from_slice(&decrypt(&cipher, &backend.get(&name).await?)
+            .map_err(|e| anyhow::anyhow!(tr!("sync-unreadable", name = name.as_str(), error = e.to_string())))?)?;
+        for record in batch.records {
+            match apply_remote(record, &mut local, config_manager, store)? {
+                Applied::Updated => report.pulled += 1,
+                Applied::Conflict => report.conflicts += 1,
+                Applied::Ignored => {}
+            }
+        }
+        store.mark_sync_applied(&name)?;
+        store.record_sync_device(&batch.device_id, &batch.created_at)?;
+    }
+
+    let pending = store.pending_sync_entries()?;
+    if !pending.is_empty() {
+        let records: Vec<SyncRecord> = pending.iter()
+            .map(|entry| SyncRecord {
+                key: entry.key.clone(),
+                clock: entry.clock.clone(),
+                updated_at: entry.updated_at.clone(),
+                device_id: entry.device_id.clone(),
+                payload: if entry.hash.is_empty() { None } else { local.get(&entry.key).cloned() },
+            })
+            .collect();
+        let batch = SyncBatch { device_id: device.to_string(), created_at: chrono::Utc::now().to_rfc3339(), records };
+        let name = format!(
+            "{}{}-{}{}",
+            own_prefix, chrono::Utc::now().format("%Y%m%dT%H%M%SZ"), uuid::Uuid::new_v4(), OBJECT_SUFFIX
+        );
+        backend.put(&name, encrypt(&cipher, &serde_json::to_vec(&batch)?)?).await?;
+        store.clear_sync_pending(pending.iter().map(|e| e.key.as_str()))?;
+        report.pushed = pending.len() as u32;
+    }
+
+    report.synced_at = chrono::Utc::now().to_rfc3339();
+    log::info!("Synced: {} pushed, {} pulled, {} conflicts", report.pushed, report.pulled, report.conflicts);
+    Ok(report)
+}
+
+/// Everything this device syncs, keyed by record key
+fn local_records(config_manager: &ConfigManager, store: &HistoryStore) -> Result<HashMap<String, serde_json::Value>> {
+    let mut records = HashMap::new();
+    for message in store.all_messages()? {
+        records.insert(format!("message:{}", message.id), serde_json::to_value(&message)?);
+    }
+    for memory in store.list_memories()? {
+        records.insert(format!("memory:{}", memory.id), serde_json::to_value(&memory)?);
+    }
+    for handoff in store.list_handoffs(HandoffTransport::Sync)? {
+        records.insert(format!("handoff:{}", handoff.id), serde_json::to_value(&handoff)?);
+    }
+    let settings = SyncedSettings {
+        preferred_model: config_manager.get_preferred_model()?,
+        generation: config_manager.get_generation_settings()?,
+        guardrails: config_manager.get_guardrails()?,
+        memory_enabled: config_manager.get_memory_enabled()?,
+    };
+    records.insert(SETTINGS_KEY.to_string(), serde_json::to_value(&settings)?);
+    Ok(records)
+}
+
+/// Bump this device's clock on every record that changed or disappeared since it was last synced
+fn stage_local_changes(device: &str, local: &HashMap<String, serde_json::Value>, store: &HistoryStore) -> Result<()> {
+    let mut entries = store.sync_entries()?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut changed = Vec::new();
+    for (key, payload) in local {
+        let hash = payload_hash(payload)?;
+        if entries.get(key).is_some_and(|entry| entry.hash == hash) {
+            continue;
+        }
+        let mut clock = entries.remove(key).map(|e| e.clock).unwrap_or_default();
+        *clock.entry(device.to_string()).or_default() += 1;
+        // Messages carry their own edit time, which is fairer for conflicts than the time of this sync
+        let updated_at = payload.get("updated_at").and_then(|v| v.as_str()).unwrap_or(&now).to_string();
+        changed.push(SyncEntry { key: key.clone(), clock, hash, updated_at, device_id: device.to_string(), pending: true });
+    }
+    // Entries left over were synced once but are gone locally
+    for (_, entry) in entries.into_iter().filter(|(key, entry)| !entry.hash.is_empty() && !local.contains_key(key)) {
+        let mut clock = entry.clock;
+        *clock.entry(device.to_string()).or_default() += 1;
+        changed.push(SyncEntry { clock, hash: String::new(), updated_at: now.clone(), device_id: device.to_string(), pending: true, ..entry });
+    }
+    for entry in &changed {
+        store.save_sync_entry(entry)?;
+    }
+    Ok(())
+}
+
+enum Applied {
+    Updated,
+    Conflict,
+    Ignored,
+}
+
+/// Apply `record` if it supersedes this device's copy, keeping `records` (from
+/// [`local_records`]) in step so the push afterwards sends what is now stored
+fn apply_remote(
+    record: SyncRecord,
+    records: &mut HashMap<String, serde_json::Value>,
+    config_manager: &ConfigManager,
+    store: &mut HistoryStore,
+) -> Result<Applied> {
+    let hash = match &record.payload {
+        Some(payload) => payload_hash(payload)?,
+        None => String::new(),
+    };
+    let local = store.sync_entry(&record.key)?;
+    let (remote_wins, conflict) = match &local {
+        None => (true, false),
+        Some(local) => match compare(&record.clock, &local.clock) {
+            Causality::After => (true, false),
+            Causality::Before | Causality::Equal => (false, false),
+            // Edited on both sides: keep the later edit, device id breaks exact ties
+            Causality::Concurrent => (
+                (record.updated_at.as_str(), record.device_id.as_str()) > (local.updated_at.as_str(), local.device_id.as_str()),
+                true,
+            ),
+        },
+    };
+
+    if conflict {
+        let local = local.expect("conflicts need a local entry");
+        if local.hash == hash {
+            // Same value reached independently; adopt the merged clock without calling it a conflict
+            store.save_sync_entry(&SyncEntry { clock: merge(&record.clock, &local.clock), pending: true, ..local })?;
+            return Ok(Applied::Ignored);
+        }
+        log::info!("Sync conflict on {}; keeping the {} edit", record.key, if remote_wins { "remote" } else { "local" });
+        let (hash, updated_at, device_id) = if remote_wins {
+            apply_payload(&record.key, record.payload, records, config_manager, store)?;
+            (hash, record.updated_at, record.device_id)
+        } else {
+            (local.hash, local.updated_at, local.device_id)
+        };
+        // The merged clock supersedes both edits, so pushing it settles every device on the winner
+        store.save_sync_entry(&SyncEntry {
+            key: record.key,
+            clock: merge(&record.clock, &local.clock),
+            hash,
+            updated_at,
+            device_id,
+            pending: true,
+        })?;
+        return Ok(Applied::Conflict);
+    }
+    if !remote_wins {
+        return Ok(Applied::Ignored);
+    }
+    apply_payload(&record.key, record.payload, records, config_manager, store)?;
+    store.save_sync_entry(&SyncEntry {
+        key: record.key,
+        clock: record.clock,
+        hash,
+        updated_at: record.updated_at,
+        device_id: record.device_id,
+        pending: false,
+    })?;
+    Ok(Applied::Updated)
+}
+
+fn apply_payload(
+    key: &str,
+    payload: Option<serde_json::Value>,
+    records: &mut HashMap<String, serde_json::Value>,
+    config_manager: &ConfigManager,
+    store: &mut HistoryStore,
+) -> Result<()> {
+    let (kind, id) = key.split_once(':').ok_or_else(|| anyhow::anyhow!("Malformed sync key {}", key))?;
+    match &payload {
+        Some(payload) => records.insert(key.to_string(), payload.clone()),
+        None => records.remove(key),
+    };
+    match (kind, payload) {
+        ("message", Some(payload)) => store.upsert_message(&serde_json::from_value::<HistoryMessage>(payload)?)?,
+        ("message", None) => store.delete_message(id)?,
+        ("memory", Some(payload)) => store.upsert_memory(&serde_json::from_value::<Memory>(payload)?)?,
+        ("memory", None) => {
+            store.remove_memory(id)?;
+        }
+        ("handoff", Some(payload)) => {
+            let handoff: Handoff = serde_json::from_value(payload)?;
+            store.save_handoff(&handoff)?;
+            let device = config_manager.get_sync_config()?.device_id;
+            if handoff.to_device == device && handoff.accepted_at.is_none() {
+                events::publish(AppEvent::HandoffReceived { handoff });
+            } else if handoff.from_device == device && handoff.accepted_at.is_some() {
+                events::publish(AppEvent::HandoffAccepted { handoff });
+            }
+        }
+        ("handoff", None) => store.delete_handoff(id)?,
+        ("config", Some(payload)) if key == SETTINGS_KEY => {
+            let settings: SyncedSettings = serde_json::from_value(payload)?;
+            config_manager.save_preferred_model(settings.preferred_model)?;
+            config_manager.save_generation_settings(settings.generation)?;
+            config_manager.save_guardrails(settings.guardrails)?;
+            config_manager.save_memory_enabled(settings.memory_enabled)?;
+        }
+        _ => log::warn!("Ignoring unknown sync record {}", key),
+    }
+    Ok(())
+}
+
+fn payload_hash(payload: &serde_json::Value) -> Result<String> {
+    Ok(format!("{:x}", Sha256::digest(serde_json::to_vec(payload)?)))
+}
+
+fn cipher(key: &str) -> Result<Aes256Gcm> {
+    let key = general_purpose::STANDARD.decode(key.trim()).map_err(|_| anyhow::anyhow!(tr!("sync-key-not-base64")))?;
+    if key.len() != 32 {
+        return Err(anyhow::anyhow!(tr!("sync-key-length")));
+    }
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+fn encrypt(cipher: &Aes256Gcm, data: &[u8]) -> Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, data)
+        .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
+    let mut combined = nonce.to_vec();
+    combined.extend_from_slice(&ciphertext);
+    Ok(combined)
+}
+
+fn decrypt(cipher: &Aes256Gcm, data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 12 {
+        return Err(anyhow::anyhow!("Invalid encrypted data"));
+    }
+    let (nonce, ciphertext) = data.split_at(12);
+    cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn clock(counters: &[(&str, u64)]) -> VectorClock {
+        counters.iter().map(|(device, counter)| (device.to_string(), *counter)).collect()
+    }
+
+    #[test]
+    fn clocks_compare_by_every_device_counter() {
+        let cases = [
+            (clock(&[]), clock(&[]), Causality::Equal),
+            (clock(&[("a", 1), ("b", 2)]), clock(&[("a", 1), ("b", 2)]), Causality::Equal),
+            // A device missing from a clock counts as zero
+            (clock(&[("a", 1), ("b", 0)]), clock(&[("a", 1)]), Causality::Equal),
+            (clock(&[("a", 1)]), clock(&[("a", 2)]), Causality::Before),
+            (clock(&[("a", 1)]), clock(&[("a", 1), ("b", 1)]), Causality::Before),
+            (clock(&[("a", 2)]), clock(&[("a", 1)]), Causality::After),
+            (clock(&[("a", 1), ("b", 1)]), clock(&[]), Causality::After),
+            (clock(&[("a", 2)]), clock(&[("a", 1), ("b", 1)]), Causality::Concurrent),
+            (clock(&[("a", 1)]), clock(&[("b", 1)]), Causality::Concurrent),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(compare(&a, &b), expected, "{:?} against {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn merged_clocks_take_each_device_highest_counter() {
+        let cases = [
+            (clock(&[]), clock(&[]), clock(&[])),
+            (clock(&[("a", 1)]), clock(&[]), clock(&[("a", 1)])),
+            (clock(&[("a", 2)]), clock(&[("a", 1), ("b", 1)]), clock(&[("a", 2), ("b", 1)])),
+            (clock(&[("a", 1), ("b", 3)]), clock(&[("a", 4), ("c", 2)]), clock(&[("a", 4), ("b", 3), ("c", 2)])),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(merge(&a, &b), expected);
+            assert_eq!(merge(&b, &a), expected);
+            assert_eq!(compare(&merge(&a, &b), &a), if a == expected { Causality::Equal } else { Causality::After });
+        }
+    }
+
+    /// A device with its own config and history, syncing through a folder shared with the others
+    struct Device {
+        dir: PathBuf,
+        sync: SyncConfig,
+        config_manager: ConfigManager,
+        store: HistoryStore,
+    }
+
+    impl Device {
+        fn new(id: &str, shared: &std::path::Path, key: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("mcp-sync-{}-{}", id, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let sync = SyncConfig {
+                enabled: true,
+                backend: Some(SyncBackendConfig::Folder { path: shared.to_string_lossy().into_owned() }),
+                encryption_key: key.to_string(),
+                device_id: id.to_string(),
+                ..SyncConfig::default()
+            };
+            let config_manager = ConfigManager::in_dir(&dir);
+            let store = HistoryStore::open(&dir).unwrap();
+            Device { dir, sync, config_manager, store }
+        }
+
+        async fn sync(&mut self) -> SyncReport {
+            sync(&self.sync, &self.config_manager, &mut self.store).await.unwrap()
+        }
+
+        fn message(&self, id: &str) -> Option<HistoryMessage> {
+            self.store.all_messages().unwrap().into_iter().find(|message| message.id == id)
+        }
+
+        /// Change a message's content as if edited at `at`
+        fn edit(&self, message: &HistoryMessage, content: &str, at: chrono::DateTime<chrono::Utc>) {
+            let edited = HistoryMessage { content: content.to_string(), updated_at: at.to_rfc3339(), ..message.clone() };
+            self.store.upsert_message(&edited).unwrap();
+        }
+    }
+
+    /// Two devices that have both synced one message
+    async fn devices_sharing_a_message() -> (PathBuf, Device, Device, HistoryMessage) {
+        let shared = std::env::temp_dir().join(format!("mcp-sync-shared-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&shared).unwrap();
+        let key = generate_key();
+        let mut a = Device::new("device-a", &shared, &key);
+        let mut b = Device::new("device-b", &shared, &key);
+        let message = HistoryMessage::new("c", "user", "original".to_string(), None, None);
+        a.store.insert(&message).unwrap();
+        a.sync().await;
+        b.sync().await;
+        assert_eq!(b.message(&message.id).unwrap().content, "original");
+        (shared, a, b, message)
+    }
+
+    fn clean_up(shared: PathBuf, devices: [Device; 2]) {
+        let _ = std::fs::remove_dir_all(shared);
+        for device in devices {
+            drop(device.store);
+            let _ = std::fs::remove_dir_all(device.dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_edits_keep_the_later_one_everywhere() {
+        let (shared, mut a, mut b, message) = devices_sharing_a_message().await;
+        let now = chrono::Utc::now();
+        b.edit(&message, "earlier edit on b", now - chrono::Duration::minutes(5));
+        a.edit(&message, "later edit on a", now);
+        b.sync().await;
+
+        // a's edit is newer, so it stays, and the merged clock goes out with it
+        let report = a.sync().await;
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(a.message(&message.id).unwrap().content, "later edit on a");
+        let entry = a.store.sync_entry(&format!("message:{}", message.id)).unwrap().unwrap();
+        assert_eq!(entry.clock, clock(&[("device-a", 2), ("device-b", 1)]));
+        assert!(!entry.pending);
+
+        // b sees a clock that follows its own edit and takes a's value without a conflict
+        let report = b.sync().await;
+        assert_eq!(report.conflicts, 0);
+        assert_eq!(b.message(&message.id).unwrap().content, "later edit on a");
+        clean_up(shared, [a, b]);
+    }
+
+    #[tokio::test]
+    async fn an_edit_newer_than_a_delete_brings_the_message_back() {
+        let (shared, mut a, mut b, message) = devices_sharing_a_message().await;
+        a.store.delete_message(&message.id).unwrap();
+        b.edit(&message, "edited after the delete", chrono::Utc::now() + chrono::Duration::minutes(5));
+
+        // a's delete goes out as a tombstone
+        a.sync().await;
+        let tombstone = a.store.sync_entry(&format!("message:{}", message.id)).unwrap().unwrap();
+        assert!(tombstone.hash.is_empty());
+        assert_eq!(tombstone.clock, clock(&[("device-a", 2)]));
+
+        // It races b's edit, which is later, so b keeps the message and pushes it back
+        let report = b.sync().await;
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(b.message(&message.id).unwrap().content, "edited after the delete");
+
+        a.sync().await;
+        assert_eq!(a.message(&message.id).unwrap().content, "edited after the delete");
+        clean_up(shared, [a, b]);
+    }
+}
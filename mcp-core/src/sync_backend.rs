@@ -0,0 +1,271 @@
+use std::path::PathBuf;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use anyhow::Result;
+use ts_rs::TS;
+
+/// Where sync objects are stored. Every backend holds a flat set of named objects.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+#[serde(tag = "type")]
+pub enum SyncBackendConfig {
+    /// A local directory, e.g. inside Dropbox or a network share
+    Folder { path: String },
+    /// A WebDAV collection that already exists, e.g. https://dav.example.com/switchboard/
+    WebDav { url: String, username: String, password: String },
+    /// An S3-compatible bucket, addressed path-style at `endpoint`
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        secret_access_key: String,
+        prefix: String,
+    },
+}
+
+pub enum SyncBackend {
+    Folder(PathBuf),
+    WebDav { client: reqwest::Client, url: String, username: String, password: String },
+    S3 { client: reqwest::Client, config: S3Config },
+}
+
+pub struct S3Config {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    prefix: String,
+}
+
+impl SyncBackend {
+    pub fn open(config: &SyncBackendConfig) -> Result<Self> {
+        Ok(match config {
+            SyncBackendConfig::Folder { path } => {
+                let path = PathBuf::from(path);
+                std::fs::create_dir_all(&path)?;
+                SyncBackend::Folder(path)
+            }
+            SyncBackendConfig::WebDav { url, username, password } => SyncBackend::WebDav {
+                client: reqwest::Client::new(),
+                url: format!("{}/", url.trim_end_matches('/')),
+                username: username.clone(),
+                password: password.clone(),
+            },
+            SyncBackendConfig::S3 { endpoint, bucket, region, access_key_id, secret_access_key, prefix } => SyncBackend::S3 {
+                client: reqwest::Client::new(),
+                config: S3Config {
+                    endpoint: endpoint.trim_end_matches('/').to_string(),
+                    bucket: bucket.clone(),
+                    region: region.clone(),
+                    access_key_id: access_key_id.clone(),
+                    secret_access_key: secret_access_key.clone(),
+                    prefix: prefix.trim_matches('/').to_string(),
+                },
+            },
+        })
+    }
+
+    /// Names of all objects ending in `suffix`
+    pub async fn list(&self, suffix: &str) -> Result<Vec<String>> {
+        let mut names = match self {
+            SyncBackend::Folder(path) => std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect(),
+            SyncBackend::WebDav { client, url, username, password } => {
+                let response = client
+                    .request(reqwest::Method::from_bytes(b"PROPFIND")?, url.as_str())
+                    .basic_auth(username, Some(password))
+                    .header("Depth", "1")
+                    .send()
+                    .await?;
+                let body = check(response).await?.text().await?;
+                let href = Regex::new(r"<(?:[A-Za-z0-9]+:)?href>([^<]+)</(?:[A-Za-z0-9]+:)?href>")?;
+                href.captures_iter(&body)
+                    .filter_map(|c| c[1].trim_end_matches('/').rsplit('/').next().map(str::to_string))
+                    .collect()
+            }
+            SyncBackend::S3 { client, config } => config.list(client).await?,
+        };
+        names.retain(|name: &String| name.ends_with(suffix));
+        names.sort();
+        Ok(names)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Vec<u8>> {
+        check_name(name)?;
+        match self {
+            SyncBackend::Folder(path) => Ok(std::fs::read(path.join(name))?),
+            SyncBackend::WebDav { client, url, username, password } => {
+                let response = client.get(format!("{}{}", url, name))
+                    .basic_auth(username, Some(password))
+                    .send()
+                    .await?;
+                Ok(check(response).await?.bytes().await?.to_vec())
+            }
+            SyncBackend::S3 { client, config } => {
+                let response = config.request(client, reqwest::Method::GET, &config.key(name), &[], Vec::new())?
+                    .send()
+                    .await?;
+                Ok(check(response).await?.bytes().await?.to_vec())
+            }
+        }
+    }
+
+    pub async fn put(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        check_name(name)?;
+        match self {
+            SyncBackend::Folder(path) => {
+                // Write then rename so other devices never read a half-written object
+                let partial = path.join(format!(".{}.partial", name));
+                std::fs::write(&partial, data)?;
+                std::fs::rename(partial, path.join(name))?;
+            }
+            SyncBackend::WebDav { client, url, username, password } => {
+                let response = client.put(format!("{}{}", url, name))
+                    .basic_auth(username, Some(password))
+                    .body(data)
+                    .send()
+                    .await?;
+                check(response).await?;
+            }
+            SyncBackend::S3 { client, config } => {
+                let response = config.request(client, reqwest::Method::PUT, &config.key(name), &[], data)?
+                    .send()
+                    .await?;
+                check(response).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(anyhow::anyhow!("Invalid sync object name: {}", name));
+    }
+    Ok(())
+}
+
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow::anyhow!("Sync backend returned {}: {}", status, body.chars().take(200).collect::<String>()))
+}
+
+impl S3Config {
+    fn key(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+
+    async fn list(&self, client: &reqwest::Client) -> Result<Vec<String>> {
+        let key = Regex::new(r"<Key>([^<]+)</Key>")?;
+        let next = Regex::new(r"<NextContinuationToken>([^<]+)</NextContinuationToken>")?;
+        let prefix = if self.prefix.is_empty() { String::new() } else { format!("{}/", self.prefix) };
+
+        let mut names = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2".to_string()), ("prefix", prefix.clone())];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.clone()));
+            }
+            let response = self.request(client, reqwest::Method::GET, "", &query, Vec::new())?.send().await?;
+            let body = check(response).await?.text().await?;
+            names.extend(key.captures_iter(&body).map(|c| c[1].trim_start_matches(&prefix).to_string()));
+            match next.captures(&body) {
+                Some(c) => token = Some(c[1].to_string()),
+                None => return Ok(names),
+            }
+        }
+    }
+
+    /// Build a request signed with AWS Signature Version 4
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        key: &str,
+        query: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder> {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = format!("{:x}", Sha256::digest(&body));
+
+        let host = self.endpoint.split("://").nth(1).unwrap_or(&self.endpoint).to_string();
+        let path = if key.is_empty() {
+            format!("/{}", uri_encode(&self.bucket, false))
+        } else {
+            format!("/{}/{}", uri_encode(&self.bucket, false), uri_encode(key, false))
+        };
+        let mut query: Vec<(String, String)> = query.iter().map(|(k, v)| (uri_encode(k, true), uri_encode(v, true))).collect();
+        query.sort();
+        let query = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, query, host, payload_hash, amz_date, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+            amz_date, scope, Sha256::digest(canonical_request.as_bytes())
+        );
+
+        let mut signing_key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes())?;
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part.as_bytes())?;
+        }
+        let signature = hmac_sha256(&signing_key, string_to_sign.as_bytes())?
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+
+        let url = if query.is_empty() {
+            format!("{}{}", self.endpoint, path)
+        } else {
+            format!("{}{}?{}", self.endpoint, path, query)
+        };
+        Ok(client.request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(
+                "Authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    self.access_key_id, scope, signature
+                ),
+            )
+            .body(body))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| anyhow::anyhow!("Invalid signing key: {}", e))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Percent-encode per SigV4 rules; `/` is left alone in paths
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
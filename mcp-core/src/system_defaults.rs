@@ -0,0 +1,136 @@
+//! Defaults an administrator provides for everyone on a shared machine, in a read-only JSON file
+//! such as `/etc/mcp-switchboard/defaults.json`. They sit under each user's config: a setting
+//! the user hasn't changed comes from this file, and personal secrets (API key, server tokens,
+//! sync keys) are never taken from it.
+//!
+//! Only the differences from these defaults are written to the user's config, so when the
+//! administrator changes a default, everyone who hadn't changed that setting gets the new value.
+
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use ts_rs::TS;
+
+pub const DEFAULTS_ENV: &str = "MCP_SWITCHBOARD_DEFAULTS";
+
+/// Top-level config settings the defaults file may provide. There's no MCP server list in the
+/// config yet; plugin grants are how tools are provisioned.
+pub const LAYERED_KEYS: [&str; 14] = [
+    "preferred_model",
+    "template_repositories",
+    "plugin_grants",
+    "server_network",
+    "generation",
+    "guardrails",
+    "memory_enabled",
+    "backup_schedule",
+    "locale",
+    "accessibility",
+    "appearance",
+    "mode",
+    "feature_flags",
+    "redaction_enabled",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+#[ts(export)]
+pub struct SystemDefaultsInfo {
+    pub path: Option<String>,
+    /// Settings the file provides, for the UI to mark as managed
+    pub settings: Vec<String>,
+}
+
+/// Where the defaults file is looked for. Portable installs don't use one.
+pub fn path() -> Option<PathBuf> {
+    if crate::portable::active() {
+        return None;
+    }
+    if let Some(path) = crate::environment::value(DEFAULTS_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    Some(crate::platform::system_dir().join("defaults.json"))
+}
+
+/// The layered settings from the defaults file at `path`; anything else in the file is ignored
+pub fn read(path: &std::path::Path) -> Map<String, Value> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Map::new(),
+        Err(e) => {
+            log::warn!("Failed to read system defaults {:?}: {}", path, e);
+            return Map::new();
+        }
+    };
+    let mut defaults = match serde_json::from_str::<Value>(&contents) {
+        Ok(Value::Object(defaults)) => defaults,
+        Ok(_) | Err(_) => {
+            log::warn!("Ignoring system defaults {:?}, which is not a JSON object", path);
+            return Map::new();
+        }
+    };
+    defaults.retain(|key, _| {
+        let layered = LAYERED_KEYS.contains(&key.as_str());
+        if !layered {
+            log::warn!("Ignoring {} in system defaults {:?}; it can't be set system-wide", key, path);
+        }
+        layered
+    });
+    log::info!("Loaded system defaults for {:?} from {:?}", defaults.keys().collect::<Vec<_>>(), path);
+    defaults
+}
+
+/// Fill in the settings `config` doesn't have from `defaults`
+pub fn fill(config: &mut Map<String, Value>, defaults: &Map<String, Value>) {
+    for (key, value) in defaults {
+        if !config.contains_key(key) {
+            config.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Drop the settings `config` has at their default, so they keep following the defaults file
+pub fn strip(config: &mut Map<String, Value>, defaults: &Map<String, Value>) {
+    config.retain(|key, value| defaults.get(key) != Some(value));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn user_settings_sit_above_the_defaults() {
+        let defaults = map(json!({ "preferred_model": "admin-model", "memory_enabled": true }));
+        let mut config = map(json!({ "together_ai_api_key": "personal", "memory_enabled": false }));
+        fill(&mut config, &defaults);
+        assert_eq!(config["preferred_model"], "admin-model");
+        assert_eq!(config["memory_enabled"], false);
+        assert_eq!(config["together_ai_api_key"], "personal");
+    }
+
+    #[test]
+    fn settings_at_their_default_are_not_saved() {
+        let defaults = map(json!({ "preferred_model": "admin-model", "memory_enabled": true }));
+        let mut config = map(json!({ "together_ai_api_key": "personal", "preferred_model": "admin-model", "memory_enabled": false }));
+        strip(&mut config, &defaults);
+        assert!(!config.contains_key("preferred_model"));
+        assert_eq!(config["memory_enabled"], false);
+        assert_eq!(config["together_ai_api_key"], "personal");
+    }
+
+    #[test]
+    fn secrets_and_unknown_settings_are_ignored() {
+        let path = std::env::temp_dir().join(format!("mcp-defaults-test-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{ "together_ai_api_key": "shared", "server_tokens": [], "locale": "de" }"#).unwrap();
+        let defaults = read(&path);
+        assert_eq!(defaults.keys().collect::<Vec<_>>(), vec!["locale"]);
+        let _ = std::fs::remove_file(&path);
+    }
+}
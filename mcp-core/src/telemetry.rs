@@ -0,0 +1,449 @@
+//! Optional OpenTelemetry trace export. Provider requests, tool calls and API server requests
+//! are recorded as spans and posted in batches to the user's collector over OTLP/HTTP, JSON
+//! encoded. Provider requests carry a W3C `traceparent` header, so a local model server that
+//! traces too lands in the same trace. Nothing is recorded while export is off.
+//!
+//! There are no MCP server connections yet; the tool calls traced are those of plugins.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, Once, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::config::ConfigManager;
+use crate::tr;
+
+/// How often queued spans are sent
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Spans kept while the collector can't be reached; the oldest are dropped past this
+const MAX_QUEUED_SPANS: usize = 2048;
+
+/// Where an OpenTelemetry Collector listens for OTLP/HTTP by default
+pub const DEFAULT_ENDPOINT: &str = "http://localhost:4318";
+
+const DEFAULT_SERVICE_NAME: &str = "mcp-switchboard";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    /// Base URL of an OTLP/HTTP collector; spans are posted to `<endpoint>/v1/traces`
+    pub endpoint: String,
+    /// Sent with every export, e.g. an API key for a hosted collector
+    pub headers: BTreeMap<String, String>,
+    /// `service.name` of the exported spans
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            enabled: false,
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            headers: BTreeMap::new(),
+            service_name: DEFAULT_SERVICE_NAME.to_string(),
+        }
+    }
+}
+
+/// `config` tidied up, or a localized error if the endpoint or a header can't be used
+pub fn validate(mut config: TelemetryConfig) -> Result<TelemetryConfig, String> {
+    config.endpoint = config.endpoint.trim().trim_end_matches('/').to_string();
+    if config.endpoint.is_empty() {
+        config.endpoint = DEFAULT_ENDPOINT.to_string();
+    }
+    let scheme_ok = config.endpoint.starts_with("http://") || config.endpoint.starts_with("https://");
+    if !scheme_ok || reqwest::Url::parse(&config.endpoint).is_err() {
+        return Err(tr!("telemetry-endpoint-invalid", endpoint = config.endpoint.as_str()));
+    }
+    config.service_name = config.service_name.trim().to_string();
+    if config.service_name.is_empty() {
+        config.service_name = DEFAULT_SERVICE_NAME.to_string();
+    }
+    let mut headers = BTreeMap::new();
+    for (name, value) in config.headers {
+        let name = name.trim().to_string();
+        let valid = reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_ok()
+            && reqwest::header::HeaderValue::from_str(value.trim()).is_ok();
+        if !valid {
+            return Err(tr!("telemetry-header-invalid", name = name.as_str()));
+        }
+        headers.insert(name, value.trim().to_string());
+    }
+    config.headers = headers;
+    Ok(config)
+}
+
+static LOADED: Once = Once::new();
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CONFIG: RwLock<Option<TelemetryConfig>> = RwLock::new(None);
+static QUEUE: Mutex<VecDeque<FinishedSpan>> = Mutex::new(VecDeque::new());
+
+/// Start or stop recording spans with `config`
+pub fn configure(config: TelemetryConfig) {
+    LOADED.call_once(|| {});
+    ENABLED.store(config.enabled, Ordering::Relaxed);
+    if !config.enabled {
+        queue().clear();
+    }
+    *CONFIG.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(config);
+}
+
+fn recording() -> bool {
+    // The saved config is read when the first span starts, so nothing early is missed
+    LOADED.call_once(|| {
+        match ConfigManager::new().and_then(|config_manager| config_manager.get_telemetry_config()) {
+            Ok(config) => {
+                ENABLED.store(config.enabled, Ordering::Relaxed);
+                *CONFIG.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(config);
+            }
+            Err(e) => log::warn!("Failed to read telemetry config: {}", e),
+        }
+    });
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn queue() -> std::sync::MutexGuard<'static, VecDeque<FinishedSpan>> {
+    QUEUE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpanKind {
+    Internal,
+    /// Handling a request made to the API server
+    Server,
+    /// A request to a provider
+    Client,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::Str(value)
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Float(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        AttributeValue::Bool(value)
+    }
+}
+
+struct SpanData {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    name: String,
+    kind: SpanKind,
+    start: SystemTime,
+    attributes: Vec<(&'static str, AttributeValue)>,
+    error: Option<String>,
+}
+
+struct FinishedSpan {
+    data: SpanData,
+    end: SystemTime,
+}
+
+/// A timed operation, queued for export when dropped. Does nothing while export is off.
+pub struct Span(Option<SpanData>);
+
+impl Span {
+    /// The root of a new trace
+    pub fn start(name: impl Into<String>, kind: SpanKind) -> Span {
+        Span::continuing(name, kind, None)
+    }
+
+    /// A span in the caller's trace when `traceparent` is a valid W3C header, else a new trace
+    pub fn continuing(name: impl Into<String>, kind: SpanKind, traceparent: Option<&str>) -> Span {
+        if !recording() {
+            return Span(None);
+        }
+        let random = uuid::Uuid::new_v4().into_bytes();
+        let span_id = random[..8].try_into().expect("uuids have 16 bytes");
+        let (trace_id, parent_span_id) = match traceparent.and_then(parse_traceparent) {
+            Some((trace_id, parent)) => (trace_id, Some(parent)),
+            None => (uuid::Uuid::new_v4().into_bytes(), None),
+        };
+        Span(Some(SpanData {
+            trace_id,
+            span_id,
+            parent_span_id,
+            name: name.into(),
+            kind,
+            start: SystemTime::now(),
+            attributes: Vec::new(),
+            error: None,
+        }))
+    }
+
+    pub fn set(&mut self, key: &'static str, value: impl Into<AttributeValue>) {
+        if let Some(data) = &mut self.0 {
+            data.attributes.retain(|(existing, _)| *existing != key);
+            data.attributes.push((key, value.into()));
+        }
+    }
+
+    /// Mark the operation as failed
+    pub fn fail(&mut self, message: impl Into<String>) {
+        if let Some(data) = &mut self.0 {
+            data.error = Some(message.into());
+        }
+    }
+
+    /// W3C trace context for requests made within this span
+    pub fn traceparent(&self) -> Option<String> {
+        self.0.as_ref().map(|data| format!("00-{}-{}-01", hex(&data.trace_id), hex(&data.span_id)))
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(data) = self.0.take() {
+            let mut queue = queue();
+            if queue.len() >= MAX_QUEUED_SPANS {
+                queue.pop_front();
+            }
+            queue.push_back(FinishedSpan { data, end: SystemTime::now() });
+        }
+    }
+}
+
+/// `stream` with `span` kept open until it ends or is dropped, failing the span on an error item
+pub fn traced<T, E: std::fmt::Display>(
+    stream: impl Stream<Item = Result<T, E>>,
+    mut span: Span,
+) -> impl Stream<Item = Result<T, E>> {
+    stream.map(move |item| {
+        if let Err(e) = &item {
+            span.fail(e.to_string());
+        }
+        item
+    })
+}
+
+/// An HTTP client sending `headers` and `span`'s trace context, for provider requests; None
+/// when there's neither to send
+pub fn http_client(span: &Span, mut headers: reqwest::header::HeaderMap) -> Option<reqwest::Client> {
+    if let Some(traceparent) = span.traceparent().and_then(|traceparent| reqwest::header::HeaderValue::from_str(&traceparent).ok()) {
+        headers.insert(reqwest::header::HeaderName::from_static("traceparent"), traceparent);
+    }
+    if headers.is_empty() {
+        return None;
+    }
+    reqwest::Client::builder().default_headers(headers).build().ok()
+}
+
+fn parse_traceparent(header: &str) -> Option<([u8; 16], [u8; 8])> {
+    let mut parts = header.trim().split('-');
+    let (version, trace_id, parent_id) = (parts.next()?, parts.next()?, parts.next()?);
+    if version != "00" || parts.next()?.len() != 2 {
+        return None;
+    }
+    let trace_id: [u8; 16] = unhex(trace_id)?.try_into().ok()?;
+    let parent_id: [u8; 8] = unhex(parent_id)?.try_into().ok()?;
+    // All-zero ids are invalid
+    (trace_id != [0; 16] && parent_id != [0; 8]).then_some((trace_id, parent_id))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok()).collect()
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+fn attribute(key: &str, value: &AttributeValue) -> serde_json::Value {
+    let value = match value {
+        AttributeValue::Str(text) => serde_json::json!({ "stringValue": text }),
+        // 64-bit integers are strings in OTLP's JSON
+        AttributeValue::Int(number) => serde_json::json!({ "intValue": number.to_string() }),
+        AttributeValue::Float(number) => serde_json::json!({ "doubleValue": number }),
+        AttributeValue::Bool(flag) => serde_json::json!({ "boolValue": flag }),
+    };
+    serde_json::json!({ "key": key, "value": value })
+}
+
+/// An OTLP `ExportTraceServiceRequest` in the JSON encoding
+fn export_request(spans: &[FinishedSpan], service_name: &str) -> serde_json::Value {
+    let spans: Vec<serde_json::Value> = spans.iter()
+        .map(|span| {
+            let data = &span.data;
+            let status = match &data.error {
+                Some(message) => serde_json::json!({ "code": 2, "message": message }),
+                None => serde_json::json!({ "code": 0 }),
+            };
+            serde_json::json!({
+                "traceId": hex(&data.trace_id),
+                "spanId": hex(&data.span_id),
+                "parentSpanId": data.parent_span_id.map(|id| hex(&id)).unwrap_or_default(),
+                "name": data.name,
+                "kind": match data.kind {
+                    SpanKind::Internal => 1,
+                    SpanKind::Server => 2,
+                    SpanKind::Client => 3,
+                },
+                "startTimeUnixNano": unix_nanos(data.start),
+                "endTimeUnixNano": unix_nanos(span.end),
+                "attributes": data.attributes.iter().map(|(key, value)| attribute(key, value)).collect::<Vec<_>>(),
+                "status": status,
+            })
+        })
+        .collect();
+    let version = AttributeValue::from(env!("CARGO_PKG_VERSION"));
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", &AttributeValue::from(service_name)),
+                    attribute("service.version", &version),
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": DEFAULT_SERVICE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+/// Send the queued spans; returns how many went out. They're dropped if the collector refuses
+/// them, so a collector that's down doesn't make the queue grow.
+pub async fn flush() -> anyhow::Result<usize> {
+    let config = CONFIG.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    let Some(config) = config.filter(|config| config.enabled) else {
+        return Ok(0);
+    };
+    let spans: Vec<FinishedSpan> = queue().drain(..).collect();
+    if spans.is_empty() {
+        return Ok(0);
+    }
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/v1/traces", config.endpoint))
+        .timeout(Duration::from_secs(10))
+        .json(&export_request(&spans, &config.service_name));
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Collector answered {}", response.status());
+    }
+    Ok(spans.len())
+}
+
+/// Export loop; spawn once at startup
+pub async fn run_export() {
+    let mut failing = false;
+    loop {
+        tokio::time::sleep(crate::low_power::poll_interval(EXPORT_INTERVAL).await).await;
+        match flush().await {
+            Ok(sent) => {
+                if failing || sent > 0 {
+                    log::debug!("Exported {} spans", sent);
+                }
+                failing = false;
+            }
+            // Only the first failure in a row is worth a warning
+            Err(e) if !failing => {
+                log::warn!("Trace export failed, dropping spans until the collector answers: {}", e);
+                failing = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_are_encoded_as_otlp_json() {
+        let (trace_id, parent) = parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(hex(&trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(parse_traceparent("00-4bf92f35-00f067aa0ba902b7-01").is_none());
+
+        let start = UNIX_EPOCH + Duration::from_millis(1_500);
+        let span = FinishedSpan {
+            data: SpanData {
+                trace_id,
+                span_id: [1, 2, 3, 4, 5, 6, 7, 8],
+                parent_span_id: Some(parent),
+                name: "chat llama".to_string(),
+                kind: SpanKind::Client,
+                start,
+                attributes: vec![("gen_ai.request.model", "llama".into()), ("switchboard.first_chunk_ms", 42i64.into())],
+                error: Some("rate limited".to_string()),
+            },
+            end: start + Duration::from_millis(250),
+        };
+        let request = export_request(std::slice::from_ref(&span), "switchboard");
+        let resource = &request["resourceSpans"][0];
+        assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "switchboard");
+        let encoded = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(encoded["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(encoded["spanId"], "0102030405060708");
+        assert_eq!(encoded["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(encoded["kind"], 3);
+        assert_eq!(encoded["startTimeUnixNano"], "1500000000");
+        assert_eq!(encoded["endTimeUnixNano"], "1750000000");
+        assert_eq!(encoded["attributes"][1]["value"]["intValue"], "42");
+        assert_eq!(encoded["status"]["code"], 2);
+    }
+
+    #[test]
+    fn configs_are_checked() {
+        let config = TelemetryConfig {
+            enabled: true,
+            endpoint: " http://collector.lan:4318/ ".to_string(),
+            headers: BTreeMap::from([(" x-api-key ".to_string(), "secret".to_string())]),
+            service_name: " ".to_string(),
+        };
+        let config = validate(config).unwrap();
+        assert_eq!(config.endpoint, "http://collector.lan:4318");
+        assert_eq!(config.service_name, DEFAULT_SERVICE_NAME);
+        assert!(config.headers.contains_key("x-api-key"));
+        assert!(validate(TelemetryConfig { endpoint: "collector:4318".to_string(), ..TelemetryConfig::default() }).is_err());
+        let bad_header = BTreeMap::from([("bad header".to_string(), String::new())]);
+        assert!(validate(TelemetryConfig { headers: bad_header, ..TelemetryConfig::default() }).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::cache::Cache;
+
+/// Default interval between background pulls of template repositories
+pub const DEFAULT_SYNC_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+/// Where a template came from, so users can judge whether to trust it
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TemplateProvenance {
+    pub repository: String,
+    pub commit: String,
+    pub path: String,
+    pub synced_at: String,
+}
+
+/// A prompt template or persona indexed from a template repository
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub content: String,
+    pub provenance: TemplateProvenance,
+}
+
+/// Outcome of syncing one repository
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TemplateSyncResult {
+    pub repository: String,
+    pub success: bool,
+    pub commit: Option<String>,
+    pub template_count: u32,
+    pub error: Option<String>,
+}
+
+// Written next to each checkout so listing doesn't need to shell out to git
+#[derive(Serialize, Deserialize)]
+struct SyncMarker {
+    repository: String,
+    commit: String,
+    synced_at: String,
+}
+
+const SYNC_MARKER: &str = ".mcp-switchboard-sync.json";
+
+/// Templates indexed from each checkout, keyed by checkout and sync time so a sync replaces them.
+/// The TTL picks up hand edits to a checkout.
+pub static INDEXED: Cache<Vec<PromptTemplate>> = Cache::new(Duration::from_secs(10 * 60), 64);
+
+/// Git-backed template library. Repositories are cloned under `<data_dir>/template-repos`.
+/// Markdown files under `templates/` become templates and under `personas/` become personas.
+pub struct TemplateLibrary {
+    root: PathBuf,
+}
+
+impl TemplateLibrary {
+    pub fn new(data_dir: &Path) -> Self {
+        TemplateLibrary {
+            root: data_dir.join("template-repos"),
+        }
+    }
+
+    /// Clone the repository if missing, otherwise fast-forward it, then re-index
+    pub async fn sync(&self, repository: &str) -> TemplateSyncResult {
+        match self.sync_inner(repository).await {
+            Ok((commit, count)) => TemplateSyncResult {
+                repository: repository.to_string(),
+                success: true,
+                commit: Some(commit),
+                template_count: count,
+                error: None,
+            },
+            Err(e) => {
+                log::error!("Failed to sync template repository {}: {}", repository, e);
+                TemplateSyncResult {
+                    repository: repository.to_string(),
+                    success: false,
+                    commit: None,
+                    template_count: 0,
+                    error: Some(e.to_string()),
+                }
+            }
+        }
+    }
+
+    async fn sync_inner(&self, repository: &str) -> Result<(String, u32)> {
+        validate_repository(repository)?;
+        let checkout = self.checkout_dir(repository);
+        std::fs::create_dir_all(&self.root)?;
+
+        if checkout.join(".git").exists() {
+            log::info!("Pulling template repository {}", repository);
+            run_git(&checkout, &["pull", "--ff-only", "--quiet"]).await?;
+        } else {
+            log::info!("Cloning template repository {}", repository);
+            let target = checkout.to_string_lossy().to_string();
+            run_git(&self.root, &["clone", "--depth", "1", "--quiet", "--", repository, &target]).await?;
+        }
+
+        let commit = run_git(&checkout, &["rev-parse", "--short", "HEAD"]).await?;
+        let marker = SyncMarker {
+            repository: repository.to_string(),
+            commit: commit.clone(),
+            synced_at: chrono::Utc::now().to_rfc3339(),
+        };
+        std::fs::write(checkout.join(SYNC_MARKER), serde_json::to_vec(&marker)?)?;
+
+        let count = index_checkout(&checkout, &marker)?.len() as u32;
+        log::info!("Indexed {} templates from {} at {}", count, repository, commit);
+        Ok((commit, count))
+    }
+
+    /// All templates from the given repositories that have been synced at least once
+    pub fn list(&self, repositories: &[String]) -> Result<Vec<PromptTemplate>> {
+        let mut templates = Vec::new();
+        for repository in repositories {
+            let checkout = self.checkout_dir(repository);
+            let marker_path = checkout.join(SYNC_MARKER);
+            if !marker_path.exists() {
+                continue;
+            }
+            let marker: SyncMarker = serde_json::from_slice(&std::fs::read(&marker_path)?)?;
+            let key = format!("{}@{}", checkout.display(), marker.synced_at);
+            let indexed = match INDEXED.get(&key) {
+                Some(indexed) => indexed,
+                None => {
+                    let indexed = index_checkout(&checkout, &marker)?;
+                    INDEXED.insert(&key, indexed.clone());
+                    indexed
+                }
+            };
+            templates.extend(indexed);
+        }
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// Delete the local checkout of a repository
+    pub fn remove(&self, repository: &str) -> Result<()> {
+        let checkout = self.checkout_dir(repository);
+        if checkout.exists() {
+            std::fs::remove_dir_all(&checkout)?;
+        }
+        Ok(())
+    }
+
+    fn checkout_dir(&self, repository: &str) -> PathBuf {
+        let digest = Sha256::digest(repository.as_bytes());
+        self.root.join(&format!("{:x}", digest)[..16])
+    }
+}
+
+/// Check that `repository` is a remote git URL: `https://`, `ssh://` or scp-like `git@host:path`.
+/// Anything else, a value starting with `-` in particular, could be read by git as an option.
+pub fn validate_repository(repository: &str) -> Result<()> {
+    let remote = ["https://", "ssh://", "git@"].iter()
+        .any(|scheme| repository.strip_prefix(scheme).is_some_and(|rest| !rest.is_empty()));
+    if !remote || repository.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(anyhow::anyhow!(crate::tr!("template-repository-invalid", repository = repository)));
+    }
+    Ok(())
+}
+
+async fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn index_checkout(checkout: &Path, marker: &SyncMarker) -> Result<Vec<PromptTemplate>> {
+    let mut templates = Vec::new();
+    for (dir, kind) in [("templates", "template"), ("personas", "persona")] {
+        collect_markdown(&checkout.join(dir), &mut |path| {
+            let raw = std::fs::read_to_string(path)?;
+            let relative = path.strip_prefix(checkout).unwrap_or(path).to_string_lossy().replace('\\', "/");
+            let (name, content) = split_title(&raw, path);
+            templates.push(PromptTemplate {
+                id: format!("{}:{}", marker.repository, relative),
+                name,
+                kind: kind.to_string(),
+                content,
+                provenance: TemplateProvenance {
+                    repository: marker.repository.clone(),
+                    commit: marker.commit.clone(),
+                    path: relative,
+                    synced_at: marker.synced_at.clone(),
+                },
+            });
+            Ok(())
+        })?;
+    }
+    Ok(templates)
+}
+
+fn collect_markdown(dir: &Path, visit: &mut dyn FnMut(&Path) -> Result<()>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_markdown(&path, visit)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            visit(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Use a leading `# Heading` as the name, falling back to the file stem
+fn split_title(raw: &str, path: &Path) -> (String, String) {
+    let trimmed = raw.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("# ") {
+        let (title, body) = rest.split_once('\n').unwrap_or((rest, ""));
+        return (title.trim().to_string(), body.trim().to_string());
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    (stem, raw.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_remote_urls_are_repositories() {
+        assert!(validate_repository("https://github.com/example/templates.git").is_ok());
+        assert!(validate_repository("ssh://git@example.com/templates.git").is_ok());
+        assert!(validate_repository("git@github.com:example/templates.git").is_ok());
+        assert!(validate_repository("--upload-pack=touch /tmp/pwned").is_err());
+        assert!(validate_repository("file:///etc").is_err());
+        assert!(validate_repository("/home/me/templates").is_err());
+        assert!(validate_repository("https://").is_err());
+        assert!(validate_repository("https://example.com/a b.git").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Reuse of tool results for repeated identical calls. Agent loops often call the same tool with
+//! the same arguments several times in a session; within a tool's TTL the earlier result is
+//! returned instead of running the tool again. Tools are only cached once given a TTL, since a
+//! tool with side effects must run every time.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ts_rs::TS;
+use crate::cache::Cache;
+use crate::tr;
+
+/// Longest a result may be reused
+pub const MAX_TTL_SECS: u32 = 24 * 3600;
+
+/// Tool results by call digest; entries expire sooner when their tool's TTL is shorter
+pub static RESULTS: Cache<String> = Cache::new(Duration::from_secs(MAX_TTL_SECS as u64), 256);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ToolCacheSettings {
+    /// Seconds a result is reused for tools without their own TTL; 0 runs them every time
+    pub default_ttl_secs: u32,
+    /// By `plugin::tool`, or by tool name for that tool from any plugin; 0 never caches the tool
+    pub ttl_overrides: BTreeMap<String, u32>,
+}
+
+impl ToolCacheSettings {
+    /// How long results of `tool` from `plugin` are reused, or None if they aren't
+    pub fn ttl_for(&self, plugin: &str, tool: &str) -> Option<Duration> {
+        let secs = self.ttl_overrides.get(&format!("{}::{}", plugin, tool))
+            .or_else(|| self.ttl_overrides.get(tool))
+            .copied()
+            .unwrap_or(self.default_ttl_secs);
+        (secs > 0).then(|| Duration::from_secs(secs as u64))
+    }
+}
+
+/// `settings` with blank override names dropped, or a localized error for a TTL that's too long
+pub fn validate(mut settings: ToolCacheSettings) -> Result<ToolCacheSettings, String> {
+    settings.ttl_overrides = settings.ttl_overrides.into_iter()
+        .map(|(name, ttl)| (name.trim().to_string(), ttl))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    let too_long = std::iter::once(&settings.default_ttl_secs)
+        .chain(settings.ttl_overrides.values())
+        .any(|ttl| *ttl > MAX_TTL_SECS);
+    if too_long {
+        return Err(tr!("tool-cache-ttl-too-long", hours = MAX_TTL_SECS / 3600));
+    }
+    Ok(settings)
+}
+
+/// Cache key for a call: a digest of the tool and its input, with object keys in a fixed order
+/// so the same arguments hit however the model ordered them
+pub fn key(plugin: &str, tool: &str, input: &serde_json::Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(plugin.as_bytes());
+    hasher.update([0]);
+    hasher.update(tool.as_bytes());
+    hasher.update([0]);
+    hasher.update(canonical(input).to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn canonical(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let sorted: BTreeMap<&String, serde_json::Value> = object.iter()
+                .map(|(key, value)| (key, canonical(value)))
+                .collect();
+            serde_json::Value::Object(sorted.into_iter().map(|(key, value)| (key.clone(), value)).collect())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calls_hash_the_same_regardless_of_argument_order() {
+        let a = serde_json::json!({"path": "README.md", "options": {"lines": 10, "from": 1}});
+        let b = serde_json::json!({"options": {"from": 1, "lines": 10}, "path": "README.md"});
+        assert_eq!(key("fs", "read_file", &a), key("fs", "read_file", &b));
+        assert_ne!(key("fs", "read_file", &a), key("web", "read_file", &a));
+        assert_ne!(key("fs", "read_file", &a), key("fs", "read_file", &serde_json::json!({"path": "LICENSE"})));
+    }
+
+    #[test]
+    fn ttls_come_from_overrides_then_the_default() {
+        let mut settings = ToolCacheSettings::default();
+        assert_eq!(settings.ttl_for("fs", "read_file"), None);
+        settings.ttl_overrides.insert(" read_file ".to_string(), 60);
+        settings.ttl_overrides.insert("mail::send".to_string(), 0);
+        settings.default_ttl_secs = 300;
+        let settings = validate(settings).unwrap();
+        assert_eq!(settings.ttl_for("fs", "read_file"), Some(Duration::from_secs(60)));
+        assert_eq!(settings.ttl_for("mail", "send"), None);
+        assert_eq!(settings.ttl_for("web", "search"), Some(Duration::from_secs(300)));
+
+        let mut too_long = settings.clone();
+        too_long.default_ttl_secs = MAX_TTL_SECS + 1;
+        assert!(validate(too_long).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Several tool calls from one agent step, run at the same time. Calls the model makes together
+//! don't depend on each other's results, so they're started together (up to a cap) and their
+//! outcomes are handed back in the order the model made them.
+
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+
+/// Calls run at once unless configured otherwise
+pub const DEFAULT_MAX_PARALLEL: u32 = 4;
+
+/// Most calls that may be configured to run at once
+pub const MAX_PARALLEL: u32 = 16;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ToolCall {
+    pub plugin: String,
+    pub tool: String,
+    #[ts(type = "unknown")]
+    pub input: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ToolCallOutcome {
+    pub plugin: String,
+    pub tool: String,
+    /// None when the call failed
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u32,
+}
+
+/// Err with a localized message unless `max_parallel` is between 1 and MAX_PARALLEL
+pub fn validate_max_parallel(max_parallel: u32) -> Result<u32, String> {
+    if (1..=MAX_PARALLEL).contains(&max_parallel) {
+        Ok(max_parallel)
+    } else {
+        Err(tr!("tool-parallelism-invalid", max = MAX_PARALLEL))
+    }
+}
+
+/// Run every call through `run`, at most `max_parallel` at a time, with outcomes in call order
+pub async fn run_all<F, Fut>(calls: Vec<ToolCall>, max_parallel: u32, run: F) -> Vec<ToolCallOutcome>
+where
+    F: Fn(ToolCall) -> Fut,
+    Fut: std::future::Future<Output = ToolCallOutcome>,
+{
+    futures::stream::iter(calls)
+        .map(run)
+        .buffered(max_parallel.clamp(1, MAX_PARALLEL) as usize)
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn calls_overlap_up_to_the_cap_and_come_back_in_order() {
+        let running = AtomicU32::new(0);
+        let most_running = AtomicU32::new(0);
+        let calls: Vec<ToolCall> = [40, 10, 30, 20, 5]
+            .map(|ms| ToolCall { plugin: "p".to_string(), tool: ms.to_string(), input: serde_json::json!({}) })
+            .into();
+        let outcomes = run_all(calls, 3, |call| {
+            let (running, most_running) = (&running, &most_running);
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most_running.fetch_max(now, Ordering::SeqCst);
+                let ms: u64 = call.tool.parse().unwrap();
+                tokio::time::sleep(Duration::from_millis(ms)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                ToolCallOutcome { plugin: call.plugin, tool: call.tool, result: None, error: None, duration_ms: ms as u32 }
+            }
+        }).await;
+        let order: Vec<&str> = outcomes.iter().map(|outcome| outcome.tool.as_str()).collect();
+        assert_eq!(order, vec!["40", "10", "30", "20", "5"]);
+        assert_eq!(most_running.load(Ordering::SeqCst), 3);
+
+        assert!(validate_max_parallel(0).is_err());
+        assert!(validate_max_parallel(MAX_PARALLEL + 1).is_err());
+        assert_eq!(validate_max_parallel(8), Ok(8));
+    }
+}
@@ -0,0 +1,194 @@
+//! Size limits on tool results. A tool that returns more than its limit (reading a large file,
+//! say) has the result cut down before it reaches the conversation, with a `ToolResultTruncated`
+//! marker where content was removed so the model knows it's only seeing part of it.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::compression;
+use crate::tr;
+
+/// Characters a tool result may have when no limit is configured for the tool
+pub const DEFAULT_MAX_CHARS: u32 = 20_000;
+
+/// Smallest limit accepted, so there's always room for some content beside the marker
+pub const MIN_MAX_CHARS: u32 = 200;
+
+/// A cut is moved back to a line break if there's one within this share of the kept text
+const LINE_SNAP_SHARE: usize = 5;
+
+/// What is kept of a result over its limit
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, TS)]
+#[ts(export)]
+pub enum TruncationStrategy {
+    /// The start of the result
+    Head,
+    /// The start and the end, which for logs and listings usually carry the most
+    #[default]
+    HeadTail,
+    /// The start, then the rest reduced to its most representative sentences
+    SummarizeOverflow,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ToolResultLimit {
+    pub max_chars: u32,
+    pub strategy: TruncationStrategy,
+}
+
+impl Default for ToolResultLimit {
+    fn default() -> Self {
+        ToolResultLimit { max_chars: DEFAULT_MAX_CHARS, strategy: TruncationStrategy::default() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ToolResultLimits {
+    pub default: ToolResultLimit,
+    /// By `plugin::tool`, or by tool name for that tool from any plugin
+    pub overrides: BTreeMap<String, ToolResultLimit>,
+}
+
+impl ToolResultLimits {
+    /// The limit for `tool` from `plugin`: its own override, else one for the tool name, else the default
+    pub fn limit_for(&self, plugin: &str, tool: &str) -> ToolResultLimit {
+        self.overrides.get(&format!("{}::{}", plugin, tool))
+            .or_else(|| self.overrides.get(tool))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// A result cut down to its limit
+#[derive(Debug, PartialEq)]
+pub struct Truncated {
+    /// What was kept, with the marker
+    pub text: String,
+    pub original_chars: u32,
+    /// Characters of the result kept, not counting the marker
+    pub kept_chars: u32,
+}
+
+/// `limits` with blank override names dropped, or a localized error for a limit that's too small
+pub fn validate(mut limits: ToolResultLimits) -> Result<ToolResultLimits, String> {
+    limits.overrides = limits.overrides.into_iter()
+        .map(|(name, limit)| (name.trim().to_string(), limit))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    let too_small = std::iter::once(&limits.default)
+        .chain(limits.overrides.values())
+        .any(|limit| limit.max_chars < MIN_MAX_CHARS);
+    if too_small {
+        return Err(tr!("tool-limit-too-small", min = MIN_MAX_CHARS));
+    }
+    Ok(limits)
+}
+
+/// `result` cut down to `limit`, or None if it fits
+pub fn truncate(result: &str, limit: ToolResultLimit) -> Option<Truncated> {
+    let chars: Vec<char> = result.chars().collect();
+    let max = limit.max_chars as usize;
+    if chars.len() <= max {
+        return None;
+    }
+    let (text, kept) = match limit.strategy {
+        TruncationStrategy::Head => {
+            let head = head_of(&chars, max);
+            let kept = head.chars().count();
+            (format!("{}\n{}", head, marker(chars.len(), kept, "the rest was cut")), kept)
+        }
+        TruncationStrategy::HeadTail => {
+            let head = head_of(&chars, max * 2 / 3);
+            let tail = tail_of(&chars, max - head.chars().count());
+            let kept = head.chars().count() + tail.chars().count();
+            (format!("{}\n{}\n{}", head, marker(chars.len(), kept, "the middle was cut"), tail), kept)
+        }
+        TruncationStrategy::SummarizeOverflow => {
+            let head = head_of(&chars, max / 2);
+            let overflow: String = chars[head.chars().count()..].iter().collect();
+            let budget = max - head.chars().count();
+            let ratio = budget as f32 / overflow.chars().count() as f32;
+            let summary = compression::compress(&overflow, ratio);
+            let summary_chars: Vec<char> = summary.chars().collect();
+            let summary = head_of(&summary_chars, budget);
+            let kept = head.chars().count() + summary.chars().count();
+            (format!("{}\n{}\n{}", head, marker(chars.len(), kept, "the rest is summarized below"), summary), kept)
+        }
+    };
+    Some(Truncated { text, original_chars: chars.len() as u32, kept_chars: kept as u32 })
+}
+
+/// Tells the model that what it's reading is incomplete
+fn marker(original: usize, kept: usize, how: &str) -> String {
+    format!("[ToolResultTruncated: {} of {} characters shown; {}]", kept, original, how)
+}
+
+/// Up to `max` characters from the start, ending at a line break if one is near the cut
+fn head_of(chars: &[char], max: usize) -> String {
+    let max = max.min(chars.len());
+    let snap_from = max - max / LINE_SNAP_SHARE;
+    let end = chars[snap_from..max].iter().rposition(|c| *c == '\n')
+        .map(|at| snap_from + at + 1)
+        .unwrap_or(max);
+    chars[..end].iter().collect()
+}
+
+/// Up to `max` characters from the end, starting after a line break if one is near the cut
+fn tail_of(chars: &[char], max: usize) -> String {
+    let start = chars.len() - max.min(chars.len());
+    let snap_to = start + max / LINE_SNAP_SHARE;
+    let start = chars[start..snap_to].iter().position(|c| *c == '\n')
+        .map(|at| start + at + 1)
+        .unwrap_or(start);
+    chars[start..].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max_chars: u32, strategy: TruncationStrategy) -> ToolResultLimit {
+        ToolResultLimit { max_chars, strategy }
+    }
+
+    #[test]
+    fn results_over_the_limit_are_cut_at_line_breaks_with_a_marker() {
+        let lines: String = (0..100).map(|i| format!("line {:03}\n", i)).collect();
+        assert_eq!(truncate(&lines, limit(1000, TruncationStrategy::HeadTail)), None);
+
+        let head = truncate(&lines, limit(300, TruncationStrategy::Head)).unwrap();
+        assert!(head.text.starts_with("line 000\n"));
+        assert!(head.text.ends_with("characters shown; the rest was cut]"));
+        assert!(head.text.contains("line 032\n\n[ToolResultTruncated: 297 of 900"));
+        assert_eq!(head.original_chars, 900);
+
+        let both = truncate(&lines, limit(300, TruncationStrategy::HeadTail)).unwrap();
+        let (before, after) = both.text.split_once("[ToolResultTruncated").unwrap();
+        assert!(before.ends_with("line 021\n\n"));
+        assert!(after.ends_with("line 099\n"));
+        assert!(both.text.contains("line 089\n"));
+        assert!(!both.text.contains("line 050"));
+
+        let summarized = truncate(&"The cache is warm. ".repeat(200), limit(400, TruncationStrategy::SummarizeOverflow)).unwrap();
+        assert!(summarized.text.contains("the rest is summarized below]\n"));
+        assert!(summarized.kept_chars < 500);
+    }
+
+    #[test]
+    fn overrides_apply_by_qualified_name_then_tool_name() {
+        let mut limits = ToolResultLimits::default();
+        limits.overrides.insert(" read_file ".to_string(), limit(500, TruncationStrategy::Head));
+        limits.overrides.insert("fs::read_file".to_string(), limit(900, TruncationStrategy::SummarizeOverflow));
+        let limits = validate(limits).unwrap();
+        assert_eq!(limits.limit_for("fs", "read_file").max_chars, 900);
+        assert_eq!(limits.limit_for("web", "read_file").max_chars, 500);
+        assert_eq!(limits.limit_for("web", "search"), ToolResultLimit::default());
+
+        let mut too_small = limits.clone();
+        too_small.default.max_chars = 10;
+        assert!(validate(too_small).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Usage statistics for tools. Every call is recorded with how long it took, whether it failed
+//! and how many tokens its result adds to the conversation, so slow or flaky plugins stand out and
+//! it's clear which tools actually get used.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// One recorded tool call
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolInvocation {
+    pub plugin: String,
+    pub tool: String,
+    /// RFC 3339
+    pub called_at: String,
+    pub duration_ms: u32,
+    /// The result came from the tool cache rather than the plugin
+    pub cached: bool,
+    pub error: Option<String>,
+    /// Estimated tokens of the result as the model gets it, after any truncation
+    pub result_tokens: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ToolStats {
+    pub plugin: String,
+    /// None for the totals over every tool of the plugin
+    pub tool: Option<String>,
+    pub calls: u32,
+    pub failures: u32,
+    pub cache_hits: u32,
+    /// Durations leave out cache hits, which don't reach the plugin
+    pub average_ms: Option<u32>,
+    pub p95_ms: Option<u32>,
+    pub result_tokens: u64,
+    pub last_error: Option<String>,
+    pub last_called_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct ToolStatsReport {
+    /// Most called first
+    pub plugins: Vec<ToolStats>,
+    /// Most called first
+    pub tools: Vec<ToolStats>,
+}
+
+/// Totals per plugin and per tool over `invocations`, which are oldest first
+pub fn summarize(invocations: &[ToolInvocation]) -> ToolStatsReport {
+    let mut plugins: BTreeMap<&str, Vec<&ToolInvocation>> = BTreeMap::new();
+    let mut tools: BTreeMap<(&str, &str), Vec<&ToolInvocation>> = BTreeMap::new();
+    for invocation in invocations {
+        plugins.entry(invocation.plugin.as_str()).or_default().push(invocation);
+        tools.entry((invocation.plugin.as_str(), invocation.tool.as_str())).or_default().push(invocation);
+    }
+    let mut report = ToolStatsReport {
+        plugins: plugins.into_iter().map(|(plugin, calls)| stats(plugin, None, &calls)).collect(),
+        tools: tools.into_iter().map(|((plugin, tool), calls)| stats(plugin, Some(tool), &calls)).collect(),
+    };
+    report.plugins.sort_by_key(|stats| std::cmp::Reverse(stats.calls));
+    report.tools.sort_by_key(|stats| std::cmp::Reverse(stats.calls));
+    report
+}
+
+fn stats(plugin: &str, tool: Option<&str>, calls: &[&ToolInvocation]) -> ToolStats {
+    let mut durations: Vec<u32> = calls.iter()
+        .filter(|call| !call.cached)
+        .map(|call| call.duration_ms)
+        .collect();
+    durations.sort_unstable();
+    let average_ms = (!durations.is_empty())
+        .then(|| (durations.iter().map(|ms| u64::from(*ms)).sum::<u64>() / durations.len() as u64) as u32);
+    // Nearest rank
+    let p95_ms = (!durations.is_empty())
+        .then(|| durations[(durations.len() * 95).div_ceil(100) - 1]);
+    ToolStats {
+        plugin: plugin.to_string(),
+        tool: tool.map(str::to_string),
+        calls: calls.len() as u32,
+        failures: calls.iter().filter(|call| call.error.is_some()).count() as u32,
+        cache_hits: calls.iter().filter(|call| call.cached).count() as u32,
+        average_ms,
+        p95_ms,
+        result_tokens: calls.iter().map(|call| u64::from(call.result_tokens)).sum(),
+        last_error: calls.iter().rev().find_map(|call| call.error.clone()),
+        last_called_at: calls.last().map(|call| call.called_at.clone()).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(plugin: &str, tool: &str, duration_ms: u32, cached: bool, error: Option<&str>) -> ToolInvocation {
+        ToolInvocation {
+            plugin: plugin.to_string(),
+            tool: tool.to_string(),
+            called_at: format!("2026-01-01T00:00:{:02}+00:00", duration_ms % 60),
+            duration_ms,
+            cached,
+            error: error.map(str::to_string),
+            result_tokens: 10,
+        }
+    }
+
+    #[test]
+    fn calls_are_totalled_per_plugin_and_tool() {
+        let mut calls: Vec<ToolInvocation> = (1..=20).map(|ms| call("fs", "read", ms * 10, false, None)).collect();
+        calls.push(call("fs", "read", 0, true, None));
+        calls.push(call("web", "search", 900, false, Some("timed out")));
+        calls.push(call("web", "search", 100, false, None));
+        calls.push(call("fs", "list", 5, false, None));
+        let report = summarize(&calls);
+
+        let read = &report.tools[0];
+        assert_eq!((read.plugin.as_str(), read.tool.as_deref()), ("fs", Some("read")));
+        assert_eq!((read.calls, read.cache_hits, read.failures), (21, 1, 0));
+        assert_eq!((read.average_ms, read.p95_ms), (Some(105), Some(190)));
+        assert_eq!(read.result_tokens, 210);
+
+        let fs = &report.plugins[0];
+        assert_eq!((fs.tool.as_ref(), fs.calls), (None, 22));
+        let web = &report.plugins[1];
+        assert_eq!((web.calls, web.failures, web.last_error.as_deref()), (2, 1, Some("timed out")));
+        assert_eq!(web.p95_ms, Some(900));
+    }
+}
@@ -0,0 +1,293 @@
+use std::path::{Path, PathBuf};
+use async_openai::Client;
+use async_openai::config::OpenAIConfig;
+use async_openai::types::{AudioInput, AudioResponseFormat, CreateTranscriptionRequestArgs, TimestampGranularity};
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::storage;
+
+/// Speech-to-text model used for audio and video attachments
+const TRANSCRIPTION_MODEL: &str = "openai/whisper-large-v3";
+
+/// File extensions accepted by the transcription endpoint
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "mpga", "mpeg", "m4a", "wav", "ogg", "flac"];
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "webm", "mov", "mkv"];
+
+/// A timestamped span of speech. `speaker` is only set when the provider returns diarization labels.
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct TranscriptSegment {
+    pub start: f64,
+    pub end: f64,
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+/// Transcript of an ingested audio/video file, linked to a conversation
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct Transcript {
+    pub id: String,
+    pub conversation_id: String,
+    pub source_file: String,
+    /// Stored copy of the media in the attachment store
+    #[serde(default)]
+    pub attachment_id: Option<String>,
+    pub media_kind: String,
+    pub language: Option<String>,
+    pub duration: Option<f64>,
+    pub created_at: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+// Subset of the verbose_json response; speaker labels are optional extensions some providers add
+#[derive(Deserialize)]
+struct VerboseTranscription {
+    text: String,
+    language: Option<String>,
+    duration: Option<f64>,
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+}
+
+#[derive(Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    end: f64,
+    text: String,
+    #[serde(alias = "speaker_id")]
+    speaker: Option<serde_json::Value>,
+}
+
+/// One segment as it appears in prompt context, alongside its bare text
+pub struct ContextLine {
+    pub rendered: String,
+    pub text: String,
+}
+
+impl Transcript {
+    /// Render the transcript as plain text with timestamps, suitable for prompt context
+    pub fn to_context_text(&self) -> String {
+        let mut out = format!("Transcript of {}:\n", self.source_file);
+        for line in self.context_lines() {
+            out.push_str(&line.rendered);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn context_lines(&self) -> Vec<ContextLine> {
+        self.segments.iter()
+            .map(|segment| {
+                let speaker = segment.speaker.as_deref().unwrap_or("Speaker");
+                ContextLine {
+                    rendered: format!(
+                        "[{} - {}] {}: {}",
+                        format_timestamp(segment.start),
+                        format_timestamp(segment.end),
+                        speaker,
+                        segment.text.trim()
+                    ),
+                    text: segment.text.trim().to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn format_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, (total % 3600) / 60, total % 60)
+}
+
+/// Classify a file as "audio" or "video" by extension, or None if it cannot be transcribed
+pub fn media_kind(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    if AUDIO_EXTENSIONS.contains(&ext.as_str()) {
+        Some("audio")
+    } else if VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+        Some("video")
+    } else {
+        None
+    }
+}
+
+/// Transcribe an audio or video file via the Together.ai speech-to-text endpoint
+pub async fn transcribe_file(api_key: String, path: &Path, conversation_id: &str) -> Result<Transcript> {
+    let kind = media_kind(path)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported media type: {}", path.display()))?;
+    if !path.exists() {
+        return Err(anyhow::anyhow!("File not found: {}", path.display()));
+    }
+
+    let config = OpenAIConfig::new()
+        .with_api_key(api_key)
+        .with_api_base("https://api.together.xyz/v1");
+    let client = Client::with_config(config);
+
+    let request = CreateTranscriptionRequestArgs::default()
+        .file(AudioInput::from(path))
+        .model(TRANSCRIPTION_MODEL)
+        .response_format(AudioResponseFormat::VerboseJson)
+        .timestamp_granularities(vec![TimestampGranularity::Segment])
+        .build()?;
+
+    let raw = client.audio().transcribe_raw(request).await?;
+    let response: VerboseTranscription = serde_json::from_slice(&raw)?;
+    let language = response.language.clone();
+    let duration = response.duration;
+    let segments = transcript_segments(response);
+
+    Ok(Transcript {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.to_string(),
+        source_file: path.to_string_lossy().to_string(),
+        attachment_id: None,
+        media_kind: kind.to_string(),
+        language,
+        duration,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        segments,
+    })
+}
+
+/// The response's segments, with numeric speaker ids labelled, or its whole text as one segment
+fn transcript_segments(response: VerboseTranscription) -> Vec<TranscriptSegment> {
+    let mut segments: Vec<TranscriptSegment> = response.segments.into_iter().map(|s| TranscriptSegment {
+        start: s.start,
+        end: s.end,
+        speaker: s.speaker.map(|v| match v {
+            serde_json::Value::String(label) => label,
+            other => format!("Speaker {}", other),
+        }),
+        text: s.text,
+    }).collect();
+
+    // Providers that don't return segments still give us the full text
+    if segments.is_empty() && !response.text.trim().is_empty() {
+        segments.push(TranscriptSegment {
+            start: 0.0,
+            end: response.duration.unwrap_or(0.0),
+            speaker: None,
+            text: response.text,
+        });
+    }
+    segments
+}
+
+/// File-backed storage of transcripts, one directory per conversation
+pub struct TranscriptStore {
+    root: PathBuf,
+}
+
+impl TranscriptStore {
+    pub fn new(data_dir: &Path) -> Self {
+        TranscriptStore {
+            root: data_dir.join("transcripts"),
+        }
+    }
+
+    pub fn save(&self, transcript: &Transcript) -> Result<()> {
+        let dir = storage::conversation_dir(&self.root, &transcript.conversation_id)?;
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.json", transcript.id));
+        std::fs::write(&path, serde_json::to_vec_pretty(transcript)?)?;
+        log::info!("Transcript saved to: {:?}", path);
+        Ok(())
+    }
+
+    pub fn list(&self, conversation_id: &str) -> Result<Vec<Transcript>> {
+        let dir = storage::conversation_dir(&self.root, conversation_id)?;
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut transcripts = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let data = std::fs::read(&path)?;
+                transcripts.push(serde_json::from_slice::<Transcript>(&data)?);
+            }
+        }
+        transcripts.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(transcripts)
+    }
+
+    /// Move a conversation's transcripts to another conversation, e.g. when the two are merged
+    pub fn reassign(&self, from: &str, to: &str) -> Result<u32> {
+        let transcripts = self.list(from)?;
+        for transcript in &transcripts {
+            self.save(&Transcript { conversation_id: to.to_string(), ..transcript.clone() })?;
+        }
+        if !transcripts.is_empty() {
+            std::fs::remove_dir_all(storage::conversation_dir(&self.root, from)?)?;
+        }
+        Ok(transcripts.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments_of(response: serde_json::Value) -> Vec<TranscriptSegment> {
+        transcript_segments(serde_json::from_value(response).unwrap())
+    }
+
+    #[test]
+    fn speaker_labels_and_ids_both_name_the_speaker() {
+        let segments = segments_of(serde_json::json!({
+            "text": "Hello there. Hi.",
+            "language": "en",
+            "duration": 4.0,
+            "segments": [
+                { "start": 0.0, "end": 2.5, "text": " Hello there.", "speaker": "Alice" },
+                { "start": 2.5, "end": 4.0, "text": " Hi.", "speaker_id": 1 },
+                { "start": 4.0, "end": 4.0, "text": "" },
+            ],
+        }));
+        let speakers: Vec<Option<&str>> = segments.iter().map(|s| s.speaker.as_deref()).collect();
+        assert_eq!(speakers, vec![Some("Alice"), Some("Speaker 1"), None]);
+        assert_eq!(segments[1].start, 2.5);
+    }
+
+    #[test]
+    fn a_response_without_segments_becomes_one_segment() {
+        let segments = segments_of(serde_json::json!({ "text": "Just the text", "duration": 7.5 }));
+        assert_eq!(segments.len(), 1);
+        assert_eq!((segments[0].start, segments[0].end), (0.0, 7.5));
+        assert_eq!(segments[0].text, "Just the text");
+        assert!(segments_of(serde_json::json!({ "text": "  " })).is_empty());
+    }
+
+    #[test]
+    fn context_lines_carry_timestamps_and_speakers() {
+        let transcript = Transcript {
+            id: "t".to_string(),
+            conversation_id: "c".to_string(),
+            source_file: "call.m4a".to_string(),
+            attachment_id: None,
+            media_kind: "audio".to_string(),
+            language: None,
+            duration: None,
+            created_at: String::new(),
+            segments: segments_of(serde_json::json!({
+                "text": "",
+                "segments": [
+                    { "start": 3725.4, "end": 3730.0, "text": " Agreed. ", "speaker": "Bob" },
+                    { "start": -1.0, "end": 1.0, "text": "Hm" },
+                ],
+            })),
+        };
+        assert_eq!(
+            transcript.to_context_text(),
+            "Transcript of call.m4a:\n[01:02:05 - 01:02:10] Bob: Agreed.\n[00:00:00 - 00:00:01] Speaker: Hm\n",
+        );
+        assert_eq!(transcript.context_lines()[0].text, "Agreed.");
+        assert_eq!(media_kind(Path::new("call.M4A")), Some("audio"));
+        assert_eq!(media_kind(Path::new("clip.webm")), Some("video"));
+        assert_eq!(media_kind(Path::new("notes.txt")), None);
+    }
+}
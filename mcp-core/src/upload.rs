@@ -0,0 +1,344 @@
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use anyhow::Result;
+use ts_rs::TS;
+use crate::history::HistoryStore;
+use crate::storage::{self, Attachment, AttachmentKind, AttachmentStore};
+use crate::tr;
+
+/// Bytes per chunk; every chunk but the last must be exactly this long
+pub const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Largest file accepted, since `begin` reserves the whole size on disk up front
+pub const MAX_UPLOAD_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Data directory holding uploads in progress, one directory per upload
+const UPLOADS_DIR: &str = "uploads";
+
+const SESSION_FILE: &str = "upload.json";
+const DATA_FILE: &str = "data.partial";
+
+/// Uploads untouched for this long are discarded when a new one begins
+const STALE_UPLOAD_SECS: i64 = 24 * 3600;
+
+/// Serializes session updates, since chunks may arrive concurrently
+static SESSIONS: Mutex<()> = Mutex::new(());
+
+/// Persisted state of an upload, so it can resume after a restart
+#[derive(Serialize, Deserialize)]
+struct UploadSession {
+    id: String,
+    conversation_id: String,
+    file_name: String,
+    kind: AttachmentKind,
+    total_bytes: u64,
+    sha256: String,
+    received: Vec<u32>,
+    updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct UploadStatus {
+    pub upload_id: String,
+    pub chunk_size: u64,
+    pub chunk_count: u32,
+    pub received_bytes: u64,
+    /// Chunk indexes still to send, in order
+    pub missing_chunks: Vec<u32>,
+}
+
+impl UploadSession {
+    fn chunk_count(&self) -> u32 {
+        self.total_bytes.div_ceil(CHUNK_SIZE).max(1) as u32
+    }
+
+    fn chunk_len(&self, index: u32) -> u64 {
+        let start = u64::from(index) * CHUNK_SIZE;
+        (self.total_bytes - start).min(CHUNK_SIZE)
+    }
+
+    fn status(&self) -> UploadStatus {
+        let missing_chunks: Vec<u32> = (0..self.chunk_count()).filter(|i| !self.received.contains(i)).collect();
+        UploadStatus {
+            upload_id: self.id.clone(),
+            chunk_size: CHUNK_SIZE,
+            chunk_count: self.chunk_count(),
+            received_bytes: self.received.iter().map(|&i| self.chunk_len(i)).sum(),
+            missing_chunks,
+        }
+    }
+}
+
+/// Start an upload, or resume the unfinished one for the same file and conversation
+pub fn begin(
+    data_dir: &Path,
+    conversation_id: &str,
+    file_name: &str,
+    total_bytes: u64,
+    sha256: &str,
+    kind: Option<AttachmentKind>,
+) -> Result<UploadStatus> {
+    storage::conversation_dir(&data_dir.join(UPLOADS_DIR), conversation_id)?;
+    let sha256 = sha256.trim().to_lowercase();
+    if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!(tr!("upload-invalid-sha256")));
+    }
+    if total_bytes > MAX_UPLOAD_BYTES {
+        return Err(anyhow::anyhow!(tr!("upload-too-large", bytes = total_bytes, limit = MAX_UPLOAD_BYTES)));
+    }
+    let _guard = SESSIONS.lock().map_err(|_| anyhow::anyhow!("Upload state is poisoned"))?;
+    prune_stale(data_dir)?;
+
+    for session in sessions(data_dir)? {
+        if session.sha256 == sha256 && session.conversation_id == conversation_id && session.total_bytes == total_bytes {
+            log::info!("Resuming upload {} of {}", session.id, file_name);
+            return Ok(session.status());
+        }
+    }
+
+    let session = UploadSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.to_string(),
+        file_name: Path::new(file_name).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow::anyhow!(tr!("upload-invalid-file-name", file_name = file_name)))?,
+        kind: kind.unwrap_or_else(|| AttachmentKind::from_path(Path::new(file_name))),
+        total_bytes,
+        sha256,
+        received: Vec::new(),
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let dir = session_dir(data_dir, &session.id)?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::File::create(dir.join(DATA_FILE))?.set_len(total_bytes)?;
+    save_session(&dir, &session)?;
+    log::info!("Started upload {} of {} ({} bytes)", session.id, session.file_name, total_bytes);
+    Ok(session.status())
+}
+
+/// Write chunk `index` after checking it against its SHA-256. Sending a chunk twice is harmless.
+pub fn write_chunk(data_dir: &Path, upload_id: &str, index: u32, data: &[u8], sha256: &str) -> Result<UploadStatus> {
+    let actual = format!("{:x}", Sha256::digest(data));
+    if actual != sha256.trim().to_lowercase() {
+        return Err(anyhow::anyhow!(tr!("upload-chunk-checksum", index = index)));
+    }
+    let dir = session_dir(data_dir, upload_id)?;
+
+    // Check against the session before writing, then record the chunk after
+    let chunk_len = {
+        let _guard = SESSIONS.lock().map_err(|_| anyhow::anyhow!("Upload state is poisoned"))?;
+        let session = load_session(&dir)?;
+        if index >= session.chunk_count() {
+            return Err(anyhow::anyhow!(tr!("upload-chunk-past-end", index = index)));
+        }
+        session.chunk_len(index)
+    };
+    if data.len() as u64 != chunk_len {
+        return Err(anyhow::anyhow!(tr!("upload-chunk-size", index = index, expected = chunk_len, actual = data.len())));
+    }
+
+    let mut file = OpenOptions::new().write(true).open(dir.join(DATA_FILE))?;
+    file.seek(SeekFrom::Start(u64::from(index) * CHUNK_SIZE))?;
+    file.write_all(data)?;
+    file.sync_data()?;
+
+    let _guard = SESSIONS.lock().map_err(|_| anyhow::anyhow!("Upload state is poisoned"))?;
+    let mut session = load_session(&dir)?;
+    if let Err(position) = session.received.binary_search(&index) {
+        session.received.insert(position, index);
+    }
+    session.updated_at = chrono::Utc::now().to_rfc3339();
+    save_session(&dir, &session)?;
+    Ok(session.status())
+}
+
+pub fn status(data_dir: &Path, upload_id: &str) -> Result<UploadStatus> {
+    let _guard = SESSIONS.lock().map_err(|_| anyhow::anyhow!("Upload state is poisoned"))?;
+    Ok(load_session(&session_dir(data_dir, upload_id)?)?.status())
+}
+
+/// Check the whole file against the checksum given at the start and move it into the attachment store
+pub fn finish(data_dir: &Path, store: &HistoryStore, upload_id: &str) -> Result<Attachment> {
+    let _guard = SESSIONS.lock().map_err(|_| anyhow::anyhow!("Upload state is poisoned"))?;
+    let dir = session_dir(data_dir, upload_id)?;
+    let session = load_session(&dir)?;
+    let status = session.status();
+    if !status.missing_chunks.is_empty() {
+        return Err(anyhow::anyhow!(tr!("upload-incomplete", missing = status.missing_chunks.len(), total = status.chunk_count)));
+    }
+
+    let data = dir.join(DATA_FILE);
+    let (_, sha256) = storage::digest_file(&data)?;
+    if sha256 != session.sha256 {
+        // Every chunk matched, so the file changed mid-upload or the starting checksum was wrong
+        std::fs::remove_dir_all(&dir)?;
+        return Err(anyhow::anyhow!(tr!("upload-checksum-mismatch")));
+    }
+
+    // Rename to the original name first so the stored copy keeps its extension and name
+    let named = dir.join("complete").join(&session.file_name);
+    std::fs::create_dir_all(dir.join("complete"))?;
+    std::fs::rename(&data, &named)?;
+    let attachment = AttachmentStore::new(data_dir).adopt(store, &named, &session.conversation_id, session.kind)?;
+    std::fs::remove_dir_all(&dir)?;
+    log::info!("Finished upload {} as attachment {}", upload_id, attachment.id);
+    Ok(attachment)
+}
+
+pub fn cancel(data_dir: &Path, upload_id: &str) -> Result<()> {
+    let _guard = SESSIONS.lock().map_err(|_| anyhow::anyhow!("Upload state is poisoned"))?;
+    let dir = session_dir(data_dir, upload_id)?;
+    if !dir.exists() {
+        return Err(anyhow::anyhow!(tr!("upload-not-found", upload_id = upload_id)));
+    }
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Upload ids name directories, so only the UUIDs handed out by `begin` are accepted
+fn session_dir(data_dir: &Path, upload_id: &str) -> Result<PathBuf> {
+    uuid::Uuid::parse_str(upload_id).map_err(|_| anyhow::anyhow!(tr!("upload-invalid-id", upload_id = upload_id)))?;
+    Ok(data_dir.join(UPLOADS_DIR).join(upload_id))
+}
+
+fn load_session(dir: &Path) -> Result<UploadSession> {
+    let data = std::fs::read(dir.join(SESSION_FILE))
+        .map_err(|_| anyhow::anyhow!(tr!("upload-gone")))?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+fn save_session(dir: &Path, session: &UploadSession) -> Result<()> {
+    let partial = dir.join(format!("{}.partial", SESSION_FILE));
+    std::fs::write(&partial, serde_json::to_vec(session)?)?;
+    std::fs::rename(partial, dir.join(SESSION_FILE))?;
+    Ok(())
+}
+
+fn sessions(data_dir: &Path) -> Result<Vec<UploadSession>> {
+    let root = data_dir.join(UPLOADS_DIR);
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+    let mut sessions = Vec::new();
+    for entry in std::fs::read_dir(root)? {
+        if let Ok(session) = load_session(&entry?.path()) {
+            sessions.push(session);
+        }
+    }
+    Ok(sessions)
+}
+
+fn prune_stale(data_dir: &Path) -> Result<()> {
+    let now = chrono::Utc::now();
+    for session in sessions(data_dir)? {
+        let stale = chrono::DateTime::parse_from_rfc3339(&session.updated_at)
+            .map(|at| now.signed_duration_since(at).num_seconds() > STALE_UPLOAD_SECS)
+            .unwrap_or(true);
+        if stale {
+            log::info!("Discarding stale upload {} of {}", session.id, session.file_name);
+            std::fs::remove_dir_all(session_dir(data_dir, &session.id)?)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_data_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mcp-upload-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    /// The results here have no Debug, so `unwrap_err` isn't available
+    fn error_of<T>(result: Result<T>) -> String {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    /// Two chunks: a full one and three bytes
+    fn two_chunk_file() -> Vec<u8> {
+        (0..CHUNK_SIZE + 3).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn the_same_file_in_the_same_conversation_resumes() {
+        let dir = temp_data_dir();
+        let file = two_chunk_file();
+        let sha256 = sha256_hex(&file);
+        let started = begin(&dir, "c1", "notes.txt", file.len() as u64, &sha256, None).unwrap();
+        assert_eq!(started.missing_chunks, vec![0, 1]);
+        let tail = &file[CHUNK_SIZE as usize..];
+        write_chunk(&dir, &started.upload_id, 1, tail, &sha256_hex(tail)).unwrap();
+
+        let resumed = begin(&dir, "c1", "notes.txt", file.len() as u64, &sha256.to_uppercase(), None).unwrap();
+        assert_eq!(resumed.upload_id, started.upload_id);
+        assert_eq!(resumed.missing_chunks, vec![0]);
+        assert_eq!(resumed.received_bytes, 3);
+
+        // Another conversation, or another file, starts over
+        let elsewhere = begin(&dir, "c2", "notes.txt", file.len() as u64, &sha256, None).unwrap();
+        assert_ne!(elsewhere.upload_id, started.upload_id);
+        assert_eq!(elsewhere.missing_chunks, vec![0, 1]);
+        let other = begin(&dir, "c1", "notes.txt", file.len() as u64, &sha256_hex(b"other"), None).unwrap();
+        assert_ne!(other.upload_id, started.upload_id);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chunks_past_the_end_or_of_the_wrong_size_are_refused() {
+        let dir = temp_data_dir();
+        let file = two_chunk_file();
+        let upload = begin(&dir, "c", "notes.txt", file.len() as u64, &sha256_hex(&file), None).unwrap();
+
+        let tail = &file[CHUNK_SIZE as usize..];
+        let error = error_of(write_chunk(&dir, &upload.upload_id, 2, tail, &sha256_hex(tail)));
+        assert_eq!(error, tr!("upload-chunk-past-end", index = 2));
+        let short = &file[..10];
+        let error = error_of(write_chunk(&dir, &upload.upload_id, 0, short, &sha256_hex(short)));
+        assert_eq!(error, tr!("upload-chunk-size", index = 0, expected = CHUNK_SIZE, actual = 10));
+        let error = error_of(write_chunk(&dir, &upload.upload_id, 1, tail, &sha256_hex(short)));
+        assert_eq!(error, tr!("upload-chunk-checksum", index = 1));
+
+        assert_eq!(status(&dir, &upload.upload_id).unwrap().missing_chunks, vec![0, 1]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_file_that_does_not_match_its_checksum_is_discarded() {
+        let dir = temp_data_dir();
+        let store = HistoryStore::open(&dir).unwrap();
+        let file = b"the file as sent".to_vec();
+        let claimed = sha256_hex(b"the file as meant");
+        let upload = begin(&dir, "c", "notes.txt", file.len() as u64, &claimed, None).unwrap();
+        write_chunk(&dir, &upload.upload_id, 0, &file, &sha256_hex(&file)).unwrap();
+
+        let error = error_of(finish(&dir, &store, &upload.upload_id));
+        assert_eq!(error, tr!("upload-checksum-mismatch"));
+        assert_eq!(error_of(status(&dir, &upload.upload_id)), tr!("upload-gone"));
+        drop(store);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn files_over_the_limit_are_refused_before_taking_space() {
+        let dir = temp_data_dir();
+        let error = error_of(begin(&dir, "c", "huge.bin", MAX_UPLOAD_BYTES + 1, &sha256_hex(b""), None));
+        assert_eq!(error, tr!("upload-too-large", bytes = MAX_UPLOAD_BYTES + 1, limit = MAX_UPLOAD_BYTES));
+        assert!(sessions(&dir).unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
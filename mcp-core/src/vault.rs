@@ -0,0 +1,157 @@
+//! Secrets for tools and integrations, such as a GitHub token, a search API key or SMTP
+//! credentials. They're kept in the encrypted config beside the provider key, each with the
+//! integration that owns it, and only that integration can read it back. The UI only ever sees
+//! metadata.
+//!
+//! Owners are namespaced, `plugin:<directory>` or `integration:<name>`, so a plugin can't read an
+//! integration's secret by giving itself the integration's name. A plugin's directory is chosen
+//! where it's installed, not by its manifest.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tr;
+
+/// Longest secret name accepted
+pub const MAX_NAME_CHARS: usize = 64;
+
+const PLUGIN_OWNER: &str = "plugin:";
+const INTEGRATION_OWNER: &str = "integration:";
+
+/// The owner a plugin's secrets are stored under, from the directory the plugin is in
+pub fn plugin_owner(id: &str) -> String {
+    format!("{}{}", PLUGIN_OWNER, id)
+}
+
+pub fn integration_owner(name: &str) -> String {
+    format!("{}{}", INTEGRATION_OWNER, name)
+}
+
+/// Whether a secret stored for `secret_owner` may be read by `owner`. Secrets from before owners
+/// had namespaces are left to the integration of that name; plugins never read them.
+fn owns(secret_owner: &str, owner: &str) -> bool {
+    secret_owner == owner || owner.strip_prefix(INTEGRATION_OWNER) == Some(secret_owner)
+}
+
+/// A secret as persisted in config
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Secret {
+    pub name: String,
+    /// The plugin or integration allowed to read it, as `plugin_owner` or `integration_owner` give it
+    pub owner: String,
+    pub value: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Secret metadata safe to show in the UI
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[ts(export)]
+pub struct SecretInfo {
+    pub name: String,
+    pub owner: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Secret {
+    pub fn info(&self) -> SecretInfo {
+        SecretInfo {
+            name: self.name.clone(),
+            owner: self.owner.clone(),
+            created_at: self.created_at.clone(),
+            updated_at: self.updated_at.clone(),
+        }
+    }
+}
+
+/// Add a secret, or replace the value and owner of the one with the same name
+pub fn store(secrets: &mut Vec<Secret>, name: &str, owner: &str, value: String) -> Result<SecretInfo, String> {
+    let (name, owner) = (name.trim(), owner.trim());
+    let valid_name = !name.is_empty()
+        && name.chars().count() <= MAX_NAME_CHARS
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid_name {
+        return Err(tr!("secret-name-invalid", max = MAX_NAME_CHARS));
+    }
+    let namespaced = [PLUGIN_OWNER, INTEGRATION_OWNER].into_iter()
+        .any(|prefix| owner.strip_prefix(prefix).is_some_and(|rest| !rest.trim().is_empty()));
+    if !namespaced {
+        return Err(tr!("secret-owner-missing"));
+    }
+    if value.is_empty() {
+        return Err(tr!("secret-value-empty"));
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    let secret = match secrets.iter_mut().find(|secret| secret.name == name) {
+        Some(secret) => {
+            secret.owner = owner.to_string();
+            secret.value = value;
+            secret.updated_at = now;
+            secret
+        }
+        None => {
+            secrets.push(Secret {
+                name: name.to_string(),
+                owner: owner.to_string(),
+                value,
+                created_at: now.clone(),
+                updated_at: now,
+            });
+            secrets.last_mut().expect("just pushed")
+        }
+    };
+    Ok(secret.info())
+}
+
+/// The value of `name` if `owner` owns it. A secret held by another integration reads the same
+/// as a missing one, so an integration can't probe for what others store.
+pub fn read<'a>(secrets: &'a [Secret], owner: &str, name: &str) -> Option<&'a str> {
+    secrets.iter()
+        .find(|secret| secret.name == name && owns(&secret.owner, owner))
+        .map(|secret| secret.value.as_str())
+}
+
+/// The value of `name` from config if `owner` owns it
+pub fn read_stored(owner: &str, name: &str) -> anyhow::Result<Option<String>> {
+    let secrets = crate::config::ConfigManager::new()?.get_secrets()?;
+    Ok(read(&secrets, owner, name).map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_owner_reads_a_secret() {
+        let (github, mailer) = (integration_owner("github"), plugin_owner("mailer"));
+        let mut secrets = Vec::new();
+        store(&mut secrets, " github_token ", &github, "ghp_1".to_string()).unwrap();
+        store(&mut secrets, "smtp.password", &mailer, "hunter2".to_string()).unwrap();
+        assert_eq!(read(&secrets, &github, "github_token"), Some("ghp_1"));
+        assert_eq!(read(&secrets, &mailer, "github_token"), None);
+        assert_eq!(read(&secrets, &github, "missing"), None);
+
+        let replaced = store(&mut secrets, "github_token", &github, "ghp_2".to_string()).unwrap();
+        assert_eq!(secrets.len(), 2);
+        assert_eq!(read(&secrets, &github, "github_token"), Some("ghp_2"));
+        assert_eq!(replaced.created_at, secrets[0].created_at);
+
+        assert!(store(&mut secrets, "has space", &github, "x".to_string()).is_err());
+        assert!(store(&mut secrets, "token", " ", "x".to_string()).is_err());
+        assert!(store(&mut secrets, "token", "github", "x".to_string()).is_err());
+        assert!(store(&mut secrets, "token", "plugin:", "x".to_string()).is_err());
+        assert!(store(&mut secrets, "token", &github, String::new()).is_err());
+    }
+
+    #[test]
+    fn a_plugin_named_like_an_integration_reads_nothing_of_it() {
+        let mut secrets = Vec::new();
+        store(&mut secrets, "github_token", &integration_owner("github"), "ghp_1".to_string()).unwrap();
+        assert_eq!(read(&secrets, &plugin_owner("github"), "github_token"), None);
+
+        // Stored before owners had namespaces: the integration keeps it, a plugin doesn't get it
+        secrets.push(Secret { owner: "github".to_string(), name: "legacy".to_string(), ..secrets[0].clone() });
+        assert_eq!(read(&secrets, &integration_owner("github"), "legacy"), Some("ghp_1"));
+        assert_eq!(read(&secrets, &plugin_owner("github"), "legacy"), None);
+    }
+}
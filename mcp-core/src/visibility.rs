@@ -0,0 +1,124 @@
+//! Holding streamed answers back from a window that can't be seen. With the setting on, a
+//! minimized or hidden window gets no chat events while an answer streams; the answer is still
+//! read from the provider and saved as it arrives, and what was held back is sent in one go
+//! once the window is shown again. Long background generations then cost the webview nothing.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use futures::StreamExt;
+use tokio::sync::watch;
+use crate::config::ConfigManager;
+use crate::generation::MessageStream;
+use crate::StreamMessage;
+
+fn visible() -> &'static watch::Sender<bool> {
+    static VISIBLE: OnceLock<watch::Sender<bool>> = OnceLock::new();
+    VISIBLE.get_or_init(|| watch::channel(true).0)
+}
+
+/// Note whether the window can be seen; showing it releases everything held back
+pub fn set_visible(is_visible: bool) {
+    visible().send_if_modified(|current| std::mem::replace(current, is_visible) != is_visible);
+}
+
+/// Whether the setting is on and the window hidden, so chat events aren't being sent
+pub fn holding() -> bool {
+    !*visible().borrow() && enabled()
+}
+
+fn enabled() -> bool {
+    ConfigManager::new()
+        .and_then(|config_manager| config_manager.get_pause_hidden_streams())
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read the hidden stream setting, streaming as usual: {}", e);
+            false
+        })
+}
+
+struct Held {
+    stream: Option<MessageStream>,
+    visible: watch::Receiver<bool>,
+    held: VecDeque<StreamMessage>,
+    ready: VecDeque<StreamMessage>,
+}
+
+/// `stream`, held back while the window is hidden if the setting is on. The stream is read at
+/// its own pace either way, so whatever it drives (saving the answer, read-aloud) carries on.
+pub fn hold_while_hidden(stream: MessageStream) -> MessageStream {
+    if !enabled() {
+        return stream;
+    }
+    let held = Held { stream: Some(stream), visible: visible().subscribe(), held: VecDeque::new(), ready: VecDeque::new() };
+    Box::pin(futures::stream::unfold(held, |mut held| async move {
+        loop {
+            if let Some(message) = held.ready.pop_front() {
+                return Some((message, held));
+            }
+            let hidden = !*held.visible.borrow_and_update();
+            if !hidden {
+                held.ready.append(&mut held.held);
+                if !held.ready.is_empty() {
+                    continue;
+                }
+            }
+            let Some(stream) = held.stream.as_mut() else {
+                if held.held.is_empty() {
+                    return None;
+                }
+                // Ended while hidden; what's held waits for the window
+                if held.visible.changed().await.is_err() {
+                    held.ready.append(&mut held.held);
+                }
+                continue;
+            };
+            tokio::select! {
+                message = stream.next() => match message {
+                    Some(message) if hidden => hold(&mut held.held, message),
+                    Some(message) => held.ready.push_back(message),
+                    None => held.stream = None,
+                },
+                // Shown again, or the sender is gone and nothing will show it
+                changed = held.visible.changed(), if hidden => if changed.is_err() {
+                    held.ready.append(&mut held.held);
+                },
+            }
+        }
+    }))
+}
+
+/// Add `message` to what's held back, merging text so it goes out as few messages as possible
+fn hold(held: &mut VecDeque<StreamMessage>, message: StreamMessage) {
+    match (held.back_mut(), message) {
+        (Some(StreamMessage::Content(text) | StreamMessage::Replace(text)), StreamMessage::Content(more)) => text.push_str(&more),
+        (_, StreamMessage::Replace(text)) => {
+            held.retain(|message| !matches!(message, StreamMessage::Content(_) | StreamMessage::Replace(_)));
+            held.push_back(StreamMessage::Replace(text));
+        }
+        (_, message) => held.push_back(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_text_is_merged() {
+        let mut held = VecDeque::new();
+        for message in [
+            StreamMessage::Content("Hel".to_string()),
+            StreamMessage::Content("lo".to_string()),
+            StreamMessage::ProviderSwitched { from: "openai".to_string(), to: "groq".to_string(), model: "groq/llama".to_string(), reason: "503".to_string() },
+            StreamMessage::Content("Draft".to_string()),
+            StreamMessage::Replace("Final".to_string()),
+            StreamMessage::Content(" answer".to_string()),
+            StreamMessage::Complete,
+        ] {
+            hold(&mut held, message);
+        }
+        assert!(matches!(
+            Vec::from(held).as_slice(),
+            [StreamMessage::ProviderSwitched { .. }, StreamMessage::Replace(text), StreamMessage::Complete] if text == "Final answer"
+        ));
+    }
+}
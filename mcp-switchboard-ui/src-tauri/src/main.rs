@@ -7,58 +7,1194 @@ use tauri::Emitter;
 // Import the pure business logic functions from mcp-core
 use mcp_core::{BuildInfo, StreamMessage};
 
+/// Call a command with its timing and payload sizes recorded for `get_ipc_stats`. The short form
+/// calls the mcp-core function of the same name; the long form times an arbitrary body.
+macro_rules! instrumented {
+    ($command:ident($($arg:ident),*)) => {
+        instrumented!($command($($arg),*), mcp_core::$command($($arg),*))
+    };
+    ($command:ident($($arg:ident),*), $body:expr) => {{
+        let request_bytes = mcp_core::payload_size(&($(&$arg,)*));
+        mcp_core::instrument(stringify!($command), request_bytes, $body).await
+    }};
+}
+
 // Tauri command wrappers - ONLY place with #[tauri::command] macros!
 #[tauri::command]
-async fn get_api_config() -> Result<Option<String>, String> {
-    mcp_core::get_api_config().await
+async fn get_api_config() -> Result<Option<String>, String> {
+    instrumented!(get_api_config())
+}
+
+#[tauri::command]
+async fn save_api_config(api_key: String) -> Result<(), String> {
+    instrumented!(save_api_config(api_key))
+}
+
+#[tauri::command]
+async fn list_providers() -> Result<Vec<mcp_core::ProviderStatus>, String> {
+    instrumented!(list_providers())
+}
+
+#[tauri::command]
+async fn set_provider_api_key(provider: mcp_core::Provider, api_key: Option<String>) -> Result<(), String> {
+    instrumented!(set_provider_api_key(provider, api_key))
+}
+
+#[tauri::command]
+async fn list_azure_profiles() -> Result<Vec<mcp_core::AzureProfile>, String> {
+    instrumented!(list_azure_profiles())
+}
+
+#[tauri::command]
+async fn save_azure_profile(profile: mcp_core::AzureProfile) -> Result<mcp_core::AzureProfile, String> {
+    instrumented!(save_azure_profile(profile))
+}
+
+#[tauri::command]
+async fn remove_azure_profile(name: String) -> Result<bool, String> {
+    instrumented!(remove_azure_profile(name))
+}
+
+#[tauri::command]
+async fn get_bedrock_account() -> Result<Option<mcp_core::BedrockAccount>, String> {
+    instrumented!(get_bedrock_account())
+}
+
+#[tauri::command]
+async fn set_bedrock_account(account: Option<mcp_core::BedrockAccount>) -> Result<(), String> {
+    instrumented!(set_bedrock_account(account))
+}
+
+#[tauri::command]
+async fn get_ollama_base_url() -> Result<Option<String>, String> {
+    instrumented!(get_ollama_base_url())
+}
+
+#[tauri::command]
+async fn set_ollama_base_url(base_url: Option<String>) -> Result<(), String> {
+    instrumented!(set_ollama_base_url(base_url))
+}
+
+#[tauri::command]
+async fn get_ollama_keep_alive() -> Result<Option<String>, String> {
+    instrumented!(get_ollama_keep_alive())
+}
+
+#[tauri::command]
+async fn set_ollama_keep_alive(keep_alive: Option<String>) -> Result<(), String> {
+    instrumented!(set_ollama_keep_alive(keep_alive))
+}
+
+#[tauri::command]
+async fn preload_model(model: String) -> Result<bool, String> {
+    instrumented!(preload_model(model))
+}
+
+#[tauri::command]
+async fn list_gateway_presets() -> Result<Vec<mcp_core::GatewayPresetInfo>, String> {
+    instrumented!(list_gateway_presets())
+}
+
+#[tauri::command]
+async fn get_gateway(provider: mcp_core::Provider) -> Result<Option<mcp_core::GatewayRoute>, String> {
+    instrumented!(get_gateway(provider))
+}
+
+#[tauri::command]
+async fn set_gateway(provider: mcp_core::Provider, route: Option<mcp_core::GatewayRoute>) -> Result<(), String> {
+    instrumented!(set_gateway(provider, route))
+}
+
+#[tauri::command]
+async fn get_failover_chain(provider: mcp_core::Provider) -> Result<Vec<String>, String> {
+    instrumented!(get_failover_chain(provider))
+}
+
+#[tauri::command]
+async fn set_failover_chain(provider: mcp_core::Provider, chain: Vec<String>) -> Result<(), String> {
+    instrumented!(set_failover_chain(provider, chain))
+}
+
+#[tauri::command]
+async fn expand_message(message: String) -> Result<String, String> {
+    instrumented!(expand_message(message))
+}
+
+#[tauri::command]
+async fn parse_mentions(message: String) -> Result<mcp_core::MessageMentions, String> {
+    instrumented!(parse_mentions(message))
+}
+
+#[tauri::command]
+async fn suggest_mentions(prefix: String) -> Result<Vec<mcp_core::MentionSuggestion>, String> {
+    instrumented!(suggest_mentions(prefix))
+}
+
+#[tauri::command]
+async fn list_text_expansions() -> Result<std::collections::BTreeMap<String, String>, String> {
+    instrumented!(list_text_expansions())
+}
+
+#[tauri::command]
+async fn save_text_expansion(trigger: String, text: String) -> Result<std::collections::BTreeMap<String, String>, String> {
+    instrumented!(save_text_expansion(trigger, text))
+}
+
+#[tauri::command]
+async fn remove_text_expansion(trigger: String) -> Result<bool, String> {
+    instrumented!(remove_text_expansion(trigger))
+}
+
+#[tauri::command]
+async fn get_provider_capabilities() -> Result<Vec<mcp_core::ProviderCapabilities>, String> {
+    instrumented!(get_provider_capabilities())
+}
+
+#[tauri::command]
+async fn list_compatible_endpoints() -> Result<Vec<mcp_core::CompatibleEndpoint>, String> {
+    instrumented!(list_compatible_endpoints())
+}
+
+#[tauri::command]
+async fn save_compatible_endpoint(endpoint: mcp_core::CompatibleEndpoint) -> Result<Vec<mcp_core::ModelInfo>, String> {
+    instrumented!(save_compatible_endpoint(endpoint))
+}
+
+#[tauri::command]
+async fn remove_compatible_endpoint(name: String) -> Result<bool, String> {
+    instrumented!(remove_compatible_endpoint(name))
+}
+
+#[tauri::command]
+async fn get_provider_headers(provider: mcp_core::Provider) -> Result<std::collections::BTreeMap<String, String>, String> {
+    instrumented!(get_provider_headers(provider))
+}
+
+#[tauri::command]
+async fn set_provider_headers(provider: mcp_core::Provider, headers: std::collections::BTreeMap<String, String>) -> Result<(), String> {
+    instrumented!(set_provider_headers(provider, headers))
+}
+
+#[tauri::command]
+async fn get_system_defaults() -> Result<mcp_core::SystemDefaultsInfo, String> {
+    instrumented!(get_system_defaults())
+}
+
+#[tauri::command]
+async fn get_policy() -> Result<mcp_core::PolicyStatus, String> {
+    instrumented!(get_policy())
+}
+
+#[tauri::command]
+async fn get_portable_status() -> Result<mcp_core::PortableStatus, String> {
+    instrumented!(get_portable_status())
+}
+
+#[tauri::command]
+async fn unlock_portable(passphrase: String) -> Result<(), String> {
+    instrumented!(unlock_portable(passphrase))
+}
+
+#[tauri::command]
+async fn change_portable_passphrase(passphrase: String) -> Result<(), String> {
+    instrumented!(change_portable_passphrase(passphrase))
+}
+
+#[tauri::command]
+async fn get_api_key_source() -> Result<Option<mcp_core::ValueSource>, String> {
+    instrumented!(get_api_key_source())
+}
+
+#[tauri::command]
+async fn rotate_encryption_key() -> Result<(), String> {
+    instrumented!(rotate_encryption_key())
+}
+
+#[tauri::command]
+async fn has_api_config() -> Result<bool, String> {
+    instrumented!(has_api_config())
+}
+
+#[tauri::command]
+async fn log_info(message: String) -> Result<(), String> {
+    instrumented!(log_info(message))
+}
+
+#[tauri::command]
+async fn get_available_models() -> Result<Vec<mcp_core::ModelInfo>, String> {
+    instrumented!(get_available_models())
+}
+
+#[tauri::command]
+async fn list_custom_models() -> Result<Vec<mcp_core::CustomModel>, String> {
+    instrumented!(list_custom_models())
+}
+
+#[tauri::command]
+async fn register_custom_model(model: mcp_core::CustomModel) -> Result<mcp_core::CustomModel, String> {
+    instrumented!(register_custom_model(model))
+}
+
+#[tauri::command]
+async fn remove_custom_model(id: String) -> Result<bool, String> {
+    instrumented!(remove_custom_model(id))
+}
+
+#[tauri::command]
+async fn check_custom_model(id: String) -> Result<mcp_core::ModelHealth, String> {
+    instrumented!(check_custom_model(id))
+}
+
+#[tauri::command]
+async fn invalidate_caches(scope: mcp_core::CacheScope) -> Result<u32, String> {
+    instrumented!(invalidate_caches(scope))
+}
+
+#[tauri::command]
+async fn get_current_model() -> Result<String, String> {
+    instrumented!(get_current_model())
+}
+
+#[tauri::command]
+async fn set_preferred_model(model: String) -> Result<(), String> {
+    instrumented!(set_preferred_model(model))
+}
+
+#[tauri::command]
+async fn get_ipc_stats() -> Result<Vec<mcp_core::IpcCommandStats>, String> {
+    instrumented!(get_ipc_stats())
+}
+
+#[tauri::command]
+async fn get_memory_stats() -> Result<mcp_core::MemoryStats, String> {
+    instrumented!(get_memory_stats())
+}
+
+#[tauri::command]
+async fn get_provider_health() -> Result<Vec<mcp_core::ProviderHealth>, String> {
+    instrumented!(get_provider_health())
+}
+
+#[tauri::command]
+async fn check_provider_health() -> Result<Vec<mcp_core::ProviderHealth>, String> {
+    instrumented!(check_provider_health())
+}
+
+#[tauri::command]
+async fn get_startup_status() -> Result<Vec<mcp_core::SubsystemStatus>, String> {
+    instrumented!(get_startup_status())
+}
+
+#[tauri::command]
+async fn get_accessibility_settings() -> Result<mcp_core::AccessibilitySettings, String> {
+    instrumented!(get_accessibility_settings())
+}
+
+#[tauri::command]
+async fn set_accessibility_settings(settings: mcp_core::AccessibilitySettings) -> Result<(), String> {
+    instrumented!(set_accessibility_settings(settings))
+}
+
+#[tauri::command]
+async fn get_read_aloud_state() -> Result<mcp_core::ReadAloudState, String> {
+    instrumented!(get_read_aloud_state())
+}
+
+#[tauri::command]
+async fn read_message_aloud(message_id: String) -> Result<mcp_core::ReadAloudState, String> {
+    instrumented!(read_message_aloud(message_id))
+}
+
+#[tauri::command]
+async fn read_aloud_spoken(message_id: String, index: u32) -> Result<mcp_core::ReadAloudState, String> {
+    instrumented!(read_aloud_spoken(message_id, index))
+}
+
+#[tauri::command]
+async fn skip_read_aloud() -> Result<mcp_core::ReadAloudState, String> {
+    instrumented!(skip_read_aloud())
+}
+
+#[tauri::command]
+async fn pause_read_aloud() -> Result<mcp_core::ReadAloudState, String> {
+    instrumented!(pause_read_aloud())
+}
+
+#[tauri::command]
+async fn resume_read_aloud() -> Result<mcp_core::ReadAloudState, String> {
+    instrumented!(resume_read_aloud())
+}
+
+#[tauri::command]
+async fn stop_read_aloud() -> Result<mcp_core::ReadAloudState, String> {
+    instrumented!(stop_read_aloud())
+}
+
+#[tauri::command]
+async fn get_onboarding_state() -> Result<mcp_core::OnboardingState, String> {
+    instrumented!(get_onboarding_state())
+}
+
+#[tauri::command]
+async fn advance_onboarding(action: mcp_core::OnboardingAction) -> Result<mcp_core::OnboardingState, String> {
+    instrumented!(advance_onboarding(action))
+}
+
+#[tauri::command]
+async fn get_mode() -> Result<mcp_core::ModeBehavior, String> {
+    instrumented!(get_mode())
+}
+
+#[tauri::command]
+async fn set_mode(mode: mcp_core::AppMode) -> Result<mcp_core::ModeBehavior, String> {
+    instrumented!(set_mode(mode))
+}
+
+#[tauri::command]
+async fn list_feature_flags() -> Result<Vec<mcp_core::FeatureFlag>, String> {
+    instrumented!(list_feature_flags())
+}
+
+#[tauri::command]
+async fn set_feature_flag(feature: mcp_core::Feature, enabled: Option<bool>) -> Result<mcp_core::FeatureFlag, String> {
+    instrumented!(set_feature_flag(feature, enabled))
+}
+
+#[tauri::command]
+async fn get_demo_mode() -> Result<bool, String> {
+    instrumented!(get_demo_mode())
+}
+
+#[tauri::command]
+async fn set_demo_mode(enabled: bool) -> Result<(), String> {
+    instrumented!(set_demo_mode(enabled))
+}
+
+#[tauri::command]
+async fn get_status() -> Result<mcp_core::StatusSnapshot, String> {
+    instrumented!(get_status())
+}
+
+#[tauri::command]
+async fn get_appearance() -> Result<mcp_core::AppearanceSettings, String> {
+    instrumented!(get_appearance())
+}
+
+#[tauri::command]
+async fn set_appearance(settings: mcp_core::AppearanceSettings) -> Result<(), String> {
+    instrumented!(set_appearance(settings))
+}
+
+#[tauri::command]
+async fn export_settings(path: String) -> Result<(), String> {
+    instrumented!(export_settings(path))
+}
+
+#[tauri::command]
+async fn import_settings(path: String) -> Result<(), String> {
+    instrumented!(import_settings(path))
+}
+
+#[tauri::command]
+async fn set_locale(locale: Option<String>) -> Result<String, String> {
+    instrumented!(set_locale(locale))
+}
+
+#[tauri::command]
+async fn get_message_catalogue() -> Result<mcp_core::MessageCatalogue, String> {
+    instrumented!(get_message_catalogue())
+}
+
+#[tauri::command]
+async fn get_build_info() -> Result<BuildInfo, String> {
+    instrumented!(get_build_info())
+}
+
+#[tauri::command]
+async fn ingest_media_file(path: String, conversation_id: String) -> Result<mcp_core::Transcript, String> {
+    instrumented!(ingest_media_file(path, conversation_id))
+}
+
+#[tauri::command]
+async fn list_transcripts(conversation_id: String) -> Result<Vec<mcp_core::Transcript>, String> {
+    instrumented!(list_transcripts(conversation_id))
+}
+
+#[tauri::command]
+async fn get_transcript_context(conversation_id: String) -> Result<String, String> {
+    instrumented!(get_transcript_context(conversation_id))
+}
+
+#[tauri::command]
+async fn capture_screen_region(
+    conversation_id: String,
+    region: Option<mcp_core::CaptureRegion>,
+    analysis: mcp_core::CaptureAnalysis,
+) -> Result<mcp_core::ScreenCapture, String> {
+    instrumented!(capture_screen_region(conversation_id, region, analysis))
+}
+
+#[tauri::command]
+async fn list_screen_captures(conversation_id: String) -> Result<Vec<mcp_core::ScreenCapture>, String> {
+    instrumented!(list_screen_captures(conversation_id))
+}
+
+#[tauri::command]
+async fn get_template_repositories() -> Result<Vec<String>, String> {
+    instrumented!(get_template_repositories())
+}
+
+#[tauri::command]
+async fn add_template_repository(repository: String) -> Result<mcp_core::TemplateSyncResult, String> {
+    instrumented!(add_template_repository(repository))
+}
+
+#[tauri::command]
+async fn remove_template_repository(repository: String) -> Result<(), String> {
+    instrumented!(remove_template_repository(repository))
+}
+
+#[tauri::command]
+async fn sync_template_repositories() -> Result<Vec<mcp_core::TemplateSyncResult>, String> {
+    instrumented!(sync_template_repositories())
+}
+
+#[tauri::command]
+async fn list_templates() -> Result<Vec<mcp_core::PromptTemplate>, String> {
+    instrumented!(list_templates())
+}
+
+#[tauri::command]
+async fn list_plugins() -> Result<Vec<mcp_core::PluginInfo>, String> {
+    instrumented!(list_plugins())
+}
+
+#[tauri::command]
+async fn reload_plugins() -> Result<Vec<mcp_core::PluginInfo>, String> {
+    instrumented!(reload_plugins())
+}
+
+#[tauri::command]
+async fn approve_plugin(id: String, permissions: Vec<String>) -> Result<Vec<mcp_core::PluginInfo>, String> {
+    instrumented!(approve_plugin(id, permissions))
+}
+
+#[tauri::command]
+async fn revoke_plugin(id: String) -> Result<Vec<mcp_core::PluginInfo>, String> {
+    instrumented!(revoke_plugin(id))
+}
+
+#[tauri::command]
+async fn invoke_plugin_command(plugin: String, command: String, input: serde_json::Value) -> Result<String, String> {
+    instrumented!(invoke_plugin_command(plugin, command, input))
+}
+
+#[tauri::command]
+async fn invoke_plugin_tool(plugin: String, tool: String, input: serde_json::Value) -> Result<String, String> {
+    instrumented!(invoke_plugin_tool(plugin, tool, input))
+}
+
+#[tauri::command]
+async fn invoke_plugin_tools(calls: Vec<mcp_core::ToolCall>) -> Result<Vec<mcp_core::ToolCallOutcome>, String> {
+    instrumented!(invoke_plugin_tools(calls))
+}
+
+#[tauri::command]
+async fn list_integrations() -> Result<Vec<mcp_core::IntegrationInfo>, String> {
+    instrumented!(list_integrations())
+}
+
+#[tauri::command]
+async fn reload_integrations() -> Result<Vec<mcp_core::IntegrationInfo>, String> {
+    instrumented!(reload_integrations())
+}
+
+#[tauri::command]
+async fn call_integration(
+    integration: String,
+    endpoint: String,
+    params: std::collections::BTreeMap<String, String>,
+    body: Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    instrumented!(call_integration(integration, endpoint, params, body))
+}
+
+#[tauri::command]
+async fn start_oauth_device_flow(integration: String) -> Result<mcp_core::DeviceAuthorization, String> {
+    instrumented!(start_oauth_device_flow(integration))
+}
+
+#[tauri::command]
+async fn start_oauth_browser_flow(integration: String) -> Result<String, String> {
+    instrumented!(start_oauth_browser_flow(integration))
+}
+
+#[tauri::command]
+async fn refresh_oauth_token(integration: String) -> Result<(), String> {
+    instrumented!(refresh_oauth_token(integration))
+}
+
+#[tauri::command]
+async fn list_secrets() -> Result<Vec<mcp_core::SecretInfo>, String> {
+    instrumented!(list_secrets())
+}
+
+#[tauri::command]
+async fn set_secret(name: String, owner: String, value: String) -> Result<mcp_core::SecretInfo, String> {
+    instrumented!(set_secret(name, owner, value))
+}
+
+#[tauri::command]
+async fn delete_secret(name: String) -> Result<bool, String> {
+    instrumented!(delete_secret(name))
+}
+
+#[tauri::command]
+async fn get_tool_stats() -> Result<mcp_core::ToolStatsReport, String> {
+    instrumented!(get_tool_stats())
+}
+
+#[tauri::command]
+async fn get_max_parallel_tools() -> Result<u32, String> {
+    instrumented!(get_max_parallel_tools())
+}
+
+#[tauri::command]
+async fn set_max_parallel_tools(max_parallel: u32) -> Result<(), String> {
+    instrumented!(set_max_parallel_tools(max_parallel))
+}
+
+#[tauri::command]
+async fn get_tool_result_limits() -> Result<mcp_core::ToolResultLimits, String> {
+    instrumented!(get_tool_result_limits())
+}
+
+#[tauri::command]
+async fn set_tool_result_limits(limits: mcp_core::ToolResultLimits) -> Result<mcp_core::ToolResultLimits, String> {
+    instrumented!(set_tool_result_limits(limits))
+}
+
+#[tauri::command]
+async fn get_tool_cache_settings() -> Result<mcp_core::ToolCacheSettings, String> {
+    instrumented!(get_tool_cache_settings())
+}
+
+#[tauri::command]
+async fn set_tool_cache_settings(settings: mcp_core::ToolCacheSettings) -> Result<mcp_core::ToolCacheSettings, String> {
+    instrumented!(set_tool_cache_settings(settings))
+}
+
+#[tauri::command]
+async fn list_scripts() -> Result<Vec<mcp_core::ScriptInfo>, String> {
+    instrumented!(list_scripts())
+}
+
+#[tauri::command]
+async fn reload_scripts() -> Result<Vec<mcp_core::ScriptInfo>, String> {
+    instrumented!(reload_scripts())
+}
+
+#[tauri::command]
+async fn start_server(port: Option<u16>) -> Result<mcp_core::ServerStatus, String> {
+    instrumented!(start_server(port))
+}
+
+#[tauri::command]
+async fn stop_server() -> Result<(), String> {
+    instrumented!(stop_server())
+}
+
+#[tauri::command]
+async fn get_server_status() -> Result<mcp_core::ServerStatus, String> {
+    instrumented!(get_server_status())
+}
+
+#[tauri::command]
+async fn set_server_auth_enabled(enabled: bool) -> Result<mcp_core::ServerStatus, String> {
+    instrumented!(set_server_auth_enabled(enabled))
+}
+
+#[tauri::command]
+async fn list_server_tokens() -> Result<Vec<mcp_core::ServerTokenInfo>, String> {
+    instrumented!(list_server_tokens())
+}
+
+#[tauri::command]
+async fn create_server_token(name: String, scope: mcp_core::TokenScope) -> Result<mcp_core::IssuedServerToken, String> {
+    instrumented!(create_server_token(name, scope))
+}
+
+#[tauri::command]
+async fn rotate_server_token(id: String) -> Result<mcp_core::IssuedServerToken, String> {
+    instrumented!(rotate_server_token(id))
+}
+
+#[tauri::command]
+async fn set_server_token_shaping(id: String, shaping: mcp_core::RequestShaping) -> Result<mcp_core::ServerTokenInfo, String> {
+    instrumented!(set_server_token_shaping(id, shaping))
+}
+
+#[tauri::command]
+async fn revoke_server_token(id: String) -> Result<(), String> {
+    instrumented!(revoke_server_token(id))
+}
+
+#[tauri::command]
+async fn start_pairing(scope: Option<mcp_core::TokenScope>) -> Result<mcp_core::PairingCode, String> {
+    instrumented!(start_pairing(scope))
+}
+
+#[tauri::command]
+async fn list_paired_devices() -> Result<Vec<mcp_core::PairedDevice>, String> {
+    instrumented!(list_paired_devices())
+}
+
+#[tauri::command]
+async fn revoke_paired_device(id: String) -> Result<(), String> {
+    instrumented!(revoke_paired_device(id))
+}
+
+#[tauri::command]
+async fn list_sync_devices() -> Result<Vec<mcp_core::SyncDevice>, String> {
+    instrumented!(list_sync_devices())
+}
+
+#[tauri::command]
+async fn handoff_conversation(conversation_id: String, device_id: String, draft: Option<String>) -> Result<mcp_core::Handoff, String> {
+    instrumented!(handoff_conversation(conversation_id, device_id, draft))
+}
+
+#[tauri::command]
+async fn list_handoffs() -> Result<Vec<mcp_core::Handoff>, String> {
+    instrumented!(list_handoffs())
+}
+
+#[tauri::command]
+async fn accept_handoff(handoff_id: String) -> Result<mcp_core::Handoff, String> {
+    instrumented!(accept_handoff(handoff_id))
+}
+
+#[tauri::command]
+async fn get_server_network() -> Result<mcp_core::ServerNetworkConfig, String> {
+    instrumented!(get_server_network())
+}
+
+#[tauri::command]
+async fn set_server_network(
+    network: mcp_core::ServerNetworkConfig,
+    confirm_lan_exposure: bool,
+) -> Result<mcp_core::ServerNetworkUpdate, String> {
+    instrumented!(set_server_network(network, confirm_lan_exposure))
+}
+
+#[tauri::command]
+async fn get_server_tls() -> Result<Option<mcp_core::ServerTlsConfig>, String> {
+    instrumented!(get_server_tls())
+}
+
+#[tauri::command]
+async fn set_server_tls(tls: Option<mcp_core::ServerTlsConfig>) -> Result<(), String> {
+    instrumented!(set_server_tls(tls))
+}
+
+#[tauri::command]
+async fn get_generation_settings() -> Result<mcp_core::GenerationSettings, String> {
+    instrumented!(get_generation_settings())
+}
+
+#[tauri::command]
+async fn set_generation_settings(settings: mcp_core::GenerationSettings) -> Result<(), String> {
+    instrumented!(set_generation_settings(settings))
+}
+
+#[tauri::command]
+async fn send_streaming_message(
+    message: String,
+    conversation_id: Option<String>,
+    metadata: Option<std::collections::BTreeMap<String, String>>,
+    window: tauri::Window,
+) -> Result<(), String> {
+    log::info!("Starting streaming message (Tauri wrapper)");
+    
+    // Call the pure business logic function to get the stream
+    instrumented!(send_streaming_message(message, conversation_id, metadata), async move {
+        let stream = mcp_core::create_streaming_chat(message, conversation_id, metadata.unwrap_or_default()).await?;
+        emit_stream(stream, &window).await
+    })
+}
+
+#[tauri::command]
+async fn send_orchestrated_chat(
+    plan: mcp_core::OrchestrationPlan,
+    conversation_id: Option<String>,
+    window: tauri::Window,
+) -> Result<(), String> {
+    log::info!("Starting orchestrated chat (Tauri wrapper)");
+    instrumented!(send_orchestrated_chat(plan, conversation_id), async move {
+        let stream = mcp_core::create_orchestrated_chat(plan, conversation_id).await?;
+        emit_stream(stream, &window).await
+    })
+}
+
+#[tauri::command]
+async fn resume_response(message_id: String, window: tauri::Window) -> Result<(), String> {
+    log::info!("Resuming response {} (Tauri wrapper)", message_id);
+    instrumented!(resume_response(message_id), async move {
+        let stream = mcp_core::resume_response(message_id).await?;
+        emit_stream(stream, &window).await
+    })
+}
+
+#[tauri::command]
+async fn get_history_insights(period: mcp_core::InsightsPeriod) -> Result<mcp_core::HistoryInsights, String> {
+    instrumented!(get_history_insights(period))
 }
 
 #[tauri::command]
-async fn save_api_config(api_key: String) -> Result<(), String> {
-    mcp_core::save_api_config(api_key).await
+async fn find_duplicate_conversations() -> Result<Vec<mcp_core::DuplicateConversations>, String> {
+    instrumented!(find_duplicate_conversations())
 }
 
 #[tauri::command]
-async fn has_api_config() -> Result<bool, String> {
-    mcp_core::has_api_config().await
+async fn merge_conversations(conversation_ids: Vec<String>) -> Result<mcp_core::MergeSummary, String> {
+    instrumented!(merge_conversations(conversation_ids))
 }
 
 #[tauri::command]
-async fn log_info(message: String) -> Result<(), String> {
-    mcp_core::log_info(message).await
+async fn list_message_origins(conversation_id: String) -> Result<Vec<mcp_core::MessageOrigin>, String> {
+    instrumented!(list_message_origins(conversation_id))
 }
 
 #[tauri::command]
-async fn get_available_models() -> Result<Vec<mcp_core::ModelInfo>, String> {
-    mcp_core::get_available_models().await
+async fn save_as_starter(conversation_id: String, name: String) -> Result<mcp_core::ConversationStarter, String> {
+    instrumented!(save_as_starter(conversation_id, name))
 }
 
 #[tauri::command]
-async fn get_current_model() -> Result<String, String> {
-    mcp_core::get_current_model().await
+async fn list_starters() -> Result<Vec<mcp_core::ConversationStarter>, String> {
+    instrumented!(list_starters())
 }
 
 #[tauri::command]
-async fn set_preferred_model(model: String) -> Result<(), String> {
-    mcp_core::set_preferred_model(model).await
+async fn delete_starter(name: String) -> Result<(), String> {
+    instrumented!(delete_starter(name))
 }
 
 #[tauri::command]
-async fn get_build_info() -> Result<BuildInfo, String> {
-    mcp_core::get_build_info().await
+async fn new_from_starter(name: String) -> Result<mcp_core::StartedConversation, String> {
+    instrumented!(new_from_starter(name))
 }
 
 #[tauri::command]
-async fn send_streaming_message(
-    message: String,
-    window: tauri::Window,
+async fn set_focused_conversation(conversation_id: Option<String>) -> Result<(), String> {
+    instrumented!(set_focused_conversation(conversation_id))
+}
+
+#[tauri::command]
+async fn mark_read(conversation_id: String) -> Result<(), String> {
+    instrumented!(mark_read(conversation_id))
+}
+
+#[tauri::command]
+async fn get_unread_conversations() -> Result<Vec<mcp_core::UnreadConversation>, String> {
+    instrumented!(get_unread_conversations())
+}
+
+#[tauri::command]
+async fn get_unread_count() -> Result<u32, String> {
+    instrumented!(get_unread_count())
+}
+
+#[tauri::command]
+async fn lock_conversation(conversation_id: String) -> Result<(), String> {
+    instrumented!(lock_conversation(conversation_id))
+}
+
+#[tauri::command]
+async fn unlock_conversation(conversation_id: String) -> Result<(), String> {
+    instrumented!(unlock_conversation(conversation_id))
+}
+
+#[tauri::command]
+async fn remove_conversation_lock(conversation_id: String) -> Result<(), String> {
+    instrumented!(remove_conversation_lock(conversation_id))
+}
+
+#[tauri::command]
+async fn relock_conversations() -> Result<(), String> {
+    instrumented!(relock_conversations())
+}
+
+#[tauri::command]
+async fn list_locked_conversations() -> Result<Vec<mcp_core::LockedConversation>, String> {
+    instrumented!(list_locked_conversations())
+}
+
+#[tauri::command]
+async fn list_conversation_messages(conversation_id: String) -> Result<Vec<mcp_core::HistoryMessage>, String> {
+    instrumented!(list_conversation_messages(conversation_id))
+}
+
+#[tauri::command]
+async fn backup_history(path: String) -> Result<mcp_core::BackupInfo, String> {
+    instrumented!(backup_history(path))
+}
+
+#[tauri::command]
+async fn restore_history(path: String, mode: mcp_core::RestoreMode) -> Result<mcp_core::RestoreSummary, String> {
+    instrumented!(restore_history(path, mode))
+}
+
+#[tauri::command]
+async fn get_database_status() -> Result<mcp_core::DatabaseStatus, String> {
+    instrumented!(get_database_status())
+}
+
+#[tauri::command]
+async fn get_storage_usage() -> Result<mcp_core::StorageUsage, String> {
+    instrumented!(get_storage_usage())
+}
+
+#[tauri::command]
+async fn detach_attachment(attachment_id: String, conversation_id: String) -> Result<(), String> {
+    instrumented!(detach_attachment(attachment_id, conversation_id))
+}
+
+#[tauri::command]
+async fn cleanup_orphaned_attachments() -> Result<mcp_core::CleanupSummary, String> {
+    instrumented!(cleanup_orphaned_attachments())
+}
+
+#[tauri::command]
+async fn begin_upload(
+    conversation_id: String,
+    file_name: String,
+    total_bytes: u64,
+    sha256: String,
+    kind: Option<mcp_core::AttachmentKind>,
+) -> Result<mcp_core::UploadStatus, String> {
+    instrumented!(begin_upload(conversation_id, file_name, total_bytes, sha256, kind))
+}
+
+#[tauri::command]
+async fn upload_chunk(upload_id: String, index: u32, data: String, sha256: String) -> Result<mcp_core::UploadStatus, String> {
+    instrumented!(upload_chunk(upload_id, index, data, sha256))
+}
+
+#[tauri::command]
+async fn get_upload_status(upload_id: String) -> Result<mcp_core::UploadStatus, String> {
+    instrumented!(get_upload_status(upload_id))
+}
+
+#[tauri::command]
+async fn finish_upload(upload_id: String) -> Result<mcp_core::Attachment, String> {
+    instrumented!(finish_upload(upload_id))
+}
+
+#[tauri::command]
+async fn cancel_upload(upload_id: String) -> Result<(), String> {
+    instrumented!(cancel_upload(upload_id))
+}
+
+#[tauri::command]
+async fn export_conversation(
+    conversation_id: String,
+    path: String,
+    format: mcp_core::ExportFormat,
+) -> Result<mcp_core::ExportSummary, String> {
+    instrumented!(export_conversation(conversation_id, path, format))
+}
+
+#[tauri::command]
+async fn cancel_export(export_id: String) -> Result<(), String> {
+    instrumented!(cancel_export(export_id))
+}
+
+#[tauri::command]
+async fn get_backup_schedule() -> Result<mcp_core::BackupSchedule, String> {
+    instrumented!(get_backup_schedule())
+}
+
+#[tauri::command]
+async fn set_backup_schedule(schedule: mcp_core::BackupSchedule) -> Result<(), String> {
+    instrumented!(set_backup_schedule(schedule))
+}
+
+#[tauri::command]
+async fn save_eval_suite(suite: mcp_core::EvalSuite) -> Result<mcp_core::EvalSuite, String> {
+    instrumented!(save_eval_suite(suite))
+}
+
+#[tauri::command]
+async fn list_eval_suites() -> Result<Vec<mcp_core::EvalSuite>, String> {
+    instrumented!(list_eval_suites())
+}
+
+#[tauri::command]
+async fn delete_eval_suite(name: String) -> Result<bool, String> {
+    instrumented!(delete_eval_suite(name))
+}
+
+#[tauri::command]
+async fn run_eval_suite(name: String) -> Result<mcp_core::EvalRun, String> {
+    instrumented!(run_eval_suite(name))
+}
+
+#[tauri::command]
+async fn list_eval_runs(suite_name: String, limit: Option<u32>) -> Result<Vec<mcp_core::EvalRun>, String> {
+    instrumented!(list_eval_runs(suite_name, limit))
+}
+
+#[tauri::command]
+async fn get_eval_scores(suite_name: String) -> Result<Vec<mcp_core::ModelScore>, String> {
+    instrumented!(get_eval_scores(suite_name))
+}
+
+#[tauri::command]
+async fn benchmark_providers(prompt: Option<String>, models: Vec<String>) -> Result<mcp_core::BenchmarkReport, String> {
+    instrumented!(benchmark_providers(prompt, models))
+}
+
+#[tauri::command]
+async fn list_benchmark_reports(limit: Option<u32>) -> Result<Vec<mcp_core::BenchmarkReport>, String> {
+    instrumented!(list_benchmark_reports(limit))
+}
+
+#[tauri::command]
+async fn delete_benchmark_report(id: String) -> Result<bool, String> {
+    instrumented!(delete_benchmark_report(id))
+}
+
+#[tauri::command]
+async fn get_sync_config() -> Result<mcp_core::SyncConfig, String> {
+    instrumented!(get_sync_config())
+}
+
+#[tauri::command]
+async fn set_sync_config(config: mcp_core::SyncConfig) -> Result<mcp_core::SyncConfig, String> {
+    instrumented!(set_sync_config(config))
+}
+
+#[tauri::command]
+async fn generate_sync_key() -> Result<String, String> {
+    instrumented!(generate_sync_key())
+}
+
+#[tauri::command]
+async fn sync_now() -> Result<mcp_core::SyncReport, String> {
+    instrumented!(sync_now())
+}
+
+#[tauri::command]
+async fn get_message_details(id: String) -> Result<mcp_core::MessageDetails, String> {
+    instrumented!(get_message_details(id))
+}
+
+#[tauri::command]
+async fn find_tagged_messages(tag: String) -> Result<Vec<mcp_core::HistoryMessage>, String> {
+    instrumented!(find_tagged_messages(tag))
+}
+
+#[tauri::command]
+async fn get_message_cost(id: String) -> Result<mcp_core::MessageCost, String> {
+    instrumented!(get_message_cost(id))
+}
+
+#[tauri::command]
+async fn get_conversation_compression(conversation_id: String) -> Result<mcp_core::ConversationCompression, String> {
+    instrumented!(get_conversation_compression(conversation_id))
+}
+
+#[tauri::command]
+async fn set_conversation_compression(
+    conversation_id: String,
+    enabled: bool,
+    ratio: Option<f32>,
+) -> Result<mcp_core::ConversationCompression, String> {
+    instrumented!(set_conversation_compression(conversation_id, enabled, ratio))
+}
+
+#[tauri::command]
+async fn get_conversation_filters(conversation_id: String) -> Result<mcp_core::ConversationFilters, String> {
+    instrumented!(get_conversation_filters(conversation_id))
+}
+
+#[tauri::command]
+async fn set_conversation_filters(conversation_id: String, filters: mcp_core::ConversationFilters) -> Result<mcp_core::ConversationFilters, String> {
+    instrumented!(set_conversation_filters(conversation_id, filters))
+}
+
+#[tauri::command]
+async fn get_plan(conversation_id: String) -> Result<Option<mcp_core::TaskPlan>, String> {
+    instrumented!(get_plan(conversation_id))
+}
+
+#[tauri::command]
+async fn update_plan_step(conversation_id: String, step_id: String, status: mcp_core::PlanStepStatus) -> Result<mcp_core::TaskPlan, String> {
+    instrumented!(update_plan_step(conversation_id, step_id, status))
+}
+
+#[tauri::command]
+async fn get_stream_recording() -> Result<bool, String> {
+    instrumented!(get_stream_recording())
+}
+
+#[tauri::command]
+async fn set_stream_recording(enabled: bool) -> Result<(), String> {
+    instrumented!(set_stream_recording(enabled))
+}
+
+#[tauri::command]
+async fn get_pause_hidden_streams() -> Result<bool, String> {
+    instrumented!(get_pause_hidden_streams())
+}
+
+#[tauri::command]
+async fn set_pause_hidden_streams(enabled: bool) -> Result<(), String> {
+    instrumented!(set_pause_hidden_streams(enabled))
+}
+
+#[tauri::command]
+async fn set_window_visible(visible: bool) -> Result<(), String> {
+    instrumented!(set_window_visible(visible))
+}
+
+#[tauri::command]
+async fn get_maintenance_status() -> Result<mcp_core::MaintenanceStatus, String> {
+    instrumented!(get_maintenance_status())
+}
+
+#[tauri::command]
+async fn get_maintenance_settings() -> Result<mcp_core::MaintenanceSettings, String> {
+    instrumented!(get_maintenance_settings())
+}
+
+#[tauri::command]
+async fn set_maintenance_settings(settings: mcp_core::MaintenanceSettings) -> Result<(), String> {
+    instrumented!(set_maintenance_settings(settings))
+}
+
+#[tauri::command]
+async fn note_user_activity() -> Result<(), String> {
+    instrumented!(note_user_activity())
+}
+
+#[tauri::command]
+async fn get_low_power_status() -> Result<mcp_core::LowPowerStatus, String> {
+    instrumented!(get_low_power_status())
+}
+
+#[tauri::command]
+async fn get_low_power_settings() -> Result<mcp_core::LowPowerSettings, String> {
+    instrumented!(get_low_power_settings())
+}
+
+#[tauri::command]
+async fn set_low_power_settings(settings: mcp_core::LowPowerSettings) -> Result<mcp_core::LowPowerStatus, String> {
+    instrumented!(set_low_power_settings(settings))
+}
+
+#[tauri::command]
+async fn set_low_power_override(active: Option<bool>) -> Result<mcp_core::LowPowerStatus, String> {
+    instrumented!(set_low_power_override(active))
+}
+
+#[tauri::command]
+async fn get_redaction_enabled() -> Result<bool, String> {
+    instrumented!(get_redaction_enabled())
+}
+
+#[tauri::command]
+async fn set_redaction_enabled(enabled: bool) -> Result<(), String> {
+    instrumented!(set_redaction_enabled(enabled))
+}
+
+#[tauri::command]
+async fn get_telemetry_config() -> Result<mcp_core::TelemetryConfig, String> {
+    instrumented!(get_telemetry_config())
+}
+
+#[tauri::command]
+async fn set_telemetry_config(config: mcp_core::TelemetryConfig) -> Result<mcp_core::TelemetryConfig, String> {
+    instrumented!(set_telemetry_config(config))
+}
+
+#[tauri::command]
+async fn get_memory_enabled() -> Result<bool, String> {
+    instrumented!(get_memory_enabled())
+}
+
+#[tauri::command]
+async fn set_memory_enabled(enabled: bool) -> Result<(), String> {
+    instrumented!(set_memory_enabled(enabled))
+}
+
+#[tauri::command]
+async fn list_memories() -> Result<Vec<mcp_core::Memory>, String> {
+    instrumented!(list_memories())
+}
+
+#[tauri::command]
+async fn forget_memory(id: String) -> Result<(), String> {
+    instrumented!(forget_memory(id))
+}
+
+#[tauri::command]
+async fn clear_memories() -> Result<u32, String> {
+    instrumented!(clear_memories())
+}
+
+#[tauri::command]
+async fn get_guardrails() -> Result<mcp_core::GuardrailConfig, String> {
+    instrumented!(get_guardrails())
+}
+
+#[tauri::command]
+async fn set_guardrails(guardrails: mcp_core::GuardrailConfig) -> Result<(), String> {
+    instrumented!(set_guardrails(guardrails))
+}
+
+#[tauri::command]
+async fn estimate_generation_cost(message: String, conversation_id: Option<String>) -> Result<mcp_core::CostEstimate, String> {
+    instrumented!(estimate_generation_cost(message, conversation_id))
+}
+
+#[tauri::command]
+async fn list_candidates(message_id: String) -> Result<Vec<mcp_core::Candidate>, String> {
+    instrumented!(list_candidates(message_id))
+}
+
+#[tauri::command]
+async fn select_candidate(candidate_id: String) -> Result<mcp_core::Candidate, String> {
+    instrumented!(select_candidate(candidate_id))
+}
+
+#[tauri::command]
+async fn regenerate_with(message_id: String, overrides: mcp_core::RegenerationOverrides) -> Result<mcp_core::Candidate, String> {
+    instrumented!(regenerate_with(message_id, overrides))
+}
+
+#[tauri::command]
+async fn replay_message(message_id: String) -> Result<mcp_core::Replay, String> {
+    instrumented!(replay_message(message_id))
+}
+
+#[tauri::command]
+async fn get_candidate_details(candidate_id: String) -> Result<mcp_core::MessageDetails, String> {
+    instrumented!(get_candidate_details(candidate_id))
+}
+
+#[tauri::command]
+async fn diff_responses(a: String, b: String) -> Result<mcp_core::ResponseDiff, String> {
+    instrumented!(diff_responses(a, b))
+}
+
+// Handle the stream and emit Tauri events
+async fn emit_stream(
+    stream: std::pin::Pin<Box<dyn futures::Stream<Item = StreamMessage> + Send>>,
+    window: &tauri::Window,
 ) -> Result<(), String> {
-    log::info!("Starting streaming message (Tauri wrapper)");
-    
-    // Call the pure business logic function to get the stream
-    let mut stream = mcp_core::create_streaming_chat(message).await?;
-    
-    // Handle the stream and emit Tauri events
+    let mut stream = mcp_core::hold_while_hidden(stream);
     while let Some(stream_message) = stream.next().await {
         match stream_message {
             StreamMessage::Content(content) => {
@@ -74,6 +1210,21 @@ async fn send_streaming_message(
                 window.emit("chat-complete", ()).map_err(|e| e.to_string())?;
                 break;
             }
+            StreamMessage::Replace(content) => {
+                window.emit("chat-replace", content).map_err(|e| e.to_string())?;
+            }
+            StreamMessage::ProviderSwitched { from, to, model, reason } => {
+                window.emit("chat-provider-switched", serde_json::json!({ "from": from, "to": to, "model": model, "reason": reason }))
+                    .map_err(|e| e.to_string())?;
+            }
+            StreamMessage::Usage { model, prompt_tokens, completion_tokens } => {
+                window.emit("chat-usage", serde_json::json!({ "model": model, "prompt_tokens": prompt_tokens, "completion_tokens": completion_tokens }))
+                    .map_err(|e| e.to_string())?;
+            }
+            StreamMessage::AgentTurn { agent, model, message_id } => {
+                window.emit("chat-agent-turn", serde_json::json!({ "agent": agent, "model": model, "message_id": message_id }))
+                    .map_err(|e| e.to_string())?;
+            }
         }
     }
     
@@ -81,6 +1232,7 @@ async fn send_streaming_message(
 }
 
 fn main() {
+    mcp_core::init_environment(std::env::args().skip(1));
     tauri::Builder::default()
         .plugin(
             tauri_plugin_log::Builder::new()
@@ -90,17 +1242,261 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             get_api_config,
             save_api_config,
+            list_providers,
+            set_provider_api_key,
+            list_azure_profiles,
+            save_azure_profile,
+            remove_azure_profile,
+            get_bedrock_account,
+            set_bedrock_account,
+            get_ollama_base_url,
+            set_ollama_base_url,
+            get_ollama_keep_alive,
+            set_ollama_keep_alive,
+            preload_model,
+            list_gateway_presets,
+            get_gateway,
+            set_gateway,
+            get_failover_chain,
+            set_failover_chain,
+            get_provider_capabilities,
+            expand_message,
+            parse_mentions,
+            suggest_mentions,
+            list_text_expansions,
+            save_text_expansion,
+            remove_text_expansion,
+            list_compatible_endpoints,
+            save_compatible_endpoint,
+            remove_compatible_endpoint,
+            get_provider_headers,
+            set_provider_headers,
             has_api_config,
+            get_system_defaults,
+            get_policy,
+            get_portable_status,
+            unlock_portable,
+            change_portable_passphrase,
+            get_api_key_source,
+            rotate_encryption_key,
             log_info,
             get_available_models,
+            list_custom_models,
+            register_custom_model,
+            remove_custom_model,
+            check_custom_model,
+            invalidate_caches,
             get_current_model,
             set_preferred_model,
+            get_generation_settings,
+            set_generation_settings,
             send_streaming_message,
-            get_build_info
+            send_orchestrated_chat,
+            resume_response,
+            list_conversation_messages,
+            get_history_insights,
+            find_duplicate_conversations,
+            merge_conversations,
+            list_message_origins,
+            save_as_starter,
+            list_starters,
+            delete_starter,
+            new_from_starter,
+            set_focused_conversation,
+            mark_read,
+            get_unread_conversations,
+            get_unread_count,
+            lock_conversation,
+            unlock_conversation,
+            remove_conversation_lock,
+            relock_conversations,
+            list_locked_conversations,
+            backup_history,
+            restore_history,
+            get_database_status,
+            get_storage_usage,
+            detach_attachment,
+            cleanup_orphaned_attachments,
+            begin_upload,
+            upload_chunk,
+            get_upload_status,
+            finish_upload,
+            cancel_upload,
+            export_conversation,
+            cancel_export,
+            get_backup_schedule,
+            set_backup_schedule,
+            save_eval_suite,
+            list_eval_suites,
+            delete_eval_suite,
+            run_eval_suite,
+            list_eval_runs,
+            get_eval_scores,
+            benchmark_providers,
+            list_benchmark_reports,
+            delete_benchmark_report,
+            get_sync_config,
+            set_sync_config,
+            generate_sync_key,
+            sync_now,
+            get_message_details,
+            find_tagged_messages,
+            get_message_cost,
+            get_conversation_compression,
+            set_conversation_compression,
+            get_conversation_filters,
+            set_conversation_filters,
+            get_plan,
+            update_plan_step,
+            get_redaction_enabled,
+            get_stream_recording,
+            set_stream_recording,
+            get_pause_hidden_streams,
+            set_pause_hidden_streams,
+            set_window_visible,
+            get_maintenance_status,
+            get_maintenance_settings,
+            set_maintenance_settings,
+            note_user_activity,
+            get_low_power_status,
+            get_low_power_settings,
+            set_low_power_settings,
+            set_low_power_override,
+            set_redaction_enabled,
+            get_telemetry_config,
+            set_telemetry_config,
+            get_memory_enabled,
+            set_memory_enabled,
+            list_memories,
+            forget_memory,
+            clear_memories,
+            get_guardrails,
+            set_guardrails,
+            estimate_generation_cost,
+            list_candidates,
+            select_candidate,
+            regenerate_with,
+            replay_message,
+            get_candidate_details,
+            diff_responses,
+            get_build_info,
+            get_ipc_stats,
+            get_startup_status,
+            get_provider_health,
+            check_provider_health,
+            get_memory_stats,
+            set_locale,
+            get_message_catalogue,
+            get_accessibility_settings,
+            set_accessibility_settings,
+            get_read_aloud_state,
+            read_message_aloud,
+            read_aloud_spoken,
+            skip_read_aloud,
+            pause_read_aloud,
+            resume_read_aloud,
+            stop_read_aloud,
+            get_onboarding_state,
+            advance_onboarding,
+            get_mode,
+            set_mode,
+            list_feature_flags,
+            set_feature_flag,
+            get_demo_mode,
+            set_demo_mode,
+            get_status,
+            get_appearance,
+            set_appearance,
+            export_settings,
+            import_settings,
+            ingest_media_file,
+            list_transcripts,
+            get_transcript_context,
+            capture_screen_region,
+            list_screen_captures,
+            get_template_repositories,
+            add_template_repository,
+            remove_template_repository,
+            sync_template_repositories,
+            list_templates,
+            list_plugins,
+            reload_plugins,
+            approve_plugin,
+            revoke_plugin,
+            invoke_plugin_command,
+            invoke_plugin_tool,
+            invoke_plugin_tools,
+            get_tool_stats,
+            list_integrations,
+            reload_integrations,
+            call_integration,
+            start_oauth_device_flow,
+            start_oauth_browser_flow,
+            refresh_oauth_token,
+            list_secrets,
+            set_secret,
+            delete_secret,
+            get_max_parallel_tools,
+            set_max_parallel_tools,
+            get_tool_result_limits,
+            set_tool_result_limits,
+            get_tool_cache_settings,
+            set_tool_cache_settings,
+            list_scripts,
+            reload_scripts,
+            start_server,
+            stop_server,
+            get_server_status,
+            set_server_auth_enabled,
+            list_server_tokens,
+            create_server_token,
+            rotate_server_token,
+            set_server_token_shaping,
+            revoke_server_token,
+            start_pairing,
+            list_paired_devices,
+            revoke_paired_device,
+            list_sync_devices,
+            handoff_conversation,
+            list_handoffs,
+            accept_handoff,
+            get_server_network,
+            set_server_network,
+            get_server_tls,
+            set_server_tls
         ])
-        .setup(|_app| {
+        .setup(|app| {
             log::info!("MCP Switchboard application starting");
             log::info!("Pure architecture: mcp-core (business logic) + Tauri (UI integration)");
+
+            // Forward the mcp-core event bus to the webview
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut events = mcp_core::subscribe_events();
+                loop {
+                    match events.recv().await {
+                        // The held-back answer is sent in one go when the window is shown
+                        Ok(mcp_core::AppEvent::ChatContent { .. }) if mcp_core::holding_chat_events() => {}
+                        Ok(event) => {
+                            // The status bar listens for its snapshot on its own channel
+                            if let mcp_core::AppEvent::StatusChanged { status } = &event {
+                                if let Err(e) = handle.emit("status-changed", status) {
+                                    log::warn!("Failed to forward status change: {}", e);
+                                }
+                            }
+                            if let Err(e) = handle.emit("app-event", event) {
+                                log::warn!("Failed to forward app event: {}", e);
+                            }
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                            log::warn!("App event forwarder lagged, skipped {} events", skipped);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+            // Scripts, plugins, schedules and the model list start once the window is up
+            tauri::async_runtime::spawn(mcp_core::run_startup());
             Ok(())
         })
         .run(tauri::generate_context!())